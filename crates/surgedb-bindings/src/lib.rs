@@ -146,6 +146,21 @@ impl From<surgedb_core::Error> for SurgeError {
             surgedb_core::Error::VectorNotFound(id) => SurgeError::VectorNotFound { id },
             surgedb_core::Error::DuplicateId(id) => SurgeError::DuplicateId { id },
             surgedb_core::Error::EmptyIndex => SurgeError::EmptyIndex,
+            surgedb_core::Error::MetadataTooLarge { size, limit } => SurgeError::InvalidConfig {
+                message: format!("metadata is {} bytes, limit is {}", size, limit),
+            },
+            surgedb_core::Error::NotUnitNorm { norm } => SurgeError::InvalidConfig {
+                message: format!("vector norm is {}, expected 1.0", norm),
+            },
+            surgedb_core::Error::InvalidQuery { norm, reason } => SurgeError::InvalidConfig {
+                message: format!("invalid query vector (norm {}): {}", norm, reason),
+            },
+            surgedb_core::Error::InvalidVector { index, value } => SurgeError::InvalidConfig {
+                message: format!(
+                    "vector component {} is {}, expected a finite number",
+                    index, value
+                ),
+            },
             surgedb_core::Error::InvalidConfig(msg) => SurgeError::InvalidConfig { message: msg },
             surgedb_core::Error::InvalidHnswParam {
                 param,
@@ -213,6 +228,11 @@ pub enum DistanceMetric {
     Cosine,
     Euclidean,
     DotProduct,
+    Manhattan,
+    /// Binary quantization only
+    Hamming,
+    /// Binary quantization only
+    Jaccard,
 }
 
 impl From<DistanceMetric> for surgedb_core::DistanceMetric {
@@ -221,6 +241,9 @@ impl From<DistanceMetric> for surgedb_core::DistanceMetric {
             DistanceMetric::Cosine => surgedb_core::DistanceMetric::Cosine,
             DistanceMetric::Euclidean => surgedb_core::DistanceMetric::Euclidean,
             DistanceMetric::DotProduct => surgedb_core::DistanceMetric::DotProduct,
+            DistanceMetric::Manhattan => surgedb_core::DistanceMetric::Manhattan,
+            DistanceMetric::Hamming => surgedb_core::DistanceMetric::Hamming,
+            DistanceMetric::Jaccard => surgedb_core::DistanceMetric::Jaccard,
         }
     }
 }
@@ -2,12 +2,19 @@
 
 use clap::{Parser, Subcommand, ValueEnum};
 use rayon::prelude::*;
-use serde::Deserialize;
-use std::path::PathBuf;
-use std::time::Instant;
+use safetensors::{Dtype, SafeTensors};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use surgedb_core::pq::{PqCodebook, PqConfig};
 use surgedb_core::{
-    Config, DistanceMetric, MmapConfig, MmapVectorDb, PersistentConfig, PersistentVectorDb,
-    QuantizationType, QuantizedConfig, QuantizedVectorDb, VectorDb,
+    BatchReport, Config, DistanceMetric, DuplicatePolicy, HnswConfig, MmapConfig, MmapVectorDb,
+    PersistentConfig, PersistentVectorDb, QuantizationType, QuantizedConfig, QuantizedVectorDb,
+    TuningTarget, VectorDb,
 };
 
 #[derive(Parser)]
@@ -56,6 +63,21 @@ enum Commands {
         dimensions: usize,
     },
 
+    /// Report quantization accuracy (reconstruction error and recall@10)
+    Accuracy {
+        /// Number of vectors to insert
+        #[arg(short, long, default_value = "5000")]
+        count: usize,
+
+        /// Vector dimensions
+        #[arg(short, long, default_value = "128")]
+        dimensions: usize,
+
+        /// Number of vectors to evaluate
+        #[arg(short, long, default_value = "500")]
+        sample: usize,
+    },
+
     /// Test persistence and recovery
     Persist {
         /// Data directory
@@ -86,12 +108,22 @@ enum Commands {
         dimensions: usize,
     },
 
-    /// Import vectors from a JSON file
+    /// Import vectors from a JSON or safetensors file
     Import {
-        /// Path to JSON file (format: [{"id": "...", "vector": [...]}, ...])
+        /// Path to the input file. JSON: [{"id": "...", "vector": [...]}, ...];
+        /// safetensors: a single `[n, dim]` tensor
         #[arg(short, long)]
         file: PathBuf,
 
+        /// Input file format
+        #[arg(long, default_value = "json")]
+        format: ImportFormat,
+
+        /// Sidecar file with one ID per line, one line per tensor row
+        /// (required for safetensors)
+        #[arg(long)]
+        ids_file: Option<PathBuf>,
+
         /// Data directory for storage
         #[arg(short, long, default_value = "./surgedb_data")]
         data_dir: PathBuf,
@@ -103,6 +135,23 @@ enum Commands {
         /// Quantization type
         #[arg(short, long, default_value = "none")]
         quantization: QuantizationArg,
+
+        /// Continue an interrupted import from its progress file
+        #[arg(long)]
+        resume: bool,
+
+        /// Rows loaded between checkpoints (each one is durable and resumable)
+        #[arg(long, default_value = "100000")]
+        checkpoint_every: usize,
+
+        /// How to treat an ID that is already stored or repeated in the input
+        #[arg(long, default_value = "skip")]
+        on_duplicate: DuplicateArg,
+
+        /// Link each chunk into the index from all cores at once (faster on
+        /// large imports; the graph is not reproducible)
+        #[arg(long)]
+        parallel_build: bool,
     },
 
     /// Search the imported database
@@ -124,6 +173,47 @@ enum Commands {
         k: usize,
     },
 
+    /// Export a database to a single portable file
+    Export {
+        /// Data directory
+        #[arg(short, long, default_value = "./surgedb_data")]
+        data_dir: PathBuf,
+
+        /// Vector dimensions
+        #[arg(short = 'n', long)]
+        dimensions: usize,
+
+        /// Path of the export file to write
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+
+    /// Recreate a database from an export file
+    Restore {
+        /// Path of the export file
+        #[arg(short, long)]
+        file: PathBuf,
+
+        /// Data directory for the new database (must be empty or missing)
+        #[arg(short, long, default_value = "./surgedb_data")]
+        data_dir: PathBuf,
+    },
+
+    /// Inspect a vector's HNSW neighborhood in an imported database
+    Inspect {
+        /// Data directory
+        #[arg(short, long, default_value = "./surgedb_data")]
+        data_dir: PathBuf,
+
+        /// Vector dimensions
+        #[arg(short = 'n', long)]
+        dimensions: usize,
+
+        /// ID of the vector to inspect
+        #[arg(short, long)]
+        id: String,
+    },
+
     /// Validate accuracy (Recall) and performance across all modes
     Validate {
         /// Number of vectors to test
@@ -137,6 +227,44 @@ enum Commands {
         /// Top K for recall calculation
         #[arg(short, long, default_value = "10")]
         k: usize,
+
+        /// Distance metric used for indexing and the exact ground truth
+        #[arg(long, value_enum, default_value = "cosine")]
+        metric: MetricArg,
+    },
+
+    /// Find the smallest-memory configuration reaching a target recall
+    OptimizeMemory {
+        /// Number of vectors to test
+        #[arg(short, long, default_value = "2000")]
+        count: usize,
+
+        /// Vector dimensions
+        #[arg(long, default_value = "128")]
+        dimensions: usize,
+
+        /// Top K for recall calculation
+        #[arg(short, long, default_value = "10")]
+        k: usize,
+
+        /// Minimum Recall@K a configuration must reach (0.0 - 1.0)
+        #[arg(long, default_value = "0.95")]
+        target_recall: f32,
+    },
+
+    /// Suggest HNSW parameters for a dataset
+    Recommend {
+        /// Number of vectors the collection will hold
+        #[arg(short, long)]
+        count: usize,
+
+        /// Vector dimensions
+        #[arg(short, long)]
+        dimensions: usize,
+
+        /// What to optimize for
+        #[arg(short, long, value_enum, default_value = "balanced")]
+        target: TargetArg,
     },
 
     /// Heavy stress test with massive scale and concurrency
@@ -156,6 +284,14 @@ enum Commands {
         /// Data directory
         #[arg(long, default_value = "./surgedb_stress")]
         data_dir: PathBuf,
+
+        /// Write a JSON summary (throughput, percentiles, disk, recovery) to this file
+        #[arg(long)]
+        report: Option<PathBuf>,
+
+        /// Stream every search's latency (and serving thread) to this CSV file
+        #[arg(long)]
+        trace_out: Option<PathBuf>,
     },
 
     /// Show version and system information
@@ -169,6 +305,65 @@ enum QuantizationArg {
     Binary,
 }
 
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum MetricArg {
+    Cosine,
+    Euclidean,
+    DotProduct,
+    Manhattan,
+}
+
+impl From<MetricArg> for DistanceMetric {
+    fn from(metric: MetricArg) -> Self {
+        match metric {
+            MetricArg::Cosine => DistanceMetric::Cosine,
+            MetricArg::Euclidean => DistanceMetric::Euclidean,
+            MetricArg::DotProduct => DistanceMetric::DotProduct,
+            MetricArg::Manhattan => DistanceMetric::Manhattan,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum TargetArg {
+    Accuracy,
+    Balanced,
+    Speed,
+}
+
+impl From<TargetArg> for TuningTarget {
+    fn from(target: TargetArg) -> Self {
+        match target {
+            TargetArg::Accuracy => TuningTarget::Accuracy,
+            TargetArg::Balanced => TuningTarget::Balanced,
+            TargetArg::Speed => TuningTarget::Speed,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ImportFormat {
+    Json,
+    Safetensors,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum DuplicateArg {
+    Skip,
+    Overwrite,
+    Error,
+}
+
+impl From<DuplicateArg> for DuplicatePolicy {
+    fn from(policy: DuplicateArg) -> Self {
+        match policy {
+            DuplicateArg::Skip => DuplicatePolicy::Skip,
+            DuplicateArg::Overwrite => DuplicatePolicy::Overwrite,
+            DuplicateArg::Error => DuplicatePolicy::Error,
+        }
+    }
+}
+
 fn main() {
     let cli = Cli::parse();
 
@@ -187,6 +382,11 @@ fn main() {
             }
         }
         Commands::Compare { count, dimensions } => run_comparison(count, dimensions),
+        Commands::Accuracy {
+            count,
+            dimensions,
+            sample,
+        } => run_accuracy_report(count, dimensions, sample),
         Commands::Persist {
             data_dir,
             count,
@@ -199,27 +399,86 @@ fn main() {
         } => run_mmap_benchmark(&data_dir, count, dimensions),
         Commands::Import {
             file,
+            format,
+            ids_file,
             data_dir,
             dimensions,
             quantization,
-        } => run_import(&file, &data_dir, dimensions, quantization),
+            resume,
+            checkpoint_every,
+            on_duplicate,
+            parallel_build,
+        } => run_import(
+            &file,
+            format,
+            ids_file.as_deref(),
+            &data_dir,
+            PersistentConfig {
+                dimensions,
+                distance_metric: DistanceMetric::Cosine,
+                hnsw: HnswConfig {
+                    parallel_build,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            quantization,
+            ImportCheckpoints {
+                data_dir: &data_dir,
+                every: checkpoint_every.max(1),
+                resume,
+                on_duplicate: on_duplicate.into(),
+            },
+        ),
         Commands::Query {
             data_dir,
             dimensions,
             vec,
             k,
         } => run_query(&data_dir, dimensions, &vec, k),
+        Commands::Export {
+            data_dir,
+            dimensions,
+            output,
+        } => run_export(&data_dir, dimensions, &output),
+        Commands::Restore { file, data_dir } => run_restore(&file, &data_dir),
+        Commands::Inspect {
+            data_dir,
+            dimensions,
+            id,
+        } => run_inspect(&data_dir, dimensions, &id),
         Commands::Validate {
             count,
             dimensions,
             k,
-        } => run_validation(count, dimensions, k),
+            metric,
+        } => run_validation(count, dimensions, k, metric.into()),
+        Commands::OptimizeMemory {
+            count,
+            dimensions,
+            k,
+            target_recall,
+        } => run_memory_optimization(count, dimensions, k, target_recall),
+        Commands::Recommend {
+            count,
+            dimensions,
+            target,
+        } => show_recommendation(count, dimensions, target.into()),
         Commands::Stress {
             count,
             dimensions,
             threads,
             data_dir,
-        } => run_stress_test(count, dimensions, threads, &data_dir),
+            report,
+            trace_out,
+        } => run_stress_test(
+            count,
+            dimensions,
+            threads,
+            &data_dir,
+            report.as_deref(),
+            trace_out.as_deref(),
+        ),
         Commands::Info => show_info(),
     }
 }
@@ -232,62 +491,259 @@ struct ImportItem {
 
 fn run_import(
     file: &PathBuf,
+    format: ImportFormat,
+    ids_file: Option<&Path>,
     data_dir: &PathBuf,
-    dimensions: usize,
+    config: PersistentConfig,
     _quantization: QuantizationArg,
+    checkpoints: ImportCheckpoints,
 ) {
     println!("SurgeDB Import");
     println!("===============");
-    println!("File: {}", file.display());
-    println!("Dimensions: {}", dimensions);
+    println!("File: {} ({:?})", file.display(), format);
+    println!("Dimensions: {}", config.dimensions);
     println!();
 
-    // Read JSON
-    let file_content = std::fs::read_to_string(file).expect("Failed to read import file");
-    let items: Vec<ImportItem> = serde_json::from_str(&file_content).expect("Failed to parse JSON");
-
-    println!("Importing {} vectors...", items.len());
-
-    let config = PersistentConfig {
-        dimensions,
-        distance_metric: DistanceMetric::Cosine,
-        ..Default::default()
-    };
-
+    let dimensions = config.dimensions;
     let mut db = PersistentVectorDb::open(data_dir, config).expect("Failed to create database");
+    if !checkpoints.resume && ImportProgress::load(data_dir).is_some() {
+        println!("Found progress from an interrupted import; pass --resume to skip ahead");
+    }
 
     let start = Instant::now();
-    let mut skip_count = 0;
-    for (i, item) in items.iter().enumerate() {
-        match db.insert(item.id.clone(), &item.vector, None) {
-            Ok(_) => {}
-            Err(surgedb_core::Error::DuplicateId(_)) => {
-                skip_count += 1;
-            }
-            Err(e) => panic!("Failed to insert: {:?}", e),
+    let imported = match format {
+        ImportFormat::Json => {
+            let file_content = std::fs::read_to_string(file).expect("Failed to read import file");
+            let items: Vec<ImportItem> =
+                serde_json::from_str(&file_content).expect("Failed to parse JSON");
+            let total = items.len();
+            println!("Importing {} vectors...", total);
+            let rows = items.into_iter().map(|item| (item.id, item.vector));
+            insert_rows(&mut db, total, rows, &checkpoints)
         }
-        if (i + 1) % 100 == 0 {
-            print!(
-                "\r  Progress: {}/{} (skipped: {})",
-                i + 1,
-                items.len(),
-                skip_count
-            );
-            use std::io::Write;
-            std::io::stdout().flush().unwrap();
+        ImportFormat::Safetensors => match ids_file {
+            Some(ids_file) => import_safetensors(&mut db, file, ids_file, dimensions, &checkpoints),
+            None => Err("--ids-file is required for safetensors imports".to_string()),
+        },
+    };
+
+    let report = match imported {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
         }
-    }
+    };
 
     db.sync().unwrap();
     println!(
-        "\r  Done! Imported {} vectors (skipped {}) in {:?}",
-        items.len() - skip_count,
-        skip_count,
+        "\r  Done! Imported {} vectors (overwrote {}, skipped {}) in {:?}",
+        report.inserted,
+        report.overwritten,
+        report.skipped,
         start.elapsed()
     );
     println!("Data stored in: {}", data_dir.display());
 }
 
+/// Import a memory-mapped `[n, dim]` F32 tensor, pairing row `i` with line
+/// `i` of `ids_file`.
+fn import_safetensors(
+    db: &mut PersistentVectorDb,
+    file: &Path,
+    ids_file: &Path,
+    dimensions: usize,
+    checkpoints: &ImportCheckpoints,
+) -> Result<BatchReport, String> {
+    let handle = std::fs::File::open(file)
+        .map_err(|e| format!("Failed to open {}: {}", file.display(), e))?;
+    // Safety: the file is only read, and is not expected to change during import
+    let mmap = unsafe { memmap2::Mmap::map(&handle) }
+        .map_err(|e| format!("Failed to map {}: {}", file.display(), e))?;
+    let tensors = SafeTensors::deserialize(&mmap)
+        .map_err(|e| format!("Invalid safetensors file: {:?}", e))?;
+
+    let names = tensors.names();
+    if names.len() != 1 {
+        return Err(format!(
+            "Expected exactly one tensor, found {}: {:?}",
+            names.len(),
+            names
+        ));
+    }
+    let tensor = tensors
+        .tensor(names[0])
+        .map_err(|e| format!("Failed to read tensor: {:?}", e))?;
+
+    if tensor.dtype() != Dtype::F32 {
+        return Err(format!(
+            "Expected an F32 tensor, found {:?}",
+            tensor.dtype()
+        ));
+    }
+    let (rows, dim) = match *tensor.shape() {
+        [rows, dim] => (rows, dim),
+        ref shape => return Err(format!("Expected a [n, dim] tensor, found {:?}", shape)),
+    };
+    if dim != dimensions {
+        return Err(format!(
+            "Tensor has {} columns, expected --dimensions {}",
+            dim, dimensions
+        ));
+    }
+
+    let ids = std::fs::read_to_string(ids_file)
+        .map_err(|e| format!("Failed to read {}: {}", ids_file.display(), e))?;
+    let ids: Vec<&str> = ids
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect();
+    if ids.len() != rows {
+        return Err(format!(
+            "{} has {} IDs but the tensor has {} rows",
+            ids_file.display(),
+            ids.len(),
+            rows
+        ));
+    }
+
+    println!("Importing {} vectors...", rows);
+    let data = tensor.data();
+    let row_bytes = dim * std::mem::size_of::<f32>();
+    let vectors = data.chunks_exact(row_bytes).map(|row| {
+        row.chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect::<Vec<f32>>()
+    });
+    let ids = ids.into_iter().map(str::to_string);
+    insert_rows(db, rows, ids.zip(vectors), checkpoints)
+}
+
+/// Where and how often an import checkpoints, and how it resolves duplicates
+struct ImportCheckpoints<'a> {
+    /// Database directory, which also holds the progress file
+    data_dir: &'a Path,
+    /// Rows loaded between checkpoints
+    every: usize,
+    /// Skip the rows recorded in the progress file
+    resume: bool,
+    /// How IDs already stored or repeated in the input are handled
+    on_duplicate: DuplicatePolicy,
+}
+
+/// Progress of an import, saved next to the database after each checkpoint
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct ImportProgress {
+    /// Input rows that are loaded (or were already present) and checkpointed
+    rows_done: usize,
+    /// ID of the last of those rows, to check that a resume reads the same input
+    last_id: String,
+}
+
+impl ImportProgress {
+    const FILE_NAME: &'static str = "import.progress";
+
+    fn load(data_dir: &Path) -> Option<Self> {
+        let data = std::fs::read_to_string(data_dir.join(Self::FILE_NAME)).ok()?;
+        serde_json::from_str(&data).ok()
+    }
+
+    /// Write via a temporary file so a crash never leaves a torn record
+    fn save(&self, data_dir: &Path) -> Result<(), String> {
+        let path = data_dir.join(Self::FILE_NAME);
+        let tmp = path.with_extension("progress.tmp");
+        let data = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        std::fs::write(&tmp, data)
+            .and_then(|()| std::fs::rename(&tmp, &path))
+            .map_err(|e| format!("Failed to save import progress: {}", e))
+    }
+
+    fn clear(data_dir: &Path) {
+        let _ = std::fs::remove_file(data_dir.join(Self::FILE_NAME));
+    }
+}
+
+/// Bulk-load `(id, vector)` rows with progress output, resolving duplicate
+/// IDs under `checkpoints.on_duplicate`. Rows skipped on resume count as
+/// skipped in the returned report.
+///
+/// Rows are loaded in chunks of `checkpoints.every`, each made durable by a
+/// checkpoint and recorded in the progress file, so an interrupted import
+/// loses at most the chunk in flight. With `checkpoints.resume` the recorded
+/// rows are skipped without being loaded; the file is removed once every row
+/// is in.
+fn insert_rows(
+    db: &mut PersistentVectorDb,
+    total: usize,
+    rows: impl Iterator<Item = (String, Vec<f32>)>,
+    checkpoints: &ImportCheckpoints,
+) -> Result<BatchReport, String> {
+    let resume = ImportProgress::load(checkpoints.data_dir).filter(|_| checkpoints.resume);
+    if let Some(progress) = &resume {
+        println!(
+            "Resuming after row {} ({})",
+            progress.rows_done, progress.last_id
+        );
+    }
+    let skip = resume.as_ref().map_or(0, |progress| progress.rows_done);
+
+    let mut report = BatchReport {
+        skipped: skip.min(total),
+        ..Default::default()
+    };
+    let mut chunk = Vec::with_capacity(checkpoints.every.min(total));
+    let mut last = None;
+    for (i, (id, vector)) in rows.enumerate() {
+        let done = i + 1;
+        if done <= skip {
+            if let Some(progress) = resume.as_ref().filter(|_| done == skip) {
+                if progress.last_id != id {
+                    return Err(format!(
+                        "Row {} is {} but the progress file expects {}; is this the same input?",
+                        done, id, progress.last_id
+                    ));
+                }
+            }
+            continue;
+        }
+        if done % 100 == 0 {
+            print!("\r  Progress: {}/{}", done, total);
+            std::io::stdout().flush().unwrap();
+        }
+
+        chunk.push((id.clone(), vector, None));
+        let progress = ImportProgress {
+            rows_done: done,
+            last_id: id,
+        };
+        if chunk.len() >= checkpoints.every {
+            report.add(load_chunk(db, &mut chunk, &progress, checkpoints)?);
+        }
+        last = Some(progress);
+    }
+    if let Some(progress) = last {
+        report.add(load_chunk(db, &mut chunk, &progress, checkpoints)?);
+    }
+
+    ImportProgress::clear(checkpoints.data_dir);
+    Ok(report)
+}
+
+/// Load and checkpoint a chunk of rows, then record `progress`
+fn load_chunk(
+    db: &mut PersistentVectorDb,
+    chunk: &mut Vec<(String, Vec<f32>, Option<serde_json::Value>)>,
+    progress: &ImportProgress,
+    checkpoints: &ImportCheckpoints,
+) -> Result<BatchReport, String> {
+    let report = db
+        .bulk_load(chunk.drain(..), checkpoints.on_duplicate)
+        .map_err(|e| format!("Failed to import: {}", e))?;
+    progress.save(checkpoints.data_dir)?;
+    Ok(report)
+}
+
 fn run_query(data_dir: &PathBuf, dimensions: usize, vec_str: &str, k: usize) {
     let query_vec: Vec<f32> = vec_str
         .split(',')
@@ -324,6 +780,88 @@ fn run_query(data_dir: &PathBuf, dimensions: usize, vec_str: &str, k: usize) {
     }
 }
 
+fn run_export(data_dir: &Path, dimensions: usize, output: &Path) {
+    let start = Instant::now();
+    match export_db(data_dir, dimensions, output) {
+        Ok(count) => println!(
+            "Exported {} vectors to {} in {:?}",
+            count,
+            output.display(),
+            start.elapsed()
+        ),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Write the database in `data_dir` to `output`. Returns the vector count.
+fn export_db(data_dir: &Path, dimensions: usize, output: &Path) -> Result<usize, String> {
+    let config = PersistentConfig {
+        dimensions,
+        distance_metric: DistanceMetric::Cosine,
+        ..Default::default()
+    };
+    let db = PersistentVectorDb::open(data_dir, config)
+        .map_err(|e| format!("Failed to open database: {}", e))?;
+    let file = std::fs::File::create(output)
+        .map_err(|e| format!("Failed to create {}: {}", output.display(), e))?;
+    db.export(file)
+        .map_err(|e| format!("Failed to export: {}", e))?;
+    Ok(db.len())
+}
+
+fn run_restore(file: &Path, data_dir: &Path) {
+    let start = Instant::now();
+    match restore_db(file, data_dir) {
+        Ok(count) => {
+            println!("Restored {} vectors in {:?}", count, start.elapsed());
+            println!("Data stored in: {}", data_dir.display());
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Create a database in `data_dir` from the export at `file`. Returns the
+/// vector count.
+fn restore_db(file: &Path, data_dir: &Path) -> Result<usize, String> {
+    let reader = std::fs::File::open(file)
+        .map_err(|e| format!("Failed to open {}: {}", file.display(), e))?;
+    let db = PersistentVectorDb::import(data_dir, reader)
+        .map_err(|e| format!("Failed to restore: {}", e))?;
+    Ok(db.len())
+}
+
+fn run_inspect(data_dir: &PathBuf, dimensions: usize, id: &str) {
+    let config = PersistentConfig {
+        dimensions,
+        distance_metric: DistanceMetric::Cosine,
+        ..Default::default()
+    };
+
+    let db = PersistentVectorDb::open(data_dir, config).expect("Failed to open database");
+
+    let Some((internal_id, layers)) = db.neighborhood(id) else {
+        eprintln!("Error: Vector '{}' not found", id);
+        return;
+    };
+
+    println!("Vector: {}", id);
+    println!("Internal ID: {}", internal_id.as_u32());
+    println!("Max Layer: {}", layers.len().saturating_sub(1));
+    println!();
+    for (layer, neighbors) in layers.iter().enumerate().rev() {
+        println!("Layer {} ({} neighbors):", layer, neighbors.len());
+        for neighbor in neighbors {
+            println!("  {}", neighbor);
+        }
+    }
+}
+
 fn run_benchmark(count: usize, dimensions: usize, quantization: QuantizationArg) {
     let quant_name = match quantization {
         QuantizationArg::None => "None (f32)",
@@ -546,6 +1084,7 @@ fn run_mmap_benchmark(data_dir: &PathBuf, count: usize, dimensions: usize) {
     let config = MmapConfig {
         dimensions,
         distance_metric: DistanceMetric::Cosine,
+        mmap_graph: true,
         ..Default::default()
     };
 
@@ -571,14 +1110,17 @@ fn run_mmap_benchmark(data_dir: &PathBuf, count: usize, dimensions: usize) {
     );
     println!();
 
+    // The graph stays in memory until the sync maps it from disk
+    run_search_bench_mmap(&db, &vectors, "Mmap vectors, in-memory graph");
     db.sync().expect("Sync failed");
-
-    // Search benchmark
-    run_search_bench_mmap(&db, &vectors, "Mmap HNSW");
+    run_search_bench_mmap(&db, &vectors, "Mmap vectors, mapped graph");
 
     // Show disk usage
     let disk_usage = db.disk_usage();
     println!("Disk usage: {:.2} MB", disk_usage as f64 / 1_000_000.0);
+    if let Ok(graph) = std::fs::metadata(db.data_dir().join("graph.mmap")) {
+        println!("Graph file: {:.2} MB", graph.len() as f64 / 1_000_000.0);
+    }
 }
 
 fn run_persistence_test(data_dir: &PathBuf, count: usize, dimensions: usize) {
@@ -853,6 +1395,61 @@ fn run_comparison(count: usize, dimensions: usize) {
     println!("      SQ8 is recommended for most use cases (4x compression, <5% recall loss)..");
 }
 
+fn run_accuracy_report(count: usize, dimensions: usize, sample: usize) {
+    println!("SurgeDB Quantization Accuracy");
+    println!("==============================");
+    println!("Vectors: {}", count);
+    println!("Dimensions: {}", dimensions);
+    println!("Sample: {}", sample);
+    println!();
+
+    let vectors: Vec<Vec<f32>> = (0..count)
+        .map(|_| {
+            (0..dimensions)
+                .map(|_| rand::random::<f32>() * 2.0 - 1.0)
+                .collect()
+        })
+        .collect();
+
+    println!(
+        "{:<15} {:>10} {:>10} {:>10} {:>10} {:>12}",
+        "Mode", "Mean Err", "P50 Err", "P95 Err", "Max Err", "Recall@10"
+    );
+    println!("{}", "-".repeat(72));
+
+    for (name, quant_type) in [
+        ("SQ8 (u8)", QuantizationType::SQ8),
+        ("Binary", QuantizationType::Binary),
+    ] {
+        let config = QuantizedConfig {
+            dimensions,
+            distance_metric: DistanceMetric::Cosine,
+            quantization: quant_type,
+            keep_originals: true,
+            ..Default::default()
+        };
+        let mut db = QuantizedVectorDb::new(config).expect("Failed to create database");
+        for (i, vector) in vectors.iter().enumerate() {
+            db.insert(format!("vec_{}", i), vector, None).unwrap();
+        }
+
+        let report = db.accuracy_report(sample).unwrap();
+        println!(
+            "{:<15} {:>10.4} {:>10.4} {:>10.4} {:>10.4} {:>11.2}%",
+            name,
+            report.mean_error,
+            report.p50_error,
+            report.p95_error,
+            report.max_error,
+            report.recall_at_10 * 100.0
+        );
+    }
+
+    println!();
+    println!("Errors are relative L2 reconstruction errors; recall compares quantized");
+    println!("and exact top-10 neighbors within the sample.");
+}
+
 fn show_info() {
     println!("SurgeDB v{}", env!("CARGO_PKG_VERSION"));
     println!();
@@ -865,7 +1462,7 @@ fn show_info() {
     println!("  - Binary quantization (32x compression)");
     println!("  - ACID-compliant persistence (WAL + snapshots)");
     println!("  - Mmap-based disk-resident vector storage");
-    println!("  - Cosine, Euclidean, and Dot Product metrics");
+    println!("  - Cosine, Euclidean, Dot Product, and Manhattan metrics");
     println!();
 
     #[cfg(target_arch = "aarch64")]
@@ -891,53 +1488,49 @@ fn show_info() {
     println!("  surgedb persist                   Test persistence & recovery");
     println!("  surgedb mmap                      Benchmark mmap storage");
     println!("  surgedb validate                  Check Recall & Quality");
-    println!("  surgedb import                    Import vectors from JSON");
+    println!("  surgedb import                    Import vectors from JSON or safetensors");
+    println!("  surgedb inspect                   Show a vector's graph neighborhood");
     println!("  surgedb query                     Search imported database");
+    println!("  surgedb recommend -c N -d D       Suggest HNSW parameters");
     println!("  surgedb stress                    Heavy Stress Test (100k+ vectors)");
 }
 
-fn run_validation(count: usize, dimensions: usize, k: usize) {
+fn show_recommendation(count: usize, dimensions: usize, target: TuningTarget) {
+    let config = HnswConfig::recommend(count, dimensions, target);
+    println!("SurgeDB HNSW Recommendation");
+    println!("===========================");
+    println!(
+        "Vectors: {}, Dimensions: {}, Target: {:?}",
+        count, dimensions, target
+    );
+    println!();
+    println!("  m:               {}", config.m);
+    println!("  m0:              {}", config.m0);
+    println!("  ef_construction: {}", config.ef_construction);
+    println!("  ef_search:       {}", config.ef_search);
+    println!();
+    println!("Raise ef_search (or pass ef per query) if measured recall falls short.");
+}
+
+fn run_validation(count: usize, dimensions: usize, k: usize, metric: DistanceMetric) {
     println!("SurgeDB Validation Suite");
     println!("==========================");
     println!("Testing accuracy and performance across all indexing modes.");
     println!(
-        "Vectors: {}, Dimensions: {}, Top K: {}",
-        count, dimensions, k
+        "Vectors: {}, Dimensions: {}, Top K: {}, Metric: {:?}",
+        count, dimensions, k, metric
     );
     println!();
 
     // 1. Generate Data
     println!("Generating random vectors...");
-    let vectors: Vec<Vec<f32>> = (0..count)
-        .map(|_| {
-            (0..dimensions)
-                .map(|_| rand::random::<f32>() * 2.0 - 1.0)
-                .collect()
-        })
-        .collect();
-
-    let queries: Vec<Vec<f32>> = (0..100)
-        .map(|_| {
-            (0..dimensions)
-                .map(|_| rand::random::<f32>() * 2.0 - 1.0)
-                .collect()
-        })
-        .collect();
+    let vectors = random_vectors(count, dimensions);
+    let queries = random_vectors(100, dimensions);
 
     // 2. Compute Ground Truth (Exact Search)
     println!("Computing Ground Truth (Exact Brute Force)...");
-    let mut ground_truth = Vec::new();
     let start = Instant::now();
-    for query in &queries {
-        let mut distances: Vec<(usize, f32)> = vectors
-            .iter()
-            .enumerate()
-            .map(|(i, v)| (i, DistanceMetric::Cosine.distance(query, v)))
-            .collect();
-        distances.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
-        let top_ids: Vec<usize> = distances.iter().take(k).map(|(i, _)| *i).collect();
-        ground_truth.push(top_ids);
-    }
+    let ground_truth = exact_top_k(&vectors, &queries, k, metric);
     println!("Ground Truth computed in {:?}", start.elapsed());
     println!();
 
@@ -951,6 +1544,7 @@ fn run_validation(count: usize, dimensions: usize, k: usize) {
     {
         let config = Config {
             dimensions,
+            distance_metric: metric,
             ..Default::default()
         };
         let mut db = VectorDb::new(config).unwrap();
@@ -972,6 +1566,7 @@ fn run_validation(count: usize, dimensions: usize, k: usize) {
     {
         let config = QuantizedConfig {
             dimensions,
+            distance_metric: metric,
             quantization: QuantizationType::SQ8,
             keep_originals: false,
             ..Default::default()
@@ -995,6 +1590,7 @@ fn run_validation(count: usize, dimensions: usize, k: usize) {
     {
         let config = QuantizedConfig {
             dimensions,
+            distance_metric: metric,
             quantization: QuantizationType::Binary,
             keep_originals: false,
             ..Default::default()
@@ -1019,7 +1615,344 @@ fn run_validation(count: usize, dimensions: usize, k: usize) {
     println!("      Higher is better (100% is perfect match).");
 }
 
-fn run_stress_test(count: usize, dimensions: usize, threads: usize, data_dir: &PathBuf) {
+/// Uniformly random vectors in `[-1, 1)`
+fn random_vectors(count: usize, dimensions: usize) -> Vec<Vec<f32>> {
+    (0..count)
+        .map(|_| {
+            (0..dimensions)
+                .map(|_| rand::random::<f32>() * 2.0 - 1.0)
+                .collect()
+        })
+        .collect()
+}
+
+/// Indices of each query's exact top-k vectors under `metric`
+fn exact_top_k(
+    vectors: &[Vec<f32>],
+    queries: &[Vec<f32>],
+    k: usize,
+    metric: DistanceMetric,
+) -> Vec<Vec<usize>> {
+    queries
+        .iter()
+        .map(|query| {
+            let mut distances: Vec<(usize, f32)> = vectors
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (i, metric.distance(query, v)))
+                .collect();
+            distances.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+            distances.iter().take(k).map(|(i, _)| *i).collect()
+        })
+        .collect()
+}
+
+/// Recall and memory footprint of one storage configuration
+struct MemoryCandidate {
+    name: &'static str,
+    recall: f32,
+    memory_bytes: usize,
+}
+
+fn run_memory_optimization(count: usize, dimensions: usize, k: usize, target_recall: f32) {
+    println!("SurgeDB Memory Optimizer");
+    println!("========================");
+    println!(
+        "Vectors: {}, Dimensions: {}, Top K: {}, Target Recall: {:.2}%",
+        count,
+        dimensions,
+        k,
+        target_recall * 100.0
+    );
+    println!();
+
+    let vectors = random_vectors(count, dimensions);
+    let queries = random_vectors(100, dimensions);
+    let ground_truth = exact_top_k(&vectors, &queries, k, DistanceMetric::Cosine);
+
+    let candidates = sweep_memory_configs(&vectors, &queries, &ground_truth, k);
+    println!("{:<20} {:>10} {:>12}", "Mode", "Recall@K", "Memory (MB)");
+    println!("{}", "-".repeat(44));
+    for candidate in &candidates {
+        println!(
+            "{:<20} {:>9.2}% {:>12.2}",
+            candidate.name,
+            candidate.recall * 100.0,
+            candidate.memory_bytes as f64 / 1_000_000.0
+        );
+    }
+    println!();
+
+    match smallest_meeting_target(&candidates, target_recall) {
+        Some(best) => println!(
+            "Smallest configuration reaching {:.2}% recall: {} ({:.2} MB)",
+            target_recall * 100.0,
+            best.name,
+            best.memory_bytes as f64 / 1_000_000.0
+        ),
+        None => {
+            eprintln!(
+                "No configuration reached {:.2}% recall",
+                target_recall * 100.0
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Measure recall and memory for f32, SQ8, Binary and PQ storage, each
+/// quantized mode with and without re-ranking against the originals
+///
+/// Re-rank variants keep the f32 originals next to the compressed copy, so
+/// they trade memory for recall.
+fn sweep_memory_configs(
+    vectors: &[Vec<f32>],
+    queries: &[Vec<f32>],
+    truth: &[Vec<usize>],
+    k: usize,
+) -> Vec<MemoryCandidate> {
+    let dimensions = vectors.first().map_or(0, Vec::len);
+    let mut candidates = Vec::new();
+
+    let mut db = VectorDb::new(Config {
+        dimensions,
+        ..Default::default()
+    })
+    .unwrap();
+    for (i, v) in vectors.iter().enumerate() {
+        db.insert(format!("{}", i), v, None).unwrap();
+    }
+    let (recall, _) = measure_db_performance(&db, queries, truth, k);
+    candidates.push(MemoryCandidate {
+        name: "f32 (None)",
+        recall,
+        memory_bytes: db.memory_usage(),
+    });
+
+    for (name, quantization, keep_originals) in [
+        ("SQ8", QuantizationType::SQ8, false),
+        ("SQ8 + rerank", QuantizationType::SQ8, true),
+        ("Binary", QuantizationType::Binary, false),
+        ("Binary + rerank", QuantizationType::Binary, true),
+    ] {
+        let config = QuantizedConfig {
+            dimensions,
+            quantization,
+            keep_originals,
+            ..Default::default()
+        };
+        let mut db = QuantizedVectorDb::new(config).unwrap();
+        for (i, v) in vectors.iter().enumerate() {
+            db.insert(format!("{}", i), v, None).unwrap();
+        }
+        let (recall, _) = measure_quantized_db_performance(&db, queries, truth, k);
+        candidates.push(MemoryCandidate {
+            name,
+            recall,
+            memory_bytes: db.memory_usage(),
+        });
+    }
+
+    candidates.extend(measure_pq(vectors, queries, truth, k));
+    candidates
+}
+
+/// Flat PQ scan with 4-dimensional sub-vectors, with and without re-ranking
+///
+/// Skipped when the dimensions don't split evenly.
+fn measure_pq(
+    vectors: &[Vec<f32>],
+    queries: &[Vec<f32>],
+    truth: &[Vec<usize>],
+    k: usize,
+) -> Vec<MemoryCandidate> {
+    let dimensions = vectors.first().map_or(0, Vec::len);
+    if dimensions == 0 || !dimensions.is_multiple_of(4) {
+        return Vec::new();
+    }
+
+    let config = PqConfig {
+        num_subvectors: dimensions / 4,
+        num_centroids: 256,
+        sample_size: 10_000,
+        max_iterations: 10,
+    };
+    let Ok(codebook) = PqCodebook::train(vectors, config) else {
+        return Vec::new();
+    };
+    let codes: Vec<Vec<u8>> = vectors.iter().map(|v| codebook.encode(v)).collect();
+    let codes_bytes = codes.len() * dimensions / 4 + codebook.centroids.len() * 4;
+    let originals_bytes = vectors.len() * dimensions * 4;
+
+    let recall_with = |rerank: bool| {
+        let mut hits = 0;
+        for (query, truth) in queries.iter().zip(truth) {
+            let table = codebook.precompute_adc(query, DistanceMetric::Cosine);
+            let mut scored: Vec<(usize, f32)> = codes
+                .iter()
+                .enumerate()
+                .map(|(i, c)| (i, codebook.distance_adc(c, &table)))
+                .collect();
+            scored.sort_by(|a, b| a.1.total_cmp(&b.1));
+            if rerank {
+                scored.truncate(k * 3);
+                for (i, distance) in &mut scored {
+                    *distance = DistanceMetric::Cosine.distance(query, &vectors[*i]);
+                }
+                scored.sort_by(|a, b| a.1.total_cmp(&b.1));
+            }
+            hits += scored
+                .iter()
+                .take(k)
+                .filter(|(i, _)| truth.contains(i))
+                .count();
+        }
+        hits as f32 / (queries.len() * k) as f32
+    };
+
+    vec![
+        MemoryCandidate {
+            name: "PQ",
+            recall: recall_with(false),
+            memory_bytes: codes_bytes,
+        },
+        MemoryCandidate {
+            name: "PQ + rerank",
+            recall: recall_with(true),
+            memory_bytes: codes_bytes + originals_bytes,
+        },
+    ]
+}
+
+/// The candidate with the least memory whose recall reaches `target`
+fn smallest_meeting_target(
+    candidates: &[MemoryCandidate],
+    target: f32,
+) -> Option<&MemoryCandidate> {
+    candidates
+        .iter()
+        .filter(|c| c.recall >= target)
+        .min_by_key(|c| c.memory_bytes)
+}
+
+/// Estimates remaining time from the throughput over a sliding window of recent samples
+struct RateEstimator {
+    samples: VecDeque<(Instant, usize)>,
+    window: usize,
+}
+
+impl RateEstimator {
+    fn new(window: usize) -> Self {
+        let mut samples = VecDeque::with_capacity(window + 1);
+        samples.push_back((Instant::now(), 0));
+        Self {
+            samples,
+            window: window.max(2),
+        }
+    }
+
+    /// Record progress and return the rolling rate (items/sec) and ETA
+    fn update(&mut self, done: usize, total: usize) -> (f64, Option<Duration>) {
+        self.samples.push_back((Instant::now(), done));
+        while self.samples.len() > self.window {
+            self.samples.pop_front();
+        }
+
+        let (first_time, first_done) = self.samples[0];
+        let elapsed = first_time.elapsed().as_secs_f64();
+        let progressed = done.saturating_sub(first_done);
+        if elapsed <= 0.0 || progressed == 0 {
+            return (0.0, None);
+        }
+
+        let rate = progressed as f64 / elapsed;
+        let remaining = total.saturating_sub(done) as f64 / rate;
+        (rate, Some(Duration::from_secs_f64(remaining)))
+    }
+}
+
+fn format_eta(eta: Option<Duration>) -> String {
+    match eta {
+        Some(d) => {
+            let secs = d.as_secs();
+            if secs >= 3600 {
+                format!("{}h{:02}m", secs / 3600, (secs % 3600) / 60)
+            } else if secs >= 60 {
+                format!("{}m{:02}s", secs / 60, secs % 60)
+            } else {
+                format!("{}s", secs)
+            }
+        }
+        None => "--".to_string(),
+    }
+}
+
+/// Machine-readable stress test summary for CI regression tracking
+#[derive(Debug, Serialize)]
+struct StressReport {
+    count: usize,
+    dimensions: usize,
+    threads: usize,
+    ingest_secs: f64,
+    ingest_vectors_per_sec: f64,
+    disk_usage_bytes: u64,
+    search_queries: usize,
+    search_secs: f64,
+    search_queries_per_sec: f64,
+    latency_p50_ms: f64,
+    latency_p95_ms: f64,
+    latency_p99_ms: f64,
+    recovery_secs: f64,
+    recovered_vectors: usize,
+}
+
+fn write_stress_report(path: &Path, report: &StressReport) -> std::io::Result<()> {
+    let json = serde_json::to_string_pretty(report)?;
+    std::fs::write(path, json)
+}
+
+/// Per-query latency trace, streamed to a CSV file as queries complete
+///
+/// Rows are `query,thread,latency_ms`, in completion order. Only the write
+/// buffer is held in memory; the first write error is kept and reported by
+/// `finish`.
+struct LatencyTrace {
+    state: Mutex<(BufWriter<File>, std::io::Result<()>)>,
+}
+
+impl LatencyTrace {
+    fn create(path: &Path) -> std::io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "query,thread,latency_ms")?;
+        Ok(Self {
+            state: Mutex::new((writer, Ok(()))),
+        })
+    }
+
+    fn record(&self, query: usize, thread: Option<usize>, latency_ms: f64) {
+        let mut state = self.state.lock().unwrap();
+        let (writer, status) = &mut *state;
+        if status.is_ok() {
+            let thread = thread.map(|t| t.to_string()).unwrap_or_default();
+            *status = writeln!(writer, "{},{},{:.6}", query, thread, latency_ms);
+        }
+    }
+
+    fn finish(self) -> std::io::Result<()> {
+        let (mut writer, status) = self.state.into_inner().unwrap();
+        status?;
+        writer.flush()
+    }
+}
+
+fn run_stress_test(
+    count: usize,
+    dimensions: usize,
+    threads: usize,
+    data_dir: &PathBuf,
+    report_path: Option<&Path>,
+    trace_path: Option<&Path>,
+) {
     println!("SurgeDB Industrial Stress Test");
     println!("===============================");
     println!("Scale: {} vectors", count);
@@ -1041,12 +1974,19 @@ fn run_stress_test(count: usize, dimensions: usize, threads: usize, data_dir: &P
     let mut db = PersistentVectorDb::open(data_dir, config).unwrap();
 
     let start = Instant::now();
+    let mut estimator = RateEstimator::new(10);
     for i in 0..count {
         let vec: Vec<f32> = (0..dimensions).map(|_| rand::random::<f32>()).collect();
         db.insert(format!("v{}", i), &vec, None).unwrap();
         if (i + 1) % 5000 == 0 {
-            print!("\r  Ingested: {}/{}", i + 1, count);
-            use std::io::Write;
+            let (rate, eta) = estimator.update(i + 1, count);
+            print!(
+                "\r  Ingested: {}/{} ({:.0}/s, ETA {})   ",
+                i + 1,
+                count,
+                rate,
+                format_eta(eta)
+            );
             std::io::stdout().flush().unwrap();
         }
     }
@@ -1079,18 +2019,56 @@ fn run_stress_test(count: usize, dimensions: usize, threads: usize, data_dir: &P
         .build()
         .unwrap();
 
+    let trace = trace_path.and_then(|path| match LatencyTrace::create(path) {
+        Ok(trace) => Some(trace),
+        Err(e) => {
+            eprintln!("Failed to create trace {}: {}", path.display(), e);
+            None
+        }
+    });
+
+    let completed = std::sync::atomic::AtomicUsize::new(0);
+    let search_estimator = Mutex::new(RateEstimator::new(10));
     let start = Instant::now();
     let latencies: Vec<f64> = pool.install(|| {
         queries
             .par_iter()
-            .map(|q| {
+            .enumerate()
+            .map(|(i, q)| {
                 let q_start = Instant::now();
                 db.search(q, 10, None).unwrap();
-                q_start.elapsed().as_secs_f64() * 1000.0
+                let latency = q_start.elapsed().as_secs_f64() * 1000.0;
+                if let Some(trace) = &trace {
+                    trace.record(i, rayon::current_thread_index(), latency);
+                }
+
+                let done = completed.fetch_add(1, std::sync::atomic::Ordering::Relaxed) + 1;
+                if done.is_multiple_of(100) {
+                    if let Ok(mut estimator) = search_estimator.try_lock() {
+                        let (rate, eta) = estimator.update(done, query_count);
+                        print!(
+                            "\r  Searched: {}/{} ({:.0}/s, ETA {})   ",
+                            done,
+                            query_count,
+                            rate,
+                            format_eta(eta)
+                        );
+                        std::io::stdout().flush().ok();
+                    }
+                }
+                latency
             })
             .collect()
     });
     let total_time = start.elapsed();
+    print!("\r");
+
+    if let (Some(trace), Some(path)) = (trace, trace_path) {
+        match trace.finish() {
+            Ok(()) => println!("  Trace written to {}", path.display()),
+            Err(e) => eprintln!("  Failed to write trace: {}", e),
+        }
+    }
 
     // Calculate Percentiles
     let mut latencies = latencies;
@@ -1123,9 +2101,33 @@ fn run_stress_test(count: usize, dimensions: usize, threads: usize, data_dir: &P
         },
     )
     .unwrap();
-    println!("  Recovered {} vectors in {:?}", db.len(), start.elapsed());
+    let recovery_time = start.elapsed();
+    println!("  Recovered {} vectors in {:?}", db.len(), recovery_time);
     println!();
 
+    if let Some(path) = report_path {
+        let report = StressReport {
+            count,
+            dimensions,
+            threads,
+            ingest_secs: ingest_time.as_secs_f64(),
+            ingest_vectors_per_sec: count as f64 / ingest_time.as_secs_f64(),
+            disk_usage_bytes: disk_size,
+            search_queries: query_count,
+            search_secs: total_time.as_secs_f64(),
+            search_queries_per_sec: query_count as f64 / total_time.as_secs_f64(),
+            latency_p50_ms: p50,
+            latency_p95_ms: p95,
+            latency_p99_ms: p99,
+            recovery_secs: recovery_time.as_secs_f64(),
+            recovered_vectors: db.len(),
+        };
+        match write_stress_report(path, &report) {
+            Ok(()) => println!("Report written to {}", path.display()),
+            Err(e) => eprintln!("Failed to write report: {}", e),
+        }
+    }
+
     println!("Stress test complete!");
 }
 
@@ -1201,3 +2203,316 @@ fn dir_size(path: &PathBuf) -> std::io::Result<u64> {
     }
     Ok(total)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use safetensors::tensor::TensorView;
+
+    #[test]
+    fn test_memory_optimizer_prefers_compressed_config() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let mut random = |count: usize| -> Vec<Vec<f32>> {
+            (0..count)
+                .map(|_| (0..16).map(|_| rng.gen_range(-1.0..1.0)).collect())
+                .collect()
+        };
+        let vectors = random(600);
+        let queries = random(20);
+        let truth = exact_top_k(&vectors, &queries, 10, DistanceMetric::Cosine);
+
+        let candidates = sweep_memory_configs(&vectors, &queries, &truth, 10);
+        let f32_memory = candidates[0].memory_bytes;
+        let best = smallest_meeting_target(&candidates, 0.9).unwrap();
+        assert_ne!(best.name, "f32 (None)");
+        assert!(best.memory_bytes < f32_memory);
+        assert!(smallest_meeting_target(&candidates, 1.01).is_none());
+    }
+
+    #[test]
+    fn test_exact_top_k_follows_metric() {
+        let vectors = vec![vec![1.0, 1.0], vec![1.8, 0.0]];
+        let queries = vec![vec![0.0, 0.0]];
+        let top = |metric| exact_top_k(&vectors, &queries, 1, metric)[0][0];
+        assert_eq!(top(DistanceMetric::Euclidean), 0);
+        assert_eq!(top(DistanceMetric::Manhattan), 1);
+    }
+
+    #[test]
+    fn test_stress_report_json_fields() {
+        let report = StressReport {
+            count: 100,
+            dimensions: 8,
+            threads: 2,
+            ingest_secs: 0.5,
+            ingest_vectors_per_sec: 200.0,
+            disk_usage_bytes: 4096,
+            search_queries: 10,
+            search_secs: 0.1,
+            search_queries_per_sec: 100.0,
+            latency_p50_ms: 1.0,
+            latency_p95_ms: 2.0,
+            latency_p99_ms: 3.0,
+            recovery_secs: 0.2,
+            recovered_vectors: 100,
+        };
+
+        let path = std::env::temp_dir().join(format!("surgedb_report_{}.json", std::process::id()));
+        write_stress_report(&path, &report).unwrap();
+        let parsed: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        for field in [
+            "ingest_vectors_per_sec",
+            "search_queries_per_sec",
+            "latency_p50_ms",
+            "latency_p95_ms",
+            "latency_p99_ms",
+            "disk_usage_bytes",
+            "recovery_secs",
+        ] {
+            assert!(parsed.get(field).is_some(), "missing field {}", field);
+        }
+        assert_eq!(parsed["recovered_vectors"], 100);
+    }
+
+    #[test]
+    fn test_rate_estimator_eta() {
+        let mut estimator = RateEstimator::new(4);
+        std::thread::sleep(Duration::from_millis(20));
+        let (rate, eta) = estimator.update(50, 100);
+        assert!(rate > 0.0);
+        assert!(eta.is_some());
+        assert_eq!(format_eta(None), "--");
+        assert_eq!(format_eta(Some(Duration::from_secs(65))), "1m05s");
+    }
+
+    #[test]
+    fn test_import_safetensors_matches_tensor_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let (rows, dim) = (5, 3);
+        let values: Vec<f32> = (0..rows * dim).map(|i| i as f32 * 0.5 - 2.0).collect();
+        let bytes: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+
+        let tensor_path = dir.path().join("embeddings.safetensors");
+        let view = TensorView::new(Dtype::F32, vec![rows, dim], &bytes).unwrap();
+        let encoded = safetensors::serialize([("embeddings", view)], &None).unwrap();
+        std::fs::write(&tensor_path, encoded).unwrap();
+
+        let ids_path = dir.path().join("ids.txt");
+        let ids: Vec<String> = (0..rows).map(|i| format!("doc{}", i)).collect();
+        std::fs::write(&ids_path, ids.join("\n")).unwrap();
+
+        let config = PersistentConfig {
+            dimensions: dim,
+            ..Default::default()
+        };
+        let data_dir = dir.path().join("data");
+        let mut db = PersistentVectorDb::open(&data_dir, config.clone()).unwrap();
+        let checkpoints = ImportCheckpoints {
+            data_dir: &data_dir,
+            every: 100,
+            resume: false,
+            on_duplicate: DuplicatePolicy::Skip,
+        };
+
+        // The tensor's second dimension must match the collection
+        let err = import_safetensors(&mut db, &tensor_path, &ids_path, dim + 1, &checkpoints)
+            .unwrap_err();
+        assert!(err.contains("columns"), "{}", err);
+        assert!(db.is_empty());
+
+        let report =
+            import_safetensors(&mut db, &tensor_path, &ids_path, dim, &checkpoints).unwrap();
+        assert_eq!(report.inserted, rows);
+        assert_eq!((report.skipped, report.overwritten), (0, 0));
+        assert_eq!(db.len(), rows);
+
+        for (i, id) in ids.iter().enumerate() {
+            let (vector, _) = db.get(id).unwrap().unwrap();
+            assert_eq!(vector, &values[i * dim..(i + 1) * dim]);
+        }
+    }
+
+    #[test]
+    fn test_interrupted_import_resumes_without_duplicates() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_dir = dir.path().join("data");
+        let (total, dim) = (250, 4);
+        let rows = || {
+            (0..total).map(move |i| {
+                (
+                    format!("doc{}", i),
+                    vec![i as f32, 1.0, 0.0, (i % 7) as f32],
+                )
+            })
+        };
+        let config = PersistentConfig {
+            dimensions: dim,
+            ..Default::default()
+        };
+        let checkpoints = |resume| ImportCheckpoints {
+            data_dir: &data_dir,
+            every: 40,
+            resume,
+            on_duplicate: DuplicatePolicy::Skip,
+        };
+
+        // Interrupt the first run at row 130, mid-chunk
+        {
+            let mut db = PersistentVectorDb::open(&data_dir, config.clone()).unwrap();
+            let interrupted = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                let rows = rows().inspect(|(id, _)| assert_ne!(id, "doc130", "interrupted"));
+                insert_rows(&mut db, total, rows, &checkpoints(false))
+            }));
+            assert!(interrupted.is_err());
+        }
+
+        // Every completed chunk is checkpointed and recorded
+        let progress = ImportProgress::load(&data_dir).unwrap();
+        assert_eq!(
+            progress,
+            ImportProgress {
+                rows_done: 120,
+                last_id: "doc119".to_string(),
+            }
+        );
+        let mut db = PersistentVectorDb::open(&data_dir, config).unwrap();
+        assert_eq!(db.len(), 120);
+
+        // A different input is refused
+        let shifted = rows().skip(1);
+        let err = insert_rows(&mut db, total, shifted, &checkpoints(true)).unwrap_err();
+        assert!(err.contains("same input"), "{}", err);
+
+        let report = insert_rows(&mut db, total, rows(), &checkpoints(true)).unwrap();
+        assert_eq!((report.inserted, report.skipped), (total - 120, 120));
+        assert_eq!(db.len(), total);
+        assert_eq!(db.physical_len(), total);
+        for (id, vector) in rows() {
+            assert_eq!(db.get(&id).unwrap().unwrap().0, vector);
+        }
+        assert!(ImportProgress::load(&data_dir).is_none());
+    }
+
+    #[test]
+    fn test_import_resolves_duplicates_under_policy() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_dir = dir.path().join("data");
+        let config = PersistentConfig {
+            dimensions: 2,
+            distance_metric: DistanceMetric::Euclidean,
+            ..Default::default()
+        };
+        let mut db = PersistentVectorDb::open(&data_dir, config).unwrap();
+        db.insert("doc0", &[0.0, 0.0], None).unwrap();
+        let rows = || {
+            ["doc0", "doc1", "doc1", "doc2"]
+                .into_iter()
+                .enumerate()
+                .map(|(i, id)| (id.to_string(), vec![i as f32, 1.0]))
+        };
+        let checkpoints = |on_duplicate| ImportCheckpoints {
+            data_dir: &data_dir,
+            // The repeated "doc1" straddles two chunks
+            every: 2,
+            resume: false,
+            on_duplicate,
+        };
+
+        let err =
+            insert_rows(&mut db, 4, rows(), &checkpoints(DuplicatePolicy::Error)).unwrap_err();
+        assert!(err.contains("Duplicate"), "{}", err);
+        assert_eq!(db.len(), 1);
+
+        let report =
+            insert_rows(&mut db, 4, rows(), &checkpoints(DuplicatePolicy::Overwrite)).unwrap();
+        assert_eq!(
+            (report.inserted, report.skipped, report.overwritten),
+            (2, 0, 2)
+        );
+        assert_eq!(db.get("doc0").unwrap().unwrap().0, vec![0.0, 1.0]);
+        assert_eq!(db.get("doc1").unwrap().unwrap().0, vec![2.0, 1.0]);
+
+        let report = insert_rows(&mut db, 4, rows(), &checkpoints(DuplicatePolicy::Skip)).unwrap();
+        assert_eq!(
+            (report.inserted, report.skipped, report.overwritten),
+            (0, 4, 0)
+        );
+        assert_eq!(db.len(), 3);
+    }
+
+    #[test]
+    fn test_export_restore_round_trips_json_import() {
+        let dir = tempfile::tempdir().unwrap();
+        let (total, dim) = (30, 4);
+        let items: Vec<serde_json::Value> = (0..total)
+            .map(|i| serde_json::json!({ "id": format!("doc{}", i), "vector": [i, 1, 0, i % 5] }))
+            .collect();
+        let json_path = dir.path().join("import.json");
+        std::fs::write(&json_path, serde_json::to_string(&items).unwrap()).unwrap();
+
+        let config = PersistentConfig {
+            dimensions: dim,
+            ..Default::default()
+        };
+        let data_dir = dir.path().join("data");
+        let items: Vec<ImportItem> =
+            serde_json::from_str(&std::fs::read_to_string(&json_path).unwrap()).unwrap();
+        {
+            let mut db = PersistentVectorDb::open(&data_dir, config.clone()).unwrap();
+            let checkpoints = ImportCheckpoints {
+                data_dir: &data_dir,
+                every: 100,
+                resume: false,
+                on_duplicate: DuplicatePolicy::Skip,
+            };
+            let rows = items
+                .iter()
+                .map(|item| (item.id.clone(), item.vector.clone()));
+            insert_rows(&mut db, total, rows, &checkpoints).unwrap();
+        }
+
+        let export_path = dir.path().join("db.export");
+        assert_eq!(export_db(&data_dir, dim, &export_path).unwrap(), total);
+
+        let restored_dir = dir.path().join("restored");
+        assert_eq!(restore_db(&export_path, &restored_dir).unwrap(), total);
+        let original = PersistentVectorDb::open(&data_dir, config.clone()).unwrap();
+        let restored = PersistentVectorDb::open(&restored_dir, config).unwrap();
+        for item in &items {
+            assert_eq!(
+                restored.get(&item.id).unwrap(),
+                original.get(&item.id).unwrap()
+            );
+        }
+
+        // A restore never writes over an existing database
+        let err = restore_db(&export_path, &data_dir).unwrap_err();
+        assert!(err.contains("not empty"), "{}", err);
+    }
+
+    #[test]
+    fn test_stress_trace_has_one_row_per_query() {
+        let dir = tempfile::tempdir().unwrap();
+        let trace_path = dir.path().join("latencies.csv");
+        run_stress_test(50, 4, 2, &dir.path().join("data"), None, Some(&trace_path));
+
+        let csv = std::fs::read_to_string(&trace_path).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("query,thread,latency_ms"));
+        let mut queries: Vec<usize> = lines
+            .map(|line| {
+                let fields: Vec<&str> = line.split(',').collect();
+                assert_eq!(fields.len(), 3, "{}", line);
+                assert!(fields[1].parse::<usize>().unwrap() < 2);
+                assert!(fields[2].parse::<f64>().unwrap() >= 0.0);
+                fields[0].parse().unwrap()
+            })
+            .collect();
+        queries.sort_unstable();
+        assert_eq!(queries, (0..1000).collect::<Vec<_>>());
+    }
+}
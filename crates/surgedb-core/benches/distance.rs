@@ -1,7 +1,11 @@
 //! Benchmarks for distance calculations
 
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
-use surgedb_core::distance::{cosine_distance, dot_product_distance, euclidean_distance};
+use surgedb_core::distance::{
+    cosine_distance, cosine_distance_blocked, dot_product_distance, dot_product_distance_blocked,
+    dot_product_distance_naive, euclidean_distance, euclidean_distance_blocked,
+    euclidean_distance_naive, manhattan_distance_blocked, manhattan_distance_naive, normalize,
+};
 
 fn generate_random_vector(dim: usize) -> Vec<f32> {
     (0..dim)
@@ -54,10 +58,71 @@ fn bench_dot_product(c: &mut Criterion) {
     group.finish();
 }
 
+/// On-the-fly cosine vs. dot product over pre-normalized vectors (`Config::normalize`)
+fn bench_normalized_cosine(c: &mut Criterion) {
+    let mut group = c.benchmark_group("normalized_cosine");
+    let dim = 768;
+
+    let a = generate_random_vector(dim);
+    let b = generate_random_vector(dim);
+    let mut a_unit = a.clone();
+    let mut b_unit = b.clone();
+    normalize(&mut a_unit);
+    normalize(&mut b_unit);
+
+    group.bench_function(BenchmarkId::new("cosine", dim), |bencher| {
+        bencher.iter(|| cosine_distance(black_box(&a), black_box(&b)))
+    });
+    group.bench_function(BenchmarkId::new("normalized_dot", dim), |bencher| {
+        bencher.iter(|| dot_product_distance(black_box(&a_unit), black_box(&b_unit)))
+    });
+
+    group.finish();
+}
+
+/// Single-pass vs. blocked kernels at 1536 dimensions, for a few block sizes
+fn bench_blocked_distance(c: &mut Criterion) {
+    let dim = 1536;
+    let a = generate_random_vector(dim);
+    let b = generate_random_vector(dim);
+
+    type Kernels = (fn(&[f32], &[f32]) -> f32, fn(&[f32], &[f32], usize) -> f32);
+    let metrics: [(&str, Kernels); 4] = [
+        ("cosine", (cosine_distance, cosine_distance_blocked)),
+        (
+            "euclidean",
+            (euclidean_distance_naive, euclidean_distance_blocked),
+        ),
+        (
+            "manhattan",
+            (manhattan_distance_naive, manhattan_distance_blocked),
+        ),
+        (
+            "dot_product",
+            (dot_product_distance_naive, dot_product_distance_blocked),
+        ),
+    ];
+    for (name, (naive, blocked)) in metrics {
+        let mut group = c.benchmark_group(format!("blocked_{}", name));
+        group.bench_function(BenchmarkId::new("naive", dim), |bencher| {
+            bencher.iter(|| naive(black_box(&a), black_box(&b)))
+        });
+        for block in [128, 256, 512] {
+            group.bench_function(
+                BenchmarkId::new(format!("block_{}", block), dim),
+                |bencher| bencher.iter(|| blocked(black_box(&a), black_box(&b), block)),
+            );
+        }
+        group.finish();
+    }
+}
+
 criterion_group!(
     benches,
     bench_cosine_distance,
     bench_euclidean_distance,
-    bench_dot_product
+    bench_dot_product,
+    bench_normalized_cosine,
+    bench_blocked_distance
 );
 criterion_main!(benches);
@@ -7,10 +7,12 @@ use rand::{rngs::StdRng, Rng, SeedableRng};
 #[cfg(feature = "persistence")]
 use serde_json::{json, Value};
 #[cfg(feature = "persistence")]
-use surgedb_core::{DistanceMetric, PersistentConfig, PersistentVectorDb};
-#[cfg(feature = "persistence")]
 use surgedb_core::types::VectorId;
 #[cfg(feature = "persistence")]
+use surgedb_core::{
+    DistanceMetric, DuplicatePolicy, MmapConfig, MmapVectorDb, PersistentConfig, PersistentVectorDb,
+};
+#[cfg(feature = "persistence")]
 use tempfile::tempdir;
 
 #[cfg(feature = "persistence")]
@@ -56,12 +58,14 @@ fn bench_open_insert_checkpoint(c: &mut Criterion) {
                         b.iter_batched(
                             || {
                                 let dir = tempdir().expect("tempdir");
-                                let mut config = PersistentConfig::default();
-                                config.dimensions = *dim;
-                                config.distance_metric = DistanceMetric::Cosine;
-                                config.sync_writes = *sync_writes;
-                                let db = PersistentVectorDb::open(dir.path(), config)
-                                    .expect("open db");
+                                let config = PersistentConfig {
+                                    dimensions: *dim,
+                                    distance_metric: DistanceMetric::Cosine,
+                                    sync_writes: *sync_writes,
+                                    ..Default::default()
+                                };
+                                let db =
+                                    PersistentVectorDb::open(dir.path(), config).expect("open db");
                                 (dir, db)
                             },
                             |(_dir, mut db)| {
@@ -82,6 +86,57 @@ fn bench_open_insert_checkpoint(c: &mut Criterion) {
     group.finish();
 }
 
+/// Per-insert import vs. `bulk_load` with synced writes and a small WAL, so
+/// the per-insert path keeps fsyncing and snapshotting the growing collection
+#[cfg(feature = "persistence")]
+fn bench_bulk_load(c: &mut Criterion) {
+    let mut group = c.benchmark_group("persistent_db_bulk_load");
+    group.sample_size(10);
+    let dim = 128;
+
+    for size in bench_sizes() {
+        let items = generate_vectors(size, dim, 42);
+        let open = || {
+            let dir = tempdir().expect("tempdir");
+            let config = PersistentConfig {
+                dimensions: dim,
+                distance_metric: DistanceMetric::Cosine,
+                sync_writes: true,
+                checkpoint_threshold: 64 * 1024,
+                ..Default::default()
+            };
+            let db = PersistentVectorDb::open(dir.path(), config).expect("open db");
+            (dir, db)
+        };
+
+        group.bench_with_input(BenchmarkId::new("per_insert", size), &size, |b, _| {
+            b.iter_batched(
+                open,
+                |(_dir, mut db)| {
+                    for (id, vec, meta) in items.clone() {
+                        db.insert(id, &vec, meta).expect("insert");
+                    }
+                    black_box(db.len());
+                },
+                BatchSize::SmallInput,
+            );
+        });
+        group.bench_with_input(BenchmarkId::new("bulk_load", size), &size, |b, _| {
+            b.iter_batched(
+                open,
+                |(_dir, mut db)| {
+                    db.bulk_load(items.clone(), DuplicatePolicy::Error)
+                        .expect("bulk_load");
+                    black_box(db.len());
+                },
+                BatchSize::SmallInput,
+            );
+        });
+    }
+
+    group.finish();
+}
+
 #[cfg(feature = "persistence")]
 fn bench_search(c: &mut Criterion) {
     let mut group = c.benchmark_group("persistent_db_search");
@@ -89,9 +144,11 @@ fn bench_search(c: &mut Criterion) {
     for dim in [128_usize, 384].iter() {
         for size in bench_sizes() {
             let dir = tempdir().expect("tempdir");
-            let mut config = PersistentConfig::default();
-            config.dimensions = *dim;
-            config.distance_metric = DistanceMetric::Cosine;
+            let config = PersistentConfig {
+                dimensions: *dim,
+                distance_metric: DistanceMetric::Cosine,
+                ..Default::default()
+            };
 
             let mut db = PersistentVectorDb::open(dir.path(), config).expect("open db");
             let items = generate_vectors(size, *dim, 77);
@@ -119,7 +176,58 @@ fn bench_search(c: &mut Criterion) {
 }
 
 #[cfg(feature = "persistence")]
-criterion_group!(benches, bench_open_insert_checkpoint, bench_search);
+fn bench_mmap_zero_copy(c: &mut Criterion) {
+    let mut group = c.benchmark_group("mmap_db_search");
+    let dim = 384;
+
+    for size in bench_sizes() {
+        let dir = tempdir().expect("tempdir");
+        let config = MmapConfig {
+            dimensions: dim,
+            distance_metric: DistanceMetric::Cosine,
+            ..Default::default()
+        };
+        {
+            let mut db = MmapVectorDb::open(dir.path(), config.clone()).expect("open db");
+            for (id, vec, _) in generate_vectors(size, dim, 77) {
+                db.insert(id, &vec).expect("insert");
+            }
+            db.sync().expect("sync");
+        }
+
+        let mut rng = StdRng::seed_from_u64(123);
+        let query: Vec<f32> = (0..dim).map(|_| rng.gen::<f32>()).collect();
+
+        for zero_copy in [true, false] {
+            let db = MmapVectorDb::open(
+                dir.path(),
+                MmapConfig {
+                    zero_copy,
+                    ..config.clone()
+                },
+            )
+            .expect("reopen db");
+            let name = if zero_copy { "zero_copy" } else { "copying" };
+            group.bench_with_input(BenchmarkId::new(name, size), &size, |b, _| {
+                b.iter(|| {
+                    let results = db.search(black_box(&query), 10).expect("search");
+                    black_box(results.len());
+                });
+            });
+        }
+    }
+
+    group.finish();
+}
+
+#[cfg(feature = "persistence")]
+criterion_group!(
+    benches,
+    bench_open_insert_checkpoint,
+    bench_bulk_load,
+    bench_search,
+    bench_mmap_zero_copy
+);
 #[cfg(feature = "persistence")]
 criterion_main!(benches);
 
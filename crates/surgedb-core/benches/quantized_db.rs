@@ -4,7 +4,8 @@ use criterion::{black_box, criterion_group, criterion_main, BatchSize, Benchmark
 use rand::{rngs::StdRng, Rng, SeedableRng};
 use serde_json::{json, Value};
 use surgedb_core::{QuantizationType, QuantizedConfig, QuantizedVectorDb, DistanceMetric};
-use surgedb_core::types::VectorId;
+use surgedb_core::pq::{ClusteredPqCodebook, PqCodebook, PqConfig};
+use surgedb_core::types::{self, InternalId, VectorId};
 
 fn bench_sizes() -> Vec<usize> {
     let mut sizes = vec![2_000, 10_000];
@@ -107,5 +108,257 @@ fn bench_search(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, bench_upsert_batch, bench_search);
+/// Tight, well-separated clusters of exactly k points: the quantized gap after
+/// the k-th candidate is large, so adaptive re-ranking computes exact distances
+/// for k candidates instead of `k * rerank_multiplier`. The saving is bounded
+/// by the HNSW traversal, which still visits `k * rerank_multiplier * 2`
+/// candidates (~6% faster at k=10, multiplier 10, 5k x 128d).
+fn bench_adaptive_rerank(c: &mut Criterion) {
+    let mut group = c.benchmark_group("quantized_db_adaptive_rerank");
+    let dim = 128;
+    let k = 10;
+    let clusters = 500;
+
+    let mut rng = StdRng::seed_from_u64(5);
+    let centers: Vec<Vec<f32>> = (0..clusters)
+        .map(|_| (0..dim).map(|_| rng.gen_range(-1.0..1.0)).collect())
+        .collect();
+    let items: Vec<(VectorId, Vec<f32>, Option<Value>)> = centers
+        .iter()
+        .enumerate()
+        .flat_map(|(c, center)| {
+            let points: Vec<_> = (0..k)
+                .map(|i| {
+                    let vector = center
+                        .iter()
+                        .map(|x| x + rng.gen_range(-0.01..0.01))
+                        .collect();
+                    (VectorId::from(format!("c{c}_{i}")), vector, None)
+                })
+                .collect();
+            points
+        })
+        .collect();
+
+    for adaptive_rerank in [false, true] {
+        let mut db = QuantizedVectorDb::new(QuantizedConfig {
+            dimensions: dim,
+            distance_metric: DistanceMetric::Cosine,
+            quantization: QuantizationType::SQ8,
+            keep_originals: true,
+            rerank_multiplier: 10,
+            adaptive_rerank,
+            ..Default::default()
+        })
+        .expect("create quantized db");
+        for (id, vector, metadata) in items.iter().cloned() {
+            db.insert(id, &vector, metadata).expect("insert");
+        }
+
+        let name = if adaptive_rerank { "adaptive" } else { "always" };
+        group.bench_function(name, |b| {
+            let mut i = 0;
+            b.iter(|| {
+                i = (i + 1) % clusters;
+                let results = db
+                    .search(black_box(&centers[i]), k, None)
+                    .expect("search");
+                black_box(results.len());
+            });
+        });
+    }
+
+    group.finish();
+}
+
+/// HNSW over quantized distances against a full quantized scan (`use_index: false`).
+/// The scan grows linearly with the collection: at 128d the two are on par
+/// at 2k vectors, while the index is ~3.5x faster at 10k.
+fn bench_index_vs_scan(c: &mut Criterion) {
+    let mut group = c.benchmark_group("quantized_db_index_vs_scan");
+    let dim = 128;
+
+    for size in bench_sizes() {
+        let items = generate_vectors(size, dim, 7);
+        let mut rng = StdRng::seed_from_u64(321);
+        let query: Vec<f32> = (0..dim).map(|_| rng.gen::<f32>()).collect();
+
+        for use_index in [true, false] {
+            let mut db = QuantizedVectorDb::new(QuantizedConfig {
+                dimensions: dim,
+                distance_metric: DistanceMetric::Cosine,
+                quantization: QuantizationType::SQ8,
+                use_index,
+                ..Default::default()
+            })
+            .expect("create quantized db");
+            db.upsert_batch(items.clone()).expect("upsert batch");
+
+            let name = if use_index { "hnsw" } else { "scan" };
+            group.bench_with_input(BenchmarkId::new(name, size), &size, |b, _| {
+                b.iter(|| {
+                    let results = db.search(black_box(&query), 10, None).expect("search");
+                    black_box(results.len());
+                });
+            });
+        }
+    }
+
+    group.finish();
+}
+
+/// Top-k selection over a brute-force scan's candidates: sorting all of them
+/// against the bounded heap in `types::top_k`. At 100k candidates the heap
+/// is ~20x faster for k=10 and ~14x for k=100.
+fn bench_top_k_selection(c: &mut Criterion) {
+    let mut group = c.benchmark_group("quantized_db_top_k_selection");
+    let mut rng = StdRng::seed_from_u64(99);
+    let candidates: Vec<(InternalId, f32)> = (0..100_000_usize)
+        .map(|i| (InternalId::from(i), rng.gen::<f32>()))
+        .collect();
+
+    for k in [10_usize, 100] {
+        group.bench_with_input(BenchmarkId::new("full_sort", k), &k, |b, &k| {
+            b.iter_batched(
+                || candidates.clone(),
+                |mut candidates| {
+                    candidates.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+                    candidates.truncate(k);
+                    black_box(candidates)
+                },
+                BatchSize::LargeInput,
+            );
+        });
+        group.bench_with_input(BenchmarkId::new("heap", k), &k, |b, &k| {
+            b.iter_batched(
+                || candidates.clone(),
+                |candidates| black_box(types::top_k(candidates, k)),
+                BatchSize::LargeInput,
+            );
+        });
+    }
+
+    group.finish();
+}
+
+/// Global PQ against per-cluster PQ at the same code size, on clusters of
+/// different scales. Reports recall@10 of a flat ADC scan for each and
+/// benchmarks the scan: at 10k x 32d in 16 clusters per-cluster codebooks
+/// lift recall from ~0.42 to ~0.74, while the scan is ~5x slower because
+/// every query fills one ADC table per cluster.
+fn bench_pq_clustered(c: &mut Criterion) {
+    let mut group = c.benchmark_group("pq_clustered");
+    let dim = 32;
+    let k = 10;
+    let clusters = 16;
+
+    let mut rng = StdRng::seed_from_u64(268);
+    let centers: Vec<(Vec<f32>, f32)> = (0..clusters)
+        .map(|c| {
+            let center = (0..dim).map(|_| rng.gen_range(-10.0..10.0)).collect();
+            (center, 0.1 * (c + 1) as f32)
+        })
+        .collect();
+    let mut sample = |n: usize| -> Vec<Vec<f32>> {
+        (0..n)
+            .map(|i| {
+                let (center, spread) = &centers[i % clusters];
+                center
+                    .iter()
+                    .map(|x| x + rng.gen_range(-*spread..*spread))
+                    .collect()
+            })
+            .collect()
+    };
+    let vectors = sample(10_000);
+    let queries = sample(100);
+    let truth: Vec<Vec<usize>> = queries
+        .iter()
+        .map(|query| {
+            let mut scored: Vec<(usize, f32)> = vectors
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (i, DistanceMetric::Euclidean.distance(query, v)))
+                .collect();
+            scored.sort_by(|a, b| a.1.total_cmp(&b.1));
+            scored.into_iter().take(k).map(|(i, _)| i).collect()
+        })
+        .collect();
+
+    let config = PqConfig {
+        num_subvectors: dim / 4,
+        num_centroids: 256,
+        sample_size: 10_000,
+        max_iterations: 10,
+    };
+    // Recall of a scan's candidates, one `(vector index, distance)` list per query
+    let recall = |scans: Vec<Vec<(usize, f32)>>| {
+        let hits: usize = scans
+            .into_iter()
+            .zip(&truth)
+            .map(|(mut scored, truth)| {
+                scored.sort_by(|a, b| a.1.total_cmp(&b.1));
+                scored
+                    .iter()
+                    .take(k)
+                    .filter(|(i, _)| truth.contains(i))
+                    .count()
+            })
+            .sum();
+        hits as f32 / (queries.len() * k) as f32
+    };
+
+    let global = PqCodebook::train(&vectors, config.clone()).expect("train global");
+    let global_codes: Vec<Vec<u8>> = vectors.iter().map(|v| global.encode(v)).collect();
+    let global_scan = |query: &[f32]| -> Vec<(usize, f32)> {
+        let table = global.precompute_adc(query, DistanceMetric::Euclidean);
+        global_codes
+            .iter()
+            .enumerate()
+            .map(|(i, codes)| (i, global.distance_adc(codes, &table)))
+            .collect()
+    };
+
+    let clustered =
+        ClusteredPqCodebook::train(&vectors, clusters, config).expect("train clustered");
+    let clustered_codes: Vec<_> = vectors.iter().map(|v| clustered.encode(v)).collect();
+    let clustered_scan = |query: &[f32]| -> Vec<(usize, f32)> {
+        let tables = clustered.precompute_adc(query, DistanceMetric::Euclidean);
+        clustered_codes
+            .iter()
+            .enumerate()
+            .map(|(i, codes)| (i, clustered.distance_adc(codes, &tables)))
+            .collect()
+    };
+
+    eprintln!(
+        "global PQ: recall@{k} {:.3}, codebook {:.1} KB",
+        recall(queries.iter().map(|q| global_scan(q)).collect()),
+        (global.centroids.len() * 4) as f64 / 1e3
+    );
+    eprintln!(
+        "per-cluster PQ: recall@{k} {:.3}, codebooks {:.1} KB",
+        recall(queries.iter().map(|q| clustered_scan(q)).collect()),
+        clustered.memory_usage() as f64 / 1e3
+    );
+
+    group.bench_function("global", |b| {
+        b.iter(|| black_box(global_scan(black_box(&queries[0])).len()));
+    });
+    group.bench_function("per_cluster", |b| {
+        b.iter(|| black_box(clustered_scan(black_box(&queries[0])).len()));
+    });
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_upsert_batch,
+    bench_search,
+    bench_adaptive_rerank,
+    bench_index_vs_scan,
+    bench_top_k_selection,
+    bench_pq_clustered
+);
 criterion_main!(benches);
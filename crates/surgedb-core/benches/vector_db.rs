@@ -4,8 +4,54 @@ use criterion::{black_box, criterion_group, criterion_main, BatchSize, Benchmark
 use rand::{rngs::StdRng, Rng, SeedableRng};
 use serde_json::{json, Value};
 use surgedb_core::filter::Filter;
-use surgedb_core::{Config, DistanceMetric, VectorDb};
-use surgedb_core::types::VectorId;
+use surgedb_core::{
+    ColumnKind, ColumnSpec, Config, DistanceMetric, HnswConfig, HnswIndex, MetadataFormat,
+    RoutingConfig, VectorDb, VectorStorage,
+};
+use surgedb_core::types::{InternalId, VectorId};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// Counts heap allocations so search paths can be compared per query, live
+/// heap bytes so storage formats can be compared by footprint, and
+/// reallocations so bulk builds can be compared by buffer growth
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+static REALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        LIVE_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        REALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        LIVE_BYTES.fetch_add(new_size, Ordering::Relaxed);
+        LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn allocations_per_call(iterations: usize, mut f: impl FnMut()) -> f64 {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for _ in 0..iterations {
+        f();
+    }
+    (ALLOCATIONS.load(Ordering::Relaxed) - before) as f64 / iterations as f64
+}
 
 fn bench_sizes() -> Vec<usize> {
     let mut sizes = vec![2_000, 10_000];
@@ -157,11 +203,458 @@ fn bench_search_filtered(c: &mut Criterion) {
     group.finish();
 }
 
+/// Compares the allocating `search` path with `search_ref` and a reused buffer.
+/// Allocation counts per query are printed alongside the timings.
+fn bench_search_ref(c: &mut Criterion) {
+    let mut group = c.benchmark_group("vector_db_search_ref");
+    let dim = 384;
+    let size = 10_000;
+    let db = build_db(dim, size, 99);
+    let mut rng = StdRng::seed_from_u64(789);
+    let query: Vec<f32> = (0..dim).map(|_| rng.gen::<f32>()).collect();
+
+    let owned = allocations_per_call(200, || {
+        black_box(db.search(&query, 10, None).expect("search"));
+    });
+    let mut out = Vec::with_capacity(10);
+    let borrowed = allocations_per_call(200, || {
+        db.search_ref(&query, 10, None, &mut out).expect("search_ref");
+        black_box(out.len());
+    });
+    println!(
+        "allocations/query at dim {dim}, n={size}: search={owned:.1}, search_ref={borrowed:.1}"
+    );
+
+    group.bench_function("search", |b| {
+        b.iter(|| black_box(db.search(black_box(&query), 10, None).expect("search")));
+    });
+    group.bench_function("search_ref", |b| {
+        let mut out = Vec::with_capacity(10);
+        b.iter(|| {
+            db.search_ref(black_box(&query), 10, None, &mut out)
+                .expect("search_ref");
+            black_box(out.len());
+        });
+    });
+
+    group.finish();
+}
+
+/// Runs `readers` search threads alongside `writers` insert threads against one
+/// shared `VectorDb`. Returns (operations/sec, p99 search latency).
+fn run_mixed(
+    db: &VectorDb,
+    dim: usize,
+    readers: usize,
+    writers: usize,
+    ops_per_thread: usize,
+    round: usize,
+) -> (f64, Duration) {
+    let start = Instant::now();
+    let mut latencies: Vec<Duration> = std::thread::scope(|scope| {
+        for w in 0..writers {
+            let items = generate_vectors(ops_per_thread, dim, (round * 64 + w) as u64);
+            scope.spawn(move || {
+                for (id, vector, metadata) in items {
+                    let id = VectorId::from(format!("r{round}_w{w}_{}", id.as_str()));
+                    db.insert_concurrent(id, &vector, metadata).expect("insert");
+                }
+            });
+        }
+
+        let handles: Vec<_> = (0..readers)
+            .map(|r| {
+                let queries = generate_vectors(ops_per_thread, dim, (1000 + r) as u64);
+                scope.spawn(move || {
+                    queries
+                        .iter()
+                        .map(|(_, query, _)| {
+                            let t = Instant::now();
+                            black_box(db.search(query, 10, None).expect("search"));
+                            t.elapsed()
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().expect("reader thread"))
+            .collect()
+    });
+    let elapsed = start.elapsed();
+
+    latencies.sort();
+    let p99 = latencies
+        .get(latencies.len() * 99 / 100)
+        .copied()
+        .unwrap_or_default();
+    let ops = (readers + writers) * ops_per_thread;
+    (ops as f64 / elapsed.as_secs_f64(), p99)
+}
+
+/// Concurrent searches with and without concurrent inserts on a shared db.
+///
+/// `HnswIndex::insert` holds the node-list write lock for its entire neighbor
+/// search, so inserts are fully serialized and every search waits behind the
+/// in-flight insert. Observed on 5k x 128d: 4 readers alone reach ~1.9k ops/s
+/// with a search p99 of ~13 ms; one concurrent writer drops that to ~1.3k ops/s
+/// and doubles the p99 to ~25 ms. Throughput and p99 are printed per mix.
+fn bench_mixed(c: &mut Criterion) {
+    let mut group = c.benchmark_group("vector_db_mixed");
+    group.sample_size(10);
+    let dim = 128;
+    let ops_per_thread = 100;
+
+    for (readers, writers) in [(4_usize, 0_usize), (4, 1), (4, 4), (1, 4)] {
+        // Build one insert at a time: a batch into an empty index leaves the
+        // batch's nodes unlinked, which would make searches unrealistically cheap
+        let db = VectorDb::new(Config {
+            dimensions: dim,
+            distance_metric: DistanceMetric::Cosine,
+            ..Default::default()
+        })
+        .expect("create db");
+        for (id, vector, metadata) in generate_vectors(5_000, dim, 11) {
+            db.insert_concurrent(id, &vector, metadata).expect("insert");
+        }
+
+        let (throughput, p99) = run_mixed(&db, dim, readers, writers, ops_per_thread, 0);
+        println!(
+            "mixed {readers}r/{writers}w: {throughput:.0} ops/s, search p99 {:.3} ms",
+            p99.as_secs_f64() * 1000.0
+        );
+
+        let mut round = 1;
+        group.bench_function(format!("{readers}r_{writers}w"), |b| {
+            b.iter(|| {
+                round += 1;
+                black_box(run_mixed(&db, dim, readers, writers, ops_per_thread, round));
+            });
+        });
+    }
+
+    group.finish();
+}
+
+/// Compares JSON and columnar metadata on 100k vectors: heap held by the
+/// collection, and filtered exact search, which checks every vector's metadata
+fn bench_metadata_format(c: &mut Criterion) {
+    let mut group = c.benchmark_group("vector_db_metadata_format");
+    group.sample_size(10);
+    let dim = 8;
+    let size = 100_000;
+    let categories = ["books", "games", "music", "films", "tools"];
+    let columns = vec![
+        ColumnSpec::new("category", ColumnKind::String),
+        ColumnSpec::new("price", ColumnKind::Integer),
+        ColumnSpec::new("rating", ColumnKind::Float),
+        ColumnSpec::new("in_stock", ColumnKind::Bool),
+    ];
+    let filter = Filter::And(vec![
+        Filter::Range {
+            field: "price".to_string(),
+            gt: None,
+            gte: Some(100.0),
+            lt: Some(400.0),
+            lte: None,
+        },
+        Filter::Exact("category".to_string(), json!("games")),
+    ]);
+    let mut rng = StdRng::seed_from_u64(259);
+    let query: Vec<f32> = (0..dim).map(|_| rng.gen::<f32>()).collect();
+
+    for (name, format) in [
+        ("json", MetadataFormat::Json),
+        ("columnar", MetadataFormat::Columnar(columns)),
+    ] {
+        // The inputs are consumed by the insert, so whatever is still live
+        // afterwards belongs to the collection
+        let before = LIVE_BYTES.load(Ordering::Relaxed);
+        let mut rng = StdRng::seed_from_u64(303);
+        let items: Vec<_> = (0..size)
+            .map(|i| {
+                let vector: Vec<f32> = (0..dim).map(|_| rng.gen::<f32>()).collect();
+                let metadata = json!({
+                    "category": categories[i % categories.len()],
+                    "price": rng.gen_range(0..1000),
+                    "rating": rng.gen_range(0..50) as f64 / 10.0,
+                    "in_stock": rng.gen::<bool>(),
+                });
+                (VectorId::from(format!("vec_{i}")), vector, Some(metadata))
+            })
+            .collect();
+
+        let mut db = VectorDb::new(Config {
+            dimensions: dim,
+            distance_metric: DistanceMetric::Euclidean,
+            hnsw: HnswConfig {
+                m: 4,
+                m0: 8,
+                ef_construction: 16,
+                ..Default::default()
+            },
+            metadata_format: format,
+            ..Default::default()
+        })
+        .expect("create db");
+        db.insert_batch(items).expect("insert batch");
+        let heap = LIVE_BYTES.load(Ordering::Relaxed).saturating_sub(before);
+        eprintln!(
+            "{name}: collection heap {:.1} MB, memory_usage() {:.1} MB",
+            heap as f64 / 1e6,
+            db.memory_usage() as f64 / 1e6
+        );
+
+        group.bench_function(BenchmarkId::new("filtered_exact", name), |b| {
+            b.iter(|| {
+                let results = db
+                    .search_exact_with_metric(
+                        black_box(&query),
+                        10,
+                        DistanceMetric::Euclidean,
+                        Some(&filter),
+                    )
+                    .expect("search filtered");
+                black_box(results.len());
+            });
+        });
+    }
+
+    group.finish();
+}
+
+/// Builds a 100k-node graph with and without `HnswIndex::reserve`, reporting
+/// how many reallocations each build makes
+fn bench_hnsw_reserve(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hnsw_reserve");
+    group.sample_size(10);
+    let dim = 8;
+    let size = 100_000;
+    let config = HnswConfig {
+        m: 4,
+        m0: 8,
+        ef_construction: 16,
+        ..Default::default()
+    };
+    let storage = VectorStorage::new(dim);
+    let mut rng = StdRng::seed_from_u64(267);
+    let vectors: Vec<Vec<f32>> = (0..size)
+        .map(|_| (0..dim).map(|_| rng.gen::<f32>()).collect())
+        .collect();
+    let items: Vec<(InternalId, &[f32])> = vectors
+        .iter()
+        .enumerate()
+        .map(|(i, v)| {
+            let id = storage
+                .insert(VectorId::from(format!("vec_{i}")), v, None)
+                .expect("insert");
+            (id, v.as_slice())
+        })
+        .collect();
+
+    for (name, reserve) in [("grow", false), ("reserve", true)] {
+        let build = || {
+            let index = HnswIndex::new(config.clone(), DistanceMetric::Euclidean);
+            if reserve {
+                index.reserve(items.len());
+            }
+            index.insert_batch(&items, &storage).expect("build");
+            index
+        };
+        let before = REALLOCATIONS.load(Ordering::Relaxed);
+        black_box(build());
+        eprintln!(
+            "{name}: {} reallocations building {size} nodes",
+            REALLOCATIONS.load(Ordering::Relaxed) - before
+        );
+
+        group.bench_function(name, |b| {
+            b.iter(|| black_box(build().len()));
+        });
+    }
+
+    group.finish();
+}
+
+/// Builds a 50k-node graph serially and with `parallel_build`, printing each
+/// one's recall@10
+fn bench_hnsw_parallel_build(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hnsw_parallel_build");
+    group.sample_size(10);
+    let dim = 32;
+    let size = 50_000;
+    let k = 10;
+    let storage = VectorStorage::new(dim);
+    let mut rng = StdRng::seed_from_u64(280);
+    let vectors: Vec<Vec<f32>> = (0..size)
+        .map(|_| (0..dim).map(|_| rng.gen::<f32>()).collect())
+        .collect();
+    let queries: Vec<Vec<f32>> = (0..100)
+        .map(|_| (0..dim).map(|_| rng.gen::<f32>()).collect())
+        .collect();
+    let items: Vec<(InternalId, &[f32])> = vectors
+        .iter()
+        .enumerate()
+        .map(|(i, v)| {
+            let id = storage
+                .insert(VectorId::from(format!("vec_{i}")), v, None)
+                .expect("insert");
+            (id, v.as_slice())
+        })
+        .collect();
+    let exact: Vec<Vec<InternalId>> = queries
+        .iter()
+        .map(|q| {
+            let mut scored: Vec<(InternalId, f32)> = items
+                .iter()
+                .map(|&(id, v)| (id, DistanceMetric::Euclidean.distance(q, v)))
+                .collect();
+            scored.sort_by(|a, b| a.1.total_cmp(&b.1));
+            scored.iter().take(k).map(|&(id, _)| id).collect()
+        })
+        .collect();
+
+    for (name, parallel_build) in [("serial", false), ("parallel", true)] {
+        let config = HnswConfig {
+            parallel_build,
+            ..Default::default()
+        };
+        let build = || {
+            let index = HnswIndex::new(config.clone(), DistanceMetric::Euclidean);
+            if parallel_build {
+                index.insert_batch(&items, &storage).expect("build");
+            } else {
+                for &(id, v) in &items {
+                    index.insert(id, v, &storage).expect("insert");
+                }
+            }
+            index
+        };
+
+        let start = Instant::now();
+        let index = build();
+        let elapsed = start.elapsed();
+        let hits: usize = queries
+            .iter()
+            .zip(&exact)
+            .map(|(q, exact)| {
+                index
+                    .search(q, k, &storage, None)
+                    .expect("search")
+                    .iter()
+                    .filter(|(id, _)| exact.contains(id))
+                    .count()
+            })
+            .sum();
+        eprintln!(
+            "{name}: built {size} nodes in {elapsed:?}, recall@{k} {:.3}",
+            hits as f32 / (queries.len() * k) as f32
+        );
+
+        group.bench_function(name, |b| {
+            b.iter(|| black_box(build().len()));
+        });
+    }
+
+    group.finish();
+}
+
+/// Compares full graph search with centroid-routed search at several
+/// `nprobe` values on clustered data, printing each one's recall@10
+fn bench_centroid_routing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("vector_db_centroid_routing");
+    let dim = 64;
+    let clusters = 32;
+    let size = 20_000;
+    let k = 10;
+    let mut rng = StdRng::seed_from_u64(273);
+    let centers: Vec<Vec<f32>> = (0..clusters)
+        .map(|_| (0..dim).map(|_| rng.gen_range(-4.0..4.0)).collect())
+        .collect();
+    let mut blob = |i: usize| -> Vec<f32> {
+        centers[i % clusters]
+            .iter()
+            .map(|c| c + rng.gen_range(-1.0..1.0))
+            .collect()
+    };
+    let items: Vec<_> = (0..size)
+        .map(|i| (VectorId::from(format!("vec_{i}")), blob(i), None))
+        .collect();
+    let queries: Vec<Vec<f32>> = (0..100).map(&mut blob).collect();
+
+    let mut db = VectorDb::new(Config {
+        dimensions: dim,
+        distance_metric: DistanceMetric::Euclidean,
+        ..Default::default()
+    })
+    .expect("create db");
+    db.insert_batch(items).expect("insert batch");
+    let exact: Vec<Vec<VectorId>> = queries
+        .iter()
+        .map(|q| {
+            db.search_exact_with_metric(q, k, DistanceMetric::Euclidean, None)
+                .expect("exact search")
+                .into_iter()
+                .map(|(id, _, _)| id)
+                .collect()
+        })
+        .collect();
+    let recall = |search: &dyn Fn(&[f32]) -> Vec<VectorId>| {
+        let found: usize = queries
+            .iter()
+            .zip(&exact)
+            .map(|(q, truth)| search(q).iter().filter(|id| truth.contains(id)).count())
+            .sum();
+        found as f64 / (queries.len() * k) as f64
+    };
+    let ids = |hits: Vec<(VectorId, f32, Option<Value>)>| -> Vec<VectorId> {
+        hits.into_iter().map(|(id, _, _)| id).collect()
+    };
+
+    eprintln!(
+        "full: recall@{k} {:.3}",
+        recall(&|q| ids(db.search(q, k, None).expect("search")))
+    );
+    group.bench_function("full", |b| {
+        let mut i = 0;
+        b.iter(|| {
+            i = (i + 1) % queries.len();
+            black_box(db.search(black_box(&queries[i]), k, None).expect("search").len());
+        });
+    });
+
+    db.train_routing(RoutingConfig {
+        num_centroids: clusters,
+        ..Default::default()
+    })
+    .expect("train routing");
+    for nprobe in [2, 4, 8] {
+        let search = |q: &[f32]| db.search_with_nprobe(q, k, nprobe, None).expect("search");
+        eprintln!("nprobe {nprobe}: recall@{k} {:.3}", recall(&|q| ids(search(q))));
+        group.bench_function(BenchmarkId::new("routed", nprobe), |b| {
+            let mut i = 0;
+            b.iter(|| {
+                i = (i + 1) % queries.len();
+                black_box(search(black_box(&queries[i])).len());
+            });
+        });
+    }
+
+    group.finish();
+}
+
 criterion_group!(
     benches,
     bench_insert_single,
     bench_upsert_batch,
     bench_search,
-    bench_search_filtered
+    bench_search_filtered,
+    bench_search_ref,
+    bench_mixed,
+    bench_metadata_format,
+    bench_hnsw_reserve,
+    bench_hnsw_parallel_build,
+    bench_centroid_routing
 );
 criterion_main!(benches);
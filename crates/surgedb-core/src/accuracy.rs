@@ -0,0 +1,161 @@
+//! Quantization accuracy reports
+//!
+//! Measures how faithfully a quantized collection represents its vectors, so
+//! a quantization mode can be judged on real data before committing to it.
+
+use crate::distance::DistanceMetric;
+use crate::quantization::{QuantizationType, SQ8Quantizer};
+use crate::quantized_storage::QuantizedStorage;
+use crate::types::InternalId;
+use serde::{Deserialize, Serialize};
+
+/// Neighbors compared per query for the recall estimate
+pub const RECALL_K: usize = 10;
+
+/// Reconstruction error and recall of a quantized collection on a sample
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccuracyReport {
+    pub quantization: QuantizationType,
+    /// Number of vectors evaluated
+    pub sampled: usize,
+    /// Reconstruction error as `|original - reconstructed| / |original|`
+    pub mean_error: f32,
+    pub p50_error: f32,
+    pub p95_error: f32,
+    pub p99_error: f32,
+    pub max_error: f32,
+    /// Overlap between quantized and exact top-10 neighbors within the sample,
+    /// with each sampled vector as the query (itself excluded)
+    pub recall_at_10: f32,
+}
+
+/// Evaluate quantization accuracy for `samples` of `(slot, original vector)`.
+///
+/// SQ8 vectors are dequantized with their stored min/scale. Binary vectors
+/// keep only signs, so they are reconstructed as `±|original| / sqrt(d)`.
+pub(crate) fn evaluate(
+    storage: &QuantizedStorage,
+    metric: DistanceMetric,
+    samples: &[(InternalId, Vec<f32>)],
+) -> AccuracyReport {
+    let quantization = storage.quantization_type();
+    let dimensions = storage.dimensions();
+
+    let mut errors: Vec<f32> = samples
+        .iter()
+        .map(|(id, original)| {
+            let reconstructed = match quantization {
+                QuantizationType::None => return 0.0,
+                QuantizationType::SQ8 => match (
+                    storage.get_quantized_bytes(*id),
+                    storage.get_sq8_metadata(*id),
+                ) {
+                    (Some(bytes), Some(meta)) => {
+                        SQ8Quantizer::new(dimensions).dequantize(&bytes, &meta)
+                    }
+                    _ => return 1.0,
+                },
+                QuantizationType::Binary => match storage.get_quantized_bytes(*id) {
+                    Some(bits) => {
+                        let magnitude = norm(original) / (dimensions as f32).sqrt();
+                        (0..dimensions)
+                            .map(|i| {
+                                if bits[i / 8] & (1 << (i % 8)) != 0 {
+                                    magnitude
+                                } else {
+                                    -magnitude
+                                }
+                            })
+                            .collect()
+                    }
+                    None => return 1.0,
+                },
+            };
+            relative_error(original, &reconstructed)
+        })
+        .collect();
+    errors.sort_by(|a, b| a.total_cmp(b));
+
+    let mean_error = if errors.is_empty() {
+        0.0
+    } else {
+        errors.iter().sum::<f32>() / errors.len() as f32
+    };
+
+    AccuracyReport {
+        quantization,
+        sampled: samples.len(),
+        mean_error,
+        p50_error: percentile(&errors, 0.50),
+        p95_error: percentile(&errors, 0.95),
+        p99_error: percentile(&errors, 0.99),
+        max_error: errors.last().copied().unwrap_or(0.0),
+        recall_at_10: recall(storage, metric, samples),
+    }
+}
+
+/// Average top-k overlap of quantized vs exact search over the sample
+fn recall(
+    storage: &QuantizedStorage,
+    metric: DistanceMetric,
+    samples: &[(InternalId, Vec<f32>)],
+) -> f32 {
+    let k = RECALL_K.min(samples.len().saturating_sub(1));
+    if k == 0 {
+        return 1.0;
+    }
+
+    let top_k = |mut scored: Vec<(usize, f32)>| -> Vec<usize> {
+        scored.sort_by(|a, b| a.1.total_cmp(&b.1));
+        scored.into_iter().take(k).map(|(i, _)| i).collect()
+    };
+
+    let mut found = 0;
+    for (q, (_, query)) in samples.iter().enumerate() {
+        let others = || samples.iter().enumerate().filter(move |(i, _)| *i != q);
+        let exact = top_k(
+            others()
+                .map(|(i, (_, original))| (i, metric.distance(query, original)))
+                .collect(),
+        );
+        let approximate = top_k(
+            others()
+                .map(|(i, (id, _))| {
+                    let distance = storage.distance(query, *id, metric);
+                    (i, distance.unwrap_or(f32::MAX))
+                })
+                .collect(),
+        );
+        found += approximate.iter().filter(|i| exact.contains(i)).count();
+    }
+
+    found as f32 / (k * samples.len()) as f32
+}
+
+fn norm(v: &[f32]) -> f32 {
+    v.iter().map(|x| x * x).sum::<f32>().sqrt()
+}
+
+fn relative_error(original: &[f32], reconstructed: &[f32]) -> f32 {
+    let diff: f32 = original
+        .iter()
+        .zip(reconstructed)
+        .map(|(a, b)| (a - b) * (a - b))
+        .sum::<f32>()
+        .sqrt();
+    let scale = norm(original);
+    if scale > 0.0 {
+        diff / scale
+    } else {
+        diff
+    }
+}
+
+/// Nearest-rank percentile of sorted values
+fn percentile(sorted: &[f32], q: f32) -> f32 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = ((sorted.len() - 1) as f32 * q).round() as usize;
+    sorted[rank]
+}
@@ -50,10 +50,7 @@ impl BitmapIndex {
                 // Index primitive value
                 if !prefix.is_empty() {
                     let val_str = primitive.to_string();
-                    let field = self
-                        .index
-                        .entry(prefix.to_string())
-                        .or_insert_with(HashMap::new);
+                    let field = self.index.entry(prefix.to_string()).or_default();
                     let entry = field
                         .entry(val_str)
                         .or_insert_with(|| Arc::new(RoaringBitmap::new()));
@@ -111,11 +108,45 @@ impl BitmapIndex {
         }
     }
 
+    /// IDs whose `key` holds `value`, directly or as an array element
+    fn value_bitmap(&self, key: &str, value: &Value) -> RoaringBitmap {
+        self.index
+            .get(key)
+            .and_then(|values| values.get(&value.to_string()))
+            .map(|bitmap| bitmap.as_ref().clone())
+            .unwrap_or_default()
+    }
+
     /// Execute a filter query and return matching internal IDs
     pub fn filter(&self, filter: &crate::filter::Filter) -> Option<Arc<RoaringBitmap>> {
         use crate::filter::Filter;
 
         match filter {
+            // Array elements are indexed like scalars under the array's key,
+            // so a primitive target's bitmap holds the documents containing it
+            Filter::Contains(key, value) if is_primitive(value) => {
+                Some(Arc::new(self.value_bitmap(key, value)))
+            }
+            Filter::ContainsAny(key, values) if values.iter().all(is_primitive) => {
+                let mut result = RoaringBitmap::new();
+                for val in values {
+                    result |= self.value_bitmap(key, val);
+                }
+                Some(Arc::new(result))
+            }
+            Filter::ContainsAll(key, values)
+                if !values.is_empty() && values.iter().all(is_primitive) =>
+            {
+                let mut result = self.value_bitmap(key, &values[0]);
+                for val in &values[1..] {
+                    result &= self.value_bitmap(key, val);
+                }
+                Some(Arc::new(result))
+            }
+            Filter::Contains(..) | Filter::ContainsAny(..) | Filter::ContainsAll(..) => {
+                // Nested arrays and objects aren't indexed as values
+                None
+            }
             Filter::Exact(key, value) => {
                 if let Some(values) = self.index.get(key) {
                     values.get(&value.to_string()).cloned()
@@ -177,6 +208,10 @@ impl BitmapIndex {
     }
 }
 
+fn is_primitive(value: &Value) -> bool {
+    !value.is_array() && !value.is_object()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -213,4 +248,35 @@ mod tests {
         assert!(result.contains(3));
         assert_eq!(result.len(), 1);
     }
+
+    #[test]
+    fn test_contains_uses_array_elements() {
+        use crate::filter::Filter;
+
+        let mut index = BitmapIndex::new();
+        index.index(InternalId::from(1), &json!({ "tags": ["ai", "db"] }));
+        index.index(InternalId::from(2), &json!({ "tags": ["db"] }));
+        index.index(InternalId::from(3), &json!({ "tags": "ai" }));
+
+        let ids = |filter: Filter| index.filter(&filter).unwrap().iter().collect::<Vec<_>>();
+        assert_eq!(ids(Filter::Contains("tags".into(), json!("ai"))), [1, 3]);
+        assert_eq!(
+            ids(Filter::ContainsAny(
+                "tags".into(),
+                vec![json!("ai"), json!("db")]
+            )),
+            [1, 2, 3]
+        );
+        assert_eq!(
+            ids(Filter::ContainsAll(
+                "tags".into(),
+                vec![json!("ai"), json!("db")]
+            )),
+            [1]
+        );
+        // Non-primitive targets fall back to scanning
+        assert!(index
+            .filter(&Filter::Contains("tags".into(), json!(["ai"])))
+            .is_none());
+    }
 }
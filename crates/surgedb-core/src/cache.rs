@@ -0,0 +1,255 @@
+//! Result cache for `VectorDb::search_by_id`
+//!
+//! "Related items" lookups repeat the same neighbor search for a document
+//! until something changes. Entries are keyed by the document's primary ID
+//! and `k`, and every write to the collection drops them all: any insert,
+//! delete or metadata change can alter another document's neighbors or the
+//! metadata returned with them.
+
+use crate::sync::RwLock;
+use crate::types::{SearchHit, VectorId};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Settings for caching `search_by_id` results
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SimilarCacheConfig {
+    /// Most result lists kept; the least recently used is evicted beyond this
+    pub capacity: usize,
+    /// How long a result list is served after it was computed; `None` keeps
+    /// it until the next write. Needs a system clock, which
+    /// `wasm32-unknown-unknown` does not have.
+    pub ttl: Option<Duration>,
+}
+
+impl Default for SimilarCacheConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 1024,
+            ttl: None,
+        }
+    }
+}
+
+/// Counters reported by `VectorDb::similar_cache_stats`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct SimilarCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Result lists currently cached
+    pub entries: usize,
+}
+
+type Hits = Vec<SearchHit>;
+
+struct Entry {
+    results: Hits,
+    /// `State::tick` at the last lookup, for LRU eviction
+    last_used: u64,
+    /// Only taken when a TTL is configured
+    created: Option<Instant>,
+}
+
+#[derive(Default)]
+struct State {
+    /// Bumped by every write; results computed under an older generation
+    /// are not stored
+    generation: u64,
+    tick: u64,
+    hits: u64,
+    misses: u64,
+    entries: HashMap<(VectorId, usize), Entry>,
+}
+
+pub(crate) struct SimilarCache {
+    config: Option<SimilarCacheConfig>,
+    state: RwLock<State>,
+}
+
+impl SimilarCache {
+    pub(crate) fn new(config: Option<SimilarCacheConfig>) -> Self {
+        Self {
+            config,
+            state: RwLock::new(State::default()),
+        }
+    }
+
+    /// The generation to pass to `put`, or `None` when caching is disabled
+    pub(crate) fn generation(&self) -> Option<u64> {
+        self.config.map(|_| self.state.read().generation)
+    }
+
+    pub(crate) fn get(&self, id: &VectorId, k: usize) -> Option<Hits> {
+        let config = self.config?;
+        let mut state = self.state.write();
+        state.tick += 1;
+        let tick = state.tick;
+        let key = (id.clone(), k);
+
+        let expired = match state.entries.get(&key) {
+            Some(entry) => entry
+                .created
+                .zip(config.ttl)
+                .is_some_and(|(created, ttl)| created.elapsed() > ttl),
+            None => {
+                state.misses += 1;
+                return None;
+            }
+        };
+        if expired {
+            state.entries.remove(&key);
+            state.misses += 1;
+            return None;
+        }
+
+        state.hits += 1;
+        let entry = state.entries.get_mut(&key)?;
+        entry.last_used = tick;
+        Some(entry.results.clone())
+    }
+
+    /// Cache `results` unless a write happened since `generation` was read
+    pub(crate) fn put(&self, id: VectorId, k: usize, generation: u64, results: &Hits) {
+        let Some(config) = self.config else {
+            return;
+        };
+        if config.capacity == 0 {
+            return;
+        }
+        let mut state = self.state.write();
+        if state.generation != generation {
+            return;
+        }
+
+        let key = (id, k);
+        if !state.entries.contains_key(&key) && state.entries.len() >= config.capacity {
+            let oldest = state
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                state.entries.remove(&oldest);
+            }
+        }
+        let last_used = state.tick;
+        state.entries.insert(
+            key,
+            Entry {
+                results: results.clone(),
+                last_used,
+                created: config.ttl.map(|_| Instant::now()),
+            },
+        );
+    }
+
+    /// Drop every entry after a write
+    pub(crate) fn invalidate(&self) {
+        if self.config.is_none() {
+            return;
+        }
+        let mut state = self.state.write();
+        state.generation += 1;
+        state.entries.clear();
+    }
+
+    pub(crate) fn stats(&self) -> SimilarCacheStats {
+        let state = self.state.read();
+        SimilarCacheStats {
+            hits: state.hits,
+            misses: state.misses,
+            entries: state.entries.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::Filter;
+    use crate::{Config, MetadataUpdate, VectorDb};
+    use serde_json::json;
+
+    fn seeded_db(config: SimilarCacheConfig) -> VectorDb {
+        let mut db = VectorDb::new(Config {
+            dimensions: 4,
+            similar_cache: Some(config),
+            ..Default::default()
+        })
+        .unwrap();
+        for i in 0..20 {
+            let vector: Vec<f32> = (0..4).map(|d| ((i * 4 + d) as f32).sin()).collect();
+            db.insert(format!("doc{}", i), &vector, Some(json!({ "i": i })))
+                .unwrap();
+        }
+        db
+    }
+
+    #[test]
+    fn test_search_by_id_hits_cache_until_update() {
+        let mut db = seeded_db(SimilarCacheConfig::default());
+
+        let first = db.search_by_id("doc3", 5, None, true).unwrap();
+        let second = db.search_by_id("doc3", 5, None, true).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(
+            db.similar_cache_stats(),
+            SimilarCacheStats {
+                hits: 1,
+                misses: 1,
+                entries: 1
+            }
+        );
+
+        // The neighbors' metadata is part of the cached results
+        let neighbor = first[0].0.to_string();
+        db.set_metadata(
+            &neighbor,
+            json!({ "edited": true }),
+            MetadataUpdate::Replace,
+        )
+        .unwrap();
+        assert_eq!(db.similar_cache_stats().entries, 0);
+        let refreshed = db.search_by_id("doc3", 5, None, true).unwrap();
+        assert_eq!(refreshed[0].2, Some(json!({ "edited": true })));
+        assert_eq!(db.similar_cache_stats().misses, 2);
+
+        db.upsert("doc3", &[-1.0, 0.0, 0.0, 0.0], None).unwrap();
+        let moved = db.search_by_id("doc3", 5, None, true).unwrap();
+        assert_ne!(moved, refreshed);
+        assert_eq!(db.similar_cache_stats().misses, 3);
+
+        // Filtered lookups are never cached
+        let filter = Filter::Not(Box::new(Filter::Exact("i".into(), json!(-1))));
+        db.search_by_id("doc3", 5, Some(&filter), true).unwrap();
+        assert_eq!(db.similar_cache_stats().hits, 1);
+    }
+
+    #[test]
+    fn test_similar_cache_evicts_and_expires() {
+        let db = seeded_db(SimilarCacheConfig {
+            capacity: 2,
+            ttl: None,
+        });
+        db.search_by_id("doc1", 3, None, true).unwrap();
+        db.search_by_id("doc2", 3, None, true).unwrap();
+        db.search_by_id("doc1", 3, None, true).unwrap();
+        db.search_by_id("doc3", 3, None, true).unwrap();
+        // doc2 was least recently used
+        assert_eq!(db.similar_cache_stats().entries, 2);
+        db.search_by_id("doc1", 3, None, true).unwrap();
+        assert_eq!(db.similar_cache_stats().hits, 2);
+        db.search_by_id("doc2", 3, None, true).unwrap();
+        assert_eq!(db.similar_cache_stats().misses, 4);
+
+        let db = seeded_db(SimilarCacheConfig {
+            capacity: 16,
+            ttl: Some(Duration::from_millis(1)),
+        });
+        db.search_by_id("doc1", 3, None, true).unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        db.search_by_id("doc1", 3, None, true).unwrap();
+        assert_eq!(db.similar_cache_stats().hits, 0);
+    }
+}
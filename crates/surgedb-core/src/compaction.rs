@@ -0,0 +1,450 @@
+//! Compaction shared by every collection type
+//!
+//! Deletes and overwrites only tombstone slots, so storage keeps growing
+//! until it is rewritten without them. A compaction runs in three phases:
+//!
+//! 1. [`Plan`]: copy the live records out under a read lock, with empty
+//!    storage and graphs to rebuild into.
+//! 2. [`Plan::rebuild`]: fill them, holding no lock on the collection.
+//! 3. [`Rebuilt::catch_up`]: under the write lock, apply the writes made
+//!    since the plan was taken, just before the collection swaps the
+//!    rebuilt storage and graphs in.
+//!
+//! [`schedule`] runs them on a background thread once a collection is past
+//! its `auto_compact_fraction`, so deletes and upserts never wait for a
+//! rebuild; the write lock is only held for the catch-up and swap.
+
+use crate::error::{Error, Result};
+use crate::hnsw::HnswIndex;
+use crate::quantized_storage::QuantizedStorage;
+use crate::storage::{VectorStorage, VectorStorageTrait};
+use crate::sync::RwLock;
+use crate::types::{InternalId, VectorId};
+use serde_json::Value;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+use tracing::{debug, warn};
+
+/// Fail unless `fraction` is a usable `auto_compact_fraction`
+pub(crate) fn validate_fraction(fraction: f32) -> Result<()> {
+    if !(0.0..1.0).contains(&fraction) {
+        return Err(Error::InvalidConfig(format!(
+            "auto_compact_fraction must be in [0, 1), got {}",
+            fraction
+        )));
+    }
+    Ok(())
+}
+
+/// Whether `tombstones` out of `slots` are past `fraction` (0 = never)
+pub(crate) fn due(fraction: f32, tombstones: usize, slots: usize) -> bool {
+    fraction > 0.0 && tombstones as f32 > fraction * slots as f32
+}
+
+/// `Sync` where graphs are built on several threads
+#[cfg(feature = "parallel")]
+pub(crate) trait Shared: Sync {}
+#[cfg(feature = "parallel")]
+impl<T: Sync> Shared for T {}
+#[cfg(not(feature = "parallel"))]
+pub(crate) trait Shared {}
+#[cfg(not(feature = "parallel"))]
+impl<T> Shared for T {}
+
+/// Slot-level access to the storage types a collection compacts
+pub(crate) trait Slots: VectorStorageTrait + Shared {
+    /// Physical slots, including deleted and superseded ones
+    fn slot_count(&self) -> usize;
+    fn external_id(&self, slot: InternalId) -> Option<VectorId>;
+    fn internal_id(&self, id: &VectorId) -> Option<InternalId>;
+    fn metadata(&self, slot: InternalId) -> Option<Value>;
+    /// Store `vector` under `id`, superseding any vector already there
+    fn put(&self, id: VectorId, vector: &[f32], metadata: Option<Value>) -> Result<InternalId>;
+    fn remove(&self, id: &VectorId) -> Result<bool>;
+    /// Replace a stored vector's metadata; `null` clears it
+    fn replace_metadata(&self, id: &VectorId, metadata: Value) -> Result<()>;
+
+    /// The ID stored in `slot`, unless the slot was deleted or superseded
+    fn live_id(&self, slot: InternalId) -> Option<VectorId> {
+        self.external_id(slot)
+            .filter(|id| self.internal_id(id) == Some(slot))
+    }
+}
+
+impl Slots for VectorStorage {
+    fn slot_count(&self) -> usize {
+        self.total_slots()
+    }
+
+    fn external_id(&self, slot: InternalId) -> Option<VectorId> {
+        self.get_external_id(slot)
+    }
+
+    fn internal_id(&self, id: &VectorId) -> Option<InternalId> {
+        self.get_internal_id(id)
+    }
+
+    fn metadata(&self, slot: InternalId) -> Option<Value> {
+        self.get_metadata(slot)
+    }
+
+    fn put(&self, id: VectorId, vector: &[f32], metadata: Option<Value>) -> Result<InternalId> {
+        self.upsert(id, vector, metadata)
+    }
+
+    fn remove(&self, id: &VectorId) -> Result<bool> {
+        self.delete(id)
+    }
+
+    fn replace_metadata(&self, id: &VectorId, metadata: Value) -> Result<()> {
+        self.update_metadata(id, metadata)
+    }
+}
+
+impl Slots for QuantizedStorage {
+    fn slot_count(&self) -> usize {
+        self.total_slots()
+    }
+
+    fn external_id(&self, slot: InternalId) -> Option<VectorId> {
+        self.get_external_id(slot)
+    }
+
+    fn internal_id(&self, id: &VectorId) -> Option<InternalId> {
+        self.get_internal_id(id)
+    }
+
+    fn metadata(&self, slot: InternalId) -> Option<Value> {
+        self.get_metadata(slot)
+    }
+
+    fn put(&self, id: VectorId, vector: &[f32], metadata: Option<Value>) -> Result<InternalId> {
+        self.upsert(id, vector, metadata)
+    }
+
+    fn remove(&self, id: &VectorId) -> Result<bool> {
+        self.delete(id)
+    }
+
+    fn replace_metadata(&self, id: &VectorId, metadata: Value) -> Result<()> {
+        self.update_metadata(id, metadata)
+    }
+}
+
+/// A collection type that can be compacted
+pub(crate) trait Compact {
+    type Storage: Slots;
+
+    /// Only needed to run compactions on their own thread
+    #[cfg_attr(
+        not(all(feature = "parallel", not(target_arch = "wasm32"))),
+        allow(dead_code)
+    )]
+    fn compactor(&self) -> &Compactor;
+
+    /// Whether tombstones are past the configured `auto_compact_fraction`
+    fn compaction_due(&self) -> bool;
+
+    /// Phase 1: snapshot the live records
+    fn plan_compaction(&self) -> Plan<Self::Storage>;
+
+    /// Phase 3: catch up and swap the rebuilt state in, returning how many
+    /// slots were reclaimed
+    fn finish_compaction(&mut self, rebuilt: Rebuilt<Self::Storage>) -> Result<usize>;
+}
+
+/// Run all three phases at once
+pub(crate) fn compact<C: Compact>(db: &mut C) -> Result<usize> {
+    let rebuilt = db.plan_compaction().rebuild()?;
+    db.finish_compaction(rebuilt)
+}
+
+/// Start a background compaction of `db` if one is due and none is running
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+pub(crate) fn schedule<C>(db: &Arc<RwLock<C>>) -> Result<()>
+where
+    C: Compact + Send + Sync + 'static,
+    C::Storage: Send,
+{
+    let guard = db.read();
+    if !guard.compaction_due() {
+        return Ok(());
+    }
+    let shared = Arc::clone(db);
+    guard.compactor().start(move || {
+        let plan = shared.read().plan_compaction();
+        match plan
+            .rebuild()
+            .and_then(|rebuilt| shared.write().finish_compaction(rebuilt))
+        {
+            Ok(reclaimed) => debug!("Background compaction reclaimed {} slots", reclaimed),
+            Err(e) => warn!("Background compaction failed: {}", e),
+        }
+    });
+    Ok(())
+}
+
+/// Compact `db` if it is due (sequential version for WASM)
+#[cfg(not(all(feature = "parallel", not(target_arch = "wasm32"))))]
+pub(crate) fn schedule<C: Compact>(db: &Arc<RwLock<C>>) -> Result<()> {
+    let mut guard = db.write();
+    if guard.compaction_due() {
+        compact(&mut *guard)?;
+    }
+    Ok(())
+}
+
+/// Block until the background compaction of `db`, if any, has finished
+pub(crate) fn wait<C: Compact>(db: &Arc<RwLock<C>>) {
+    #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+    {
+        // Join outside the lock: the job needs it to finish
+        let handle = db.read().compactor().handle.lock().take();
+        if let Some(handle) = handle {
+            let _ = handle.join();
+        }
+    }
+    #[cfg(not(all(feature = "parallel", not(target_arch = "wasm32"))))]
+    let _ = db;
+}
+
+/// Per-collection compaction state
+#[derive(Default)]
+pub(crate) struct Compactor {
+    /// Bumped whenever storage is replaced; a rebuild planned against older
+    /// storage is dropped instead of swapped in
+    epoch: AtomicU64,
+    #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+    handle: parking_lot::Mutex<Option<std::thread::JoinHandle<()>>>,
+}
+
+impl Compactor {
+    /// Record that storage was replaced, by a compaction or a clear
+    pub fn invalidate(&self) {
+        self.epoch.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn epoch(&self) -> u64 {
+        self.epoch.load(Ordering::Relaxed)
+    }
+
+    /// Run `job` on its own thread unless the previous one is still running
+    #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+    fn start(&self, job: impl FnOnce() + Send + 'static) {
+        let mut handle = self.handle.lock();
+        if handle.as_ref().is_some_and(|h| !h.is_finished()) {
+            return;
+        }
+        *handle = Some(std::thread::spawn(job));
+    }
+}
+
+/// A live record copied out of the old storage
+struct Record {
+    slot: InternalId,
+    id: VectorId,
+    vector: Vec<f32>,
+    metadata: Option<Value>,
+}
+
+/// Phase 1 output: the live records and what to rebuild them into
+pub(crate) struct Plan<S> {
+    epoch: u64,
+    /// Slots in the old storage when the records were copied
+    slots: usize,
+    records: Vec<Record>,
+    storage: S,
+    indexes: Vec<HnswIndex>,
+}
+
+impl<S: Slots> Plan<S> {
+    /// Copy the live records of `current`, to be rebuilt into the empty
+    /// `storage` and `indexes`
+    pub fn new(compactor: &Compactor, current: &S, storage: S, indexes: Vec<HnswIndex>) -> Self {
+        let slots = current.slot_count();
+        let records = (0..slots)
+            .map(InternalId::from)
+            .filter_map(|slot| {
+                let id = current.live_id(slot)?;
+                Some(Record {
+                    slot,
+                    id,
+                    vector: current.get_vector_data(slot)?,
+                    metadata: current.metadata(slot),
+                })
+            })
+            .collect();
+        Self {
+            epoch: compactor.epoch(),
+            slots,
+            records,
+            storage,
+            indexes,
+        }
+    }
+
+    /// Phase 2: store the records in slot order and build every graph
+    pub fn rebuild(self) -> Result<Rebuilt<S>> {
+        let mut moved = Vec::with_capacity(self.records.len());
+        for record in &self.records {
+            let slot =
+                self.storage
+                    .put(record.id.clone(), &record.vector, record.metadata.clone())?;
+            moved.push(slot);
+        }
+        let items: Vec<(InternalId, &[f32])> = moved
+            .iter()
+            .zip(&self.records)
+            .map(|(slot, record)| (*slot, record.vector.as_slice()))
+            .collect();
+        for index in &self.indexes {
+            index.build(&items, &self.storage)?;
+        }
+
+        let moved = moved
+            .into_iter()
+            .zip(self.records)
+            .map(|(new, record)| Moved {
+                old: record.slot,
+                new,
+                metadata: record.metadata,
+            })
+            .collect();
+        Ok(Rebuilt {
+            epoch: self.epoch,
+            slots: self.slots,
+            moved,
+            storage: self.storage,
+            indexes: self.indexes,
+        })
+    }
+}
+
+/// A record's slot in the old and the rebuilt storage
+struct Moved {
+    old: InternalId,
+    new: InternalId,
+    /// Metadata as copied, to spot later updates
+    metadata: Option<Value>,
+}
+
+/// Phase 2 output: rebuilt storage and graphs, as of the plan
+pub(crate) struct Rebuilt<S> {
+    epoch: u64,
+    slots: usize,
+    moved: Vec<Moved>,
+    pub storage: S,
+    /// In the order they were handed to `Plan::new`
+    pub indexes: Vec<HnswIndex>,
+}
+
+impl<S: Slots> Rebuilt<S> {
+    /// Phase 3: apply the writes `current` took since the plan, returning
+    /// false if its storage has been replaced since and the rebuild is stale
+    ///
+    /// Records deleted or superseded since are dropped, metadata updated in
+    /// place is copied over, and vectors written to new slots are added.
+    pub fn catch_up(
+        &mut self,
+        compactor: &Compactor,
+        current: &S,
+        eager_edge_cleanup: bool,
+    ) -> Result<bool> {
+        if compactor.epoch() != self.epoch {
+            return Ok(false);
+        }
+
+        for moved in &self.moved {
+            match current.live_id(moved.old) {
+                None => {
+                    if let Some(id) = self.storage.external_id(moved.new) {
+                        self.storage.remove(&id)?;
+                        if eager_edge_cleanup {
+                            for index in &self.indexes {
+                                index.remove(moved.new, &self.storage);
+                            }
+                        }
+                    }
+                }
+                Some(id) => {
+                    let metadata = current.metadata(moved.old);
+                    if metadata != moved.metadata {
+                        self.storage
+                            .replace_metadata(&id, metadata.unwrap_or(Value::Null))?;
+                    }
+                }
+            }
+        }
+
+        for slot in (self.slots..current.slot_count()).map(InternalId::from) {
+            let (Some(id), Some(vector)) = (current.live_id(slot), current.get_vector_data(slot))
+            else {
+                continue;
+            };
+            let new = self.storage.put(id, &vector, current.metadata(slot))?;
+            for index in &self.indexes {
+                index.insert(new, &vector, &self.storage)?;
+            }
+        }
+
+        compactor.invalidate();
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Config, DistanceMetric, MetadataUpdate, VectorDb};
+    use serde_json::json;
+
+    fn db() -> VectorDb {
+        let mut db = VectorDb::new(Config {
+            dimensions: 2,
+            distance_metric: DistanceMetric::Euclidean,
+            ..Default::default()
+        })
+        .unwrap();
+        for i in 0..10 {
+            db.insert(format!("v{}", i), &[i as f32, 0.0], None)
+                .unwrap();
+        }
+        db.delete("v9").unwrap();
+        db
+    }
+
+    #[test]
+    fn test_catch_up_applies_writes_made_during_rebuild() {
+        let mut db = db();
+        let rebuilt = db.plan_compaction().rebuild().unwrap();
+
+        db.delete("v0").unwrap();
+        db.upsert("v1", &[1.0, 5.0], None).unwrap();
+        db.set_metadata("v2", json!({"tag": "x"}), MetadataUpdate::Replace)
+            .unwrap();
+        db.insert("new", &[20.0, 0.0], None).unwrap();
+
+        db.finish_compaction(rebuilt).unwrap();
+        assert_eq!(db.len(), 9);
+        assert!(db.get("v0").unwrap().is_none());
+        assert_eq!(db.get("v1").unwrap().unwrap().0, vec![1.0, 5.0]);
+        assert_eq!(db.get("v2").unwrap().unwrap().1, Some(json!({"tag": "x"})));
+        let results = db.search(&[20.0, 0.0], 1, None).unwrap();
+        assert_eq!(results[0].0.as_str(), "new");
+        let results = db.search(&[1.0, 5.0], 1, None).unwrap();
+        assert_eq!(results[0].0.as_str(), "v1");
+    }
+
+    #[test]
+    fn test_rebuild_of_replaced_storage_is_dropped() {
+        let mut db = db();
+        let rebuilt = db.plan_compaction().rebuild().unwrap();
+        db.clear();
+        db.insert("a", &[1.0, 1.0], None).unwrap();
+
+        assert_eq!(db.finish_compaction(rebuilt).unwrap(), 0);
+        assert_eq!(db.len(), 1);
+        assert!(db.get("v1").unwrap().is_none());
+    }
+}
@@ -1,7 +1,8 @@
 use crate::sync::RwLock;
-use crate::types::VectorId;
+use crate::types::{ListPage, VectorId};
 use crate::{
-    Config, Error, QuantizationType, QuantizedConfig, QuantizedVectorDb, Result, VectorDb,
+    Config, DistanceMetric, Error, Facets, QuantizationType, QuantizedConfig, QuantizedVectorDb,
+    Result, VectorDb,
 };
 use serde::Serialize;
 use serde_json::Value;
@@ -9,17 +10,140 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use tracing::{debug, info};
 
+/// Apply `search` to every query on the rayon pool, keeping query order
+#[cfg(feature = "parallel")]
+fn search_each<T: Send>(
+    queries: &[(Vec<f32>, usize)],
+    search: impl Fn(&[f32], usize) -> Result<T> + Sync,
+) -> Result<Vec<T>> {
+    use rayon::prelude::*;
+    queries
+        .par_iter()
+        .map(|(query, k)| search(query, *k))
+        .collect()
+}
+
+#[cfg(not(feature = "parallel"))]
+fn search_each<T>(
+    queries: &[(Vec<f32>, usize)],
+    search: impl Fn(&[f32], usize) -> Result<T>,
+) -> Result<Vec<T>> {
+    queries.iter().map(|(query, k)| search(query, *k)).collect()
+}
+
+/// How `Collection::search_with_options` searches; every option combines
+/// with the others
+#[derive(Debug, Clone, Default)]
+pub struct SearchOptions {
+    /// HNSW candidate list size (at least `k`); the collection's `ef_search`
+    /// when unset
+    pub ef: Option<usize>,
+    pub filter: Option<crate::filter::Filter>,
+    /// Keep only the closest hit per distinct value of this metadata field,
+    /// for up to `k` groups (see `VectorDb::search_grouped`)
+    pub group_by: Option<String>,
+    /// With `group_by`: how hits lacking the field are treated
+    pub group_missing: crate::MissingGroup,
+    /// Attach each hit's stored vector, as `get` returns it
+    pub include_vectors: bool,
+    /// Report the routing lists probed in `SearchOutcome::routing`
+    pub debug: bool,
+}
+
+/// Hits of `Collection::search_with_options`
+#[derive(Debug, Clone, Default)]
+pub struct SearchOutcome {
+    /// Each hit with its stored vector, when `include_vectors` is set
+    pub hits: Vec<(crate::types::SearchHit, Option<Vec<f32>>)>,
+    /// Traversal stats; with `group_by`, those of the final pass
+    pub stats: crate::SearchStats,
+    /// With `debug`, the lists the search probed (see
+    /// `VectorDb::search_debug`); `None` unless routing is trained
+    pub routing: Option<crate::RoutingDebug>,
+}
+
+/// Hits, stats and routing report of one pass of `search_with_options`
+type Pass = (
+    Vec<crate::types::SearchHit>,
+    crate::SearchStats,
+    Option<crate::RoutingDebug>,
+);
+
+/// Run one search under `options` against a locked collection of `len`
+/// vectors, given a `search_with_ef_debug` or equivalent and its `get`
+fn search_with_options(
+    query: &[f32],
+    k: usize,
+    options: &SearchOptions,
+    len: usize,
+    search: impl Fn(&[f32], usize, Option<usize>, Option<&crate::filter::Filter>) -> Result<Pass>,
+    get: impl Fn(&str) -> Result<Option<(Vec<f32>, Option<Value>)>>,
+) -> Result<SearchOutcome> {
+    let search = |n| search(query, n, options.ef, options.filter.as_ref());
+    let (hits, stats, routing) = match &options.group_by {
+        Some(field) => {
+            let last = std::cell::Cell::new((crate::SearchStats::default(), None));
+            let hits = crate::group::search_grouped(k, field, options.group_missing, len, |n| {
+                let (hits, stats, routing) = search(n)?;
+                last.set((stats, routing));
+                Ok(hits)
+            })?;
+            let (stats, routing) = last.take();
+            (hits, stats, routing)
+        }
+        None => search(k)?,
+    };
+    let hits = if options.include_vectors {
+        attach_vectors(hits, get)?
+            .into_iter()
+            .map(|(hit, vector)| (hit, Some(vector)))
+            .collect()
+    } else {
+        hits.into_iter().map(|hit| (hit, None)).collect()
+    };
+    Ok(SearchOutcome {
+        hits,
+        stats,
+        routing,
+    })
+}
+
+/// `search_with_ef_stats` with no routing report, for collections that
+/// cannot be routed
+fn unrouted((hits, stats): (Vec<crate::types::SearchHit>, crate::SearchStats)) -> Pass {
+    (hits, stats, None)
+}
+
+/// Pair each hit with its vector from `get`, dropping hits that have none
+fn attach_vectors(
+    hits: Vec<crate::types::SearchHit>,
+    get: impl Fn(&str) -> Result<Option<(Vec<f32>, Option<Value>)>>,
+) -> Result<Vec<(crate::types::SearchHit, Vec<f32>)>> {
+    let mut with_vectors = Vec::with_capacity(hits.len());
+    for hit in hits {
+        if let Some((vector, _)) = get(&hit.0.as_str())? {
+            with_vectors.push((hit, vector));
+        }
+    }
+    Ok(with_vectors)
+}
+
+/// Size and configuration of one collection
 #[derive(Debug, Clone, Serialize)]
 pub struct CollectionStats {
+    pub name: String,
     pub vector_count: usize,
-    pub memory_usage_bytes: usize,
-    pub quantization: String,
     pub dimensions: usize,
+    pub distance_metric: DistanceMetric,
+    pub quantization: String,
+    /// Estimated in-memory size of vectors, metadata and the index
+    pub memory_bytes: usize,
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct DatabaseStats {
-    pub collections: HashMap<String, CollectionStats>,
+    /// Per-collection breakdown, sorted by name
+    pub collections: Vec<CollectionStats>,
     pub total_vectors: usize,
     pub total_memory_bytes: usize,
 }
@@ -35,28 +159,79 @@ pub enum Collection {
 impl Collection {
     pub fn insert(&self, id: String, vector: &[f32], metadata: Option<Value>) -> Result<()> {
         match self {
-            Collection::Standard(db) => db.write().insert(id, vector, metadata),
+            // Shared lock: searches keep running during the graph insert
+            Collection::Standard(db) => db.read().insert_concurrent(id, vector, metadata),
             Collection::Quantized(db) => db.write().insert(id, vector, metadata),
             #[cfg(feature = "persistence")]
             Collection::Persistent(db) => db.write().insert(id, vector, metadata),
         }
     }
 
-    pub fn upsert(&self, id: String, vector: &[f32], metadata: Option<Value>) -> Result<()> {
+    /// Insert several vectors under one write lock, returning each one's
+    /// outcome in order
+    ///
+    /// Every item keeps the semantics of `insert`: an ID that is already
+    /// stored, or taken earlier in the batch, fails with `DuplicateId`
+    /// without affecting the others.
+    pub fn insert_each(&self, items: Vec<(String, Vec<f32>, Option<Value>)>) -> Vec<Result<()>> {
         match self {
-            Collection::Standard(db) => db.write().upsert(id, vector, metadata),
-            Collection::Quantized(db) => db.write().upsert(id, vector, metadata),
+            Collection::Standard(db) => {
+                let mut db = db.write();
+                let batch = items
+                    .iter()
+                    .map(|(id, vector, metadata)| {
+                        (
+                            VectorId::from(id.as_str()),
+                            vector.clone(),
+                            metadata.clone(),
+                        )
+                    })
+                    .collect();
+                // The batch insert is all-or-nothing, so on failure each item
+                // is retried on its own to find the ones at fault
+                if db.insert_batch(batch).is_ok() {
+                    return items.iter().map(|_| Ok(())).collect();
+                }
+                items
+                    .into_iter()
+                    .map(|(id, vector, metadata)| db.insert(id, &vector, metadata))
+                    .collect()
+            }
+            Collection::Quantized(db) => {
+                let mut db = db.write();
+                items
+                    .into_iter()
+                    .map(|(id, vector, metadata)| db.insert(id, &vector, metadata))
+                    .collect()
+            }
             #[cfg(feature = "persistence")]
             Collection::Persistent(db) => {
                 let mut db = db.write();
-                let _ = db.delete(id.clone());
-                db.insert(id, vector, metadata)
+                items
+                    .into_iter()
+                    .map(|(id, vector, metadata)| db.insert(id, &vector, metadata))
+                    .collect()
             }
         }
     }
 
+    pub fn upsert(&self, id: String, vector: &[f32], metadata: Option<Value>) -> Result<()> {
+        let upserted = match self {
+            Collection::Standard(db) => db.write().upsert(id, vector, metadata),
+            Collection::Quantized(db) => db.write().upsert(id, vector, metadata),
+            #[cfg(feature = "persistence")]
+            Collection::Persistent(db) => db.write().transaction(vec![crate::Op::Upsert {
+                id: id.into(),
+                vector: vector.to_vec(),
+                metadata,
+            }]),
+        };
+        upserted?;
+        self.compact_if_due()
+    }
+
     pub fn upsert_batch(&self, items: Vec<(String, Vec<f32>, Option<Value>)>) -> Result<()> {
-        match self {
+        let upserted = match self {
             Collection::Standard(db) => {
                 let items_converted: Vec<(VectorId, Vec<f32>, Option<Value>)> = items
                     .into_iter()
@@ -72,23 +247,118 @@ impl Collection {
                 db.write().upsert_batch(items_converted)
             }
             #[cfg(feature = "persistence")]
-            Collection::Persistent(db) => {
-                let mut db = db.write();
-                for (id, vector, metadata) in items {
-                    let _ = db.delete(id.clone());
-                    db.insert(id, &vector, metadata)?;
-                }
-                Ok(())
-            }
-        }
+            Collection::Persistent(db) => db.write().transaction(
+                items
+                    .into_iter()
+                    .map(|(id, vector, metadata)| crate::Op::Upsert {
+                        id: id.into(),
+                        vector,
+                        metadata,
+                    })
+                    .collect(),
+            ),
+        };
+        upserted?;
+        self.compact_if_due()
     }
 
     pub fn delete(&self, id: &str) -> Result<bool> {
+        let deleted = match self {
+            Collection::Standard(db) => db.write().delete(id)?,
+            Collection::Quantized(db) => db.write().delete(id)?,
+            #[cfg(feature = "persistence")]
+            Collection::Persistent(db) => db.write().delete(id)?,
+        };
+        if deleted {
+            self.compact_if_due()?;
+        }
+        Ok(deleted)
+    }
+
+    /// Rebuild storage and the graph without deleted or superseded slots,
+    /// returning how many slots were reclaimed
+    pub fn compact(&self) -> Result<usize> {
+        match self {
+            Collection::Standard(db) => db.write().compact(),
+            Collection::Quantized(db) => db.write().compact(),
+            #[cfg(feature = "persistence")]
+            Collection::Persistent(db) => db.write().compact(),
+        }
+    }
+
+    /// Number of deleted or superseded slots awaiting compaction
+    pub fn deleted_count(&self) -> usize {
         match self {
-            Collection::Standard(db) => db.write().delete(id),
-            Collection::Quantized(db) => db.write().delete(id),
+            Collection::Standard(db) => db.read().deleted_count(),
+            Collection::Quantized(db) => db.read().deleted_count(),
             #[cfg(feature = "persistence")]
-            Collection::Persistent(db) => db.write().delete(id),
+            Collection::Persistent(db) => db.read().deleted_count(),
+        }
+    }
+
+    /// Start a background compaction once tombstones are past the
+    /// configured `auto_compact_fraction`
+    ///
+    /// Searches and writes keep running while storage and the graph are
+    /// rebuilt; the write lock is only taken to apply what changed meanwhile
+    /// and swap the result in. Without the `parallel` feature it compacts
+    /// in place.
+    fn compact_if_due(&self) -> Result<()> {
+        match self {
+            Collection::Standard(db) => crate::compaction::schedule(db),
+            Collection::Quantized(db) => crate::compaction::schedule(db),
+            #[cfg(feature = "persistence")]
+            Collection::Persistent(db) => crate::compaction::schedule(db),
+        }
+    }
+
+    /// Block until a background compaction, if one is running, has finished
+    pub fn wait_for_compaction(&self) {
+        match self {
+            Collection::Standard(db) => crate::compaction::wait(db),
+            Collection::Quantized(db) => crate::compaction::wait(db),
+            #[cfg(feature = "persistence")]
+            Collection::Persistent(db) => crate::compaction::wait(db),
+        }
+    }
+
+    /// Drop every vector, keeping the collection and its configuration
+    pub fn clear(&self) -> Result<()> {
+        match self {
+            Collection::Standard(db) => db.write().clear(),
+            Collection::Quantized(db) => db.write().clear(),
+            #[cfg(feature = "persistence")]
+            Collection::Persistent(db) => return db.write().clear(),
+        }
+        Ok(())
+    }
+
+    /// Merge `patch` into the metadata of every vector matching `filter`
+    pub fn update_metadata_by_filter(
+        &self,
+        filter: &crate::filter::Filter,
+        patch: &Value,
+    ) -> Result<usize> {
+        match self {
+            Collection::Standard(db) => db.write().update_metadata_by_filter(filter, patch),
+            Collection::Quantized(db) => db.write().update_metadata_by_filter(filter, patch),
+            #[cfg(feature = "persistence")]
+            Collection::Persistent(db) => db.write().update_metadata_by_filter(filter, patch),
+        }
+    }
+
+    /// Replace or merge the metadata of one vector, leaving its vector alone
+    pub fn set_metadata(
+        &self,
+        id: &str,
+        metadata: Value,
+        mode: crate::types::MetadataUpdate,
+    ) -> Result<()> {
+        match self {
+            Collection::Standard(db) => db.write().set_metadata(id, metadata, mode),
+            Collection::Quantized(db) => db.write().set_metadata(id, metadata, mode),
+            #[cfg(feature = "persistence")]
+            Collection::Persistent(db) => db.write().set_metadata(id, metadata, mode),
         }
     }
 
@@ -115,6 +385,266 @@ impl Collection {
         }
     }
 
+    pub fn search_with_stats(
+        &self,
+        query: &[f32],
+        k: usize,
+        filter: Option<&crate::filter::Filter>,
+    ) -> Result<(Vec<crate::types::SearchHit>, crate::SearchStats)> {
+        match self {
+            Collection::Standard(db) => db.read().search_with_stats(query, k, filter),
+            Collection::Quantized(db) => db.read().search_with_stats(query, k, filter),
+            #[cfg(feature = "persistence")]
+            Collection::Persistent(db) => db.read().search_with_stats(query, k, filter),
+        }
+    }
+
+    /// Like `search`, with each hit's stored vector attached
+    ///
+    /// Vectors are read under the same lock as the search, so each one is
+    /// the hit's. They are what `get` returns: for quantized collections
+    /// without `keep_originals`, the de-quantized approximation.
+    pub fn search_with_vectors(
+        &self,
+        query: &[f32],
+        k: usize,
+        filter: Option<&crate::filter::Filter>,
+    ) -> Result<Vec<(crate::types::SearchHit, Vec<f32>)>> {
+        match self {
+            Collection::Standard(db) => {
+                let db = &*db.read();
+                attach_vectors(db.search(query, k, filter)?, |id| db.get(id))
+            }
+            Collection::Quantized(db) => {
+                let db = &*db.read();
+                attach_vectors(db.search(query, k, filter)?, |id| db.get(id))
+            }
+            #[cfg(feature = "persistence")]
+            Collection::Persistent(db) => {
+                let db = &*db.read();
+                attach_vectors(db.search(query, k, filter)?, |id| db.get(id))
+            }
+        }
+    }
+
+    /// Train nearest-centroid routing (see `VectorDb::train_routing`);
+    /// quantized and persistent collections cannot be routed
+    pub fn train_routing(&self, config: crate::RoutingConfig) -> Result<()> {
+        match self {
+            Collection::Standard(db) => db.write().train_routing(config),
+            Collection::Quantized(_) => Err(Error::InvalidConfig(
+                "routing is not supported for quantized collections".to_string(),
+            )),
+            #[cfg(feature = "persistence")]
+            Collection::Persistent(_) => Err(Error::InvalidConfig(
+                "routing is not supported for persistent collections".to_string(),
+            )),
+        }
+    }
+
+    /// Search under `options`, all read under one lock
+    pub fn search_with_options(
+        &self,
+        query: &[f32],
+        k: usize,
+        options: &SearchOptions,
+    ) -> Result<SearchOutcome> {
+        match self {
+            Collection::Standard(db) => {
+                let db = &*db.read();
+                search_with_options(
+                    query,
+                    k,
+                    options,
+                    db.len(),
+                    |query, n, ef, filter| {
+                        if options.debug {
+                            db.search_with_ef_debug(query, n, ef, filter)
+                        } else {
+                            db.search_with_ef_stats(query, n, ef, filter).map(unrouted)
+                        }
+                    },
+                    |id| db.get(id),
+                )
+            }
+            Collection::Quantized(db) => {
+                let db = &*db.read();
+                search_with_options(
+                    query,
+                    k,
+                    options,
+                    db.len(),
+                    |query, n, ef, filter| {
+                        db.search_with_ef_stats(query, n, ef, filter).map(unrouted)
+                    },
+                    |id| db.get(id),
+                )
+            }
+            #[cfg(feature = "persistence")]
+            Collection::Persistent(db) => {
+                let db = &*db.read();
+                search_with_options(
+                    query,
+                    k,
+                    options,
+                    db.len(),
+                    |query, n, ef, filter| {
+                        db.search_with_ef_stats(query, n, ef, filter).map(unrouted)
+                    },
+                    |id| db.get(id),
+                )
+            }
+        }
+    }
+
+    /// Search with a candidate list of `ef` instead of the configured `ef_search`
+    pub fn search_with_ef(
+        &self,
+        query: &[f32],
+        k: usize,
+        ef: usize,
+        filter: Option<&crate::filter::Filter>,
+    ) -> Result<Vec<(VectorId, f32, Option<Value>)>> {
+        match self {
+            Collection::Standard(db) => db.read().search_with_ef(query, k, ef, filter),
+            Collection::Quantized(db) => db.read().search_with_ef(query, k, ef, filter),
+            #[cfg(feature = "persistence")]
+            Collection::Persistent(db) => db.read().search_with_ef(query, k, ef, filter),
+        }
+    }
+
+    /// Search with the stored vector of `id` as the query, optionally
+    /// leaving `id` itself out of the results
+    pub fn search_by_id(
+        &self,
+        id: &str,
+        k: usize,
+        filter: Option<&crate::filter::Filter>,
+        exclude_self: bool,
+    ) -> Result<Vec<(VectorId, f32, Option<Value>)>> {
+        match self {
+            Collection::Standard(db) => db.read().search_by_id(id, k, filter, exclude_self),
+            Collection::Quantized(db) => db.read().search_by_id(id, k, filter, exclude_self),
+            #[cfg(feature = "persistence")]
+            Collection::Persistent(db) => db.read().search_by_id(id, k, filter, exclude_self),
+        }
+    }
+
+    /// Run several searches under one lock, each with its own `k`
+    ///
+    /// Returns one result list per `(query, k)` pair, in order.
+    /// Run several searches under one read lock, returning results in query
+    /// order; with the `parallel` feature the queries run on the rayon pool
+    pub fn search_batch(
+        &self,
+        queries: &[(Vec<f32>, usize)],
+        filter: Option<&crate::filter::Filter>,
+    ) -> Result<Vec<Vec<crate::types::SearchHit>>> {
+        match self {
+            Collection::Standard(db) => {
+                let db = &*db.read();
+                search_each(queries, |query, k| db.search(query, k, filter))
+            }
+            Collection::Quantized(db) => {
+                let db = &*db.read();
+                search_each(queries, |query, k| db.search(query, k, filter))
+            }
+            #[cfg(feature = "persistence")]
+            Collection::Persistent(db) => {
+                let db = &*db.read();
+                search_each(queries, |query, k| db.search(query, k, filter))
+            }
+        }
+    }
+
+    /// `search_batch` under `options`, applied to every query
+    pub fn search_batch_with_options(
+        &self,
+        queries: &[(Vec<f32>, usize)],
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchOutcome>> {
+        match self {
+            Collection::Standard(db) => {
+                let db = &*db.read();
+                search_each(queries, |query, k| {
+                    search_with_options(
+                        query,
+                        k,
+                        options,
+                        db.len(),
+                        |query, n, ef, filter| {
+                            if options.debug {
+                                db.search_with_ef_debug(query, n, ef, filter)
+                            } else {
+                                db.search_with_ef_stats(query, n, ef, filter).map(unrouted)
+                            }
+                        },
+                        |id| db.get(id),
+                    )
+                })
+            }
+            Collection::Quantized(db) => {
+                let db = &*db.read();
+                search_each(queries, |query, k| {
+                    search_with_options(
+                        query,
+                        k,
+                        options,
+                        db.len(),
+                        |query, n, ef, filter| {
+                            db.search_with_ef_stats(query, n, ef, filter).map(unrouted)
+                        },
+                        |id| db.get(id),
+                    )
+                })
+            }
+            #[cfg(feature = "persistence")]
+            Collection::Persistent(db) => {
+                let db = &*db.read();
+                search_each(queries, |query, k| {
+                    search_with_options(
+                        query,
+                        k,
+                        options,
+                        db.len(),
+                        |query, n, ef, filter| {
+                            db.search_with_ef_stats(query, n, ef, filter).map(unrouted)
+                        },
+                        |id| db.get(id),
+                    )
+                })
+            }
+        }
+    }
+
+    pub fn search_grouped(
+        &self,
+        query: &[f32],
+        k: usize,
+        group_by: &str,
+        missing: crate::MissingGroup,
+        filter: Option<&crate::filter::Filter>,
+    ) -> Result<Vec<crate::types::SearchHit>> {
+        match self {
+            Collection::Standard(db) => db
+                .read()
+                .search_grouped(query, k, group_by, missing, filter),
+            Collection::Quantized(db) => {
+                let db = db.read();
+                crate::group::search_grouped(k, group_by, missing, db.len(), |n| {
+                    db.search(query, n, filter)
+                })
+            }
+            #[cfg(feature = "persistence")]
+            Collection::Persistent(db) => {
+                let db = db.read();
+                crate::group::search_grouped(k, group_by, missing, db.len(), |n| {
+                    db.search(query, n, filter)
+                })
+            }
+        }
+    }
+
     pub fn search_ids(
         &self,
         query: &[f32],
@@ -138,62 +668,232 @@ impl Collection {
         }
     }
 
-    pub fn stats(&self) -> CollectionStats {
+    pub fn list_after(&self, cursor: Option<u64>, limit: usize) -> ListPage {
+        match self {
+            Collection::Standard(db) => db.read().list_after(cursor, limit),
+            Collection::Quantized(db) => db.read().list_after(cursor, limit),
+            #[cfg(feature = "persistence")]
+            Collection::Persistent(db) => db.read().list_after(cursor, limit),
+        }
+    }
+
+    /// Iterate over every record in listing order, `page_size` at a time
+    ///
+    /// Each page takes the collection's read lock only while it is fetched,
+    /// so writes can interleave with a long export.
+    pub fn export(&self, page_size: usize) -> ExportPages {
+        ExportPages {
+            collection: self.clone(),
+            cursor: None,
+            page_size: page_size.max(1),
+            done: false,
+        }
+    }
+
+    pub fn facet(&self, field: &str, top_n: usize, sample: Option<usize>) -> Facets {
+        match self {
+            Collection::Standard(db) => db.read().facet_sampled(field, top_n, sample),
+            Collection::Quantized(db) => db.read().facet_sampled(field, top_n, sample),
+            #[cfg(feature = "persistence")]
+            Collection::Persistent(db) => db.read().facet_sampled(field, top_n, sample),
+        }
+    }
+
+    /// Size and configuration of this collection, reported under `name`
+    pub fn stats(&self, name: &str) -> CollectionStats {
+        let name = name.to_string();
         match self {
             Collection::Standard(db) => {
                 let db = db.read();
                 CollectionStats {
+                    name,
                     vector_count: db.len(),
-                    memory_usage_bytes: db.memory_usage(),
-                    quantization: "None".to_string(),
                     dimensions: db.config().dimensions,
+                    distance_metric: db.config().distance_metric,
+                    quantization: "None".to_string(),
+                    memory_bytes: db.memory_usage(),
                 }
             }
             Collection::Quantized(db) => {
                 let db = db.read();
                 CollectionStats {
+                    name,
                     vector_count: db.len(),
-                    memory_usage_bytes: db.memory_usage(),
-                    quantization: format!("{:?}", db.config().quantization),
                     dimensions: db.config().dimensions,
+                    distance_metric: db.config().distance_metric,
+                    quantization: format!("{:?}", db.config().quantization),
+                    memory_bytes: db.memory_usage(),
                 }
             }
             #[cfg(feature = "persistence")]
             Collection::Persistent(db) => {
                 let db = db.read();
-                let disk_usage = if let Ok(metadata) = std::fs::metadata(db.data_dir()) {
-                    if metadata.is_dir() {
-                        get_dir_size(db.data_dir()).unwrap_or(0)
-                    } else {
-                        metadata.len()
-                    }
-                } else {
-                    0
-                };
                 CollectionStats {
+                    name,
                     vector_count: db.len(),
-                    memory_usage_bytes: disk_usage as usize,
-                    quantization: "None".to_string(),
                     dimensions: db.config().dimensions,
+                    distance_metric: db.config().distance_metric,
+                    quantization: "None".to_string(),
+                    memory_bytes: db.memory_usage(),
                 }
             }
         }
     }
-}
 
-#[cfg(feature = "persistence")]
-fn get_dir_size(path: impl AsRef<std::path::Path>) -> std::io::Result<u64> {
-    let mut size = 0;
-    for entry in std::fs::read_dir(path)? {
-        let entry = entry?;
-        let metadata = entry.metadata()?;
-        if metadata.is_dir() {
-            size += get_dir_size(entry.path())?;
-        } else {
-            size += metadata.len();
+    /// The collection's effective configuration as JSON
+    pub fn config(&self) -> Value {
+        let config = match self {
+            Collection::Standard(db) => serde_json::to_value(db.read().config()),
+            Collection::Quantized(db) => serde_json::to_value(db.read().config()),
+            #[cfg(feature = "persistence")]
+            Collection::Persistent(db) => serde_json::to_value(db.read().config()),
+        };
+        config.unwrap_or(Value::Null)
+    }
+
+    /// Number of live vectors
+    pub fn len(&self) -> usize {
+        match self {
+            Collection::Standard(db) => db.read().len(),
+            Collection::Quantized(db) => db.read().len(),
+            #[cfg(feature = "persistence")]
+            Collection::Persistent(db) => db.read().len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Configured vector dimensionality (before any projection)
+    pub fn dimensions(&self) -> usize {
+        match self {
+            Collection::Standard(db) => db.read().config().dimensions,
+            Collection::Quantized(db) => db.read().config().dimensions,
+            #[cfg(feature = "persistence")]
+            Collection::Persistent(db) => db.read().config().dimensions,
+        }
+    }
+
+    /// Metric search distances are measured in
+    pub fn distance_metric(&self) -> crate::DistanceMetric {
+        match self {
+            Collection::Standard(db) => db.read().config().distance_metric,
+            Collection::Quantized(db) => db.read().config().distance_metric,
+            #[cfg(feature = "persistence")]
+            Collection::Persistent(db) => db.read().config().distance_metric,
+        }
+    }
+
+    /// Score presentation used when a search does not pick one
+    pub fn score_transform(&self) -> crate::ScoreTransform {
+        match self {
+            Collection::Standard(db) => db.read().config().score_transform,
+            Collection::Quantized(db) => db.read().config().score_transform,
+            #[cfg(feature = "persistence")]
+            Collection::Persistent(db) => db.read().config().score_transform,
+        }
+    }
+
+    pub fn optimize(&self) -> crate::hnsw::DegreeReport {
+        match self {
+            Collection::Standard(db) => db.read().optimize(),
+            Collection::Quantized(db) => db.read().optimize(),
+            #[cfg(feature = "persistence")]
+            Collection::Persistent(db) => db.read().optimize(),
+        }
+    }
+
+    /// Sampled self-recall of the collection's index, or `None` for a
+    /// quantized collection without one
+    pub fn self_recall(&self, sample: usize, k: usize, seed: u64) -> Option<crate::SelfRecall> {
+        match self {
+            Collection::Standard(db) => Some(db.read().self_recall(sample, k, seed)),
+            Collection::Quantized(db) => db.read().self_recall(sample, k, seed),
+            #[cfg(feature = "persistence")]
+            Collection::Persistent(db) => Some(db.read().self_recall(sample, k, seed)),
+        }
+    }
+
+    /// Up to `limit` edges of HNSW layer `layer`, evenly spaced through the
+    /// graph when it has more, or `None` for a quantized collection without
+    /// an index
+    pub fn graph_edges(&self, layer: usize, limit: usize) -> Option<GraphEdges> {
+        match self {
+            Collection::Standard(db) => {
+                let db = db.read();
+                Some(GraphEdges::sample(|| db.graph_edges(layer), limit))
+            }
+            Collection::Quantized(db) => {
+                let db = db.read();
+                let indexed = db.graph_edges(layer).is_some();
+                let edges = || db.graph_edges(layer).into_iter().flatten();
+                indexed.then(|| GraphEdges::sample(edges, limit))
+            }
+            #[cfg(feature = "persistence")]
+            Collection::Persistent(db) => {
+                let db = db.read();
+                Some(GraphEdges::sample(|| db.graph_edges(layer), limit))
+            }
+        }
+    }
+
+    /// Snapshot a persistent collection and truncate its WAL
+    ///
+    /// Returns `false` for in-memory collections, which have nothing to checkpoint.
+    #[cfg(feature = "persistence")]
+    pub fn checkpoint(&self) -> Result<bool> {
+        match self {
+            Collection::Standard(_) | Collection::Quantized(_) => Ok(false),
+            Collection::Persistent(db) => db.write().checkpoint().map(|_| true),
+        }
+    }
+
+    /// Ship the latest snapshot chain through `shipper` if it is due,
+    /// returning how many files were copied
+    ///
+    /// Returns `None` for in-memory collections, which have no snapshots,
+    /// and when the shipper's interval has not passed yet.
+    #[cfg(feature = "persistence")]
+    pub fn ship_snapshots(
+        &self,
+        shipper: &mut crate::shipping::SnapshotShipper,
+    ) -> Result<Option<usize>> {
+        match self {
+            Collection::Standard(_) | Collection::Quantized(_) => Ok(None),
+            Collection::Persistent(db) => shipper.maybe_ship(&db.read()),
+        }
+    }
+
+    #[cfg(feature = "persistence")]
+    pub fn index_stats(&self, timestamp_ms: u64) -> crate::timeseries::IndexStatsSample {
+        let (len, memory_usage_bytes, physical_len, disk_usage_bytes) = match self {
+            Collection::Standard(db) => {
+                let db = db.read();
+                (db.len(), db.memory_usage(), db.physical_len(), 0)
+            }
+            Collection::Quantized(db) => {
+                let db = db.read();
+                (db.len(), db.memory_usage(), db.physical_len(), 0)
+            }
+            Collection::Persistent(db) => {
+                let db = db.read();
+                (
+                    db.len(),
+                    db.memory_usage(),
+                    db.physical_len(),
+                    db.disk_usage(),
+                )
+            }
+        };
+        crate::timeseries::IndexStatsSample {
+            timestamp_ms,
+            len,
+            memory_usage_bytes,
+            tombstone_count: physical_len - len,
+            disk_usage_bytes,
         }
     }
-    Ok(size)
 }
 
 pub struct Database {
@@ -245,6 +945,17 @@ impl Database {
                         dimensions: config.dimensions,
                         distance_metric: config.distance_metric,
                         hnsw: config.hnsw.clone(),
+                        default_metadata: config.default_metadata.clone(),
+                        max_metadata_bytes: config.max_metadata_bytes,
+                        projection: config.projection,
+                        normalize: config.normalize,
+                        require_unit_norm: config.require_unit_norm,
+                        validate_finite: config.validate_finite,
+                        validate_query: config.validate_query,
+                        dimension_mismatch: config.dimension_mismatch,
+                        score_transform: config.score_transform,
+                        insert_mode: config.insert_mode,
+                        auto_compact_fraction: config.auto_compact_fraction,
                         ..Default::default()
                     };
                     let p_db = crate::persistent::PersistentVectorDb::open(entry.path(), p_config)?;
@@ -266,6 +977,11 @@ impl Database {
 
         #[cfg(feature = "persistence")]
         let collection = if let Some(base_path) = &self.path {
+            if !config.additional_metrics.is_empty() {
+                return Err(Error::InvalidConfig(
+                    "additional_metrics is not supported for persistent collections".to_string(),
+                ));
+            }
             let col_path = base_path.join(name);
             std::fs::create_dir_all(&col_path)?;
             let meta_path = col_path.join("metadata.json");
@@ -277,6 +993,17 @@ impl Database {
                 dimensions: config.dimensions,
                 distance_metric: config.distance_metric,
                 hnsw: config.hnsw,
+                default_metadata: config.default_metadata,
+                max_metadata_bytes: config.max_metadata_bytes,
+                projection: config.projection,
+                normalize: config.normalize,
+                require_unit_norm: config.require_unit_norm,
+                validate_finite: config.validate_finite,
+                validate_query: config.validate_query,
+                dimension_mismatch: config.dimension_mismatch,
+                score_transform: config.score_transform,
+                insert_mode: config.insert_mode,
+                auto_compact_fraction: config.auto_compact_fraction,
                 ..Default::default()
             };
             let p_db = crate::persistent::PersistentVectorDb::open(col_path, p_config)?;
@@ -296,6 +1023,18 @@ impl Database {
         if config.quantization == QuantizationType::None {
             let db = VectorDb::new(config)?;
             Ok(Collection::Standard(Arc::new(RwLock::new(db))))
+        } else if config.normalize {
+            Err(Error::InvalidConfig(
+                "normalize is not supported for quantized collections".to_string(),
+            ))
+        } else if !config.additional_metrics.is_empty() {
+            Err(Error::InvalidConfig(
+                "additional_metrics is not supported for quantized collections".to_string(),
+            ))
+        } else if config.insert_mode != crate::InsertMode::Always {
+            Err(Error::InvalidConfig(
+                "insert_mode is not supported for quantized collections".to_string(),
+            ))
         } else {
             let q_config = QuantizedConfig {
                 dimensions: config.dimensions,
@@ -304,6 +1043,18 @@ impl Database {
                 quantization: config.quantization,
                 keep_originals: false,
                 rerank_multiplier: 3,
+                adaptive_rerank: false,
+                rerank_gap_threshold: 0.05,
+                default_metadata: config.default_metadata,
+                max_metadata_bytes: config.max_metadata_bytes,
+                projection: config.projection,
+                require_unit_norm: config.require_unit_norm,
+                validate_finite: config.validate_finite,
+                validate_query: config.validate_query,
+                dimension_mismatch: config.dimension_mismatch,
+                use_index: true,
+                score_transform: config.score_transform,
+                auto_compact_fraction: config.auto_compact_fraction,
             };
             let db = QuantizedVectorDb::new(q_config)?;
             Ok(Collection::Quantized(Arc::new(RwLock::new(db))))
@@ -334,26 +1085,137 @@ impl Database {
             .ok_or_else(|| Error::CollectionNotFound(name.to_string()))
     }
 
+    /// Size and configuration of the collection `name`
+    pub fn collection_stats(&self, name: &str) -> Result<CollectionStats> {
+        self.get_collection(name)
+            .map(|collection| collection.stats(name))
+    }
+
     pub fn list_collections(&self) -> Vec<String> {
         self.collections.read().keys().cloned().collect()
     }
 
+    /// Sample index statistics for every collection
+    #[cfg(feature = "persistence")]
+    pub fn index_stats(
+        &self,
+        timestamp_ms: u64,
+    ) -> HashMap<String, crate::timeseries::IndexStatsSample> {
+        self.collections
+            .read()
+            .iter()
+            .map(|(name, collection)| (name.clone(), collection.index_stats(timestamp_ms)))
+            .collect()
+    }
+
+    /// Checkpoint every persistent collection, returning each one's outcome
+    #[cfg(feature = "persistence")]
+    pub fn checkpoint_all(&self) -> Vec<(String, Result<()>)> {
+        let collections: Vec<(String, Collection)> = self
+            .collections
+            .read()
+            .iter()
+            .map(|(name, collection)| (name.clone(), collection.clone()))
+            .collect();
+        collections
+            .into_iter()
+            .filter_map(|(name, collection)| match collection.checkpoint() {
+                Ok(false) => None,
+                Ok(true) => Some((name, Ok(()))),
+                Err(e) => Some((name, Err(e))),
+            })
+            .collect()
+    }
+
     pub fn get_stats(&self) -> DatabaseStats {
         let collections = self.collections.read();
-        let mut stats_map = HashMap::new();
-        let mut total_vectors = 0;
-        let mut total_memory = 0;
-        for (name, collection) in collections.iter() {
-            let stats = collection.stats();
-            total_vectors += stats.vector_count;
-            total_memory += stats.memory_usage_bytes;
-            stats_map.insert(name.clone(), stats);
-        }
+        let mut stats: Vec<CollectionStats> = collections
+            .iter()
+            .map(|(name, collection)| collection.stats(name))
+            .collect();
+        stats.sort_by(|a, b| a.name.cmp(&b.name));
         DatabaseStats {
-            collections: stats_map,
-            total_vectors,
-            total_memory_bytes: total_memory,
+            total_vectors: stats.iter().map(|s| s.vector_count).sum(),
+            total_memory_bytes: stats.iter().map(|s| s.memory_bytes).sum(),
+            collections: stats,
+        }
+    }
+}
+
+/// One exported record: ID, vector and metadata
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportRecord {
+    pub id: VectorId,
+    pub vector: Vec<f32>,
+    pub metadata: Option<Value>,
+}
+
+/// Edges of one HNSW layer from `Collection::graph_edges`
+#[derive(Debug, Clone)]
+pub struct GraphEdges {
+    /// Live edges on the layer
+    pub total: usize,
+    /// `(from, to)` pairs: all of them, or every n-th when there are more
+    /// than the requested limit
+    pub edges: Vec<(VectorId, VectorId)>,
+}
+
+impl GraphEdges {
+    /// Count the edges, then walk them again taking an evenly spaced sample
+    fn sample<I>(edges: impl Fn() -> I, limit: usize) -> Self
+    where
+        I: Iterator<Item = (VectorId, VectorId)>,
+    {
+        let total = edges().count();
+        let step = total.div_ceil(limit.max(1)).max(1);
+        Self {
+            total,
+            edges: edges().step_by(step).take(limit).collect(),
+        }
+    }
+}
+
+/// Pages of full records from `Collection::export`
+///
+/// Pages follow `list_after`, so a vector updated during the export moves to
+/// the end and may appear twice, and one deleted before its page is skipped.
+pub struct ExportPages {
+    collection: Collection,
+    cursor: Option<u64>,
+    page_size: usize,
+    done: bool,
+}
+
+impl Iterator for ExportPages {
+    type Item = Result<Vec<ExportRecord>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let listed = self.collection.list_after(self.cursor, self.page_size);
+        self.cursor = listed.next_cursor;
+        self.done = self.cursor.is_none();
+
+        let mut page = Vec::with_capacity(listed.entries.len());
+        for (id, _) in listed.entries {
+            match self.collection.get(&id.as_str()) {
+                Ok(Some((vector, metadata))) => page.push(ExportRecord {
+                    id,
+                    vector,
+                    metadata,
+                }),
+                Ok(None) => {}
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        if page.is_empty() && self.done {
+            return None;
         }
+        Some(Ok(page))
     }
 }
 
@@ -1,12 +1,23 @@
 //! SIMD-optimized distance calculations
 //!
 //! This module provides highly optimized distance functions using platform-specific
-//! SIMD instructions (NEON on ARM, AVX on x86).
+//! SIMD instructions (NEON on ARM, AVX on x86). Above `BLOCKED_DISTANCE_THRESHOLD`
+//! dimensions, most metrics accumulate over cache-sized blocks instead.
 
+use crate::error::{Error, Result};
 use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Vectors with more dimensions than this are compared block by block (see
+/// `distance_block`)
+pub const BLOCKED_DISTANCE_THRESHOLD: usize = 1024;
+
+/// Default block size in dimensions: 1 KiB per side, so a block of both
+/// vectors stays in L1 while its partial sums are taken
+pub const DEFAULT_DISTANCE_BLOCK: usize = 256;
 
 /// Distance metric to use for vector similarity
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
 pub enum DistanceMetric {
     /// Cosine similarity (1 - cos(a, b))
     /// Best for normalized embeddings (OpenAI, sentence-transformers)
@@ -20,6 +31,34 @@ pub enum DistanceMetric {
     /// Dot product (inner product)
     /// Fast but requires normalized vectors for proper similarity
     DotProduct,
+
+    /// Manhattan distance (L1 norm)
+    /// Less sensitive than Euclidean to a few large per-dimension differences
+    Manhattan,
+
+    /// Weighted blend of direction and magnitude:
+    /// `w * cosine + (1 - w) * |a - b| / (|a| + |b|)`
+    ///
+    /// The euclidean term is scaled into [0, 1] so the weight is meaningful
+    /// across vector magnitudes. The blend is not a true metric, so HNSW
+    /// navigation on it is heuristic and recall can drop; it is best used
+    /// for exact or re-rank search (e.g. quantized collections that keep
+    /// originals). `cosine_weight = 1.0` ranks exactly like `Cosine`.
+    Hybrid { cosine_weight: f32 },
+
+    /// Number of differing bits between binary codes
+    ///
+    /// Only valid for binary-quantized collections. Float vectors are
+    /// compared by the bits they quantize to (1 where a component is
+    /// positive), so reranking against kept originals agrees with the codes.
+    Hamming,
+
+    /// Jaccard distance between binary codes read as sets of set bits:
+    /// `1 - |a ∩ b| / |a ∪ b|`, in [0, 1], and 0 between two empty sets
+    ///
+    /// Only valid for binary-quantized collections, e.g. for minhash or
+    /// other set-similarity signatures.
+    Jaccard,
 }
 
 impl DistanceMetric {
@@ -30,12 +69,187 @@ impl DistanceMetric {
             DistanceMetric::Cosine => cosine_distance(a, b),
             DistanceMetric::Euclidean => euclidean_distance(a, b),
             DistanceMetric::DotProduct => dot_product_distance(a, b),
+            DistanceMetric::Manhattan => manhattan_distance(a, b),
+            DistanceMetric::Hybrid { cosine_weight } => hybrid_distance(a, b, *cosine_weight),
+            DistanceMetric::Hamming => hamming_distance(a, b),
+            DistanceMetric::Jaccard => jaccard_distance(a, b),
+        }
+    }
+
+    /// Like `distance`, always using the portable scalar kernels, as a
+    /// reference to check the SIMD paths against
+    pub fn distance_scalar(&self, a: &[f32], b: &[f32]) -> f32 {
+        match self {
+            DistanceMetric::Cosine => cosine_distance_scalar(a, b),
+            DistanceMetric::Euclidean => euclidean_distance_scalar(a, b),
+            DistanceMetric::DotProduct => 1.0 - dot_product_scalar(a, b),
+            DistanceMetric::Manhattan => manhattan_distance_scalar(a, b),
+            DistanceMetric::Hybrid { cosine_weight } => {
+                let norms = dot_product_scalar(a, a).sqrt() + dot_product_scalar(b, b).sqrt();
+                let euclidean = if norms > 0.0 {
+                    euclidean_distance_scalar(a, b) / norms
+                } else {
+                    0.0
+                };
+                cosine_weight * cosine_distance_scalar(a, b) + (1.0 - cosine_weight) * euclidean
+            }
+            DistanceMetric::Hamming => hamming_distance(a, b),
+            DistanceMetric::Jaccard => jaccard_distance(a, b),
+        }
+    }
+
+    /// True for the metrics over bit sets, Hamming and Jaccard
+    pub fn is_binary(&self) -> bool {
+        matches!(self, DistanceMetric::Hamming | DistanceMetric::Jaccard)
+    }
+
+    /// Check that this metric is meaningful over a collection's storage
+    ///
+    /// Binary metrics need binary-quantized storage (`binary_storage`);
+    /// every other metric works on any storage.
+    pub fn check_storage(&self, binary_storage: bool) -> Result<()> {
+        if self.is_binary() && !binary_storage {
+            return Err(Error::InvalidConfig(format!(
+                "{:?} distance is only supported with binary quantization",
+                self
+            )));
+        }
+        Ok(())
+    }
+
+    /// Metric giving identical distances over unit-length vectors, at lower cost
+    ///
+    /// Cosine reduces to dot product once both sides are normalized, so the
+    /// per-comparison norm computation can be skipped.
+    #[inline]
+    pub fn for_normalized(self) -> Self {
+        match self {
+            DistanceMetric::Cosine => DistanceMetric::DotProduct,
+            other => other,
+        }
+    }
+
+    /// Convert a distance under this metric to a similarity (higher is closer)
+    ///
+    /// - Cosine: `1 - distance`, the cosine similarity in [-1, 1]
+    /// - DotProduct: `1 - distance`, which is the dot product itself, since
+    ///   its distance is stored as `1 - dot`
+    /// - Euclidean, Manhattan: `1 / (1 + distance)`, in (0, 1]
+    /// - Hybrid: `1 - distance`, as for Cosine
+    /// - Hamming: `1 / (1 + distance)`, as for Euclidean
+    /// - Jaccard: `1 - distance`, the Jaccard index in [0, 1]
+    ///
+    /// Distance 0 maps to similarity 1 for every metric.
+    #[inline]
+    pub fn to_similarity(&self, distance: f32) -> f32 {
+        match self {
+            DistanceMetric::Cosine
+            | DistanceMetric::DotProduct
+            | DistanceMetric::Hybrid { .. }
+            | DistanceMetric::Jaccard => 1.0 - distance,
+            DistanceMetric::Euclidean | DistanceMetric::Manhattan | DistanceMetric::Hamming => {
+                1.0 / (1.0 + distance)
+            }
+        }
+    }
+
+    /// Inverse of [`to_similarity`](Self::to_similarity), e.g. to turn a
+    /// similarity threshold into a distance bound
+    ///
+    /// For Euclidean, Manhattan and Hamming, similarities outside (0, 1] have no
+    /// distance and give a negative or infinite result.
+    #[inline]
+    pub fn from_similarity(&self, similarity: f32) -> f32 {
+        match self {
+            DistanceMetric::Cosine
+            | DistanceMetric::DotProduct
+            | DistanceMetric::Hybrid { .. }
+            | DistanceMetric::Jaccard => 1.0 - similarity,
+            DistanceMetric::Euclidean | DistanceMetric::Manhattan | DistanceMetric::Hamming => {
+                1.0 / similarity - 1.0
+            }
+        }
+    }
+}
+
+/// Name of the kernels `DistanceMetric::distance` runs on this machine:
+/// `"neon"`, `"avx"`, `"simd128"` or `"scalar"`
+pub fn simd_backend() -> &'static str {
+    #[cfg(all(target_arch = "aarch64", feature = "simd"))]
+    {
+        "neon"
+    }
+
+    #[cfg(all(target_arch = "x86_64", feature = "simd"))]
+    {
+        if is_x86_feature_detected!("avx") {
+            "avx"
+        } else {
+            "scalar"
+        }
+    }
+
+    #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+    {
+        "simd128"
+    }
+
+    #[cfg(not(any(
+        all(feature = "simd", any(target_arch = "aarch64", target_arch = "x86_64")),
+        all(target_arch = "wasm32", target_feature = "simd128")
+    )))]
+    {
+        "scalar"
+    }
+}
+
+/// Block size of the blocked kernels, 0 when blocking is off
+static DISTANCE_BLOCK: AtomicUsize = AtomicUsize::new(DEFAULT_DISTANCE_BLOCK);
+
+/// Block size, in dimensions, of the blocked kernels used above
+/// `BLOCKED_DISTANCE_THRESHOLD`, or `None` if every distance runs in one pass
+///
+/// `DEFAULT_DISTANCE_BLOCK` unless changed with `set_distance_block`.
+pub fn distance_block() -> Option<usize> {
+    Some(DISTANCE_BLOCK.load(Ordering::Relaxed)).filter(|&block| block > 0)
+}
+
+/// Set the block size of the blocked kernels for the whole process, or
+/// `None` to run every distance in one pass
+///
+/// Meant to be called once at startup, before any collection is searched;
+/// changing it later only affects distances computed afterwards.
+pub fn set_distance_block(block: Option<usize>) {
+    DISTANCE_BLOCK.store(block.unwrap_or(0), Ordering::Relaxed);
+}
+
+#[inline]
+fn block_for(dimensions: usize) -> Option<usize> {
+    if dimensions > BLOCKED_DISTANCE_THRESHOLD {
+        distance_block()
+    } else {
+        None
+    }
+}
+
+/// Scale a vector to unit L2 norm in place
+/// Zero vectors are left unchanged
+#[inline]
+pub fn normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        for x in vector.iter_mut() {
+            *x /= norm;
         }
     }
 }
 
 /// Cosine distance: 1 - cosine_similarity
 /// Returns 0 for identical vectors, 2 for opposite vectors
+///
+/// Always a single pass: its fused kernel already beats the three passes
+/// per block of `cosine_distance_blocked`. Normalized collections compare
+/// with `dot_product_distance`, which is blocked.
 #[inline]
 pub fn cosine_distance(a: &[f32], b: &[f32]) -> f32 {
     #[cfg(all(target_arch = "aarch64", feature = "simd"))]
@@ -48,90 +262,230 @@ pub fn cosine_distance(a: &[f32], b: &[f32]) -> f32 {
         cosine_distance_avx(a, b)
     }
 
-    #[cfg(not(feature = "simd"))]
-    {
-        cosine_distance_scalar(a, b)
-    }
-
     #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
     {
         cosine_distance_wasm(a, b)
     }
 
-    #[cfg(all(
-        feature = "simd",
-        not(any(target_arch = "aarch64", target_arch = "x86_64")),
-        not(all(target_arch = "wasm32", target_feature = "simd128"))
-    ))]
+    #[cfg(not(any(
+        all(feature = "simd", any(target_arch = "aarch64", target_arch = "x86_64")),
+        all(target_arch = "wasm32", target_feature = "simd128")
+    )))]
     {
         cosine_distance_scalar(a, b)
     }
 }
 
+/// Hybrid distance: `w * cosine + (1 - w) * normalized_euclidean`
+/// where the euclidean term is `|a - b| / (|a| + |b|)`, in [0, 1]
+#[inline]
+pub fn hybrid_distance(a: &[f32], b: &[f32], cosine_weight: f32) -> f32 {
+    let norms = dot_product_scalar(a, a).sqrt() + dot_product_scalar(b, b).sqrt();
+    let euclidean = if norms > 0.0 {
+        euclidean_distance(a, b) / norms
+    } else {
+        0.0
+    };
+    cosine_weight * cosine_distance(a, b) + (1.0 - cosine_weight) * euclidean
+}
+
 /// Euclidean distance (L2)
 #[inline]
 pub fn euclidean_distance(a: &[f32], b: &[f32]) -> f32 {
+    match block_for(a.len()) {
+        Some(block) => euclidean_distance_blocked(a, b, block),
+        None => euclidean_distance_naive(a, b),
+    }
+}
+
+/// `euclidean_distance` in a single pass, whatever the dimension
+#[inline]
+pub fn euclidean_distance_naive(a: &[f32], b: &[f32]) -> f32 {
+    squared_euclidean_naive(a, b).sqrt()
+}
+
+/// Squared Euclidean distance in a single pass
+#[inline]
+fn squared_euclidean_naive(a: &[f32], b: &[f32]) -> f32 {
     #[cfg(all(target_arch = "aarch64", feature = "simd"))]
     {
-        euclidean_distance_neon(a, b)
+        squared_euclidean_neon(a, b)
     }
 
     #[cfg(all(target_arch = "x86_64", feature = "simd"))]
     {
-        euclidean_distance_avx(a, b)
+        squared_euclidean_avx(a, b)
     }
 
     #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
     {
-        euclidean_distance_wasm(a, b)
+        squared_euclidean_wasm(a, b)
     }
 
-    #[cfg(not(feature = "simd"))]
+    #[cfg(not(any(
+        all(feature = "simd", any(target_arch = "aarch64", target_arch = "x86_64")),
+        all(target_arch = "wasm32", target_feature = "simd128")
+    )))]
     {
-        euclidean_distance_scalar(a, b)
+        squared_euclidean_scalar(a, b)
     }
+}
+
+/// Manhattan distance (L1)
+#[inline]
+pub fn manhattan_distance(a: &[f32], b: &[f32]) -> f32 {
+    match block_for(a.len()) {
+        Some(block) => manhattan_distance_blocked(a, b, block),
+        None => manhattan_distance_naive(a, b),
+    }
+}
 
-    #[cfg(all(
-        feature = "simd",
-        not(any(target_arch = "aarch64", target_arch = "x86_64")),
-        not(all(target_arch = "wasm32", target_feature = "simd128"))
-    ))]
+/// `manhattan_distance` in a single pass, whatever the dimension
+#[inline]
+pub fn manhattan_distance_naive(a: &[f32], b: &[f32]) -> f32 {
+    #[cfg(all(target_arch = "aarch64", feature = "simd"))]
     {
-        euclidean_distance_scalar(a, b)
+        manhattan_distance_neon(a, b)
+    }
+
+    #[cfg(all(target_arch = "x86_64", feature = "simd"))]
+    {
+        manhattan_distance_avx(a, b)
+    }
+
+    #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+    {
+        manhattan_distance_wasm(a, b)
+    }
+
+    #[cfg(not(any(
+        all(feature = "simd", any(target_arch = "aarch64", target_arch = "x86_64")),
+        all(target_arch = "wasm32", target_feature = "simd128")
+    )))]
+    {
+        manhattan_distance_scalar(a, b)
+    }
+}
+
+/// Hamming distance between the sign bits of two vectors: the number of
+/// dimensions where exactly one side is positive
+#[inline]
+pub fn hamming_distance(a: &[f32], b: &[f32]) -> f32 {
+    a.iter()
+        .zip(b)
+        .filter(|&(&x, &y)| (x > 0.0) != (y > 0.0))
+        .count() as f32
+}
+
+/// Jaccard distance between the sets of positive dimensions of two vectors
+#[inline]
+pub fn jaccard_distance(a: &[f32], b: &[f32]) -> f32 {
+    let (mut both, mut either) = (0usize, 0usize);
+    for (&x, &y) in a.iter().zip(b) {
+        let (x, y) = (x > 0.0, y > 0.0);
+        both += usize::from(x && y);
+        either += usize::from(x || y);
+    }
+    if either == 0 {
+        0.0
+    } else {
+        1.0 - both as f32 / either as f32
     }
 }
 
 /// Dot product distance (1 - dot_product for normalized vectors)
 #[inline]
 pub fn dot_product_distance(a: &[f32], b: &[f32]) -> f32 {
+    match block_for(a.len()) {
+        Some(block) => dot_product_distance_blocked(a, b, block),
+        None => dot_product_distance_naive(a, b),
+    }
+}
+
+/// `dot_product_distance` in a single pass, whatever the dimension
+#[inline]
+pub fn dot_product_distance_naive(a: &[f32], b: &[f32]) -> f32 {
+    1.0 - dot_product(a, b)
+}
+
+#[inline]
+fn dot_product(a: &[f32], b: &[f32]) -> f32 {
     #[cfg(all(target_arch = "aarch64", feature = "simd"))]
     {
-        1.0 - dot_product_neon(a, b)
+        dot_product_neon(a, b)
     }
 
     #[cfg(all(target_arch = "x86_64", feature = "simd"))]
     {
-        1.0 - dot_product_avx(a, b)
+        dot_product_avx(a, b)
     }
 
     #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
     {
-        1.0 - dot_product_wasm(a, b)
+        dot_product_wasm(a, b)
     }
 
-    #[cfg(not(feature = "simd"))]
+    #[cfg(not(any(
+        all(feature = "simd", any(target_arch = "aarch64", target_arch = "x86_64")),
+        all(target_arch = "wasm32", target_feature = "simd128")
+    )))]
     {
-        1.0 - dot_product_scalar(a, b)
+        dot_product_scalar(a, b)
     }
+}
 
-    #[cfg(all(
-        feature = "simd",
-        not(any(target_arch = "aarch64", target_arch = "x86_64")),
-        not(all(target_arch = "wasm32", target_feature = "simd128"))
-    ))]
-    {
-        1.0 - dot_product_scalar(a, b)
+// =============================================================================
+// Blocked implementations (high dimensions)
+// =============================================================================
+//
+// Each block of `block` dimensions goes through the single-pass kernel and
+// the partial sums are accumulated, so the kernels only ever walk short,
+// cache-resident slices and each partial sum stays small.
+
+/// Cosine distance accumulated over blocks of `block` dimensions
+///
+/// The three sums are taken in separate passes over each block, which is
+/// then still in L1. Not used by `cosine_distance` (see there).
+pub fn cosine_distance_blocked(a: &[f32], b: &[f32], block: usize) -> f32 {
+    let (mut dot, mut norm_a, mut norm_b) = (0.0f32, 0.0f32, 0.0f32);
+    for (a, b) in a.chunks(block).zip(b.chunks(block)) {
+        dot += dot_product(a, b);
+        norm_a += dot_product(a, a);
+        norm_b += dot_product(b, b);
+    }
+
+    let denom = (norm_a * norm_b).sqrt();
+    if denom == 0.0 {
+        return 1.0;
     }
+
+    1.0 - (dot / denom)
+}
+
+/// Euclidean distance accumulated over blocks of `block` dimensions
+pub fn euclidean_distance_blocked(a: &[f32], b: &[f32], block: usize) -> f32 {
+    a.chunks(block)
+        .zip(b.chunks(block))
+        .map(|(a, b)| squared_euclidean_naive(a, b))
+        .sum::<f32>()
+        .sqrt()
+}
+
+/// Manhattan distance accumulated over blocks of `block` dimensions
+pub fn manhattan_distance_blocked(a: &[f32], b: &[f32], block: usize) -> f32 {
+    a.chunks(block)
+        .zip(b.chunks(block))
+        .map(|(a, b)| manhattan_distance_naive(a, b))
+        .sum()
+}
+
+/// Dot product distance accumulated over blocks of `block` dimensions
+pub fn dot_product_distance_blocked(a: &[f32], b: &[f32], block: usize) -> f32 {
+    1.0 - a
+        .chunks(block)
+        .zip(b.chunks(block))
+        .map(|(a, b)| dot_product(a, b))
+        .sum::<f32>()
 }
 
 // =============================================================================
@@ -139,7 +493,6 @@ pub fn dot_product_distance(a: &[f32], b: &[f32]) -> f32 {
 // =============================================================================
 
 #[inline]
-#[allow(dead_code)]
 fn cosine_distance_scalar(a: &[f32], b: &[f32]) -> f32 {
     let mut dot = 0.0f32;
     let mut norm_a = 0.0f32;
@@ -160,18 +513,30 @@ fn cosine_distance_scalar(a: &[f32], b: &[f32]) -> f32 {
 }
 
 #[inline]
-#[allow(dead_code)]
 fn euclidean_distance_scalar(a: &[f32], b: &[f32]) -> f32 {
+    squared_euclidean_scalar(a, b).sqrt()
+}
+
+#[inline]
+fn squared_euclidean_scalar(a: &[f32], b: &[f32]) -> f32 {
     let mut sum = 0.0f32;
     for i in 0..a.len() {
         let diff = a[i] - b[i];
         sum += diff * diff;
     }
-    sum.sqrt()
+    sum
+}
+
+#[inline]
+fn manhattan_distance_scalar(a: &[f32], b: &[f32]) -> f32 {
+    let mut sum = 0.0f32;
+    for i in 0..a.len() {
+        sum += (a[i] - b[i]).abs();
+    }
+    sum
 }
 
 #[inline]
-#[allow(dead_code)]
 fn dot_product_scalar(a: &[f32], b: &[f32]) -> f32 {
     let mut sum = 0.0f32;
     for i in 0..a.len() {
@@ -240,7 +605,7 @@ fn cosine_distance_neon(a: &[f32], b: &[f32]) -> f32 {
 
 #[cfg(all(target_arch = "aarch64", feature = "simd"))]
 #[inline]
-fn euclidean_distance_neon(a: &[f32], b: &[f32]) -> f32 {
+fn squared_euclidean_neon(a: &[f32], b: &[f32]) -> f32 {
     use std::arch::aarch64::*;
 
     debug_assert_eq!(a.len(), b.len());
@@ -268,7 +633,39 @@ fn euclidean_distance_neon(a: &[f32], b: &[f32]) -> f32 {
             sum += diff * diff;
         }
 
-        sum.sqrt()
+        sum
+    }
+}
+
+#[cfg(all(target_arch = "aarch64", feature = "simd"))]
+#[inline]
+fn manhattan_distance_neon(a: &[f32], b: &[f32]) -> f32 {
+    use std::arch::aarch64::*;
+
+    debug_assert_eq!(a.len(), b.len());
+
+    let n = a.len();
+    let chunks = n / 4;
+
+    unsafe {
+        let mut sum_acc = vdupq_n_f32(0.0);
+
+        for i in 0..chunks {
+            let offset = i * 4;
+            let va = vld1q_f32(a.as_ptr().add(offset));
+            let vb = vld1q_f32(b.as_ptr().add(offset));
+
+            sum_acc = vaddq_f32(sum_acc, vabdq_f32(va, vb));
+        }
+
+        let mut sum = vaddvq_f32(sum_acc);
+
+        // Handle remainder
+        for i in (chunks * 4)..n {
+            sum += (a[i] - b[i]).abs();
+        }
+
+        sum
     }
 }
 
@@ -375,18 +772,18 @@ unsafe fn cosine_distance_avx_inner(a: &[f32], b: &[f32]) -> f32 {
 
 #[cfg(all(target_arch = "x86_64", feature = "simd"))]
 #[inline]
-fn euclidean_distance_avx(a: &[f32], b: &[f32]) -> f32 {
+fn squared_euclidean_avx(a: &[f32], b: &[f32]) -> f32 {
     if is_x86_feature_detected!("avx") {
-        unsafe { euclidean_distance_avx_inner(a, b) }
+        unsafe { squared_euclidean_avx_inner(a, b) }
     } else {
-        euclidean_distance_scalar(a, b)
+        squared_euclidean_scalar(a, b)
     }
 }
 
 #[cfg(all(target_arch = "x86_64", feature = "simd"))]
 #[target_feature(enable = "avx")]
 #[inline]
-unsafe fn euclidean_distance_avx_inner(a: &[f32], b: &[f32]) -> f32 {
+unsafe fn squared_euclidean_avx_inner(a: &[f32], b: &[f32]) -> f32 {
     use std::arch::x86_64::*;
 
     let n = a.len();
@@ -417,7 +814,55 @@ unsafe fn euclidean_distance_avx_inner(a: &[f32], b: &[f32]) -> f32 {
         sum += diff * diff;
     }
 
-    sum.sqrt()
+    sum
+}
+
+#[cfg(all(target_arch = "x86_64", feature = "simd"))]
+#[inline]
+fn manhattan_distance_avx(a: &[f32], b: &[f32]) -> f32 {
+    if is_x86_feature_detected!("avx") {
+        unsafe { manhattan_distance_avx_inner(a, b) }
+    } else {
+        manhattan_distance_scalar(a, b)
+    }
+}
+
+#[cfg(all(target_arch = "x86_64", feature = "simd"))]
+#[target_feature(enable = "avx")]
+#[inline]
+unsafe fn manhattan_distance_avx_inner(a: &[f32], b: &[f32]) -> f32 {
+    use std::arch::x86_64::*;
+
+    let n = a.len();
+    let chunks = n / 8;
+
+    // Clearing the sign bit gives the absolute value
+    let sign_mask = _mm256_set1_ps(-0.0);
+    let mut sum_acc = _mm256_setzero_ps();
+
+    for i in 0..chunks {
+        let offset = i * 8;
+        let va = _mm256_loadu_ps(a.as_ptr().add(offset));
+        let vb = _mm256_loadu_ps(b.as_ptr().add(offset));
+        let diff = _mm256_andnot_ps(sign_mask, _mm256_sub_ps(va, vb));
+        sum_acc = _mm256_add_ps(sum_acc, diff);
+    }
+
+    // Horizontal sum
+    let high = _mm256_extractf128_ps(sum_acc, 1);
+    let low = _mm256_castps256_ps128(sum_acc);
+    let sum128 = _mm_add_ps(high, low);
+    let high64 = _mm_movehl_ps(sum128, sum128);
+    let sum64 = _mm_add_ps(sum128, high64);
+    let high32 = _mm_shuffle_ps(sum64, sum64, 1);
+    let mut sum = _mm_cvtss_f32(_mm_add_ss(sum64, high32));
+
+    // Handle remainder
+    for i in (chunks * 8)..n {
+        sum += (a[i] - b[i]).abs();
+    }
+
+    sum
 }
 
 #[cfg(all(target_arch = "x86_64", feature = "simd"))]
@@ -533,7 +978,7 @@ fn cosine_distance_wasm(a: &[f32], b: &[f32]) -> f32 {
 
 #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
 #[inline]
-fn euclidean_distance_wasm(a: &[f32], b: &[f32]) -> f32 {
+fn squared_euclidean_wasm(a: &[f32], b: &[f32]) -> f32 {
     use core::arch::wasm32::*;
 
     debug_assert_eq!(a.len(), b.len());
@@ -563,7 +1008,40 @@ fn euclidean_distance_wasm(a: &[f32], b: &[f32]) -> f32 {
         sum += diff * diff;
     }
 
-    sum.sqrt()
+    sum
+}
+
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+#[inline]
+fn manhattan_distance_wasm(a: &[f32], b: &[f32]) -> f32 {
+    use core::arch::wasm32::*;
+
+    debug_assert_eq!(a.len(), b.len());
+
+    let n = a.len();
+    let chunks = n / 4;
+
+    let mut sum_acc = f32x4_splat(0.0);
+
+    for i in 0..chunks {
+        let offset = i * 4;
+        let va = unsafe { v128_load(a.as_ptr().add(offset) as *const v128) };
+        let vb = unsafe { v128_load(b.as_ptr().add(offset) as *const v128) };
+
+        sum_acc = f32x4_add(sum_acc, f32x4_abs(f32x4_sub(va, vb)));
+    }
+
+    let mut sum = f32x4_extract_lane::<0>(sum_acc)
+        + f32x4_extract_lane::<1>(sum_acc)
+        + f32x4_extract_lane::<2>(sum_acc)
+        + f32x4_extract_lane::<3>(sum_acc);
+
+    // Handle remainder
+    for i in (chunks * 4)..n {
+        sum += (a[i] - b[i]).abs();
+    }
+
+    sum
 }
 
 #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
@@ -642,6 +1120,19 @@ mod tests {
         assert_float_eq(euclidean_distance(&a, &b), 1.0);
     }
 
+    #[test]
+    fn test_manhattan_distance() {
+        let a = vec![0.0, 0.0, 0.0, 0.0];
+        let b = vec![1.0, -2.0, 0.0, 0.5];
+        assert_float_eq(manhattan_distance(&a, &b), 3.5);
+
+        // Long enough to cover the SIMD body and the scalar remainder
+        let a: Vec<f32> = (0..19).map(|i| i as f32 * 0.5).collect();
+        let b: Vec<f32> = (0..19).map(|i| 9.0 - i as f32).collect();
+        let expected: f32 = a.iter().zip(&b).map(|(x, y)| (x - y).abs()).sum();
+        assert_float_eq(manhattan_distance(&a, &b), expected);
+    }
+
     #[test]
     fn test_dot_product_distance() {
         let a = vec![1.0, 0.0, 0.0, 0.0];
@@ -659,4 +1150,131 @@ mod tests {
         let dist = cosine_distance(&a, &b);
         assert!((0.0..=2.0).contains(&dist));
     }
+
+    #[test]
+    fn test_hybrid_distance_weighs_magnitude() {
+        let query = vec![1.0, 0.0];
+        // Nearly the same direction but ten times longer
+        let long = vec![10.0, 1.0];
+        // Further off-axis but a similar length to the query
+        let close = vec![0.8, 0.4];
+
+        let cosine = DistanceMetric::Cosine;
+        assert!(cosine.distance(&query, &long) < cosine.distance(&query, &close));
+
+        let hybrid = DistanceMetric::Hybrid { cosine_weight: 0.5 };
+        assert!(hybrid.distance(&query, &close) < hybrid.distance(&query, &long));
+
+        let pure = DistanceMetric::Hybrid { cosine_weight: 1.0 };
+        for v in [&long, &close] {
+            assert_float_eq(pure.distance(&query, v), cosine.distance(&query, v));
+        }
+    }
+
+    #[test]
+    fn test_similarity_round_trip() {
+        let metrics = [
+            DistanceMetric::Cosine,
+            DistanceMetric::Euclidean,
+            DistanceMetric::DotProduct,
+            DistanceMetric::Manhattan,
+            DistanceMetric::Hybrid { cosine_weight: 0.5 },
+        ];
+        for metric in metrics {
+            // Identical vectors are maximally similar under every metric
+            assert_float_eq(metric.to_similarity(0.0), 1.0);
+            assert!(metric.to_similarity(0.5) > metric.to_similarity(1.5));
+            for distance in [0.0, 0.25, 1.0, 2.0, 10.0] {
+                let similarity = metric.to_similarity(distance);
+                assert_float_eq(metric.from_similarity(similarity), distance);
+            }
+        }
+
+        // Opposite vectors under cosine; the dot product passes through
+        assert_float_eq(DistanceMetric::Cosine.to_similarity(2.0), -1.0);
+        let a = [0.6, 0.8];
+        let dot = DistanceMetric::DotProduct;
+        assert_float_eq(dot.to_similarity(dot.distance(&a, &a)), 1.0);
+        assert_float_eq(dot.to_similarity(dot.distance(&a, &[2.0, 0.0])), 1.2);
+
+        // Euclidean similarity approaches 0 far away and has no distance below it
+        assert!(DistanceMetric::Euclidean.to_similarity(1e6) > 0.0);
+        assert_eq!(
+            DistanceMetric::Euclidean.from_similarity(0.0),
+            f32::INFINITY
+        );
+    }
+
+    /// Whichever SIMD path is compiled in (NEON, AVX or wasm32 simd128)
+    /// agrees with the scalar implementations
+    #[test]
+    fn test_simd_matches_scalar() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(384);
+        for _ in 0..20 {
+            let a: Vec<f32> = (0..384).map(|_| rng.gen_range(-1.0..1.0)).collect();
+            let b: Vec<f32> = (0..384).map(|_| rng.gen_range(-1.0..1.0)).collect();
+
+            assert_float_eq(cosine_distance(&a, &b), cosine_distance_scalar(&a, &b));
+            assert_float_eq(
+                euclidean_distance(&a, &b),
+                euclidean_distance_scalar(&a, &b),
+            );
+            assert_float_eq(
+                dot_product_distance(&a, &b),
+                1.0 - dot_product_scalar(&a, &b),
+            );
+        }
+    }
+
+    /// Blocked kernels agree with the single-pass ones, for blocks that do
+    /// and don't divide the dimension, and are used above the threshold
+    #[test]
+    fn test_blocked_matches_naive() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let close = |blocked: f32, naive: f32| {
+            assert!(
+                (blocked - naive).abs() <= 1e-4 * naive.abs().max(1.0),
+                "blocked {} vs naive {}",
+                blocked,
+                naive
+            );
+        };
+        let mut rng = StdRng::seed_from_u64(1536);
+        for _ in 0..10 {
+            let a: Vec<f32> = (0..1536).map(|_| rng.gen_range(-1.0..1.0)).collect();
+            let b: Vec<f32> = (0..1536).map(|_| rng.gen_range(-1.0..1.0)).collect();
+            for block in [1, 100, 256, 1536, 4096] {
+                close(
+                    cosine_distance_blocked(&a, &b, block),
+                    cosine_distance(&a, &b),
+                );
+                close(
+                    euclidean_distance_blocked(&a, &b, block),
+                    euclidean_distance_naive(&a, &b),
+                );
+                close(
+                    manhattan_distance_blocked(&a, &b, block),
+                    manhattan_distance_naive(&a, &b),
+                );
+                close(
+                    dot_product_distance_blocked(&a, &b, block),
+                    dot_product_distance_naive(&a, &b),
+                );
+            }
+
+            if let Some(block) = distance_block() {
+                assert_eq!(
+                    euclidean_distance(&a, &b),
+                    euclidean_distance_blocked(&a, &b, block)
+                );
+                assert_eq!(
+                    dot_product_distance(&a, &b),
+                    dot_product_distance_blocked(&a, &b, block)
+                );
+            }
+        }
+    }
 }
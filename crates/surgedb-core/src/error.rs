@@ -35,6 +35,22 @@ pub enum Error {
     #[error("Index is empty, cannot search")]
     EmptyIndex,
 
+    /// Serialized metadata exceeds the configured size limit
+    #[error("Metadata too large: {size} bytes exceeds limit of {limit} bytes")]
+    MetadataTooLarge { size: usize, limit: usize },
+
+    /// Vector is not unit length but the collection requires normalized inputs
+    #[error("Vector is not unit length: norm = {norm}")]
+    NotUnitNorm { norm: f32 },
+
+    /// Query vector rejected by `validate_query`
+    #[error("Invalid query vector (norm = {norm}): {reason}")]
+    InvalidQuery { norm: f32, reason: &'static str },
+
+    /// Vector has a NaN or infinite component (rejected under `validate_finite`)
+    #[error("Invalid vector: component {index} is {value}, expected a finite number")]
+    InvalidVector { index: usize, value: f32 },
+
     // =========================================================================
     // Configuration Errors
     // =========================================================================
@@ -160,6 +176,10 @@ impl Error {
             Error::DimensionMismatch { .. }
                 | Error::VectorNotFound(_)
                 | Error::DuplicateId(_)
+                | Error::MetadataTooLarge { .. }
+                | Error::NotUnitNorm { .. }
+                | Error::InvalidQuery { .. }
+                | Error::InvalidVector { .. }
                 | Error::InvalidConfig(_)
                 | Error::InvalidHnswParam { .. }
                 | Error::CollectionNotFound(_)
@@ -175,6 +195,10 @@ impl Error {
             Error::VectorNotFound(_) => 1002,
             Error::DuplicateId(_) => 1003,
             Error::EmptyIndex => 1004,
+            Error::MetadataTooLarge { .. } => 1005,
+            Error::NotUnitNorm { .. } => 1006,
+            Error::InvalidQuery { .. } => 1007,
+            Error::InvalidVector { .. } => 1008,
 
             // Config errors: 1100-1199
             Error::InvalidConfig(_) => 1100,
@@ -241,6 +265,16 @@ mod tests {
             Error::VectorNotFound("test".into()),
             Error::DuplicateId("test".into()),
             Error::EmptyIndex,
+            Error::MetadataTooLarge { size: 2, limit: 1 },
+            Error::NotUnitNorm { norm: 2.0 },
+            Error::InvalidQuery {
+                norm: 0.0,
+                reason: "test",
+            },
+            Error::InvalidVector {
+                index: 0,
+                value: f32::NAN,
+            },
             Error::InvalidConfig("test".into()),
             Error::Storage("test".into()),
             Error::CollectionNotFound("test".into()),
@@ -0,0 +1,191 @@
+//! Portable single-file export of a persistent database
+//!
+//! An export holds every live vector, its metadata, the aliases and the
+//! collection-level configuration, so a database can be moved between
+//! machines without copying its data directory. The HNSW graph is not
+//! included; it is rebuilt on import.
+//!
+//! Layout: `magic(4) + version(1)`, then bincode-encoded an `ExportHeader`,
+//! batches of `Vec<StoredVector>` ending with an empty batch, and the aliases.
+//! Vectors are written as stored, i.e. already projected or normalized.
+
+use crate::distance::DistanceMetric;
+use crate::error::{Error, Result};
+use crate::hnsw::HnswConfig;
+use crate::metadata::MetadataFormat;
+use crate::persistent::PersistentConfig;
+use crate::projection::ProjectionConfig;
+use crate::score::ScoreTransform;
+use crate::snapshot::StoredVector;
+use crate::types::{DimensionPolicy, VectorId};
+use bincode::{deserialize_from, serialize_into};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{BufReader, BufWriter, Read, Write};
+
+/// Magic bytes opening every export
+const EXPORT_MAGIC: &[u8; 4] = b"ZEXP";
+
+/// Export format version
+const EXPORT_VERSION: u8 = 1;
+
+/// Vectors per serialized batch
+const BATCH_SIZE: usize = 1000;
+
+/// The parts of `PersistentConfig` that describe the data rather than the
+/// machine; WAL, sync and snapshot settings take their defaults on import
+#[derive(Debug, Serialize, Deserialize)]
+struct ExportHeader {
+    dimensions: usize,
+    distance_metric: DistanceMetric,
+    hnsw: HnswConfig,
+    #[serde(with = "crate::types::metadata_serde")]
+    default_metadata: Option<Value>,
+    max_metadata_bytes: usize,
+    projection: Option<ProjectionConfig>,
+    normalize: bool,
+    require_unit_norm: bool,
+    #[serde(default = "default_validate_finite")]
+    validate_finite: bool,
+    validate_query: bool,
+    dimension_mismatch: DimensionPolicy,
+    metadata_format: MetadataFormat,
+    score_transform: ScoreTransform,
+}
+
+fn default_validate_finite() -> bool {
+    true
+}
+
+impl ExportHeader {
+    fn new(config: &PersistentConfig) -> Self {
+        Self {
+            dimensions: config.dimensions,
+            distance_metric: config.distance_metric,
+            hnsw: config.hnsw.clone(),
+            default_metadata: config.default_metadata.clone(),
+            max_metadata_bytes: config.max_metadata_bytes,
+            projection: config.projection,
+            normalize: config.normalize,
+            require_unit_norm: config.require_unit_norm,
+            validate_finite: config.validate_finite,
+            validate_query: config.validate_query,
+            dimension_mismatch: config.dimension_mismatch,
+            metadata_format: config.metadata_format.clone(),
+            score_transform: config.score_transform,
+        }
+    }
+
+    fn into_config(self) -> PersistentConfig {
+        PersistentConfig {
+            dimensions: self.dimensions,
+            distance_metric: self.distance_metric,
+            hnsw: self.hnsw,
+            default_metadata: self.default_metadata,
+            max_metadata_bytes: self.max_metadata_bytes,
+            projection: self.projection,
+            normalize: self.normalize,
+            require_unit_norm: self.require_unit_norm,
+            validate_finite: self.validate_finite,
+            validate_query: self.validate_query,
+            dimension_mismatch: self.dimension_mismatch,
+            metadata_format: self.metadata_format,
+            score_transform: self.score_transform,
+            ..Default::default()
+        }
+    }
+}
+
+/// Write an export of `vectors` and `aliases` under `config`
+pub(crate) fn write(
+    writer: impl Write,
+    config: &PersistentConfig,
+    vectors: impl Iterator<Item = StoredVector>,
+    aliases: &[(VectorId, VectorId)],
+) -> Result<()> {
+    let mut writer = BufWriter::new(writer);
+    writer.write_all(EXPORT_MAGIC)?;
+    writer.write_all(&[EXPORT_VERSION])?;
+    serialize_into(&mut writer, &ExportHeader::new(config))?;
+
+    let mut batch = Vec::with_capacity(BATCH_SIZE);
+    for vector in vectors {
+        batch.push(vector);
+        if batch.len() == BATCH_SIZE {
+            serialize_into(&mut writer, &batch)?;
+            batch.clear();
+        }
+    }
+    if !batch.is_empty() {
+        serialize_into(&mut writer, &batch)?;
+    }
+    serialize_into(&mut writer, &Vec::<StoredVector>::new())?;
+
+    serialize_into(&mut writer, aliases)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Streaming reader for an export written by [`write`]
+pub(crate) struct ExportReader<R: Read> {
+    reader: BufReader<R>,
+    done: bool,
+}
+
+impl<R: Read> ExportReader<R> {
+    /// Check the header and return the reader with the exported configuration
+    pub(crate) fn open(reader: R) -> Result<(Self, PersistentConfig)> {
+        let mut reader = BufReader::new(reader);
+
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != EXPORT_MAGIC {
+            return Err(Error::Deserialization {
+                message: format!(
+                    "Invalid export magic bytes: expected {:?}, got {:?}",
+                    EXPORT_MAGIC, magic
+                ),
+            });
+        }
+
+        let mut version = [0u8; 1];
+        reader.read_exact(&mut version)?;
+        if version[0] != EXPORT_VERSION {
+            return Err(Error::UnsupportedVersion {
+                version: version[0],
+                supported: "1",
+            });
+        }
+
+        let header: ExportHeader = deserialize_from(&mut reader).map_err(decode_error)?;
+        Ok((
+            Self {
+                reader,
+                done: false,
+            },
+            header.into_config(),
+        ))
+    }
+
+    /// The next batch of vectors, or `None` once all have been read
+    pub(crate) fn next_batch(&mut self) -> Result<Option<Vec<StoredVector>>> {
+        if self.done {
+            return Ok(None);
+        }
+        let batch: Vec<StoredVector> = deserialize_from(&mut self.reader).map_err(decode_error)?;
+        self.done = batch.is_empty();
+        Ok(Some(batch).filter(|batch| !batch.is_empty()))
+    }
+
+    /// The aliases stored after the vectors
+    pub(crate) fn aliases(mut self) -> Result<Vec<(VectorId, VectorId)>> {
+        while self.next_batch()?.is_some() {}
+        deserialize_from(&mut self.reader).map_err(decode_error)
+    }
+}
+
+fn decode_error(err: Box<bincode::ErrorKind>) -> Error {
+    Error::Deserialization {
+        message: format!("Truncated or corrupted export: {}", err),
+    }
+}
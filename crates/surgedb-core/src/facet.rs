@@ -0,0 +1,153 @@
+//! Metadata facet counts
+//!
+//! Aggregates the distinct values of one metadata field across a collection,
+//! for faceted UIs ("category: books (120), movies (87), ...").
+
+use crate::filter::get_value_by_path;
+use crate::types::InternalId;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// One distinct value of a faceted field and how many vectors hold it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FacetValue {
+    pub value: Value,
+    /// Exact count, or an estimate scaled from the sample when approximate
+    pub count: usize,
+}
+
+/// Top values of a metadata field, most frequent first
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Facets {
+    pub field: String,
+    pub values: Vec<FacetValue>,
+    /// Number of live vectors whose metadata was inspected
+    pub scanned: usize,
+    /// True when counts were extrapolated from a sample
+    pub approximate: bool,
+}
+
+/// Count the values of `field` over a collection's storage slots.
+///
+/// `entry` returns `None` for deleted or superseded slots and the stored
+/// metadata otherwise. With `sample`, only about that many evenly strided
+/// slots are read and counts are scaled up to `live` vectors. Ties are broken
+/// by the value's JSON text so results are deterministic.
+pub(crate) fn aggregate(
+    field: &str,
+    top_n: usize,
+    sample: Option<usize>,
+    slots: usize,
+    live: usize,
+    entry: impl Fn(InternalId) -> Option<Option<Value>>,
+) -> Facets {
+    let stride = match sample {
+        Some(sample) if sample > 0 && sample < live => slots.div_ceil(sample).max(1),
+        _ => 1,
+    };
+
+    let mut counts: HashMap<String, (Value, usize)> = HashMap::new();
+    let mut scanned = 0;
+    for slot in (0..slots).step_by(stride) {
+        let Some(metadata) = entry(InternalId::from(slot)) else {
+            continue;
+        };
+        scanned += 1;
+        let Some(value) = metadata
+            .as_ref()
+            .and_then(|meta| get_value_by_path(meta, field))
+        else {
+            continue;
+        };
+        // Arrays count once per element, so tag lists facet naturally
+        let values = match value {
+            Value::Array(items) => items.iter().collect(),
+            other => vec![other],
+        };
+        for value in values {
+            counts
+                .entry(value.to_string())
+                .or_insert_with(|| (value.clone(), 0))
+                .1 += 1;
+        }
+    }
+
+    let approximate = stride > 1;
+    let scale = if approximate && scanned > 0 {
+        live as f64 / scanned as f64
+    } else {
+        1.0
+    };
+
+    let mut values: Vec<(String, Value, usize)> = counts
+        .into_iter()
+        .map(|(key, (value, count))| (key, value, count))
+        .collect();
+    values.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.0.cmp(&b.0)));
+    values.truncate(top_n);
+
+    Facets {
+        field: field.to_string(),
+        values: values
+            .into_iter()
+            .map(|(_, value, count)| FacetValue {
+                value,
+                count: (count as f64 * scale).round() as usize,
+            })
+            .collect(),
+        scanned,
+        approximate,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_aggregate_counts_and_orders() {
+        let metadata = [
+            Some(json!({"category": "books"})),
+            Some(json!({"category": "movies"})),
+            None,
+            Some(json!({"category": "books"})),
+            Some(json!({"other": 1})),
+        ];
+        // Slot 2 is a tombstone
+        let facets = aggregate("category", 10, None, metadata.len(), 4, |id| {
+            if id.as_usize() == 2 {
+                None
+            } else {
+                Some(metadata[id.as_usize()].clone())
+            }
+        });
+
+        assert!(!facets.approximate);
+        assert_eq!(facets.scanned, 4);
+        assert_eq!(
+            facets.values,
+            vec![
+                FacetValue {
+                    value: json!("books"),
+                    count: 2
+                },
+                FacetValue {
+                    value: json!("movies"),
+                    count: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_aggregate_sample_scales_counts() {
+        let facets = aggregate("k", 1, Some(100), 1000, 1000, |_| {
+            Some(Some(json!({"k": "v"})))
+        });
+        assert!(facets.approximate);
+        assert_eq!(facets.scanned, 100);
+        assert_eq!(facets.values[0].count, 1000);
+    }
+}
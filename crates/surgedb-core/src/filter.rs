@@ -9,6 +9,12 @@ pub enum Filter {
     Exact(String, Value),
     /// One of: key in [values]
     OneOf(String, Vec<Value>),
+    /// Array contains value: a scalar at key counts as a one-element array
+    Contains(String, Value),
+    /// Array contains at least one of [values]
+    ContainsAny(String, Vec<Value>),
+    /// Array contains every one of [values]
+    ContainsAll(String, Vec<Value>),
     /// Logical AND
     And(Vec<Filter>),
     /// Logical OR
@@ -16,7 +22,11 @@ pub enum Filter {
     /// Logical NOT
     Not(Box<Filter>),
     /// Range filter for numeric values
+    ///
+    /// Unset bounds are open. Values that are missing or not numbers never
+    /// match. `key` is accepted in place of `field` when deserializing.
     Range {
+        #[serde(alias = "key")]
         field: String,
         gt: Option<f64>,
         gte: Option<f64>,
@@ -49,6 +59,13 @@ impl Filter {
                     false
                 }
             }
+            Filter::Contains(key, target) => {
+                get_value_by_path(metadata, key).is_some_and(|value| contains(value, target))
+            }
+            Filter::ContainsAny(key, targets) => get_value_by_path(metadata, key)
+                .is_some_and(|value| targets.iter().any(|target| contains(value, target))),
+            Filter::ContainsAll(key, targets) => get_value_by_path(metadata, key)
+                .is_some_and(|value| targets.iter().all(|target| contains(value, target))),
             Filter::And(filters) => filters.iter().all(|f| f.matches(metadata)),
             Filter::Or(filters) => filters.iter().any(|f| f.matches(metadata)),
             Filter::Not(filter) => !filter.matches(metadata),
@@ -117,6 +134,14 @@ impl Filter {
     }
 }
 
+/// Whether an array holds `target` as an element, or a scalar equals it
+fn contains(value: &Value, target: &Value) -> bool {
+    match value {
+        Value::Array(elements) => elements.contains(target),
+        scalar => scalar == target,
+    }
+}
+
 fn parse_geo_point(value: &Value) -> Option<(f64, f64)> {
     match value {
         Value::Object(map) => {
@@ -144,7 +169,7 @@ fn haversine_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
 }
 
 /// Helper to get a value from a JSON object using a dot-notation path
-fn get_value_by_path<'a>(metadata: &'a Value, path: &str) -> Option<&'a Value> {
+pub(crate) fn get_value_by_path<'a>(metadata: &'a Value, path: &str) -> Option<&'a Value> {
     if path.is_empty() {
         return Some(metadata);
     }
@@ -218,4 +243,63 @@ mod tests {
 
         assert!(filter.matches(&meta));
     }
+
+    #[test]
+    fn test_range_bounds() {
+        let meta = json!({"price": 19.99, "stock": 3, "meta": {"price": 5}, "name": "pen"});
+        let range = |field: &str, gt, gte, lt, lte| Filter::Range {
+            field: field.to_string(),
+            gt,
+            gte,
+            lt,
+            lte,
+        };
+
+        assert!(range("price", Some(10.0), None, Some(20.0), None).matches(&meta));
+        assert!(range("price", None, Some(19.99), None, Some(19.99)).matches(&meta));
+        assert!(!range("price", Some(19.99), None, None, None).matches(&meta));
+        assert!(!range("price", None, None, Some(19.99), None).matches(&meta));
+        // Integers compare as numbers, nested keys use dot notation
+        assert!(range("stock", None, Some(3.0), None, None).matches(&meta));
+        assert!(range("meta.price", None, None, Some(10.0), None).matches(&meta));
+        // Non-numeric and missing values never match
+        assert!(!range("name", None, None, None, None).matches(&meta));
+        assert!(!range("missing", None, None, None, None).matches(&meta));
+    }
+
+    #[test]
+    fn test_range_deserializes_with_key_or_field() {
+        for body in [
+            json!({"Range": {"key": "meta.price", "gte": 1, "lt": 10}}),
+            json!({"Range": {"field": "meta.price", "gte": 1, "lt": 10}}),
+        ] {
+            let filter: Filter = serde_json::from_value(body).unwrap();
+            assert!(filter.matches(&json!({"meta": {"price": 5}})));
+            assert!(!filter.matches(&json!({"meta": {"price": 10}})));
+        }
+    }
+
+    #[test]
+    fn test_contains_on_tag_lists() {
+        let meta = json!({"tags": ["ai", "db"], "lang": "en", "doc": {"ids": [1, 2]}});
+        let tags = |values: &[&str]| values.iter().map(|v| json!(v)).collect::<Vec<_>>();
+
+        assert!(Filter::Contains("tags".into(), json!("ai")).matches(&meta));
+        assert!(!Filter::Contains("tags".into(), json!("web")).matches(&meta));
+        assert!(Filter::Contains("doc.ids".into(), json!(2)).matches(&meta));
+        // Scalars are a one-element set, missing fields match nothing
+        assert!(Filter::Contains("lang".into(), json!("en")).matches(&meta));
+        assert!(!Filter::Contains("missing".into(), json!("en")).matches(&meta));
+
+        assert!(Filter::ContainsAny("tags".into(), tags(&["web", "db"])).matches(&meta));
+        assert!(!Filter::ContainsAny("tags".into(), tags(&["web", "ml"])).matches(&meta));
+        assert!(!Filter::ContainsAny("tags".into(), vec![]).matches(&meta));
+        assert!(Filter::ContainsAll("tags".into(), tags(&["db", "ai"])).matches(&meta));
+        assert!(!Filter::ContainsAll("tags".into(), tags(&["ai", "web"])).matches(&meta));
+        assert!(Filter::ContainsAll("lang".into(), tags(&["en"])).matches(&meta));
+
+        let filter: Filter =
+            serde_json::from_value(json!({"ContainsAny": ["tags", ["db"]]})).unwrap();
+        assert!(filter.matches(&meta));
+    }
 }
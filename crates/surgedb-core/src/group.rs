@@ -0,0 +1,69 @@
+//! Search results grouped by a metadata field
+//!
+//! Keeps only the best hit per distinct value of a field, for "one result per
+//! author" style diversification.
+
+use crate::error::Result;
+use crate::filter::get_value_by_path;
+use crate::types::SearchHit;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+
+/// Treatment of hits whose metadata lacks the grouping field
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum MissingGroup {
+    /// Leave them out of the results
+    #[default]
+    Exclude,
+    /// Put them all in one group of their own, as if the field were `null`
+    Null,
+}
+
+/// Candidates fetched per requested group on the first pass
+const OVERFETCH: usize = 4;
+
+/// Return at most one hit, the closest, per distinct value of `field`, for
+/// up to `k` groups
+///
+/// `search(n)` returns the `n` nearest hits sorted by distance. The fetch size
+/// doubles until `k` groups are filled, the search returns fewer hits than
+/// asked (nothing more to find), or it reaches `len`, the collection size.
+pub(crate) fn search_grouped(
+    k: usize,
+    field: &str,
+    missing: MissingGroup,
+    len: usize,
+    search: impl Fn(usize) -> Result<Vec<SearchHit>>,
+) -> Result<Vec<SearchHit>> {
+    if k == 0 {
+        return Ok(Vec::new());
+    }
+
+    let mut fetch = k.saturating_mul(OVERFETCH);
+    loop {
+        let hits = search(fetch)?;
+        let exhausted = hits.len() < fetch || fetch >= len;
+
+        let mut seen = HashSet::new();
+        let mut groups = Vec::with_capacity(k);
+        for hit in hits {
+            let key = match hit.2.as_ref().and_then(|m| get_value_by_path(m, field)) {
+                Some(value) => value.to_string(),
+                None if missing == MissingGroup::Null => Value::Null.to_string(),
+                None => continue,
+            };
+            if seen.insert(key) {
+                groups.push(hit);
+                if groups.len() == k {
+                    return Ok(groups);
+                }
+            }
+        }
+
+        if exhausted {
+            return Ok(groups);
+        }
+        fetch = fetch.saturating_mul(2);
+    }
+}
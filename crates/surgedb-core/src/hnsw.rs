@@ -3,7 +3,8 @@
 //! This is the core indexing algorithm that enables fast approximate nearest neighbor search.
 //! The implementation supports:
 //! - In-memory mode (fastest, for hot data)
-//! - Mmap mode (for disk-resident vectors) [TODO]
+//! - Mmap mode: searching a graph laid out in a memory-mapped file
+//!   (see `mmap_graph`), so the graph need not fit in RAM
 //! - Hybrid mode (adaptive) [TODO]
 
 use crate::distance::DistanceMetric;
@@ -15,10 +16,10 @@ use crate::types::InternalId;
 #[cfg(not(all(target_arch = "wasm32", feature = "wasm")))]
 use rand::Rng;
 use roaring::RoaringBitmap;
-use std::sync::Arc;
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 use std::collections::{BinaryHeap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
 use std::sync::OnceLock;
 
 fn bitmap_filter_enabled() -> bool {
@@ -43,6 +44,106 @@ pub struct HnswConfig {
 
     /// Normalization factor for level generation (1/ln(M))
     pub ml: f64,
+
+    /// Actively unlink deleted/updated nodes from the graph instead of leaving
+    /// stale edges until neighbors are re-pruned. Each removal scans the
+    /// whole graph (O(n * M)), so this is off by default.
+    #[serde(default)]
+    pub eager_edge_cleanup: bool,
+
+    /// Shuffle each `upsert_batch` before building the graph. Inserting
+    /// pre-sorted (e.g. clustered) data in order yields a skewed graph;
+    /// a random order typically restores recall.
+    #[serde(default)]
+    pub shuffle_inserts: bool,
+
+    /// Seed for `shuffle_inserts`, so builds are reproducible
+    #[serde(default)]
+    pub shuffle_seed: u64,
+
+    /// Stop a filtered search after visiting this many nodes (0 = unlimited).
+    /// A filtered search keeps expanding until it holds `ef` matches, so a
+    /// filter that matches nothing nearby otherwise walks the whole graph.
+    #[serde(default)]
+    pub max_filtered_visits: usize,
+
+    /// Where node levels get their randomness
+    #[serde(default)]
+    pub rng: RngSource,
+
+    /// Link each node to its `m` closest candidates instead of applying the
+    /// diversity heuristic (HNSW paper, Algorithm 4). Clustered data then
+    /// gets fewer links between clusters and lower recall; kept so older
+    /// benchmarks stay comparable.
+    #[serde(default)]
+    pub simple_select: bool,
+
+    /// After the heuristic, fill up to `m` links with the closest candidates
+    /// it pruned (`keepPrunedConnections` in the paper)
+    #[serde(default = "default_keep_pruned_connections")]
+    pub keep_pruned_connections: bool,
+
+    /// Build batches with `HnswIndex::insert_batch_parallel`, linking nodes
+    /// from all threads at once. Much faster on large builds, but the graph
+    /// depends on thread scheduling and is not reproducible. Ignored without
+    /// the `parallel` feature.
+    #[serde(default)]
+    pub parallel_build: bool,
+}
+
+fn default_keep_pruned_connections() -> bool {
+    true
+}
+
+/// Random source for HNSW level assignment
+///
+/// Levels only need a well-spread distribution, not unpredictability, so the
+/// default is a seeded xorshift generator that needs no OS entropy and
+/// builds the same graph for the same sequence of inserts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RngSource {
+    /// xorshift64* seeded with this value
+    Xorshift(u64),
+    /// `rand::thread_rng()`, seeded from the OS. Builds are not reproducible.
+    /// Where it is unavailable (WASM), `Math.random()` is used instead.
+    ThreadRng,
+}
+
+impl Default for RngSource {
+    fn default() -> Self {
+        RngSource::Xorshift(DEFAULT_RNG_SEED)
+    }
+}
+
+const DEFAULT_RNG_SEED: u64 = 0x5eed_5ca1_ab1e_0001;
+
+/// Initial xorshift state for `seed`; xorshift must never hold zero
+fn xorshift_state(seed: u64) -> u64 {
+    // SplitMix64 finalizer, so nearby seeds start far apart
+    let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    (z ^ (z >> 31)).max(1)
+}
+
+fn xorshift_step(mut x: u64) -> u64 {
+    x ^= x >> 12;
+    x ^= x << 25;
+    x ^= x >> 27;
+    x
+}
+
+/// A float in [0, 1) from the platform's entropy-seeded generator
+fn thread_random() -> f64 {
+    #[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+    {
+        js_sys::Math::random()
+    }
+
+    #[cfg(not(all(target_arch = "wasm32", feature = "wasm")))]
+    {
+        rand::thread_rng().gen()
+    }
 }
 
 impl Default for HnswConfig {
@@ -54,6 +155,14 @@ impl Default for HnswConfig {
             ef_construction: 200,
             ef_search: 100,
             ml: 1.0 / (m as f64).ln(),
+            eager_edge_cleanup: false,
+            shuffle_inserts: false,
+            shuffle_seed: 0,
+            max_filtered_visits: 0,
+            rng: RngSource::default(),
+            simple_select: false,
+            keep_pruned_connections: true,
+            parallel_build: false,
         }
     }
 }
@@ -68,6 +177,14 @@ impl HnswConfig {
             ef_construction: 100,
             ef_search: 50,
             ml: 1.0 / (m as f64).ln(),
+            eager_edge_cleanup: false,
+            shuffle_inserts: false,
+            shuffle_seed: 0,
+            max_filtered_visits: 0,
+            rng: RngSource::default(),
+            simple_select: false,
+            keep_pruned_connections: true,
+            parallel_build: false,
         }
     }
 
@@ -80,10 +197,97 @@ impl HnswConfig {
             ef_construction: 400,
             ef_search: 200,
             ml: 1.0 / (m as f64).ln(),
+            eager_edge_cleanup: false,
+            shuffle_inserts: false,
+            shuffle_seed: 0,
+            max_filtered_visits: 0,
+            rng: RngSource::default(),
+            simple_select: false,
+            keep_pruned_connections: true,
+            parallel_build: false,
+        }
+    }
+
+    /// Suggest parameters for a dataset of `num_vectors` vectors of `dimensions`
+    ///
+    /// M grows with dimensionality, since high-dimensional data needs more
+    /// links to stay navigable, and is trimmed for small collections where
+    /// the graph is shallow anyway. `target` then scales M and the candidate
+    /// lists; `ef_search` also grows for collections past a million vectors.
+    /// These are starting points to tune against measured recall.
+    pub fn recommend(num_vectors: usize, dimensions: usize, target: TuningTarget) -> Self {
+        let base_m: usize = match dimensions {
+            0..=64 => 12,
+            65..=256 => 16,
+            257..=768 => 24,
+            _ => 32,
+        };
+        let base_m = if num_vectors < 10_000 {
+            base_m.min(16)
+        } else {
+            base_m
+        };
+
+        let (m, ef_construction, ef_search) = match target {
+            TuningTarget::Accuracy => ((base_m * 3 / 2).min(64), 400, 200),
+            TuningTarget::Balanced => (base_m, 200, 100),
+            TuningTarget::Speed => ((base_m * 2 / 3).max(8), 100, 40),
+        };
+        let ef_search = if num_vectors > 1_000_000 {
+            ef_search * 2
+        } else {
+            ef_search
+        };
+
+        Self {
+            m,
+            m0: m * 2,
+            ef_construction: ef_construction.max(m),
+            ef_search,
+            ml: 1.0 / (m as f64).ln(),
+            ..Self::default()
+        }
+    }
+
+    /// Check that the parameters describe a buildable graph
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |message: String| Err(Error::InvalidConfig(message));
+        if self.m < 2 {
+            return invalid(format!("HNSW m must be at least 2, got {}", self.m));
+        }
+        if self.m0 < self.m {
+            return invalid(format!(
+                "HNSW m0 ({}) must be at least m ({})",
+                self.m0, self.m
+            ));
+        }
+        if self.ef_construction < self.m {
+            return invalid(format!(
+                "HNSW ef_construction ({}) must be at least m ({})",
+                self.ef_construction, self.m
+            ));
+        }
+        if self.ef_search == 0 {
+            return invalid("HNSW ef_search must be at least 1".to_string());
         }
+        if !(self.ml.is_finite() && self.ml > 0.0) {
+            return invalid(format!("HNSW ml must be positive, got {}", self.ml));
+        }
+        Ok(())
     }
 }
 
+/// What `HnswConfig::recommend` optimizes for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TuningTarget {
+    /// Highest recall, at the cost of build time, memory and latency
+    Accuracy,
+    /// Good recall at moderate cost
+    Balanced,
+    /// Lowest latency and memory, accepting lower recall
+    Speed,
+}
+
 /// A node in the HNSW graph
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HnswNode {
@@ -97,6 +301,27 @@ pub struct HnswNode {
     pub(crate) neighbors: Vec<Vec<InternalId>>,
 }
 
+/// Read access to a graph's neighbor lists, wherever they are held
+pub(crate) trait GraphLayout {
+    /// Neighbors of `node` on `layer`; empty if the node does not reach it
+    fn neighbors_on(&self, node: InternalId, layer: usize)
+        -> impl Iterator<Item = InternalId> + '_;
+}
+
+impl GraphLayout for [HnswNode] {
+    fn neighbors_on(
+        &self,
+        node: InternalId,
+        layer: usize,
+    ) -> impl Iterator<Item = InternalId> + '_ {
+        self.get(node.as_usize())
+            .and_then(|node| node.neighbors.get(layer))
+            .into_iter()
+            .flatten()
+            .copied()
+    }
+}
+
 impl HnswNode {
     fn new(id: InternalId, max_layer: usize) -> Self {
         Self {
@@ -107,6 +332,39 @@ impl HnswNode {
     }
 }
 
+/// Nodes built serially by `HnswIndex::insert_batch_parallel` before it
+/// goes wide, so the parallel inserts start from a navigable graph
+#[cfg(feature = "parallel")]
+const PARALLEL_BUILD_SEED: usize = 1000;
+
+/// `HnswIndex::remove` sweeps one-way edges to removed nodes once they make
+/// up this fraction (1 / divisor) of the graph
+const SWEEP_DIVISOR: usize = 16;
+
+/// A node during `HnswIndex::insert_batch_parallel`, its neighbor lists
+/// behind a lock of their own
+#[cfg(feature = "parallel")]
+struct LockedNode {
+    id: InternalId,
+    max_layer: usize,
+    neighbors: parking_lot::Mutex<Vec<Vec<InternalId>>>,
+}
+
+#[cfg(feature = "parallel")]
+impl GraphLayout for [LockedNode] {
+    fn neighbors_on(
+        &self,
+        node: InternalId,
+        layer: usize,
+    ) -> impl Iterator<Item = InternalId> + '_ {
+        // Copied out, so the lock is not held while the search scores them
+        self.get(node.as_usize())
+            .and_then(|node| node.neighbors.lock().get(layer).cloned())
+            .into_iter()
+            .flatten()
+    }
+}
+
 /// Candidate for search with distance
 #[derive(Debug, Clone, Copy)]
 struct Candidate {
@@ -167,12 +425,47 @@ impl Ord for MaxCandidate {
     }
 }
 
+/// What a search examined besides the neighbors it returned
+///
+/// Distinguishes "the filter matched nothing nearby" from "nothing matches at
+/// all" when a filtered search comes back short.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchStats {
+    /// Live candidates reached during traversal that the filter rejected
+    pub filtered_out: usize,
+    /// True when the traversal ran out of candidates instead of stopping at
+    /// the distance bound, i.e. every node reachable in the graph was examined
+    pub exhausted: bool,
+}
+
+/// How often stored vectors come back when searched for, as a graph health
+/// signal
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SelfRecall {
+    /// Vectors searched for
+    pub sampled: usize,
+    /// Fraction found in their own top-k (1.0 when nothing was sampled)
+    pub recall: f32,
+}
+
+/// Edges for a new node, computed under the graph read lock by
+/// `HnswIndex::plan_links` and applied under the write lock
+struct PlannedLinks {
+    /// The node's own neighbors, by layer
+    own: Vec<Vec<InternalId>>,
+    /// `(neighbor, layer, pruned)`: the reverse link to add to `neighbor`,
+    /// or the pruned list replacing its neighbors when the link overflows it
+    reverse: Vec<(InternalId, usize, Option<Vec<InternalId>>)>,
+}
+
 struct SearchContext<'a> {
     query: &'a [f32],
     ef: usize,
     layer: usize,
-    filter: Option<&'a Filter>,
-    filter_bitmap: Option<Arc<RoaringBitmap>>,
+    /// Nodes failing this are traversed but not returned
+    predicate: Option<&'a dyn Fn(InternalId) -> bool>,
+    /// Nodes failing this are neither scored nor traversed
+    route: Option<&'a dyn Fn(InternalId) -> bool>,
 }
 
 /// State of the HNSW index for serialization
@@ -183,11 +476,50 @@ pub struct HnswState {
     pub max_layer: usize,
 }
 
+/// A neighbor list longer than the configured maximum degree
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DegreeViolation {
+    pub node: InternalId,
+    pub layer: usize,
+    pub degree: usize,
+    /// M on upper layers, M0 on layer 0
+    pub max_degree: usize,
+}
+
+/// Result of a degree audit over the whole graph
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DegreeReport {
+    /// Number of nodes inspected
+    pub nodes_checked: usize,
+    /// Largest degree seen on any layer
+    pub max_observed_degree: usize,
+    /// Every over-degree neighbor list
+    pub violations: Vec<DegreeViolation>,
+}
+
+impl DegreeReport {
+    /// True when no neighbor list exceeds its maximum degree
+    pub fn is_within_bounds(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
 /// The HNSW index
+///
+/// Writers (inserts, removals, repairs and state loads) are serialized with
+/// each other. Searches are not serialized with writers: they only wait
+/// while an insert publishes its node and while it links it, not during its
+/// neighbor search. A search therefore sees each insert either not at all
+/// or fully linked, and returns a node only once it is linked. Removals,
+/// repairs and state loads still hold the graph write lock throughout.
 pub struct HnswIndex {
     config: HnswConfig,
     distance_metric: DistanceMetric,
 
+    /// Held by every graph mutation, so an insert can plan its links under a
+    /// read lock without another writer changing the graph underneath it
+    writer: RwLock<()>,
+
     /// All nodes in the graph
     nodes: RwLock<Vec<HnswNode>>,
 
@@ -196,57 +528,126 @@ pub struct HnswIndex {
 
     /// Maximum layer in the graph
     max_layer: RwLock<usize>,
+
+    /// Nodes taken out by `remove`. They keep their slot (nodes are indexed by
+    /// internal ID) but have no edges and are never returned. Not part of
+    /// `HnswState`: removed nodes are always deleted in storage as well.
+    removed: RwLock<RoaringBitmap>,
+
+    /// Removed nodes that other nodes may still hold one-way edges to. Their
+    /// edges are swept in one pass over the graph once they make up
+    /// `1 / SWEEP_DIVISOR` of it, or before the graph is serialized.
+    unswept: RwLock<RoaringBitmap>,
+
+    /// xorshift state under `RngSource::Xorshift`
+    rng_state: AtomicU64,
 }
 
 impl HnswIndex {
     /// Create a new HNSW index
     pub fn new(config: HnswConfig, distance_metric: DistanceMetric) -> Self {
         Self {
-            config,
             distance_metric,
+            writer: RwLock::new(()),
             nodes: RwLock::new(Vec::new()),
             entry_point: RwLock::new(None),
             max_layer: RwLock::new(0),
+            removed: RwLock::new(RoaringBitmap::new()),
+            unswept: RwLock::new(RoaringBitmap::new()),
+            rng_state: AtomicU64::new(match config.rng {
+                RngSource::Xorshift(seed) => xorshift_state(seed),
+                RngSource::ThreadRng => 0,
+            }),
+            config,
         }
     }
 
+    /// Make room for `additional` more nodes, so a bulk build does not
+    /// repeatedly grow the node vector
+    ///
+    /// Only capacity changes; the graph and search results are unaffected.
+    pub fn reserve(&self, additional: usize) {
+        self.nodes.write().reserve(additional);
+    }
+
+    /// Capacity for a new neighbor list on `layer`: its full degree plus the
+    /// reverse link that is pushed before pruning
+    fn neighbor_capacity(&self, layer: usize) -> usize {
+        let max_connections = if layer == 0 {
+            self.config.m0
+        } else {
+            self.config.m
+        };
+        max_connections + 1
+    }
+
     /// Generate a random level for a new node
     fn random_level(&self) -> usize {
+        let r = match self.config.rng {
+            RngSource::Xorshift(_) => self.next_xorshift(),
+            RngSource::ThreadRng => thread_random(),
+        };
+        // Keep r in (0, 1) so the log stays finite
+        let r = r.max(f64::EPSILON);
+        // Math.log avoids potential intrinsic issues on WASM
         #[cfg(all(target_arch = "wasm32", feature = "wasm"))]
-        {
-            let r = {
-                let val = js_sys::Math::random();
-                if val < f64::EPSILON {
-                    f64::EPSILON
-                } else {
-                    val
-                }
-            };
-            // Use js_sys::Math::log to avoid potential intrinsic issues on WASM
-            let ln_val = js_sys::Math::log(r);
-            (-ln_val * self.config.ml).floor() as usize
-        }
-
+        let ln_r = js_sys::Math::log(r);
         #[cfg(not(all(target_arch = "wasm32", feature = "wasm")))]
-        {
-            let mut rng = rand::thread_rng();
-            let r: f64 = rng.gen();
-            (-r.ln() * self.config.ml).floor() as usize
-        }
+        let ln_r = r.ln();
+        (-ln_r * self.config.ml).floor() as usize
+    }
+
+    /// Next xorshift64* draw as a float in [0, 1)
+    fn next_xorshift(&self) -> f64 {
+        let previous = self
+            .rng_state
+            .fetch_update(AtomicOrdering::Relaxed, AtomicOrdering::Relaxed, |x| {
+                Some(xorshift_step(x))
+            })
+            .unwrap_or_else(|x| x);
+        let x = xorshift_step(previous).wrapping_mul(0x2545_f491_4f6c_dd1d);
+        // Top 53 bits fill an f64 mantissa exactly
+        (x >> 11) as f64 / (1u64 << 53) as f64
     }
 
     /// Insert multiple vectors in a batch
+    ///
+    /// Neighbor searches run in parallel against the graph as it was before
+    /// each round, so the batch is split into rounds no larger than the graph
+    /// built so far; otherwise a batch into an empty index would stay unlinked.
+    /// With `HnswConfig::parallel_build` the batch goes to
+    /// `insert_batch_parallel` instead.
     #[cfg(feature = "parallel")]
     pub fn insert_batch(
         &self,
         items: &[(InternalId, &[f32])],
         storage: &(impl VectorStorageTrait + Sync),
+    ) -> Result<()> {
+        if self.config.parallel_build {
+            return self.insert_batch_parallel(items, storage);
+        }
+        let mut start = 0;
+        while start < items.len() {
+            let round = self.len().clamp(1, items.len() - start);
+            self.insert_round(&items[start..start + round], storage)?;
+            start += round;
+        }
+        Ok(())
+    }
+
+    /// Insert one round of a batch: parallel neighbor search, then sequential linking
+    #[cfg(feature = "parallel")]
+    fn insert_round(
+        &self,
+        items: &[(InternalId, &[f32])],
+        storage: &(impl VectorStorageTrait + Sync),
     ) -> Result<()> {
         use rayon::prelude::*; // Use inside function to avoid trait/impl conflict
 
         if items.is_empty() {
             return Ok(());
         }
+        let _writer = self.writer.write();
 
         // Generate levels for all new nodes upfront
         let mut new_nodes_data = Vec::with_capacity(items.len());
@@ -273,9 +674,13 @@ impl HnswIndex {
 
                         // Traverse from top layer to node_level + 1
                         for layer in (node_level + 1..=max_layer).rev() {
-                            if let Ok(next_ep) =
-                                self.search_layer_single(vector, current_ep, layer, &nodes, storage)
-                            {
+                            if let Ok(next_ep) = self.search_layer_single(
+                                vector,
+                                current_ep,
+                                layer,
+                                nodes.as_slice(),
+                                storage,
+                            ) {
                                 current_ep = next_ep;
                             }
                         }
@@ -287,11 +692,11 @@ impl HnswIndex {
                                 query: vector,
                                 ef: self.config.ef_construction,
                                 layer,
-                                filter: None,
-                                filter_bitmap: None,
+                                predicate: None,
+                                route: None,
                             };
                             if let Ok(neighbors) =
-                                self.search_layer(ctx, current_ep, &nodes, storage)
+                                self.search_layer(ctx, current_ep, nodes.as_slice(), storage)
                             {
                                 // Select best neighbors
                                 let m = if layer == 0 {
@@ -338,8 +743,9 @@ impl HnswIndex {
             if let Ok(neighbors_by_layer) = &search_results[i] {
                 for (layer, candidates) in neighbors_by_layer.iter().enumerate() {
                     if layer < new_node.neighbors.len() {
-                        new_node.neighbors[layer] =
-                            candidates.iter().map(|c: &Candidate| c.id).collect();
+                        let list = &mut new_node.neighbors[layer];
+                        list.reserve_exact(self.neighbor_capacity(layer));
+                        list.extend(candidates.iter().map(|c: &Candidate| c.id));
                     }
                 }
             }
@@ -365,30 +771,15 @@ impl HnswIndex {
                                 };
 
                                 if neighbor_node.neighbors[layer].len() > max_connections {
-                                    if let Some(nv) = storage.get_vector_data(neighbor.id) {
-                                        let mut candidates: Vec<Candidate> = neighbor_node
-                                            .neighbors[layer]
-                                            .iter()
-                                            .filter_map(|&n_id| {
-                                                storage
-                                                    .distance(n_id, &nv, self.distance_metric)
-                                                    .map(|dist| Candidate {
-                                                        id: n_id,
-                                                        distance: dist,
-                                                    })
-                                            })
-                                            .collect();
-                                        candidates.sort_by(|a, b| {
-                                            a.distance
-                                                .partial_cmp(&b.distance)
-                                                .unwrap_or(Ordering::Equal)
-                                        });
-                                        neighbor_node.neighbors[layer] = candidates
-                                            .into_iter()
-                                            .take(max_connections)
-                                            .map(|c| c.id)
-                                            .collect();
-                                    }
+                                    let kept = self.prune_connections(
+                                        neighbor.id,
+                                        &neighbor_node.neighbors[layer],
+                                        layer,
+                                        storage,
+                                    );
+                                    // Refill in place to keep the list's allocation
+                                    neighbor_node.neighbors[layer].clear();
+                                    neighbor_node.neighbors[layer].extend(kept);
                                 }
                             }
                         }
@@ -406,6 +797,209 @@ impl HnswIndex {
         Ok(())
     }
 
+    /// Insert multiple vectors, linking them from many threads at once
+    ///
+    /// Until the graph holds `PARALLEL_BUILD_SEED` nodes, inserts run
+    /// serially. The rest of the batch is then inserted concurrently: each
+    /// node searches the graph as it stands, including nodes other threads
+    /// have just linked, and every neighbor list is locked on its own while
+    /// a link is added, so threads only contend when linking to the same
+    /// node. Recall stays close to a serial build, but the graph depends on
+    /// thread scheduling and is not reproducible, even with a seeded `rng`.
+    ///
+    /// The graph write lock is held until the whole batch is linked, so
+    /// searches wait for the build to finish.
+    #[cfg(feature = "parallel")]
+    pub fn insert_batch_parallel(
+        &self,
+        items: &[(InternalId, &[f32])],
+        storage: &(impl VectorStorageTrait + Sync),
+    ) -> Result<()> {
+        use rayon::prelude::*;
+
+        let mut start = 0;
+        while start < items.len()
+            && (self.len() < PARALLEL_BUILD_SEED || self.entry_point.read().is_none())
+        {
+            let (internal_id, vector) = items[start];
+            self.insert(internal_id, vector, storage)?;
+            start += 1;
+        }
+        let items = &items[start..];
+        if items.is_empty() {
+            return Ok(());
+        }
+
+        let writer = self.writer.write();
+        let Some(ep) = *self.entry_point.read() else {
+            // Emptied by a removal since seeding; seed again
+            drop(writer);
+            return self.insert_batch_parallel(items, storage);
+        };
+        let current_max_layer = *self.max_layer.read();
+        let levels: Vec<usize> = items.iter().map(|_| self.random_level()).collect();
+
+        let mut nodes = self.nodes.write();
+        let mut graph: Vec<LockedNode> = std::mem::take(&mut *nodes)
+            .into_iter()
+            .map(|node| LockedNode {
+                id: node.id,
+                max_layer: node.max_layer,
+                neighbors: parking_lot::Mutex::new(node.neighbors),
+            })
+            .collect();
+        // Publish every node up front, so a thread can link to a node whose
+        // own search is still running
+        graph.extend(
+            items
+                .iter()
+                .zip(&levels)
+                .map(|(&(internal_id, _), &level)| LockedNode {
+                    id: internal_id,
+                    max_layer: level,
+                    neighbors: parking_lot::Mutex::new(vec![Vec::new(); level + 1]),
+                }),
+        );
+
+        let linked = items.par_iter().zip(levels.par_iter()).try_for_each(
+            |(&(internal_id, vector), &level)| {
+                self.link_locked(
+                    internal_id,
+                    vector,
+                    level,
+                    (ep, current_max_layer),
+                    &graph,
+                    storage,
+                )
+            },
+        );
+
+        // Layers above the old top one only hold nodes from this batch, and
+        // only a handful of them, so link those exhaustively
+        let mut top = (ep, current_max_layer);
+        let mut upper: Vec<InternalId> = Vec::new();
+        for (&(internal_id, _), &level) in items.iter().zip(&levels) {
+            if level <= current_max_layer {
+                continue;
+            }
+            for layer in current_max_layer + 1..=level {
+                let pool: Vec<InternalId> = upper
+                    .iter()
+                    .copied()
+                    .filter(|id| graph[id.as_usize()].max_layer >= layer)
+                    .collect();
+                for neighbor in self.prune_connections(internal_id, &pool, layer, storage) {
+                    self.add_locked_link(internal_id, neighbor, layer, &graph, storage);
+                    self.add_locked_link(neighbor, internal_id, layer, &graph, storage);
+                }
+            }
+            upper.push(internal_id);
+            if level > top.1 {
+                top = (internal_id, level);
+            }
+        }
+
+        *nodes = graph
+            .into_iter()
+            .map(|node| HnswNode {
+                id: node.id,
+                max_layer: node.max_layer,
+                neighbors: node.neighbors.into_inner(),
+            })
+            .collect();
+        *self.entry_point.write() = Some(top.0);
+        *self.max_layer.write() = top.1;
+
+        linked
+    }
+
+    /// Search for and link one node of `insert_batch_parallel` on every layer
+    /// up to `node_level`, locking one neighbor list at a time
+    #[cfg(feature = "parallel")]
+    fn link_locked(
+        &self,
+        internal_id: InternalId,
+        vector: &[f32],
+        node_level: usize,
+        (ep, current_max_layer): (InternalId, usize),
+        graph: &[LockedNode],
+        storage: &impl VectorStorageTrait,
+    ) -> Result<()> {
+        let mut current_ep = ep;
+        for layer in (node_level + 1..=current_max_layer).rev() {
+            current_ep = self.search_layer_single(vector, current_ep, layer, graph, storage)?;
+        }
+
+        let start_layer = node_level.min(current_max_layer);
+        for layer in (0..=start_layer).rev() {
+            let ctx = SearchContext {
+                query: vector,
+                ef: self.config.ef_construction,
+                layer,
+                predicate: None,
+                route: None,
+            };
+            let mut neighbors = self.search_layer_with_stats(
+                ctx,
+                current_ep,
+                graph,
+                storage,
+                &mut SearchStats::default(),
+            )?;
+            // Another thread may already have linked this node
+            neighbors.retain(|c| c.id != internal_id);
+
+            let m = if layer == 0 {
+                self.config.m0
+            } else {
+                self.config.m
+            };
+            let selected = self.select_neighbors(&neighbors, m, storage);
+            for neighbor in &selected {
+                self.add_locked_link(internal_id, neighbor.id, layer, graph, storage);
+                self.add_locked_link(neighbor.id, internal_id, layer, graph, storage);
+            }
+            if let Some(closest) = selected.first() {
+                current_ep = closest.id;
+            }
+        }
+        Ok(())
+    }
+
+    /// Add the edge `from -> to` on `layer` of a locked graph, pruning
+    /// `from`'s list if it overflows
+    #[cfg(feature = "parallel")]
+    fn add_locked_link(
+        &self,
+        from: InternalId,
+        to: InternalId,
+        layer: usize,
+        graph: &[LockedNode],
+        storage: &impl VectorStorageTrait,
+    ) {
+        let node = &graph[from.as_usize()];
+        if node.max_layer < layer {
+            return;
+        }
+        let mut neighbors = node.neighbors.lock();
+        let list = &mut neighbors[layer];
+        if list.contains(&to) {
+            return;
+        }
+        list.push(to);
+        let max_connections = if layer == 0 {
+            self.config.m0
+        } else {
+            self.config.m
+        };
+        if list.len() > max_connections {
+            let kept = self.prune_connections(from, list, layer, storage);
+            // Refill in place to keep the list's allocation
+            list.clear();
+            list.extend(kept);
+        }
+    }
+
     /// Insert multiple vectors in a batch (sequential version for WASM)
     #[cfg(not(feature = "parallel"))]
     pub fn insert_batch(
@@ -420,7 +1014,46 @@ impl HnswIndex {
         Ok(())
     }
 
+    /// Build an empty index from `items`, as compaction does
+    ///
+    /// With `HnswConfig::parallel_build` the items go to
+    /// `insert_batch_parallel`; otherwise they are inserted one at a time,
+    /// which links a fresh graph better than `insert_batch`'s rounds.
+    #[cfg(feature = "parallel")]
+    pub fn build(
+        &self,
+        items: &[(InternalId, &[f32])],
+        storage: &(impl VectorStorageTrait + Sync),
+    ) -> Result<()> {
+        self.reserve(items.len());
+        if self.config.parallel_build {
+            return self.insert_batch_parallel(items, storage);
+        }
+        for &(internal_id, vector) in items {
+            self.insert(internal_id, vector, storage)?;
+        }
+        Ok(())
+    }
+
+    /// Build an empty index from `items` (sequential version for WASM)
+    #[cfg(not(feature = "parallel"))]
+    pub fn build(
+        &self,
+        items: &[(InternalId, &[f32])],
+        storage: &impl VectorStorageTrait,
+    ) -> Result<()> {
+        self.reserve(items.len());
+        for &(internal_id, vector) in items {
+            self.insert(internal_id, vector, storage)?;
+        }
+        Ok(())
+    }
+
     /// Insert a new vector into the index
+    ///
+    /// Searches keep running while the neighbor search for the new node is
+    /// under way; only publishing the empty node and linking it take the
+    /// graph write lock. See [`HnswIndex`] for what concurrent searches see.
     pub fn insert(
         &self,
         internal_id: InternalId,
@@ -428,29 +1061,87 @@ impl HnswIndex {
         storage: &impl VectorStorageTrait,
     ) -> Result<()> {
         let node_level = self.random_level();
+        let _writer = self.writer.write();
+
+        // Publish the node with no edges; nothing links to it yet, so
+        // searches cannot reach it until it is linked below
+        let (ep, current_max_layer) = {
+            let mut nodes = self.nodes.write();
+            nodes.push(HnswNode::new(internal_id, node_level));
+            let mut entry_point = self.entry_point.write();
+            match *entry_point {
+                Some(ep) => (ep, *self.max_layer.read()),
+                None => {
+                    // The first node is the entry point and has nothing to link
+                    *entry_point = Some(internal_id);
+                    *self.max_layer.write() = node_level;
+                    return Ok(());
+                }
+            }
+        };
 
-        let mut nodes = self.nodes.write();
-        let mut entry_point = self.entry_point.write();
-        let mut max_layer = self.max_layer.write();
+        // Other writers wait on `writer`, so the graph cannot change between
+        // planning the links and applying them
+        let links = {
+            let nodes = self.nodes.read();
+            self.plan_links(
+                internal_id,
+                vector,
+                node_level,
+                (ep, current_max_layer),
+                &nodes,
+                storage,
+            )?
+        };
 
-        // Create the new node
-        let new_node = HnswNode::new(internal_id, node_level);
-        nodes.push(new_node);
+        let mut nodes = self.nodes.write();
+        for (layer, selected) in links.own.into_iter().enumerate() {
+            let list = &mut nodes[internal_id.as_usize()].neighbors[layer];
+            list.reserve_exact(self.neighbor_capacity(layer));
+            list.extend(selected);
+        }
+        for (neighbor_id, layer, pruned) in links.reverse {
+            let list = &mut nodes[neighbor_id.as_usize()].neighbors[layer];
+            match pruned {
+                None => list.push(internal_id),
+                Some(kept) => {
+                    // Refill in place to keep the list's allocation
+                    list.clear();
+                    list.extend(kept);
+                }
+            }
+        }
 
-        // If this is the first node, set it as entry point and return
-        if entry_point.is_none() {
-            *entry_point = Some(internal_id);
-            *max_layer = node_level;
-            return Ok(());
+        // Update entry point if new node has higher layer
+        if node_level > current_max_layer {
+            *self.entry_point.write() = Some(internal_id);
+            *self.max_layer.write() = node_level;
         }
 
-        let ep = entry_point.expect("Entry point should be Some here");
-        let current_max_layer = *max_layer;
+        Ok(())
+    }
+
+    /// Find the neighbors of a node about to be inserted on every layer up to
+    /// `node_level`, and the reverse links that go with them, starting from
+    /// the graph's current entry point and top layer
+    fn plan_links(
+        &self,
+        internal_id: InternalId,
+        vector: &[f32],
+        node_level: usize,
+        (ep, current_max_layer): (InternalId, usize),
+        nodes: &[HnswNode],
+        storage: &impl VectorStorageTrait,
+    ) -> Result<PlannedLinks> {
+        let mut links = PlannedLinks {
+            own: vec![Vec::new(); node_level + 1],
+            reverse: Vec::new(),
+        };
 
         // Search from top layer to node_level + 1, finding the closest node
         let mut current_ep = ep;
         for layer in (node_level + 1..=current_max_layer).rev() {
-            current_ep = self.search_layer_single(vector, current_ep, layer, &nodes, storage)?;
+            current_ep = self.search_layer_single(vector, current_ep, layer, nodes, storage)?;
         }
 
         // For layers from min(node_level, max_layer) down to 0, find and connect neighbors
@@ -460,10 +1151,10 @@ impl HnswIndex {
                 query: vector,
                 ef: self.config.ef_construction,
                 layer,
-                filter: None,
-                filter_bitmap: None,
+                predicate: None,
+                route: None,
             };
-            let neighbors = self.search_layer(ctx, current_ep, &nodes, storage)?;
+            let neighbors = self.search_layer(ctx, current_ep, nodes, storage)?;
 
             // Select M best neighbors using heuristic
             let m = if layer == 0 {
@@ -473,75 +1164,330 @@ impl HnswIndex {
             };
             let selected = self.select_neighbors(&neighbors, m, storage);
 
-            // Connect new node to selected neighbors
-            let node_idx = internal_id.as_usize();
-            nodes[node_idx].neighbors[layer] = selected.iter().map(|c| c.id).collect();
-
-            // Add bidirectional connections
+            // Add bidirectional connections, pruning lists that overflow
             for neighbor in &selected {
-                let neighbor_idx = neighbor.id.as_usize();
-                let neighbor_node = &mut nodes[neighbor_idx];
-
-                if neighbor_node.max_layer >= layer {
-                    neighbor_node.neighbors[layer].push(internal_id);
-
-                    // Prune if too many connections
-                    let max_connections = if layer == 0 {
-                        self.config.m0
-                    } else {
-                        self.config.m
-                    };
-
-                    if neighbor_node.neighbors[layer].len() > max_connections {
-                        // Get distances and prune
-                        if let Some(nv) = storage.get_vector_data(neighbor.id) {
-                            let mut candidates: Vec<Candidate> = neighbor_node.neighbors[layer]
-                                .iter()
-                                .filter_map(|&n_id| {
-                                    storage
-                                        .distance(n_id, &nv, self.distance_metric)
-                                        .map(|dist| Candidate {
-                                            id: n_id,
-                                            distance: dist,
-                                        })
-                                })
-                                .collect();
-                            candidates.sort_by(|a, b| {
-                                a.distance
-                                    .partial_cmp(&b.distance)
-                                    .unwrap_or(Ordering::Equal)
-                            });
-                            neighbor_node.neighbors[layer] = candidates
-                                .into_iter()
-                                .take(max_connections)
-                                .map(|c| c.id)
-                                .collect();
-                        }
-                    }
+                let neighbor_node = &nodes[neighbor.id.as_usize()];
+                if neighbor_node.max_layer < layer {
+                    continue;
                 }
+                let current = &neighbor_node.neighbors[layer];
+                let pruned = if current.len() + 1 > m {
+                    let mut pool = Vec::with_capacity(current.len() + 1);
+                    pool.extend_from_slice(current);
+                    pool.push(internal_id);
+                    Some(self.prune_connections(neighbor.id, &pool, layer, storage))
+                } else {
+                    None
+                };
+                links.reverse.push((neighbor.id, layer, pruned));
             }
 
             if !selected.is_empty() {
                 current_ep = selected[0].id;
             }
+            links.own[layer] = selected.into_iter().map(|c| c.id).collect();
         }
 
-        // Update entry point if new node has higher layer
-        if node_level > current_max_layer {
-            *entry_point = Some(internal_id);
-            *max_layer = node_level;
+        Ok(links)
+    }
+
+    /// Remove a node from the graph so that no other node keeps an edge to it
+    ///
+    /// Used when a vector is deleted or superseded by an update. Each former
+    /// neighbor is offered the removed node's other neighbors as replacement
+    /// candidates (re-pruned with the selection heuristic), which keeps the
+    /// surrounding region connected. One-way edges from nodes the removed
+    /// one did not link back to are swept later, in one pass for many
+    /// removals, so the amortized cost is O(M) per removal. Until then they
+    /// are skipped by searches and left out of `neighbors`.
+    ///
+    /// The node is never returned by a search afterwards. If it was the entry
+    /// point, the live node with the highest layer takes over; once no live
+    /// node is left, searches return no results until the next insert.
+    pub fn remove(&self, internal_id: InternalId, storage: &impl VectorStorageTrait) {
+        let _writer = self.writer.write();
+        let mut nodes = self.nodes.write();
+        let mut entry_point = self.entry_point.write();
+        let mut max_layer = self.max_layer.write();
+
+        let mut removed = self.removed.write();
+        let mut unswept = self.unswept.write();
+
+        let node_idx = internal_id.as_usize();
+        if node_idx >= nodes.len() || !removed.insert(internal_id.as_u32()) {
+            return;
         }
 
-        Ok(())
-    }
+        let empty = vec![Vec::new(); nodes[node_idx].max_layer + 1];
+        let former = std::mem::replace(&mut nodes[node_idx].neighbors, empty);
+        let dead = |id: InternalId| storage.is_deleted(id) || removed.contains(id.as_u32());
 
-    /// Search for a single nearest neighbor in a layer (greedy search)
-    fn search_layer_single(
-        &self,
-        query: &[f32],
-        entry: InternalId,
-        layer: usize,
-        nodes: &[HnswNode],
+        // Re-link former neighbors through the removed node's other neighbors
+        for (layer, former_layer) in former.iter().enumerate() {
+            for &neighbor_id in former_layer {
+                if dead(neighbor_id) {
+                    continue;
+                }
+                let neighbor_idx = neighbor_id.as_usize();
+                if nodes[neighbor_idx].max_layer < layer {
+                    continue;
+                }
+
+                let mut pool: Vec<InternalId> = nodes[neighbor_idx].neighbors[layer]
+                    .iter()
+                    .copied()
+                    .filter(|&id| !dead(id))
+                    .collect();
+                for &candidate in former_layer {
+                    if candidate != neighbor_id && !dead(candidate) && !pool.contains(&candidate) {
+                        pool.push(candidate);
+                    }
+                }
+
+                nodes[neighbor_idx].neighbors[layer] =
+                    self.prune_connections(neighbor_id, &pool, layer, storage);
+            }
+        }
+
+        unswept.insert(internal_id.as_u32());
+        if unswept.len() as usize * SWEEP_DIVISOR >= nodes.len() {
+            Self::sweep(&mut nodes, &mut unswept);
+        }
+
+        if *entry_point == Some(internal_id) {
+            let replacement = nodes
+                .iter()
+                .filter(|n| !dead(n.id))
+                .max_by_key(|n| n.max_layer)
+                .map(|n| (n.id, n.max_layer));
+            *entry_point = replacement.map(|(id, _)| id);
+            *max_layer = replacement.map_or(0, |(_, layer)| layer);
+        }
+    }
+
+    /// Drop every edge to an unswept removed node
+    fn sweep(nodes: &mut [HnswNode], unswept: &mut RoaringBitmap) {
+        if unswept.is_empty() {
+            return;
+        }
+        for node in nodes.iter_mut() {
+            for layer_neighbors in node.neighbors.iter_mut() {
+                layer_neighbors.retain(|id| !unswept.contains(id.as_u32()));
+            }
+        }
+        unswept.clear();
+    }
+
+    /// Sweep pending edges to removed nodes now, ahead of a full-graph read
+    fn flush_sweep(&self) {
+        if self.unswept.read().is_empty() {
+            return;
+        }
+        let _writer = self.writer.write();
+        Self::sweep(&mut self.nodes.write(), &mut self.unswept.write());
+    }
+
+    /// Select at most M (or M0 on layer 0) connections for a node from a candidate pool
+    fn prune_connections(
+        &self,
+        node_id: InternalId,
+        pool: &[InternalId],
+        layer: usize,
+        storage: &impl VectorStorageTrait,
+    ) -> Vec<InternalId> {
+        let max_connections = if layer == 0 {
+            self.config.m0
+        } else {
+            self.config.m
+        };
+
+        let node_vector = match storage.get_vector_data(node_id) {
+            Some(v) => v,
+            None => return pool.iter().copied().take(max_connections).collect(),
+        };
+
+        let mut candidates: Vec<Candidate> = pool
+            .iter()
+            .filter_map(|&id| {
+                storage
+                    .distance(id, &node_vector, self.distance_metric)
+                    .map(|distance| Candidate { id, distance })
+            })
+            .collect();
+        candidates.sort_by(|a, b| {
+            a.distance
+                .partial_cmp(&b.distance)
+                .unwrap_or(Ordering::Equal)
+        });
+
+        self.select_neighbors(&candidates, max_connections, storage)
+            .into_iter()
+            .map(|c| c.id)
+            .collect()
+    }
+
+    /// Check graph invariants
+    ///
+    /// Verifies that every edge points at an existing node that lives on the
+    /// edge's layer, that no node links to itself, and that no live node keeps
+    /// an edge to a deleted one, other than a removed node awaiting its sweep.
+    /// Stale edges are expected when `eager_edge_cleanup` is disabled.
+    pub fn verify(&self, storage: &impl VectorStorageTrait) -> Result<()> {
+        let nodes = self.nodes.read();
+        let removed = self.removed.read();
+        let unswept = self.unswept.read();
+
+        for (idx, node) in nodes.iter().enumerate() {
+            if node.id.as_usize() != idx {
+                return Err(Error::IndexCorrupted {
+                    message: format!("node at position {} has id {}", idx, node.id.as_u32()),
+                });
+            }
+            if storage.is_deleted(node.id) || removed.contains(node.id.as_u32()) {
+                continue;
+            }
+
+            for (layer, layer_neighbors) in node.neighbors.iter().enumerate() {
+                for &neighbor_id in layer_neighbors {
+                    let neighbor =
+                        nodes
+                            .get(neighbor_id.as_usize())
+                            .ok_or_else(|| Error::IndexCorrupted {
+                                message: format!(
+                                    "node {} links to missing node {} on layer {}",
+                                    idx,
+                                    neighbor_id.as_u32(),
+                                    layer
+                                ),
+                            })?;
+                    if neighbor_id == node.id {
+                        return Err(Error::IndexCorrupted {
+                            message: format!("node {} links to itself on layer {}", idx, layer),
+                        });
+                    }
+                    if neighbor.max_layer < layer {
+                        return Err(Error::IndexCorrupted {
+                            message: format!(
+                                "node {} links to node {} on layer {} above its max layer {}",
+                                idx,
+                                neighbor_id.as_u32(),
+                                layer,
+                                neighbor.max_layer
+                            ),
+                        });
+                    }
+                    let stale =
+                        storage.is_deleted(neighbor_id) || removed.contains(neighbor_id.as_u32());
+                    if stale && !unswept.contains(neighbor_id.as_u32()) {
+                        return Err(Error::IndexCorrupted {
+                            message: format!(
+                                "node {} keeps a stale edge to deleted node {} on layer {}",
+                                idx,
+                                neighbor_id.as_u32(),
+                                layer
+                            ),
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Maximum neighbors per node on a layer (M0 on layer 0, M above)
+    fn max_degree(&self, layer: usize) -> usize {
+        if layer == 0 {
+            self.config.m0
+        } else {
+            self.config.m
+        }
+    }
+
+    /// Report nodes whose neighbor lists exceed M (or M0 on layer 0)
+    pub fn audit_degrees(&self) -> DegreeReport {
+        self.audit_nodes(&self.nodes.read())
+    }
+
+    fn audit_nodes(&self, nodes: &[HnswNode]) -> DegreeReport {
+        let mut report = DegreeReport {
+            nodes_checked: nodes.len(),
+            ..Default::default()
+        };
+        for node in nodes {
+            for (layer, layer_neighbors) in node.neighbors.iter().enumerate() {
+                let degree = layer_neighbors.len();
+                report.max_observed_degree = report.max_observed_degree.max(degree);
+                let max_degree = self.max_degree(layer);
+                if degree > max_degree {
+                    report.violations.push(DegreeViolation {
+                        node: node.id,
+                        layer,
+                        degree,
+                        max_degree,
+                    });
+                }
+            }
+        }
+        report
+    }
+
+    /// Re-prune every over-degree neighbor list with the selection heuristic
+    ///
+    /// Returns the audit taken before the repair. A neighbor that loses its
+    /// only incoming edge on a layer is re-linked from one of its own
+    /// neighbors with spare capacity, so the repair does not strand nodes.
+    /// Each such check scans the layer, so the cost is O(n * M) per dropped
+    /// edge; repairs are expected to be rare.
+    pub fn auto_repair_degrees(&self, storage: &impl VectorStorageTrait) -> DegreeReport {
+        let _writer = self.writer.write();
+        let mut nodes = self.nodes.write();
+        let report = self.audit_nodes(&nodes);
+
+        for violation in &report.violations {
+            let (node_id, layer) = (violation.node, violation.layer);
+            let before = nodes[node_id.as_usize()].neighbors[layer].clone();
+            let kept = self.prune_connections(node_id, &before, layer, storage);
+            nodes[node_id.as_usize()].neighbors[layer] = kept.clone();
+
+            for &dropped in before.iter().filter(|id| !kept.contains(id)) {
+                if storage.is_deleted(dropped) {
+                    continue;
+                }
+                let reachable = nodes.iter().any(|n| {
+                    n.max_layer >= layer && n.id != dropped && n.neighbors[layer].contains(&dropped)
+                });
+                if reachable {
+                    continue;
+                }
+
+                let max_degree = self.max_degree(layer);
+                let host = nodes[dropped.as_usize()]
+                    .neighbors
+                    .get(layer)
+                    .and_then(|own| {
+                        own.iter().copied().find(|&h| {
+                            h != node_id
+                                && !storage.is_deleted(h)
+                                && nodes[h.as_usize()].neighbors[layer].len() < max_degree
+                        })
+                    });
+                if let Some(host) = host {
+                    nodes[host.as_usize()].neighbors[layer].push(dropped);
+                }
+            }
+        }
+
+        report
+    }
+
+    /// Search for a single nearest neighbor in a layer (greedy search)
+    fn search_layer_single<G: GraphLayout + ?Sized>(
+        &self,
+        query: &[f32],
+        entry: InternalId,
+        layer: usize,
+        graph: &G,
         storage: &impl VectorStorageTrait,
     ) -> Result<InternalId> {
         let mut current = entry;
@@ -550,17 +1496,14 @@ impl HnswIndex {
             .unwrap_or(f32::MAX);
 
         loop {
-            let node = &nodes[current.as_usize()];
             let mut changed = false;
 
-            if node.max_layer >= layer {
-                for &neighbor_id in &node.neighbors[layer] {
-                    if let Some(dist) = storage.distance(neighbor_id, query, self.distance_metric) {
-                        if dist < current_dist {
-                            current = neighbor_id;
-                            current_dist = dist;
-                            changed = true;
-                        }
+            for neighbor_id in graph.neighbors_on(current, layer) {
+                if let Some(dist) = storage.distance(neighbor_id, query, self.distance_metric) {
+                    if dist < current_dist {
+                        current = neighbor_id;
+                        current_dist = dist;
+                        changed = true;
                     }
                 }
             }
@@ -580,6 +1523,18 @@ impl HnswIndex {
         entry: InternalId,
         nodes: &[HnswNode],
         storage: &impl VectorStorageTrait,
+    ) -> Result<Vec<Candidate>> {
+        self.search_layer_with_stats(ctx, entry, nodes, storage, &mut SearchStats::default())
+    }
+
+    /// `search_layer`, recording filter rejections and frontier exhaustion
+    fn search_layer_with_stats<G: GraphLayout + ?Sized>(
+        &self,
+        ctx: SearchContext,
+        entry: InternalId,
+        graph: &G,
+        storage: &impl VectorStorageTrait,
+        stats: &mut SearchStats,
     ) -> Result<Vec<Candidate>> {
         let visited_cap = ctx.ef.saturating_mul(4).max(64);
         let mut visited = HashSet::with_capacity(visited_cap);
@@ -597,71 +1552,72 @@ impl HnswIndex {
         });
 
         // Check if entry point matches filter and is not deleted
-        let entry_matches = if let Some(ref bitmap) = ctx.filter_bitmap {
-            bitmap.contains(entry.as_u32())
-        } else if let Some(f) = ctx.filter {
-            storage
-                .get_metadata(entry)
-                .map(|m| f.matches(&m))
-                .unwrap_or(false)
-        } else {
-            true
-        };
-        let entry_valid = !storage.is_deleted(entry) && entry_matches;
+        let entry_matches = ctx.predicate.is_none_or(|matches| matches(entry));
+        let removed = self.removed.read();
+        let entry_live = !storage.is_deleted(entry) && !removed.contains(entry.as_u32());
+        if entry_live && !entry_matches {
+            stats.filtered_out += 1;
+        }
 
-        if entry_valid {
+        if entry_live && entry_matches {
             results.push(MaxCandidate {
                 id: entry,
                 distance: entry_dist,
             });
         }
 
+        stats.exhausted = true;
         while let Some(current) = candidates.pop() {
             // Get the furthest result
             let furthest = results.peek().map(|c| c.distance).unwrap_or(f32::MAX);
 
-            if current.distance > furthest {
+            // Unfiltered, every visited node is a result and this is the usual
+            // bound. Filtered, keep expanding until `ef` matches are held.
+            if current.distance > furthest && results.len() >= ctx.ef {
+                stats.exhausted = false;
+                break;
+            }
+            if ctx.predicate.is_some()
+                && self.config.max_filtered_visits > 0
+                && visited.len() >= self.config.max_filtered_visits
+            {
+                stats.exhausted = false;
                 break;
             }
 
-            let node = &nodes[current.id.as_usize()];
-            if node.max_layer >= ctx.layer {
-                for &neighbor_id in &node.neighbors[ctx.layer] {
-                    if visited.insert(neighbor_id) {
-                        if let Some(dist) =
-                            storage.distance(neighbor_id, ctx.query, self.distance_metric)
-                        {
-                            let furthest = results.peek().map(|c| c.distance).unwrap_or(f32::MAX);
+            for neighbor_id in graph.neighbors_on(current.id, ctx.layer) {
+                if ctx.route.is_some_and(|routed| !routed(neighbor_id)) {
+                    continue;
+                }
+                if visited.insert(neighbor_id) {
+                    if let Some(dist) =
+                        storage.distance(neighbor_id, ctx.query, self.distance_metric)
+                    {
+                        let furthest = results.peek().map(|c| c.distance).unwrap_or(f32::MAX);
+
+                        if dist < furthest || results.len() < ctx.ef {
+                            candidates.push(Candidate {
+                                id: neighbor_id,
+                                distance: dist,
+                            });
+
+                            // Check filter and deleted status before adding to results
+                            let matches_filter =
+                                ctx.predicate.is_none_or(|matches| matches(neighbor_id));
+                            let neighbor_live = !storage.is_deleted(neighbor_id)
+                                && !removed.contains(neighbor_id.as_u32());
+                            if neighbor_live && !matches_filter {
+                                stats.filtered_out += 1;
+                            }
 
-                            if dist < furthest || results.len() < ctx.ef {
-                                candidates.push(Candidate {
+                            if neighbor_live && matches_filter {
+                                results.push(MaxCandidate {
                                     id: neighbor_id,
                                     distance: dist,
                                 });
 
-                                // Check filter and deleted status before adding to results
-                                let matches_filter = if let Some(ref bitmap) = ctx.filter_bitmap {
-                                    bitmap.contains(neighbor_id.as_u32())
-                                } else if let Some(f) = ctx.filter {
-                                    storage
-                                        .get_metadata(neighbor_id)
-                                        .map(|m| f.matches(&m))
-                                        .unwrap_or(false)
-                                } else {
-                                    true
-                                };
-                                let neighbor_valid =
-                                    !storage.is_deleted(neighbor_id) && matches_filter;
-
-                                if neighbor_valid {
-                                    results.push(MaxCandidate {
-                                        id: neighbor_id,
-                                        distance: dist,
-                                    });
-
-                                    if results.len() > ctx.ef {
-                                        results.pop();
-                                    }
+                                if results.len() > ctx.ef {
+                                    results.pop();
                                 }
                             }
                         }
@@ -688,6 +1644,10 @@ impl HnswIndex {
     }
 
     /// Select best neighbors using the heuristic from HNSW paper (Algorithm 4)
+    ///
+    /// A candidate is kept only if it is closer to the new node than to every
+    /// neighbor already kept, which spreads links across directions instead
+    /// of spending them all inside one cluster.
     fn select_neighbors(
         &self,
         candidates: &[Candidate],
@@ -697,11 +1657,14 @@ impl HnswIndex {
         if candidates.len() <= m {
             return candidates.to_vec();
         }
+        // Candidates are already sorted by distance to query (closest first)
+        if self.config.simple_select {
+            return candidates[..m].to_vec();
+        }
 
         let mut result: Vec<Candidate> = Vec::with_capacity(m);
         let mut discard = Vec::new();
 
-        // Candidates are already sorted by distance to query (closest first)
         for &candidate in candidates {
             if result.len() >= m {
                 break;
@@ -729,23 +1692,11 @@ impl HnswIndex {
             }
         }
 
-        // Fill remaining spots from discard pile if needed (to ensure connectivity)
-        // This is optional but recommended to maintain M connections
-        /*
-        while result.len() < m && !discard.is_empty() {
-            result.push(discard.remove(0));
-        }
-        */
-
         // If we filtered too aggressively and have fewer than M (but we started with > M),
         // fill up with the best discarded ones.
-        if result.len() < m {
-            for &candidate in &discard {
-                if result.len() >= m {
-                    break;
-                }
-                result.push(candidate);
-            }
+        if self.config.keep_pruned_connections {
+            let missing = m - result.len();
+            result.extend(discard.into_iter().take(missing));
         }
 
         result
@@ -759,43 +1710,222 @@ impl HnswIndex {
         storage: &impl VectorStorageTrait,
         filter: Option<&Filter>,
     ) -> Result<Vec<(InternalId, f32)>> {
-        let nodes = self.nodes.read();
-        let entry_point = self.entry_point.read();
-        let max_layer = *self.max_layer.read();
+        self.search_with_stats(query, k, storage, filter)
+            .map(|(results, _)| results)
+    }
 
-        let ep = match *entry_point {
-            Some(ep) => ep,
-            None => return Err(Error::EmptyIndex),
-        };
+    /// Like `search`, also reporting filter rejections and whether the
+    /// layer-0 traversal was exhausted
+    pub fn search_with_stats(
+        &self,
+        query: &[f32],
+        k: usize,
+        storage: &impl VectorStorageTrait,
+        filter: Option<&Filter>,
+    ) -> Result<(Vec<(InternalId, f32)>, SearchStats)> {
+        self.search_with_ef(query, k, None, storage, filter)
+    }
 
-        // Traverse from top layer to layer 1
-        let mut current_ep = ep;
-        for layer in (1..=max_layer).rev() {
-            current_ep = self.search_layer_single(query, current_ep, layer, &nodes, storage)?;
-        }
+    /// Like `search_with_stats`, with a layer-0 candidate list of `ef`
+    /// instead of the configured `ef_search` (`None` keeps the configured
+    /// value). The list never holds fewer than `k` candidates.
+    pub fn search_with_ef(
+        &self,
+        query: &[f32],
+        k: usize,
+        ef: Option<usize>,
+        storage: &impl VectorStorageTrait,
+        filter: Option<&Filter>,
+    ) -> Result<(Vec<(InternalId, f32)>, SearchStats)> {
+        self.search_routed(query, k, ef, storage, filter, None)
+    }
 
-        // Search in layer 0 with ef_search
-        let ef = self.config.ef_search.max(k);
+    /// Like `search_with_ef`, confining the layer-0 traversal to nodes
+    /// passing `route`; the others are skipped without computing their
+    /// distance, which is what makes a narrow route cheaper than a filter.
+    /// The entry point reached through the upper layers is always scored.
+    pub fn search_routed(
+        &self,
+        query: &[f32],
+        k: usize,
+        ef: Option<usize>,
+        storage: &impl VectorStorageTrait,
+        filter: Option<&Filter>,
+        route: Option<&dyn Fn(InternalId) -> bool>,
+    ) -> Result<(Vec<(InternalId, f32)>, SearchStats)> {
+        let Some(filter) = filter else {
+            return self.search_inner(query, k, ef, storage, None, route);
+        };
         let filter_bitmap = if bitmap_filter_enabled() {
-            filter.and_then(|f| storage.filter_bitmap(f))
+            storage.filter_bitmap(filter)
         } else {
             None
         };
+        let matches = |id: InternalId| match &filter_bitmap {
+            Some(bitmap) => bitmap.contains(id.as_u32()),
+            None => storage.matches_filter(id, filter),
+        };
+        self.search_inner(query, k, ef, storage, Some(&matches), route)
+    }
+
+    /// Search for the k nearest neighbors satisfying `predicate`
+    ///
+    /// The predicate is checked during the layer-0 traversal, which keeps
+    /// expanding past rejected nodes until it holds `ef` (at least `k`)
+    /// matches, runs out of graph, or reaches `max_filtered_visits`.
+    pub fn search_with_predicate(
+        &self,
+        query: &[f32],
+        k: usize,
+        ef: Option<usize>,
+        storage: &impl VectorStorageTrait,
+        predicate: &dyn Fn(InternalId) -> bool,
+    ) -> Result<(Vec<(InternalId, f32)>, SearchStats)> {
+        self.search_inner(query, k, ef, storage, Some(predicate), None)
+    }
+
+    fn search_inner(
+        &self,
+        query: &[f32],
+        k: usize,
+        ef: Option<usize>,
+        storage: &impl VectorStorageTrait,
+        predicate: Option<&dyn Fn(InternalId) -> bool>,
+        route: Option<&dyn Fn(InternalId) -> bool>,
+    ) -> Result<(Vec<(InternalId, f32)>, SearchStats)> {
+        let nodes = self.nodes.read();
+        let top = (
+            *self.entry_point.read(),
+            *self.max_layer.read(),
+            nodes.len(),
+        );
         let ctx = SearchContext {
             query,
-            ef,
+            ef: ef.unwrap_or(self.config.ef_search).max(k),
             layer: 0,
-            filter,
-            filter_bitmap,
+            predicate,
+            route,
         };
-        let candidates = self.search_layer(ctx, current_ep, &nodes, storage)?;
+        self.search_graph(nodes.as_slice(), top, ctx, k, storage)
+    }
+
+    /// Descend `graph` from the entry point in `top` and search layer 0 with
+    /// `ctx`, returning the best `k`
+    ///
+    /// `top` is `(entry_point, max_layer, node_count)`.
+    fn search_graph<G: GraphLayout + ?Sized>(
+        &self,
+        graph: &G,
+        (entry_point, max_layer, node_count): (Option<InternalId>, usize, usize),
+        ctx: SearchContext,
+        k: usize,
+        storage: &impl VectorStorageTrait,
+    ) -> Result<(Vec<(InternalId, f32)>, SearchStats)> {
+        let ep = match entry_point {
+            Some(ep) => ep,
+            None if node_count == 0 => return Err(Error::EmptyIndex),
+            // Every node has been removed
+            None => return Ok((Vec::new(), SearchStats::default())),
+        };
+
+        // Traverse from top layer to layer 1
+        let mut current_ep = ep;
+        for layer in (1..=max_layer).rev() {
+            current_ep = self.search_layer_single(ctx.query, current_ep, layer, graph, storage)?;
+        }
+
+        // Search in layer 0
+        let mut stats = SearchStats::default();
+        let candidates =
+            self.search_layer_with_stats(ctx, current_ep, graph, storage, &mut stats)?;
 
         // Return top k
-        Ok(candidates
+        let results = candidates
             .into_iter()
             .take(k)
             .map(|c| (c.id, c.distance))
-            .collect())
+            .collect();
+        Ok((results, stats))
+    }
+
+    /// Like `search_with_ef`, walking `graph`, mapped from disk, instead of
+    /// this index's own nodes
+    #[cfg(feature = "persistence")]
+    pub fn search_mapped(
+        &self,
+        graph: &crate::mmap_graph::MmapGraph,
+        query: &[f32],
+        k: usize,
+        ef: Option<usize>,
+        storage: &impl VectorStorageTrait,
+    ) -> Result<(Vec<(InternalId, f32)>, SearchStats)> {
+        let top = (graph.entry_point(), graph.max_layer(), graph.len());
+        let ctx = SearchContext {
+            query,
+            ef: ef.unwrap_or(self.config.ef_search).max(k),
+            layer: 0,
+            predicate: None,
+            route: None,
+        };
+        self.search_graph(graph, top, ctx, k, storage)
+    }
+
+    /// Write the graph to `path` in the memory-mapped layout, with layer-0
+    /// rows of `m0` neighbors and upper rows of `m` (see `MmapGraph`)
+    #[cfg(feature = "persistence")]
+    pub fn save_mapped(&self, path: &std::path::Path) -> Result<()> {
+        self.flush_sweep();
+        let _writer = self.writer.read();
+        let nodes = self.nodes.read();
+        crate::mmap_graph::MmapGraph::write(
+            path,
+            &nodes,
+            *self.entry_point.read(),
+            *self.max_layer.read(),
+            (self.config.m0, self.config.m),
+        )
+    }
+
+    /// Get a node's neighbor lists, indexed by layer (layer 0 first)
+    ///
+    /// Returns an empty list if the node is not in the graph. The node's max
+    /// layer is `neighbors.len() - 1`.
+    pub fn neighbors(&self, internal_id: InternalId) -> Vec<Vec<InternalId>> {
+        let nodes = self.nodes.read();
+        let Some(node) = nodes.get(internal_id.as_usize()) else {
+            return Vec::new();
+        };
+        let unswept = self.unswept.read();
+        if unswept.is_empty() {
+            return node.neighbors.clone();
+        }
+        node.neighbors
+            .iter()
+            .map(|layer| {
+                layer
+                    .iter()
+                    .copied()
+                    .filter(|id| !unswept.contains(id.as_u32()))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Every directed edge of the graph as `(from, to, layer)`
+    ///
+    /// Nodes are visited in internal ID order and the graph lock is only
+    /// held while one node's lists are copied, so a long-running consumer
+    /// does not stall inserts; edges added during the walk may be missed.
+    pub fn export_edges(&self) -> impl Iterator<Item = (InternalId, InternalId, usize)> + '_ {
+        (0..self.len()).flat_map(move |idx| {
+            let from = InternalId::from(idx);
+            self.neighbors(from)
+                .into_iter()
+                .enumerate()
+                .flat_map(move |(layer, neighbors)| {
+                    neighbors.into_iter().map(move |to| (from, to, layer))
+                })
+        })
     }
 
     /// Get the number of nodes in the index
@@ -810,6 +1940,7 @@ impl HnswIndex {
 
     /// Get the current state of the index for serialization
     pub fn get_state(&self) -> HnswState {
+        self.flush_sweep();
         let nodes = self.nodes.read();
         let entry_point = self.entry_point.read();
         let max_layer = self.max_layer.read();
@@ -823,15 +1954,78 @@ impl HnswIndex {
 
     /// Load the index state from serialized data
     pub fn load_state(&self, state: HnswState) {
+        let _writer = self.writer.write();
         let mut self_nodes = self.nodes.write();
         let mut self_entry_point = self.entry_point.write();
         let mut self_max_layer = self.max_layer.write();
+        let mut self_removed = self.removed.write();
 
         *self_nodes = state.nodes;
+        self_removed.clear();
+        self.unswept.write().clear();
         *self_entry_point = state.entry_point;
         *self_max_layer = state.max_layer;
     }
 
+    /// Drop every node, leaving an empty graph with the same configuration
+    pub fn clear(&self) {
+        self.load_state(HnswState {
+            nodes: Vec::new(),
+            entry_point: None,
+            max_layer: 0,
+        });
+    }
+
+    /// Search for up to `sample` stored vectors, picked with `seed`, and
+    /// report how many return themselves in their top `k`
+    ///
+    /// A healthy graph scores close to 1.0. A drop means parts of the graph
+    /// have become unreachable, through corruption or heavy deletion.
+    pub fn self_recall(
+        &self,
+        storage: &impl VectorStorageTrait,
+        sample: usize,
+        k: usize,
+        seed: u64,
+    ) -> SelfRecall {
+        use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+        let mut ids: Vec<InternalId> = {
+            let nodes = self.nodes.read();
+            let removed = self.removed.read();
+            nodes
+                .iter()
+                .map(|node| node.id)
+                .filter(|id| !removed.contains(id.as_u32()) && !storage.is_deleted(*id))
+                .collect()
+        };
+        let (ids, _) = ids.partial_shuffle(&mut StdRng::seed_from_u64(seed), sample);
+
+        let mut sampled = 0;
+        let mut found = 0;
+        for &id in ids.iter() {
+            let Some(vector) = storage.get_vector_data(id) else {
+                continue;
+            };
+            sampled += 1;
+            if self
+                .search(&vector, k, storage, None)
+                .is_ok_and(|results| results.iter().any(|&(hit, _)| hit == id))
+            {
+                found += 1;
+            }
+        }
+
+        SelfRecall {
+            sampled,
+            recall: if sampled == 0 {
+                1.0
+            } else {
+                found as f32 / sampled as f32
+            },
+        }
+    }
+
     /// Get approximate memory usage in bytes
     pub fn memory_usage(&self) -> usize {
         let nodes = self.nodes.read();
@@ -854,6 +2048,301 @@ mod tests {
         VectorStorage::new(4)
     }
 
+    #[test]
+    fn test_recommend_orders_targets() {
+        for (count, dim) in [(1_000, 32), (50_000, 384), (5_000_000, 1536)] {
+            let configs = [
+                TuningTarget::Speed,
+                TuningTarget::Balanced,
+                TuningTarget::Accuracy,
+            ]
+            .map(|target| HnswConfig::recommend(count, dim, target));
+            for config in &configs {
+                config.validate().unwrap();
+                assert_eq!(config.m0, config.m * 2);
+            }
+            for pair in configs.windows(2) {
+                let (cheaper, better) = (&pair[0], &pair[1]);
+                assert!(cheaper.m <= better.m, "{:?}", pair);
+                assert!(cheaper.ef_construction < better.ef_construction);
+                assert!(cheaper.ef_search < better.ef_search);
+            }
+        }
+
+        // More dimensions and more vectors never ask for a sparser graph
+        let balanced = |count, dim| HnswConfig::recommend(count, dim, TuningTarget::Balanced);
+        assert!(balanced(100_000, 64).m < balanced(100_000, 1024).m);
+        assert!(balanced(1_000, 1024).m < balanced(100_000, 1024).m);
+        assert!(balanced(100_000, 128).ef_search < balanced(2_000_000, 128).ef_search);
+    }
+
+    #[test]
+    fn test_seeded_rng_builds_reproducible_graphs() {
+        // Neither build touches thread_rng, as on targets without OS entropy
+        let build = |rng| {
+            let storage = VectorStorage::new(4);
+            let index = HnswIndex::new(
+                HnswConfig {
+                    rng,
+                    ..HnswConfig::default()
+                },
+                DistanceMetric::Euclidean,
+            );
+            for i in 0..300 {
+                let v: Vec<f32> = (0..4).map(|d| ((i * 7 + d * 13) as f32).sin()).collect();
+                let id = storage.insert(i.to_string().into(), &v, None).unwrap();
+                index.insert(id, &v, &storage).unwrap();
+            }
+            let results = index
+                .search(&[0.1, 0.2, 0.3, 0.4], 5, &storage, None)
+                .unwrap();
+            assert_eq!(results.len(), 5);
+            serde_json::to_string(&index.get_state()).unwrap()
+        };
+
+        let graph = build(RngSource::Xorshift(42));
+        assert_eq!(graph, build(RngSource::Xorshift(42)));
+        assert_ne!(graph, build(RngSource::Xorshift(43)));
+        assert_eq!(build(RngSource::default()), build(RngSource::default()));
+    }
+
+    #[test]
+    fn test_reserve_does_not_change_graph_or_results() {
+        let build = |reserve: bool| {
+            let storage = VectorStorage::new(4);
+            let index = HnswIndex::new(HnswConfig::default(), DistanceMetric::Euclidean);
+            let vectors: Vec<Vec<f32>> = (0..500)
+                .map(|i| (0..4).map(|d| ((i * 5 + d * 11) as f32).cos()).collect())
+                .collect();
+            let items: Vec<(InternalId, &[f32])> = vectors
+                .iter()
+                .enumerate()
+                .map(|(i, v)| {
+                    let id = storage.insert(i.to_string().into(), v, None).unwrap();
+                    (id, v.as_slice())
+                })
+                .collect();
+            if reserve {
+                index.reserve(items.len());
+                assert!(index.nodes.read().capacity() >= items.len());
+            }
+            index.insert_batch(&items, &storage).unwrap();
+            let results = index
+                .search(&[0.3, -0.2, 0.5, 0.1], 10, &storage, None)
+                .unwrap();
+            (serde_json::to_string(&index.get_state()).unwrap(), results)
+        };
+
+        let (graph, results) = build(false);
+        let (reserved_graph, reserved_results) = build(true);
+        assert_eq!(graph, reserved_graph);
+        assert_eq!(results.len(), 10);
+        assert_eq!(results, reserved_results);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_parallel_build_matches_serial_recall() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        const DIM: usize = 16;
+        let mut rng = StdRng::seed_from_u64(5);
+        let vectors: Vec<Vec<f32>> = (0..2500)
+            .map(|_| (0..DIM).map(|_| rng.gen_range(-1.0..1.0)).collect())
+            .collect();
+        let queries: Vec<Vec<f32>> = (0..100)
+            .map(|_| (0..DIM).map(|_| rng.gen_range(-1.0..1.0)).collect())
+            .collect();
+
+        let recall = |parallel_build| {
+            let storage = VectorStorage::new(DIM);
+            let index = HnswIndex::new(
+                HnswConfig {
+                    m: 8,
+                    m0: 16,
+                    ef_construction: 64,
+                    ef_search: 10,
+                    parallel_build,
+                    ..HnswConfig::default()
+                },
+                DistanceMetric::Euclidean,
+            );
+            let ids: Vec<InternalId> = vectors
+                .iter()
+                .enumerate()
+                .map(|(i, v)| storage.insert(i.to_string().into(), v, None).unwrap())
+                .collect();
+            let items: Vec<(InternalId, &[f32])> = ids
+                .iter()
+                .zip(&vectors)
+                .map(|(&id, v)| (id, v.as_slice()))
+                .collect();
+            if parallel_build {
+                // Several threads even on a single-core machine, so links race
+                rayon::ThreadPoolBuilder::new()
+                    .num_threads(4)
+                    .build()
+                    .unwrap()
+                    .install(|| index.insert_batch(&items, &storage))
+                    .unwrap();
+            } else {
+                for &(id, v) in &items {
+                    index.insert(id, v, &storage).unwrap();
+                }
+            }
+            assert_eq!(index.len(), vectors.len());
+            assert!(index.audit_degrees().is_within_bounds());
+
+            let mut hits = 0;
+            for query in &queries {
+                let mut exact: Vec<(usize, f32)> = vectors
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| (i, DistanceMetric::Euclidean.distance(query, v)))
+                    .collect();
+                exact.sort_by(|a, b| a.1.total_cmp(&b.1));
+                let exact: HashSet<u32> = exact.iter().take(10).map(|(i, _)| *i as u32).collect();
+                hits += index
+                    .search(query, 10, &storage, None)
+                    .unwrap()
+                    .iter()
+                    .filter(|(id, _)| exact.contains(&id.as_u32()))
+                    .count();
+            }
+            hits as f32 / (queries.len() * 10) as f32
+        };
+
+        let (serial, parallel) = (recall(false), recall(true));
+        assert!(
+            parallel >= serial - 0.02,
+            "parallel {} vs serial {}",
+            parallel,
+            serial
+        );
+    }
+
+    #[test]
+    fn test_heuristic_select_beats_simple_on_clusters() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        const DIM: usize = 8;
+        let mut rng = StdRng::seed_from_u64(11);
+        let centers: Vec<Vec<f32>> = (0..40)
+            .map(|_| (0..DIM).map(|_| rng.gen_range(-10.0..10.0)).collect())
+            .collect();
+        let mut near = |center: &[f32]| -> Vec<f32> {
+            center
+                .iter()
+                .map(|c| c + rng.gen_range(-0.5..0.5))
+                .collect()
+        };
+        let vectors: Vec<Vec<f32>> = (0..2000).map(|i| near(&centers[i % 40])).collect();
+        let queries: Vec<Vec<f32>> = (0..100).map(|i| near(&centers[(i * 7) % 40])).collect();
+
+        let recall = |simple_select| {
+            let storage = VectorStorage::new(DIM);
+            let index = HnswIndex::new(
+                HnswConfig {
+                    m: 8,
+                    m0: 16,
+                    ef_construction: 32,
+                    ef_search: 64,
+                    simple_select,
+                    ..HnswConfig::default()
+                },
+                DistanceMetric::Euclidean,
+            );
+            for (i, v) in vectors.iter().enumerate() {
+                let id = storage.insert(i.to_string().into(), v, None).unwrap();
+                index.insert(id, v, &storage).unwrap();
+            }
+
+            let mut hits = 0;
+            for query in &queries {
+                let mut exact: Vec<(usize, f32)> = vectors
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| (i, DistanceMetric::Euclidean.distance(query, v)))
+                    .collect();
+                exact.sort_by(|a, b| a.1.total_cmp(&b.1));
+                let exact: HashSet<u32> = exact.iter().take(10).map(|(i, _)| *i as u32).collect();
+                hits += index
+                    .search(query, 10, &storage, None)
+                    .unwrap()
+                    .iter()
+                    .filter(|(id, _)| exact.contains(&id.as_u32()))
+                    .count();
+            }
+            hits as f32 / (queries.len() * 10) as f32
+        };
+
+        let (heuristic, simple) = (recall(false), recall(true));
+        assert!(
+            heuristic >= 0.9,
+            "heuristic {} vs simple {}",
+            heuristic,
+            simple
+        );
+        assert!(
+            heuristic > simple + 0.2,
+            "heuristic {} vs simple {}",
+            heuristic,
+            simple
+        );
+    }
+
+    #[test]
+    fn test_self_recall_drops_when_graph_is_damaged() {
+        let storage = VectorStorage::new(4);
+        let index = HnswIndex::new(HnswConfig::default(), DistanceMetric::Euclidean);
+        for i in 0..500 {
+            let v: Vec<f32> = (0..4).map(|d| ((i * 5 + d * 11) as f32).cos()).collect();
+            let id = storage.insert(i.to_string().into(), &v, None).unwrap();
+            index.insert(id, &v, &storage).unwrap();
+        }
+
+        let healthy = index.self_recall(&storage, 100, 10, 1);
+        assert_eq!(healthy.sampled, 100);
+        assert!(healthy.recall >= 0.99, "{:?}", healthy);
+
+        // Cut every other node out of the base layer
+        for node in index.nodes.write().iter_mut().step_by(2) {
+            node.neighbors[0].clear();
+        }
+        let damaged = index.self_recall(&storage, 100, 10, 1);
+        assert!(damaged.recall < 0.9, "{:?}", damaged);
+    }
+
+    #[test]
+    fn test_validate_rejects_unbuildable_configs() {
+        HnswConfig::default().validate().unwrap();
+        let invalid = [
+            HnswConfig {
+                m: 1,
+                ..Default::default()
+            },
+            HnswConfig {
+                m0: 8,
+                ..Default::default()
+            },
+            HnswConfig {
+                ef_construction: 4,
+                ..Default::default()
+            },
+            HnswConfig {
+                ef_search: 0,
+                ..Default::default()
+            },
+        ];
+        for config in invalid {
+            assert!(
+                matches!(config.validate(), Err(Error::InvalidConfig(_))),
+                "{:?}",
+                config
+            );
+        }
+    }
+
     #[test]
     fn test_single_insert() {
         let config = HnswConfig::default();
@@ -918,4 +2407,351 @@ mod tests {
         let first_id = storage.get_external_id(results[0].0).unwrap();
         assert_eq!(first_id.as_str(), "vec0");
     }
+
+    #[test]
+    fn test_remove_after_update_drops_stale_edges() {
+        let config = HnswConfig {
+            eager_edge_cleanup: true,
+            ..Default::default()
+        };
+        let index = HnswIndex::new(config, DistanceMetric::Euclidean);
+        let storage = create_test_storage();
+
+        for i in 0..50 {
+            let v = [(i as f32).sin(), (i as f32).cos(), i as f32 * 0.01, 1.0];
+            let id = storage
+                .insert(format!("vec{}", i).into(), &v, None)
+                .unwrap();
+            index.insert(id, &v, &storage).unwrap();
+        }
+
+        // Update vec7: the old internal node becomes stale
+        let old = storage.get_internal_id(&"vec7".into()).unwrap();
+        let new_vector = [0.6, -0.8, 0.3, 1.0];
+        let new = storage.upsert("vec7".into(), &new_vector, None).unwrap();
+        index.insert(new, &new_vector, &storage).unwrap();
+        assert!(index.verify(&storage).is_err());
+
+        index.remove(old, &storage);
+        index.verify(&storage).unwrap();
+
+        // The updated vector is still reachable at its new position
+        let results = index.search(&new_vector, 1, &storage, None).unwrap();
+        assert_eq!(results[0].0, new);
+    }
+
+    #[test]
+    fn test_neighbors_matches_graph() {
+        let index = HnswIndex::new(HnswConfig::default(), DistanceMetric::Euclidean);
+        let storage = create_test_storage();
+
+        for i in 0..20 {
+            let v = [i as f32, (i % 3) as f32, 0.0, 1.0];
+            let id = storage
+                .insert(format!("vec{}", i).into(), &v, None)
+                .unwrap();
+            index.insert(id, &v, &storage).unwrap();
+        }
+
+        let state = index.get_state();
+        for node in &state.nodes {
+            let neighbors = index.neighbors(node.id);
+            assert_eq!(neighbors.len(), node.max_layer + 1);
+            assert_eq!(neighbors, node.neighbors);
+        }
+        assert!(index.neighbors(InternalId::from(100)).is_empty());
+    }
+
+    #[test]
+    fn test_export_edges_matches_neighbor_lists() {
+        let config = HnswConfig {
+            rng: RngSource::Xorshift(3),
+            ..Default::default()
+        };
+        let index = HnswIndex::new(config, DistanceMetric::Euclidean);
+        let storage = create_test_storage();
+        for i in 0..40 {
+            let v = [i as f32, (i % 5) as f32, (i % 2) as f32, 1.0];
+            let id = storage
+                .insert(format!("vec{}", i).into(), &v, None)
+                .unwrap();
+            index.insert(id, &v, &storage).unwrap();
+        }
+
+        let mut expected = Vec::new();
+        for node in &index.get_state().nodes {
+            for (layer, neighbors) in node.neighbors.iter().enumerate() {
+                expected.extend(neighbors.iter().map(|&to| (node.id, to, layer)));
+            }
+        }
+        let exported: Vec<_> = index.export_edges().collect();
+        assert!(exported.iter().any(|&(_, _, layer)| layer > 0));
+        assert_eq!(exported, expected);
+    }
+
+    #[test]
+    fn test_auto_repair_degrees() {
+        let config = HnswConfig {
+            m: 4,
+            m0: 8,
+            ..Default::default()
+        };
+        let index = HnswIndex::new(config, DistanceMetric::Euclidean);
+        let storage = create_test_storage();
+
+        for i in 0..60 {
+            let v = [(i as f32).sin(), (i as f32).cos(), i as f32 * 0.01, 1.0];
+            let id = storage
+                .insert(format!("vec{}", i).into(), &v, None)
+                .unwrap();
+            index.insert(id, &v, &storage).unwrap();
+        }
+        assert!(index.audit_degrees().is_within_bounds());
+
+        let reachable_on_layer0 = |index: &HnswIndex| {
+            let nodes = index.nodes.read();
+            let start = index.entry_point.read().unwrap();
+            let mut seen = std::collections::HashSet::from([start]);
+            let mut stack = vec![start];
+            while let Some(id) = stack.pop() {
+                for &n in &nodes[id.as_usize()].neighbors[0] {
+                    if seen.insert(n) {
+                        stack.push(n);
+                    }
+                }
+            }
+            seen.len()
+        };
+        let reachable_before = reachable_on_layer0(&index);
+
+        // Over-connect node 5 to every other node on layer 0
+        let hub = InternalId::from(5);
+        {
+            let mut nodes = index.nodes.write();
+            let extra: Vec<InternalId> = (0..60)
+                .map(InternalId::from)
+                .filter(|&id| id != hub && !nodes[5].neighbors[0].contains(&id))
+                .collect();
+            nodes[5].neighbors[0].extend(extra);
+        }
+
+        let report = index.audit_degrees();
+        assert_eq!(report.violations.len(), 1);
+        assert_eq!(report.violations[0].node, hub);
+        assert_eq!(report.violations[0].degree, 59);
+        assert_eq!(report.max_observed_degree, 59);
+
+        let repaired = index.auto_repair_degrees(&storage);
+        assert_eq!(repaired, report);
+
+        let after = index.audit_degrees();
+        assert!(after.is_within_bounds());
+        assert_eq!(index.neighbors(hub)[0].len(), 8);
+        index.verify(&storage).unwrap();
+        assert_eq!(reachable_on_layer0(&index), reachable_before);
+    }
+
+    #[test]
+    fn test_remove_keeps_recall_on_survivors() {
+        use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+        let index = HnswIndex::new(HnswConfig::default(), DistanceMetric::Euclidean);
+        let storage = VectorStorage::new(8);
+        let mut rng = StdRng::seed_from_u64(7);
+
+        let mut ids = Vec::new();
+        for i in 0..500 {
+            let v: Vec<f32> = (0..8).map(|_| rng.gen_range(-1.0..1.0)).collect();
+            let id = storage
+                .insert(format!("vec{}", i).into(), &v, None)
+                .unwrap();
+            index.insert(id, &v, &storage).unwrap();
+            ids.push(id);
+        }
+
+        ids.shuffle(&mut rng);
+        let (removed, survivors) = ids.split_at(250);
+        for &id in removed {
+            let external = storage.get_external_id(id).unwrap();
+            storage.delete(&external).unwrap();
+            index.remove(id, &storage);
+        }
+        index.verify(&storage).unwrap();
+
+        let k = 10;
+        let mut hits = 0;
+        for _ in 0..50 {
+            let query: Vec<f32> = (0..8).map(|_| rng.gen_range(-1.0..1.0)).collect();
+            let mut exact: Vec<(InternalId, f32)> = survivors
+                .iter()
+                .map(|&id| {
+                    let d = storage.distance(id, &query, DistanceMetric::Euclidean);
+                    (id, d.unwrap())
+                })
+                .collect();
+            exact.sort_by(|a, b| a.1.total_cmp(&b.1));
+            let exact: HashSet<InternalId> = exact.iter().take(k).map(|(id, _)| *id).collect();
+
+            let results = index.search(&query, k, &storage, None).unwrap();
+            assert_eq!(results.len(), k);
+            for (id, _) in &results {
+                assert!(!removed.contains(id));
+            }
+            hits += results.iter().filter(|(id, _)| exact.contains(id)).count();
+        }
+        let recall = hits as f32 / (50 * k) as f32;
+        assert!(recall >= 0.9, "recall {}", recall);
+    }
+
+    #[test]
+    fn test_remove_defers_sweep_of_one_way_edges() {
+        let index = HnswIndex::new(HnswConfig::default(), DistanceMetric::Euclidean);
+        let storage = create_test_storage();
+        for i in 0..200 {
+            let v = [(i as f32).sin(), (i as f32).cos(), i as f32 * 0.01, 1.0];
+            let id = storage
+                .insert(format!("vec{}", i).into(), &v, None)
+                .unwrap();
+            index.insert(id, &v, &storage).unwrap();
+        }
+
+        let removed: Vec<InternalId> = (0..3).map(|i| InternalId::from(i * 50 + 1)).collect();
+        for &id in &removed {
+            let external = storage.get_external_id(id).unwrap();
+            storage.delete(&external).unwrap();
+            index.remove(id, &storage);
+        }
+        assert_eq!(index.unswept.read().len(), 3);
+        index.verify(&storage).unwrap();
+        let links_to_removed = |neighbors: &[Vec<InternalId>]| {
+            neighbors.iter().flatten().any(|id| removed.contains(id))
+        };
+        for idx in 0..200 {
+            assert!(!links_to_removed(&index.neighbors(InternalId::from(idx))));
+        }
+
+        // Serializing the graph sweeps the pending edges for good
+        let state = index.get_state();
+        assert!(index.unswept.read().is_empty());
+        assert!(!state.nodes.iter().any(|n| links_to_removed(&n.neighbors)));
+    }
+
+    #[test]
+    fn test_remove_entry_point_and_every_node() {
+        let index = HnswIndex::new(HnswConfig::default(), DistanceMetric::Euclidean);
+        let storage = create_test_storage();
+
+        let mut ids = Vec::new();
+        for i in 0..30 {
+            let v = [i as f32, (i % 5) as f32, 0.0, 1.0];
+            let id = storage
+                .insert(format!("vec{}", i).into(), &v, None)
+                .unwrap();
+            index.insert(id, &v, &storage).unwrap();
+            ids.push(id);
+        }
+
+        // Removed nodes are skipped even while storage still holds them
+        let entry = index.entry_point.read().unwrap();
+        index.remove(entry, &storage);
+        assert_ne!(*index.entry_point.read(), Some(entry));
+        for id in &ids {
+            let v = storage.get_vector_data(*id).unwrap();
+            let results = index.search(&v, 30, &storage, None).unwrap();
+            assert_eq!(results.len(), 29);
+            assert!(results.iter().all(|(hit, _)| *hit != entry));
+        }
+
+        for &id in &ids {
+            index.remove(id, &storage);
+        }
+        let results = index
+            .search(&[1.0, 1.0, 0.0, 1.0], 5, &storage, None)
+            .unwrap();
+        assert!(results.is_empty());
+
+        // The next insert starts a fresh graph
+        let v = [3.0, 3.0, 0.0, 1.0];
+        let id = storage.insert("fresh".into(), &v, None).unwrap();
+        index.insert(id, &v, &storage).unwrap();
+        let results = index.search(&v, 5, &storage, None).unwrap();
+        assert_eq!(results, vec![(id, 0.0)]);
+    }
+
+    #[test]
+    fn test_predicate_search_fills_k_under_restrictive_filter() {
+        use std::cell::Cell;
+
+        // Points along a line, so the nearest match to the query sits at the
+        // front and every other match is behind non-matching neighbors
+        let point = |i: usize| {
+            let mut v = vec![0.0; 8];
+            v[0] = i as f32;
+            v
+        };
+        let build = |max_filtered_visits| {
+            let config = HnswConfig {
+                max_filtered_visits,
+                ..Default::default()
+            };
+            let index = HnswIndex::new(config, DistanceMetric::Euclidean);
+            let storage = VectorStorage::new(8);
+            for i in 0..2000 {
+                let v = point(i);
+                let id = storage
+                    .insert(format!("vec{}", i).into(), &v, None)
+                    .unwrap();
+                index.insert(id, &v, &storage).unwrap();
+            }
+            (index, storage)
+        };
+        let (index, storage) = build(0);
+        let query = point(0);
+        let k = 10;
+
+        // One node in a hundred matches; stopping at the usual distance
+        // bound would return only the first
+        let rare = |id: InternalId| id.as_u32().is_multiple_of(100);
+        let (results, _) = index
+            .search_with_predicate(&query, k, Some(k), &storage, &rare)
+            .unwrap();
+        assert_eq!(results.len(), k);
+        assert!(results.iter().all(|(id, _)| rare(*id)));
+
+        let mut exact: Vec<(InternalId, f32)> = (0..2000)
+            .map(InternalId::from)
+            .filter(|&id| rare(id))
+            .map(|id| {
+                let d = storage.distance(id, &query, DistanceMetric::Euclidean);
+                (id, d.unwrap())
+            })
+            .collect();
+        exact.sort_by(|a, b| a.1.total_cmp(&b.1));
+        let exact: HashSet<InternalId> = exact.iter().take(k).map(|(id, _)| *id).collect();
+        let hits = results.iter().filter(|(id, _)| exact.contains(id)).count();
+        assert!(hits >= 8, "{} of {} exact matches", hits, k);
+
+        // A predicate that matches nothing walks the graph unless capped
+        let calls = Cell::new(0);
+        let none = |_: InternalId| {
+            calls.set(calls.get() + 1);
+            false
+        };
+        let (results, stats) = index
+            .search_with_predicate(&query, k, None, &storage, &none)
+            .unwrap();
+        assert!(results.is_empty());
+        assert!(stats.exhausted);
+        assert!(calls.get() > 1900, "{} nodes visited", calls.get());
+
+        let (capped, storage) = build(200);
+        calls.set(0);
+        let (results, stats) = capped
+            .search_with_predicate(&query, k, None, &storage, &none)
+            .unwrap();
+        assert!(results.is_empty());
+        assert!(!stats.exhausted);
+        let m0 = capped.config.m0;
+        assert!(calls.get() <= 200 + m0, "{} nodes visited", calls.get());
+    }
 }
@@ -0,0 +1,49 @@
+//! Callbacks run after writes to a `VectorDb`
+//!
+//! Embedders register these to keep derived state, such as an external
+//! cache, in step with the collection. Hooks run on the writing thread once
+//! the write has succeeded and every internal lock has been released, so a
+//! hook may read from the same `VectorDb`; a slow hook delays the write call
+//! that triggered it.
+
+use crate::types::VectorId;
+use serde_json::Value;
+
+/// Called with the ID, stored vector and metadata of each vector written
+pub type InsertHook = Box<dyn Fn(&VectorId, &[f32], &Option<Value>) + Send + Sync>;
+
+/// Called with the primary ID of each vector deleted
+pub type DeleteHook = Box<dyn Fn(&VectorId) + Send + Sync>;
+
+#[derive(Default)]
+pub(crate) struct Hooks {
+    on_insert: Option<InsertHook>,
+    on_delete: Option<DeleteHook>,
+}
+
+impl Hooks {
+    pub(crate) fn set_insert(&mut self, hook: InsertHook) {
+        self.on_insert = Some(hook);
+    }
+
+    pub(crate) fn set_delete(&mut self, hook: DeleteHook) {
+        self.on_delete = Some(hook);
+    }
+
+    /// Whether writes need to keep a copy of their metadata for `inserted`
+    pub(crate) fn wants_inserts(&self) -> bool {
+        self.on_insert.is_some()
+    }
+
+    pub(crate) fn inserted(&self, id: &VectorId, vector: &[f32], metadata: &Option<Value>) {
+        if let Some(hook) = &self.on_insert {
+            hook(id, vector, metadata);
+        }
+    }
+
+    pub(crate) fn deleted(&self, id: &VectorId) {
+        if let Some(hook) = &self.on_delete {
+            hook(id);
+        }
+    }
+}
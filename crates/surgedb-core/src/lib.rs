@@ -49,31 +49,51 @@
 //! ```
 
 // Core modules (always available)
+pub mod accuracy;
 pub mod bitmap_index;
+pub mod cache;
+mod compaction;
 pub mod distance;
 pub mod error;
+pub mod facet;
 pub mod filter;
+pub mod group;
 pub mod hnsw;
+pub mod hooks;
+pub mod metadata;
 pub mod multi_vector;
 pub mod pq;
+pub mod projection;
 pub mod quantization;
 pub mod quantized_storage;
+pub mod routing;
+pub mod score;
+pub mod self_test;
+pub mod shard;
+pub mod snapshot;
 pub mod sparse;
 pub mod storage;
 pub mod sync;
+pub mod transaction;
 pub mod types;
 
 // Persistence modules (native only, requires filesystem)
 #[cfg(feature = "persistence")]
 pub mod diskann;
 #[cfg(feature = "persistence")]
+mod export;
+#[cfg(feature = "persistence")]
 pub mod mmap_db;
 #[cfg(feature = "persistence")]
+pub mod mmap_graph;
+#[cfg(feature = "persistence")]
 pub mod mmap_storage;
 #[cfg(feature = "persistence")]
 pub mod persistent;
 #[cfg(feature = "persistence")]
-pub mod snapshot;
+pub mod shipping;
+#[cfg(feature = "persistence")]
+pub mod timeseries;
 #[cfg(feature = "persistence")]
 pub mod wal;
 
@@ -81,28 +101,68 @@ pub mod wal;
 pub mod db;
 
 // Re-exports - Core (always available)
+pub use accuracy::AccuracyReport;
+pub use cache::{SimilarCacheConfig, SimilarCacheStats};
 pub use distance::DistanceMetric;
 pub use error::{Error, Result};
-pub use hnsw::{HnswConfig, HnswIndex};
-pub use quantization::{BinaryQuantizer, QuantizationType, SQ8Quantizer};
+pub use facet::{FacetValue, Facets};
+pub use group::MissingGroup;
+pub use hnsw::{
+    DegreeReport, HnswConfig, HnswIndex, RngSource, SearchStats, SelfRecall, TuningTarget,
+};
+pub use hooks::{DeleteHook, InsertHook};
+pub use metadata::{ColumnKind, ColumnSpec, MetadataFormat};
+pub use projection::{ProjectionConfig, RandomProjection};
+pub use quantization::{BinaryQuantizer, QuantizationType, SQ8Metadata, SQ8Quantizer};
 pub use quantized_storage::QuantizedStorage;
-pub use storage::{VectorStorage, VectorStorageTrait};
-pub use types::{Vector, VectorId};
+pub use routing::{ProbedList, RoutingConfig, RoutingDebug};
+pub use score::ScoreTransform;
+pub use self_test::SelfTestReport;
+pub use shard::{ShardBackend, ShardedClient, ShardedSearchResult};
+pub use snapshot::Snapshot;
+pub use storage::{SlotGaps, VectorStorage, VectorStorageTrait};
+pub use transaction::Op;
+pub use types::{
+    BatchReport, DimensionPolicy, DuplicatePolicy, InsertMode, ListPage, MetadataUpdate, SearchHit,
+    Vector, VectorId, QUERY_NORM_TOLERANCE, UNIT_NORM_EPSILON,
+};
 
 // Re-exports - Persistence (native only)
 #[cfg(feature = "persistence")]
 pub use mmap_db::{MmapConfig, MmapVectorDb};
 #[cfg(feature = "persistence")]
+pub use mmap_graph::MmapGraph;
+#[cfg(feature = "persistence")]
 pub use mmap_storage::MmapStorage;
 #[cfg(feature = "persistence")]
 pub use persistent::{PersistentConfig, PersistentVectorDb};
 #[cfg(feature = "persistence")]
-pub use snapshot::{Snapshot, SnapshotManager};
+pub use shipping::{LocalDestination, SnapshotDestination, SnapshotShipper};
+#[cfg(feature = "persistence")]
+pub use snapshot::SnapshotManager;
+#[cfg(feature = "persistence")]
+pub use timeseries::{IndexStatsSample, StatsCollector, StatsTimeSeries};
 #[cfg(feature = "persistence")]
 pub use wal::{Wal, WalEntry};
 
 // Re-exports - Database (conditional based on features)
-pub use db::{Database, DatabaseStats};
+pub use db::{
+    CollectionStats, Database, DatabaseStats, ExportPages, ExportRecord, GraphEdges, SearchOptions,
+    SearchOutcome,
+};
+
+/// Cargo features this build of the crate was compiled with
+pub fn enabled_features() -> Vec<&'static str> {
+    [
+        ("simd", cfg!(feature = "simd")),
+        ("persistence", cfg!(feature = "persistence")),
+        ("parallel", cfg!(feature = "parallel")),
+        ("wasm", cfg!(feature = "wasm")),
+    ]
+    .into_iter()
+    .filter_map(|(name, enabled)| enabled.then_some(name))
+    .collect()
+}
 
 /// Main database configuration (unquantized)
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -117,6 +177,76 @@ pub struct Config {
     pub max_vectors: usize,
     /// Quantization type (None by default)
     pub quantization: QuantizationType,
+    /// Collection-level metadata merged into every returned vector's metadata.
+    /// Per-vector keys override these defaults; the defaults are not stored per
+    /// vector and are not visible to filters.
+    #[serde(default)]
+    pub default_metadata: Option<serde_json::Value>,
+    /// Maximum serialized size of a vector's metadata in bytes (0 = unlimited)
+    #[serde(default)]
+    pub max_metadata_bytes: usize,
+    /// Reduce inputs to a lower dimensionality with a seeded random projection.
+    /// Inserts and queries are both projected; `get` returns the projected vector.
+    #[serde(default)]
+    pub projection: Option<ProjectionConfig>,
+    /// L2-normalize vectors on insert and query. Cosine collections then
+    /// search with dot product, which yields the same distances without
+    /// per-comparison norms; `get` returns the normalized vector.
+    /// Not supported for quantized collections.
+    #[serde(default)]
+    pub normalize: bool,
+    /// Reject inserted vectors whose L2 norm is not 1 (within `UNIT_NORM_EPSILON`),
+    /// for models that silently misbehave on unnormalized inputs
+    #[serde(default)]
+    pub require_unit_norm: bool,
+    /// Reject inserted vectors with NaN or infinite components with
+    /// `Error::InvalidVector`; stored, they poison every distance to them
+    #[serde(default = "default_validate_finite")]
+    pub validate_finite: bool,
+    /// Reject queries that are not finite, zero under cosine, or whose norm is
+    /// more than `QUERY_NORM_TOLERANCE` times outside the range of inserted
+    /// vectors; these otherwise return meaningless neighbours without error
+    #[serde(default)]
+    pub validate_query: bool,
+    /// How vectors of the wrong length are handled (see `DimensionPolicy`
+    /// for the recall implications of padding and truncation)
+    #[serde(default)]
+    pub dimension_mismatch: DimensionPolicy,
+    /// Extra metrics to maintain a separate HNSW graph for, so `search_with_metric`
+    /// can query the same vectors under each with full index acceleration.
+    /// Vectors are stored once, but every extra graph roughly adds another
+    /// `index` share of `memory_usage()` and repeats the insert-time graph work.
+    #[serde(default)]
+    pub additional_metrics: Vec<DistanceMetric>,
+    /// How vector metadata is held in memory. `MetadataFormat::Columnar`
+    /// stores the declared fields in typed columns, which is smaller and
+    /// faster to filter on; `get` returns the same metadata either way.
+    #[serde(default)]
+    pub metadata_format: MetadataFormat,
+    /// How search distances are reported as scores when a request does not
+    /// choose; ranking is unaffected
+    #[serde(default)]
+    pub score_transform: ScoreTransform,
+    /// Whether inserts, and upserts of new IDs, skip vectors whose content
+    /// is already stored (see `InsertMode::DedupByContent`). Quantized
+    /// collections do not support it.
+    #[serde(default)]
+    pub insert_mode: InsertMode,
+    /// Cache unfiltered `search_by_id` results until the next write (see
+    /// `SimilarCacheConfig`); disabled by default
+    #[serde(default)]
+    pub similar_cache: Option<SimilarCacheConfig>,
+    /// Compact in the background after a delete or upsert once deleted and
+    /// superseded slots make up more than this fraction of storage (0 =
+    /// never). Applies to collections in a `Database`; a bare `VectorDb`
+    /// reports it through `needs_compaction`. Compaction renumbers internal
+    /// IDs, so leave it off when relying on `insert_at` slots.
+    #[serde(default)]
+    pub auto_compact_fraction: f32,
+}
+
+fn default_validate_finite() -> bool {
+    true
 }
 
 impl Default for Config {
@@ -127,12 +257,26 @@ impl Default for Config {
             hnsw: HnswConfig::default(),
             max_vectors: 0,
             quantization: QuantizationType::None,
+            default_metadata: None,
+            max_metadata_bytes: 0,
+            projection: None,
+            normalize: false,
+            require_unit_norm: false,
+            validate_finite: true,
+            validate_query: false,
+            dimension_mismatch: DimensionPolicy::Strict,
+            additional_metrics: Vec::new(),
+            metadata_format: MetadataFormat::Json,
+            score_transform: ScoreTransform::Distance,
+            insert_mode: InsertMode::Always,
+            similar_cache: None,
+            auto_compact_fraction: 0.0,
         }
     }
 }
 
 /// Configuration for quantized vector database
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize)]
 pub struct QuantizedConfig {
     /// Dimensionality of vectors
     pub dimensions: usize,
@@ -146,6 +290,35 @@ pub struct QuantizedConfig {
     pub keep_originals: bool,
     /// Number of candidates to fetch before re-ranking (if keep_originals is true)
     pub rerank_multiplier: usize,
+    /// Only re-rank the full `k * rerank_multiplier` pool when the quantized
+    /// scores are ambiguous; otherwise just the top k get exact distances
+    pub adaptive_rerank: bool,
+    /// Quantized distance gap between the k-th and (k+1)-th candidates above
+    /// which the top k are considered unambiguous (used by `adaptive_rerank`)
+    pub rerank_gap_threshold: f32,
+    /// Collection-level metadata merged into every returned vector's metadata
+    pub default_metadata: Option<serde_json::Value>,
+    /// Maximum serialized size of a vector's metadata in bytes (0 = unlimited)
+    pub max_metadata_bytes: usize,
+    /// Reduce inputs to a lower dimensionality with a seeded random projection
+    pub projection: Option<ProjectionConfig>,
+    /// Reject inserted vectors whose L2 norm is not 1 (within `UNIT_NORM_EPSILON`)
+    pub require_unit_norm: bool,
+    /// Reject vectors with NaN or infinite components (see `Config::validate_finite`)
+    pub validate_finite: bool,
+    /// Reject out-of-distribution queries (see `Config::validate_query`)
+    pub validate_query: bool,
+    /// How vectors of the wrong length are handled (see `Config::dimension_mismatch`)
+    pub dimension_mismatch: DimensionPolicy,
+    /// Search an HNSW graph navigated by quantized distances; when false (and
+    /// always for Binary), every search scans all vectors
+    pub use_index: bool,
+    /// Default presentation of search distances (see `Config::score_transform`)
+    pub score_transform: ScoreTransform,
+    /// Tombstone fraction past which `needs_compaction` reports true and a
+    /// `Database` collection compacts in the background (see
+    /// `Config::auto_compact_fraction`)
+    pub auto_compact_fraction: f32,
 }
 
 impl Default for QuantizedConfig {
@@ -157,6 +330,18 @@ impl Default for QuantizedConfig {
             quantization: QuantizationType::SQ8,
             keep_originals: false,
             rerank_multiplier: 3,
+            adaptive_rerank: false,
+            rerank_gap_threshold: 0.05,
+            default_metadata: None,
+            max_metadata_bytes: 0,
+            projection: None,
+            require_unit_norm: false,
+            validate_finite: true,
+            validate_query: false,
+            dimension_mismatch: DimensionPolicy::Strict,
+            use_index: true,
+            score_transform: ScoreTransform::Distance,
+            auto_compact_fraction: 0.0,
         }
     }
 }
@@ -168,18 +353,81 @@ pub struct VectorDb {
     config: Config,
     storage: VectorStorage,
     index: HnswIndex,
+    /// Graphs for `Config::additional_metrics`, keyed by the configured metric
+    extra_indexes: Vec<(DistanceMetric, HnswIndex)>,
+    projection: Option<RandomProjection>,
+    aliases: types::Aliases,
+    query_norms: types::QueryNorms,
+    /// Content lookup for `InsertMode::DedupByContent`
+    content_hashes: types::ContentHashes,
+    /// Orders storage slot allocation with HNSW node creation for `&self` inserts
+    insert_lock: sync::RwLock<()>,
+    /// `search_by_id` results, dropped by every write
+    similar_cache: cache::SimilarCache,
+    /// Centroids confining primary-graph searches, once trained
+    routing: Option<routing::CentroidRouter>,
+    hooks: hooks::Hooks,
+    /// Next candidate for `insert_auto`
+    next_auto_id: std::sync::atomic::AtomicU64,
+    compactor: compaction::Compactor,
 }
 
 impl VectorDb {
     /// Create a new vector database with the given configuration
     pub fn new(config: Config) -> Result<Self> {
-        let storage = VectorStorage::new(config.dimensions);
-        let index = HnswIndex::new(config.hnsw.clone(), config.distance_metric);
+        config.hnsw.validate()?;
+        config.metadata_format.validate()?;
+        config.distance_metric.check_storage(false)?;
+        compaction::validate_fraction(config.auto_compact_fraction)?;
+        config.insert_mode.validate()?;
+        let projection = config
+            .projection
+            .map(|p| RandomProjection::new(config.dimensions, p))
+            .transpose()?;
+        let stored_dim = projection
+            .as_ref()
+            .map_or(config.dimensions, RandomProjection::target_dim);
+        let mut storage = VectorStorage::new(stored_dim);
+        storage.set_metadata_format(&config.metadata_format);
+        let graph_metric = |metric: DistanceMetric| {
+            if config.normalize {
+                metric.for_normalized()
+            } else {
+                metric
+            }
+        };
+        let index = HnswIndex::new(config.hnsw.clone(), graph_metric(config.distance_metric));
+
+        let mut extra_indexes: Vec<(DistanceMetric, HnswIndex)> = Vec::new();
+        for &metric in &config.additional_metrics {
+            metric.check_storage(false)?;
+            if metric == config.distance_metric || extra_indexes.iter().any(|(m, _)| *m == metric) {
+                return Err(Error::InvalidConfig(format!(
+                    "distance metric {:?} is configured more than once",
+                    metric
+                )));
+            }
+            extra_indexes.push((
+                metric,
+                HnswIndex::new(config.hnsw.clone(), graph_metric(metric)),
+            ));
+        }
 
         Ok(Self {
+            query_norms: types::QueryNorms::new(config.validate_query),
+            similar_cache: cache::SimilarCache::new(config.similar_cache),
             config,
             storage,
             index,
+            extra_indexes,
+            projection,
+            aliases: types::Aliases::default(),
+            content_hashes: types::ContentHashes::default(),
+            insert_lock: sync::RwLock::new(()),
+            routing: None,
+            hooks: hooks::Hooks::default(),
+            next_auto_id: std::sync::atomic::AtomicU64::new(0),
+            compactor: compaction::Compactor::default(),
         })
     }
 
@@ -190,28 +438,151 @@ impl VectorDb {
         vector: &[f32],
         metadata: Option<Value>,
     ) -> Result<()> {
+        self.insert_concurrent(id, vector, metadata)
+    }
+
+    /// Insert a vector through a shared reference, so writers and searchers
+    /// can use the same `VectorDb` from multiple threads
+    ///
+    /// Inserts are serialized with each other. Searches run alongside an
+    /// insert's neighbor search and only wait while its node is published
+    /// and linked (see `HnswIndex`).
+    pub fn insert_concurrent(
+        &self,
+        id: impl Into<VectorId>,
+        vector: &[f32],
+        metadata: Option<Value>,
+    ) -> Result<()> {
+        self.insert_or_existing(id, vector, metadata).map(|_| ())
+    }
+
+    /// Insert a vector through a shared reference, returning the ID it is
+    /// stored under
+    ///
+    /// That is `id`, unless `InsertMode::DedupByContent` finds the vector
+    /// already stored: then nothing is written, `metadata` is discarded and
+    /// the stored vector's primary ID is returned.
+    pub fn insert_or_existing(
+        &self,
+        id: impl Into<VectorId>,
+        vector: &[f32],
+        metadata: Option<Value>,
+    ) -> Result<VectorId> {
         let id = id.into();
 
-        if vector.len() != self.config.dimensions {
-            return Err(Error::DimensionMismatch {
-                expected: self.config.dimensions,
-                got: vector.len(),
-            });
+        types::check_finite(vector, self.config.validate_finite)?;
+        types::check_unit_norm(vector, self.config.require_unit_norm)?;
+        self.query_norms.observe(vector);
+        let vector = &*projection::prepare_normalized(
+            self.projection.as_ref(),
+            self.config.dimensions,
+            self.config.dimension_mismatch,
+            self.config.normalize,
+            vector,
+        )?;
+        types::check_metadata_size(metadata.as_ref(), self.config.max_metadata_bytes)?;
+        let hooked = self.hooks.wants_inserts().then(|| metadata.clone());
+
+        {
+            // HNSW nodes are stored positionally, so they must be created in slot order
+            let _guard = self.insert_lock.write();
+            if let Some(existing) = self.find_duplicate(vector)? {
+                return Ok(existing);
+            }
+            let internal_id = self.storage.insert(id.clone(), vector, metadata)?;
+            // Searches that raced with this insert may have seen the stored
+            // vector before its graph node, so only drop the cache afterwards
+            let indexed = self
+                .indexes()
+                .try_for_each(|index| index.insert(internal_id, vector, &self.storage));
+            self.similar_cache.invalidate();
+            indexed?;
         }
 
-        let internal_id = self.storage.insert(id.clone(), vector, metadata)?;
-        self.index.insert(internal_id, vector, &self.storage)?;
+        if let Some(metadata) = hooked {
+            self.hooks.inserted(&id, vector, &metadata);
+        }
+        Ok(id)
+    }
 
-        Ok(())
+    /// Insert a vector under the next free integer ID, returning the ID it
+    /// is stored under
+    ///
+    /// IDs count up from 0, skipping integer IDs that are already taken, so
+    /// auto IDs can be mixed with explicit ones. As with
+    /// `insert_or_existing`, a vector found by `InsertMode::DedupByContent`
+    /// returns its existing ID instead.
+    pub fn insert_auto(&self, vector: &[f32], metadata: Option<Value>) -> Result<VectorId> {
+        let id = loop {
+            let id = VectorId::from(
+                self.next_auto_id
+                    .fetch_add(1, std::sync::atomic::Ordering::Relaxed),
+            );
+            if self.storage.get_internal_id(&id).is_none() {
+                break id;
+            }
+        };
+        self.insert_or_existing(id, vector, metadata)
     }
 
-    /// Delete a vector by ID
+    /// The primary ID of a stored vector matching the prepared `vector` under
+    /// `InsertMode::DedupByContent`
+    fn find_duplicate(&self, vector: &[f32]) -> Result<Option<VectorId>> {
+        self.content_hashes.find_duplicate(
+            self.config.insert_mode,
+            vector,
+            &self.storage,
+            &self.index,
+        )
+    }
+
+    /// Delete a vector by its primary ID or an alias, dropping its aliases
     pub fn delete(&mut self, id: impl Into<VectorId>) -> Result<bool> {
-        let id = id.into();
-        self.storage.delete(&id)
+        let id = self.resolve(&id.into());
+        self.similar_cache.invalidate();
+        let previous = self.storage.get_internal_id(&id);
+        let deleted = self.storage.delete(&id)?;
+        if deleted {
+            self.aliases.remove_target(&id);
+        }
+
+        if self.config.hnsw.eager_edge_cleanup {
+            if let Some(old) = previous {
+                for index in self.indexes() {
+                    index.remove(old, &self.storage);
+                }
+            }
+        }
+
+        if deleted {
+            self.hooks.deleted(&id);
+        }
+        Ok(deleted)
+    }
+
+    /// Make `alias` a secondary key for the vector `id` (a primary ID or alias)
+    ///
+    /// `get`, `delete` and `search_by_id` accept the alias in place of the
+    /// primary ID. Fails with `DuplicateId` if `alias` is already a primary ID
+    /// or an alias of another vector. Deleting the vector drops its aliases.
+    pub fn add_alias(&mut self, alias: impl Into<VectorId>, id: &str) -> Result<()> {
+        let storage = &self.storage;
+        self.aliases.add(alias.into(), &VectorId::from(id), |id| {
+            storage.get_internal_id(id).is_some()
+        })?;
+        Ok(())
+    }
+
+    /// Resolve a primary ID or alias to the primary ID
+    fn resolve(&self, id: &VectorId) -> VectorId {
+        self.aliases
+            .resolve(id, |id| self.storage.get_internal_id(id).is_some())
     }
 
     /// Insert or update a vector with the given ID and optional metadata
+    ///
+    /// Under `InsertMode::DedupByContent`, a new ID whose content is already
+    /// stored is skipped like an insert; updates are always written.
     pub fn upsert(
         &mut self,
         id: impl Into<VectorId>,
@@ -220,35 +591,191 @@ impl VectorDb {
     ) -> Result<()> {
         let id = id.into();
 
-        if vector.len() != self.config.dimensions {
-            return Err(Error::DimensionMismatch {
-                expected: self.config.dimensions,
-                got: vector.len(),
-            });
+        types::check_finite(vector, self.config.validate_finite)?;
+        types::check_unit_norm(vector, self.config.require_unit_norm)?;
+        self.query_norms.observe(vector);
+        let vector = &*projection::prepare_normalized(
+            self.projection.as_ref(),
+            self.config.dimensions,
+            self.config.dimension_mismatch,
+            self.config.normalize,
+            vector,
+        )?;
+        types::check_metadata_size(metadata.as_ref(), self.config.max_metadata_bytes)?;
+        let previous = self.storage.get_internal_id(&id);
+        if previous.is_none() && self.find_duplicate(vector)?.is_some() {
+            return Ok(());
         }
+        let hooked = self.hooks.wants_inserts().then(|| metadata.clone());
 
+        self.similar_cache.invalidate();
         let internal_id = self.storage.upsert(id.clone(), vector, metadata)?;
-        self.index.insert(internal_id, vector, &self.storage)?;
+        for index in self.indexes() {
+            index.insert(internal_id, vector, &self.storage)?;
+        }
+
+        if self.config.hnsw.eager_edge_cleanup {
+            if let Some(old) = previous {
+                for index in self.indexes() {
+                    index.remove(old, &self.storage);
+                }
+            }
+        }
 
+        if let Some(metadata) = hooked {
+            self.hooks.inserted(&id, vector, &metadata);
+        }
         Ok(())
     }
 
+    /// Insert new vectors, writing them to storage under a single lock
+    /// acquisition and then indexing each one
+    ///
+    /// Atomic like `VectorStorage::insert_batch`: if any vector fails
+    /// validation or any ID is a duplicate, within the batch or of a stored
+    /// vector, nothing is inserted. Under `InsertMode::DedupByContent`,
+    /// vectors already stored or repeated within the batch are skipped.
+    pub fn insert_batch(&mut self, items: Vec<(VectorId, Vec<f32>, Option<Value>)>) -> Result<()> {
+        self.insert_new(items).map(|_| ())
+    }
+
+    /// `insert_batch`, returning how many items `InsertMode` skipped
+    fn insert_new(&mut self, items: Vec<(VectorId, Vec<f32>, Option<Value>)>) -> Result<usize> {
+        let mut prepared = Vec::with_capacity(items.len());
+        for (id, vector, metadata) in items {
+            types::check_finite(&vector, self.config.validate_finite)?;
+            types::check_unit_norm(&vector, self.config.require_unit_norm)?;
+            self.query_norms.observe(&vector);
+            let vector = projection::prepare_normalized(
+                self.projection.as_ref(),
+                self.config.dimensions,
+                self.config.dimension_mismatch,
+                self.config.normalize,
+                &vector,
+            )?
+            .into_owned();
+            types::check_metadata_size(metadata.as_ref(), self.config.max_metadata_bytes)?;
+            prepared.push((id, vector, metadata));
+        }
+        let (prepared, skipped) = types::dedup_batch(
+            self.config.insert_mode,
+            prepared,
+            |_| true,
+            |vector| self.find_duplicate(vector),
+        )?;
+        let (records, vectors): (Vec<_>, Vec<_>) = prepared
+            .into_iter()
+            .map(|(id, vector, metadata)| ((id, metadata), vector))
+            .unzip();
+
+        let hooked = if self.hooks.wants_inserts() {
+            records.clone()
+        } else {
+            Vec::new()
+        };
+
+        {
+            let _guard = self.insert_lock.write();
+            self.similar_cache.invalidate();
+            let internal_ids = self.storage.insert_batch(
+                records
+                    .into_iter()
+                    .zip(&vectors)
+                    .map(|((id, metadata), vector)| (id, vector.as_slice(), metadata))
+                    .collect(),
+            )?;
+            let hnsw_items: Vec<(types::InternalId, &[f32])> = internal_ids
+                .into_iter()
+                .zip(vectors.iter().map(Vec::as_slice))
+                .collect();
+            for index in self.indexes() {
+                index.insert_batch(&hnsw_items, &self.storage)?;
+            }
+        }
+
+        for ((id, metadata), vector) in hooked.iter().zip(&vectors) {
+            self.hooks.inserted(id, vector, metadata);
+        }
+        Ok(skipped)
+    }
+
     /// Batch insert/upsert vectors
+    ///
+    /// Under `InsertMode::DedupByContent`, vectors for new IDs are skipped
+    /// when their content is stored under an ID the batch leaves alone, or
+    /// repeated within the batch. Updates of stored IDs are always written.
     pub fn upsert_batch(&mut self, items: Vec<(VectorId, Vec<f32>, Option<Value>)>) -> Result<()> {
+        self.upsert_all(items).map(|_| ())
+    }
+
+    /// `upsert_batch`, returning how many items `InsertMode` skipped
+    fn upsert_all(&mut self, mut items: Vec<(VectorId, Vec<f32>, Option<Value>)>) -> Result<usize> {
         if items.is_empty() {
-            return Ok(());
+            return Ok(0);
         }
 
-        // Validate dimensions
-        for (_, vector, _) in &items {
+        // Validate dimensions, fitting mismatched vectors under the policy
+        for (_, vector, metadata) in &mut items {
+            types::check_finite(vector, self.config.validate_finite)?;
+            types::check_unit_norm(vector, self.config.require_unit_norm)?;
+            self.query_norms.observe(vector);
             if vector.len() != self.config.dimensions {
-                return Err(Error::DimensionMismatch {
-                    expected: self.config.dimensions,
-                    got: vector.len(),
-                });
+                *vector = self
+                    .config
+                    .dimension_mismatch
+                    .fit(self.config.dimensions, vector)?
+                    .into_owned();
             }
+            types::check_metadata_size(metadata.as_ref(), self.config.max_metadata_bytes)?;
         }
 
+        let items = if self.config.hnsw.shuffle_inserts {
+            types::shuffle_batch(items, self.config.hnsw.shuffle_seed)
+        } else {
+            items
+        };
+
+        let items = match &self.projection {
+            Some(projection) => items
+                .into_iter()
+                .map(|(id, vector, metadata)| (id, projection.project(&vector), metadata))
+                .collect(),
+            None => items,
+        };
+        let items = if self.config.normalize {
+            items
+                .into_iter()
+                .map(|(id, mut vector, metadata)| {
+                    distance::normalize(&mut vector);
+                    (id, vector, metadata)
+                })
+                .collect()
+        } else {
+            items
+        };
+        let batch_ids: std::collections::HashSet<VectorId> =
+            items.iter().map(|(id, _, _)| id.clone()).collect();
+        let (items, skipped) = types::dedup_batch(
+            self.config.insert_mode,
+            items,
+            |id| self.storage.get_internal_id(id).is_none(),
+            |vector| {
+                Ok(self
+                    .find_duplicate(vector)?
+                    .filter(|stored| !batch_ids.contains(stored)))
+            },
+        )?;
+
+        let previous: Vec<types::InternalId> = if self.config.hnsw.eager_edge_cleanup {
+            items
+                .iter()
+                .filter_map(|(id, _, _)| self.storage.get_internal_id(id))
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        self.similar_cache.invalidate();
         // 1. Batch Upsert into Storage (Single lock acquisition)
         let internal_ids = self.storage.upsert_batch(&items)?;
 
@@ -261,20 +788,196 @@ impl VectorDb {
             .map(|(id, (_, vec, _))| (*id, vec.as_slice()))
             .collect();
 
-        self.index.insert_batch(&hnsw_items, &self.storage)?;
+        for index in self.indexes() {
+            index.reserve(hnsw_items.len());
+            index.insert_batch(&hnsw_items, &self.storage)?;
+            for &old in &previous {
+                index.remove(old, &self.storage);
+            }
+        }
+
+        for (id, vector, metadata) in &items {
+            self.hooks.inserted(id, vector, metadata);
+        }
+        Ok(skipped)
+    }
+
+    /// Import a batch, resolving IDs that are already stored or repeated
+    /// within the batch under `policy`
+    ///
+    /// Duplicates are resolved before anything is written, so
+    /// `DuplicatePolicy::Error` leaves the database unchanged. The remaining
+    /// items go through `insert_batch`, or `upsert_batch` when overwriting.
+    pub fn import_batch(
+        &mut self,
+        items: Vec<(VectorId, Vec<f32>, Option<Value>)>,
+        policy: DuplicatePolicy,
+    ) -> Result<BatchReport> {
+        let (items, report) = types::resolve_duplicates(
+            items
+                .into_iter()
+                .map(|(id, vector, metadata)| (id, (vector, metadata))),
+            policy,
+            |id| self.storage.get_internal_id(id).is_some(),
+        )?;
+        let items = items
+            .into_iter()
+            .map(|(id, (vector, metadata))| (id, vector, metadata))
+            .collect();
+        let deduplicated = match policy {
+            DuplicatePolicy::Overwrite => self.upsert_all(items)?,
+            DuplicatePolicy::Skip | DuplicatePolicy::Error => self.insert_new(items)?,
+        };
+        Ok(report.deduplicated(deduplicated))
+    }
+
+    /// Apply several writes atomically: either all of them or none
+    ///
+    /// Every op is validated (dimensions, duplicate IDs, missing IDs,
+    /// metadata size) before anything changes, taking earlier ops in the
+    /// batch into account. Ops then apply in order; should one still fail,
+    /// those already applied are rolled back and the error is returned.
+    ///
+    /// Under `InsertMode::DedupByContent`, an `Insert` or `Upsert` creating
+    /// an ID no other op touches is skipped when its content is stored under
+    /// an ID the transaction leaves alone, or created earlier in it.
+    pub fn transaction(&mut self, ops: Vec<Op>) -> Result<()> {
+        let ops = transaction::plan(
+            ops,
+            self.config.max_metadata_bytes,
+            |id| self.storage.get_internal_id(id).is_some(),
+            |id| self.resolve(id),
+            |vector, metadata| {
+                types::check_finite(vector, self.config.validate_finite)?;
+                types::check_unit_norm(vector, self.config.require_unit_norm)?;
+                self.query_norms.observe(vector);
+                let vector = projection::prepare_normalized(
+                    self.projection.as_ref(),
+                    self.config.dimensions,
+                    self.config.dimension_mismatch,
+                    self.config.normalize,
+                    vector,
+                )?;
+                types::check_metadata_size(metadata, self.config.max_metadata_bytes)?;
+                Ok(vector.into_owned())
+            },
+        )?;
+        let ops = transaction::dedup(
+            ops,
+            self.config.insert_mode,
+            |id| self.storage.get_internal_id(id).is_some(),
+            |vector| self.find_duplicate(vector),
+        )?;
+
+        // Deleting an ID that is not stored is a no-op, and not reported
+        let mut live: std::collections::HashMap<&VectorId, bool> = Default::default();
+        let deletes: Vec<bool> = ops
+            .iter()
+            .map(|op| {
+                let id = op.id();
+                let was_live = live
+                    .get(id)
+                    .copied()
+                    .unwrap_or_else(|| self.storage.get_internal_id(id).is_some());
+                let is_delete = matches!(op, Op::Delete { .. });
+                live.insert(id, !is_delete);
+                is_delete && was_live
+            })
+            .collect();
+
+        self.similar_cache.invalidate();
+        transaction::Writer {
+            storage: &self.storage,
+            indexes: std::iter::once(&self.index)
+                .chain(self.extra_indexes.iter().map(|(_, index)| index))
+                .collect(),
+            aliases: &mut self.aliases,
+            eager_edge_cleanup: self.config.hnsw.eager_edge_cleanup,
+        }
+        .apply_all(&ops)?;
 
+        for (op, deleted) in ops.iter().zip(deletes) {
+            match op {
+                Op::Insert {
+                    id,
+                    vector,
+                    metadata,
+                }
+                | Op::Upsert {
+                    id,
+                    vector,
+                    metadata,
+                } => self.hooks.inserted(id, vector, metadata),
+                Op::Delete { id } if deleted => self.hooks.deleted(id),
+                _ => {}
+            }
+        }
         Ok(())
     }
 
-    /// Retrieve a vector by its external ID
+    /// Call `hook` after every successful write of a vector: `insert`,
+    /// `upsert`, the batch variants and transaction inserts and upserts,
+    /// replacing any previous insert hook
+    ///
+    /// The hook receives the vector as stored, after any projection or
+    /// normalization. It runs on the writing thread once the write has
+    /// finished and no lock of this collection is held (see `hooks`).
+    /// An insert skipped by `InsertMode::DedupByContent` does not call it.
+    pub fn on_insert(&mut self, hook: InsertHook) {
+        self.hooks.set_insert(hook);
+    }
+
+    /// Call `hook` with the primary ID of every vector removed by `delete`
+    /// or a transaction delete, replacing any previous delete hook
+    ///
+    /// Like `on_insert`, it runs after the write under no lock. Deleting a
+    /// missing ID does not call it, and neither does `clear`.
+    pub fn on_delete(&mut self, hook: DeleteHook) {
+        self.hooks.set_delete(hook);
+    }
+
+    /// Merge a JSON patch into the metadata of every vector matching `filter`
+    ///
+    /// The patch follows JSON merge patch rules: object keys merge
+    /// recursively and `null` removes a key. All updates apply as one
+    /// transaction. Returns how many vectors changed.
+    pub fn update_metadata_by_filter(
+        &mut self,
+        filter: &filter::Filter,
+        patch: &Value,
+    ) -> Result<usize> {
+        let ops = transaction::patch_metadata(&self.storage, filter, patch);
+        let updated = ops.len();
+        self.transaction(ops)?;
+        Ok(updated)
+    }
+
+    /// Replace (or with `MetadataUpdate::Merge`, shallow-merge) the metadata
+    /// of the vector `id`, a primary ID or alias
+    ///
+    /// The vector and its graph node are left as they are. Fails with
+    /// `VectorNotFound` if there is no such vector.
+    pub fn set_metadata(&mut self, id: &str, metadata: Value, mode: MetadataUpdate) -> Result<()> {
+        let id = self.resolve(&VectorId::from(id));
+        let internal_id = self
+            .storage
+            .get_internal_id(&id)
+            .ok_or_else(|| Error::VectorNotFound(id.to_string()))?;
+        let metadata = mode.apply(self.storage.get_metadata(internal_id), metadata);
+        types::check_metadata_size(Some(&metadata), self.config.max_metadata_bytes)?;
+        self.similar_cache.invalidate();
+        self.storage.update_metadata(&id, metadata)
+    }
+
+    /// Retrieve a vector by its primary ID or an alias
     pub fn get(&self, id: &str) -> Result<Option<(Vec<f32>, Option<Value>)>> {
-        let id = VectorId::from(id);
+        let id = self.resolve(&VectorId::from(id));
         if let Some(internal_id) = self.storage.get_internal_id(&id) {
             let vector = self
                 .storage
                 .get(internal_id)
                 .ok_or(Error::VectorNotFound(id.to_string()))?;
-            let metadata = self.storage.get_metadata(internal_id);
+            let metadata = self.with_defaults(self.storage.get_metadata(internal_id));
             Ok(Some((vector, metadata)))
         } else {
             Ok(None)
@@ -285,21 +988,101 @@ impl VectorDb {
     pub fn list(&self, offset: usize, limit: usize) -> Vec<(VectorId, Option<Value>)> {
         let ids = self.storage.all_internal_ids();
         ids.iter()
-            .filter_map(|&internal_id| {
-                // Filter stale
-                let ext_id = self.storage.get_external_id(internal_id)?;
-                let current_internal = self.storage.get_internal_id(&ext_id)?;
-                if current_internal != internal_id {
-                    return None;
-                }
-                let metadata = self.storage.get_metadata(internal_id);
-                Some((ext_id, metadata))
-            })
+            .filter_map(|&internal_id| self.list_entry(internal_id))
             .skip(offset)
             .take(limit)
             .collect()
     }
 
+    /// List up to `limit` vector IDs and metadata from a cursor (keyset pagination)
+    ///
+    /// `cursor` is the previous page's `next_cursor`, or `None` for the first
+    /// page. Unlike `list`, earlier entries are not re-scanned. The cursor is
+    /// a storage position, so it stays valid when vectors are updated or
+    /// deleted between pages; an updated vector moves to the end of the
+    /// listing. `compact` and `clear` renumber positions.
+    pub fn list_after(&self, cursor: Option<u64>, limit: usize) -> ListPage {
+        types::list_page(cursor, limit, self.physical_len(), |internal_id| {
+            self.list_entry(internal_id)
+        })
+    }
+
+    /// Resolve a storage slot to its listing entry, skipping stale slots
+    fn list_entry(&self, internal_id: types::InternalId) -> Option<(VectorId, Option<Value>)> {
+        let ext_id = self.storage.get_external_id(internal_id)?;
+        let current_internal = self.storage.get_internal_id(&ext_id)?;
+        if current_internal != internal_id {
+            return None;
+        }
+        let metadata = self.with_defaults(self.storage.get_metadata(internal_id));
+        Some((ext_id, metadata))
+    }
+
+    /// Count the most frequent values of a metadata field (dot paths allowed)
+    ///
+    /// Only stored metadata is counted, like filters: collection default
+    /// metadata is not included. Array values count once per element.
+    pub fn facet(&self, field: &str, top_n: usize) -> Facets {
+        self.facet_sampled(field, top_n, None)
+    }
+
+    /// Like `facet`, but with `sample` only about that many vectors are read
+    /// and the counts are scaled up to the collection size
+    pub fn facet_sampled(&self, field: &str, top_n: usize, sample: Option<usize>) -> Facets {
+        facet::aggregate(
+            field,
+            top_n,
+            sample,
+            self.physical_len(),
+            self.len(),
+            |internal_id| {
+                let ext_id = self.storage.get_external_id(internal_id)?;
+                (self.storage.get_internal_id(&ext_id)? == internal_id)
+                    .then(|| self.storage.get_metadata(internal_id))
+            },
+        )
+    }
+
+    /// Get a vector's HNSW neighbors per layer (layer 0 first), resolved to external IDs
+    ///
+    /// Returns the vector's internal ID alongside the layers; the max layer is
+    /// `layers.len() - 1`. Neighbors that are deleted or superseded by an update
+    /// are omitted.
+    pub fn neighborhood(&self, id: &str) -> Option<(types::InternalId, Vec<Vec<VectorId>>)> {
+        let internal_id = self.storage.get_internal_id(&VectorId::from(id))?;
+        let layers = self
+            .index
+            .neighbors(internal_id)
+            .into_iter()
+            .map(|layer| {
+                layer
+                    .into_iter()
+                    .filter_map(|neighbor| {
+                        let ext_id = self.storage.get_external_id(neighbor)?;
+                        (self.storage.get_internal_id(&ext_id)? == neighbor).then_some(ext_id)
+                    })
+                    .collect()
+            })
+            .collect();
+        Some((internal_id, layers))
+    }
+
+    /// Edges of HNSW layer `layer` as `(from, to)` external IDs, for
+    /// visualizing the graph
+    ///
+    /// Edges touching a deleted or superseded vector are skipped. See
+    /// `HnswIndex::export_edges` for consistency under concurrent writes.
+    pub fn graph_edges(&self, layer: usize) -> impl Iterator<Item = (VectorId, VectorId)> + '_ {
+        let live = |internal_id| {
+            let ext_id = self.storage.get_external_id(internal_id)?;
+            (self.storage.get_internal_id(&ext_id)? == internal_id).then_some(ext_id)
+        };
+        self.index
+            .export_edges()
+            .filter(move |&(_, _, edge_layer)| edge_layer == layer)
+            .filter_map(move |(from, to, _)| Some((live(from)?, live(to)?)))
+    }
+
     /// Search for the k nearest neighbors
     pub fn search(
         &self,
@@ -307,127 +1090,777 @@ impl VectorDb {
         k: usize,
         filter: Option<&filter::Filter>,
     ) -> Result<Vec<(VectorId, f32, Option<Value>)>> {
-        if query.len() != self.config.dimensions {
-            return Err(Error::DimensionMismatch {
-                expected: self.config.dimensions,
-                got: query.len(),
-            });
-        }
-
-        // We search for more candidates (2x k) to account for potential stale/deleted entries
-        // that might be filtered out.
-        let search_k = k * 2;
-        let results = self
-            .index
-            .search(query, search_k, &self.storage.view(), filter)?;
-
-        // Map internal IDs back to external IDs and fetch metadata
-        // Filter out stale entries (where internal_id doesn't match current mapping)
-        let mapped: Vec<(VectorId, f32, Option<Value>)> = results
-            .into_iter()
-            .filter_map(|(internal_id, distance)| {
-                let ext_id = self.storage.get_external_id(internal_id)?;
-                let current_internal = self.storage.get_internal_id(&ext_id)?;
-
-                if current_internal != internal_id {
-                    // This is a stale entry (overwritten by upsert)
-                    return None;
-                }
+        self.search_with_metric(query, k, self.config.distance_metric, filter)
+    }
 
-                let metadata = self.storage.get_metadata(internal_id);
-                Some((ext_id, distance, metadata))
-            })
-            .take(k)
-            .collect();
+    /// Search for the k nearest neighbors under `metric`, which must be the
+    /// collection's distance metric or one of its `additional_metrics`.
+    ///
+    /// A graph built for one metric does not find nearest neighbors under
+    /// another, so other metrics are rejected with `InvalidConfig` instead of
+    /// returning wrong results; use `search_exact_with_metric` for them.
+    pub fn search_with_metric(
+        &self,
+        query: &[f32],
+        k: usize,
+        metric: DistanceMetric,
+        filter: Option<&filter::Filter>,
+    ) -> Result<Vec<(VectorId, f32, Option<Value>)>> {
+        let index = self.index_for(metric)?;
+        self.query_norms.check(query, metric)?;
+        let query = &*projection::prepare_normalized(
+            self.projection.as_ref(),
+            self.config.dimensions,
+            self.config.dimension_mismatch,
+            self.config.normalize,
+            query,
+        )?;
 
-        Ok(mapped)
+        self.search_prepared(index, query, k, filter, None, None)
+            .map(|(results, _)| results)
     }
 
-    /// Search for the k nearest neighbors (without metadata)
-    pub fn search_ids(
+    /// Search for the k nearest neighbors under any `metric` by scoring every
+    /// live vector, so metrics without a graph of their own give exact results.
+    /// Runs in O(n); index the metric via `additional_metrics` for fast search.
+    pub fn search_exact_with_metric(
         &self,
         query: &[f32],
         k: usize,
+        metric: DistanceMetric,
         filter: Option<&filter::Filter>,
-    ) -> Result<Vec<(VectorId, f32)>> {
-        if query.len() != self.config.dimensions {
-            return Err(Error::DimensionMismatch {
-                expected: self.config.dimensions,
-                got: query.len(),
-            });
-        }
+    ) -> Result<Vec<types::SearchHit>> {
+        metric.check_storage(false)?;
+        self.query_norms.check(query, metric)?;
+        let query = &*projection::prepare_normalized(
+            self.projection.as_ref(),
+            self.config.dimensions,
+            self.config.dimension_mismatch,
+            self.config.normalize,
+            query,
+        )?;
 
-        let search_k = k * 2;
-        let results = self
-            .index
-            .search(query, search_k, &self.storage.view(), filter)?;
+        let mut scored: Vec<(types::InternalId, f32)> = self
+            .storage
+            .all_internal_ids()
+            .into_iter()
+            .filter(|&id| filter.is_none_or(|f| self.storage.matches_filter(id, f)))
+            .filter_map(|id| {
+                let vector = self.storage.get(id)?;
+                Some((id, metric.distance(query, &vector)))
+            })
+            .collect();
+        scored.sort_by(|a, b| a.1.total_cmp(&b.1));
 
-        let mapped: Vec<(VectorId, f32)> = results
+        Ok(scored
             .into_iter()
-            .filter_map(|(internal_id, distance)| {
-                let ext_id = self.storage.get_external_id(internal_id)?;
-                let current_internal = self.storage.get_internal_id(&ext_id)?;
-                if current_internal != internal_id {
-                    return None;
-                }
-                Some((ext_id, distance))
+            .filter_map(|(id, distance)| {
+                let (ext_id, metadata) = self.list_entry(id)?;
+                Some((ext_id, distance, metadata))
             })
             .take(k)
-            .collect();
+            .collect())
+    }
 
-        Ok(mapped)
+    /// Like `search`, also reporting how many candidates the filter rejected
+    /// and whether the graph was fully explored, so an empty filtered result
+    /// can be told apart from an empty collection
+    pub fn search_with_stats(
+        &self,
+        query: &[f32],
+        k: usize,
+        filter: Option<&filter::Filter>,
+    ) -> Result<(Vec<types::SearchHit>, SearchStats)> {
+        self.search_with_ef_stats(query, k, None, filter)
     }
 
-    /// Get the number of vectors in the database
-    pub fn len(&self) -> usize {
-        self.storage.len()
+    /// Like `search`, with a candidate list of `ef` (at least `k`) instead of
+    /// `HnswConfig::ef_search`, to trade latency for recall per query
+    pub fn search_with_ef(
+        &self,
+        query: &[f32],
+        k: usize,
+        ef: usize,
+        filter: Option<&filter::Filter>,
+    ) -> Result<Vec<types::SearchHit>> {
+        self.search_with_ef_stats(query, k, Some(ef), filter)
+            .map(|(results, _)| results)
     }
 
-    /// Check if the database is empty
-    pub fn is_empty(&self) -> bool {
-        self.storage.is_empty()
+    /// `search_with_stats` with an optional candidate list of `ef` (see
+    /// `search_with_ef`)
+    pub fn search_with_ef_stats(
+        &self,
+        query: &[f32],
+        k: usize,
+        ef: Option<usize>,
+        filter: Option<&filter::Filter>,
+    ) -> Result<(Vec<types::SearchHit>, SearchStats)> {
+        self.query_norms.check(query, self.config.distance_metric)?;
+        let query = &*projection::prepare_normalized(
+            self.projection.as_ref(),
+            self.config.dimensions,
+            self.config.dimension_mismatch,
+            self.config.normalize,
+            query,
+        )?;
+        self.search_prepared(&self.index, query, k, filter, None, ef)
     }
 
-    /// Get configuration
-    pub fn config(&self) -> &Config {
-        &self.config
+    /// Like `search`, probing the `nprobe` routing centroids nearest the
+    /// query instead of `RoutingConfig::nprobe`. Fails with `InvalidConfig`
+    /// unless routing is trained (see `train_routing`).
+    pub fn search_with_nprobe(
+        &self,
+        query: &[f32],
+        k: usize,
+        nprobe: usize,
+        filter: Option<&filter::Filter>,
+    ) -> Result<Vec<types::SearchHit>> {
+        self.search_debug(query, k, Some(nprobe), filter)
+            .map(|(hits, _)| hits)
     }
 
-    /// Get approximate memory usage in bytes
-    pub fn memory_usage(&self) -> usize {
-        self.storage.memory_usage() + self.index.memory_usage()
+    /// Routed search that also reports the centroid lists it probed and how
+    /// many graph candidates each contributed, to tune `nprobe`
+    ///
+    /// `nprobe` defaults to `RoutingConfig::nprobe`. Fails with
+    /// `InvalidConfig` unless routing is trained.
+    pub fn search_debug(
+        &self,
+        query: &[f32],
+        k: usize,
+        nprobe: Option<usize>,
+        filter: Option<&filter::Filter>,
+    ) -> Result<(Vec<types::SearchHit>, RoutingDebug)> {
+        if self.routing.is_none() {
+            return Err(Error::InvalidConfig(
+                "routing is not trained for this collection".into(),
+            ));
+        }
+        let (hits, _, debug) = self.search_probing(query, k, None, nprobe, filter)?;
+        Ok((hits, debug.unwrap_or_default()))
     }
-}
 
-/// Quantized vector database with configurable compression
-///
-/// Uses SQ8 (4x compression) or Binary (32x compression) quantization
-/// to dramatically reduce memory usage with minimal accuracy loss.
-pub struct QuantizedVectorDb {
-    config: QuantizedConfig,
-    storage: QuantizedStorage,
+    /// `search_with_ef_stats` that also reports the routing lists probed, as
+    /// `search_debug` does; `None` without trained routing
+    pub fn search_with_ef_debug(
+        &self,
+        query: &[f32],
+        k: usize,
+        ef: Option<usize>,
+        filter: Option<&filter::Filter>,
+    ) -> Result<(Vec<types::SearchHit>, SearchStats, Option<RoutingDebug>)> {
+        self.search_probing(query, k, ef, None, filter)
+    }
+
+    /// Search probing `nprobe` routing lists (`RoutingConfig::nprobe` when
+    /// unset), reporting them when routing is trained
+    fn search_probing(
+        &self,
+        query: &[f32],
+        k: usize,
+        ef: Option<usize>,
+        nprobe: Option<usize>,
+        filter: Option<&filter::Filter>,
+    ) -> Result<(Vec<types::SearchHit>, SearchStats, Option<RoutingDebug>)> {
+        self.query_norms.check(query, self.config.distance_metric)?;
+        let query = &*projection::prepare_normalized(
+            self.projection.as_ref(),
+            self.config.dimensions,
+            self.config.dimension_mismatch,
+            self.config.normalize,
+            query,
+        )?;
+        let nprobe = self
+            .routing
+            .as_ref()
+            .map(|router| nprobe.unwrap_or(router.config().nprobe));
+        let (results, stats) = self.search_graph(&self.index, query, k * 2, ef, filter, nprobe)?;
+        let debug =
+            self.routing.as_ref().zip(nprobe).map(|(router, nprobe)| {
+                router.debug(query, nprobe, results.iter().map(|&(id, _)| id))
+            });
+        Ok((self.map_hits(results, k, None), stats, debug))
+    }
+
+    /// The routing list a stored vector is assigned to, given its primary
+    /// ID or an alias; `None` without trained routing
+    pub fn routing_list(&self, id: &str) -> Option<usize> {
+        let router = self.routing.as_ref()?;
+        let internal_id = self
+            .storage
+            .get_internal_id(&self.resolve(&VectorId::from(id)))?;
+        router.catch_up(&self.storage);
+        router.list_of(internal_id)
+    }
+
+    /// Search keeping only the closest hit per distinct value of the metadata
+    /// field `group_by`, for up to `k` groups
+    ///
+    /// Over-fetches as needed to fill `k` groups; hits without the field are
+    /// handled according to `missing`.
+    pub fn search_grouped(
+        &self,
+        query: &[f32],
+        k: usize,
+        group_by: &str,
+        missing: MissingGroup,
+        filter: Option<&filter::Filter>,
+    ) -> Result<Vec<types::SearchHit>> {
+        group::search_grouped(k, group_by, missing, self.len(), |n| {
+            self.search(query, n, filter)
+        })
+    }
+
+    /// Find the k nearest neighbors of a stored vector, given its primary ID
+    /// or an alias, using the stored vector as the query. With
+    /// `exclude_self` the vector itself is left out of the results.
+    pub fn search_by_id(
+        &self,
+        id: &str,
+        k: usize,
+        filter: Option<&filter::Filter>,
+        exclude_self: bool,
+    ) -> Result<Vec<(VectorId, f32, Option<Value>)>> {
+        let primary = self.resolve(&VectorId::from(id));
+        // Only the usual unfiltered, self-excluding lookup is cached
+        let generation = match (filter, exclude_self) {
+            (None, true) => self.similar_cache.generation(),
+            _ => None,
+        };
+        if generation.is_some() {
+            if let Some(results) = self.similar_cache.get(&primary, k) {
+                return Ok(results);
+            }
+        }
+
+        let internal_id = self
+            .storage
+            .get_internal_id(&primary)
+            .ok_or_else(|| Error::VectorNotFound(id.to_string()))?;
+        // Stored vectors are already projected and normalized
+        let query = self
+            .storage
+            .get(internal_id)
+            .ok_or_else(|| Error::VectorNotFound(id.to_string()))?;
+        let exclude = exclude_self.then_some(internal_id);
+        let (results, _) = self.search_prepared(&self.index, &query, k, filter, exclude, None)?;
+        if let Some(generation) = generation {
+            self.similar_cache.put(primary, k, generation, &results);
+        }
+        Ok(results)
+    }
+
+    /// Hit and miss counts of the `search_by_id` cache (see
+    /// `Config::similar_cache`); all zero when it is disabled
+    pub fn similar_cache_stats(&self) -> SimilarCacheStats {
+        self.similar_cache.stats()
+    }
+
+    /// Search with a query that has already been projected and normalized
+    fn search_prepared(
+        &self,
+        index: &HnswIndex,
+        query: &[f32],
+        k: usize,
+        filter: Option<&filter::Filter>,
+        exclude: Option<types::InternalId>,
+        ef: Option<usize>,
+    ) -> Result<(Vec<types::SearchHit>, SearchStats)> {
+        // We search for more candidates (2x k) to account for potential stale/deleted entries
+        // that might be filtered out.
+        let search_k = (k + usize::from(exclude.is_some())) * 2;
+        let nprobe = match &self.routing {
+            Some(router) if std::ptr::eq(index, &self.index) => Some(router.config().nprobe),
+            _ => None,
+        };
+        let (results, stats) = self.search_graph(index, query, search_k, ef, filter, nprobe)?;
+        Ok((self.map_hits(results, k, exclude), stats))
+    }
+
+    /// Walk `index`, confined to the `nprobe` nearest routing centroids when
+    /// given and routing is trained
+    fn search_graph(
+        &self,
+        index: &HnswIndex,
+        query: &[f32],
+        k: usize,
+        ef: Option<usize>,
+        filter: Option<&filter::Filter>,
+        nprobe: Option<usize>,
+    ) -> Result<(Vec<(types::InternalId, f32)>, SearchStats)> {
+        let storage = self.storage.view();
+        match (&self.routing, nprobe) {
+            (Some(router), Some(nprobe)) => {
+                router.catch_up(&self.storage);
+                router.route(query, nprobe, |route| {
+                    index.search_routed(query, k, ef, &storage, filter, Some(route))
+                })
+            }
+            _ => index.search_with_ef(query, k, ef, &storage, filter),
+        }
+    }
+
+    /// Map graph results to external IDs with metadata, dropping stale
+    /// entries and `exclude`
+    fn map_hits(
+        &self,
+        results: Vec<(types::InternalId, f32)>,
+        k: usize,
+        exclude: Option<types::InternalId>,
+    ) -> Vec<types::SearchHit> {
+        // Map internal IDs back to external IDs and fetch metadata
+        // Filter out stale entries (where internal_id doesn't match current mapping)
+        results
+            .into_iter()
+            .filter(|(internal_id, _)| Some(*internal_id) != exclude)
+            .filter_map(|(internal_id, distance)| {
+                let ext_id = self.storage.get_external_id(internal_id)?;
+                let current_internal = self.storage.get_internal_id(&ext_id)?;
+
+                if current_internal != internal_id {
+                    // This is a stale entry (overwritten by upsert)
+                    return None;
+                }
+
+                let metadata = self.with_defaults(self.storage.get_metadata(internal_id));
+                Some((ext_id, distance, metadata))
+            })
+            .take(k)
+            .collect()
+    }
+
+    /// Search for the k nearest neighbors into a caller-owned buffer
+    ///
+    /// The query is borrowed all the way down to the distance kernels and
+    /// `out` is cleared and refilled, so callers issuing many queries can reuse
+    /// one buffer. Metadata is not fetched. Stale and deleted entries are
+    /// already excluded during graph traversal, so no over-fetch is needed.
+    pub fn search_ref(
+        &self,
+        query: &[f32],
+        k: usize,
+        filter: Option<&filter::Filter>,
+        out: &mut Vec<(VectorId, f32)>,
+    ) -> Result<()> {
+        out.clear();
+
+        self.query_norms.check(query, self.config.distance_metric)?;
+        let query = &*projection::prepare_normalized(
+            self.projection.as_ref(),
+            self.config.dimensions,
+            self.config.dimension_mismatch,
+            self.config.normalize,
+            query,
+        )?;
+
+        let results = self.index.search(query, k, &self.storage.view(), filter)?;
+
+        out.extend(results.into_iter().filter_map(|(internal_id, distance)| {
+            self.storage
+                .get_external_id(internal_id)
+                .map(|ext_id| (ext_id, distance))
+        }));
+
+        Ok(())
+    }
+
+    /// Search for the k nearest neighbors (without metadata)
+    pub fn search_ids(
+        &self,
+        query: &[f32],
+        k: usize,
+        filter: Option<&filter::Filter>,
+    ) -> Result<Vec<(VectorId, f32)>> {
+        self.query_norms.check(query, self.config.distance_metric)?;
+        let query = &*projection::prepare_normalized(
+            self.projection.as_ref(),
+            self.config.dimensions,
+            self.config.dimension_mismatch,
+            self.config.normalize,
+            query,
+        )?;
+
+        let search_k = k * 2;
+        let results = self
+            .index
+            .search(query, search_k, &self.storage.view(), filter)?;
+
+        let mapped: Vec<(VectorId, f32)> = results
+            .into_iter()
+            .filter_map(|(internal_id, distance)| {
+                let ext_id = self.storage.get_external_id(internal_id)?;
+                let current_internal = self.storage.get_internal_id(&ext_id)?;
+                if current_internal != internal_id {
+                    return None;
+                }
+                Some((ext_id, distance))
+            })
+            .take(k)
+            .collect();
+
+        Ok(mapped)
+    }
+
+    /// Drop every vector and alias, keeping the configuration
+    ///
+    /// Storage slots and the graphs start over, so the next insert takes
+    /// internal ID 0 again.
+    pub fn clear(&mut self) {
+        let mut storage = VectorStorage::new(self.storage.dimensions());
+        storage.set_metadata_format(&self.config.metadata_format);
+        self.storage = storage;
+        for index in self.indexes() {
+            index.clear();
+        }
+        self.aliases = types::Aliases::default();
+        self.content_hashes = types::ContentHashes::default();
+        self.query_norms = types::QueryNorms::new(self.config.validate_query);
+        self.routing = None;
+        *self.next_auto_id.get_mut() = 0;
+        self.similar_cache.invalidate();
+        self.compactor.invalidate();
+    }
+
+    /// Rebuild storage and the graphs without deleted or superseded slots,
+    /// returning how many slots were reclaimed
+    ///
+    /// Live vectors are copied into a fresh flat buffer in slot order, so
+    /// internal IDs are renumbered, and every graph is rebuilt over the new
+    /// IDs (in parallel with `HnswConfig::parallel_build`). Trained routing
+    /// is retrained with its previous settings. Collections in a `Database`
+    /// run this in the background once `Config::auto_compact_fraction` is
+    /// reached.
+    pub fn compact(&mut self) -> Result<usize> {
+        if self.deleted_count() == 0 {
+            return Ok(0);
+        }
+        compaction::compact(self)
+    }
+
+    /// Whether deleted and superseded slots are past
+    /// `Config::auto_compact_fraction`
+    pub fn needs_compaction(&self) -> bool {
+        compaction::due(
+            self.config.auto_compact_fraction,
+            self.deleted_count(),
+            self.physical_len(),
+        )
+    }
+
+    /// Get the number of live vectors in the database (excluding deleted ones)
+    pub fn len(&self) -> usize {
+        self.storage.len()
+    }
+
+    /// Get the number of physical slots, including deleted and superseded vectors
+    /// that still occupy storage until compaction
+    pub fn physical_len(&self) -> usize {
+        self.storage.total_slots()
+    }
+
+    /// Get the number of deleted or superseded slots awaiting `compact`
+    pub fn deleted_count(&self) -> usize {
+        self.storage.deleted_count()
+    }
+
+    /// Check if the database is empty
+    pub fn is_empty(&self) -> bool {
+        self.storage.is_empty()
+    }
+
+    /// Get configuration
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Merge the collection's default metadata into a vector's metadata
+    fn with_defaults(&self, metadata: Option<Value>) -> Option<Value> {
+        types::merge_default_metadata(self.config.default_metadata.as_ref(), metadata)
+    }
+
+    /// The primary graph followed by one graph per additional metric
+    fn indexes(&self) -> impl Iterator<Item = &HnswIndex> {
+        std::iter::once(&self.index).chain(self.extra_indexes.iter().map(|(_, index)| index))
+    }
+
+    /// Graph built for `metric`, if the collection maintains one
+    fn index_for(&self, metric: DistanceMetric) -> Result<&HnswIndex> {
+        if metric == self.config.distance_metric {
+            return Ok(&self.index);
+        }
+        self.extra_indexes
+            .iter()
+            .find(|(m, _)| *m == metric)
+            .map(|(_, index)| index)
+            .ok_or_else(|| {
+                Error::InvalidConfig(format!(
+                    "distance metric {:?} is not indexed for this collection; \
+                     use an exact search to query it",
+                    metric
+                ))
+            })
+    }
+
+    /// Run graph maintenance: re-prune HNSW neighbor lists that exceed the
+    /// configured maximum degree. Returns the audit of the primary graph
+    /// taken before the repair; additional-metric graphs are repaired too.
+    pub fn optimize(&self) -> DegreeReport {
+        for (_, index) in &self.extra_indexes {
+            index.auto_repair_degrees(&self.storage);
+        }
+        self.index.auto_repair_degrees(&self.storage)
+    }
+
+    /// Check index health by searching for up to `sample` stored vectors and
+    /// counting how many find themselves in their top `k`
+    pub fn self_recall(&self, sample: usize, k: usize, seed: u64) -> SelfRecall {
+        self.index.self_recall(&self.storage, sample, k, seed)
+    }
+
+    /// Cluster the stored vectors around `config.num_centroids` k-means
+    /// centroids, after which searches on the primary metric only walk the
+    /// graph nodes of the `config.nprobe` centroids nearest each query
+    ///
+    /// Suits collections with natural clusters; recall drops when a query's
+    /// neighbors spread over more clusters than are probed. Vectors inserted
+    /// later are assigned to the existing centroids, so retrain after the
+    /// data drifts. Routing is not serialized and is dropped by `clear`.
+    pub fn train_routing(&mut self, config: RoutingConfig) -> Result<()> {
+        let metric = if self.config.normalize {
+            self.config.distance_metric.for_normalized()
+        } else {
+            self.config.distance_metric
+        };
+        self.routing = Some(routing::CentroidRouter::train(
+            &self.storage,
+            metric,
+            config,
+        )?);
+        self.similar_cache.invalidate();
+        Ok(())
+    }
+
+    /// Go back to searching the whole graph
+    pub fn clear_routing(&mut self) {
+        self.routing = None;
+        self.similar_cache.invalidate();
+    }
+
+    /// Settings of the trained routing, if any
+    pub fn routing(&self) -> Option<&RoutingConfig> {
+        self.routing.as_ref().map(routing::CentroidRouter::config)
+    }
+
+    /// Get approximate memory usage in bytes
+    pub fn memory_usage(&self) -> usize {
+        self.storage.memory_usage()
+            + self.indexes().map(HnswIndex::memory_usage).sum::<usize>()
+            + self
+                .projection
+                .as_ref()
+                .map_or(0, RandomProjection::memory_usage)
+            + self
+                .routing
+                .as_ref()
+                .map_or(0, routing::CentroidRouter::memory_usage)
+    }
+
+    /// Serialize the live vectors, their aliases and the HNSW graph in the
+    /// snapshot format, for targets without a filesystem
+    ///
+    /// The graph is only written while storage has no deleted or superseded
+    /// slots, since its nodes are positional; otherwise `from_bytes` rebuilds
+    /// it from the vectors.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let _guard = self.insert_lock.read();
+        let mut snapshot = Snapshot::new(0, 0, self.storage.dimensions());
+        for internal_id in self.storage.all_internal_ids() {
+            let Some(id) = self.storage.get_external_id(internal_id) else {
+                continue;
+            };
+            if self.storage.get_internal_id(&id) != Some(internal_id) {
+                continue;
+            }
+            if let Some(vector) = self.storage.get(internal_id) {
+                snapshot.add_vector(id, vector, self.storage.get_metadata(internal_id));
+            }
+        }
+        snapshot.aliases = self
+            .aliases
+            .iter()
+            .map(|(alias, primary)| (alias.clone(), primary.clone()))
+            .collect();
+        if self.storage.total_slots() == snapshot.len() {
+            snapshot.set_hnsw_state(self.index.get_state());
+        }
+
+        let mut bytes = Vec::new();
+        snapshot.write_to(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Restore a database serialized by [`VectorDb::to_bytes`]
+    ///
+    /// `config` must describe the same stored dimensionality; graphs for
+    /// `Config::additional_metrics` are rebuilt.
+    pub fn from_bytes(config: Config, bytes: &[u8]) -> Result<Self> {
+        let snapshot = Snapshot::read_from(bytes, "serialized database")?;
+        let mut db = Self::new(config)?;
+        if snapshot.dimensions != db.storage.dimensions() {
+            return Err(Error::InvalidConfig(format!(
+                "Snapshot dimensions ({}) don't match config ({})",
+                snapshot.dimensions,
+                db.storage.dimensions()
+            )));
+        }
+
+        let observe_norms = db.projection.is_none() && !db.config.normalize;
+        let internal_ids = db.storage.insert_batch(
+            snapshot
+                .vectors
+                .iter()
+                .map(|stored| {
+                    if observe_norms {
+                        db.query_norms.observe(&stored.vector);
+                    }
+                    (
+                        stored.id.clone(),
+                        stored.vector.as_slice(),
+                        stored.metadata.clone(),
+                    )
+                })
+                .collect(),
+        )?;
+        for (alias, primary) in snapshot.aliases {
+            db.aliases.restore(alias, primary);
+        }
+
+        let items: Vec<(types::InternalId, &[f32])> = internal_ids
+            .into_iter()
+            .zip(
+                snapshot
+                    .vectors
+                    .iter()
+                    .map(|stored| stored.vector.as_slice()),
+            )
+            .collect();
+        match snapshot.hnsw_state {
+            Some(state) if state.nodes.len() == items.len() => db.index.load_state(state),
+            _ => db.index.insert_batch(&items, &db.storage)?,
+        }
+        for (_, index) in &db.extra_indexes {
+            index.insert_batch(&items, &db.storage)?;
+        }
+
+        Ok(db)
+    }
+}
+
+impl compaction::Compact for VectorDb {
+    type Storage = VectorStorage;
+
+    fn compactor(&self) -> &compaction::Compactor {
+        &self.compactor
+    }
+
+    fn compaction_due(&self) -> bool {
+        self.needs_compaction()
+    }
+
+    fn plan_compaction(&self) -> compaction::Plan<VectorStorage> {
+        let mut storage = VectorStorage::new(self.storage.dimensions());
+        storage.set_metadata_format(&self.config.metadata_format);
+        let graph = |metric: DistanceMetric| {
+            let metric = if self.config.normalize {
+                metric.for_normalized()
+            } else {
+                metric
+            };
+            HnswIndex::new(self.config.hnsw.clone(), metric)
+        };
+        let indexes = std::iter::once(self.config.distance_metric)
+            .chain(self.extra_indexes.iter().map(|(metric, _)| *metric))
+            .map(graph)
+            .collect();
+        compaction::Plan::new(&self.compactor, &self.storage, storage, indexes)
+    }
+
+    fn finish_compaction(
+        &mut self,
+        mut rebuilt: compaction::Rebuilt<VectorStorage>,
+    ) -> Result<usize> {
+        if !rebuilt.catch_up(
+            &self.compactor,
+            &self.storage,
+            self.config.hnsw.eager_edge_cleanup,
+        )? {
+            return Ok(0);
+        }
+        let reclaimed = self
+            .physical_len()
+            .saturating_sub(rebuilt.storage.total_slots());
+        let mut indexes = rebuilt.indexes.into_iter();
+        self.storage = rebuilt.storage;
+        if let Some(index) = indexes.next() {
+            self.index = index;
+        }
+        for ((_, extra), index) in self.extra_indexes.iter_mut().zip(indexes) {
+            *extra = index;
+        }
+        self.content_hashes = types::ContentHashes::default();
+        if let Some(config) = self.routing().cloned() {
+            self.train_routing(config)?;
+        }
+        self.similar_cache.invalidate();
+        Ok(reclaimed)
+    }
+}
+
+/// Quantized vector database with configurable compression
+///
+/// Uses SQ8 (4x compression) or Binary (32x compression) quantization
+/// to dramatically reduce memory usage with minimal accuracy loss.
+pub struct QuantizedVectorDb {
+    config: QuantizedConfig,
+    storage: QuantizedStorage,
     index: Option<HnswIndex>,
+    projection: Option<RandomProjection>,
+    query_norms: types::QueryNorms,
+    compactor: compaction::Compactor,
 }
 
 impl QuantizedVectorDb {
     /// Create a new quantized vector database
     pub fn new(config: QuantizedConfig) -> Result<Self> {
-        let storage = QuantizedStorage::new(
-            config.dimensions,
-            config.quantization,
-            config.keep_originals,
-        );
+        config.hnsw.validate()?;
+        config
+            .distance_metric
+            .check_storage(config.quantization == QuantizationType::Binary)?;
+        compaction::validate_fraction(config.auto_compact_fraction)?;
+        let projection = config
+            .projection
+            .map(|p| RandomProjection::new(config.dimensions, p))
+            .transpose()?;
+        let stored_dim = projection
+            .as_ref()
+            .map_or(config.dimensions, RandomProjection::target_dim);
+        let storage = QuantizedStorage::new(stored_dim, config.quantization, config.keep_originals);
 
-        let index = if config.quantization == QuantizationType::Binary {
-            None
-        } else {
+        let index = if config.use_index && config.quantization != QuantizationType::Binary {
             Some(HnswIndex::new(config.hnsw.clone(), config.distance_metric))
+        } else {
+            None
         };
 
         Ok(Self {
+            query_norms: types::QueryNorms::new(config.validate_query),
             config,
             storage,
             index,
+            projection,
+            compactor: compaction::Compactor::default(),
         })
     }
 
@@ -440,12 +1873,16 @@ impl QuantizedVectorDb {
     ) -> Result<()> {
         let id = id.into();
 
-        if vector.len() != self.config.dimensions {
-            return Err(Error::DimensionMismatch {
-                expected: self.config.dimensions,
-                got: vector.len(),
-            });
-        }
+        types::check_finite(vector, self.config.validate_finite)?;
+        types::check_unit_norm(vector, self.config.require_unit_norm)?;
+        self.query_norms.observe(vector);
+        let vector = &*projection::prepare(
+            self.projection.as_ref(),
+            self.config.dimensions,
+            self.config.dimension_mismatch,
+            vector,
+        )?;
+        types::check_metadata_size(metadata.as_ref(), self.config.max_metadata_bytes)?;
 
         let internal_id = self.storage.insert(id, vector, metadata)?;
 
@@ -459,7 +1896,16 @@ impl QuantizedVectorDb {
     /// Delete a vector by ID
     pub fn delete(&mut self, id: impl Into<VectorId>) -> Result<bool> {
         let id = id.into();
-        self.storage.delete(&id)
+        let previous = self.storage.get_internal_id(&id);
+        let deleted = self.storage.delete(&id)?;
+
+        if let (Some(index), Some(old)) = (&self.index, previous) {
+            if self.config.hnsw.eager_edge_cleanup {
+                index.remove(old, &self.storage);
+            }
+        }
+
+        Ok(deleted)
     }
 
     /// Insert or update a vector with the given ID and optional metadata
@@ -471,38 +1917,126 @@ impl QuantizedVectorDb {
     ) -> Result<()> {
         let id = id.into();
 
-        if vector.len() != self.config.dimensions {
-            return Err(Error::DimensionMismatch {
-                expected: self.config.dimensions,
-                got: vector.len(),
-            });
-        }
+        types::check_finite(vector, self.config.validate_finite)?;
+        types::check_unit_norm(vector, self.config.require_unit_norm)?;
+        self.query_norms.observe(vector);
+        let vector = &*projection::prepare(
+            self.projection.as_ref(),
+            self.config.dimensions,
+            self.config.dimension_mismatch,
+            vector,
+        )?;
+        types::check_metadata_size(metadata.as_ref(), self.config.max_metadata_bytes)?;
 
+        let previous = self.storage.get_internal_id(&id);
         let internal_id = self.storage.upsert(id, vector, metadata)?;
 
         if let Some(index) = &mut self.index {
             index.insert(internal_id, vector, &self.storage)?;
+
+            if self.config.hnsw.eager_edge_cleanup {
+                if let Some(old) = previous {
+                    index.remove(old, &self.storage);
+                }
+            }
         }
 
         Ok(())
     }
 
+    /// Merge a JSON patch into every vector matching `filter` (see
+    /// `VectorDb::update_metadata_by_filter`)
+    ///
+    /// Metadata is updated in place; quantized vectors are not touched.
+    pub fn update_metadata_by_filter(
+        &mut self,
+        filter: &filter::Filter,
+        patch: &Value,
+    ) -> Result<usize> {
+        let mut updates = Vec::new();
+        for internal_id in self.storage.all_internal_ids() {
+            let live = self
+                .storage
+                .get_external_id(internal_id)
+                .is_some_and(|id| self.storage.get_internal_id(&id) == Some(internal_id));
+            let before = self.storage.get_metadata(internal_id);
+            if !live || !before.as_ref().is_some_and(|meta| filter.matches(meta)) {
+                continue;
+            }
+            let after = types::merge_patch(before.clone(), patch);
+            if after != before {
+                types::check_metadata_size(after.as_ref(), self.config.max_metadata_bytes)?;
+                updates.push((internal_id, after));
+            }
+        }
+
+        let updated = updates.len();
+        for (internal_id, metadata) in updates {
+            self.storage.set_metadata(internal_id, metadata);
+        }
+        Ok(updated)
+    }
+
+    /// Replace or merge the metadata of the vector `id` (see
+    /// `VectorDb::set_metadata`)
+    pub fn set_metadata(&mut self, id: &str, metadata: Value, mode: MetadataUpdate) -> Result<()> {
+        let id = VectorId::from(id);
+        let internal_id = self
+            .storage
+            .get_internal_id(&id)
+            .ok_or_else(|| Error::VectorNotFound(id.to_string()))?;
+        let metadata = mode.apply(self.storage.get_metadata(internal_id), metadata);
+        types::check_metadata_size(Some(&metadata), self.config.max_metadata_bytes)?;
+        self.storage.update_metadata(&id, metadata)
+    }
+
     /// Batch insert/upsert vectors
-    pub fn upsert_batch(&mut self, items: Vec<(VectorId, Vec<f32>, Option<Value>)>) -> Result<()> {
+    pub fn upsert_batch(
+        &mut self,
+        mut items: Vec<(VectorId, Vec<f32>, Option<Value>)>,
+    ) -> Result<()> {
         if items.is_empty() {
             return Ok(());
         }
 
-        // Validate dimensions
-        for (_, vector, _) in &items {
+        // Validate dimensions, fitting mismatched vectors under the policy
+        for (_, vector, metadata) in &mut items {
+            types::check_finite(vector, self.config.validate_finite)?;
+            types::check_unit_norm(vector, self.config.require_unit_norm)?;
+            self.query_norms.observe(vector);
             if vector.len() != self.config.dimensions {
-                return Err(Error::DimensionMismatch {
-                    expected: self.config.dimensions,
-                    got: vector.len(),
-                });
+                *vector = self
+                    .config
+                    .dimension_mismatch
+                    .fit(self.config.dimensions, vector)?
+                    .into_owned();
             }
+            types::check_metadata_size(metadata.as_ref(), self.config.max_metadata_bytes)?;
         }
 
+        let items = if self.config.hnsw.shuffle_inserts {
+            types::shuffle_batch(items, self.config.hnsw.shuffle_seed)
+        } else {
+            items
+        };
+
+        let items = match &self.projection {
+            Some(projection) => items
+                .into_iter()
+                .map(|(id, vector, metadata)| (id, projection.project(&vector), metadata))
+                .collect(),
+            None => items,
+        };
+
+        let previous: Vec<types::InternalId> = if self.config.hnsw.eager_edge_cleanup {
+            items
+                .iter()
+                .filter_map(|(id, _, _)| self.storage.get_internal_id(id))
+                .collect()
+        } else {
+            Vec::new()
+        };
+
         // 1. Batch Upsert into Storage (Single lock acquisition)
         let internal_ids = self.storage.upsert_batch(&items)?;
 
@@ -515,44 +2049,103 @@ impl QuantizedVectorDb {
                 .collect();
 
             index.insert_batch(&hnsw_items, &self.storage)?;
+
+            for old in previous {
+                index.remove(old, &self.storage);
+            }
         }
 
         Ok(())
     }
 
-    /// Retrieve a vector by its external ID
+    /// Retrieve a vector and its metadata by external ID
+    ///
+    /// The vector is exact with `keep_originals` or without quantization.
+    /// Otherwise it is reconstructed and only approximates what was inserted:
+    /// SQ8 is off by up to half a quantization step per dimension, and Binary
+    /// keeps just each dimension's sign, as +1.0 or -1.0.
     pub fn get(&self, id: &str) -> Result<Option<(Vec<f32>, Option<Value>)>> {
         let id = VectorId::from(id);
         if let Some(internal_id) = self.storage.get_internal_id(&id) {
             let vector =
                 crate::storage::VectorStorageTrait::get_vector_data(&self.storage, internal_id)
                     .ok_or(Error::VectorNotFound(id.to_string()))?;
-            let metadata = self.storage.get_metadata(internal_id);
+            let metadata = self.with_defaults(self.storage.get_metadata(internal_id));
             Ok(Some((vector, metadata)))
         } else {
             Ok(None)
         }
     }
 
+    /// Get a vector's raw quantized bytes and, for SQ8, its min/scale parameters
+    ///
+    /// Returns `None` for unknown IDs. Feed the bytes and metadata to
+    /// `SQ8Quantizer::dequantize` to reconstruct the stored approximation.
+    pub fn get_quantized(&self, id: &str) -> Option<(Vec<u8>, Option<SQ8Metadata>)> {
+        let internal_id = self.storage.get_internal_id(&VectorId::from(id))?;
+        let bytes = self.storage.get_quantized_bytes(internal_id)?;
+        Some((bytes, self.storage.get_sq8_metadata(internal_id)))
+    }
+
     /// List all vector IDs and metadata (pagination)
     pub fn list(&self, offset: usize, limit: usize) -> Vec<(VectorId, Option<Value>)> {
         let ids = self.storage.all_internal_ids();
         ids.iter()
-            .filter_map(|&internal_id| {
-                // Filter stale
-                let ext_id = self.storage.get_external_id(internal_id)?;
-                let current_internal = self.storage.get_internal_id(&ext_id)?;
-                if current_internal != internal_id {
-                    return None;
-                }
-                let metadata = self.storage.get_metadata(internal_id);
-                Some((ext_id, metadata))
-            })
+            .filter_map(|&internal_id| self.list_entry(internal_id))
             .skip(offset)
             .take(limit)
             .collect()
     }
 
+    /// List up to `limit` vector IDs and metadata from a cursor (keyset pagination)
+    ///
+    /// `cursor` is the previous page's `next_cursor`, or `None` for the first
+    /// page. Unlike `list`, earlier entries are not re-scanned. The cursor is
+    /// a storage position, so it stays valid when vectors are updated or
+    /// deleted between pages; an updated vector moves to the end of the
+    /// listing. `compact` and `clear` renumber positions.
+    pub fn list_after(&self, cursor: Option<u64>, limit: usize) -> ListPage {
+        types::list_page(cursor, limit, self.physical_len(), |internal_id| {
+            self.list_entry(internal_id)
+        })
+    }
+
+    /// Resolve a storage slot to its listing entry, skipping stale slots
+    fn list_entry(&self, internal_id: types::InternalId) -> Option<(VectorId, Option<Value>)> {
+        let ext_id = self.storage.get_external_id(internal_id)?;
+        let current_internal = self.storage.get_internal_id(&ext_id)?;
+        if current_internal != internal_id {
+            return None;
+        }
+        let metadata = self.with_defaults(self.storage.get_metadata(internal_id));
+        Some((ext_id, metadata))
+    }
+
+    /// Count the most frequent values of a metadata field (dot paths allowed)
+    ///
+    /// Only stored metadata is counted, like filters: collection default
+    /// metadata is not included. Array values count once per element.
+    pub fn facet(&self, field: &str, top_n: usize) -> Facets {
+        self.facet_sampled(field, top_n, None)
+    }
+
+    /// Like `facet`, but with `sample` only about that many vectors are read
+    /// and the counts are scaled up to the collection size
+    pub fn facet_sampled(&self, field: &str, top_n: usize, sample: Option<usize>) -> Facets {
+        facet::aggregate(
+            field,
+            top_n,
+            sample,
+            self.physical_len(),
+            self.len(),
+            |internal_id| {
+                let ext_id = self.storage.get_external_id(internal_id)?;
+                (self.storage.get_internal_id(&ext_id)? == internal_id)
+                    .then(|| self.storage.get_metadata(internal_id))
+            },
+        )
+    }
+
     /// Search for the k nearest neighbors
     pub fn search(
         &self,
@@ -560,13 +2153,101 @@ impl QuantizedVectorDb {
         k: usize,
         filter: Option<&filter::Filter>,
     ) -> Result<Vec<(VectorId, f32, Option<Value>)>> {
-        if query.len() != self.config.dimensions {
-            return Err(Error::DimensionMismatch {
-                expected: self.config.dimensions,
-                got: query.len(),
-            });
+        self.search_with_stats(query, k, filter)
+            .map(|(results, _)| results)
+    }
+
+    /// Like `search`, also reporting filter rejections and whether the
+    /// search was exhaustive (see `VectorDb::search_with_stats`)
+    pub fn search_with_stats(
+        &self,
+        query: &[f32],
+        k: usize,
+        filter: Option<&filter::Filter>,
+    ) -> Result<(Vec<types::SearchHit>, SearchStats)> {
+        self.search_inner(query, k, None, filter)
+    }
+
+    /// Like `search`, with an HNSW candidate list of `ef` (see
+    /// `VectorDb::search_with_ef`); unindexed collections (Binary, or
+    /// `use_index: false`) scan every vector and ignore it
+    pub fn search_with_ef(
+        &self,
+        query: &[f32],
+        k: usize,
+        ef: usize,
+        filter: Option<&filter::Filter>,
+    ) -> Result<Vec<types::SearchHit>> {
+        self.search_inner(query, k, Some(ef), filter)
+            .map(|(results, _)| results)
+    }
+
+    /// `search_with_stats` with an optional candidate list of `ef` (see
+    /// `search_with_ef`)
+    pub fn search_with_ef_stats(
+        &self,
+        query: &[f32],
+        k: usize,
+        ef: Option<usize>,
+        filter: Option<&filter::Filter>,
+    ) -> Result<(Vec<types::SearchHit>, SearchStats)> {
+        self.search_inner(query, k, ef, filter)
+    }
+
+    /// Find the k nearest neighbors of a stored vector, given its ID (see
+    /// `VectorDb::search_by_id`)
+    ///
+    /// The query is the stored vector as `get` returns it, so without
+    /// `keep_originals` it is the quantized approximation.
+    pub fn search_by_id(
+        &self,
+        id: &str,
+        k: usize,
+        filter: Option<&filter::Filter>,
+        exclude_self: bool,
+    ) -> Result<Vec<types::SearchHit>> {
+        let id = VectorId::from(id);
+        let query = self
+            .storage
+            .get_internal_id(&id)
+            .and_then(|internal_id| {
+                crate::storage::VectorStorageTrait::get_vector_data(&self.storage, internal_id)
+            })
+            .ok_or_else(|| Error::VectorNotFound(id.to_string()))?;
+        let (mut results, _) =
+            self.search_prepared(&query, k + usize::from(exclude_self), None, filter)?;
+        if exclude_self {
+            results.retain(|(hit, _, _)| *hit != id);
         }
+        results.truncate(k);
+        Ok(results)
+    }
+
+    fn search_inner(
+        &self,
+        query: &[f32],
+        k: usize,
+        ef: Option<usize>,
+        filter: Option<&filter::Filter>,
+    ) -> Result<(Vec<types::SearchHit>, SearchStats)> {
+        self.query_norms.check(query, self.config.distance_metric)?;
+        let query = &*projection::prepare(
+            self.projection.as_ref(),
+            self.config.dimensions,
+            self.config.dimension_mismatch,
+            query,
+        )?;
+        self.search_prepared(query, k, ef, filter)
+    }
 
+    /// `search_inner` for a query already projected into the stored space
+    fn search_prepared(
+        &self,
+        query: &[f32],
+        k: usize,
+        ef: Option<usize>,
+        filter: Option<&filter::Filter>,
+    ) -> Result<(Vec<types::SearchHit>, SearchStats)> {
         if self.storage.is_empty() {
             return Err(Error::EmptyIndex);
         }
@@ -584,37 +2265,42 @@ impl QuantizedVectorDb {
         let search_k = k * multiplier * 2;
 
         // Use HNSW if available
+        let mut stats = SearchStats::default();
         let results: Vec<(types::InternalId, f32)> = if let Some(index) = &self.index {
             // HNSW Search
-            index.search(query, search_k, &self.storage.view(), filter)?
+            let (results, index_stats) =
+                index.search_with_ef(query, search_k, ef, &self.storage.view(), filter)?;
+            stats = index_stats;
+            results
         } else {
             // Fallback to Brute Force
             let storage_view = self.storage.view();
             let quantized_query = self.storage.quantize_query(query);
+            stats.exhausted = true;
 
-            let mut candidates: Vec<(types::InternalId, f32)> = self
+            let candidates = self
                 .storage
                 .all_internal_ids()
                 .into_iter()
                 .filter(|&id| {
-                    if let Some(f) = filter {
-                        if let Some(meta) = self.storage.get_metadata(id) {
-                            f.matches(&meta)
-                        } else {
-                            false
-                        }
-                    } else {
-                        true
+                    let matches = match filter {
+                        Some(f) => self
+                            .storage
+                            .get_metadata(id)
+                            .is_some_and(|meta| f.matches(&meta)),
+                        None => true,
+                    };
+                    if !matches && self.list_entry(id).is_some() {
+                        stats.filtered_out += 1;
                     }
+                    matches
                 })
                 .filter_map(|id| {
                     storage_view
                         .distance_quantized(query, &quantized_query, id, metric)
                         .map(|dist| (id, dist))
-                })
-                .collect();
-            candidates.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
-            candidates.into_iter().take(search_k).collect()
+                });
+            types::top_k(candidates, search_k)
         };
 
         // Filter stale results
@@ -630,25 +2316,10 @@ impl QuantizedVectorDb {
             })
             .collect();
 
-        // If re-ranking is enabled
-        let final_results: Vec<(types::InternalId, f32)> =
-            if self.config.keep_originals && self.config.quantization != QuantizationType::None {
-                let k_rerank = k * self.config.rerank_multiplier;
-                let top_candidates: Vec<_> = valid_candidates.into_iter().take(k_rerank).collect();
-
-                // Re-rank using original vectors
-                let mut reranked: Vec<_> = top_candidates
-                    .into_iter()
-                    .filter_map(|(id, _)| {
-                        self.storage.get_original(id).map(|orig| {
-                            let dist = metric.distance(query, &orig);
-                            (id, dist)
-                        })
-                    })
-                    .collect();
-
-                reranked.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
-                reranked.into_iter().take(k).collect()
+        // If re-ranking is enabled
+        let final_results: Vec<(types::InternalId, f32)> =
+            if self.config.keep_originals && self.config.quantization != QuantizationType::None {
+                self.rerank(query, valid_candidates, k)
             } else {
                 valid_candidates.into_iter().take(k).collect()
             };
@@ -658,13 +2329,13 @@ impl QuantizedVectorDb {
             .into_iter()
             .filter_map(|(internal_id, distance)| {
                 self.storage.get_external_id(internal_id).map(|ext_id| {
-                    let metadata = self.storage.get_metadata(internal_id);
+                    let metadata = self.with_defaults(self.storage.get_metadata(internal_id));
                     (ext_id, distance, metadata)
                 })
             })
             .collect();
 
-        Ok(mapped)
+        Ok((mapped, stats))
     }
 
     /// Search for the k nearest neighbors (without metadata)
@@ -674,12 +2345,13 @@ impl QuantizedVectorDb {
         k: usize,
         filter: Option<&filter::Filter>,
     ) -> Result<Vec<(VectorId, f32)>> {
-        if query.len() != self.config.dimensions {
-            return Err(Error::DimensionMismatch {
-                expected: self.config.dimensions,
-                got: query.len(),
-            });
-        }
+        self.query_norms.check(query, self.config.distance_metric)?;
+        let query = &*projection::prepare(
+            self.projection.as_ref(),
+            self.config.dimensions,
+            self.config.dimension_mismatch,
+            query,
+        )?;
 
         if self.storage.is_empty() {
             return Err(Error::EmptyIndex);
@@ -700,7 +2372,7 @@ impl QuantizedVectorDb {
             let storage_view = self.storage.view();
             let quantized_query = self.storage.quantize_query(query);
 
-            let mut candidates: Vec<(types::InternalId, f32)> = self
+            let candidates = self
                 .storage
                 .all_internal_ids()
                 .into_iter()
@@ -719,10 +2391,8 @@ impl QuantizedVectorDb {
                     storage_view
                         .distance_quantized(query, &quantized_query, id, metric)
                         .map(|dist| (id, dist))
-                })
-                .collect();
-            candidates.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
-            candidates.into_iter().take(search_k).collect()
+                });
+            types::top_k(candidates, search_k)
         };
 
         let valid_candidates: Vec<(types::InternalId, f32)> = results
@@ -739,21 +2409,7 @@ impl QuantizedVectorDb {
 
         let final_results: Vec<(types::InternalId, f32)> =
             if self.config.keep_originals && self.config.quantization != QuantizationType::None {
-                let k_rerank = k * self.config.rerank_multiplier;
-                let top_candidates: Vec<_> = valid_candidates.into_iter().take(k_rerank).collect();
-
-                let mut reranked: Vec<_> = top_candidates
-                    .into_iter()
-                    .filter_map(|(id, _)| {
-                        self.storage.get_original(id).map(|orig| {
-                            let dist = metric.distance(query, &orig);
-                            (id, dist)
-                        })
-                    })
-                    .collect();
-
-                reranked.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
-                reranked.into_iter().take(k).collect()
+                self.rerank(query, valid_candidates, k)
             } else {
                 valid_candidates.into_iter().take(k).collect()
             };
@@ -770,11 +2426,97 @@ impl QuantizedVectorDb {
         Ok(mapped)
     }
 
-    /// Get the number of vectors in the database
+    /// Re-rank quantized candidates (sorted by approximate distance) using the
+    /// original vectors, returning the top k by exact distance
+    fn rerank(
+        &self,
+        query: &[f32],
+        candidates: Vec<(types::InternalId, f32)>,
+        k: usize,
+    ) -> Vec<(types::InternalId, f32)> {
+        // A wide quantized gap after the k-th candidate means the top-k set is
+        // settled, so only those k need exact distances
+        let unambiguous = self.config.adaptive_rerank
+            && k > 0
+            && candidates.len() > k
+            && candidates[k].1 - candidates[k - 1].1 > self.config.rerank_gap_threshold;
+        let k_rerank = if unambiguous {
+            k
+        } else {
+            k * self.config.rerank_multiplier
+        };
+
+        let metric = self.config.distance_metric;
+        let mut reranked: Vec<_> = candidates
+            .into_iter()
+            .take(k_rerank)
+            .filter_map(|(id, _)| {
+                self.storage
+                    .get_original(id)
+                    .map(|orig| (id, metric.distance(query, &orig)))
+            })
+            .collect();
+
+        reranked.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+        reranked.truncate(k);
+        reranked
+    }
+
+    /// Drop every vector, keeping the configuration
+    ///
+    /// Storage slots and the graph start over, so the next insert takes
+    /// internal ID 0 again.
+    pub fn clear(&mut self) {
+        self.storage = QuantizedStorage::new(
+            self.storage.dimensions(),
+            self.config.quantization,
+            self.config.keep_originals,
+        );
+        if let Some(index) = &self.index {
+            index.clear();
+        }
+        self.query_norms = types::QueryNorms::new(self.config.validate_query);
+        self.compactor.invalidate();
+    }
+
+    /// Rebuild storage and the graph without deleted or superseded slots,
+    /// returning how many slots were reclaimed (see `VectorDb::compact`)
+    ///
+    /// Without `keep_originals`, vectors are re-quantized from their
+    /// reconstruction.
+    pub fn compact(&mut self) -> Result<usize> {
+        if self.deleted_count() == 0 {
+            return Ok(0);
+        }
+        compaction::compact(self)
+    }
+
+    /// Whether deleted and superseded slots are past
+    /// `QuantizedConfig::auto_compact_fraction`
+    pub fn needs_compaction(&self) -> bool {
+        compaction::due(
+            self.config.auto_compact_fraction,
+            self.deleted_count(),
+            self.physical_len(),
+        )
+    }
+
+    /// Get the number of live vectors in the database (excluding deleted ones)
     pub fn len(&self) -> usize {
         self.storage.len()
     }
 
+    /// Get the number of physical slots, including deleted and superseded vectors
+    /// that still occupy storage until compaction
+    pub fn physical_len(&self) -> usize {
+        self.storage.total_slots()
+    }
+
+    /// Get the number of deleted or superseded slots awaiting `compact`
+    pub fn deleted_count(&self) -> usize {
+        self.physical_len() - self.len()
+    }
+
     /// Check if the database is empty
     pub fn is_empty(&self) -> bool {
         self.storage.is_empty()
@@ -785,55 +2527,754 @@ impl QuantizedVectorDb {
         &self.config
     }
 
+    /// Merge the collection's default metadata into a vector's metadata
+    fn with_defaults(&self, metadata: Option<Value>) -> Option<Value> {
+        types::merge_default_metadata(self.config.default_metadata.as_ref(), metadata)
+    }
+
+    /// Run graph maintenance: re-prune HNSW neighbor lists that exceed the
+    /// configured maximum degree. Returns the audit taken before the repair.
+    pub fn optimize(&self) -> DegreeReport {
+        self.index
+            .as_ref()
+            .map(|index| index.auto_repair_degrees(&self.storage))
+            .unwrap_or_default()
+    }
+
+    /// Check index health by searching for up to `sample` stored vectors and
+    /// counting how many find themselves in their top `k`. `None` without an
+    /// index.
+    pub fn self_recall(&self, sample: usize, k: usize, seed: u64) -> Option<SelfRecall> {
+        self.index
+            .as_ref()
+            .map(|index| index.self_recall(&self.storage, sample, k, seed))
+    }
+
+    /// Edges of HNSW layer `layer` as `(from, to)` external IDs (see
+    /// `VectorDb::graph_edges`). `None` without an index.
+    pub fn graph_edges(
+        &self,
+        layer: usize,
+    ) -> Option<impl Iterator<Item = (VectorId, VectorId)> + '_> {
+        let live = |internal_id| {
+            let ext_id = self.storage.get_external_id(internal_id)?;
+            (self.storage.get_internal_id(&ext_id)? == internal_id).then_some(ext_id)
+        };
+        let index = self.index.as_ref()?;
+        Some(
+            index
+                .export_edges()
+                .filter(move |&(_, _, edge_layer)| edge_layer == layer)
+                .filter_map(move |(from, to, _)| Some((live(from)?, live(to)?))),
+        )
+    }
+
     /// Get approximate memory usage in bytes
     pub fn memory_usage(&self) -> usize {
-        self.storage.memory_usage() + self.index.as_ref().map(|i| i.memory_usage()).unwrap_or(0)
+        self.storage.memory_usage()
+            + self.index.as_ref().map(|i| i.memory_usage()).unwrap_or(0)
+            + self
+                .projection
+                .as_ref()
+                .map_or(0, RandomProjection::memory_usage)
     }
 
     /// Get compression ratio compared to unquantized storage
     pub fn compression_ratio(&self) -> f32 {
         self.storage.compression_ratio()
     }
+
+    /// Report reconstruction error and recall@10 on about `sample` evenly
+    /// spaced live vectors, using the stored originals as ground truth
+    ///
+    /// Requires `keep_originals`; otherwise pass the originals to
+    /// `accuracy_report_for`. Recall compares quantized and exact distances
+    /// within the sample, so it costs `O(sample²)` distance computations.
+    pub fn accuracy_report(&self, sample: usize) -> Result<AccuracyReport> {
+        if !self.config.keep_originals && self.config.quantization != QuantizationType::None {
+            return Err(Error::InvalidConfig(
+                "accuracy_report requires keep_originals; use accuracy_report_for".to_string(),
+            ));
+        }
+
+        let live: Vec<types::InternalId> = (0..self.physical_len())
+            .map(types::InternalId::from)
+            .filter(|&internal_id| self.list_entry(internal_id).is_some())
+            .collect();
+        let stride = live.len().div_ceil(sample.max(1)).max(1);
+        let samples: Vec<_> = live
+            .into_iter()
+            .step_by(stride)
+            .filter_map(|internal_id| {
+                crate::storage::VectorStorageTrait::get_vector_data(&self.storage, internal_id)
+                    .map(|original| (internal_id, original))
+            })
+            .collect();
+
+        Ok(accuracy::evaluate(
+            &self.storage,
+            self.config.distance_metric,
+            &samples,
+        ))
+    }
+
+    /// Like `accuracy_report`, with caller-provided `(id, original vector)`
+    /// pairs as ground truth, for collections that do not keep originals
+    pub fn accuracy_report_for(
+        &self,
+        originals: &[(VectorId, Vec<f32>)],
+    ) -> Result<AccuracyReport> {
+        let samples = originals
+            .iter()
+            .map(|(id, vector)| {
+                let internal_id = self
+                    .storage
+                    .get_internal_id(id)
+                    .ok_or_else(|| Error::VectorNotFound(id.to_string()))?;
+                let vector = projection::prepare(
+                    self.projection.as_ref(),
+                    self.config.dimensions,
+                    self.config.dimension_mismatch,
+                    vector,
+                )?;
+                Ok((internal_id, vector.into_owned()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(accuracy::evaluate(
+            &self.storage,
+            self.config.distance_metric,
+            &samples,
+        ))
+    }
+}
+
+impl compaction::Compact for QuantizedVectorDb {
+    type Storage = QuantizedStorage;
+
+    fn compactor(&self) -> &compaction::Compactor {
+        &self.compactor
+    }
+
+    fn compaction_due(&self) -> bool {
+        self.needs_compaction()
+    }
+
+    fn plan_compaction(&self) -> compaction::Plan<QuantizedStorage> {
+        let storage = QuantizedStorage::new(
+            self.storage.dimensions(),
+            self.config.quantization,
+            self.config.keep_originals,
+        );
+        let indexes = self
+            .index
+            .iter()
+            .map(|_| HnswIndex::new(self.config.hnsw.clone(), self.config.distance_metric))
+            .collect();
+        compaction::Plan::new(&self.compactor, &self.storage, storage, indexes)
+    }
+
+    fn finish_compaction(
+        &mut self,
+        mut rebuilt: compaction::Rebuilt<QuantizedStorage>,
+    ) -> Result<usize> {
+        if !rebuilt.catch_up(
+            &self.compactor,
+            &self.storage,
+            self.config.hnsw.eager_edge_cleanup,
+        )? {
+            return Ok(0);
+        }
+        let reclaimed = self
+            .physical_len()
+            .saturating_sub(rebuilt.storage.total_slots());
+        self.storage = rebuilt.storage;
+        if let Some(index) = rebuilt.indexes.pop() {
+            self.index = Some(index);
+        }
+        Ok(reclaimed)
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_basic_insert_and_search() {
+        let config = Config {
+            dimensions: 4,
+            ..Default::default()
+        };
+
+        let mut db = VectorDb::new(config).unwrap();
+
+        db.insert("vec1", &[1.0, 0.0, 0.0, 0.0], None).unwrap();
+        db.insert("vec2", &[0.0, 1.0, 0.0, 0.0], None).unwrap();
+        db.insert("vec3", &[0.9, 0.1, 0.0, 0.0], None).unwrap();
+
+        let results = db.search(&[1.0, 0.0, 0.0, 0.0], 2, None).unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0.as_str(), "vec1"); // Exact match should be first
+    }
+
+    #[test]
+    fn test_insert_with_metadata() {
+        let config = Config {
+            dimensions: 4,
+            ..Default::default()
+        };
+
+        let mut db = VectorDb::new(config).unwrap();
+        let meta = serde_json::json!({"type": "test"});
+
+        db.insert("vec1", &[1.0, 0.0, 0.0, 0.0], Some(meta.clone()))
+            .unwrap();
+
+        let results = db.search(&[1.0, 0.0, 0.0, 0.0], 1, None).unwrap();
+        assert_eq!(results[0].2, Some(meta));
+    }
+
+    #[test]
+    fn test_insert_batch_indexes_all_or_nothing() {
+        let mut db = VectorDb::new(Config {
+            dimensions: 4,
+            ..Default::default()
+        })
+        .unwrap();
+        db.insert("a", &[0.0, 0.0, 1.0, 0.0], None).unwrap();
+
+        let vector = |i: usize| vec![1.0, i as f32, 0.5, -(i as f32)];
+        let items = (0..50)
+            .map(|i| (VectorId::from(format!("v{}", i)), vector(i), None))
+            .collect();
+        db.insert_batch(items).unwrap();
+        assert_eq!(db.len(), 51);
+        for i in [0, 17, 49] {
+            let results = db.search(&vector(i), 1, None).unwrap();
+            assert_eq!(results[0].0.as_str(), format!("v{}", i));
+        }
+
+        // A stored ID or a bad vector anywhere in the batch rejects all of it
+        let clash = vec![
+            (VectorId::from("new"), vector(60), None),
+            (VectorId::from("a"), vector(61), None),
+        ];
+        assert!(matches!(db.insert_batch(clash), Err(Error::DuplicateId(_))));
+        let short = vec![
+            (VectorId::from("new"), vector(60), None),
+            (VectorId::from("bad"), vec![1.0], None),
+        ];
+        assert!(db.insert_batch(short).is_err());
+        assert_eq!(db.len(), 51);
+        assert!(db.get("new").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_columnar_metadata_filters_like_json() {
+        use crate::filter::Filter;
+        use serde_json::json;
+        let make = |metadata_format| {
+            VectorDb::new(Config {
+                dimensions: 4,
+                distance_metric: DistanceMetric::Euclidean,
+                metadata_format,
+                ..Default::default()
+            })
+            .unwrap()
+        };
+        let mut json_db = make(MetadataFormat::Json);
+        let mut columnar_db = make(MetadataFormat::Columnar(vec![
+            ColumnSpec::new("category", ColumnKind::String),
+            ColumnSpec::new("price", ColumnKind::Integer),
+            ColumnSpec::new("rating", ColumnKind::Float),
+            ColumnSpec::new("in_stock", ColumnKind::Bool),
+        ]));
+
+        let categories = ["books", "games", "music"];
+        for i in 0..300usize {
+            let metadata = match i % 10 {
+                // Mistyped, missing and non-object metadata bypass the columns
+                7 => Some(json!({"category": i, "price": "cheap", "rating": 3})),
+                8 => Some(json!(["books", i])),
+                9 => None,
+                _ => Some(json!({
+                    "category": categories[i % 3],
+                    "price": (i % 50) as i64 - 10,
+                    "rating": (i % 9) as f64 / 2.0,
+                    "in_stock": i % 4 == 0,
+                    "shop": {"city": if i % 2 == 0 { "berlin" } else { "paris" }},
+                })),
+            };
+            let vector = [i as f32, (i % 17) as f32, 1.0, -(i as f32)];
+            json_db
+                .insert(i.to_string(), &vector, metadata.clone())
+                .unwrap();
+            columnar_db
+                .insert(i.to_string(), &vector, metadata)
+                .unwrap();
+        }
+        for i in 0..300 {
+            let id = i.to_string();
+            assert_eq!(
+                json_db.get(id.as_str()).unwrap(),
+                columnar_db.get(id.as_str()).unwrap()
+            );
+        }
+
+        let range = |field: &str, gte: f64, lt: f64| Filter::Range {
+            field: field.into(),
+            gt: None,
+            gte: Some(gte),
+            lt: Some(lt),
+            lte: None,
+        };
+        let filters = [
+            Filter::Exact("category".into(), json!("games")),
+            Filter::Exact("price".into(), json!(5)),
+            Filter::Exact("price".into(), json!(5.0)),
+            Filter::Exact("rating".into(), json!(1.5)),
+            Filter::Exact("in_stock".into(), json!(true)),
+            Filter::Exact("price".into(), json!("cheap")),
+            Filter::OneOf("category".into(), vec![json!("books"), json!(17)]),
+            range("price", 0.0, 20.0),
+            range("rating", 1.0, 3.0),
+            range("category", 0.0, 100.0),
+            Filter::Exact("shop.city".into(), json!("paris")),
+            Filter::And(vec![
+                range("price", -5.0, 30.0),
+                Filter::Not(Box::new(Filter::Exact("category".into(), json!("music")))),
+            ]),
+            Filter::Or(vec![
+                Filter::Exact("in_stock".into(), json!(true)),
+                range("rating", 3.5, 10.0),
+            ]),
+        ];
+        let query = [150.0, 8.0, 1.0, -150.0];
+        for filter in &filters {
+            let search = |db: &VectorDb| {
+                db.search_exact_with_metric(&query, 300, DistanceMetric::Euclidean, Some(filter))
+                    .unwrap()
+            };
+            assert_eq!(search(&columnar_db), search(&json_db), "{:?}", filter);
+
+            for (_, _, metadata) in columnar_db.search(&query, 20, Some(filter)).unwrap() {
+                assert!(filter.matches(&metadata.unwrap()), "{:?}", filter);
+            }
+        }
+        assert!(columnar_db.memory_usage() < json_db.memory_usage());
+    }
+
+    #[test]
+    fn test_default_metadata_merged_into_results() {
+        let config = Config {
+            dimensions: 4,
+            default_metadata: Some(serde_json::json!({"tenant": "acme", "tier": "free"})),
+            ..Default::default()
+        };
+
+        let mut db = VectorDb::new(config).unwrap();
+        db.insert(
+            "vec1",
+            &[1.0, 0.0, 0.0, 0.0],
+            Some(serde_json::json!({"tier": "pro", "title": "a"})),
+        )
+        .unwrap();
+        db.insert("vec2", &[0.0, 1.0, 0.0, 0.0], None).unwrap();
+
+        let results = db.search(&[1.0, 0.0, 0.0, 0.0], 2, None).unwrap();
+        assert_eq!(results[0].0.as_str(), "vec1");
+        // Per-vector keys override defaults
+        assert_eq!(
+            results[0].2,
+            Some(serde_json::json!({"tenant": "acme", "tier": "pro", "title": "a"}))
+        );
+        // Vectors without metadata still get the defaults
+        assert_eq!(
+            results[1].2,
+            Some(serde_json::json!({"tenant": "acme", "tier": "free"}))
+        );
+
+        let (_, meta) = db.get("vec2").unwrap().unwrap();
+        assert_eq!(
+            meta,
+            Some(serde_json::json!({"tenant": "acme", "tier": "free"}))
+        );
+    }
+
+    #[test]
+    fn test_neighborhood_resolves_graph() {
+        let config = Config {
+            dimensions: 4,
+            ..Default::default()
+        };
+
+        let mut db = VectorDb::new(config).unwrap();
+        for i in 0..10 {
+            db.insert(format!("vec{}", i), &[1.0, i as f32, 0.0, 0.0], None)
+                .unwrap();
+        }
+
+        let (internal_id, layers) = db.neighborhood("vec3").unwrap();
+        let expected = db.index.neighbors(internal_id);
+        assert_eq!(layers.len(), expected.len());
+        for (resolved, raw) in layers.iter().zip(expected.iter()) {
+            let raw_ids: Vec<VectorId> = raw
+                .iter()
+                .filter_map(|&n| db.storage.get_external_id(n))
+                .collect();
+            assert_eq!(resolved, &raw_ids);
+        }
+        assert!(!layers[0].is_empty());
+        assert!(db.neighborhood("missing").is_none());
+    }
+
+    #[test]
+    fn test_max_metadata_bytes() {
+        let config = Config {
+            dimensions: 4,
+            max_metadata_bytes: 32,
+            ..Default::default()
+        };
+
+        let mut db = VectorDb::new(config).unwrap();
+
+        let small = serde_json::json!({"k": "v"});
+        db.insert("small", &[1.0, 0.0, 0.0, 0.0], Some(small))
+            .unwrap();
+
+        let large = serde_json::json!({"text": "x".repeat(64)});
+        let result = db.insert("large", &[0.0, 1.0, 0.0, 0.0], Some(large.clone()));
+        assert!(matches!(
+            result,
+            Err(Error::MetadataTooLarge { limit: 32, .. })
+        ));
+        assert!(db.get("large").unwrap().is_none());
+
+        let batch = vec![(
+            VectorId::from("batch"),
+            vec![0.0, 0.0, 1.0, 0.0],
+            Some(large),
+        )];
+        assert!(db.upsert_batch(batch).is_err());
+        assert_eq!(db.len(), 1);
+    }
+
+    #[test]
+    fn test_require_unit_norm() {
+        let make = |require_unit_norm| {
+            VectorDb::new(Config {
+                dimensions: 4,
+                require_unit_norm,
+                ..Default::default()
+            })
+            .unwrap()
+        };
+
+        let mut db = make(true);
+        db.insert("unit", &[0.6, 0.8, 0.0, 0.0], None).unwrap();
+        let result = db.insert("long", &[3.0, 4.0, 0.0, 0.0], None);
+        assert!(matches!(result, Err(Error::NotUnitNorm { norm }) if (norm - 5.0).abs() < 1e-5));
+        assert!(db.upsert("unit", &[0.5, 0.5, 0.0, 0.0], None).is_err());
+        let batch = vec![(VectorId::from("b"), vec![0.0, 0.0, 2.0, 0.0], None)];
+        assert!(db.upsert_batch(batch).is_err());
+        assert_eq!(db.len(), 1);
+
+        let mut unchecked = make(false);
+        unchecked
+            .insert("long", &[3.0, 4.0, 0.0, 0.0], None)
+            .unwrap();
+        assert_eq!(unchecked.len(), 1);
+    }
+
+    #[test]
+    fn test_validate_finite_rejects_nan_and_inf() {
+        let make = |validate_finite| {
+            VectorDb::new(Config {
+                dimensions: 3,
+                validate_finite,
+                ..Default::default()
+            })
+            .unwrap()
+        };
+
+        let mut db = make(true);
+        db.insert("ok", &[1.0, 0.0, 0.0], None).unwrap();
+        let result = db.insert("nan", &[1.0, f32::NAN, 0.0], None);
+        assert!(matches!(
+            result,
+            Err(Error::InvalidVector { index: 1, value }) if value.is_nan()
+        ));
+        let result = db.upsert("ok", &[0.0, 0.0, f32::INFINITY], None);
+        assert!(matches!(result, Err(Error::InvalidVector { index: 2, .. })));
+        let batch = vec![
+            (VectorId::from("a"), vec![0.0, 1.0, 0.0], None),
+            (VectorId::from("b"), vec![f32::NEG_INFINITY, 0.0, 0.0], None),
+        ];
+        assert!(db.insert_batch(batch).is_err());
+        assert_eq!(db.len(), 1);
+        assert_eq!(db.get("ok").unwrap().unwrap().0, vec![1.0, 0.0, 0.0]);
+
+        // On by default, including for configs saved before the flag existed
+        assert!(Config::default().validate_finite);
+        let saved: Config = serde_json::from_value(serde_json::json!({
+            "dimensions": 3,
+            "distance_metric": "Cosine",
+            "hnsw": HnswConfig::default(),
+            "max_vectors": 0,
+            "quantization": "None",
+        }))
+        .unwrap();
+        assert!(saved.validate_finite);
+
+        let mut unchecked = make(false);
+        unchecked
+            .insert("nan", &[1.0, f32::NAN, 0.0], None)
+            .unwrap();
+        assert_eq!(unchecked.len(), 1);
+    }
+
+    #[test]
+    fn test_search_with_ef() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let mut db = VectorDb::new(Config {
+            dimensions: 8,
+            distance_metric: DistanceMetric::Euclidean,
+            hnsw: HnswConfig {
+                ef_search: 4,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .unwrap();
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut vector = || -> Vec<f32> { (0..8).map(|_| rng.gen::<f32>()).collect() };
+        for i in 0..300 {
+            db.insert(format!("v{}", i), &vector(), None).unwrap();
+        }
+
+        let query = vector();
+        // ef_search applies when ef matches it
+        assert_eq!(
+            db.search_with_ef(&query, 3, 4, None).unwrap(),
+            db.search(&query, 3, None).unwrap()
+        );
+        // An ef below k is raised to k
+        assert_eq!(db.search_with_ef(&query, 10, 1, None).unwrap().len(), 10);
+
+        // A wide candidate list matches brute force
+        let exact = db
+            .search_exact_with_metric(&query, 10, DistanceMetric::Euclidean, None)
+            .unwrap();
+        assert_eq!(db.search_with_ef(&query, 10, 300, None).unwrap(), exact);
+    }
+
+    #[test]
+    fn test_validate_query() {
+        let make = |validate_query| {
+            let mut db = VectorDb::new(Config {
+                dimensions: 4,
+                validate_query,
+                ..Default::default()
+            })
+            .unwrap();
+            db.insert("a", &[1.0, 0.0, 0.0, 0.0], None).unwrap();
+            db.insert("b", &[0.0, 2.0, 0.0, 0.0], None).unwrap();
+            db
+        };
+
+        let db = make(true);
+        let result = db.search(&[0.0; 4], 1, None);
+        assert!(matches!(result, Err(Error::InvalidQuery { norm, .. }) if norm == 0.0));
+        assert!(db.search_ids(&[0.0; 4], 1, None).is_err());
+
+        let results = db.search(&[0.9, 0.1, 0.0, 0.0], 1, None).unwrap();
+        assert_eq!(results[0].0.as_str(), "a");
+
+        // Far outside the stored norms of 1 and 2
+        let result = db.search(&[1e4, 0.0, 0.0, 0.0], 1, None);
+        assert!(matches!(result, Err(Error::InvalidQuery { .. })));
+        let result = db.search(&[f32::NAN, 0.0, 0.0, 0.0], 1, None);
+        assert!(matches!(result, Err(Error::InvalidQuery { .. })));
+
+        // Zero queries are only meaningless under cosine
+        let mut euclidean = VectorDb::new(Config {
+            dimensions: 4,
+            distance_metric: DistanceMetric::Euclidean,
+            validate_query: true,
+            ..Default::default()
+        })
+        .unwrap();
+        euclidean.insert("a", &[0.0, 0.0, 0.0, 0.5], None).unwrap();
+        assert_eq!(euclidean.search(&[0.0; 4], 1, None).unwrap().len(), 1);
+
+        assert!(make(false).search(&[1e4, 0.0, 0.0, 0.0], 1, None).is_ok());
+    }
+
+    #[test]
+    fn test_dedup_by_content_returns_original_id() {
+        use serde_json::json;
+        let make = |epsilon| {
+            VectorDb::new(Config {
+                dimensions: 4,
+                distance_metric: DistanceMetric::DotProduct,
+                insert_mode: InsertMode::DedupByContent { epsilon },
+                ..Default::default()
+            })
+            .unwrap()
+        };
+
+        let mut db = make(0.0);
+        let vector = [0.5, -0.0, 1.0, 2.0];
+        let first = db
+            .insert_or_existing("first", &vector, Some(json!({"n": 1})))
+            .unwrap();
+        db.insert("other", &[4.0, 3.0, 2.0, 1.0], None).unwrap();
+        let second = db
+            .insert_or_existing("second", &[0.5, 0.0, 1.0, 2.0], Some(json!({"n": 2})))
+            .unwrap();
+        assert_eq!([first.as_str(), second.as_str()], ["first", "first"]);
+        assert_eq!(db.len(), 2);
+        assert!(db.get("second").unwrap().is_none());
+        assert_eq!(db.get("first").unwrap().unwrap().1, Some(json!({"n": 1})));
+
+        // Near duplicates only match within epsilon
+        db.insert("near", &[0.5, 0.0, 1.0, 2.001], None).unwrap();
+        assert_eq!(db.len(), 3);
+        let mut db = make(0.01);
+        db.insert("first", &vector, None).unwrap();
+        let near = db
+            .insert_or_existing("near", &[0.5, 0.0, 1.0, 2.001], None)
+            .unwrap();
+        assert_eq!(near.as_str(), "first");
+
+        // A deleted vector no longer absorbs its duplicates
+        db.delete("first").unwrap();
+        let again = db.insert_or_existing("again", &vector, None).unwrap();
+        assert_eq!(again.as_str(), "again");
+        assert_eq!(db.len(), 1);
+
+        let invalid = VectorDb::new(Config {
+            insert_mode: InsertMode::DedupByContent { epsilon: -1.0 },
+            ..Default::default()
+        });
+        assert!(matches!(invalid, Err(Error::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn test_dimension_mismatch_policies() {
+        let make = |dimension_mismatch| {
+            VectorDb::new(Config {
+                dimensions: 4,
+                distance_metric: DistanceMetric::Euclidean,
+                dimension_mismatch,
+                ..Default::default()
+            })
+            .unwrap()
+        };
+        let short = [1.0, 2.0, 3.0];
+        let long = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let mismatch = |result: Result<()>, got| {
+            assert!(matches!(
+                result,
+                Err(Error::DimensionMismatch { expected: 4, got: g }) if g == got
+            ));
+        };
+
+        let mut strict = make(DimensionPolicy::Strict);
+        mismatch(strict.insert("short", &short, None), 3);
+        mismatch(strict.insert("long", &long, None), 5);
+        let batch = vec![(VectorId::from("b"), long.to_vec(), None)];
+        assert!(strict.upsert_batch(batch).is_err());
+        assert!(strict.search(&short, 1, None).is_err());
+        assert!(strict.is_empty());
+
+        let mut pad = make(DimensionPolicy::PadZero);
+        pad.insert("short", &short, None).unwrap();
+        mismatch(pad.insert("long", &long, None), 5);
+        let batch = vec![(VectorId::from("b"), vec![0.0, 1.0], None)];
+        pad.upsert_batch(batch).unwrap();
+        assert_eq!(pad.get("short").unwrap().unwrap().0, [1.0, 2.0, 3.0, 0.0]);
+        assert_eq!(pad.get("b").unwrap().unwrap().0, [0.0, 1.0, 0.0, 0.0]);
+        let results = pad.search(&short, 1, None).unwrap();
+        assert_eq!(results[0].0.as_str(), "short");
+        assert!(results[0].1 < 1e-6);
+
+        let mut truncate = make(DimensionPolicy::Truncate);
+        truncate.insert("long", &long, None).unwrap();
+        mismatch(truncate.insert("short", &short, None), 3);
+        assert_eq!(
+            truncate.get("long").unwrap().unwrap().0,
+            [1.0, 2.0, 3.0, 4.0]
+        );
+        let results = truncate
+            .search(&[1.0, 2.0, 3.0, 4.0, 9.0], 1, None)
+            .unwrap();
+        assert!(results[0].1 < 1e-6);
+    }
 
     #[test]
-    fn test_basic_insert_and_search() {
+    fn test_search_ref_matches_search() {
         let config = Config {
             dimensions: 4,
             ..Default::default()
         };
 
         let mut db = VectorDb::new(config).unwrap();
+        for i in 0..20 {
+            db.insert(format!("vec{}", i), &[1.0, i as f32 * 0.1, 0.0, 0.0], None)
+                .unwrap();
+        }
+        // Stale entry from an update must not show up
+        db.upsert("vec0", &[0.0, 0.0, 1.0, 0.0], None).unwrap();
 
-        db.insert("vec1", &[1.0, 0.0, 0.0, 0.0], None).unwrap();
-        db.insert("vec2", &[0.0, 1.0, 0.0, 0.0], None).unwrap();
-        db.insert("vec3", &[0.9, 0.1, 0.0, 0.0], None).unwrap();
+        let query = [1.0, 0.05, 0.0, 0.0];
+        let expected: Vec<VectorId> = db
+            .search(&query, 5, None)
+            .unwrap()
+            .into_iter()
+            .map(|(id, _, _)| id)
+            .collect();
 
-        let results = db.search(&[1.0, 0.0, 0.0, 0.0], 2, None).unwrap();
+        let mut out = Vec::with_capacity(5);
+        db.search_ref(&query, 5, None, &mut out).unwrap();
+        let got: Vec<VectorId> = out.iter().map(|(id, _)| id.clone()).collect();
+        assert_eq!(got, expected);
 
-        assert_eq!(results.len(), 2);
-        assert_eq!(results[0].0.as_str(), "vec1"); // Exact match should be first
+        // The buffer is cleared on reuse
+        db.search_ref(&query, 2, None, &mut out).unwrap();
+        assert_eq!(out.len(), 2);
     }
 
     #[test]
-    fn test_insert_with_metadata() {
-        let config = Config {
+    fn test_len_excludes_tombstones() {
+        let mut db = VectorDb::new(Config {
             dimensions: 4,
             ..Default::default()
-        };
-
-        let mut db = VectorDb::new(config).unwrap();
-        let meta = serde_json::json!({"type": "test"});
+        })
+        .unwrap();
+        let mut qdb = QuantizedVectorDb::new(QuantizedConfig {
+            dimensions: 4,
+            ..Default::default()
+        })
+        .unwrap();
 
-        db.insert("vec1", &[1.0, 0.0, 0.0, 0.0], Some(meta.clone()))
-            .unwrap();
+        for i in 0..10 {
+            let v = [1.0, i as f32, 0.0, 0.0];
+            db.insert(format!("vec{}", i), &v, None).unwrap();
+            qdb.insert(format!("vec{}", i), &v, None).unwrap();
+        }
+        for i in 0..3 {
+            assert!(db.delete(format!("vec{}", i)).unwrap());
+            assert!(qdb.delete(format!("vec{}", i)).unwrap());
+        }
 
-        let results = db.search(&[1.0, 0.0, 0.0, 0.0], 1, None).unwrap();
-        assert_eq!(results[0].2, Some(meta));
+        assert_eq!(db.len(), 7);
+        assert_eq!(db.physical_len(), 10);
+        assert_eq!(qdb.len(), 7);
+        assert_eq!(qdb.physical_len(), 10);
     }
 
     #[test]
@@ -861,6 +3302,269 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_top_k_matches_full_sort() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(7);
+        // Coarse distances so plenty of candidates tie
+        let candidates: Vec<(types::InternalId, f32)> = (0..2000)
+            .map(|i| {
+                (
+                    types::InternalId::from(i),
+                    rng.gen_range(0..50) as f32 / 10.0,
+                )
+            })
+            .collect();
+        let mut sorted = candidates.clone();
+        sorted.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+
+        for k in [0, 1, 10, 100, 1999, 2000, 5000] {
+            let expected: Vec<_> = sorted.iter().copied().take(k).collect();
+            assert_eq!(
+                types::top_k(candidates.iter().copied(), k),
+                expected,
+                "k = {}",
+                k
+            );
+        }
+    }
+
+    #[test]
+    fn test_quantized_index_matches_scan() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(11);
+        let vectors: Vec<Vec<f32>> = (0..1000)
+            .map(|_| (0..16).map(|_| rng.gen_range(-1.0..1.0)).collect())
+            .collect();
+        let build = |use_index| {
+            let mut db = QuantizedVectorDb::new(QuantizedConfig {
+                dimensions: 16,
+                quantization: QuantizationType::SQ8,
+                use_index,
+                ..Default::default()
+            })
+            .unwrap();
+            for (i, v) in vectors.iter().enumerate() {
+                db.insert(format!("vec{}", i), v, None).unwrap();
+            }
+            db
+        };
+        let (indexed, scanned) = (build(true), build(false));
+        assert!(indexed.index.is_some());
+        assert!(scanned.index.is_none());
+
+        let k = 10;
+        let mut hits = 0;
+        for _ in 0..20 {
+            let query: Vec<f32> = (0..16).map(|_| rng.gen_range(-1.0..1.0)).collect();
+            let exact: std::collections::HashSet<VectorId> = scanned
+                .search_ids(&query, k, None)
+                .unwrap()
+                .into_iter()
+                .map(|(id, _)| id)
+                .collect();
+            let results = indexed.search_ids(&query, k, None).unwrap();
+            assert_eq!(results.len(), k);
+            hits += results.iter().filter(|(id, _)| exact.contains(id)).count();
+        }
+        let recall = hits as f32 / (20 * k) as f32;
+        assert!(recall >= 0.9, "recall {}", recall);
+    }
+
+    #[test]
+    fn test_get_exact_with_originals_approximate_without() {
+        let vector = [0.25, -0.5, 1.0, 0.75];
+        let meta = serde_json::json!({"k": "v"});
+        for quantization in [
+            QuantizationType::None,
+            QuantizationType::SQ8,
+            QuantizationType::Binary,
+        ] {
+            for keep_originals in [true, false] {
+                let mut db = QuantizedVectorDb::new(QuantizedConfig {
+                    dimensions: 4,
+                    quantization,
+                    keep_originals,
+                    ..Default::default()
+                })
+                .unwrap();
+                db.insert("vec1", &vector, Some(meta.clone())).unwrap();
+
+                let (restored, metadata) = db.get("vec1").unwrap().unwrap();
+                assert_eq!(metadata, Some(meta.clone()));
+                assert!(db.get("missing").unwrap().is_none());
+                match (quantization, keep_originals) {
+                    (QuantizationType::Binary, false) => {
+                        assert_eq!(restored, vec![1.0, -1.0, 1.0, 1.0])
+                    }
+                    (QuantizationType::SQ8, false) => {
+                        for (a, b) in restored.iter().zip(vector) {
+                            assert!((a - b).abs() < 0.01, "{:?}", restored);
+                        }
+                    }
+                    _ => assert_eq!(restored, vector),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_quantized_dequantizes_to_approximation() {
+        let mut db = QuantizedVectorDb::new(QuantizedConfig {
+            dimensions: 4,
+            quantization: QuantizationType::SQ8,
+            ..Default::default()
+        })
+        .unwrap();
+        let vector = [0.25, -0.5, 1.0, 0.75];
+        db.insert("vec1", &vector, None).unwrap();
+
+        let (bytes, meta) = db.get_quantized("vec1").unwrap();
+        let meta = meta.unwrap();
+        let restored = SQ8Quantizer::new(4).dequantize(&bytes, &meta);
+        for (a, b) in restored.iter().zip(&vector) {
+            assert!((a - b).abs() <= meta.scale, "{} vs {}", a, b);
+        }
+        assert!(db.get_quantized("missing").is_none());
+    }
+
+    #[test]
+    fn test_alias_resolves_and_dies_with_primary() {
+        let mut db = VectorDb::new(Config {
+            dimensions: 2,
+            ..Default::default()
+        })
+        .unwrap();
+        db.insert("uuid-1", &[1.0, 0.0], None).unwrap();
+        db.insert("uuid-2", &[0.9, 0.1], None).unwrap();
+        db.insert("uuid-3", &[0.0, 1.0], None).unwrap();
+
+        db.add_alias("intro-post", "uuid-1").unwrap();
+        // Aliases may be chained through another alias
+        db.add_alias("welcome", "intro-post").unwrap();
+        assert_eq!(
+            db.get("welcome").unwrap().unwrap().0,
+            db.get("uuid-1").unwrap().unwrap().0
+        );
+
+        let results = db.search_by_id("intro-post", 1, None, true).unwrap();
+        assert_eq!(results[0].0.as_str(), "uuid-2");
+
+        assert!(matches!(
+            db.add_alias("uuid-2", "uuid-1"),
+            Err(Error::DuplicateId(_))
+        ));
+        assert!(matches!(
+            db.add_alias("intro-post", "uuid-3"),
+            Err(Error::DuplicateId(_))
+        ));
+        assert!(matches!(
+            db.add_alias("orphan", "missing"),
+            Err(Error::VectorNotFound(_))
+        ));
+
+        assert!(db.delete("uuid-1").unwrap());
+        assert!(db.get("intro-post").unwrap().is_none());
+        assert!(db.get("welcome").unwrap().is_none());
+        // The freed alias can be reused for another vector
+        db.add_alias("intro-post", "uuid-3").unwrap();
+        assert!(db.delete("intro-post").unwrap());
+        assert!(db.get("uuid-3").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_to_bytes_round_trip() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let config = Config {
+            dimensions: 16,
+            ..Default::default()
+        };
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut db = VectorDb::new(config.clone()).unwrap();
+        for i in 0..200 {
+            let v: Vec<f32> = (0..16).map(|_| rng.gen_range(-1.0..1.0)).collect();
+            db.insert(format!("v{}", i), &v, Some(serde_json::json!({ "i": i })))
+                .unwrap();
+        }
+        db.add_alias("first", "v0").unwrap();
+        let query: Vec<f32> = (0..16).map(|_| rng.gen_range(-1.0..1.0)).collect();
+
+        let restored = VectorDb::from_bytes(config.clone(), &db.to_bytes().unwrap()).unwrap();
+        assert_eq!(restored.len(), 200);
+        assert_eq!(restored.get("first").unwrap(), db.get("v0").unwrap());
+        assert_eq!(
+            restored.search(&query, 10, None).unwrap(),
+            db.search(&query, 10, None).unwrap()
+        );
+
+        // With a deleted slot the graph is rebuilt instead of restored
+        db.delete("v3").unwrap();
+        let restored = VectorDb::from_bytes(config, &db.to_bytes().unwrap()).unwrap();
+        assert_eq!(restored.len(), 199);
+        assert!(restored.get("v3").unwrap().is_none());
+        assert_eq!(restored.search(&query, 1, None).unwrap().len(), 1);
+
+        let wrong = Config {
+            dimensions: 8,
+            ..Default::default()
+        };
+        assert!(matches!(
+            VectorDb::from_bytes(wrong, &db.to_bytes().unwrap()),
+            Err(Error::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_accuracy_report_sq8_beats_binary() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(11);
+        let vectors: Vec<(VectorId, Vec<f32>)> = (0..300)
+            .map(|i| {
+                let v = (0..64).map(|_| rng.gen_range(-1.0..1.0)).collect();
+                (VectorId::from(format!("vec{}", i)), v)
+            })
+            .collect();
+        let build = |quantization, keep_originals| {
+            let mut db = QuantizedVectorDb::new(QuantizedConfig {
+                dimensions: 64,
+                quantization,
+                keep_originals,
+                ..Default::default()
+            })
+            .unwrap();
+            for (id, v) in &vectors {
+                db.insert(id.clone(), v, None).unwrap();
+            }
+            db
+        };
+
+        let sq8 = build(QuantizationType::SQ8, true)
+            .accuracy_report(vectors.len())
+            .unwrap();
+        let binary_db = build(QuantizationType::Binary, false);
+        assert!(matches!(
+            binary_db.accuracy_report(100),
+            Err(Error::InvalidConfig(_))
+        ));
+        let binary = binary_db.accuracy_report_for(&vectors).unwrap();
+
+        assert_eq!(sq8.sampled, 300);
+        assert_eq!(binary.sampled, 300);
+        assert!(sq8.recall_at_10 > 0.8, "sq8 recall {}", sq8.recall_at_10);
+        assert!(
+            sq8.recall_at_10 > binary.recall_at_10,
+            "sq8 {} vs binary {}",
+            sq8.recall_at_10,
+            binary.recall_at_10
+        );
+        assert!(sq8.mean_error < binary.mean_error);
+        assert!(sq8.p50_error <= sq8.p95_error && sq8.p95_error <= sq8.max_error);
+    }
+
     #[test]
     fn test_quantized_binary_insert_and_search() {
         let config = QuantizedConfig {
@@ -887,6 +3591,64 @@ mod tests {
         assert_eq!(results[0].0.as_str(), "vec1");
     }
 
+    #[test]
+    fn test_binary_metrics_report_raw_scores() {
+        let signature = |bits: &[usize]| -> Vec<f32> {
+            (0..16)
+                .map(|i| if bits.contains(&i) { 1.0 } else { -1.0 })
+                .collect()
+        };
+        let build = |distance_metric| {
+            let mut db = QuantizedVectorDb::new(QuantizedConfig {
+                dimensions: 16,
+                quantization: QuantizationType::Binary,
+                distance_metric,
+                ..Default::default()
+            })
+            .unwrap();
+            db.insert("same", &signature(&[0, 1, 2, 3]), None).unwrap();
+            db.insert("near", &signature(&[0, 1, 2]), None).unwrap();
+            db.insert("far", &signature(&[8, 9, 10, 11]), None).unwrap();
+            db
+        };
+        let query = signature(&[0, 1, 2, 3]);
+
+        let scores = |metric| {
+            let results = build(metric).search(&query, 3, None).unwrap();
+            let ids: Vec<String> = results.iter().map(|(id, _, _)| id.to_string()).collect();
+            assert_eq!(ids, ["same", "near", "far"]);
+            results.iter().map(|(_, d, _)| *d).collect::<Vec<f32>>()
+        };
+        assert_eq!(scores(DistanceMetric::Hamming), [0.0, 1.0, 8.0]);
+        assert_eq!(scores(DistanceMetric::Jaccard), [0.0, 0.25, 1.0]);
+
+        // Bit-set metrics have no meaning over float or SQ8 storage
+        for metric in [DistanceMetric::Hamming, DistanceMetric::Jaccard] {
+            let float = VectorDb::new(Config {
+                dimensions: 16,
+                distance_metric: metric,
+                ..Default::default()
+            });
+            assert!(matches!(float, Err(Error::InvalidConfig(_))));
+            let sq8 = QuantizedVectorDb::new(QuantizedConfig {
+                dimensions: 16,
+                quantization: QuantizationType::SQ8,
+                distance_metric: metric,
+                ..Default::default()
+            });
+            assert!(matches!(sq8, Err(Error::InvalidConfig(_))));
+        }
+        let db = VectorDb::new(Config {
+            dimensions: 16,
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(matches!(
+            db.search_exact_with_metric(&query, 1, DistanceMetric::Hamming, None),
+            Err(Error::InvalidConfig(_))
+        ));
+    }
+
     #[test]
     fn test_quantized_with_reranking() {
         let config = QuantizedConfig {
@@ -910,6 +3672,68 @@ mod tests {
         assert_eq!(results[0].0.as_str(), "vec1");
     }
 
+    #[test]
+    fn test_adaptive_rerank_recall() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let dim = 32;
+        let k = 10;
+        let mut rng = StdRng::seed_from_u64(17);
+        // Uniform data: quantized scores are tightly packed, so nothing is skipped
+        let data: Vec<Vec<f32>> = (0..2000)
+            .map(|_| (0..dim).map(|_| rng.gen_range(-1.0..1.0)).collect())
+            .collect();
+        let queries: Vec<Vec<f32>> = (0..50)
+            .map(|_| (0..dim).map(|_| rng.gen_range(-1.0..1.0)).collect())
+            .collect();
+
+        let recall = |adaptive_rerank: bool| {
+            let mut db = QuantizedVectorDb::new(QuantizedConfig {
+                dimensions: dim,
+                // Brute-force search keeps the comparison deterministic
+                quantization: QuantizationType::Binary,
+                keep_originals: true,
+                adaptive_rerank,
+                ..Default::default()
+            })
+            .unwrap();
+            for (i, v) in data.iter().enumerate() {
+                db.insert(format!("v{}", i), v, None).unwrap();
+            }
+
+            let mut hits = 0;
+            for query in &queries {
+                let mut exact: Vec<(usize, f32)> = data
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| (i, DistanceMetric::Cosine.distance(query, v)))
+                    .collect();
+                exact.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+                let truth: std::collections::HashSet<String> = exact
+                    .iter()
+                    .take(k)
+                    .map(|(i, _)| format!("v{}", i))
+                    .collect();
+
+                let results = db.search(query, k, None).unwrap();
+                hits += results
+                    .iter()
+                    .filter(|(id, _, _)| truth.contains(&*id.as_str()))
+                    .count();
+            }
+            hits as f64 / (queries.len() * k) as f64
+        };
+
+        let full = recall(false);
+        let adaptive = recall(true);
+        assert!(
+            adaptive >= full,
+            "adaptive recall {} regressed from {}",
+            adaptive,
+            full
+        );
+    }
+
     #[test]
     fn test_compression_ratio() {
         let config = QuantizedConfig {
@@ -931,4 +3755,354 @@ mod tests {
         println!("SQ8 compression ratio: {:.2}x", ratio);
         assert!(ratio > 3.5, "Expected > 3.5x compression, got {}", ratio);
     }
+
+    #[test]
+    fn test_projection_preserves_ordering() {
+        let config = Config {
+            dimensions: 256,
+            distance_metric: DistanceMetric::Euclidean,
+            projection: Some(ProjectionConfig {
+                target_dim: 64,
+                seed: 42,
+            }),
+            ..Default::default()
+        };
+        let mut db = VectorDb::new(config).unwrap();
+
+        // Points at increasing distance from the origin along a fixed direction,
+        // plus a far-away cluster in an orthogonal direction
+        let axis = |i: usize, scale: f32| {
+            let mut v = vec![0.0; 256];
+            v[i] = scale;
+            v
+        };
+        for step in 0..8 {
+            db.insert(format!("near{}", step), &axis(0, step as f32 * 2.0), None)
+                .unwrap();
+        }
+        db.insert("far", &axis(128, 100.0), None).unwrap();
+
+        assert_eq!(db.get("far").unwrap().unwrap().0.len(), 64);
+
+        let results = db.search(&axis(0, 0.0), 9, None).unwrap();
+        let ids: Vec<_> = results.iter().map(|(id, _, _)| id.as_str()).collect();
+        let expected: Vec<String> = (0..8).map(|step| format!("near{}", step)).collect();
+        assert_eq!(ids[..8], expected);
+        assert_eq!(ids[8], "far");
+
+        // Queries must use the original dimensionality
+        assert!(db.search(&[0.0; 64], 1, None).is_err());
+    }
+
+    #[test]
+    fn test_normalized_matches_cosine_ranking() {
+        let make = |normalize| {
+            VectorDb::new(Config {
+                dimensions: 16,
+                distance_metric: DistanceMetric::Cosine,
+                normalize,
+                ..Default::default()
+            })
+            .unwrap()
+        };
+        let mut cosine = make(false);
+        let mut normalized = make(true);
+
+        // Varying magnitudes, so un-normalized dot product would rank differently
+        let vector = |seed: usize| -> Vec<f32> {
+            (0..16)
+                .map(|d| {
+                    (((seed * 7919 + d * d * 104_729) % 1009) as f32 - 504.0)
+                        * (1 + seed % 5) as f32
+                })
+                .collect()
+        };
+        for i in 0..64 {
+            cosine.insert(format!("v{}", i), &vector(i), None).unwrap();
+            normalized
+                .insert(format!("v{}", i), &vector(i), None)
+                .unwrap();
+        }
+
+        let stored = normalized.get("v3").unwrap().unwrap().0;
+        let norm: f32 = stored.iter().map(|x| x * x).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 1e-5);
+
+        for q in [100, 101, 102] {
+            let query: Vec<f32> = vector(q).iter().map(|x| x * 3.0).collect();
+            let expected = cosine.search(&query, 10, None).unwrap();
+            let actual = normalized.search(&query, 10, None).unwrap();
+
+            let expected_ids: Vec<_> = expected.iter().map(|(id, _, _)| id.as_str()).collect();
+            let actual_ids: Vec<_> = actual.iter().map(|(id, _, _)| id.as_str()).collect();
+            assert_eq!(actual_ids, expected_ids);
+            for ((_, a, _), (_, b, _)) in actual.iter().zip(&expected) {
+                assert!((a - b).abs() < 1e-4, "{} vs {}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_additional_metric_matches_single_metric_index() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let dim = 8;
+        let mut rng = StdRng::seed_from_u64(5);
+        // Varying magnitudes so cosine and euclidean rankings differ
+        let data: Vec<Vec<f32>> = (0..300)
+            .map(|_| {
+                let scale = rng.gen_range(0.1..10.0);
+                (0..dim).map(|_| rng.gen_range(-1.0..1.0) * scale).collect()
+            })
+            .collect();
+
+        let build = |distance_metric, additional_metrics| {
+            let mut db = VectorDb::new(Config {
+                dimensions: dim,
+                distance_metric,
+                additional_metrics,
+                ..Default::default()
+            })
+            .unwrap();
+            for (i, v) in data.iter().enumerate() {
+                db.insert(format!("v{}", i), v, None).unwrap();
+            }
+            db
+        };
+        let dual = build(DistanceMetric::Cosine, vec![DistanceMetric::Euclidean]);
+        let cosine = build(DistanceMetric::Cosine, Vec::new());
+        let euclidean = build(DistanceMetric::Euclidean, Vec::new());
+
+        let ids = |results: Vec<(VectorId, f32, Option<Value>)>| -> Vec<String> {
+            results
+                .into_iter()
+                .map(|(id, _, _)| id.to_string())
+                .collect()
+        };
+        let mut differs = false;
+        for _ in 0..10 {
+            let query: Vec<f32> = (0..dim).map(|_| rng.gen_range(-1.0..1.0)).collect();
+            let by_cosine = ids(dual.search(&query, 10, None).unwrap());
+            let by_euclidean = ids(dual
+                .search_with_metric(&query, 10, DistanceMetric::Euclidean, None)
+                .unwrap());
+            assert_eq!(by_cosine, ids(cosine.search(&query, 10, None).unwrap()));
+            assert_eq!(
+                by_euclidean,
+                ids(euclidean.search(&query, 10, None).unwrap())
+            );
+            differs |= by_cosine != by_euclidean;
+        }
+        assert!(differs);
+
+        assert!(matches!(
+            dual.search_with_metric(&data[0], 1, DistanceMetric::DotProduct, None),
+            Err(Error::InvalidConfig(_))
+        ));
+        // Exact mode serves the unindexed metric, matching brute force
+        let query = &data[0];
+        let mut truth: Vec<(usize, f32)> = data
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (i, DistanceMetric::DotProduct.distance(query, v)))
+            .collect();
+        truth.sort_by(|a, b| a.1.total_cmp(&b.1));
+        let exact = dual
+            .search_exact_with_metric(query, 10, DistanceMetric::DotProduct, None)
+            .unwrap();
+        assert_eq!(
+            ids(exact),
+            truth[..10]
+                .iter()
+                .map(|(i, _)| format!("v{}", i))
+                .collect::<Vec<_>>()
+        );
+        assert!(dual.memory_usage() > cosine.memory_usage());
+    }
+
+    #[test]
+    fn test_shuffle_inserts_recall_on_sorted_clusters() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let dim = 16;
+        let k = 10;
+        let mut rng = StdRng::seed_from_u64(1);
+        // Ten tight clusters, emitted one cluster after another
+        let mut data: Vec<Vec<f32>> = Vec::new();
+        for _ in 0..10 {
+            let center: Vec<f32> = (0..dim).map(|_| rng.gen_range(-10.0..10.0)).collect();
+            for _ in 0..200 {
+                data.push(
+                    center
+                        .iter()
+                        .map(|x| x + rng.gen_range(-1.0..1.0))
+                        .collect(),
+                );
+            }
+        }
+
+        let recall = |shuffle_inserts: bool| {
+            let mut db = VectorDb::new(Config {
+                dimensions: dim,
+                distance_metric: DistanceMetric::Euclidean,
+                hnsw: HnswConfig {
+                    shuffle_inserts,
+                    shuffle_seed: 3,
+                    rng: RngSource::Xorshift(11),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .unwrap();
+            let items = data
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (VectorId::from(format!("v{}", i)), v.clone(), None))
+                .collect();
+            db.upsert_batch(items).unwrap();
+            assert_eq!(db.len(), data.len());
+
+            let mut hits = 0;
+            for q in 0..50 {
+                let query = &data[q * 37 % data.len()];
+                let mut exact: Vec<(usize, f32)> = data
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| (i, DistanceMetric::Euclidean.distance(query, v)))
+                    .collect();
+                exact.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+                let truth: std::collections::HashSet<String> = exact
+                    .iter()
+                    .take(k)
+                    .map(|(i, _)| format!("v{}", i))
+                    .collect();
+                hits += db
+                    .search(query, k, None)
+                    .unwrap()
+                    .iter()
+                    .filter(|(id, _, _)| truth.contains(&*id.as_str()))
+                    .count();
+            }
+            hits as f64 / (50 * k) as f64
+        };
+
+        let sorted = recall(false);
+        let shuffled = recall(true);
+        assert!(
+            shuffled > 0.9 && shuffled > sorted + 0.3,
+            "recall@{}: sorted={:.3} shuffled={:.3}",
+            k,
+            sorted,
+            shuffled
+        );
+    }
+
+    #[test]
+    fn test_shuffle_inserts_keeps_last_duplicate() {
+        let mut db = VectorDb::new(Config {
+            dimensions: 4,
+            hnsw: HnswConfig {
+                shuffle_inserts: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .unwrap();
+        let batch = (0..20)
+            .map(|i| {
+                let id = VectorId::from(format!("v{}", i % 5));
+                (id, vec![1.0, i as f32, 0.0, 0.0], None)
+            })
+            .collect();
+        db.upsert_batch(batch).unwrap();
+
+        assert_eq!(db.len(), 5);
+        for i in 15..20 {
+            let (stored, _) = db.get(&format!("v{}", i % 5)).unwrap().unwrap();
+            assert_eq!(stored, vec![1.0, i as f32, 0.0, 0.0]);
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_concurrent_mixed_inserts_and_searches() {
+        let config = Config {
+            dimensions: 8,
+            ..Default::default()
+        };
+        let db = VectorDb::new(config).unwrap();
+        let vector = |seed: usize| -> Vec<f32> {
+            (0..8)
+                .map(|d| ((seed * 31 + d * 7) % 97) as f32 + 1.0)
+                .collect()
+        };
+        for i in 0..100 {
+            db.insert_concurrent(format!("seed{}", i), &vector(i), None)
+                .unwrap();
+        }
+
+        let writers = 4;
+        let per_writer = 150;
+        std::thread::scope(|scope| {
+            for w in 0..writers {
+                let db = &db;
+                scope.spawn(move || {
+                    for i in 0..per_writer {
+                        let id = format!("w{}-{}", w, i);
+                        db.insert_concurrent(id, &vector(w * 1000 + i), None)
+                            .unwrap();
+                    }
+                });
+            }
+            for r in 0..4 {
+                let db = &db;
+                scope.spawn(move || {
+                    for i in 0..200 {
+                        let results = db.search(&vector(r * 100 + i), 5, None).unwrap();
+                        assert!(!results.is_empty());
+                    }
+                });
+            }
+        });
+
+        assert_eq!(db.len(), 100 + writers * per_writer);
+        for w in 0..writers {
+            for i in 0..per_writer {
+                let id = format!("w{}-{}", w, i);
+                let (stored, _) = db.get(&id).unwrap().expect("lost insert");
+                assert_eq!(stored, vector(w * 1000 + i));
+            }
+        }
+        let (_, layers) = db.neighborhood("w3-149").unwrap();
+        assert!(!layers[0].is_empty());
+    }
+
+    /// Storage and graph both grow per vector, so doubling the count about
+    /// doubles the reported memory
+    #[test]
+    fn test_memory_usage_grows_linearly() {
+        let mut db = VectorDb::new(Config {
+            dimensions: 32,
+            ..Default::default()
+        })
+        .unwrap();
+        let empty = db.memory_usage();
+
+        let mut usage = Vec::new();
+        for i in 0..2000 {
+            let vector: Vec<f32> = (0..32).map(|d| ((i * 7 + d * 13) % 29) as f32).collect();
+            db.insert(format!("v{}", i), &vector, None).unwrap();
+            if i + 1 == 500 || i + 1 == 1000 || i + 1 == 2000 {
+                usage.push((db.memory_usage() - empty) as f64);
+            }
+        }
+
+        // Buffers grow by doubling, so allow for slack in either direction
+        for pair in usage.windows(2) {
+            let ratio = pair[1] / pair[0];
+            assert!((1.5..=3.0).contains(&ratio), "ratio {}", ratio);
+        }
+        // At least the raw vector data is accounted for
+        assert!(usage[2] >= (2000 * 32 * 4) as f64);
+    }
 }
@@ -0,0 +1,515 @@
+//! Metadata storage formats
+//!
+//! By default each vector's metadata is kept as a `serde_json::Value`. The
+//! columnar format instead stores declared top-level fields in typed columns
+//! (strings interned, numbers and booleans as primitives), which is smaller
+//! and lets filters on those fields compare primitives instead of walking
+//! JSON. Metadata reads back identically in either format.
+
+use crate::filter::Filter;
+use crate::types::InternalId;
+use roaring::RoaringBitmap;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// How vector metadata is held in memory
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MetadataFormat {
+    /// Each vector's metadata as a JSON value
+    #[default]
+    Json,
+    /// The declared top-level fields in typed columns. Undeclared fields,
+    /// values of another type and non-object metadata are kept as JSON.
+    Columnar(Vec<ColumnSpec>),
+}
+
+impl MetadataFormat {
+    /// Check that column names are unique top-level field names
+    pub fn validate(&self) -> crate::error::Result<()> {
+        if let MetadataFormat::Columnar(specs) = self {
+            for (i, spec) in specs.iter().enumerate() {
+                if spec.name.is_empty() || spec.name.contains('.') {
+                    return Err(crate::error::Error::InvalidConfig(format!(
+                        "metadata column '{}' must be a non-empty top-level field name",
+                        spec.name
+                    )));
+                }
+                if specs[..i].iter().any(|other| other.name == spec.name) {
+                    return Err(crate::error::Error::InvalidConfig(format!(
+                        "metadata column '{}' is declared more than once",
+                        spec.name
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A metadata field stored in its own column
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColumnSpec {
+    pub name: String,
+    pub kind: ColumnKind,
+}
+
+impl ColumnSpec {
+    pub fn new(name: impl Into<String>, kind: ColumnKind) -> Self {
+        Self {
+            name: name.into(),
+            kind,
+        }
+    }
+}
+
+/// Value type of a metadata column
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColumnKind {
+    /// Interned strings
+    String,
+    /// JSON integers that fit in an `i64`
+    Integer,
+    /// JSON floating-point numbers
+    Float,
+    Bool,
+}
+
+/// Per-vector metadata in one of the `MetadataFormat`s
+pub(crate) enum MetadataStore {
+    Json(HashMap<InternalId, Value>),
+    Columnar(Columns),
+}
+
+impl Default for MetadataStore {
+    fn default() -> Self {
+        Self::new(&MetadataFormat::Json)
+    }
+}
+
+impl MetadataStore {
+    pub(crate) fn new(format: &MetadataFormat) -> Self {
+        match format {
+            MetadataFormat::Json => Self::Json(HashMap::new()),
+            MetadataFormat::Columnar(specs) => Self::Columnar(Columns::new(specs)),
+        }
+    }
+
+    pub(crate) fn insert(&mut self, internal_id: InternalId, metadata: Value) {
+        match self {
+            Self::Json(map) => {
+                map.insert(internal_id, metadata);
+            }
+            Self::Columnar(columns) => columns.insert(internal_id, metadata),
+        }
+    }
+
+    pub(crate) fn remove(&mut self, internal_id: &InternalId) -> Option<Value> {
+        match self {
+            Self::Json(map) => map.remove(internal_id),
+            Self::Columnar(columns) => columns.remove(*internal_id),
+        }
+    }
+
+    pub(crate) fn get(&self, internal_id: &InternalId) -> Option<Value> {
+        match self {
+            Self::Json(map) => map.get(internal_id).cloned(),
+            Self::Columnar(columns) => columns.get(*internal_id),
+        }
+    }
+
+    /// Whether the vector has metadata matching `filter`, without
+    /// materializing it where possible
+    pub(crate) fn matches(&self, internal_id: InternalId, filter: &Filter) -> bool {
+        match self {
+            Self::Json(map) => map.get(&internal_id).is_some_and(|m| filter.matches(m)),
+            Self::Columnar(columns) => {
+                columns.present.contains(internal_id.as_u32())
+                    && columns.matches(internal_id, filter)
+            }
+        }
+    }
+
+    /// Approximate heap usage in bytes
+    pub(crate) fn memory_usage(&self) -> usize {
+        // Hash map entry: key, value and control bytes
+        let entry = std::mem::size_of::<InternalId>() + std::mem::size_of::<Value>() + 8;
+        match self {
+            Self::Json(map) => map
+                .values()
+                .map(|value| entry + json_heap_size(value))
+                .sum(),
+            Self::Columnar(columns) => columns.memory_usage(entry),
+        }
+    }
+}
+
+/// Heap bytes owned by a JSON object's entries, sized as B-tree node slots
+fn map_heap_size(map: &Map<String, Value>) -> usize {
+    let node = std::mem::size_of::<String>() + std::mem::size_of::<Value>();
+    map.iter()
+        .map(|(k, v)| node + k.capacity() + json_heap_size(v))
+        .sum()
+}
+
+/// Heap bytes owned by a JSON value
+fn json_heap_size(value: &Value) -> usize {
+    match value {
+        Value::String(s) => s.capacity(),
+        Value::Array(items) => items
+            .iter()
+            .map(|v| std::mem::size_of::<Value>() + json_heap_size(v))
+            .sum(),
+        Value::Object(map) => map_heap_size(map),
+        _ => 0,
+    }
+}
+
+/// Typed values of one column, indexed by slot
+enum ColumnValues {
+    String(Vec<u32>),
+    Integer(Vec<i64>),
+    Float(Vec<f64>),
+    Bool(Vec<bool>),
+}
+
+struct Column {
+    name: String,
+    values: ColumnValues,
+    /// Slots holding a value in this column
+    valid: RoaringBitmap,
+}
+
+impl Column {
+    fn new(spec: &ColumnSpec) -> Self {
+        let values = match spec.kind {
+            ColumnKind::String => ColumnValues::String(Vec::new()),
+            ColumnKind::Integer => ColumnValues::Integer(Vec::new()),
+            ColumnKind::Float => ColumnValues::Float(Vec::new()),
+            ColumnKind::Bool => ColumnValues::Bool(Vec::new()),
+        };
+        Self {
+            name: spec.name.clone(),
+            values,
+            valid: RoaringBitmap::new(),
+        }
+    }
+
+    /// Store `value` at `slot` if it has the column's type, handing it back
+    /// otherwise
+    fn set(&mut self, slot: usize, value: Value, strings: &mut Interner) -> Option<Value> {
+        fn put<T: Default + Clone>(values: &mut Vec<T>, slot: usize, value: T) {
+            if values.len() <= slot {
+                values.resize(slot + 1, T::default());
+            }
+            values[slot] = value;
+        }
+        match (&mut self.values, &value) {
+            (ColumnValues::String(values), Value::String(s)) => {
+                put(values, slot, strings.intern(s))
+            }
+            (ColumnValues::Integer(values), Value::Number(n)) if n.as_i64().is_some() => {
+                put(values, slot, n.as_i64().unwrap_or_default())
+            }
+            (ColumnValues::Float(values), Value::Number(n)) if n.is_f64() => {
+                put(values, slot, n.as_f64().unwrap_or_default())
+            }
+            (ColumnValues::Bool(values), Value::Bool(b)) => put(values, slot, *b),
+            _ => return Some(value),
+        }
+        self.valid.insert(slot as u32);
+        None
+    }
+
+    fn get(&self, slot: usize, strings: &Interner) -> Option<Value> {
+        if !self.valid.contains(slot as u32) {
+            return None;
+        }
+        Some(match &self.values {
+            ColumnValues::String(values) => Value::String(strings.resolve(values[slot]).to_owned()),
+            ColumnValues::Integer(values) => Value::from(values[slot]),
+            ColumnValues::Float(values) => Value::from(values[slot]),
+            ColumnValues::Bool(values) => Value::Bool(values[slot]),
+        })
+    }
+
+    /// Compare the value at `slot` with `expected` as JSON equality would
+    fn equals(&self, slot: usize, expected: &Value, strings: &Interner) -> bool {
+        match (&self.values, expected) {
+            (ColumnValues::String(values), Value::String(s)) => {
+                strings.lookup(s) == Some(values[slot])
+            }
+            (ColumnValues::Integer(values), Value::Number(n)) => {
+                !n.is_f64() && n.as_i64() == Some(values[slot])
+            }
+            (ColumnValues::Float(values), Value::Number(n)) => {
+                n.is_f64() && n.as_f64() == Some(values[slot])
+            }
+            (ColumnValues::Bool(values), Value::Bool(b)) => values[slot] == *b,
+            _ => false,
+        }
+    }
+
+    fn number(&self, slot: usize) -> Option<f64> {
+        match &self.values {
+            ColumnValues::Integer(values) => Some(values[slot] as f64),
+            ColumnValues::Float(values) => Some(values[slot]),
+            _ => None,
+        }
+    }
+
+    fn memory_usage(&self) -> usize {
+        let values = match &self.values {
+            ColumnValues::String(values) => values.capacity() * 4,
+            ColumnValues::Integer(values) => values.capacity() * 8,
+            ColumnValues::Float(values) => values.capacity() * 8,
+            ColumnValues::Bool(values) => values.capacity(),
+        };
+        values + self.valid.serialized_size()
+    }
+}
+
+/// String table shared by all string columns
+#[derive(Default)]
+struct Interner {
+    ids: HashMap<String, u32>,
+    values: Vec<String>,
+}
+
+impl Interner {
+    fn intern(&mut self, s: &str) -> u32 {
+        if let Some(&id) = self.ids.get(s) {
+            return id;
+        }
+        let id = self.values.len() as u32;
+        self.values.push(s.to_owned());
+        self.ids.insert(s.to_owned(), id);
+        id
+    }
+
+    fn lookup(&self, s: &str) -> Option<u32> {
+        self.ids.get(s).copied()
+    }
+
+    fn resolve(&self, id: u32) -> &str {
+        &self.values[id as usize]
+    }
+}
+
+pub(crate) struct Columns {
+    columns: Vec<Column>,
+    strings: Interner,
+    /// Slots that have metadata
+    present: RoaringBitmap,
+    /// Object fields not held in a column, by slot
+    rest: HashMap<InternalId, Map<String, Value>>,
+    /// Metadata that is not a JSON object, stored whole
+    other: HashMap<InternalId, Value>,
+}
+
+impl Columns {
+    fn new(specs: &[ColumnSpec]) -> Self {
+        Self {
+            columns: specs.iter().map(Column::new).collect(),
+            strings: Interner::default(),
+            present: RoaringBitmap::new(),
+            rest: HashMap::new(),
+            other: HashMap::new(),
+        }
+    }
+
+    fn column(&self, name: &str) -> Option<&Column> {
+        self.columns.iter().find(|column| column.name == name)
+    }
+
+    fn insert(&mut self, internal_id: InternalId, metadata: Value) {
+        self.remove(internal_id);
+        self.present.insert(internal_id.as_u32());
+
+        let mut map = match metadata {
+            Value::Object(map) => map,
+            other => {
+                self.other.insert(internal_id, other);
+                return;
+            }
+        };
+        let slot = internal_id.as_usize();
+        for column in &mut self.columns {
+            if let Some(value) = map.remove(&column.name) {
+                if let Some(value) = column.set(slot, value, &mut self.strings) {
+                    map.insert(column.name.clone(), value);
+                }
+            }
+        }
+        if !map.is_empty() {
+            self.rest.insert(internal_id, map);
+        }
+    }
+
+    fn remove(&mut self, internal_id: InternalId) -> Option<Value> {
+        let value = self.get(internal_id)?;
+        self.present.remove(internal_id.as_u32());
+        for column in &mut self.columns {
+            column.valid.remove(internal_id.as_u32());
+        }
+        self.rest.remove(&internal_id);
+        self.other.remove(&internal_id);
+        Some(value)
+    }
+
+    fn get(&self, internal_id: InternalId) -> Option<Value> {
+        if !self.present.contains(internal_id.as_u32()) {
+            return None;
+        }
+        if let Some(other) = self.other.get(&internal_id) {
+            return Some(other.clone());
+        }
+        let mut map = self.rest.get(&internal_id).cloned().unwrap_or_default();
+        let slot = internal_id.as_usize();
+        for column in &self.columns {
+            if let Some(value) = column.get(slot, &self.strings) {
+                map.insert(column.name.clone(), value);
+            }
+        }
+        Some(Value::Object(map))
+    }
+
+    /// `Filter::matches` on the metadata at a present slot, reading declared
+    /// columns directly and materializing the JSON only for anything else
+    fn matches(&self, internal_id: InternalId, filter: &Filter) -> bool {
+        let slot = internal_id.as_usize();
+        let columnar = !self.other.contains_key(&internal_id);
+        let column = |field: &str| {
+            columnar
+                .then(|| self.column(field))
+                .flatten()
+                // A value of another type stays in `rest`
+                .filter(|column| {
+                    column.valid.contains(slot as u32)
+                        || !self
+                            .rest
+                            .get(&internal_id)
+                            .is_some_and(|rest| rest.contains_key(field))
+                })
+        };
+        let valid = |column: &Column| column.valid.contains(slot as u32);
+
+        match filter {
+            Filter::Exact(field, expected) if column(field).is_some() => {
+                column(field).is_some_and(|c| valid(c) && c.equals(slot, expected, &self.strings))
+            }
+            Filter::OneOf(field, allowed) if column(field).is_some() => {
+                column(field).is_some_and(|c| {
+                    valid(c) && allowed.iter().any(|v| c.equals(slot, v, &self.strings))
+                })
+            }
+            Filter::Range {
+                field,
+                gt,
+                gte,
+                lt,
+                lte,
+            } if column(field).is_some() => column(field)
+                .filter(|c| valid(c))
+                .and_then(|c| c.number(slot))
+                .is_some_and(|num| {
+                    let cmp = |limit: &f64| num.partial_cmp(limit);
+                    gt.as_ref()
+                        .is_none_or(|l| cmp(l) == Some(Ordering::Greater))
+                        && gte.as_ref().is_none_or(|l| {
+                            matches!(cmp(l), Some(Ordering::Greater | Ordering::Equal))
+                        })
+                        && lt.as_ref().is_none_or(|l| cmp(l) == Some(Ordering::Less))
+                        && lte.as_ref().is_none_or(|l| {
+                            matches!(cmp(l), Some(Ordering::Less | Ordering::Equal))
+                        })
+                }),
+            Filter::And(filters) => filters.iter().all(|f| self.matches(internal_id, f)),
+            Filter::Or(filters) => filters.iter().any(|f| self.matches(internal_id, f)),
+            Filter::Not(filter) => !self.matches(internal_id, filter),
+            _ => self
+                .get(internal_id)
+                .is_some_and(|metadata| filter.matches(&metadata)),
+        }
+    }
+
+    fn memory_usage(&self, entry: usize) -> usize {
+        let columns: usize = self.columns.iter().map(Column::memory_usage).sum();
+        let strings: usize = self
+            .strings
+            .values
+            .iter()
+            .map(|s| 2 * s.capacity() + 2 * std::mem::size_of::<String>() + 12)
+            .sum();
+        let rest: usize = self
+            .rest
+            .values()
+            .map(|map| entry + map_heap_size(map))
+            .sum();
+        let other: usize = self
+            .other
+            .values()
+            .map(|value| entry + json_heap_size(value))
+            .sum();
+        columns + strings + rest + other + self.present.serialized_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn columnar() -> MetadataStore {
+        MetadataStore::new(&MetadataFormat::Columnar(vec![
+            ColumnSpec::new("category", ColumnKind::String),
+            ColumnSpec::new("count", ColumnKind::Integer),
+            ColumnSpec::new("score", ColumnKind::Float),
+            ColumnSpec::new("active", ColumnKind::Bool),
+        ]))
+    }
+
+    #[test]
+    fn test_columnar_round_trips_metadata() {
+        let mut store = columnar();
+        let values = [
+            json!({"category": "a", "count": 3, "score": 0.5, "active": true, "extra": [1, 2]}),
+            // Mistyped and missing columns fall back to JSON
+            json!({"category": 7, "count": 2.5, "score": 1, "nested": {"x": "y"}}),
+            json!({}),
+            json!("not an object"),
+            json!({"count": -4}),
+        ];
+        for (i, value) in values.iter().enumerate() {
+            store.insert(InternalId::from(i), value.clone());
+        }
+        for (i, value) in values.iter().enumerate() {
+            assert_eq!(store.get(&InternalId::from(i)).as_ref(), Some(value));
+        }
+        assert_eq!(store.get(&InternalId::from(9)), None);
+
+        assert_eq!(store.remove(&InternalId::from(0)), Some(values[0].clone()));
+        assert_eq!(store.get(&InternalId::from(0)), None);
+        store.insert(InternalId::from(1), json!({"category": "b"}));
+        assert_eq!(
+            store.get(&InternalId::from(1)),
+            Some(json!({"category": "b"}))
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_columns() {
+        for specs in [
+            vec![ColumnSpec::new("a.b", ColumnKind::String)],
+            vec![ColumnSpec::new("", ColumnKind::Bool)],
+            vec![
+                ColumnSpec::new("a", ColumnKind::Bool),
+                ColumnSpec::new("a", ColumnKind::Float),
+            ],
+        ] {
+            assert!(MetadataFormat::Columnar(specs).validate().is_err());
+        }
+        assert!(MetadataFormat::Json.validate().is_ok());
+    }
+}
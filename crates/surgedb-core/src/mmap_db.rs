@@ -5,11 +5,18 @@
 
 use crate::distance::DistanceMetric;
 use crate::error::{Error, Result};
-use crate::hnsw::{HnswConfig, HnswIndex};
+use crate::hnsw::{HnswConfig, HnswIndex, HnswState};
+use crate::mmap_graph::MmapGraph;
 use crate::mmap_storage::MmapStorage;
 use crate::types::VectorId;
 use std::path::{Path, PathBuf};
 
+/// Header for the persisted HNSW graph (`index.state`)
+const GRAPH_MAGIC: &[u8; 4] = b"ZHNS";
+/// Bump whenever `HnswState`'s serialized layout changes, and teach
+/// `decode_graph` to migrate the previous version
+const GRAPH_VERSION: u8 = 1;
+
 /// Configuration for mmap-based database
 #[derive(Debug, Clone)]
 pub struct MmapConfig {
@@ -19,6 +26,15 @@ pub struct MmapConfig {
     pub distance_metric: DistanceMetric,
     /// HNSW configuration
     pub hnsw: HnswConfig,
+    /// Compute search distances directly against the mapped file instead of
+    /// copying each vector out first. Only worth disabling to compare paths.
+    pub zero_copy: bool,
+    /// Also write the graph in a memory-mapped layout (`graph.mmap`) and
+    /// search it from the mapping, so the graph need not fit in RAM. A write
+    /// loads the graph back into memory until the next `sync`.
+    pub mmap_graph: bool,
+    /// Reject vectors with NaN or infinite components (see `Config::validate_finite`)
+    pub validate_finite: bool,
 }
 
 impl Default for MmapConfig {
@@ -27,6 +43,9 @@ impl Default for MmapConfig {
             dimensions: 384,
             distance_metric: DistanceMetric::Cosine,
             hnsw: HnswConfig::default(),
+            zero_copy: true,
+            mmap_graph: false,
+            validate_finite: true,
         }
     }
 }
@@ -35,61 +54,116 @@ impl Default for MmapConfig {
 ///
 /// Uses memory-mapped storage for vectors, allowing datasets larger than RAM.
 /// The HNSW index is kept in memory for fast search, while vector data is
-/// demand-paged from disk.
+/// demand-paged from disk. With `MmapConfig::mmap_graph` the index is
+/// demand-paged too between writes.
 pub struct MmapVectorDb {
     config: MmapConfig,
     storage: MmapStorage,
     index: HnswIndex,
+    /// The graph searched instead of `index` while it is unmodified; `index`
+    /// is empty meanwhile
+    mapped: Option<MmapGraph>,
     data_dir: PathBuf,
 }
 
 impl MmapVectorDb {
     /// Open or create a mmap-based database at the given path
     pub fn open(path: impl AsRef<Path>, config: MmapConfig) -> Result<Self> {
+        config.hnsw.validate()?;
+        config.distance_metric.check_storage(false)?;
         let data_dir = path.as_ref().to_path_buf();
         std::fs::create_dir_all(&data_dir)?;
 
-        let storage = MmapStorage::open(&data_dir, config.dimensions)?;
+        let mut storage = MmapStorage::open(&data_dir, config.dimensions)?;
+        storage.set_zero_copy(config.zero_copy);
         let index = HnswIndex::new(config.hnsw.clone(), config.distance_metric);
 
         let mut db = Self {
             config,
             storage,
             index,
+            mapped: None,
             data_dir,
         };
 
-        // Try to load index from disk, otherwise rebuild
-        if db.load_index().is_err() {
-            db.rebuild_index()?;
-            db.save_index()?;
+        if db.config.mmap_graph {
+            // A graph file that lags the vectors (a crash before `sync`) is
+            // ignored and rewritten from `index.state`
+            if let Ok(graph) = MmapGraph::open(db.data_dir.join("graph.mmap")) {
+                if graph.len() == db.storage.len() {
+                    db.mapped = Some(graph);
+                    return Ok(db);
+                }
+            }
+        }
+
+        // Try to load index from disk, otherwise rebuild. A graph written by a
+        // newer format is an error rather than something to silently discard.
+        match db.load_index() {
+            Ok(false) => {}
+            // Rewrite migrated graphs in the current format
+            Ok(true) => db.save_index()?,
+            Err(e @ Error::UnsupportedVersion { .. }) => return Err(e),
+            Err(_) => {
+                db.rebuild_index()?;
+                db.save_index()?;
+            }
+        }
+        if db.config.mmap_graph {
+            db.map_graph()?;
         }
 
         Ok(db)
     }
 
-    /// Load HNSW index state from disk
-    fn load_index(&self) -> Result<()> {
+    /// Load HNSW index state from disk, returning whether it was migrated
+    /// from an older format
+    fn load_index(&self) -> Result<bool> {
         let path = self.data_dir.join("index.state");
         if !path.exists() {
             return Err(Error::Storage("Index state not found".into()));
         }
 
         let data = std::fs::read(path)?;
-        let state = bincode::deserialize(&data).map_err(|e| Error::Storage(e.to_string()))?;
+        let (state, migrated) = decode_graph(&data)?;
         self.index.load_state(state);
-        Ok(())
+        Ok(migrated)
     }
 
     /// Save HNSW index state to disk
     pub fn save_index(&self) -> Result<()> {
+        if self.mapped.is_some() {
+            // Unmodified since it was written
+            return Ok(());
+        }
         let path = self.data_dir.join("index.state");
-        let state = self.index.get_state();
-        let data = bincode::serialize(&state).map_err(|e| Error::Storage(e.to_string()))?;
+        let data = encode_graph(&self.index.get_state(), GRAPH_VERSION)?;
         std::fs::write(path, data)?;
         Ok(())
     }
 
+    /// Write the in-memory graph to `graph.mmap`, then search the mapping
+    /// and release the in-memory copy
+    fn map_graph(&mut self) -> Result<()> {
+        let path = self.data_dir.join("graph.mmap");
+        self.index.save_mapped(&path)?;
+        self.mapped = Some(MmapGraph::open(path)?);
+        self.index.clear();
+        Ok(())
+    }
+
+    /// Load a mapped graph back into memory before modifying it
+    fn unmap_graph(&mut self) {
+        if let Some(graph) = self.mapped.take() {
+            self.index.load_state(graph.to_state());
+        }
+    }
+
+    /// Whether searches currently walk the graph mapped from `graph.mmap`
+    pub fn is_graph_mapped(&self) -> bool {
+        self.mapped.is_some()
+    }
+
     /// Rebuild HNSW index from storage
     fn rebuild_index(&mut self) -> Result<()> {
         let ids = self.storage.all_internal_ids();
@@ -113,15 +187,48 @@ impl MmapVectorDb {
                 got: vector.len(),
             });
         }
+        crate::types::check_finite(vector, self.config.validate_finite)?;
 
+        self.unmap_graph();
         let internal_id = self.storage.insert(id, vector)?;
         self.index.insert(internal_id, vector, &self.storage)?;
 
         Ok(())
     }
 
+    /// Drop every vector and reset the index, truncating the files on disk
+    pub fn clear(&mut self) -> Result<()> {
+        self.mapped = None;
+        self.storage.clear()?;
+        self.index.clear();
+        self.save_index()?;
+        if self.config.mmap_graph {
+            self.map_graph()?;
+        }
+        Ok(())
+    }
+
     /// Search for the k nearest neighbors
     pub fn search(&self, query: &[f32], k: usize) -> Result<Vec<(VectorId, f32)>> {
+        self.search_inner(query, k, None)
+    }
+
+    /// Like `search`, with a candidate list of `ef` (see `VectorDb::search_with_ef`)
+    pub fn search_with_ef(
+        &self,
+        query: &[f32],
+        k: usize,
+        ef: usize,
+    ) -> Result<Vec<(VectorId, f32)>> {
+        self.search_inner(query, k, Some(ef))
+    }
+
+    fn search_inner(
+        &self,
+        query: &[f32],
+        k: usize,
+        ef: Option<usize>,
+    ) -> Result<Vec<(VectorId, f32)>> {
         if query.len() != self.config.dimensions {
             return Err(Error::DimensionMismatch {
                 expected: self.config.dimensions,
@@ -129,7 +236,11 @@ impl MmapVectorDb {
             });
         }
 
-        let results = self.index.search(query, k, &self.storage.view(), None)?;
+        let storage = self.storage.view();
+        let (results, _) = match &self.mapped {
+            Some(graph) => self.index.search_mapped(graph, query, k, ef, &storage)?,
+            None => self.index.search_with_ef(query, k, ef, &storage, None)?,
+        };
 
         let mapped: Vec<(VectorId, f32)> = results
             .into_iter()
@@ -169,13 +280,47 @@ impl MmapVectorDb {
     }
 
     /// Sync data to disk
-    pub fn sync(&self) -> Result<()> {
+    ///
+    /// With `MmapConfig::mmap_graph`, this also rewrites `graph.mmap` and
+    /// switches searches back to the mapping, so it takes `&mut self`.
+    pub fn sync(&mut self) -> Result<()> {
         self.storage.sync()?;
         self.save_index()?;
+        if self.config.mmap_graph && self.mapped.is_none() {
+            self.map_graph()?;
+        }
         Ok(())
     }
 }
 
+/// Serialize a graph as `magic(4) + version(1) + bincode(state)`
+fn encode_graph(state: &HnswState, version: u8) -> Result<Vec<u8>> {
+    let mut data = Vec::with_capacity(GRAPH_MAGIC.len() + 1);
+    data.extend_from_slice(GRAPH_MAGIC);
+    data.push(version);
+    bincode::serialize_into(&mut data, state).map_err(|e| Error::Storage(e.to_string()))?;
+    Ok(data)
+}
+
+/// Deserialize a graph written by `encode_graph`, returning whether it had to
+/// be migrated. Files without the magic predate versioning and hold a bare
+/// bincode `HnswState` (version 0).
+fn decode_graph(data: &[u8]) -> Result<(HnswState, bool)> {
+    let decode = |bytes: &[u8]| -> Result<HnswState> {
+        bincode::deserialize(bytes).map_err(|e| Error::Storage(e.to_string()))
+    };
+
+    match data.strip_prefix(GRAPH_MAGIC.as_slice()) {
+        None => Ok((decode(data)?, true)),
+        Some([GRAPH_VERSION, state @ ..]) => Ok((decode(state)?, false)),
+        Some([version, ..]) => Err(Error::UnsupportedVersion {
+            version: *version,
+            supported: "0-1",
+        }),
+        Some([]) => Err(Error::Storage("Truncated index state header".into())),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -201,6 +346,35 @@ mod tests {
         assert_eq!(results[0].0.as_str(), "vec1");
     }
 
+    #[test]
+    fn test_mmap_db_rejects_non_finite() {
+        let dir = tempdir().unwrap();
+        let config = MmapConfig {
+            dimensions: 4,
+            ..Default::default()
+        };
+
+        let mut db = MmapVectorDb::open(dir.path(), config.clone()).unwrap();
+        assert!(matches!(
+            db.insert("nan", &[1.0, f32::NAN, 0.0, 0.0]),
+            Err(Error::InvalidVector { index: 1, .. })
+        ));
+        assert!(db.insert("inf", &[f32::INFINITY, 0.0, 0.0, 0.0]).is_err());
+        assert!(db.is_empty());
+        drop(db);
+
+        let mut db = MmapVectorDb::open(
+            dir.path(),
+            MmapConfig {
+                validate_finite: false,
+                ..config
+            },
+        )
+        .unwrap();
+        db.insert("nan", &[1.0, f32::NAN, 0.0, 0.0]).unwrap();
+        assert_eq!(db.len(), 1);
+    }
+
     #[test]
     fn test_mmap_db_persistence() {
         let dir = tempdir().unwrap();
@@ -256,4 +430,131 @@ mod tests {
         let expected_min = 1000 * 128 * 4; // At least the raw vector data
         assert!(db.disk_usage() >= expected_min as u64);
     }
+
+    #[test]
+    fn test_mmap_db_rejects_newer_graph_version() {
+        let dir = tempdir().unwrap();
+        let config = MmapConfig {
+            dimensions: 4,
+            ..Default::default()
+        };
+
+        let state = {
+            let mut db = MmapVectorDb::open(dir.path(), config.clone()).unwrap();
+            db.insert("vec1", &[1.0, 0.0, 0.0, 0.0]).unwrap();
+            db.insert("vec2", &[0.0, 1.0, 0.0, 0.0]).unwrap();
+            db.sync().unwrap();
+            db.index.get_state()
+        };
+
+        // A graph from a future layout must not be loaded or overwritten
+        let path = dir.path().join("index.state");
+        let future = encode_graph(&state, GRAPH_VERSION + 1).unwrap();
+        std::fs::write(&path, &future).unwrap();
+        match MmapVectorDb::open(dir.path(), config.clone()) {
+            Err(Error::UnsupportedVersion { version, .. }) => {
+                assert_eq!(version, GRAPH_VERSION + 1)
+            }
+            other => panic!("expected UnsupportedVersion, got {:?}", other.err()),
+        }
+        assert_eq!(std::fs::read(&path).unwrap(), future);
+
+        // Pre-versioning graphs are migrated to the current format
+        std::fs::write(&path, bincode::serialize(&state).unwrap()).unwrap();
+        let db = MmapVectorDb::open(dir.path(), config).unwrap();
+        assert_eq!(
+            db.search(&[1.0, 0.0, 0.0, 0.0], 1).unwrap()[0].0.as_str(),
+            "vec1"
+        );
+        let migrated = std::fs::read(&path).unwrap();
+        assert_eq!(&migrated[..4], GRAPH_MAGIC);
+        assert_eq!(migrated[4], GRAPH_VERSION);
+    }
+
+    #[test]
+    fn test_zero_copy_search_matches_copying_path() {
+        let dir = tempdir().unwrap();
+        let config = MmapConfig {
+            dimensions: 48,
+            ..Default::default()
+        };
+        let queries: Vec<Vec<f32>> = (0..10)
+            .map(|q| (0..48).map(|j| ((q * 7 + j) as f32).cos()).collect())
+            .collect();
+
+        let zero_copy: Vec<_> = {
+            let mut db = MmapVectorDb::open(dir.path(), config.clone()).unwrap();
+            for i in 0..500 {
+                let vector: Vec<f32> = (0..48).map(|j| ((i * 31 + j) as f32).sin()).collect();
+                db.insert(format!("v{}", i), &vector).unwrap();
+            }
+            db.sync().unwrap();
+            queries.iter().map(|q| db.search(q, 10).unwrap()).collect()
+        };
+
+        // Same graph from disk, distances computed on copied vectors
+        let copying = MmapVectorDb::open(
+            dir.path(),
+            MmapConfig {
+                zero_copy: false,
+                ..config
+            },
+        )
+        .unwrap();
+        for (query, expected) in queries.iter().zip(&zero_copy) {
+            assert_eq!(&copying.search(query, 10).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_mmap_graph_lifecycle() {
+        let dir = tempdir().unwrap();
+        let config = MmapConfig {
+            dimensions: 16,
+            mmap_graph: true,
+            ..Default::default()
+        };
+        let vector =
+            |i: usize| -> Vec<f32> { (0..16).map(|j| ((i * 13 + j) as f32).sin()).collect() };
+        let queries: Vec<Vec<f32>> = (0..10).map(|q| vector(q * 37 + 5)).collect();
+
+        let mut db = MmapVectorDb::open(dir.path(), config.clone()).unwrap();
+        for i in 0..400 {
+            db.insert(format!("v{}", i), &vector(i)).unwrap();
+        }
+        assert!(!db.is_graph_mapped());
+        let in_memory: Vec<_> = queries.iter().map(|q| db.search(q, 10).unwrap()).collect();
+
+        // The same graph, walked from the mapping
+        db.sync().unwrap();
+        assert!(db.is_graph_mapped());
+        assert_eq!(db.index.len(), 0);
+        for (query, expected) in queries.iter().zip(&in_memory) {
+            assert_eq!(&db.search(query, 10).unwrap(), expected);
+        }
+
+        // A write loads the graph back, and the next sync maps it again
+        db.insert("late", &vector(1000)).unwrap();
+        assert!(!db.is_graph_mapped());
+        assert_eq!(db.search(&vector(1000), 1).unwrap()[0].0.as_str(), "late");
+        db.sync().unwrap();
+        drop(db);
+
+        let db = MmapVectorDb::open(dir.path(), config.clone()).unwrap();
+        assert!(db.is_graph_mapped());
+        assert_eq!(db.search(&vector(1000), 1).unwrap()[0].0.as_str(), "late");
+
+        // index.state stays current for opening without the mapped graph
+        drop(db);
+        let db = MmapVectorDb::open(
+            dir.path(),
+            MmapConfig {
+                mmap_graph: false,
+                ..config
+            },
+        )
+        .unwrap();
+        assert!(!db.is_graph_mapped());
+        assert_eq!(db.search(&vector(1000), 1).unwrap()[0].0.as_str(), "late");
+    }
 }
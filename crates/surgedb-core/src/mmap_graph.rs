@@ -0,0 +1,360 @@
+//! Memory-mapped HNSW graph
+//!
+//! Lays a graph's neighbor lists out as fixed-width rows in a flat file, so
+//! a search can walk the graph straight from the mapping and only the rows
+//! it visits are paged in, instead of every node being held in RAM.
+//!
+//! ## Layout
+//! All integers are little-endian `u32`s.
+//! - Header (8 words): magic, version, `m0`, `m`, node count, entry point,
+//!   max layer and number of upper-layer rows
+//! - Node table: `(max_layer, first_upper_row)` for each node
+//! - Layer 0: one row of `m0` neighbors per node
+//! - Upper layers: rows of `m` neighbors; a node's layers `1..=max_layer`
+//!   are the consecutive rows from its `first_upper_row`
+//!
+//! Rows with fewer neighbors than their width are padded with `SENTINEL`,
+//! which also stands for a missing entry point or upper row.
+
+use crate::error::{Error, Result};
+use crate::hnsw::{GraphLayout, HnswNode, HnswState};
+use crate::mmap_storage::Mmap;
+use crate::types::InternalId;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+const GRAPH_MAGIC: &[u8; 4] = b"ZHGM";
+const GRAPH_VERSION: u8 = 1;
+const HEADER_WORDS: usize = 8;
+/// Pads neighbor rows and marks absent IDs
+const SENTINEL: u32 = u32::MAX;
+
+/// A read-only HNSW graph mapped from a file written by `write`
+pub struct MmapGraph {
+    mmap: Mmap,
+    m0: usize,
+    m: usize,
+    node_count: usize,
+    entry_point: Option<InternalId>,
+    max_layer: usize,
+    /// Word offsets of the layer-0 and upper-layer rows
+    layer0_start: usize,
+    upper_start: usize,
+}
+
+impl MmapGraph {
+    /// Write `nodes` in the mapped layout with rows of `m0` and `m`
+    /// neighbors, replacing `path` atomically
+    ///
+    /// Fails if a neighbor list is longer than its row; repair the graph's
+    /// degrees (`HnswIndex::auto_repair_degrees`) first.
+    pub(crate) fn write(
+        path: &Path,
+        nodes: &[HnswNode],
+        entry_point: Option<InternalId>,
+        max_layer: usize,
+        (m0, m): (usize, usize),
+    ) -> Result<()> {
+        for node in nodes {
+            for (layer, neighbors) in node.neighbors.iter().enumerate() {
+                let width = if layer == 0 { m0 } else { m };
+                if neighbors.len() > width {
+                    return Err(Error::Storage(format!(
+                        "Node {} has {} neighbors on layer {}, more than the {} a mapped row holds",
+                        node.id.as_u32(),
+                        neighbors.len(),
+                        layer,
+                        width
+                    )));
+                }
+            }
+        }
+
+        let tmp = path.with_extension("tmp");
+        let mut out = BufWriter::new(File::create(&tmp)?);
+
+        let upper_rows: usize = nodes.iter().map(|node| node.max_layer).sum();
+        let entry_point = entry_point.map_or(SENTINEL, |ep| ep.as_u32());
+        out.write_all(GRAPH_MAGIC)?;
+        out.write_all(&[GRAPH_VERSION, 0, 0, 0])?;
+        for word in [m0, m, nodes.len()] {
+            out.write_all(&(word as u32).to_le_bytes())?;
+        }
+        out.write_all(&entry_point.to_le_bytes())?;
+        for word in [max_layer, upper_rows] {
+            out.write_all(&(word as u32).to_le_bytes())?;
+        }
+
+        let mut next_row = 0u32;
+        for node in nodes {
+            let first_row = if node.max_layer == 0 {
+                SENTINEL
+            } else {
+                next_row
+            };
+            next_row += node.max_layer as u32;
+            out.write_all(&(node.max_layer as u32).to_le_bytes())?;
+            out.write_all(&first_row.to_le_bytes())?;
+        }
+        for node in nodes {
+            write_row(&mut out, &node.neighbors[0], m0)?;
+        }
+        for node in nodes {
+            for neighbors in &node.neighbors[1..] {
+                write_row(&mut out, neighbors, m)?;
+            }
+        }
+
+        let file = out.into_inner().map_err(|e| e.into_error())?;
+        file.sync_all()?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Map a graph file written by `write`
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let file = File::open(path.as_ref())?;
+        let len = file.metadata()?.len() as usize;
+        if len < HEADER_WORDS * 4 {
+            return Err(Error::Storage("Truncated graph file".into()));
+        }
+        let mmap = Mmap::new(&file, len)?;
+        let bytes = mmap.as_slice();
+        if bytes.len() != len {
+            return Err(Error::Storage(
+                "Memory-mapped graphs are not supported on this platform".into(),
+            ));
+        }
+        if &bytes[0..4] != GRAPH_MAGIC {
+            return Err(Error::Storage("Invalid graph file".into()));
+        }
+        if bytes[4] != GRAPH_VERSION {
+            return Err(Error::UnsupportedVersion {
+                version: bytes[4],
+                supported: "1",
+            });
+        }
+
+        let header = |index| word(bytes, index) as usize;
+        let (m0, m, node_count) = (header(2), header(3), header(4));
+        let (max_layer, upper_rows) = (header(6), header(7));
+        let layer0_start = HEADER_WORDS + 2 * node_count;
+        let upper_start = layer0_start + node_count * m0;
+        if len != (upper_start + upper_rows * m) * 4 {
+            return Err(Error::Storage(
+                "Graph file size does not match its header".into(),
+            ));
+        }
+        let entry_point = match word(bytes, 5) {
+            SENTINEL => None,
+            ep if (ep as usize) < node_count => Some(InternalId(ep)),
+            ep => {
+                return Err(Error::Storage(format!(
+                    "Graph entry point {} is out of range",
+                    ep
+                )))
+            }
+        };
+
+        Ok(Self {
+            mmap,
+            m0,
+            m,
+            node_count,
+            entry_point,
+            max_layer,
+            layer0_start,
+            upper_start,
+        })
+    }
+
+    /// Number of nodes, including removed ones (which have no edges)
+    pub fn len(&self) -> usize {
+        self.node_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.node_count == 0
+    }
+
+    pub fn entry_point(&self) -> Option<InternalId> {
+        self.entry_point
+    }
+
+    pub fn max_layer(&self) -> usize {
+        self.max_layer
+    }
+
+    /// Size of the mapped file in bytes
+    pub fn disk_usage(&self) -> u64 {
+        self.mmap.as_slice().len() as u64
+    }
+
+    /// Copy the graph back into memory, e.g. to modify it
+    pub fn to_state(&self) -> HnswState {
+        let nodes = (0..self.node_count)
+            .map(|i| {
+                let id = InternalId::from(i);
+                let max_layer = self.node_max_layer(i);
+                HnswNode {
+                    id,
+                    max_layer,
+                    neighbors: (0..=max_layer)
+                        .map(|layer| self.neighbors_on(id, layer).collect())
+                        .collect(),
+                }
+            })
+            .collect();
+        HnswState {
+            nodes,
+            entry_point: self.entry_point,
+            max_layer: self.max_layer,
+        }
+    }
+
+    fn node_max_layer(&self, node: usize) -> usize {
+        word(self.mmap.as_slice(), HEADER_WORDS + 2 * node) as usize
+    }
+
+    /// Word range of `node`'s row on `layer`, if it has one
+    fn row(&self, node: usize, layer: usize) -> Option<std::ops::Range<usize>> {
+        if node >= self.node_count {
+            return None;
+        }
+        if layer == 0 {
+            let start = self.layer0_start + node * self.m0;
+            return Some(start..start + self.m0);
+        }
+        if layer > self.node_max_layer(node) {
+            return None;
+        }
+        let first_row = word(self.mmap.as_slice(), HEADER_WORDS + 2 * node + 1) as usize;
+        let start = self.upper_start + (first_row + layer - 1) * self.m;
+        Some(start..start + self.m)
+    }
+}
+
+impl GraphLayout for MmapGraph {
+    fn neighbors_on(
+        &self,
+        node: InternalId,
+        layer: usize,
+    ) -> impl Iterator<Item = InternalId> + '_ {
+        let bytes = self
+            .row(node.as_usize(), layer)
+            .and_then(|row| self.mmap.as_slice().get(row.start * 4..row.end * 4))
+            .unwrap_or_default();
+        bytes
+            .chunks_exact(4)
+            .map(|w| u32::from_le_bytes([w[0], w[1], w[2], w[3]]))
+            .take_while(|&id| id != SENTINEL)
+            .map(InternalId)
+    }
+}
+
+fn word(bytes: &[u8], index: usize) -> u32 {
+    let at = index * 4;
+    u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+}
+
+/// Write `neighbors` padded to `width` with `SENTINEL`
+fn write_row(out: &mut impl Write, neighbors: &[InternalId], width: usize) -> Result<()> {
+    for neighbor in neighbors {
+        out.write_all(&neighbor.as_u32().to_le_bytes())?;
+    }
+    for _ in neighbors.len()..width {
+        out.write_all(&SENTINEL.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distance::DistanceMetric;
+    use crate::hnsw::{HnswConfig, HnswIndex};
+    use crate::storage::VectorStorage;
+    use tempfile::tempdir;
+
+    fn build(count: usize) -> (HnswIndex, VectorStorage) {
+        let storage = VectorStorage::new(8);
+        let index = HnswIndex::new(
+            HnswConfig {
+                m: 4,
+                m0: 8,
+                ..Default::default()
+            },
+            DistanceMetric::Euclidean,
+        );
+        for i in 0..count {
+            let vector: Vec<f32> = (0..8).map(|d| ((i * 7 + d * 13) as f32).sin()).collect();
+            let id = storage.insert(i.to_string().into(), &vector, None).unwrap();
+            index.insert(id, &vector, &storage).unwrap();
+        }
+        (index, storage)
+    }
+
+    #[test]
+    fn test_mapped_graph_matches_in_memory_graph() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("graph.mmap");
+        let (index, storage) = build(300);
+        index.save_mapped(&path).unwrap();
+        let graph = MmapGraph::open(&path).unwrap();
+
+        let state = index.get_state();
+        assert_eq!(graph.len(), 300);
+        assert_eq!(graph.entry_point(), state.entry_point);
+        assert_eq!(graph.max_layer(), state.max_layer);
+        assert!(state.max_layer > 0);
+        let mapped = graph.to_state();
+        for (node, copy) in state.nodes.iter().zip(&mapped.nodes) {
+            assert_eq!(node.max_layer, copy.max_layer);
+            assert_eq!(node.neighbors, copy.neighbors);
+        }
+
+        // Rows are fixed width, whatever each node's degree
+        let rows = 300 * 8 + state.nodes.iter().map(|n| n.max_layer).sum::<usize>() * 4;
+        assert_eq!(
+            graph.disk_usage() as usize,
+            (HEADER_WORDS + 2 * 300 + rows) * 4
+        );
+
+        for i in 0..20 {
+            let query: Vec<f32> = (0..8).map(|d| ((i * 3 + d) as f32).cos()).collect();
+            assert_eq!(
+                index
+                    .search_mapped(&graph, &query, 5, None, &storage)
+                    .unwrap(),
+                index
+                    .search_with_ef(&query, 5, None, &storage, None)
+                    .unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_rejects_overfull_rows_and_bad_files() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("graph.mmap");
+        let (index, _) = build(50);
+        let mut state = index.get_state();
+        state.nodes[3].neighbors[0] = (0..9).map(InternalId::from).collect();
+        let err = MmapGraph::write(&path, &state.nodes, state.entry_point, 0, (8, 4));
+        assert!(matches!(err, Err(Error::Storage(_))), "{:?}", err);
+        assert!(!path.exists() && !path.with_extension("tmp").exists());
+
+        index.save_mapped(&path).unwrap();
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes.pop();
+        std::fs::write(&path, &bytes).unwrap();
+        assert!(MmapGraph::open(&path).is_err());
+        bytes[4] = GRAPH_VERSION + 1;
+        std::fs::write(&path, &bytes).unwrap();
+        assert!(matches!(
+            MmapGraph::open(&path),
+            Err(Error::UnsupportedVersion { .. })
+        ));
+    }
+}
@@ -44,10 +44,13 @@ pub struct MmapStorage {
 
     /// Current file size in bytes
     file_size: RwLock<u64>,
+
+    /// Compute distances against the mapped bytes without copying
+    zero_copy: bool,
 }
 
 /// Simple mmap wrapper
-struct Mmap {
+pub(crate) struct Mmap {
     ptr: *mut u8,
     len: usize,
 }
@@ -59,7 +62,7 @@ unsafe impl Sync for Mmap {}
 
 impl Mmap {
     #[cfg(unix)]
-    fn new(file: &File, len: usize) -> Result<Self> {
+    pub(crate) fn new(file: &File, len: usize) -> Result<Self> {
         use std::os::unix::io::AsRawFd;
 
         if len == 0 {
@@ -96,7 +99,7 @@ impl Mmap {
     }
 
     #[cfg(not(unix))]
-    fn new(_file: &File, len: usize) -> Result<Self> {
+    pub(crate) fn new(_file: &File, len: usize) -> Result<Self> {
         // Fallback for non-Unix platforms
         Ok(Self {
             ptr: std::ptr::null_mut(),
@@ -104,7 +107,7 @@ impl Mmap {
         })
     }
 
-    fn as_slice(&self) -> &[u8] {
+    pub(crate) fn as_slice(&self) -> &[u8] {
         if self.ptr.is_null() || self.len == 0 {
             &[]
         } else {
@@ -211,6 +214,7 @@ impl MmapStorage {
             id_to_internal: RwLock::new(id_to_internal),
             internal_to_id: RwLock::new(internal_to_id),
             file_size: RwLock::new(file_size),
+            zero_copy: true,
         })
     }
 
@@ -330,6 +334,25 @@ impl MmapStorage {
         Ok(internal_id)
     }
 
+    /// Drop every vector, truncating the data file back to its header
+    pub fn clear(&self) -> Result<()> {
+        {
+            let data_file = self.data_file.write();
+            // Unmap before truncating so no mapping outlives the file's pages
+            let mut mmap = self.mmap.write();
+            *mmap = None;
+            data_file.set_len(HEADER_SIZE as u64)?;
+            data_file.sync_all()?;
+
+            *self.count.write() = 0;
+            *self.file_size.write() = HEADER_SIZE as u64;
+            self.id_to_internal.write().clear();
+            self.internal_to_id.write().clear();
+        }
+
+        self.save_index()
+    }
+
     /// Remap the file after it grows
     fn remap(&self) -> Result<()> {
         let data_file = self.data_file.read();
@@ -344,32 +367,11 @@ impl MmapStorage {
         Ok(())
     }
 
-    /// Get a vector by internal ID (zero-copy when possible)
+    /// Get a copy of a vector by internal ID
     #[inline]
     pub fn get(&self, internal_id: InternalId) -> Option<Vec<f32>> {
         let mmap = self.mmap.read();
-        let mmap = mmap.as_ref()?;
-
-        let bytes = mmap.as_slice();
-        if bytes.is_empty() {
-            return None;
-        }
-
-        let vector_size = self.dimensions * 4; // f32 = 4 bytes
-        let offset = HEADER_SIZE + internal_id.as_usize() * vector_size;
-        let end = offset + vector_size;
-
-        if end > bytes.len() {
-            return None;
-        }
-
-        let vector_bytes = &bytes[offset..end];
-        let vector: Vec<f32> = vector_bytes
-            .chunks_exact(4)
-            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
-            .collect();
-
-        Some(vector)
+        vector_bytes(mmap.as_ref()?.as_slice(), self.dimensions, internal_id).map(decode)
     }
 
     /// Get vector data for distance calculation
@@ -378,6 +380,12 @@ impl MmapStorage {
         self.get(internal_id)
     }
 
+    /// Choose whether distances read the mapped bytes in place (the default)
+    /// or copy each vector out first
+    pub fn set_zero_copy(&mut self, enabled: bool) {
+        self.zero_copy = enabled;
+    }
+
     /// Get external ID from internal ID
     pub fn get_external_id(&self, internal_id: InternalId) -> Option<VectorId> {
         let internal_to_id = self.internal_to_id.read();
@@ -429,6 +437,7 @@ impl MmapStorage {
         MmapStorageView {
             guard: self.mmap.read(),
             dimensions: self.dimensions,
+            zero_copy: self.zero_copy,
         }
     }
 }
@@ -437,32 +446,17 @@ impl MmapStorage {
 pub struct MmapStorageView<'a> {
     guard: parking_lot::RwLockReadGuard<'a, Option<Mmap>>,
     dimensions: usize,
+    zero_copy: bool,
 }
 
 impl<'a> crate::storage::VectorStorageTrait for MmapStorageView<'a> {
     fn get_vector_data(&self, internal_id: InternalId) -> Option<Vec<f32>> {
-        let mmap = self.guard.as_ref()?;
-        let bytes = mmap.as_slice();
-
-        if bytes.is_empty() {
-            return None;
-        }
-
-        let vector_size = self.dimensions * 4;
-        let offset = HEADER_SIZE + internal_id.as_usize() * vector_size;
-        let end = offset + vector_size;
-
-        if end > bytes.len() {
-            return None;
-        }
-
-        let vector_bytes = &bytes[offset..end];
-        let vector: Vec<f32> = vector_bytes
-            .chunks_exact(4)
-            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
-            .collect();
-
-        Some(vector)
+        vector_bytes(
+            self.guard.as_ref()?.as_slice(),
+            self.dimensions,
+            internal_id,
+        )
+        .map(decode)
     }
 
     fn distance(
@@ -471,34 +465,12 @@ impl<'a> crate::storage::VectorStorageTrait for MmapStorageView<'a> {
         query: &[f32],
         metric: DistanceMetric,
     ) -> Option<f32> {
-        let mmap = self.guard.as_ref()?;
-        let bytes = mmap.as_slice();
-
-        if bytes.is_empty() {
-            return None;
-        }
-
-        let vector_size = self.dimensions * 4;
-        let offset = HEADER_SIZE + internal_id.as_usize() * vector_size;
-        let end = offset + vector_size;
-
-        if end > bytes.len() {
-            return None;
-        }
-
-        let vector_bytes = &bytes[offset..end];
-
-        let (prefix, vector_slice, suffix) = unsafe { vector_bytes.align_to::<f32>() };
-
-        if !prefix.is_empty() || !suffix.is_empty() {
-            let vector: Vec<f32> = vector_bytes
-                .chunks_exact(4)
-                .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
-                .collect();
-            Some(metric.distance(query, &vector))
-        } else {
-            Some(metric.distance(query, vector_slice))
-        }
+        let bytes = vector_bytes(
+            self.guard.as_ref()?.as_slice(),
+            self.dimensions,
+            internal_id,
+        )?;
+        Some(distance_to(bytes, query, metric, self.zero_copy))
     }
 }
 
@@ -515,44 +487,66 @@ impl crate::storage::VectorStorageTrait for MmapStorage {
         metric: DistanceMetric,
     ) -> Option<f32> {
         let mmap = self.mmap.read();
-        let mmap = mmap.as_ref()?;
-
-        let bytes = mmap.as_slice();
-        if bytes.is_empty() {
-            return None;
-        }
-
-        let vector_size = self.dimensions * 4; // f32 = 4 bytes
-        let offset = HEADER_SIZE + internal_id.as_usize() * vector_size;
-        let end = offset + vector_size;
+        let bytes = vector_bytes(mmap.as_ref()?.as_slice(), self.dimensions, internal_id)?;
+        Some(distance_to(bytes, query, metric, self.zero_copy))
+    }
+}
 
-        if end > bytes.len() {
-            return None;
-        }
+/// The stored bytes of one vector, if it lies within the mapped region
+#[inline]
+fn vector_bytes(bytes: &[u8], dimensions: usize, internal_id: InternalId) -> Option<&[u8]> {
+    let vector_size = dimensions * 4; // f32 = 4 bytes
+    let offset = HEADER_SIZE + internal_id.as_usize() * vector_size;
+    bytes.get(offset..offset + vector_size)
+}
 
-        let vector_bytes = &bytes[offset..end];
+/// Decode little-endian f32s into a new vector
+#[inline]
+fn decode(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect()
+}
 
-        // This is safe because f32 alignment is usually 4, and mmap gives aligned pages.
-        // However, to be strictly safe and avoid unaligned access on some archs,
-        // we might need `align_to`, but for performance we assume alignment or handle it.
-        // For now, we will perform a safe cast if aligned, or fallback to copy if not?
-        // Actually, SIMD functions in distance.rs usually handle unaligned loads (vld1q_f32 on NEON handles unaligned).
-        // But we need a &[f32] slice. casting &[u8] to &[f32] is unsafe if not aligned.
+thread_local! {
+    /// Scratch vector for unaligned reads, reused across distance calls
+    static UNALIGNED_SCRATCH: std::cell::RefCell<Vec<f32>> = const {
+        std::cell::RefCell::new(Vec::new())
+    };
+}
 
-        let (prefix, vector_slice, suffix) = unsafe { vector_bytes.align_to::<f32>() };
+/// Distance from `query` to the vector stored in `bytes`
+///
+/// With `zero_copy`, an aligned little-endian vector is reinterpreted as
+/// `&[f32]` in place, and an unaligned one is decoded into a per-thread
+/// scratch buffer instead of a fresh allocation. Without it, every call
+/// copies the vector out, which is the reference the fast path must match.
+#[inline]
+fn distance_to(bytes: &[u8], query: &[f32], metric: DistanceMetric, zero_copy: bool) -> f32 {
+    if !zero_copy {
+        return metric.distance(query, &decode(bytes));
+    }
 
-        if !prefix.is_empty() || !suffix.is_empty() {
-            // Unaligned access fallback (should be rare with mmap)
-            // Or if the file offset isn't aligned (HEADER_SIZE=16 is aligned to 4/8/16)
-            let vector: Vec<f32> = vector_bytes
-                .chunks_exact(4)
-                .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
-                .collect();
-            Some(metric.distance(query, &vector))
-        } else {
-            Some(metric.distance(query, vector_slice))
+    if cfg!(target_endian = "little") {
+        // SAFETY: every bit pattern is a valid f32, and align_to only yields
+        // the middle slice for the part of `bytes` that is suitably aligned
+        let (prefix, vector, suffix) = unsafe { bytes.align_to::<f32>() };
+        if prefix.is_empty() && suffix.is_empty() {
+            return metric.distance(query, vector);
         }
     }
+
+    UNALIGNED_SCRATCH.with(|scratch| {
+        let mut scratch = scratch.borrow_mut();
+        scratch.clear();
+        scratch.extend(
+            bytes
+                .chunks_exact(4)
+                .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]])),
+        );
+        metric.distance(query, &scratch)
+    })
 }
 
 impl Drop for MmapStorage {
@@ -642,4 +636,23 @@ mod tests {
         let expected_size = HEADER_SIZE + 1000 * dims * 4;
         assert!(storage.disk_usage() >= expected_size as u64);
     }
+
+    #[test]
+    fn test_distance_paths_agree_on_unaligned_bytes() {
+        let vector: Vec<f32> = (0..37).map(|i| (i as f32 * 0.37).cos()).collect();
+        let query: Vec<f32> = (0..37).map(|i| (i as f32 * 0.11).sin()).collect();
+
+        // One leading byte knocks the vector off f32 alignment
+        let mut bytes = vec![0u8];
+        bytes.extend(vector.iter().flat_map(|v| v.to_le_bytes()));
+
+        let unaligned = &bytes[1..];
+        assert_ne!(unaligned.as_ptr() as usize % std::mem::align_of::<f32>(), 0);
+
+        for metric in [DistanceMetric::Cosine, DistanceMetric::Euclidean] {
+            let expected = metric.distance(&query, &vector);
+            assert_eq!(distance_to(unaligned, &query, metric, true), expected);
+            assert_eq!(distance_to(unaligned, &query, metric, false), expected);
+        }
+    }
 }
@@ -54,14 +54,7 @@ impl MultiVectorStorage {
             for q in query {
                 let mut max_sim = f32::NEG_INFINITY;
                 for d in &doc_vectors {
-                    let dist = metric.distance(q, d);
-                    // Convert distance to similarity (1 - dist for cosine)
-                    // Assuming cosine distance [0, 2] -> similarity [1, -1]
-                    // Or usually we want high score = match.
-                    // Let's use distance for now and minimize it?
-                    // MaxSim usually maximizes dot product.
-                    // If metric is Cosine Distance, sim = 1 - dist.
-                    let sim = 1.0 - dist;
+                    let sim = metric.to_similarity(metric.distance(q, d));
                     if sim > max_sim {
                         max_sim = sim;
                     }
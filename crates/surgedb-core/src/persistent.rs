@@ -4,18 +4,34 @@
 
 use crate::distance::DistanceMetric;
 use crate::error::{Error, Result};
-use crate::hnsw::{HnswConfig, HnswIndex};
-use crate::snapshot::{Snapshot, SnapshotManager};
+use crate::export::{self, ExportReader};
+use crate::facet::Facets;
+use crate::hnsw::{
+    DegreeReport, HnswConfig, HnswIndex, HnswNode, HnswState, SearchStats, SelfRecall,
+};
+use crate::metadata::MetadataFormat;
+use crate::projection::{ProjectionConfig, RandomProjection};
+use crate::score::ScoreTransform;
+use crate::snapshot::{GraphDelta, Snapshot, SnapshotDelta, SnapshotManager, StoredVector};
 use crate::storage::{VectorStorage, VectorStorageTrait};
-use crate::types::VectorId;
+use crate::transaction::{self, Op, Writer};
+use crate::types::{
+    Aliases, BatchReport, DimensionPolicy, DuplicatePolicy, InsertMode, InternalId, ListPage,
+    MetadataUpdate, SearchHit, VectorId,
+};
 use crate::wal::{Wal, WalEntry};
 use serde_json::Value;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
+
+/// File in the data directory locked by the writable primary
+const LOCK_FILE: &str = "LOCK";
 
 /// Configuration for persistent database
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize)]
 pub struct PersistentConfig {
     /// Dimensionality of vectors
     pub dimensions: usize,
@@ -27,8 +43,51 @@ pub struct PersistentConfig {
     pub sync_writes: bool,
     /// Auto-checkpoint when WAL exceeds this size (bytes)
     pub checkpoint_threshold: u64,
+    /// Start a new WAL segment file once the current one would exceed this
+    /// size in bytes (0 = a single file). Segments are deleted once a
+    /// checkpoint covers them.
+    pub wal_segment_size: u64,
     /// Number of snapshots to retain
     pub snapshot_retain_count: usize,
+    /// Write checkpoints as deltas holding only the slots and graph nodes
+    /// changed since the previous checkpoint, chained onto a full snapshot.
+    /// Full snapshots then keep deleted slots too, so graph node IDs stay
+    /// valid across the chain.
+    pub incremental_snapshots: bool,
+    /// Deltas chained onto one snapshot before the next checkpoint collapses
+    /// the chain by writing a full snapshot
+    pub max_snapshot_deltas: usize,
+    /// Collection-level metadata merged into every returned vector's metadata
+    pub default_metadata: Option<Value>,
+    /// Maximum serialized size of a vector's metadata in bytes (0 = unlimited)
+    pub max_metadata_bytes: usize,
+    /// Reduce inputs to a lower dimensionality with a seeded random projection.
+    /// The WAL and snapshots hold projected vectors, so the seed must not change.
+    pub projection: Option<ProjectionConfig>,
+    /// L2-normalize vectors on insert and query (see `Config::normalize`).
+    /// The WAL and snapshots hold normalized vectors.
+    pub normalize: bool,
+    /// Reject inserted vectors whose L2 norm is not 1 (see `Config::require_unit_norm`)
+    pub require_unit_norm: bool,
+    /// Reject vectors with NaN or infinite components (see `Config::validate_finite`)
+    pub validate_finite: bool,
+    /// Reject out-of-distribution queries (see `Config::validate_query`)
+    pub validate_query: bool,
+    /// How vectors of the wrong length are handled (see `Config::dimension_mismatch`).
+    /// Fitted vectors are what the WAL and snapshots hold.
+    pub dimension_mismatch: DimensionPolicy,
+    /// How metadata is held in memory (see `Config::metadata_format`).
+    /// The WAL and snapshots store JSON in either format.
+    pub metadata_format: MetadataFormat,
+    /// Default presentation of search distances (see `Config::score_transform`)
+    pub score_transform: ScoreTransform,
+    /// Whether inserts skip vectors whose content is already stored (see
+    /// `Config::insert_mode`). Skipped inserts are not logged.
+    pub insert_mode: InsertMode,
+    /// Tombstone fraction past which `needs_compaction` reports true and a
+    /// `Database` collection compacts in the background (see
+    /// `Config::auto_compact_fraction`)
+    pub auto_compact_fraction: f32,
 }
 
 impl Default for PersistentConfig {
@@ -39,7 +98,22 @@ impl Default for PersistentConfig {
             hnsw: HnswConfig::default(),
             sync_writes: false,
             checkpoint_threshold: 64 * 1024 * 1024, // 64MB
+            wal_segment_size: 0,
             snapshot_retain_count: 3,
+            incremental_snapshots: false,
+            max_snapshot_deltas: 8,
+            default_metadata: None,
+            max_metadata_bytes: 0,
+            projection: None,
+            normalize: false,
+            require_unit_norm: false,
+            validate_finite: true,
+            validate_query: false,
+            dimension_mismatch: DimensionPolicy::Strict,
+            metadata_format: MetadataFormat::Json,
+            score_transform: ScoreTransform::Distance,
+            insert_mode: InsertMode::Always,
+            auto_compact_fraction: 0.0,
         }
     }
 }
@@ -52,33 +126,121 @@ pub struct PersistentVectorDb {
     wal: Wal,
     snapshot_manager: SnapshotManager,
     data_dir: PathBuf,
+    projection: Option<RandomProjection>,
+    aliases: Aliases,
+    query_norms: crate::types::QueryNorms,
+    /// Content lookup for `InsertMode::DedupByContent`
+    content_hashes: crate::types::ContentHashes,
+    /// Held while this instance is the writable primary; `None` on a follower
+    lock: Option<File>,
+    /// Sequence number of the last WAL record applied
+    applied_seq: u64,
+    /// What the snapshot chain on disk holds, when the next checkpoint can
+    /// be a delta on top of it
+    snapshot_mark: Option<SnapshotMark>,
+    compactor: crate::compaction::Compactor,
+}
+
+/// Fingerprints of the state the latest snapshot or delta holds, to find
+/// what an incremental checkpoint has to write
+struct SnapshotMark {
+    /// ID of the latest snapshot or delta
+    id: u64,
+    /// Deltas chained onto the full snapshot so far
+    deltas: usize,
+    /// Per slot, a hash of its deletion and metadata
+    slots: Vec<u64>,
+    /// Per graph node, a hash of its neighbor lists
+    nodes: Vec<u64>,
+}
+
+impl SnapshotMark {
+    fn slot(deleted: bool, metadata: Option<&Value>) -> u64 {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        deleted.hash(&mut hasher);
+        metadata.map(Value::to_string).hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn node(node: &HnswNode) -> u64 {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        node.neighbors.hash(&mut hasher);
+        hasher.finish()
+    }
 }
 
 impl PersistentVectorDb {
     /// Open or create a persistent database at the given path
+    ///
+    /// The data directory is locked for writing until the database is
+    /// dropped, so a second writer fails with `Error::LockFailed`.
     pub fn open(path: impl AsRef<Path>, config: PersistentConfig) -> Result<Self> {
         let data_dir = path.as_ref().to_path_buf();
         std::fs::create_dir_all(&data_dir)?;
+        let lock = lock_data_dir(&data_dir)?;
 
-        let wal_dir = data_dir.join("wal");
-        let snapshot_dir = data_dir.join("snapshots");
-
-        let mut wal = Wal::open(&wal_dir)?;
+        let mut wal = Wal::open(data_dir.join("wal"))?;
         wal.set_max_size(config.checkpoint_threshold);
+        wal.set_segment_size(config.wal_segment_size);
+
+        Self::with_wal(data_dir, config, wal, Some(lock))
+    }
+
+    /// Open a read-only follower of the primary writing to `path`
+    ///
+    /// The follower loads the primary's snapshot and WAL like a recovery,
+    /// then keeps a warm index by applying new WAL records on each
+    /// [`catch_up`](Self::catch_up). Writes are rejected until it is
+    /// [`promote`](Self::promote)d.
+    pub fn open_follower(path: impl AsRef<Path>, config: PersistentConfig) -> Result<Self> {
+        let data_dir = path.as_ref().to_path_buf();
+        let wal = Wal::open_read_only(data_dir.join("wal"))?;
+        Self::with_wal(data_dir, config, wal, None)
+    }
+
+    fn with_wal(
+        data_dir: PathBuf,
+        config: PersistentConfig,
+        wal: Wal,
+        lock: Option<File>,
+    ) -> Result<Self> {
+        config.hnsw.validate()?;
+        config.metadata_format.validate()?;
+        config.distance_metric.check_storage(false)?;
+        config.insert_mode.validate()?;
+        crate::compaction::validate_fraction(config.auto_compact_fraction)?;
+        let snapshot_dir = data_dir.join("snapshots");
 
         let mut snapshot_manager = SnapshotManager::new(&snapshot_dir)?;
         snapshot_manager.set_retain_count(config.snapshot_retain_count);
 
-        let storage = VectorStorage::new(config.dimensions);
-        let index = HnswIndex::new(config.hnsw.clone(), config.distance_metric);
+        let projection = config
+            .projection
+            .map(|p| RandomProjection::new(config.dimensions, p))
+            .transpose()?;
+        let stored_dim = projection
+            .as_ref()
+            .map_or(config.dimensions, RandomProjection::target_dim);
+        let storage = new_storage(&config, stored_dim);
+        let index = HnswIndex::new(config.hnsw.clone(), index_metric(&config));
 
         let mut db = Self {
+            query_norms: crate::types::QueryNorms::new(config.validate_query),
             config,
             storage,
             index,
             wal,
             snapshot_manager,
             data_dir,
+            projection,
+            aliases: Aliases::default(),
+            content_hashes: crate::types::ContentHashes::default(),
+            lock,
+            applied_seq: 0,
+            snapshot_mark: None,
+            compactor: crate::compaction::Compactor::default(),
         };
 
         // Recover from snapshot and WAL
@@ -89,29 +251,47 @@ impl PersistentVectorDb {
 
     /// Recover database state from snapshot and WAL
     fn recover(&mut self) -> Result<()> {
-        let mut last_wal_seq = 0u64;
+        self.applied_seq = 0;
+        self.snapshot_mark = None;
 
-        // 1. Load latest snapshot if available
-        if let Some(snapshot) = self.snapshot_manager.load_latest()? {
-            debug!("Loading snapshot for recovery...");
-            last_wal_seq = snapshot.wal_seq;
+        // 1. Load latest snapshot (and its deltas) if available
+        if let Some((snapshot, deltas)) = self.snapshot_manager.load_chain()? {
+            debug!("Loading snapshot with {} deltas for recovery...", deltas);
+            self.applied_seq = snapshot.wal_seq;
 
-            // Verify dimensions match
-            if snapshot.dimensions != self.config.dimensions {
+            // Verify dimensions match (snapshots hold projected vectors)
+            if snapshot.dimensions != self.storage.dimensions() {
                 return Err(Error::InvalidConfig(format!(
                     "Snapshot dimensions ({}) don't match config ({})",
-                    snapshot.dimensions, self.config.dimensions
+                    snapshot.dimensions,
+                    self.storage.dimensions()
                 )));
             }
 
-            // Restore vectors from snapshot
+            // Restore vectors from snapshot. Positional snapshots repeat the
+            // IDs of superseded slots, which the upsert of the live slot marks
+            // deleted; explicitly deleted slots are marked afterwards.
             for stored in snapshot.vectors {
                 self.storage
-                    .insert(stored.id, &stored.vector, stored.metadata)?;
+                    .upsert(stored.id, &stored.vector, stored.metadata)?;
+            }
+            for slot in snapshot.deleted {
+                if let Some(id) = self.storage.get_external_id(slot) {
+                    if self.storage.get_internal_id(&id) == Some(slot) {
+                        self.storage.delete(&id)?;
+                    }
+                }
+            }
+
+            for (alias, primary) in snapshot.aliases {
+                self.aliases.restore(alias, primary);
             }
 
             // Restore HNSW state if available
             if let Some(state) = snapshot.hnsw_state {
+                if self.config.incremental_snapshots {
+                    self.snapshot_mark = Some(self.mark(snapshot.id, deltas, &state));
+                }
                 self.index.load_state(state);
             } else {
                 // Fallback: rebuild index if state is missing
@@ -124,41 +304,182 @@ impl PersistentVectorDb {
         }
 
         // 2. Replay WAL entries after snapshot
-        let entries = self.wal.read_after(last_wal_seq)?;
-        let total = entries.len();
+        let records = self.wal.read_records_after(self.applied_seq)?;
+        let total = records.len();
         if total > 0 {
             info!("Replaying {} WAL entries...", total);
         }
 
-        for (i, entry) in entries.into_iter().enumerate() {
+        for (i, (seq, entry)) in records.into_iter().enumerate() {
             if i > 0 && i % 5000 == 0 {
                 info!("Progress: {}/{} entries replayed...", i, total);
             }
-            match entry {
-                WalEntry::Insert {
-                    id,
-                    vector,
-                    metadata,
-                } => {
-                    // Skip if already in storage (duplicate)
-                    if self.storage.get_internal_id(&id).is_none() {
-                        let internal_id = self.storage.insert(id, &vector, metadata)?;
-                        self.index.insert(internal_id, &vector, &self.storage)?;
+            self.replay(entry)?;
+            self.applied_seq = seq;
+        }
+
+        // Stored vectors only share the scale of raw queries when they are
+        // neither projected nor normalized
+        if self.projection.is_none() && !self.config.normalize {
+            for internal_id in self.storage.all_internal_ids() {
+                if let Some(vector) = self.storage.get(internal_id) {
+                    self.query_norms.observe(&vector);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Apply one WAL entry to the in-memory state
+    fn replay(&mut self, entry: WalEntry) -> Result<()> {
+        match entry {
+            WalEntry::Insert {
+                id,
+                vector,
+                metadata,
+            } => {
+                // Skip if already in storage (duplicate)
+                if self.storage.get_internal_id(&id).is_none() {
+                    let internal_id = self.storage.insert(id, &vector, metadata)?;
+                    self.index.insert(internal_id, &vector, &self.storage)?;
+                }
+            }
+            WalEntry::Delete { id } => {
+                let previous = self.storage.get_internal_id(&id);
+                if self.storage.delete(&id).unwrap_or(false) {
+                    self.aliases.remove_target(&id);
+                }
+                if self.config.hnsw.eager_edge_cleanup {
+                    if let Some(old) = previous {
+                        self.index.remove(old, &self.storage);
                     }
                 }
-                WalEntry::Delete { id } => {
-                    let _ = self.storage.delete(&id);
+            }
+            WalEntry::Alias { alias, id } => {
+                let storage = &self.storage;
+                let _ = self
+                    .aliases
+                    .add(alias, &id, |id| storage.get_internal_id(id).is_some());
+            }
+            entry @ (WalEntry::Upsert { .. } | WalEntry::SetMetadata { .. }) => {
+                let ops: Vec<Op> = entry.into_op().into_iter().collect();
+                self.writer().apply_all(&ops)?;
+            }
+            WalEntry::Transaction { entries } => {
+                let ops: Vec<Op> = entries.into_iter().filter_map(WalEntry::into_op).collect();
+                // Rolled back when it was first applied, so it is again
+                if let Err(e) = self.writer().apply_all(&ops) {
+                    warn!("Skipping WAL transaction that failed to apply: {}", e);
                 }
-                WalEntry::Checkpoint { .. } => {}
             }
+            WalEntry::Clear => self.reset(),
+            WalEntry::Checkpoint { .. } => {}
+        }
+        Ok(())
+    }
+
+    /// Empty storage, the index and aliases, keeping the configuration
+    fn reset(&mut self) {
+        self.snapshot_mark = None;
+        self.storage = new_storage(&self.config, self.storage.dimensions());
+        self.index = HnswIndex::new(self.config.hnsw.clone(), index_metric(&self.config));
+        self.aliases = Aliases::default();
+        self.content_hashes = crate::types::ContentHashes::default();
+        self.query_norms = crate::types::QueryNorms::new(self.config.validate_query);
+        self.compactor.invalidate();
+    }
+
+    fn writer(&mut self) -> Writer<'_> {
+        Writer {
+            storage: &self.storage,
+            indexes: vec![&self.index],
+            aliases: &mut self.aliases,
+            eager_edge_cleanup: self.config.hnsw.eager_edge_cleanup,
+        }
+    }
+
+    /// Whether this instance is a read-only follower
+    pub fn is_follower(&self) -> bool {
+        self.lock.is_none()
+    }
+
+    /// Apply the WAL records the primary wrote since the last catch-up,
+    /// returning how many were applied
+    ///
+    /// If the primary checkpointed and cleared records this follower had not
+    /// seen yet, the state is reloaded from the new snapshot instead. Does
+    /// nothing on a primary.
+    pub fn catch_up(&mut self) -> Result<usize> {
+        if !self.is_follower() {
+            return Ok(0);
+        }
+
+        let records = self.wal.read_records_after(self.applied_seq)?;
+        if records
+            .first()
+            .is_some_and(|(seq, _)| *seq > self.applied_seq + 1)
+        {
+            debug!(
+                "Primary checkpointed past seq {}, reloading",
+                self.applied_seq
+            );
+            let before = self.applied_seq;
+            self.reset();
+            self.recover()?;
+            return Ok((self.applied_seq - before) as usize);
+        }
+
+        let applied = records.len();
+        for (seq, entry) in records {
+            self.replay(entry)?;
+            self.applied_seq = seq;
+        }
+        Ok(applied)
+    }
+
+    /// Turn a follower into the writable primary
+    ///
+    /// Takes the data directory lock, which fails with `Error::LockFailed`
+    /// while the old primary still holds it, applies whatever the primary
+    /// wrote last, and opens the WAL for appending. Stream consumption stops:
+    /// later `catch_up` calls do nothing. Promoting a primary is a no-op.
+    pub fn promote(&mut self) -> Result<()> {
+        if !self.is_follower() {
+            return Ok(());
         }
 
+        let lock = lock_data_dir(&self.data_dir)?;
+        self.catch_up()?;
+
+        let mut wal = Wal::open(self.data_dir.join("wal"))?;
+        wal.set_max_size(self.config.checkpoint_threshold);
+        wal.set_segment_size(self.config.wal_segment_size);
+        self.wal = wal;
+        self.lock = Some(lock);
+        // The old primary may have extended the chain since it was loaded
+        self.snapshot_mark = None;
+        info!(
+            "Promoted follower at {} to primary",
+            self.data_dir.display()
+        );
+        Ok(())
+    }
+
+    /// Reject writes on a follower
+    fn check_writable(&self) -> Result<()> {
+        if self.is_follower() {
+            return Err(Error::InvalidConfig(
+                "Database is a read-only follower; promote() it to accept writes".to_string(),
+            ));
+        }
         Ok(())
     }
 
-    /// Delete a vector by ID
+    /// Delete a vector by its primary ID or an alias, dropping its aliases
     pub fn delete(&mut self, id: impl Into<VectorId>) -> Result<bool> {
-        let id = id.into();
+        self.check_writable()?;
+        let id = self.resolve(&id.into());
 
         // Write to WAL
         self.wal.append(WalEntry::Delete { id: id.clone() })?;
@@ -168,7 +489,17 @@ impl PersistentVectorDb {
         }
 
         // Apply to storage
+        let previous = self.storage.get_internal_id(&id);
         let deleted = self.storage.delete(&id)?;
+        if deleted {
+            self.aliases.remove_target(&id);
+        }
+
+        if self.config.hnsw.eager_edge_cleanup {
+            if let Some(old) = previous {
+                self.index.remove(old, &self.storage);
+            }
+        }
 
         // Checkpoint if needed
         if self.wal.needs_checkpoint() {
@@ -178,6 +509,50 @@ impl PersistentVectorDb {
         Ok(deleted)
     }
 
+    /// Drop every vector and alias (see `VectorDb::clear`)
+    ///
+    /// Logged to the WAL, so recovery replays the truncation.
+    pub fn clear(&mut self) -> Result<()> {
+        self.check_writable()?;
+        self.wal.append(WalEntry::Clear)?;
+
+        if self.config.sync_writes {
+            self.wal.sync()?;
+        }
+
+        self.reset();
+
+        if self.wal.needs_checkpoint() {
+            self.checkpoint()?;
+        }
+
+        Ok(())
+    }
+
+    /// Make `alias` a secondary key for the vector `id` (see `VectorDb::add_alias`)
+    ///
+    /// Aliases are logged to the WAL and stored in snapshots.
+    pub fn add_alias(&mut self, alias: impl Into<VectorId>, id: &str) -> Result<()> {
+        self.check_writable()?;
+        let storage = &self.storage;
+        let alias = alias.into();
+        let primary = self.aliases.add(alias.clone(), &VectorId::from(id), |id| {
+            storage.get_internal_id(id).is_some()
+        })?;
+
+        self.wal.append(WalEntry::Alias { alias, id: primary })?;
+        if self.config.sync_writes {
+            self.wal.sync()?;
+        }
+        Ok(())
+    }
+
+    /// Resolve a primary ID or alias to the primary ID
+    fn resolve(&self, id: &VectorId) -> VectorId {
+        self.aliases
+            .resolve(id, |id| self.storage.get_internal_id(id).is_some())
+    }
+
     /// Insert a vector with the given ID and optional metadata
     pub fn insert(
         &mut self,
@@ -185,13 +560,11 @@ impl PersistentVectorDb {
         vector: &[f32],
         metadata: Option<Value>,
     ) -> Result<()> {
+        self.check_writable()?;
         let id = id.into();
-
-        if vector.len() != self.config.dimensions {
-            return Err(Error::DimensionMismatch {
-                expected: self.config.dimensions,
-                got: vector.len(),
-            });
+        let vector = &*self.prepare(vector, metadata.as_ref())?;
+        if self.find_duplicate(vector)?.is_some() {
+            return Ok(());
         }
 
         // Write to WAL first (durability)
@@ -217,6 +590,229 @@ impl PersistentVectorDb {
         Ok(())
     }
 
+    /// Apply several writes atomically (see `VectorDb::transaction`)
+    ///
+    /// The batch is logged as one WAL record, so recovery replays all of it or
+    /// none of it.
+    pub fn transaction(&mut self, ops: Vec<Op>) -> Result<()> {
+        self.check_writable()?;
+        let ops = transaction::plan(
+            ops,
+            self.config.max_metadata_bytes,
+            |id| self.storage.get_internal_id(id).is_some(),
+            |id| self.resolve(id),
+            |vector, metadata| Ok(self.prepare(vector, metadata)?.into_owned()),
+        )?;
+        let ops = transaction::dedup(
+            ops,
+            self.config.insert_mode,
+            |id| self.storage.get_internal_id(id).is_some(),
+            |vector| self.find_duplicate(vector),
+        )?;
+        if ops.is_empty() {
+            return Ok(());
+        }
+
+        self.wal.append(WalEntry::Transaction {
+            entries: ops.iter().cloned().map(WalEntry::from).collect(),
+        })?;
+        if self.config.sync_writes {
+            self.wal.sync()?;
+        }
+
+        self.writer().apply_all(&ops)?;
+
+        if self.wal.needs_checkpoint() {
+            self.checkpoint()?;
+        }
+        Ok(())
+    }
+
+    /// Merge a JSON patch into every vector matching `filter` (see
+    /// `VectorDb::update_metadata_by_filter`)
+    ///
+    /// The updates are logged as one WAL transaction.
+    pub fn update_metadata_by_filter(
+        &mut self,
+        filter: &crate::filter::Filter,
+        patch: &Value,
+    ) -> Result<usize> {
+        let ops = transaction::patch_metadata(&self.storage, filter, patch);
+        let updated = ops.len();
+        self.transaction(ops)?;
+        Ok(updated)
+    }
+
+    /// Replace or merge the metadata of the vector `id` (see
+    /// `VectorDb::set_metadata`), logged to the WAL as a metadata-only write
+    pub fn set_metadata(&mut self, id: &str, metadata: Value, mode: MetadataUpdate) -> Result<()> {
+        let id = self.resolve(&VectorId::from(id));
+        let internal_id = self
+            .storage
+            .get_internal_id(&id)
+            .ok_or_else(|| Error::VectorNotFound(id.to_string()))?;
+        let metadata = mode.apply(self.storage.get_metadata(internal_id), metadata);
+        self.transaction(vec![Op::SetMetadata {
+            id,
+            metadata: (!metadata.is_null()).then_some(metadata),
+        }])
+    }
+
+    /// Load many vectors at once in import mode, resolving IDs already
+    /// present or repeated within the batch under `policy`
+    ///
+    /// Items are applied without WAL records, syncs or intermediate
+    /// checkpoints; a single checkpoint at the end makes the whole batch
+    /// durable, after which normal per-write logging resumes. Duplicates are
+    /// resolved before anything is written (so `DuplicatePolicy::Error`
+    /// loads nothing), and validated vectors are written to storage as one
+    /// batch. Vectors skipped by `InsertMode::DedupByContent` count as
+    /// skipped in the report.
+    ///
+    /// A crash before that checkpoint completes loses every vector from the
+    /// batch (the previous state recovers intact), so re-run the import from
+    /// the start. If an item fails validation, the vectors before it are
+    /// still checkpointed and the error is returned.
+    pub fn bulk_load<K: Into<VectorId>>(
+        &mut self,
+        items: impl IntoIterator<Item = (K, Vec<f32>, Option<Value>)>,
+        policy: DuplicatePolicy,
+    ) -> Result<BatchReport> {
+        self.check_writable()?;
+        let (items, report) = crate::types::resolve_duplicates(
+            items
+                .into_iter()
+                .map(|(id, vector, metadata)| (id.into(), (vector, metadata))),
+            policy,
+            |id| self.storage.get_internal_id(id).is_some(),
+        )?;
+
+        let mut records = Vec::with_capacity(items.len());
+        let mut outcome = Ok(());
+        for (id, (vector, metadata)) in items {
+            match self.prepare(&vector, metadata.as_ref()) {
+                Ok(vector) => records.push((id, vector.into_owned(), metadata)),
+                Err(e) => {
+                    outcome = Err(e);
+                    break;
+                }
+            }
+        }
+        let batch_ids: std::collections::HashSet<VectorId> =
+            records.iter().map(|(id, _, _)| id.clone()).collect();
+        let (records, deduplicated) = crate::types::dedup_batch(
+            self.config.insert_mode,
+            records,
+            |id| self.storage.get_internal_id(id).is_none(),
+            |vector| {
+                Ok(self
+                    .find_duplicate(vector)?
+                    .filter(|stored| !batch_ids.contains(stored)))
+            },
+        )?;
+
+        if !records.is_empty() {
+            let previous: Vec<InternalId> = if self.config.hnsw.eager_edge_cleanup {
+                records
+                    .iter()
+                    .filter_map(|(id, _, _)| self.storage.get_internal_id(id))
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            // One storage write for the whole batch, then index it
+            let internal_ids = match policy {
+                DuplicatePolicy::Overwrite => self.storage.upsert_batch(&records)?,
+                DuplicatePolicy::Skip | DuplicatePolicy::Error => self.storage.insert_batch(
+                    records
+                        .iter()
+                        .map(|(id, vector, metadata)| {
+                            (id.clone(), vector.as_slice(), metadata.clone())
+                        })
+                        .collect(),
+                )?,
+            };
+            let hnsw_items: Vec<(InternalId, &[f32])> = internal_ids
+                .into_iter()
+                .zip(records.iter().map(|(_, vector, _)| vector.as_slice()))
+                .collect();
+            self.index.insert_batch(&hnsw_items, &self.storage)?;
+            for &old in &previous {
+                self.index.remove(old, &self.storage);
+            }
+            self.checkpoint()?;
+        }
+        outcome.map(|()| report.deduplicated(deduplicated))
+    }
+
+    /// The primary ID of a stored vector matching the prepared `vector` under
+    /// `InsertMode::DedupByContent`
+    fn find_duplicate(&self, vector: &[f32]) -> Result<Option<VectorId>> {
+        self.content_hashes.find_duplicate(
+            self.config.insert_mode,
+            vector,
+            &self.storage,
+            &self.index,
+        )
+    }
+
+    /// Validate an incoming vector and its metadata, returning the vector as stored
+    fn prepare<'a>(
+        &self,
+        vector: &'a [f32],
+        metadata: Option<&Value>,
+    ) -> Result<std::borrow::Cow<'a, [f32]>> {
+        crate::types::check_finite(vector, self.config.validate_finite)?;
+        crate::types::check_unit_norm(vector, self.config.require_unit_norm)?;
+        self.query_norms.observe(vector);
+        let vector = crate::projection::prepare_normalized(
+            self.projection.as_ref(),
+            self.config.dimensions,
+            self.config.dimension_mismatch,
+            self.config.normalize,
+            vector,
+        )?;
+        crate::types::check_metadata_size(metadata, self.config.max_metadata_bytes)?;
+        Ok(vector)
+    }
+
+    /// Get a vector's HNSW neighbors per layer (layer 0 first), resolved to external IDs
+    ///
+    /// Returns the vector's internal ID alongside the layers; the max layer is
+    /// `layers.len() - 1`. Neighbors that are deleted or superseded by an update
+    /// are omitted.
+    pub fn neighborhood(&self, id: &str) -> Option<(InternalId, Vec<Vec<VectorId>>)> {
+        let internal_id = self.storage.get_internal_id(&VectorId::from(id))?;
+        let layers = self
+            .index
+            .neighbors(internal_id)
+            .into_iter()
+            .map(|layer| {
+                layer
+                    .into_iter()
+                    .filter_map(|neighbor| {
+                        let ext_id = self.storage.get_external_id(neighbor)?;
+                        (self.storage.get_internal_id(&ext_id)? == neighbor).then_some(ext_id)
+                    })
+                    .collect()
+            })
+            .collect();
+        Some((internal_id, layers))
+    }
+
+    /// Edges of HNSW layer `layer` as `(from, to)` external IDs (see
+    /// `VectorDb::graph_edges`)
+    pub fn graph_edges(&self, layer: usize) -> impl Iterator<Item = (VectorId, VectorId)> + '_ {
+        let live = |internal_id| {
+            let ext_id = self.storage.get_external_id(internal_id)?;
+            (self.storage.get_internal_id(&ext_id)? == internal_id).then_some(ext_id)
+        };
+        self.index
+            .export_edges()
+            .filter(move |&(_, _, edge_layer)| edge_layer == layer)
+            .filter_map(move |(from, to, _)| Some((live(from)?, live(to)?)))
+    }
+
     /// Search for the k nearest neighbors
     pub fn search(
         &self,
@@ -224,26 +820,112 @@ impl PersistentVectorDb {
         k: usize,
         filter: Option<&crate::filter::Filter>,
     ) -> Result<Vec<(VectorId, f32, Option<Value>)>> {
-        if query.len() != self.config.dimensions {
-            return Err(Error::DimensionMismatch {
-                expected: self.config.dimensions,
-                got: query.len(),
-            });
+        self.search_with_stats(query, k, filter)
+            .map(|(results, _)| results)
+    }
+
+    /// Like `search`, also reporting filter rejections and whether the graph
+    /// was fully explored (see `VectorDb::search_with_stats`)
+    pub fn search_with_stats(
+        &self,
+        query: &[f32],
+        k: usize,
+        filter: Option<&crate::filter::Filter>,
+    ) -> Result<(Vec<SearchHit>, SearchStats)> {
+        self.search_inner(query, k, None, filter)
+    }
+
+    /// Like `search`, with a candidate list of `ef` (see `VectorDb::search_with_ef`)
+    pub fn search_with_ef(
+        &self,
+        query: &[f32],
+        k: usize,
+        ef: usize,
+        filter: Option<&crate::filter::Filter>,
+    ) -> Result<Vec<SearchHit>> {
+        self.search_inner(query, k, Some(ef), filter)
+            .map(|(results, _)| results)
+    }
+
+    /// `search_with_stats` with an optional candidate list of `ef` (see
+    /// `VectorDb::search_with_ef`)
+    pub fn search_with_ef_stats(
+        &self,
+        query: &[f32],
+        k: usize,
+        ef: Option<usize>,
+        filter: Option<&crate::filter::Filter>,
+    ) -> Result<(Vec<SearchHit>, SearchStats)> {
+        self.search_inner(query, k, ef, filter)
+    }
+
+    /// Find the k nearest neighbors of a stored vector, given its primary ID
+    /// or an alias (see `VectorDb::search_by_id`)
+    pub fn search_by_id(
+        &self,
+        id: &str,
+        k: usize,
+        filter: Option<&crate::filter::Filter>,
+        exclude_self: bool,
+    ) -> Result<Vec<SearchHit>> {
+        let primary = self.resolve(&VectorId::from(id));
+        // Stored vectors are already projected and normalized
+        let query = self
+            .storage
+            .get_internal_id(&primary)
+            .and_then(|internal_id| self.storage.get(internal_id))
+            .ok_or_else(|| Error::VectorNotFound(id.to_string()))?;
+        let (mut results, _) =
+            self.search_prepared(&query, k + usize::from(exclude_self), None, filter)?;
+        if exclude_self {
+            results.retain(|(hit, _, _)| *hit != primary);
         }
+        results.truncate(k);
+        Ok(results)
+    }
+
+    fn search_inner(
+        &self,
+        query: &[f32],
+        k: usize,
+        ef: Option<usize>,
+        filter: Option<&crate::filter::Filter>,
+    ) -> Result<(Vec<SearchHit>, SearchStats)> {
+        self.query_norms.check(query, self.config.distance_metric)?;
+        let query = &*crate::projection::prepare_normalized(
+            self.projection.as_ref(),
+            self.config.dimensions,
+            self.config.dimension_mismatch,
+            self.config.normalize,
+            query,
+        )?;
+        self.search_prepared(query, k, ef, filter)
+    }
 
-        let results = self.index.search(query, k, &self.storage, filter)?;
+    /// `search_inner` for a query already projected and normalized like the
+    /// stored vectors
+    fn search_prepared(
+        &self,
+        query: &[f32],
+        k: usize,
+        ef: Option<usize>,
+        filter: Option<&crate::filter::Filter>,
+    ) -> Result<(Vec<SearchHit>, SearchStats)> {
+        let (results, stats) = self
+            .index
+            .search_with_ef(query, k, ef, &self.storage, filter)?;
 
         let mapped: Vec<(VectorId, f32, Option<Value>)> = results
             .into_iter()
             .filter_map(|(internal_id, distance)| {
                 self.storage.get_external_id(internal_id).map(|ext_id| {
-                    let metadata = self.storage.get_metadata(internal_id);
+                    let metadata = self.with_defaults(self.storage.get_metadata(internal_id));
                     (ext_id, distance, metadata)
                 })
             })
             .collect();
 
-        Ok(mapped)
+        Ok((mapped, stats))
     }
 
     /// Search for the k nearest neighbors (without metadata)
@@ -253,12 +935,14 @@ impl PersistentVectorDb {
         k: usize,
         filter: Option<&crate::filter::Filter>,
     ) -> Result<Vec<(VectorId, f32)>> {
-        if query.len() != self.config.dimensions {
-            return Err(Error::DimensionMismatch {
-                expected: self.config.dimensions,
-                got: query.len(),
-            });
-        }
+        self.query_norms.check(query, self.config.distance_metric)?;
+        let query = &*crate::projection::prepare_normalized(
+            self.projection.as_ref(),
+            self.config.dimensions,
+            self.config.dimension_mismatch,
+            self.config.normalize,
+            query,
+        )?;
 
         let search_k = k * 2;
         let results = self
@@ -282,20 +966,67 @@ impl PersistentVectorDb {
     }
 
     /// Create a checkpoint (snapshot + clear WAL)
+    ///
+    /// With `incremental_snapshots`, this writes a delta holding only what
+    /// changed since the previous checkpoint, unless there is no chain to
+    /// extend yet or it has reached `max_snapshot_deltas`; a full snapshot
+    /// then starts a new chain.
     pub fn checkpoint(&mut self) -> Result<()> {
-        let snapshot_id = SystemTime::now()
+        self.check_writable()?;
+        let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .map(|d| d.as_millis() as u64)
             .unwrap_or(0);
+        // Deltas must sort after the snapshot they follow
+        let snapshot_id = self
+            .snapshot_mark
+            .as_ref()
+            .map_or(now, |mark| now.max(mark.id + 1));
 
         let wal_seq = self.wal.seq();
+        let state = self.index.get_state();
+
+        let deltas = match self.snapshot_mark.take() {
+            Some(mark)
+                if self.config.incremental_snapshots
+                    && mark.deltas < self.config.max_snapshot_deltas =>
+            {
+                let delta = self.delta_since(&mark, snapshot_id, wal_seq, &state);
+                self.snapshot_manager.save_delta(&delta)?;
+                mark.deltas + 1
+            }
+            _ => {
+                let snapshot = self.full_snapshot(snapshot_id, wal_seq, state.clone());
+                self.snapshot_manager.save(&snapshot)?;
+                0
+            }
+        };
+        if self.config.incremental_snapshots {
+            self.snapshot_mark = Some(self.mark(snapshot_id, deltas, &state));
+        }
 
-        let mut snapshot = Snapshot::new(snapshot_id, wal_seq, self.config.dimensions);
+        // Clear WAL
+        self.wal.clear()?;
+
+        // Log checkpoint in new WAL
+        self.wal.append(WalEntry::Checkpoint { snapshot_id })?;
+
+        Ok(())
+    }
+
+    /// The whole database as a snapshot
+    ///
+    /// With `incremental_snapshots` every slot is kept, deleted ones
+    /// included, so deltas can address slots and graph nodes by position.
+    fn full_snapshot(&self, snapshot_id: u64, wal_seq: u64, state: HnswState) -> Snapshot {
+        let mut snapshot = Snapshot::new(snapshot_id, wal_seq, self.storage.dimensions());
+        let positional = self.config.incremental_snapshots;
 
         // Add all vectors to snapshot
         for internal_id in self.storage.all_internal_ids() {
+            let deleted = self.storage.is_deleted(internal_id);
             // Skip deleted vectors
-            if self.storage.is_deleted(internal_id) {
+            if deleted && !positional {
                 continue;
             }
 
@@ -303,23 +1034,204 @@ impl PersistentVectorDb {
                 if let Some(vector) = self.storage.get(internal_id) {
                     let metadata = self.storage.get_metadata(internal_id);
                     snapshot.add_vector(ext_id, vector, metadata);
+                    if deleted {
+                        snapshot.deleted.push(internal_id);
+                    }
                 }
             }
         }
 
+        snapshot.aliases = self.alias_pairs();
+
         // Add index state to snapshot
-        snapshot.set_hnsw_state(self.index.get_state());
+        snapshot.set_hnsw_state(state);
+        snapshot
+    }
 
-        // Save snapshot
-        self.snapshot_manager.save(&snapshot)?;
+    /// The slots and graph nodes that changed since `mark`, as a delta on top of it
+    fn delta_since(
+        &self,
+        mark: &SnapshotMark,
+        snapshot_id: u64,
+        wal_seq: u64,
+        state: &HnswState,
+    ) -> SnapshotDelta {
+        let first_slot = mark.slots.len();
+        let mut delta = SnapshotDelta {
+            id: snapshot_id,
+            parent: mark.id,
+            wal_seq,
+            dimensions: self.storage.dimensions(),
+            first_slot,
+            vectors: Vec::new(),
+            metadata: Vec::new(),
+            deleted: Vec::new(),
+            graph: None,
+            aliases: self.alias_pairs(),
+        };
 
-        // Clear WAL
-        self.wal.clear()?;
+        for internal_id in self.storage.all_internal_ids() {
+            let slot = internal_id.as_usize();
+            let deleted = self.storage.is_deleted(internal_id);
+            let metadata = self.storage.get_metadata(internal_id);
+            if slot >= first_slot {
+                delta.vectors.push(StoredVector {
+                    id: self
+                        .storage
+                        .get_external_id(internal_id)
+                        .unwrap_or_else(|| VectorId::from("")),
+                    vector: self.storage.get(internal_id).unwrap_or_default(),
+                    metadata,
+                });
+                if deleted {
+                    delta.deleted.push(internal_id);
+                }
+            } else if SnapshotMark::slot(deleted, metadata.as_ref()) != mark.slots[slot] {
+                // Slots are never undeleted, so a changed deleted slot is newly deleted
+                if deleted {
+                    delta.deleted.push(internal_id);
+                } else {
+                    delta.metadata.push((internal_id, metadata));
+                }
+            }
+        }
 
-        // Log checkpoint in new WAL
-        self.wal.append(WalEntry::Checkpoint { snapshot_id })?;
+        delta.graph = Some(GraphDelta {
+            nodes: state
+                .nodes
+                .iter()
+                .enumerate()
+                .filter(|(i, node)| mark.nodes.get(*i) != Some(&SnapshotMark::node(node)))
+                .map(|(_, node)| node.clone())
+                .collect(),
+            entry_point: state.entry_point,
+            max_layer: state.max_layer,
+        });
+        delta
+    }
 
-        Ok(())
+    /// Fingerprint the current slots and `state` as held by snapshot `id`
+    fn mark(&self, id: u64, deltas: usize, state: &HnswState) -> SnapshotMark {
+        SnapshotMark {
+            id,
+            deltas,
+            slots: self
+                .storage
+                .all_internal_ids()
+                .into_iter()
+                .map(|internal_id| {
+                    SnapshotMark::slot(
+                        self.storage.is_deleted(internal_id),
+                        self.storage.get_metadata(internal_id).as_ref(),
+                    )
+                })
+                .collect(),
+            nodes: state.nodes.iter().map(SnapshotMark::node).collect(),
+        }
+    }
+
+    fn alias_pairs(&self) -> Vec<(VectorId, VectorId)> {
+        self.aliases
+            .iter()
+            .map(|(alias, primary)| (alias.clone(), primary.clone()))
+            .collect()
+    }
+
+    /// Rebuild storage and the index without deleted or superseded slots,
+    /// returning how many slots were reclaimed
+    ///
+    /// The result is written as a fresh snapshot, the WAL is truncated and
+    /// older snapshots are deleted, since they still hold the removed
+    /// vectors; disk usage drops along with memory. The index is rebuilt by
+    /// re-inserting every live vector (in parallel with
+    /// `HnswConfig::parallel_build`), and internal IDs are renumbered.
+    pub fn compact(&mut self) -> Result<usize> {
+        self.check_writable()?;
+        crate::compaction::compact(self)
+    }
+
+    /// Whether deleted and superseded slots are past
+    /// `PersistentConfig::auto_compact_fraction`; always false on a follower
+    pub fn needs_compaction(&self) -> bool {
+        !self.is_follower()
+            && crate::compaction::due(
+                self.config.auto_compact_fraction,
+                self.deleted_count(),
+                self.physical_len(),
+            )
+    }
+
+    /// Stream every live vector, its metadata, the aliases and the
+    /// configuration into a single portable file
+    ///
+    /// Machine-local settings (WAL, sync and snapshot tuning) and the HNSW
+    /// graph are left out; [`import`](Self::import) rebuilds the graph.
+    pub fn export(&self, writer: impl Write) -> Result<()> {
+        let vectors = self
+            .storage
+            .all_internal_ids()
+            .into_iter()
+            .filter_map(|internal_id| {
+                if self.storage.is_deleted(internal_id) {
+                    return None;
+                }
+                Some(StoredVector {
+                    id: self.storage.get_external_id(internal_id)?,
+                    vector: self.storage.get(internal_id)?,
+                    metadata: self.storage.get_metadata(internal_id),
+                })
+            });
+        let aliases: Vec<_> = self
+            .aliases
+            .iter()
+            .map(|(alias, primary)| (alias.clone(), primary.clone()))
+            .collect();
+        export::write(writer, &self.config, vectors, &aliases)
+    }
+
+    /// Create a database at `path` from an [`export`](Self::export)
+    ///
+    /// `path` must not hold an existing database. The exported configuration
+    /// is used, with defaults for the machine-local settings, and the result
+    /// is checkpointed before it is returned. A file written by an unknown
+    /// format version fails with `Error::UnsupportedVersion`.
+    pub fn import(path: impl AsRef<Path>, reader: impl Read) -> Result<Self> {
+        let path = path.as_ref();
+        if std::fs::read_dir(path).is_ok_and(|mut entries| entries.next().is_some()) {
+            return Err(Error::InvalidConfig(format!(
+                "Cannot import into {}: directory is not empty",
+                path.display()
+            )));
+        }
+
+        let (mut reader, config) = ExportReader::open(reader)?;
+        let mut db = Self::open(path, config)?;
+        while let Some(batch) = reader.next_batch()? {
+            let internal_ids = db.storage.insert_batch(
+                batch
+                    .iter()
+                    .map(|stored| {
+                        (
+                            stored.id.clone(),
+                            stored.vector.as_slice(),
+                            stored.metadata.clone(),
+                        )
+                    })
+                    .collect(),
+            )?;
+            let items: Vec<(InternalId, &[f32])> = internal_ids
+                .into_iter()
+                .zip(batch.iter().map(|stored| stored.vector.as_slice()))
+                .collect();
+            db.index.insert_batch(&items, &db.storage)?;
+        }
+        for (alias, primary) in reader.aliases()? {
+            db.aliases.restore(alias, primary);
+        }
+
+        db.checkpoint()?;
+        info!("Imported {} vectors into {}", db.len(), path.display());
+        Ok(db)
     }
 
     /// Force sync WAL to disk
@@ -327,20 +1239,32 @@ impl PersistentVectorDb {
         self.wal.sync()
     }
 
-    /// Get the number of vectors in the database
+    /// Get the number of live vectors in the database (excluding deleted ones)
     pub fn len(&self) -> usize {
         self.storage.len()
     }
 
-    /// Retrieve a vector by its external ID
+    /// Get the number of physical slots, including deleted and superseded vectors
+    /// that still occupy storage until the next snapshot is loaded
+    pub fn physical_len(&self) -> usize {
+        self.storage.total_slots()
+    }
+
+    /// Check whether a primary ID or alias is stored
+    pub fn contains(&self, id: &str) -> bool {
+        let id = self.resolve(&VectorId::from(id));
+        self.storage.get_internal_id(&id).is_some()
+    }
+
+    /// Retrieve a vector by its primary ID or an alias
     pub fn get(&self, id: &str) -> Result<Option<(Vec<f32>, Option<Value>)>> {
-        let id = VectorId::from(id);
+        let id = self.resolve(&VectorId::from(id));
         if let Some(internal_id) = self.storage.get_internal_id(&id) {
             let vector = self
                 .storage
                 .get(internal_id)
                 .ok_or(Error::VectorNotFound(id.to_string()))?;
-            let metadata = self.storage.get_metadata(internal_id);
+            let metadata = self.with_defaults(self.storage.get_metadata(internal_id));
             Ok(Some((vector, metadata)))
         } else {
             Ok(None)
@@ -351,37 +1275,227 @@ impl PersistentVectorDb {
     pub fn list(&self, offset: usize, limit: usize) -> Vec<(VectorId, Option<Value>)> {
         let ids = self.storage.all_internal_ids();
         ids.iter()
-            .filter_map(|&internal_id| {
-                let ext_id = self.storage.get_external_id(internal_id)?;
-                // Check if deleted
-                if self.storage.is_deleted(internal_id) {
-                    return None;
-                }
-                // Check if stale
-                let current_internal = self.storage.get_internal_id(&ext_id)?;
-                if current_internal != internal_id {
-                    return None;
-                }
-                let metadata = self.storage.get_metadata(internal_id);
-                Some((ext_id, metadata))
-            })
+            .filter_map(|&internal_id| self.list_entry(internal_id))
             .skip(offset)
             .take(limit)
             .collect()
     }
 
+    /// List up to `limit` vector IDs and metadata from a cursor (keyset pagination)
+    ///
+    /// `cursor` is the previous page's `next_cursor`, or `None` for the first
+    /// page. Unlike `list`, earlier entries are not re-scanned. The cursor is
+    /// a storage position, so it stays valid when vectors are updated or
+    /// deleted between pages; an updated vector moves to the end of the
+    /// listing. `compact` and `clear` renumber positions.
+    pub fn list_after(&self, cursor: Option<u64>, limit: usize) -> ListPage {
+        crate::types::list_page(cursor, limit, self.physical_len(), |internal_id| {
+            self.list_entry(internal_id)
+        })
+    }
+
+    /// Resolve a storage slot to its listing entry, skipping stale slots
+    fn list_entry(&self, internal_id: InternalId) -> Option<(VectorId, Option<Value>)> {
+        let ext_id = self.storage.get_external_id(internal_id)?;
+        // Check if deleted
+        if self.storage.is_deleted(internal_id) {
+            return None;
+        }
+        // Check if stale
+        let current_internal = self.storage.get_internal_id(&ext_id)?;
+        if current_internal != internal_id {
+            return None;
+        }
+        let metadata = self.with_defaults(self.storage.get_metadata(internal_id));
+        Some((ext_id, metadata))
+    }
+
+    /// Count the most frequent values of a metadata field (see `VectorDb::facet`)
+    pub fn facet(&self, field: &str, top_n: usize) -> Facets {
+        self.facet_sampled(field, top_n, None)
+    }
+
+    /// Like `facet`, but counts are extrapolated from about `sample` vectors
+    pub fn facet_sampled(&self, field: &str, top_n: usize, sample: Option<usize>) -> Facets {
+        crate::facet::aggregate(
+            field,
+            top_n,
+            sample,
+            self.physical_len(),
+            self.len(),
+            |internal_id| {
+                let ext_id = self.storage.get_external_id(internal_id)?;
+                (self.storage.get_internal_id(&ext_id)? == internal_id)
+                    .then(|| self.storage.get_metadata(internal_id))
+            },
+        )
+    }
+
+    /// Get the number of deleted or superseded slots still held in storage
+    pub fn tombstone_count(&self) -> usize {
+        self.physical_len() - self.len()
+    }
+
+    /// Get the number of deleted or superseded slots awaiting `compact`
+    /// (the same as `tombstone_count`)
+    pub fn deleted_count(&self) -> usize {
+        self.tombstone_count()
+    }
+
     /// Check if the database is empty
     pub fn is_empty(&self) -> bool {
         self.storage.is_empty()
     }
 
+    /// Run graph maintenance: re-prune HNSW neighbor lists that exceed the
+    /// configured maximum degree. Returns the audit taken before the repair;
+    /// the repaired graph is persisted by the next checkpoint.
+    pub fn optimize(&self) -> DegreeReport {
+        self.index.auto_repair_degrees(&self.storage)
+    }
+
+    /// Check index health by searching for up to `sample` stored vectors and
+    /// counting how many find themselves in their top `k`
+    pub fn self_recall(&self, sample: usize, k: usize, seed: u64) -> SelfRecall {
+        self.index.self_recall(&self.storage, sample, k, seed)
+    }
+
+    /// Get approximate in-memory usage in bytes
+    pub fn memory_usage(&self) -> usize {
+        self.storage.memory_usage() + self.index.memory_usage()
+    }
+
+    /// Get the on-disk size of the WAL and snapshots in bytes
+    pub fn disk_usage(&self) -> u64 {
+        dir_size(&self.data_dir).unwrap_or(0)
+    }
+
     /// Get configuration
     pub fn config(&self) -> &PersistentConfig {
         &self.config
     }
 
+    /// Merge the collection's default metadata into a vector's metadata
+    fn with_defaults(&self, metadata: Option<Value>) -> Option<Value> {
+        crate::types::merge_default_metadata(self.config.default_metadata.as_ref(), metadata)
+    }
+
     /// Get data directory
     pub fn data_dir(&self) -> &Path {
         &self.data_dir
     }
+
+    /// Get the directory snapshots and deltas are written to
+    pub fn snapshot_dir(&self) -> &Path {
+        self.snapshot_manager.dir()
+    }
+}
+
+impl crate::compaction::Compact for PersistentVectorDb {
+    type Storage = VectorStorage;
+
+    fn compactor(&self) -> &crate::compaction::Compactor {
+        &self.compactor
+    }
+
+    fn compaction_due(&self) -> bool {
+        self.needs_compaction()
+    }
+
+    fn plan_compaction(&self) -> crate::compaction::Plan<VectorStorage> {
+        crate::compaction::Plan::new(
+            &self.compactor,
+            &self.storage,
+            new_storage(&self.config, self.storage.dimensions()),
+            vec![HnswIndex::new(
+                self.config.hnsw.clone(),
+                index_metric(&self.config),
+            )],
+        )
+    }
+
+    /// The result is checkpointed, which truncates the WAL, and older
+    /// snapshots are deleted
+    fn finish_compaction(
+        &mut self,
+        mut rebuilt: crate::compaction::Rebuilt<VectorStorage>,
+    ) -> Result<usize> {
+        self.check_writable()?;
+        if !rebuilt.catch_up(
+            &self.compactor,
+            &self.storage,
+            self.config.hnsw.eager_edge_cleanup,
+        )? {
+            return Ok(0);
+        }
+        let reclaimed = self
+            .physical_len()
+            .saturating_sub(rebuilt.storage.total_slots());
+        self.storage = rebuilt.storage;
+        if let Some(index) = rebuilt.indexes.pop() {
+            self.index = index;
+        }
+        self.content_hashes = crate::types::ContentHashes::default();
+        self.snapshot_mark = None;
+
+        self.checkpoint()?;
+        self.snapshot_manager.prune(1)?;
+        info!("Compacted {} reclaimed slots", reclaimed);
+        Ok(reclaimed)
+    }
+}
+
+/// Metric the index compares stored vectors with
+fn new_storage(config: &PersistentConfig, dimensions: usize) -> VectorStorage {
+    let mut storage = VectorStorage::new(dimensions);
+    storage.set_metadata_format(&config.metadata_format);
+    storage
+}
+
+fn index_metric(config: &PersistentConfig) -> DistanceMetric {
+    if config.normalize {
+        config.distance_metric.for_normalized()
+    } else {
+        config.distance_metric
+    }
+}
+
+/// Take the exclusive write lock on a data directory
+///
+/// The lock is released when the returned file is closed, including when the
+/// process dies, so a crashed primary never leaves a stale lock behind.
+fn lock_data_dir(data_dir: &Path) -> Result<File> {
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(data_dir.join(LOCK_FILE))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::io::AsRawFd;
+
+        let locked = unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) };
+        if locked != 0 {
+            return Err(Error::LockFailed {
+                message: format!("{} is locked by another writer", data_dir.display()),
+            });
+        }
+    }
+
+    Ok(file)
+}
+
+fn dir_size(path: &Path) -> std::io::Result<u64> {
+    let mut size = 0;
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            size += dir_size(&entry.path())?;
+        } else {
+            size += metadata.len();
+        }
+    }
+    Ok(size)
 }
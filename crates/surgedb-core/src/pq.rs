@@ -179,12 +179,24 @@ impl PqCodebook {
                         // Let's use L2 for now as standard PQ.
                         crate::distance::euclidean_distance(sub_query, centroid).powi(2)
                     }
-                    DistanceMetric::Euclidean => {
+                    DistanceMetric::Euclidean | DistanceMetric::Hybrid { .. } => {
                         crate::distance::euclidean_distance(sub_query, centroid).powi(2)
                     }
                     DistanceMetric::DotProduct => {
                         crate::distance::dot_product_distance(sub_query, centroid)
                     }
+                    // L1 sums over dimensions, so the sub-vector terms add up exactly
+                    DistanceMetric::Manhattan => {
+                        crate::distance::manhattan_distance(sub_query, centroid)
+                    }
+                    // Differing bits also add up over sub-vectors
+                    DistanceMetric::Hamming => {
+                        crate::distance::hamming_distance(sub_query, centroid)
+                    }
+                    // Not a sum over sub-vectors, so rank by L2 as for Cosine
+                    DistanceMetric::Jaccard => {
+                        crate::distance::euclidean_distance(sub_query, centroid).powi(2)
+                    }
                 };
 
                 table.push(dist);
@@ -206,8 +218,137 @@ impl PqCodebook {
     }
 }
 
+/// PQ codes for a vector along with the cluster whose codebook produced them
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClusteredCodes {
+    /// Index into `ClusteredPqCodebook::codebooks`
+    pub cluster: u32,
+    /// Codes from that cluster's codebook
+    pub codes: Vec<u8>,
+}
+
+/// Per-cluster Product Quantization
+///
+/// Vectors are assigned to the nearest of a set of k-means centroids and
+/// encoded with a codebook trained on that cluster alone. On heterogeneous
+/// data each codebook only has to cover one region of the space, so the
+/// same number of code bytes reconstructs vectors more closely than a
+/// single global codebook.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusteredPqCodebook {
+    /// Coarse centroids used for cluster assignment, one per codebook
+    pub centroids: Vec<Vec<f32>>,
+    /// One codebook per cluster
+    pub codebooks: Vec<PqCodebook>,
+}
+
+impl ClusteredPqCodebook {
+    /// Cluster the training set into up to `num_clusters` clusters and train
+    /// one codebook per cluster
+    ///
+    /// Clusters that end up with no training vectors are dropped.
+    pub fn train(vectors: &[Vec<f32>], num_clusters: usize, config: PqConfig) -> Result<Self> {
+        if vectors.is_empty() {
+            return Err(Error::InvalidConfig("Training set cannot be empty".into()));
+        }
+        if num_clusters == 0 || num_clusters > u32::MAX as usize {
+            return Err(Error::InvalidConfig(format!(
+                "num_clusters must be between 1 and {}, got {}",
+                u32::MAX,
+                num_clusters
+            )));
+        }
+
+        let mut rng = rand::thread_rng();
+        let sample: Vec<Vec<f32>> = if vectors.len() > config.sample_size {
+            vectors
+                .choose_multiple(&mut rng, config.sample_size)
+                .cloned()
+                .collect()
+        } else {
+            vectors.to_vec()
+        };
+
+        let coarse = kmeans(&sample, num_clusters, config.max_iterations);
+        let mut members: Vec<Vec<Vec<f32>>> = vec![Vec::new(); coarse.len()];
+        for vector in sample {
+            members[nearest_centroid(&coarse, &vector)].push(vector);
+        }
+
+        let mut centroids = Vec::with_capacity(coarse.len());
+        let mut codebooks = Vec::with_capacity(coarse.len());
+        for (centroid, members) in coarse.into_iter().zip(members) {
+            if members.is_empty() {
+                continue;
+            }
+            codebooks.push(PqCodebook::train(&members, config.clone())?);
+            centroids.push(centroid);
+        }
+
+        Ok(Self {
+            centroids,
+            codebooks,
+        })
+    }
+
+    /// Index of the cluster `vector` is assigned to
+    pub fn assign(&self, vector: &[f32]) -> usize {
+        nearest_centroid(&self.centroids, vector)
+    }
+
+    /// Encode a vector with the codebook of its cluster
+    pub fn encode(&self, vector: &[f32]) -> ClusteredCodes {
+        let cluster = self.assign(vector);
+        ClusteredCodes {
+            cluster: cluster as u32,
+            codes: self.codebooks[cluster].encode(vector),
+        }
+    }
+
+    /// Decode codes back to an approximate vector
+    pub fn decode(&self, codes: &ClusteredCodes) -> Vec<f32> {
+        self.codebooks[codes.cluster as usize].decode(&codes.codes)
+    }
+
+    /// Pre-compute one ADC table per cluster for a query
+    pub fn precompute_adc(&self, query: &[f32], metric: DistanceMetric) -> Vec<Vec<f32>> {
+        self.codebooks
+            .iter()
+            .map(|codebook| codebook.precompute_adc(query, metric))
+            .collect()
+    }
+
+    /// Calculate distance using the tables from `precompute_adc`
+    #[inline]
+    pub fn distance_adc(&self, codes: &ClusteredCodes, adc_tables: &[Vec<f32>]) -> f32 {
+        let cluster = codes.cluster as usize;
+        self.codebooks[cluster].distance_adc(&codes.codes, &adc_tables[cluster])
+    }
+
+    /// Bytes held by the coarse centroids and every codebook
+    pub fn memory_usage(&self) -> usize {
+        let floats: usize = self.centroids.iter().map(Vec::len).sum::<usize>()
+            + self
+                .codebooks
+                .iter()
+                .map(|codebook| codebook.centroids.len())
+                .sum::<usize>();
+        floats * std::mem::size_of::<f32>()
+    }
+}
+
+/// Index of the centroid nearest to `vector`
+fn nearest_centroid(centroids: &[Vec<f32>], vector: &[f32]) -> usize {
+    centroids
+        .iter()
+        .map(|centroid| crate::distance::euclidean_distance(vector, centroid))
+        .enumerate()
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map_or(0, |(i, _)| i)
+}
+
 /// Simple k-means clustering
-fn kmeans(vectors: &[Vec<f32>], k: usize, max_iter: usize) -> Vec<Vec<f32>> {
+pub(crate) fn kmeans(vectors: &[Vec<f32>], k: usize, max_iter: usize) -> Vec<Vec<f32>> {
     let dim = vectors[0].len();
     let mut rng = rand::thread_rng();
 
@@ -311,4 +452,79 @@ mod tests {
             assert!(!x.is_nan());
         }
     }
+
+    /// Two far-apart clusters with very different spreads
+    fn clustered_vectors() -> Vec<Vec<f32>> {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(3);
+        (0..400)
+            .map(|i| {
+                let (center, spread) = if i % 2 == 0 {
+                    (10.0, 0.1)
+                } else {
+                    (-10.0, 2.0)
+                };
+                (0..8)
+                    .map(|_| center + rng.gen_range(-spread..spread))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_clustered_pq_encodes_with_cluster_codebook() {
+        let vectors = clustered_vectors();
+        let config = PqConfig {
+            num_subvectors: 4,
+            num_centroids: 16,
+            sample_size: 400,
+            max_iterations: 10,
+        };
+
+        let codebook = ClusteredPqCodebook::train(&vectors, 2, config).unwrap();
+        assert_eq!(codebook.codebooks.len(), 2);
+
+        let near = codebook.encode(&vectors[0]);
+        let far = codebook.encode(&vectors[1]);
+        assert_ne!(near.cluster, far.cluster);
+        assert_eq!(near.codes.len(), 4);
+        assert_eq!(codebook.decode(&near).len(), 8);
+
+        let tables = codebook.precompute_adc(&vectors[0], DistanceMetric::Euclidean);
+        assert!(codebook.distance_adc(&near, &tables) < codebook.distance_adc(&far, &tables));
+
+        assert!(matches!(
+            ClusteredPqCodebook::train(&vectors, 0, PqConfig::default()),
+            Err(Error::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_clustered_pq_persists() {
+        use std::io::{Seek, SeekFrom};
+
+        let vectors = clustered_vectors();
+        let config = PqConfig {
+            num_subvectors: 2,
+            num_centroids: 8,
+            sample_size: 400,
+            max_iterations: 5,
+        };
+        let codebook = ClusteredPqCodebook::train(&vectors, 4, config).unwrap();
+        let codes: Vec<ClusteredCodes> = vectors.iter().map(|v| codebook.encode(v)).collect();
+
+        let mut file = tempfile::tempfile().unwrap();
+        bincode::serialize_into(&mut file, &(&codebook, &codes)).unwrap();
+        file.seek(SeekFrom::Start(0)).unwrap();
+        let (loaded, loaded_codes): (ClusteredPqCodebook, Vec<ClusteredCodes>) =
+            bincode::deserialize_from(&mut file).unwrap();
+
+        assert_eq!(loaded.centroids, codebook.centroids);
+        assert_eq!(loaded_codes, codes);
+        for (vector, codes) in vectors.iter().zip(&codes) {
+            assert_eq!(&loaded.encode(vector), codes);
+            assert_eq!(loaded.decode(codes), codebook.decode(codes));
+        }
+    }
 }
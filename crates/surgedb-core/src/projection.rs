@@ -0,0 +1,142 @@
+//! Random projection for dimensionality reduction
+//!
+//! Projects input vectors onto a lower-dimensional subspace using a dense
+//! Rademacher matrix (entries ±1/√target_dim). By the Johnson-Lindenstrauss
+//! lemma, pairwise distances are approximately preserved, so well-separated
+//! vectors keep their relative ordering while storage shrinks proportionally.
+
+use crate::distance::{dot_product_distance, normalize};
+use crate::error::{Error, Result};
+use crate::types::DimensionPolicy;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// Random projection configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectionConfig {
+    /// Dimensionality of the projected (stored) vectors
+    pub target_dim: usize,
+    /// Seed for the projection matrix; the same seed always yields the same matrix
+    pub seed: u64,
+}
+
+/// A fixed random projection matrix
+#[derive(Debug, Clone)]
+pub struct RandomProjection {
+    input_dim: usize,
+    target_dim: usize,
+    /// Row-major `target_dim x input_dim` matrix
+    matrix: Vec<f32>,
+}
+
+impl RandomProjection {
+    /// Build the projection matrix for `input_dim`-dimensional inputs
+    pub fn new(input_dim: usize, config: ProjectionConfig) -> Result<Self> {
+        if config.target_dim == 0 || config.target_dim > input_dim {
+            return Err(Error::InvalidConfig(format!(
+                "projection target_dim must be in 1..={}, got {}",
+                input_dim, config.target_dim
+            )));
+        }
+
+        let scale = 1.0 / (config.target_dim as f32).sqrt();
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let matrix = (0..config.target_dim * input_dim)
+            .map(|_| if rng.gen::<bool>() { scale } else { -scale })
+            .collect();
+
+        Ok(Self {
+            input_dim,
+            target_dim: config.target_dim,
+            matrix,
+        })
+    }
+
+    /// Project a vector of `input_dim` dimensions down to `target_dim`
+    pub fn project(&self, vector: &[f32]) -> Vec<f32> {
+        debug_assert_eq!(vector.len(), self.input_dim);
+        self.matrix
+            .chunks_exact(self.input_dim)
+            .map(|row| 1.0 - dot_product_distance(row, vector))
+            .collect()
+    }
+
+    /// Dimensionality of projected vectors
+    pub fn target_dim(&self) -> usize {
+        self.target_dim
+    }
+
+    /// Get approximate memory usage of the matrix in bytes
+    pub fn memory_usage(&self) -> usize {
+        self.matrix.capacity() * std::mem::size_of::<f32>()
+    }
+}
+
+/// Fit an input vector to `dimensions` under `policy` and apply the
+/// projection, if any
+///
+/// Borrows the input unchanged when it already fits and no projection is
+/// configured.
+pub(crate) fn prepare<'a>(
+    projection: Option<&RandomProjection>,
+    dimensions: usize,
+    policy: DimensionPolicy,
+    vector: &'a [f32],
+) -> Result<Cow<'a, [f32]>> {
+    let vector = policy.fit(dimensions, vector)?;
+    Ok(match projection {
+        Some(projection) => Cow::Owned(projection.project(&vector)),
+        None => vector,
+    })
+}
+
+/// Like `prepare`, additionally scaling the result to unit length when
+/// `unit_norm` is set (after projection, so stored vectors are unit length)
+pub(crate) fn prepare_normalized<'a>(
+    projection: Option<&RandomProjection>,
+    dimensions: usize,
+    policy: DimensionPolicy,
+    unit_norm: bool,
+    vector: &'a [f32],
+) -> Result<Cow<'a, [f32]>> {
+    let mut prepared = prepare(projection, dimensions, policy, vector)?;
+    if unit_norm {
+        normalize(prepared.to_mut());
+    }
+    Ok(prepared)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_projection_is_deterministic() {
+        let config = ProjectionConfig {
+            target_dim: 16,
+            seed: 7,
+        };
+        let a = RandomProjection::new(64, config).unwrap();
+        let b = RandomProjection::new(64, config).unwrap();
+        let v: Vec<f32> = (0..64).map(|i| i as f32 / 64.0).collect();
+
+        assert_eq!(a.project(&v), b.project(&v));
+        assert_eq!(a.project(&v).len(), 16);
+    }
+
+    #[test]
+    fn test_invalid_target_dim() {
+        let zero = ProjectionConfig {
+            target_dim: 0,
+            seed: 0,
+        };
+        let larger = ProjectionConfig {
+            target_dim: 65,
+            seed: 0,
+        };
+        assert!(RandomProjection::new(64, zero).is_err());
+        assert!(RandomProjection::new(64, larger).is_err());
+    }
+}
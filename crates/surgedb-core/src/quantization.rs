@@ -106,6 +106,13 @@ impl SQ8Quantizer {
             DistanceMetric::DotProduct => {
                 self.asymmetric_dot_product_distance(query, quantized, metadata)
             }
+            // No fused kernel for these, so reconstruct
+            DistanceMetric::Manhattan
+            | DistanceMetric::Hybrid { .. }
+            | DistanceMetric::Hamming
+            | DistanceMetric::Jaccard => {
+                metric.distance(query, &self.dequantize(quantized, metadata))
+            }
         }
     }
 
@@ -293,6 +300,22 @@ impl BinaryQuantizer {
         result
     }
 
+    /// Reconstruct a binary vector as +1.0 for set bits and -1.0 otherwise
+    ///
+    /// Only each dimension's sign survives quantization, so this is a
+    /// direction, not the original magnitudes.
+    pub fn dequantize(&self, binary: &[u8]) -> Vec<f32> {
+        (0..self.dimensions)
+            .map(|i| {
+                if binary[i / 8] & (1 << (i % 8)) != 0 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            })
+            .collect()
+    }
+
     /// Calculate Hamming distance between two binary vectors
     #[inline]
     pub fn hamming_distance(&self, a: &[u8], b: &[u8]) -> u32 {
@@ -345,6 +368,34 @@ impl BinaryQuantizer {
         }
     }
 
+    /// Jaccard distance between two binary codes read as sets of set bits
+    #[inline]
+    pub fn jaccard_distance(&self, a: &[u8], b: &[u8]) -> f32 {
+        let (mut both, mut either) = (0u32, 0u32);
+        for (&x, &y) in a.iter().zip(b) {
+            both += (x & y).count_ones();
+            either += (x | y).count_ones();
+        }
+        if either == 0 {
+            0.0
+        } else {
+            1.0 - both as f32 / either as f32
+        }
+    }
+
+    /// Distance between two binary codes under `metric`
+    ///
+    /// Hamming and Jaccard are exact on the codes; any other metric gets the
+    /// approximate cosine distance from `hamming_to_cosine`.
+    #[inline]
+    pub fn distance(&self, a: &[u8], b: &[u8], metric: DistanceMetric) -> f32 {
+        match metric {
+            DistanceMetric::Hamming => self.hamming_distance(a, b) as f32,
+            DistanceMetric::Jaccard => self.jaccard_distance(a, b),
+            _ => self.hamming_to_cosine(self.hamming_distance(a, b)),
+        }
+    }
+
     /// Convert Hamming distance to approximate cosine distance
     /// Useful for ranking compatibility with non-quantized results
     #[inline]
@@ -414,6 +465,19 @@ mod tests {
         assert_eq!(binary[0], 0b00100101);
     }
 
+    #[test]
+    fn test_binary_dequantize_keeps_signs() {
+        let quantizer = BinaryQuantizer::new(10);
+        let vector = vec![0.3, -2.0, 0.0, 5.0, -0.1, 0.2, 0.2, -0.2, 1.0, -1.0];
+
+        let restored = quantizer.dequantize(&quantizer.quantize(&vector));
+
+        assert_eq!(
+            restored,
+            vec![1.0, -1.0, -1.0, 1.0, -1.0, 1.0, 1.0, -1.0, 1.0, -1.0]
+        );
+    }
+
     #[test]
     fn test_binary_hamming_distance() {
         let quantizer = BinaryQuantizer::new(8);
@@ -430,6 +494,34 @@ mod tests {
         assert_eq!(dist, 4);
     }
 
+    #[test]
+    fn test_binary_jaccard_distance() {
+        let quantizer = BinaryQuantizer::new(10);
+        let code = |bits: &[usize]| {
+            let v: Vec<f32> = (0..10)
+                .map(|i| if bits.contains(&i) { 1.0 } else { -1.0 })
+                .collect();
+            quantizer.quantize(&v)
+        };
+
+        // {0, 1, 2, 9} and {1, 2, 3}: 2 shared of 5
+        let (a, b) = (code(&[0, 1, 2, 9]), code(&[1, 2, 3]));
+        assert!((quantizer.jaccard_distance(&a, &b) - 0.6).abs() < 1e-6);
+        assert_eq!(quantizer.distance(&a, &b, DistanceMetric::Hamming), 3.0);
+        assert_eq!(quantizer.distance(&a, &b, DistanceMetric::Jaccard), 0.6);
+        assert_eq!(quantizer.jaccard_distance(&code(&[]), &code(&[])), 0.0);
+
+        // The float kernels agree with the codes
+        let v = |bits: &[usize]| -> Vec<f32> {
+            (0..10)
+                .map(|i| if bits.contains(&i) { 0.5 } else { 0.0 })
+                .collect()
+        };
+        let (x, y) = (v(&[0, 1, 2, 9]), v(&[1, 2, 3]));
+        assert_eq!(DistanceMetric::Hamming.distance(&x, &y), 3.0);
+        assert!((DistanceMetric::Jaccard.distance(&x, &y) - 0.6).abs() < 1e-6);
+    }
+
     #[test]
     fn test_compression_ratio() {
         // SQ8: 4 bytes -> 1 byte = 4x compression
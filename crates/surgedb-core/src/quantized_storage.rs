@@ -362,8 +362,7 @@ impl QuantizedStorage {
                 let query_binary = quantizer.quantize(query);
                 let stored = &binary_vectors[start..end];
 
-                let hamming = quantizer.hamming_distance(&query_binary, stored);
-                Some(quantizer.hamming_to_cosine(hamming))
+                Some(quantizer.distance(&query_binary, stored, metric))
             }
         }
     }
@@ -381,11 +380,55 @@ impl QuantizedStorage {
         None
     }
 
+    /// Get the stored quantized bytes of a vector: `dimensions` bytes for SQ8,
+    /// one bit per dimension for Binary. Returns `None` without quantization.
+    pub fn get_quantized_bytes(&self, internal_id: InternalId) -> Option<Vec<u8>> {
+        let (vectors, width) = match self.quantization {
+            QuantizationType::None => return None,
+            QuantizationType::SQ8 => (self.sq8_vectors.read(), self.dimensions),
+            QuantizationType::Binary => (
+                self.binary_vectors.read(),
+                self.binary_quantizer.as_ref()?.byte_size(),
+            ),
+        };
+        let start = internal_id.as_usize() * width;
+        vectors.get(start..start + width).map(<[u8]>::to_vec)
+    }
+
+    /// Get the SQ8 reconstruction parameters (min and scale) of a vector
+    pub fn get_sq8_metadata(&self, internal_id: InternalId) -> Option<SQ8Metadata> {
+        self.sq8_metadata
+            .read()
+            .get(internal_id.as_usize())
+            .cloned()
+    }
+
     /// Get metadata for a vector
     pub fn get_metadata(&self, internal_id: InternalId) -> Option<Value> {
         self.metadata.read().get(&internal_id).cloned()
     }
 
+    /// Replace the metadata of a stored vector
+    pub fn set_metadata(&self, internal_id: InternalId, metadata: Option<Value>) {
+        let mut store = self.metadata.write();
+        match metadata {
+            Some(meta) => store.insert(internal_id, meta),
+            None => store.remove(&internal_id),
+        };
+    }
+
+    /// Replace a vector's metadata in place, keeping its quantized bytes and
+    /// graph node; `null` clears it
+    ///
+    /// Fails with `VectorNotFound` if no vector is stored under `id`.
+    pub fn update_metadata(&self, id: &VectorId, metadata: Value) -> Result<()> {
+        let internal_id = self
+            .get_internal_id(id)
+            .ok_or_else(|| Error::VectorNotFound(id.to_string()))?;
+        self.set_metadata(internal_id, (!metadata.is_null()).then_some(metadata));
+        Ok(())
+    }
+
     /// Get external ID from internal ID
     pub fn get_external_id(&self, internal_id: InternalId) -> Option<VectorId> {
         let internal_to_id = self.internal_to_id.read();
@@ -403,8 +446,13 @@ impl QuantizedStorage {
         (0..internal_to_id.len()).map(InternalId::from).collect()
     }
 
-    /// Get the number of stored vectors
+    /// Get the number of active vectors (excluding deleted and stale entries)
     pub fn len(&self) -> usize {
+        self.id_to_internal.read().len()
+    }
+
+    /// Get the total number of slots used (including stale/deleted)
+    pub fn total_slots(&self) -> usize {
         self.internal_to_id.read().len()
     }
 
@@ -436,7 +484,7 @@ impl QuantizedStorage {
 
     /// Get compression ratio compared to f32 storage
     pub fn compression_ratio(&self) -> f32 {
-        let count = self.len();
+        let count = self.total_slots();
         if count == 0 {
             return 1.0;
         }
@@ -551,8 +599,11 @@ impl VectorStorageTrait for QuantizedStorage {
                 Some(quantizer.dequantize(&sq8_vectors[start..end], &sq8_metadata[idx]))
             }
             QuantizationType::Binary => {
-                // Binary cannot be easily dequantized to f32 without massive loss
-                None
+                let quantizer = self.binary_quantizer.as_ref()?;
+                let binary_vectors = self.binary_vectors.read();
+                let start = internal_id.as_usize() * quantizer.byte_size();
+                let stored = binary_vectors.get(start..start + quantizer.byte_size())?;
+                Some(quantizer.dequantize(stored))
             }
         }
     }
@@ -654,8 +705,7 @@ impl<'a> QuantizedStorageView<'a> {
                 }
 
                 let stored = &binary_vectors[start..end];
-                let hamming = quantizer.hamming_distance(query_binary, stored);
-                Some(quantizer.hamming_to_cosine(hamming))
+                Some(quantizer.distance(query_binary, stored, metric))
             }
         }
     }
@@ -705,7 +755,15 @@ impl<'a> VectorStorageTrait for QuantizedStorageView<'a> {
 
                 Some(quantizer.dequantize(&sq8_vectors[start..end], &sq8_metadata[idx]))
             }
-            QuantizationType::Binary => None,
+            QuantizationType::Binary => {
+                let quantizer = self.binary_quantizer?;
+                let start = internal_id.as_usize() * quantizer.byte_size();
+                let stored = self
+                    .binary_vectors
+                    .as_ref()?
+                    .get(start..start + quantizer.byte_size())?;
+                Some(quantizer.dequantize(stored))
+            }
         }
     }
 
@@ -759,6 +817,35 @@ mod tests {
         assert!(dist < 0.01, "dist={}", dist);
     }
 
+    #[test]
+    fn test_quantized_bytes_round_trip() {
+        let storage = QuantizedStorage::new(4, QuantizationType::SQ8, false);
+        let vector = vec![-1.0, 0.5, 2.0, 0.0];
+        storage.insert("a".into(), &[0.0; 4], None).unwrap();
+        let internal_id = storage.insert("b".into(), &vector, None).unwrap();
+
+        let bytes = storage.get_quantized_bytes(internal_id).unwrap();
+        let meta = storage.get_sq8_metadata(internal_id).unwrap();
+        assert_eq!(bytes.len(), 4);
+        assert_eq!((bytes[0], bytes[2]), (0, 255));
+        assert_eq!(meta.min, -1.0);
+
+        let restored = SQ8Quantizer::new(4).dequantize(&bytes, &meta);
+        for (a, b) in restored.iter().zip(&vector) {
+            assert!((a - b).abs() <= meta.scale / 2.0 + 1e-6, "{} vs {}", a, b);
+        }
+        assert!(storage.get_quantized_bytes(InternalId::from(2)).is_none());
+
+        let binary = QuantizedStorage::new(10, QuantizationType::Binary, false);
+        let v = [1.0, -1.0, 1.0, -1.0, -1.0, -1.0, -1.0, -1.0, 1.0, -1.0];
+        let internal_id = binary.insert("v".into(), &v, None).unwrap();
+        assert_eq!(
+            binary.get_quantized_bytes(internal_id).unwrap(),
+            vec![0b0000_0101, 0b0000_0001]
+        );
+        assert!(binary.get_sq8_metadata(internal_id).is_none());
+    }
+
     #[test]
     fn test_binary_storage() {
         let storage = QuantizedStorage::new(8, QuantizationType::Binary, false);
@@ -0,0 +1,209 @@
+//! Nearest-centroid routing for HNSW search
+//!
+//! On collections with natural clusters most of a query's neighbors sit in
+//! the clusters closest to it. A router holds a small set of k-means
+//! centroids and the nearest centroid of every stored vector; a routed
+//! search only scores and traverses graph nodes assigned to the `nprobe`
+//! centroids nearest the query. This is lighter than a full IVF index: the
+//! HNSW graph is unchanged and the router only narrows its layer-0 walk.
+
+use crate::distance::DistanceMetric;
+use crate::error::{Error, Result};
+use crate::storage::{VectorStorage, VectorStorageTrait};
+use crate::sync::RwLock;
+use crate::types::InternalId;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+/// Settings for `VectorDb::train_routing`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoutingConfig {
+    /// Number of k-means centroids
+    pub num_centroids: usize,
+    /// Centroids searched per query unless overridden; higher trades
+    /// latency for recall, and `num_centroids` searches the whole graph
+    pub nprobe: usize,
+    /// Vectors sampled to train the centroids
+    pub sample_size: usize,
+    /// Max k-means iterations
+    pub max_iterations: usize,
+}
+
+impl Default for RoutingConfig {
+    fn default() -> Self {
+        Self {
+            num_centroids: 16,
+            nprobe: 4,
+            sample_size: 10_000,
+            max_iterations: 20,
+        }
+    }
+}
+
+impl RoutingConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.num_centroids == 0 || self.num_centroids > u32::MAX as usize {
+            return Err(Error::InvalidConfig(format!(
+                "num_centroids must be between 1 and {}, got {}",
+                u32::MAX,
+                self.num_centroids
+            )));
+        }
+        if self.nprobe == 0 || self.nprobe > self.num_centroids {
+            return Err(Error::InvalidConfig(format!(
+                "nprobe must be between 1 and num_centroids ({}), got {}",
+                self.num_centroids, self.nprobe
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// One centroid list probed by a routed search
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProbedList {
+    /// Index of the centroid
+    pub list: usize,
+    /// Graph candidates from this list kept by the search; a probed list
+    /// that contributes nothing suggests `nprobe` can be lowered
+    pub candidates: usize,
+}
+
+/// Which lists a routed search probed, from `VectorDb::search_debug`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoutingDebug {
+    /// Probed lists, nearest the query first
+    pub probed: Vec<ProbedList>,
+}
+
+/// Trained centroids and the centroid of every storage slot
+pub(crate) struct CentroidRouter {
+    config: RoutingConfig,
+    metric: DistanceMetric,
+    centroids: Vec<Vec<f32>>,
+    /// Nearest centroid per internal ID. Slots written after the last
+    /// search are assigned by the next one (see `catch_up`).
+    assignments: RwLock<Vec<u32>>,
+}
+
+impl CentroidRouter {
+    /// Cluster a sample of the stored vectors and assign every slot
+    pub(crate) fn train(
+        storage: &VectorStorage,
+        metric: DistanceMetric,
+        config: RoutingConfig,
+    ) -> Result<Self> {
+        config.validate()?;
+        let mut ids = storage.all_internal_ids();
+        ids.retain(|&id| !storage.is_deleted(id));
+        if ids.is_empty() {
+            return Err(Error::EmptyIndex);
+        }
+        let sample: Vec<Vec<f32>> = ids
+            .choose_multiple(&mut rand::thread_rng(), config.sample_size.max(1))
+            .filter_map(|&id| storage.get(id))
+            .collect();
+
+        let router = Self {
+            config,
+            metric,
+            centroids: crate::pq::kmeans(&sample, config.num_centroids, config.max_iterations),
+            assignments: RwLock::new(Vec::new()),
+        };
+        router.catch_up(storage);
+        Ok(router)
+    }
+
+    pub(crate) fn config(&self) -> &RoutingConfig {
+        &self.config
+    }
+
+    /// Assign the slots added since the last call
+    pub(crate) fn catch_up(&self, storage: &VectorStorage) {
+        let total = storage.total_slots();
+        if self.assignments.read().len() >= total {
+            return;
+        }
+        let mut assignments = self.assignments.write();
+        for slot in assignments.len()..total {
+            let centroid = storage
+                .get(InternalId::from(slot))
+                .map_or(0, |vector| self.nearest(&vector, 1)[0]);
+            assignments.push(centroid as u32);
+        }
+    }
+
+    /// Run `search` with a route admitting only the slots assigned to the
+    /// `nprobe` centroids nearest `query`. Slots not yet assigned are
+    /// always admitted.
+    pub(crate) fn route<R>(
+        &self,
+        query: &[f32],
+        nprobe: usize,
+        search: impl FnOnce(&dyn Fn(InternalId) -> bool) -> R,
+    ) -> R {
+        let mut probed = vec![false; self.centroids.len()];
+        for centroid in self.nearest(query, nprobe) {
+            probed[centroid] = true;
+        }
+        let assignments = self.assignments.read();
+        search(&|slot: InternalId| {
+            assignments
+                .get(slot.as_usize())
+                .is_none_or(|&centroid| probed[centroid as usize])
+        })
+    }
+
+    /// The `nprobe` lists a routed search for `query` probes, counting
+    /// `candidates` by the list they are assigned to
+    pub(crate) fn debug(
+        &self,
+        query: &[f32],
+        nprobe: usize,
+        candidates: impl IntoIterator<Item = InternalId>,
+    ) -> RoutingDebug {
+        let mut probed: Vec<ProbedList> = self
+            .nearest(query, nprobe)
+            .into_iter()
+            .map(|list| ProbedList {
+                list,
+                candidates: 0,
+            })
+            .collect();
+        let assignments = self.assignments.read();
+        for slot in candidates {
+            let Some(&centroid) = assignments.get(slot.as_usize()) else {
+                continue;
+            };
+            if let Some(entry) = probed.iter_mut().find(|p| p.list == centroid as usize) {
+                entry.candidates += 1;
+            }
+        }
+        RoutingDebug { probed }
+    }
+
+    /// The list `slot` is assigned to, once caught up
+    pub(crate) fn list_of(&self, slot: InternalId) -> Option<usize> {
+        self.assignments
+            .read()
+            .get(slot.as_usize())
+            .map(|&centroid| centroid as usize)
+    }
+
+    /// Indexes of the `n` centroids closest to `vector`
+    fn nearest(&self, vector: &[f32], n: usize) -> Vec<usize> {
+        let mut order: Vec<(usize, f32)> = self
+            .centroids
+            .iter()
+            .map(|centroid| self.metric.distance(vector, centroid))
+            .enumerate()
+            .collect();
+        order.sort_by(|a, b| a.1.total_cmp(&b.1));
+        order.into_iter().take(n.max(1)).map(|(i, _)| i).collect()
+    }
+
+    pub(crate) fn memory_usage(&self) -> usize {
+        self.centroids.iter().map(Vec::len).sum::<usize>() * std::mem::size_of::<f32>()
+            + self.assignments.read().capacity() * std::mem::size_of::<u32>()
+    }
+}
@@ -0,0 +1,155 @@
+//! Presentation of search distances as scores
+//!
+//! Search always ranks by distance; a `ScoreTransform` only changes the
+//! number reported alongside each hit, so clients of one collection can each
+//! pick the scale they expect.
+
+use crate::distance::DistanceMetric;
+use serde::{Deserialize, Serialize};
+
+/// How a result's distance is reported as its score
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum ScoreTransform {
+    /// The raw distance under the collection's metric (lower is closer)
+    #[default]
+    Distance,
+    /// A similarity where higher is closer, per
+    /// `DistanceMetric::to_similarity`: `1 - distance` for Cosine, DotProduct,
+    /// Hybrid and Jaccard, `1 / (1 + distance)` for Euclidean, Manhattan and
+    /// Hamming
+    Similarity,
+    /// `Similarity` rescaled into [0, 1], for UIs that show a percentage:
+    /// `1 - distance / 2` for Cosine and Hybrid (whose distances lie in
+    /// [0, 2]), `Similarity` itself for the other bounded metrics. The dot
+    /// product is unbounded, so DotProduct squashes it with the logistic
+    /// function, `1 / (1 + e^-dot)`, which is 0.5 at a dot product of 0.
+    NormalizedSimilarity,
+    /// Relevance in (0, 1): `1 / (1 + e^(steepness * (distance - midpoint)))`,
+    /// which is 0.5 at `midpoint` and falls as distance grows
+    Sigmoid {
+        #[serde(default = "default_sigmoid_midpoint")]
+        midpoint: f32,
+        #[serde(default = "default_sigmoid_steepness")]
+        steepness: f32,
+    },
+}
+
+fn default_sigmoid_midpoint() -> f32 {
+    0.5
+}
+
+fn default_sigmoid_steepness() -> f32 {
+    10.0
+}
+
+impl ScoreTransform {
+    /// Sigmoid with the default midpoint (0.5) and steepness (10.0), suited
+    /// to cosine distances
+    pub fn sigmoid() -> Self {
+        ScoreTransform::Sigmoid {
+            midpoint: default_sigmoid_midpoint(),
+            steepness: default_sigmoid_steepness(),
+        }
+    }
+
+    /// Score for a hit at `distance` under `metric`
+    pub fn apply(&self, metric: DistanceMetric, distance: f32) -> f32 {
+        match *self {
+            ScoreTransform::Distance => distance,
+            ScoreTransform::Similarity => metric.to_similarity(distance),
+            ScoreTransform::NormalizedSimilarity => match metric {
+                DistanceMetric::Cosine | DistanceMetric::Hybrid { .. } => {
+                    (1.0 - distance / 2.0).clamp(0.0, 1.0)
+                }
+                DistanceMetric::Euclidean
+                | DistanceMetric::Manhattan
+                | DistanceMetric::Hamming
+                | DistanceMetric::Jaccard => metric.to_similarity(distance),
+                DistanceMetric::DotProduct => 1.0 / (1.0 + (-metric.to_similarity(distance)).exp()),
+            },
+            ScoreTransform::Sigmoid {
+                midpoint,
+                steepness,
+            } => 1.0 / (1.0 + (steepness * (distance - midpoint)).exp()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-6
+    }
+
+    #[test]
+    fn test_distance_is_identity() {
+        assert_eq!(
+            ScoreTransform::Distance.apply(DistanceMetric::Euclidean, 2.5),
+            2.5
+        );
+    }
+
+    #[test]
+    fn test_similarity_per_metric() {
+        let similarity = ScoreTransform::Similarity;
+        assert!(close(similarity.apply(DistanceMetric::Cosine, 0.2), 0.8));
+        assert!(close(
+            similarity.apply(DistanceMetric::DotProduct, -1.0),
+            2.0
+        ));
+        assert!(close(
+            similarity.apply(DistanceMetric::Euclidean, 3.0),
+            0.25
+        ));
+        assert!(close(similarity.apply(DistanceMetric::Manhattan, 0.0), 1.0));
+    }
+
+    #[test]
+    fn test_normalized_similarity_stays_in_unit_interval() {
+        let normalized = ScoreTransform::NormalizedSimilarity;
+        let cosine = DistanceMetric::Cosine;
+        assert!(close(normalized.apply(cosine, 0.0), 1.0));
+        assert!(close(normalized.apply(cosine, 1.0), 0.5));
+        assert!(close(normalized.apply(cosine, 2.0), 0.0));
+        assert!(close(
+            normalized.apply(DistanceMetric::Euclidean, 3.0),
+            0.25
+        ));
+
+        let dot = DistanceMetric::DotProduct;
+        assert!(close(normalized.apply(dot, 1.0), 0.5));
+        for distance in [-100.0, -3.0, 0.0, 1.0, 5.0, 100.0] {
+            let score = normalized.apply(dot, distance);
+            assert!((0.0..=1.0).contains(&score), "{}", score);
+        }
+        assert!(normalized.apply(dot, -3.0) > normalized.apply(dot, 5.0));
+
+        let parsed: ScoreTransform = serde_json::from_str(r#""NormalizedSimilarity""#).unwrap();
+        assert_eq!(parsed, normalized);
+    }
+
+    #[test]
+    fn test_sigmoid_squashes_into_unit_interval() {
+        let sigmoid = ScoreTransform::sigmoid();
+        let metric = DistanceMetric::Cosine;
+        assert!(close(sigmoid.apply(metric, 0.5), 0.5));
+        assert!(close(
+            sigmoid.apply(metric, 0.0),
+            1.0 / (1.0 + (-5.0f32).exp())
+        ));
+        assert!(sigmoid.apply(metric, 0.1) > sigmoid.apply(metric, 0.9));
+        assert!(sigmoid.apply(metric, 2.0) > 0.0);
+
+        let parsed: ScoreTransform =
+            serde_json::from_str(r#"{"Sigmoid": {"midpoint": 1.0}}"#).unwrap();
+        assert_eq!(
+            parsed,
+            ScoreTransform::Sigmoid {
+                midpoint: 1.0,
+                steepness: 10.0
+            }
+        );
+    }
+}
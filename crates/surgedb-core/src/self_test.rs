@@ -0,0 +1,118 @@
+//! Startup recall self-test
+//!
+//! Builds a tiny in-memory index per metric and compares its search results
+//! with brute force under the scalar reference kernels. A broken distance
+//! function or SIMD path shows up as recall far below what HNSW normally
+//! reaches, rather than as silently wrong search results.
+
+use crate::distance::DistanceMetric;
+use crate::error::Result;
+use crate::{Config, VectorDb};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Recall below this fails the self-test; a healthy index scores close to 1.0
+pub const MIN_SELF_TEST_RECALL: f32 = 0.5;
+
+/// Metrics checked by `run`
+pub const SELF_TEST_METRICS: [DistanceMetric; 4] = [
+    DistanceMetric::Cosine,
+    DistanceMetric::Euclidean,
+    DistanceMetric::DotProduct,
+    DistanceMetric::Manhattan,
+];
+
+const VECTORS: usize = 500;
+const QUERIES: usize = 20;
+const DIMENSIONS: usize = 32;
+const K: usize = 10;
+
+/// Recall of the index against brute force for one metric
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SelfTestReport {
+    pub metric: DistanceMetric,
+    /// Mean overlap between the index's and the exact top-k
+    pub recall: f32,
+}
+
+impl SelfTestReport {
+    pub fn passed(&self) -> bool {
+        self.recall >= MIN_SELF_TEST_RECALL
+    }
+}
+
+/// Run the self-test for every metric in `SELF_TEST_METRICS`
+pub fn run() -> Result<Vec<SelfTestReport>> {
+    SELF_TEST_METRICS
+        .iter()
+        .map(|&metric| run_with(metric, |a, b| metric.distance_scalar(a, b)))
+        .collect()
+}
+
+/// Self-test `metric`, taking ground truth from `reference`
+pub(crate) fn run_with(
+    metric: DistanceMetric,
+    reference: impl Fn(&[f32], &[f32]) -> f32,
+) -> Result<SelfTestReport> {
+    // Seeded so a failure reproduces; unit length keeps dot product meaningful
+    let mut rng = StdRng::seed_from_u64(0x5e1f);
+    let mut random_vector = || {
+        let mut vector: Vec<f32> = (0..DIMENSIONS).map(|_| rng.gen_range(-1.0..1.0)).collect();
+        crate::distance::normalize(&mut vector);
+        vector
+    };
+    let vectors: Vec<Vec<f32>> = (0..VECTORS).map(|_| random_vector()).collect();
+    let queries: Vec<Vec<f32>> = (0..QUERIES).map(|_| random_vector()).collect();
+
+    let mut db = VectorDb::new(Config {
+        dimensions: DIMENSIONS,
+        distance_metric: metric,
+        ..Default::default()
+    })?;
+    for (i, vector) in vectors.iter().enumerate() {
+        db.insert(i.to_string(), vector, None)?;
+    }
+
+    let mut hits = 0;
+    for query in &queries {
+        let mut exact: Vec<(usize, f32)> = vectors
+            .iter()
+            .enumerate()
+            .map(|(i, vector)| (i, reference(query, vector)))
+            .collect();
+        exact.sort_by(|a, b| a.1.total_cmp(&b.1));
+        let exact: HashSet<String> = exact.iter().take(K).map(|(i, _)| i.to_string()).collect();
+
+        hits += db
+            .search_ids(query, K, None)?
+            .iter()
+            .filter(|(id, _)| exact.contains(&*id.as_str()))
+            .count();
+    }
+
+    Ok(SelfTestReport {
+        metric,
+        recall: hits as f32 / (QUERIES * K) as f32,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_self_test_passes() {
+        for report in run().unwrap() {
+            assert!(report.passed(), "{:?}", report);
+        }
+    }
+
+    #[test]
+    fn test_self_test_catches_broken_metric() {
+        // A kernel returning negated distances ranks neighbors backwards
+        let metric = DistanceMetric::Euclidean;
+        let report = run_with(metric, |a, b| -metric.distance_scalar(a, b)).unwrap();
+        assert!(!report.passed(), "{:?}", report);
+    }
+}
@@ -0,0 +1,326 @@
+//! Client-side sharding with consistent hashing
+//!
+//! `ShardedClient` spreads vectors across N backends by hashing their IDs onto
+//! a ring of virtual nodes, so adding or removing a shard only moves the keys
+//! adjacent to it. Searches fan out to every shard and the per-shard top-k
+//! lists are merged by distance.
+//!
+//! Backends implement [`ShardBackend`]. In-process collections implement it
+//! out of the box; remote servers can be plugged in by wrapping an HTTP client.
+
+use crate::db::Collection;
+use crate::error::{Error, Result};
+use crate::filter::Filter;
+use crate::types::VectorId;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use tracing::warn;
+
+/// Default number of virtual nodes placed on the ring per shard
+pub const DEFAULT_VIRTUAL_NODES: usize = 64;
+
+/// A single shard that the router can forward requests to
+pub trait ShardBackend {
+    /// Insert or update a vector
+    fn upsert(&self, id: &str, vector: &[f32], metadata: Option<Value>) -> Result<()>;
+
+    /// Delete a vector by ID
+    fn delete(&self, id: &str) -> Result<bool>;
+
+    /// Retrieve a vector by ID
+    fn get(&self, id: &str) -> Result<Option<(Vec<f32>, Option<Value>)>>;
+
+    /// Search the shard for its k nearest neighbors
+    fn search(
+        &self,
+        query: &[f32],
+        k: usize,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<(VectorId, f32, Option<Value>)>>;
+}
+
+impl ShardBackend for Collection {
+    fn upsert(&self, id: &str, vector: &[f32], metadata: Option<Value>) -> Result<()> {
+        Collection::upsert(self, id.to_string(), vector, metadata)
+    }
+
+    fn delete(&self, id: &str) -> Result<bool> {
+        Collection::delete(self, id)
+    }
+
+    fn get(&self, id: &str) -> Result<Option<(Vec<f32>, Option<Value>)>> {
+        Collection::get(self, id)
+    }
+
+    fn search(
+        &self,
+        query: &[f32],
+        k: usize,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<(VectorId, f32, Option<Value>)>> {
+        Collection::search(self, query, k, filter)
+    }
+}
+
+/// Merged result of a fan-out search
+#[derive(Debug, Clone)]
+pub struct ShardedSearchResult {
+    /// Global top-k across all reachable shards, closest first
+    pub results: Vec<(VectorId, f32, Option<Value>)>,
+    /// Names of shards that failed; non-empty means the results are partial
+    pub failed_shards: Vec<String>,
+}
+
+impl ShardedSearchResult {
+    /// True if every shard answered
+    pub fn is_complete(&self) -> bool {
+        self.failed_shards.is_empty()
+    }
+}
+
+/// Routes vector IDs to shards via consistent hashing
+pub struct ShardedClient {
+    shards: Vec<(String, Box<dyn ShardBackend>)>,
+    ring: BTreeMap<u64, usize>,
+    virtual_nodes: usize,
+}
+
+impl Default for ShardedClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ShardedClient {
+    /// Create an empty router using [`DEFAULT_VIRTUAL_NODES`] per shard
+    pub fn new() -> Self {
+        Self::with_virtual_nodes(DEFAULT_VIRTUAL_NODES)
+    }
+
+    /// Create an empty router with a custom number of virtual nodes per shard
+    pub fn with_virtual_nodes(virtual_nodes: usize) -> Self {
+        Self {
+            shards: Vec::new(),
+            ring: BTreeMap::new(),
+            virtual_nodes: virtual_nodes.max(1),
+        }
+    }
+
+    /// Register a shard under a unique name
+    ///
+    /// The name (not the registration order) determines ring placement, so
+    /// routing is stable across restarts as long as shard names are.
+    pub fn add_shard(
+        &mut self,
+        name: impl Into<String>,
+        backend: Box<dyn ShardBackend>,
+    ) -> Result<()> {
+        let name = name.into();
+        if self.shards.iter().any(|(n, _)| *n == name) {
+            return Err(Error::InvalidConfig(format!(
+                "Duplicate shard name: {}",
+                name
+            )));
+        }
+
+        let index = self.shards.len();
+        for replica in 0..self.virtual_nodes {
+            self.ring
+                .insert(hash_key(&format!("{}#{}", name, replica)), index);
+        }
+        self.shards.push((name, backend));
+        Ok(())
+    }
+
+    /// Number of registered shards
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Name of the shard that owns the given vector ID
+    pub fn shard_for(&self, id: &str) -> Result<&str> {
+        let index = self.route(id)?;
+        Ok(&self.shards[index].0)
+    }
+
+    fn route(&self, id: &str) -> Result<usize> {
+        let hash = hash_key(id);
+        self.ring
+            .range(hash..)
+            .next()
+            .or_else(|| self.ring.iter().next())
+            .map(|(_, &index)| index)
+            .ok_or_else(|| Error::InvalidConfig("No shards registered".to_string()))
+    }
+
+    /// Insert or update a vector on its owning shard
+    pub fn upsert(&self, id: &str, vector: &[f32], metadata: Option<Value>) -> Result<()> {
+        let index = self.route(id)?;
+        self.shards[index].1.upsert(id, vector, metadata)
+    }
+
+    /// Delete a vector from its owning shard
+    pub fn delete(&self, id: &str) -> Result<bool> {
+        let index = self.route(id)?;
+        self.shards[index].1.delete(id)
+    }
+
+    /// Retrieve a vector from its owning shard
+    pub fn get(&self, id: &str) -> Result<Option<(Vec<f32>, Option<Value>)>> {
+        let index = self.route(id)?;
+        self.shards[index].1.get(id)
+    }
+
+    /// Search every shard and merge the results into a global top-k
+    ///
+    /// Shards that return an error are skipped and reported in
+    /// `failed_shards`. An error is returned only if no shard answered.
+    pub fn search(
+        &self,
+        query: &[f32],
+        k: usize,
+        filter: Option<&Filter>,
+    ) -> Result<ShardedSearchResult> {
+        if self.shards.is_empty() {
+            return Err(Error::InvalidConfig("No shards registered".to_string()));
+        }
+
+        let mut merged = Vec::new();
+        let mut failed_shards = Vec::new();
+        let mut last_error = None;
+
+        for (name, backend) in &self.shards {
+            match backend.search(query, k, filter) {
+                Ok(results) => merged.extend(results),
+                // An empty shard has nothing to contribute but is healthy
+                Err(Error::EmptyIndex) => {}
+                Err(e) => {
+                    warn!("Shard {} failed during search: {}", name, e);
+                    failed_shards.push(name.clone());
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        if failed_shards.len() == self.shards.len() {
+            if let Some(e) = last_error {
+                return Err(e);
+            }
+        }
+
+        merged.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+        merged.truncate(k);
+
+        Ok(ShardedSearchResult {
+            results: merged,
+            failed_shards,
+        })
+    }
+}
+
+/// 64-bit FNV-1a, chosen for a hash that is stable across builds and platforms
+fn hash_key(key: &str) -> u64 {
+    const OFFSET: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+    let mut hash = OFFSET;
+    for byte in key.as_bytes() {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(PRIME);
+    }
+    // FNV clusters similar short keys; a final avalanche spreads them over the ring
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51afd7ed558ccd);
+    hash ^= hash >> 33;
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::Config;
+
+    fn in_memory_shard(db: &Database, name: &str) -> Box<dyn ShardBackend> {
+        db.create_collection(
+            name,
+            Config {
+                dimensions: 4,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        Box::new(db.get_collection(name).unwrap())
+    }
+
+    struct DownShard;
+
+    impl ShardBackend for DownShard {
+        fn upsert(&self, _: &str, _: &[f32], _: Option<Value>) -> Result<()> {
+            Err(Error::Storage("shard unreachable".into()))
+        }
+        fn delete(&self, _: &str) -> Result<bool> {
+            Err(Error::Storage("shard unreachable".into()))
+        }
+        fn get(&self, _: &str) -> Result<Option<(Vec<f32>, Option<Value>)>> {
+            Err(Error::Storage("shard unreachable".into()))
+        }
+        fn search(
+            &self,
+            _: &[f32],
+            _: usize,
+            _: Option<&Filter>,
+        ) -> Result<Vec<(VectorId, f32, Option<Value>)>> {
+            Err(Error::Storage("shard unreachable".into()))
+        }
+    }
+
+    #[test]
+    fn test_routing_and_merged_search() {
+        let db = Database::new();
+        let mut client = ShardedClient::new();
+        client.add_shard("a", in_memory_shard(&db, "a")).unwrap();
+        client.add_shard("b", in_memory_shard(&db, "b")).unwrap();
+
+        for i in 0..40 {
+            let v = [1.0, i as f32 * 0.1, 0.0, 0.0];
+            client.upsert(&format!("vec{}", i), &v, None).unwrap();
+        }
+
+        // Routing is deterministic and each vector lives only on its owner
+        let a = db.get_collection("a").unwrap();
+        let b = db.get_collection("b").unwrap();
+        for i in 0..40 {
+            let id = format!("vec{}", i);
+            let owner = client.shard_for(&id).unwrap();
+            assert_eq!(owner, client.shard_for(&id).unwrap());
+            let (own, other) = if owner == "a" { (&a, &b) } else { (&b, &a) };
+            assert!(own.get(&id).unwrap().is_some());
+            assert!(other.get(&id).unwrap().is_none());
+        }
+        assert!(!a.is_empty() && !b.is_empty());
+
+        // The merged top-k equals the global top-k
+        let query = [1.0, 0.0, 0.0, 0.0];
+        let result = client.search(&query, 5, None).unwrap();
+        assert!(result.is_complete());
+        assert_eq!(result.results.len(), 5);
+        assert_eq!(result.results[0].0.as_str(), "vec0");
+        assert!(result.results.windows(2).all(|w| w[0].1 <= w[1].1));
+    }
+
+    #[test]
+    fn test_partial_results_when_shard_down() {
+        let db = Database::new();
+        let mut client = ShardedClient::new();
+        client.add_shard("up", in_memory_shard(&db, "up")).unwrap();
+        client.add_shard("down", Box::new(DownShard)).unwrap();
+
+        let up = db.get_collection("up").unwrap();
+        up.upsert("v1".to_string(), &[1.0, 0.0, 0.0, 0.0], None)
+            .unwrap();
+
+        let result = client.search(&[1.0, 0.0, 0.0, 0.0], 3, None).unwrap();
+        assert_eq!(result.failed_shards, vec!["down".to_string()]);
+        assert_eq!(result.results.len(), 1);
+    }
+}
@@ -0,0 +1,181 @@
+//! Periodic snapshot shipping for warm standbys
+//!
+//! `SnapshotShipper` copies a persistent database's latest snapshot chain
+//! (the full snapshot and the deltas written on top of it) to a
+//! [`SnapshotDestination`], so a standby can be bootstrapped from a recent
+//! snapshot and then catch up from streamed WAL.
+//!
+//! Destinations implement [`SnapshotDestination`]. A local directory is
+//! supported out of the box; object stores can be plugged in by wrapping
+//! their client.
+
+use crate::error::Result;
+use crate::persistent::PersistentVectorDb;
+use crate::snapshot::SnapshotManager;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// Somewhere shipped snapshot files are stored
+///
+/// Files are addressed by name (`snapshot_<id>.snap` or `.delta`) and never
+/// change once written, so a name already present need not be sent again.
+pub trait SnapshotDestination: Send + Sync {
+    /// Store the file at `source` under `name`, replacing any file of that name
+    ///
+    /// The file must not be visible under `name` until fully written.
+    fn put(&self, name: &str, source: &Path) -> Result<()>;
+
+    /// Names of the files stored so far
+    fn list(&self) -> Result<Vec<String>>;
+
+    /// Delete a stored file
+    fn remove(&self, name: &str) -> Result<()>;
+}
+
+/// Ships snapshots into a local directory, laid out as a data directory
+///
+/// Files land in `<dir>/snapshots`, so `dir` can be opened directly with
+/// `PersistentVectorDb::open` to bring up the standby.
+#[derive(Debug, Clone)]
+pub struct LocalDestination {
+    dir: PathBuf,
+}
+
+impl LocalDestination {
+    /// Ship into the data directory `dir`, creating it if needed
+    pub fn new(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref().join("snapshots");
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// Directory the snapshot files are written to
+    pub fn snapshot_dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+impl SnapshotDestination for LocalDestination {
+    fn put(&self, name: &str, source: &Path) -> Result<()> {
+        let path = self.dir.join(name);
+        let tmp = self.dir.join(format!("{}.tmp", name));
+        fs::copy(source, &tmp)?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    fn list(&self) -> Result<Vec<String>> {
+        let mut names = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let name = entry?.file_name().to_string_lossy().into_owned();
+            if !name.ends_with(".tmp") {
+                names.push(name);
+            }
+        }
+        Ok(names)
+    }
+
+    fn remove(&self, name: &str) -> Result<()> {
+        fs::remove_file(self.dir.join(name))?;
+        Ok(())
+    }
+}
+
+/// Copies a database's latest snapshot chain to a destination on an interval
+///
+/// The shipper does not run on its own; callers advance it with
+/// `maybe_ship` on whatever schedule suits them (the server uses a
+/// background task). It only reads files already written by checkpoints.
+pub struct SnapshotShipper {
+    destination: Box<dyn SnapshotDestination>,
+    interval: Duration,
+    last_shipped: Option<Instant>,
+}
+
+impl SnapshotShipper {
+    /// Ship to `destination` at most once per `interval`
+    pub fn new(destination: impl SnapshotDestination + 'static, interval: Duration) -> Self {
+        Self {
+            destination: Box::new(destination),
+            interval,
+            last_shipped: None,
+        }
+    }
+
+    /// Check whether the interval has passed since the last shipment
+    pub fn is_due(&self) -> bool {
+        self.last_shipped
+            .is_none_or(|last| last.elapsed() >= self.interval)
+    }
+
+    /// Ship `db`'s latest snapshot chain if due, returning how many files
+    /// were copied, or `None` if it wasn't due yet
+    pub fn maybe_ship(&mut self, db: &PersistentVectorDb) -> Result<Option<usize>> {
+        if !self.is_due() {
+            return Ok(None);
+        }
+        self.ship(db).map(Some)
+    }
+
+    /// Ship `db`'s latest snapshot chain now, returning how many files were
+    /// copied
+    ///
+    /// The snapshot goes first and its deltas follow in order, so the
+    /// destination always holds a loadable chain. Files of older chains are
+    /// removed once the new one is complete.
+    pub fn ship(&mut self, db: &PersistentVectorDb) -> Result<usize> {
+        let copied = ship_chain(self.destination.as_ref(), db.snapshot_dir())?;
+        self.last_shipped = Some(Instant::now());
+        Ok(copied)
+    }
+}
+
+/// Copy the latest snapshot chain in `snapshot_dir` to `destination`,
+/// returning how many files were copied
+fn ship_chain(destination: &dyn SnapshotDestination, snapshot_dir: &Path) -> Result<usize> {
+    let manager = SnapshotManager::new(snapshot_dir)?;
+    let Some((base_id, base)) = manager.list_snapshots()?.pop() else {
+        return Ok(0);
+    };
+    let chain: Vec<PathBuf> = std::iter::once(base)
+        .chain(
+            manager
+                .list_deltas()?
+                .into_iter()
+                .filter(|(id, _)| *id > base_id)
+                .map(|(_, path)| path),
+        )
+        .collect();
+
+    let shipped: HashSet<String> = destination.list()?.into_iter().collect();
+    let mut keep = HashSet::new();
+    let mut copied = 0;
+    for path in &chain {
+        let name = file_name(path);
+        if !shipped.contains(&name) {
+            destination.put(&name, path)?;
+            copied += 1;
+        }
+        keep.insert(name);
+    }
+    for name in shipped.difference(&keep) {
+        destination.remove(name)?;
+    }
+
+    debug!(
+        "Shipped snapshot chain {} ({} of {} files copied)",
+        base_id,
+        copied,
+        chain.len()
+    );
+    Ok(copied)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
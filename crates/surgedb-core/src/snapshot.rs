@@ -2,22 +2,44 @@
 //!
 //! Snapshots contain the complete database state at a point in time.
 //! Combined with WAL, they enable fast recovery without replaying the entire history.
+//!
+//! A snapshot can be followed by a chain of deltas, each holding only the
+//! slots and graph nodes that changed since the one before it. Loading folds
+//! the chain into its base; [`SnapshotManager::collapse`] writes the result
+//! as a new base.
 
 use crate::error::{Error, Result};
-use crate::hnsw::HnswState;
-use crate::types::VectorId;
+use crate::hnsw::{HnswNode, HnswState};
+use crate::types::{InternalId, VectorId};
 use bincode::{deserialize_from, serialize_into};
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::Value;
-use std::fs::{self, File};
-use std::io::{BufReader, BufWriter};
-use std::path::{Path, PathBuf};
+use std::borrow::Cow;
+use std::io::{Read, Write};
+use tracing::warn;
+#[cfg(feature = "persistence")]
+use {
+    std::fs::{self, File},
+    std::io::{BufReader, BufWriter},
+    std::path::{Path, PathBuf},
+};
 
 /// Magic bytes for snapshot files
 const SNAPSHOT_MAGIC: &[u8; 4] = b"ZSNP";
 
+/// Magic bytes for snapshot delta files
+const DELTA_MAGIC: &[u8; 4] = b"ZSND";
+
 /// Snapshot format version
-const SNAPSHOT_VERSION: u8 = 2;
+const SNAPSHOT_VERSION: u8 = 5;
+
+/// Snapshot delta format version
+const DELTA_VERSION: u8 = 1;
+
+/// Oldest snapshot format that can still be loaded (version 2 has no aliases,
+/// version 3 stores the graph without a checksum, version 4 has no deleted
+/// slots)
+const MIN_SNAPSHOT_VERSION: u8 = 2;
 
 /// Stored vector data
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,6 +63,13 @@ pub struct Snapshot {
     pub vectors: Vec<StoredVector>,
     /// HNSW index state
     pub hnsw_state: Option<HnswState>,
+    /// Vector ID aliases as `(alias, primary ID)`
+    pub aliases: Vec<(VectorId, VectorId)>,
+    /// Positions in `vectors` that are deleted or superseded
+    ///
+    /// Only positional snapshots, which keep every slot so the graph's node
+    /// IDs stay valid, list any; others leave dead slots out instead.
+    pub deleted: Vec<InternalId>,
 }
 
 impl Snapshot {
@@ -52,6 +81,8 @@ impl Snapshot {
             dimensions,
             vectors: Vec::new(),
             hnsw_state: None,
+            aliases: Vec::new(),
+            deleted: Vec::new(),
         }
     }
 
@@ -78,96 +109,99 @@ impl Snapshot {
     pub fn is_empty(&self) -> bool {
         self.vectors.is_empty()
     }
-}
-
-/// Snapshot file header
-#[derive(Debug, Serialize, Deserialize)]
-struct SnapshotHeader {
-    magic: [u8; 4],
-    version: u8,
-    id: u64,
-    wal_seq: u64,
-    dimensions: usize,
-    vector_count: usize,
-}
-
-/// Snapshot manager
-pub struct SnapshotManager {
-    dir: PathBuf,
-    /// Keep this many snapshots
-    retain_count: usize,
-}
 
-impl SnapshotManager {
-    /// Create a new snapshot manager
-    pub fn new(dir: impl AsRef<Path>) -> Result<Self> {
-        let dir = dir.as_ref().to_path_buf();
-        fs::create_dir_all(&dir)?;
+    /// Fold a delta written on top of this snapshot into it
+    ///
+    /// Fails with `Error::Storage` if the delta doesn't follow this snapshot.
+    /// If either side has no usable graph the result has none either, so
+    /// recovery rebuilds the index.
+    pub fn apply(&mut self, delta: SnapshotDelta) -> Result<()> {
+        if delta.parent != self.id || delta.first_slot != self.vectors.len() {
+            return Err(Error::Storage(format!(
+                "Snapshot delta {} doesn't follow snapshot {} ({} slots)",
+                delta.id,
+                self.id,
+                self.vectors.len()
+            )));
+        }
 
-        Ok(Self {
-            dir,
-            retain_count: 3,
-        })
-    }
+        for (slot, metadata) in delta.metadata {
+            if let Some(stored) = self.vectors.get_mut(slot.as_usize()) {
+                stored.metadata = metadata;
+            }
+        }
+        self.vectors.extend(delta.vectors);
+        for &slot in &delta.deleted {
+            if let Some(stored) = self.vectors.get_mut(slot.as_usize()) {
+                stored.metadata = None;
+            }
+        }
+        self.deleted.extend(delta.deleted);
+
+        self.hnsw_state = match (self.hnsw_state.take(), delta.graph) {
+            (Some(mut state), Some(graph)) => {
+                let mut linked = true;
+                for node in graph.nodes {
+                    let slot = node.id.as_usize();
+                    match slot.cmp(&state.nodes.len()) {
+                        std::cmp::Ordering::Less => state.nodes[slot] = node,
+                        std::cmp::Ordering::Equal => state.nodes.push(node),
+                        std::cmp::Ordering::Greater => linked = false,
+                    }
+                }
+                state.entry_point = graph.entry_point;
+                state.max_layer = graph.max_layer;
+                linked.then_some(state)
+            }
+            _ => None,
+        };
 
-    /// Set how many snapshots to retain
-    pub fn set_retain_count(&mut self, count: usize) {
-        self.retain_count = count.max(1);
+        self.id = delta.id;
+        self.wal_seq = delta.wal_seq;
+        self.aliases = delta.aliases;
+        Ok(())
     }
 
-    /// Save a snapshot to disk
-    pub fn save(&self, snapshot: &Snapshot) -> Result<PathBuf> {
-        let filename = format!("snapshot_{:016}.snap", snapshot.id);
-        let path = self.dir.join(&filename);
-
-        let file = File::create(&path)?;
-        let mut writer = BufWriter::new(file);
-
+    /// Write the snapshot in the on-disk snapshot format
+    pub fn write_to(&self, mut writer: impl Write) -> Result<()> {
         // Write header
         let header = SnapshotHeader {
             magic: *SNAPSHOT_MAGIC,
             version: SNAPSHOT_VERSION,
-            id: snapshot.id,
-            wal_seq: snapshot.wal_seq,
-            dimensions: snapshot.dimensions,
-            vector_count: snapshot.vectors.len(),
+            id: self.id,
+            wal_seq: self.wal_seq,
+            dimensions: self.dimensions,
+            vector_count: self.vectors.len(),
         };
 
         serialize_into(&mut writer, &header).map_err(|e| Error::Storage(e.to_string()))?;
 
         // Write HNSW state
-        serialize_into(&mut writer, &snapshot.hnsw_state)
-            .map_err(|e| Error::Storage(e.to_string()))?;
+        let graph = self
+            .hnsw_state
+            .as_ref()
+            .map(GraphBlob::encode)
+            .transpose()?;
+        serialize_into(&mut writer, &graph).map_err(|e| Error::Storage(e.to_string()))?;
 
         // Write vectors in batches for efficiency
         const BATCH_SIZE: usize = 1000;
-        for chunk in snapshot.vectors.chunks(BATCH_SIZE) {
+        for chunk in self.vectors.chunks(BATCH_SIZE) {
             serialize_into(&mut writer, &chunk.to_vec())
                 .map_err(|e| Error::Storage(e.to_string()))?;
         }
 
-        // Cleanup old snapshots
-        self.cleanup()?;
-
-        Ok(path)
-    }
-
-    /// Load the latest snapshot
-    pub fn load_latest(&self) -> Result<Option<Snapshot>> {
-        let snapshots = self.list_snapshots()?;
+        serialize_into(&mut writer, &self.aliases).map_err(|e| Error::Storage(e.to_string()))?;
+        serialize_into(&mut writer, &self.deleted).map_err(|e| Error::Storage(e.to_string()))?;
+        writer.flush()?;
 
-        if let Some((_, path)) = snapshots.last() {
-            self.load(path).map(Some)
-        } else {
-            Ok(None)
-        }
+        Ok(())
     }
 
-    /// Load a specific snapshot
-    pub fn load(&self, path: &Path) -> Result<Snapshot> {
-        let file = File::open(path)?;
-        let mut reader = BufReader::new(file);
-
+    /// Read a snapshot written by [`Snapshot::write_to`]
+    ///
+    /// `source` names where the bytes came from and only shows up in warnings.
+    pub fn read_from(mut reader: impl Read, source: &str) -> Result<Self> {
         // Read header
         let header: SnapshotHeader =
             deserialize_from(&mut reader).map_err(|e| Error::Storage(e.to_string()))?;
@@ -177,7 +211,7 @@ impl SnapshotManager {
             return Err(Error::Storage("Invalid snapshot magic bytes".into()));
         }
 
-        if header.version != SNAPSHOT_VERSION {
+        if !(MIN_SNAPSHOT_VERSION..=SNAPSHOT_VERSION).contains(&header.version) {
             return Err(Error::Storage(format!(
                 "Unsupported snapshot version: {}",
                 header.version
@@ -185,8 +219,13 @@ impl SnapshotManager {
         }
 
         // Read HNSW state
-        let hnsw_state: Option<HnswState> =
-            deserialize_from(&mut reader).map_err(|e| Error::Storage(e.to_string()))?;
+        let hnsw_state: Option<HnswState> = if header.version >= 4 {
+            let graph: Option<GraphBlob> =
+                deserialize_from(&mut reader).map_err(|e| Error::Storage(e.to_string()))?;
+            graph.and_then(|graph| graph.decode(source))
+        } else {
+            deserialize_from(&mut reader).map_err(|e| Error::Storage(e.to_string()))?
+        };
 
         // Read vectors
         let mut vectors = Vec::with_capacity(header.vector_count);
@@ -199,17 +238,347 @@ impl SnapshotManager {
             vectors.extend(batch);
         }
 
+        let aliases = if header.version >= 3 {
+            deserialize_from(&mut reader).map_err(|e| Error::Storage(e.to_string()))?
+        } else {
+            Vec::new()
+        };
+        let deleted = if header.version >= 5 {
+            deserialize_from(&mut reader).map_err(|e| Error::Storage(e.to_string()))?
+        } else {
+            Vec::new()
+        };
+
         Ok(Snapshot {
             id: header.id,
             wal_seq: header.wal_seq,
             dimensions: header.dimensions,
             vectors,
             hnsw_state,
+            aliases,
+            deleted,
+        })
+    }
+}
+
+/// What changed between a snapshot (or delta) and the next checkpoint
+///
+/// Slots are positional, as in the live storage: slots from `first_slot` on
+/// are new and carried whole, while earlier slots only carry replaced
+/// metadata or their deletion. Graph nodes that were added or relinked are
+/// carried whole.
+#[derive(Debug, Clone)]
+pub struct SnapshotDelta {
+    /// Delta ID, greater than `parent`
+    pub id: u64,
+    /// ID of the snapshot or delta this one follows
+    pub parent: u64,
+    /// WAL sequence number at snapshot time
+    pub wal_seq: u64,
+    /// Database dimensions
+    pub dimensions: usize,
+    /// Number of slots in the parent
+    pub first_slot: usize,
+    /// Slots appended since the parent, starting at `first_slot`
+    pub vectors: Vec<StoredVector>,
+    /// Earlier slots whose metadata was replaced in place
+    pub metadata: Vec<(InternalId, Option<Value>)>,
+    /// Slots deleted or superseded since the parent
+    pub deleted: Vec<InternalId>,
+    /// Changed graph nodes, or `None` if the graph is to be rebuilt
+    pub graph: Option<GraphDelta>,
+    /// Vector ID aliases as `(alias, primary ID)`
+    pub aliases: Vec<(VectorId, VectorId)>,
+}
+
+/// Graph nodes added or relinked since the parent, with the graph's new
+/// entry point
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GraphDelta {
+    pub nodes: Vec<HnswNode>,
+    pub entry_point: Option<InternalId>,
+    pub max_layer: usize,
+}
+
+/// On-disk layout of a delta after its magic bytes and version
+#[derive(Serialize, Deserialize)]
+struct DeltaRecord<'a> {
+    id: u64,
+    parent: u64,
+    wal_seq: u64,
+    dimensions: usize,
+    first_slot: usize,
+    vectors: Cow<'a, [StoredVector]>,
+    /// Metadata as JSON text, like `StoredVector::metadata`
+    metadata: Vec<(InternalId, Option<String>)>,
+    deleted: Cow<'a, [InternalId]>,
+    aliases: Cow<'a, [(VectorId, VectorId)]>,
+    graph: Option<GraphBlob>,
+}
+
+impl SnapshotDelta {
+    /// Write the delta in the on-disk delta format, checksumming the graph
+    /// like a snapshot's
+    pub fn write_to(&self, mut writer: impl Write) -> Result<()> {
+        let record = DeltaRecord {
+            id: self.id,
+            parent: self.parent,
+            wal_seq: self.wal_seq,
+            dimensions: self.dimensions,
+            first_slot: self.first_slot,
+            vectors: Cow::Borrowed(&self.vectors),
+            metadata: self
+                .metadata
+                .iter()
+                .map(|(slot, metadata)| (*slot, metadata.as_ref().map(Value::to_string)))
+                .collect(),
+            deleted: Cow::Borrowed(&self.deleted),
+            aliases: Cow::Borrowed(&self.aliases),
+            graph: self.graph.as_ref().map(GraphBlob::encode).transpose()?,
+        };
+        serialize_into(&mut writer, &(DELTA_MAGIC, DELTA_VERSION, record))
+            .map_err(|e| Error::Storage(e.to_string()))?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Read a delta written by [`SnapshotDelta::write_to`]
+    ///
+    /// `source` names where the bytes came from and only shows up in warnings.
+    pub fn read_from(mut reader: impl Read, source: &str) -> Result<Self> {
+        let (magic, version): ([u8; 4], u8) =
+            deserialize_from(&mut reader).map_err(|e| Error::Storage(e.to_string()))?;
+        if magic != *DELTA_MAGIC {
+            return Err(Error::Storage("Invalid snapshot delta magic bytes".into()));
+        }
+        if version != DELTA_VERSION {
+            return Err(Error::Storage(format!(
+                "Unsupported snapshot delta version: {}",
+                version
+            )));
+        }
+
+        let record: DeltaRecord<'static> =
+            deserialize_from(&mut reader).map_err(|e| Error::Storage(e.to_string()))?;
+        let metadata = record
+            .metadata
+            .into_iter()
+            .map(|(slot, json)| {
+                let metadata = json
+                    .map(|json| serde_json::from_str(&json))
+                    .transpose()
+                    .map_err(|e| Error::Storage(e.to_string()))?;
+                Ok((slot, metadata))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            id: record.id,
+            parent: record.parent,
+            wal_seq: record.wal_seq,
+            dimensions: record.dimensions,
+            first_slot: record.first_slot,
+            vectors: record.vectors.into_owned(),
+            metadata,
+            deleted: record.deleted.into_owned(),
+            graph: record.graph.and_then(|graph| graph.decode(source)),
+            aliases: record.aliases.into_owned(),
+        })
+    }
+}
+
+/// Snapshot file header
+#[derive(Debug, Serialize, Deserialize)]
+struct SnapshotHeader {
+    magic: [u8; 4],
+    version: u8,
+    id: u64,
+    wal_seq: u64,
+    dimensions: usize,
+    vector_count: usize,
+}
+
+/// Serialized HNSW graph with a checksum, written from version 4 on
+///
+/// A graph that fails its checksum or doesn't decode is dropped on load, so
+/// recovery rebuilds the index from the vectors instead of trusting it.
+#[derive(Debug, Serialize, Deserialize)]
+struct GraphBlob {
+    checksum: u32,
+    bytes: Vec<u8>,
+}
+
+impl GraphBlob {
+    fn encode(state: &impl Serialize) -> Result<Self> {
+        let bytes = bincode::serialize(state).map_err(|e| Error::Storage(e.to_string()))?;
+        Ok(Self {
+            checksum: crc32(&bytes),
+            bytes,
+        })
+    }
+
+    fn decode<T: DeserializeOwned>(self, source: &str) -> Option<T> {
+        if crc32(&self.bytes) != self.checksum {
+            warn!(
+                "HNSW graph in {} fails its checksum, rebuilding the index",
+                source
+            );
+            return None;
+        }
+        match bincode::deserialize(&self.bytes) {
+            Ok(state) => Some(state),
+            Err(e) => {
+                warn!(
+                    "HNSW graph in {} doesn't decode ({}), rebuilding the index",
+                    source, e
+                );
+                None
+            }
+        }
+    }
+}
+
+/// Simple CRC32 implementation (IEEE polynomial)
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    let mut crc: u32 = 0xFFFFFFFF;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB88320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// Snapshot manager
+#[cfg(feature = "persistence")]
+pub struct SnapshotManager {
+    dir: PathBuf,
+    /// Keep this many snapshots
+    retain_count: usize,
+}
+
+#[cfg(feature = "persistence")]
+impl SnapshotManager {
+    /// Create a new snapshot manager
+    pub fn new(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+
+        Ok(Self {
+            dir,
+            retain_count: 3,
         })
     }
 
+    /// Set how many snapshots to retain
+    pub fn set_retain_count(&mut self, count: usize) {
+        self.retain_count = count.max(1);
+    }
+
+    /// Save a snapshot to disk
+    pub fn save(&self, snapshot: &Snapshot) -> Result<PathBuf> {
+        let filename = format!("snapshot_{:016}.snap", snapshot.id);
+        let path = self.dir.join(&filename);
+
+        snapshot.write_to(BufWriter::new(File::create(&path)?))?;
+
+        // Cleanup old snapshots
+        self.cleanup()?;
+
+        Ok(path)
+    }
+
+    /// Save a delta on top of the latest snapshot
+    ///
+    /// The file only appears once fully written, so a crash mid-write
+    /// leaves the chain as it was.
+    pub fn save_delta(&self, delta: &SnapshotDelta) -> Result<PathBuf> {
+        let path = self.dir.join(format!("snapshot_{:016}.delta", delta.id));
+        let tmp = path.with_extension("delta.tmp");
+        delta.write_to(BufWriter::new(File::create(&tmp)?))?;
+        fs::rename(&tmp, &path)?;
+        Ok(path)
+    }
+
+    /// Load the latest snapshot, with the chain of deltas written on top of
+    /// it folded in
+    pub fn load_latest(&self) -> Result<Option<Snapshot>> {
+        Ok(self.load_chain()?.map(|(snapshot, _)| snapshot))
+    }
+
+    /// Load the latest snapshot with its deltas folded in, and how many
+    /// deltas that took
+    ///
+    /// Fails if a delta is unreadable or doesn't follow the one before it,
+    /// since the WAL no longer holds the writes it covers.
+    pub fn load_chain(&self) -> Result<Option<(Snapshot, usize)>> {
+        let Some((base_id, path)) = self.list_snapshots()?.pop() else {
+            return Ok(None);
+        };
+        let mut snapshot = self.load(&path)?;
+
+        let deltas: Vec<PathBuf> = self
+            .list_deltas()?
+            .into_iter()
+            .filter(|(id, _)| *id > base_id)
+            .map(|(_, path)| path)
+            .collect();
+        for path in &deltas {
+            let delta = SnapshotDelta::read_from(
+                BufReader::new(File::open(path)?),
+                &path.display().to_string(),
+            )?;
+            snapshot.apply(delta)?;
+        }
+        Ok(Some((snapshot, deltas.len())))
+    }
+
+    /// Fold the latest snapshot's deltas into a new snapshot and delete
+    /// them, returning its path, or `None` if there were no deltas
+    ///
+    /// The new snapshot takes the last delta's ID and is written before any
+    /// delta is removed, so the chain loads the same after a crash.
+    pub fn collapse(&self) -> Result<Option<PathBuf>> {
+        let Some((snapshot, deltas)) = self.load_chain()? else {
+            return Ok(None);
+        };
+        if deltas == 0 {
+            return Ok(None);
+        }
+
+        let path = self.dir.join(format!("snapshot_{:016}.snap", snapshot.id));
+        let tmp = path.with_extension("snap.tmp");
+        snapshot.write_to(BufWriter::new(File::create(&tmp)?))?;
+        fs::rename(&tmp, &path)?;
+        self.cleanup()?;
+        Ok(Some(path))
+    }
+
+    /// Load a specific snapshot
+    pub fn load(&self, path: &Path) -> Result<Snapshot> {
+        Snapshot::read_from(
+            BufReader::new(File::open(path)?),
+            &path.display().to_string(),
+        )
+    }
+
     /// List all snapshots sorted by ID
     pub fn list_snapshots(&self) -> Result<Vec<(u64, PathBuf)>> {
+        self.list_files(".snap")
+    }
+
+    /// List all snapshot deltas sorted by ID
+    pub fn list_deltas(&self) -> Result<Vec<(u64, PathBuf)>> {
+        self.list_files(".delta")
+    }
+
+    /// List `snapshot_<id><suffix>` files sorted by ID
+    fn list_files(&self, suffix: &str) -> Result<Vec<(u64, PathBuf)>> {
         let mut snapshots = Vec::new();
 
         for entry in fs::read_dir(&self.dir)? {
@@ -217,15 +586,13 @@ impl SnapshotManager {
             let path = entry.path();
 
             if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                if name.starts_with("snapshot_") && name.ends_with(".snap") {
-                    // Parse ID from filename
-                    if let Some(id_str) = name
-                        .strip_prefix("snapshot_")
-                        .and_then(|s| s.strip_suffix(".snap"))
-                    {
-                        if let Ok(id) = id_str.parse::<u64>() {
-                            snapshots.push((id, path));
-                        }
+                // Parse ID from filename
+                if let Some(id_str) = name
+                    .strip_prefix("snapshot_")
+                    .and_then(|s| s.strip_suffix(suffix))
+                {
+                    if let Ok(id) = id_str.parse::<u64>() {
+                        snapshots.push((id, path));
                     }
                 }
             }
@@ -237,16 +604,27 @@ impl SnapshotManager {
 
     /// Delete old snapshots, keeping only retain_count
     fn cleanup(&self) -> Result<()> {
-        let snapshots = self.list_snapshots()?;
+        self.prune(self.retain_count).map(drop)
+    }
 
-        if snapshots.len() > self.retain_count {
-            let to_delete = snapshots.len() - self.retain_count;
-            for (_, path) in snapshots.into_iter().take(to_delete) {
+    /// Delete all but the `keep` newest snapshots, returning how many were
+    /// removed
+    ///
+    /// Deltas written before the newest snapshot are deleted as well, since
+    /// it supersedes them.
+    pub fn prune(&self, keep: usize) -> Result<usize> {
+        let snapshots = self.list_snapshots()?;
+        let to_delete = snapshots.len().saturating_sub(keep);
+        let latest = snapshots.last().map_or(0, |(id, _)| *id);
+        for (_, path) in snapshots.into_iter().take(to_delete) {
+            fs::remove_file(path)?;
+        }
+        for (id, path) in self.list_deltas()? {
+            if id <= latest {
                 fs::remove_file(path)?;
             }
         }
-
-        Ok(())
+        Ok(to_delete)
     }
 
     /// Get snapshot directory
@@ -255,7 +633,7 @@ impl SnapshotManager {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "persistence"))]
 mod tests {
     use super::*;
     use tempfile::tempdir;
@@ -352,4 +730,80 @@ mod tests {
         assert_eq!(loaded.vectors.len(), 5000);
         assert_eq!(loaded.vectors[4999].id.as_str(), "v4999");
     }
+
+    #[test]
+    fn test_graph_checksum_rejects_corruption() {
+        let state = HnswState {
+            nodes: Vec::new(),
+            entry_point: Some(7.into()),
+            max_layer: 3,
+        };
+        let path = "snapshot_test.snap";
+
+        let decoded: HnswState = GraphBlob::encode(&state).unwrap().decode(path).unwrap();
+        assert_eq!(decoded.entry_point, state.entry_point);
+        assert_eq!(decoded.max_layer, 3);
+
+        let mut corrupt = GraphBlob::encode(&state).unwrap();
+        let last = corrupt.bytes.len() - 1;
+        corrupt.bytes[last] ^= 0xFF;
+        assert!(corrupt.decode::<HnswState>(path).is_none());
+    }
+
+    #[test]
+    fn test_delta_chain_loads_and_collapses() {
+        let dir = tempdir().unwrap();
+        let manager = SnapshotManager::new(dir.path()).unwrap();
+
+        let mut base = Snapshot::new(1, 10, 2);
+        base.add_vector("v0".into(), vec![0.0, 0.0], None);
+        base.add_vector("v1".into(), vec![1.0, 1.0], None);
+        manager.save(&base).unwrap();
+
+        let delta = |id, parent, first_slot| SnapshotDelta {
+            id,
+            parent,
+            wal_seq: id * 10,
+            dimensions: 2,
+            first_slot,
+            vectors: vec![StoredVector {
+                id: format!("v{}", first_slot).into(),
+                vector: vec![first_slot as f32; 2],
+                metadata: None,
+            }],
+            metadata: vec![(0.into(), Some(serde_json::json!({"delta": id})))],
+            deleted: Vec::new(),
+            graph: None,
+            aliases: Vec::new(),
+        };
+        manager.save_delta(&delta(2, 1, 2)).unwrap();
+        let mut second = delta(3, 2, 3);
+        second.deleted.push(1.into());
+        manager.save_delta(&second).unwrap();
+
+        let (loaded, deltas) = manager.load_chain().unwrap().unwrap();
+        assert_eq!(deltas, 2);
+        assert_eq!((loaded.id, loaded.wal_seq), (3, 30));
+        assert_eq!(loaded.vectors.len(), 4);
+        assert_eq!(loaded.vectors[3].id.as_str(), "v3");
+        assert_eq!(
+            loaded.vectors[0].metadata,
+            Some(serde_json::json!({"delta": 3}))
+        );
+        assert_eq!(loaded.deleted, vec![InternalId::from(1)]);
+
+        // A delta that doesn't follow the chain is refused
+        manager.save_delta(&delta(4, 2, 4)).unwrap();
+        assert!(manager.load_chain().is_err());
+        std::fs::remove_file(dir.path().join("snapshot_0000000000000004.delta")).unwrap();
+
+        let collapsed = manager.collapse().unwrap().unwrap();
+        assert!(collapsed.ends_with("snapshot_0000000000000003.snap"));
+        assert!(manager.list_deltas().unwrap().is_empty());
+        let (reloaded, deltas) = manager.load_chain().unwrap().unwrap();
+        assert_eq!(deltas, 0);
+        assert_eq!(reloaded.vectors.len(), 4);
+        assert_eq!(reloaded.deleted, loaded.deleted);
+        assert!(manager.collapse().unwrap().is_none());
+    }
 }
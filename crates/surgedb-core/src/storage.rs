@@ -6,12 +6,13 @@ use crate::bitmap_index::BitmapIndex;
 use crate::distance::DistanceMetric;
 use crate::error::{Error, Result};
 use crate::filter::Filter;
+use crate::metadata::{MetadataFormat, MetadataStore};
 use crate::sync::RwLock;
 use crate::types::{InternalId, VectorId};
 use roaring::RoaringBitmap;
-use std::sync::Arc;
 use serde_json::Value;
 use std::collections::HashMap;
+use std::sync::Arc;
 
 /// Trait for vector storage backends
 pub trait VectorStorageTrait {
@@ -33,6 +34,13 @@ pub trait VectorStorageTrait {
         None
     }
 
+    /// Whether the vector's metadata matches `filter`; vectors without
+    /// metadata never match
+    fn matches_filter(&self, internal_id: InternalId, filter: &Filter) -> bool {
+        self.get_metadata(internal_id)
+            .is_some_and(|metadata| filter.matches(&metadata))
+    }
+
     /// Optional bitmap-accelerated filtering
     fn filter_bitmap(&self, _filter: &Filter) -> Option<Arc<RoaringBitmap>> {
         None
@@ -44,6 +52,16 @@ pub trait VectorStorageTrait {
     }
 }
 
+/// How `VectorStorage::insert_at` treats an internal ID beyond the next free slot
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SlotGaps {
+    /// Reject it, so slots must be assigned densely and in order
+    #[default]
+    Reject,
+    /// Fill the skipped slots with deleted placeholders
+    Tombstone,
+}
+
 /// In-memory vector storage with ID mapping
 pub struct VectorStorage {
     /// Dimensionality of stored vectors
@@ -59,13 +77,16 @@ pub struct VectorStorage {
     internal_to_id: RwLock<Vec<VectorId>>,
 
     /// Optional metadata for each vector
-    metadata: RwLock<HashMap<InternalId, Value>>,
+    metadata: RwLock<MetadataStore>,
 
-    /// Set of deleted internal IDs
-    deleted: RwLock<std::collections::HashSet<InternalId>>,
+    /// Tombstones: internal IDs of deleted or superseded slots
+    deleted: RwLock<RoaringBitmap>,
 
     /// Bitmap index for metadata filtering
     bitmap_index: RwLock<BitmapIndex>,
+
+    /// Gap handling for `insert_at`
+    slot_gaps: SlotGaps,
 }
 
 impl VectorStorage {
@@ -76,12 +97,30 @@ impl VectorStorage {
             vectors: RwLock::new(Vec::new()),
             id_to_internal: RwLock::new(HashMap::new()),
             internal_to_id: RwLock::new(Vec::new()),
-            metadata: RwLock::new(HashMap::new()),
-            deleted: RwLock::new(std::collections::HashSet::new()),
+            metadata: RwLock::new(MetadataStore::default()),
+            deleted: RwLock::new(RoaringBitmap::new()),
             bitmap_index: RwLock::new(BitmapIndex::new()),
+            slot_gaps: SlotGaps::default(),
         }
     }
 
+    /// Set how `insert_at` handles skipped internal IDs
+    pub fn set_slot_gaps(&mut self, slot_gaps: SlotGaps) {
+        self.slot_gaps = slot_gaps;
+    }
+
+    /// Set how metadata is held in memory, converting any already stored
+    pub fn set_metadata_format(&mut self, format: &MetadataFormat) {
+        let mut store = MetadataStore::new(format);
+        let mut metadata = self.metadata.write();
+        for internal_id in (0..self.internal_to_id.read().len()).map(InternalId::from) {
+            if let Some(meta) = metadata.remove(&internal_id) {
+                store.insert(internal_id, meta);
+            }
+        }
+        *metadata = store;
+    }
+
     /// Delete a vector by ID
     /// Returns true if the vector existed and was deleted
     pub fn delete(&self, id: &VectorId) -> Result<bool> {
@@ -90,7 +129,7 @@ impl VectorStorage {
         let mut id_to_internal = self.id_to_internal.write();
 
         if let Some(internal_id) = id_to_internal.remove(id) {
-            self.deleted.write().insert(internal_id);
+            self.deleted.write().insert(internal_id.as_u32());
             if let Some(meta) = self.metadata.write().remove(&internal_id) {
                 self.bitmap_index.write().remove(internal_id, &meta);
             }
@@ -100,6 +139,28 @@ impl VectorStorage {
         }
     }
 
+    /// Replace a vector's metadata in place, keeping its vector and slot (and
+    /// so its graph node); `null` clears it
+    ///
+    /// Fails with `VectorNotFound` if no vector is stored under `id`.
+    pub fn update_metadata(&self, id: &VectorId, metadata: Value) -> Result<()> {
+        let id_to_internal = self.id_to_internal.read();
+        let internal_id = *id_to_internal
+            .get(id)
+            .ok_or_else(|| Error::VectorNotFound(id.to_string()))?;
+
+        let mut metadata_store = self.metadata.write();
+        let mut bitmap_index = self.bitmap_index.write();
+        if let Some(old_meta) = metadata_store.remove(&internal_id) {
+            bitmap_index.remove(internal_id, &old_meta);
+        }
+        if !metadata.is_null() {
+            bitmap_index.index(internal_id, &metadata);
+            metadata_store.insert(internal_id, metadata);
+        }
+        Ok(())
+    }
+
     /// Insert a vector and return its internal ID
     pub fn insert(
         &self,
@@ -110,6 +171,69 @@ impl VectorStorage {
         self.insert_internal(id, vector, metadata, false)
     }
 
+    /// Insert a vector at a caller-chosen internal ID
+    ///
+    /// Lets a migration rebuild an identical slot layout (and so identical
+    /// mmap files) from another store. Slots are positional and never
+    /// overwritten: `internal_id` must not be assigned yet, and it must be the
+    /// next free slot unless gaps are allowed with `SlotGaps::Tombstone`, in
+    /// which case the skipped slots become deleted placeholders. Collisions
+    /// and rejected gaps fail with `Error::Storage`.
+    pub fn insert_at(
+        &self,
+        internal_id: InternalId,
+        id: VectorId,
+        vector: &[f32],
+        metadata: Option<Value>,
+    ) -> Result<()> {
+        if vector.len() != self.dimensions {
+            return Err(Error::DimensionMismatch {
+                expected: self.dimensions,
+                got: vector.len(),
+            });
+        }
+
+        let mut vectors = self.vectors.write();
+        let mut internal_to_id = self.internal_to_id.write();
+        let mut id_to_internal = self.id_to_internal.write();
+        let mut metadata_store = self.metadata.write();
+        let mut bitmap_index = self.bitmap_index.write();
+
+        let next = internal_to_id.len();
+        let slot = internal_id.as_usize();
+        if slot < next {
+            return Err(Error::Storage(format!(
+                "Internal ID {} is already assigned",
+                slot
+            )));
+        }
+        if slot > next && self.slot_gaps == SlotGaps::Reject {
+            return Err(Error::Storage(format!(
+                "Internal ID {} would leave a gap after {}",
+                slot, next
+            )));
+        }
+        if id_to_internal.contains_key(&id) {
+            return Err(Error::DuplicateId(id.to_string()));
+        }
+
+        if slot > next {
+            vectors.resize(slot * self.dimensions, 0.0);
+            internal_to_id.resize(slot, VectorId::from(""));
+            self.deleted.write().insert_range(next as u32..slot as u32);
+        }
+
+        vectors.extend_from_slice(vector);
+        id_to_internal.insert(id.clone(), internal_id);
+        internal_to_id.push(id);
+        if let Some(meta) = metadata {
+            bitmap_index.index(internal_id, &meta);
+            metadata_store.insert(internal_id, meta);
+        }
+
+        Ok(())
+    }
+
     /// Insert or update a vector (upsert)
     /// If the ID exists, it creates a new internal record and updates the mapping.
     /// The old internal record becomes inaccessible via ID lookup (stale).
@@ -168,7 +292,7 @@ impl VectorStorage {
 
         // Update mappings
         if let Some(old_internal_id) = id_to_internal.insert(id.clone(), internal_id) {
-            self.deleted.write().insert(old_internal_id);
+            self.deleted.write().insert(old_internal_id.as_u32());
             if let Some(old_meta) = metadata_store.remove(&old_internal_id) {
                 bitmap_index.remove(old_internal_id, &old_meta);
             }
@@ -184,6 +308,63 @@ impl VectorStorage {
         Ok(internal_id)
     }
 
+    /// Insert new vectors, returning their internal IDs in order
+    ///
+    /// Each lock is taken once for the whole batch. The batch is atomic: a
+    /// dimension mismatch, or an ID that repeats within the batch or already
+    /// exists, fails it before anything is written.
+    pub fn insert_batch(
+        &self,
+        items: Vec<(VectorId, &[f32], Option<Value>)>,
+    ) -> Result<Vec<InternalId>> {
+        if items.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        for (_, vector, _) in &items {
+            if vector.len() != self.dimensions {
+                return Err(Error::DimensionMismatch {
+                    expected: self.dimensions,
+                    got: vector.len(),
+                });
+            }
+        }
+
+        let mut vectors = self.vectors.write();
+        let mut internal_to_id = self.internal_to_id.write();
+        let mut id_to_internal = self.id_to_internal.write();
+        let mut metadata_store = self.metadata.write();
+        let mut bitmap_index = self.bitmap_index.write();
+
+        let mut batch_ids = std::collections::HashSet::with_capacity(items.len());
+        for (id, _, _) in &items {
+            if id_to_internal.contains_key(id) || !batch_ids.insert(id) {
+                return Err(Error::DuplicateId(id.to_string()));
+            }
+        }
+
+        vectors.reserve(items.len() * self.dimensions);
+        internal_to_id.reserve(items.len());
+        id_to_internal.reserve(items.len());
+
+        let start_internal_id = internal_to_id.len();
+        let mut result_ids = Vec::with_capacity(items.len());
+        for (i, (id, vector, metadata)) in items.into_iter().enumerate() {
+            let internal_id = InternalId::from(start_internal_id + i);
+            result_ids.push(internal_id);
+
+            vectors.extend_from_slice(vector);
+            id_to_internal.insert(id.clone(), internal_id);
+            internal_to_id.push(id);
+            if let Some(meta) = metadata {
+                bitmap_index.index(internal_id, &meta);
+                metadata_store.insert(internal_id, meta);
+            }
+        }
+
+        Ok(result_ids)
+    }
+
     /// Batch insert/upsert vectors
     /// Optimized to acquire locks once for the entire batch
     pub fn upsert_batch(
@@ -222,7 +403,7 @@ impl VectorStorage {
 
             // Update mappings
             if let Some(old_internal_id) = id_to_internal.insert(id.clone(), internal_id) {
-                self.deleted.write().insert(old_internal_id);
+                self.deleted.write().insert(old_internal_id.as_u32());
                 if let Some(old_meta) = metadata_store.remove(&old_internal_id) {
                     bitmap_index.remove(old_internal_id, &old_meta);
                 }
@@ -262,7 +443,13 @@ impl VectorStorage {
 
     /// Get metadata for a vector
     pub fn get_metadata(&self, internal_id: InternalId) -> Option<Value> {
-        self.metadata.read().get(&internal_id).cloned()
+        self.metadata.read().get(&internal_id)
+    }
+
+    /// Whether the vector's metadata matches `filter`, reading columnar
+    /// metadata in place
+    pub fn matches_filter(&self, internal_id: InternalId, filter: &Filter) -> bool {
+        self.metadata.read().matches(internal_id, filter)
     }
 
     /// Get internal ID from external ID
@@ -286,6 +473,12 @@ impl VectorStorage {
         self.internal_to_id.read().len()
     }
 
+    /// Get the number of tombstoned slots (deleted, superseded or gap
+    /// placeholders) still occupying space until compaction
+    pub fn deleted_count(&self) -> usize {
+        self.deleted.read().len() as usize
+    }
+
     /// Check if storage is empty
     pub fn is_empty(&self) -> bool {
         self.len() == 0
@@ -313,7 +506,7 @@ impl VectorStorage {
         let map_overhead = count * (64 + 4);
         let rev_map_overhead = count * 64;
 
-        vectors_size + map_overhead + rev_map_overhead
+        vectors_size + map_overhead + rev_map_overhead + self.metadata.read().memory_usage()
     }
 
     /// Create a view of the storage that holds a read lock
@@ -333,8 +526,8 @@ impl VectorStorage {
 /// This avoids repeated locking during search
 pub struct VectorStorageView<'a> {
     guard: crate::sync::RwLockReadGuard<'a, Vec<f32>>,
-    metadata_guard: crate::sync::RwLockReadGuard<'a, HashMap<InternalId, Value>>,
-    deleted_guard: crate::sync::RwLockReadGuard<'a, std::collections::HashSet<InternalId>>,
+    metadata_guard: crate::sync::RwLockReadGuard<'a, MetadataStore>,
+    deleted_guard: crate::sync::RwLockReadGuard<'a, RoaringBitmap>,
     bitmap_guard: crate::sync::RwLockReadGuard<'a, BitmapIndex>,
     dimensions: usize,
 }
@@ -369,10 +562,15 @@ impl<'a> VectorStorageTrait for VectorStorageView<'a> {
     }
 
     fn get_metadata(&self, internal_id: InternalId) -> Option<Value> {
-        if self.deleted_guard.contains(&internal_id) {
+        if self.deleted_guard.contains(internal_id.as_u32()) {
             return None;
         }
-        self.metadata_guard.get(&internal_id).cloned()
+        self.metadata_guard.get(&internal_id)
+    }
+
+    fn matches_filter(&self, internal_id: InternalId, filter: &Filter) -> bool {
+        !self.deleted_guard.contains(internal_id.as_u32())
+            && self.metadata_guard.matches(internal_id, filter)
     }
 
     fn filter_bitmap(&self, filter: &Filter) -> Option<Arc<RoaringBitmap>> {
@@ -380,7 +578,7 @@ impl<'a> VectorStorageTrait for VectorStorageView<'a> {
     }
 
     fn is_deleted(&self, internal_id: InternalId) -> bool {
-        self.deleted_guard.contains(&internal_id)
+        self.deleted_guard.contains(internal_id.as_u32())
     }
 }
 
@@ -408,14 +606,19 @@ impl VectorStorageTrait for VectorStorage {
         }
     }
     fn get_metadata(&self, internal_id: InternalId) -> Option<Value> {
-        if self.deleted.read().contains(&internal_id) {
+        if self.deleted.read().contains(internal_id.as_u32()) {
             return None;
         }
-        self.metadata.read().get(&internal_id).cloned()
+        self.metadata.read().get(&internal_id)
+    }
+
+    fn matches_filter(&self, internal_id: InternalId, filter: &Filter) -> bool {
+        !self.deleted.read().contains(internal_id.as_u32())
+            && self.metadata.read().matches(internal_id, filter)
     }
 
     fn is_deleted(&self, internal_id: InternalId) -> bool {
-        self.deleted.read().contains(&internal_id)
+        self.deleted.read().contains(internal_id.as_u32())
     }
 }
 
@@ -473,6 +676,43 @@ mod tests {
         assert_eq!(storage.get_external_id(internal_id), Some(id));
     }
 
+    #[test]
+    fn test_insert_at_explicit_internal_ids() {
+        let mut storage = VectorStorage::new(2);
+        storage
+            .insert_at(InternalId::from(0), "a".into(), &[1.0, 0.0], None)
+            .unwrap();
+        storage
+            .insert_at(InternalId::from(1), "b".into(), &[0.0, 1.0], None)
+            .unwrap();
+
+        // No overwrite, no gaps by default
+        let collision = storage.insert_at(InternalId::from(1), "c".into(), &[1.0, 1.0], None);
+        assert!(matches!(collision, Err(Error::Storage(_))));
+        let gap = storage.insert_at(InternalId::from(4), "c".into(), &[1.0, 1.0], None);
+        assert!(matches!(gap, Err(Error::Storage(_))));
+
+        storage.set_slot_gaps(SlotGaps::Tombstone);
+        storage
+            .insert_at(InternalId::from(4), "c".into(), &[1.0, 1.0], None)
+            .unwrap();
+
+        assert_eq!(storage.get(InternalId::from(0)).unwrap(), vec![1.0, 0.0]);
+        assert_eq!(storage.get(InternalId::from(1)).unwrap(), vec![0.0, 1.0]);
+        assert_eq!(storage.get(InternalId::from(4)).unwrap(), vec![1.0, 1.0]);
+        assert_eq!(
+            storage.get_internal_id(&"c".into()),
+            Some(InternalId::from(4))
+        );
+        assert!(storage.is_deleted(InternalId::from(2)));
+        assert_eq!(storage.len(), 3);
+        assert_eq!(storage.total_slots(), 5);
+
+        // Regular inserts continue after the highest slot
+        let next = storage.insert("d".into(), &[0.5, 0.5], None).unwrap();
+        assert_eq!(next, InternalId::from(5));
+    }
+
     #[test]
     fn test_metadata() {
         let storage = VectorStorage::new(4);
@@ -483,4 +723,41 @@ mod tests {
         let internal_id = storage.insert(id, &vector, Some(meta.clone())).unwrap();
         assert_eq!(storage.get_metadata(internal_id), Some(meta));
     }
+
+    #[test]
+    fn test_insert_batch_is_atomic() {
+        let storage = VectorStorage::new(2);
+        storage.insert("a".into(), &[0.0, 0.0], None).unwrap();
+        let meta = serde_json::json!({"n": 1});
+
+        let ids = storage
+            .insert_batch(vec![
+                ("b".into(), &[1.0, 1.0][..], Some(meta.clone())),
+                ("c".into(), &[2.0, 2.0][..], None),
+            ])
+            .unwrap();
+        assert_eq!(ids, vec![InternalId::from(1), InternalId::from(2)]);
+        assert_eq!(storage.get(ids[1]).unwrap(), vec![2.0, 2.0]);
+        assert_eq!(storage.get_metadata(ids[0]), Some(meta));
+
+        // Duplicates against existing data or within the batch write nothing
+        for batch in [
+            vec![
+                ("d".into(), &[3.0, 3.0][..], None),
+                ("a".into(), &[4.0, 4.0][..], None),
+            ],
+            vec![
+                ("d".into(), &[3.0, 3.0][..], None),
+                ("d".into(), &[4.0, 4.0][..], None),
+            ],
+            vec![
+                ("d".into(), &[3.0, 3.0][..], None),
+                ("e".into(), &[4.0][..], None),
+            ],
+        ] {
+            assert!(storage.insert_batch(batch).is_err());
+            assert_eq!(storage.len(), 3);
+            assert!(storage.get_internal_id(&"d".into()).is_none());
+        }
+    }
 }
@@ -0,0 +1,152 @@
+//! Index statistics time series
+//!
+//! Periodically sampled per-collection internals (live count, memory,
+//! tombstones, disk) kept in fixed-size ring buffers for capacity planning.
+
+use crate::db::Database;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Point-in-time statistics for one collection
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct IndexStatsSample {
+    /// Milliseconds since the Unix epoch
+    pub timestamp_ms: u64,
+    /// Number of live vectors
+    pub len: usize,
+    /// Approximate in-memory size of vectors and index in bytes
+    pub memory_usage_bytes: usize,
+    /// Deleted or superseded slots still held in storage
+    pub tombstone_count: usize,
+    /// On-disk size of the WAL and snapshots in bytes (0 for in-memory collections)
+    pub disk_usage_bytes: u64,
+}
+
+/// Fixed-capacity ring buffer of samples, oldest first
+#[derive(Debug, Clone)]
+pub struct StatsTimeSeries {
+    capacity: usize,
+    samples: VecDeque<IndexStatsSample>,
+}
+
+impl StatsTimeSeries {
+    /// Create an empty series holding at most `capacity` samples
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            samples: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Append a sample, evicting the oldest once full
+    pub fn record(&mut self, sample: IndexStatsSample) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.samples.len() >= self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    /// Iterate samples, oldest first
+    pub fn samples(&self) -> impl Iterator<Item = &IndexStatsSample> {
+        self.samples.iter()
+    }
+
+    /// Most recent sample
+    pub fn latest(&self) -> Option<&IndexStatsSample> {
+        self.samples.back()
+    }
+
+    /// Number of samples held
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Check if no samples have been recorded
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+}
+
+/// Samples every collection of a `Database` into per-collection time series
+///
+/// The collector does not run on its own; callers advance it with `collect`
+/// on whatever interval suits them (the server uses its metrics tick).
+#[derive(Debug, Clone)]
+pub struct StatsCollector {
+    capacity: usize,
+    series: HashMap<String, StatsTimeSeries>,
+}
+
+impl StatsCollector {
+    /// Create a collector keeping at most `capacity` samples per collection
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            series: HashMap::new(),
+        }
+    }
+
+    /// Record one sample for every collection
+    ///
+    /// Series for collections that no longer exist are dropped.
+    pub fn collect(&mut self, db: &Database) {
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+
+        let samples = db.index_stats(timestamp_ms);
+        self.series.retain(|name, _| samples.contains_key(name));
+        for (name, sample) in samples {
+            let capacity = self.capacity;
+            self.series
+                .entry(name)
+                .or_insert_with(|| StatsTimeSeries::new(capacity))
+                .record(sample);
+        }
+    }
+
+    /// Get the series for one collection
+    pub fn series(&self, name: &str) -> Option<&StatsTimeSeries> {
+        self.series.get(name)
+    }
+
+    /// Export all series as plain sample lists, oldest first
+    pub fn export(&self) -> HashMap<String, Vec<IndexStatsSample>> {
+        self.series
+            .iter()
+            .map(|(name, series)| (name.clone(), series.samples().copied().collect()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(len: usize) -> IndexStatsSample {
+        IndexStatsSample {
+            timestamp_ms: len as u64,
+            len,
+            memory_usage_bytes: 0,
+            tombstone_count: 0,
+            disk_usage_bytes: 0,
+        }
+    }
+
+    #[test]
+    fn test_ring_buffer_evicts_oldest() {
+        let mut series = StatsTimeSeries::new(3);
+        for len in 0..5 {
+            series.record(sample(len));
+        }
+
+        let lens: Vec<usize> = series.samples().map(|s| s.len).collect();
+        assert_eq!(lens, vec![2, 3, 4]);
+        assert_eq!(series.latest().map(|s| s.len), Some(4));
+    }
+}
@@ -0,0 +1,336 @@
+//! Atomic multi-operation writes
+//!
+//! A transaction is validated as a whole before anything is applied, then
+//! applied in order; if applying still fails part way, the operations already
+//! applied are undone so the collection is left as it was.
+
+use crate::error::{Error, Result};
+use crate::filter::Filter;
+use crate::hnsw::HnswIndex;
+use crate::storage::VectorStorage;
+use crate::types::{self, Aliases, InsertMode, VectorId};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+/// One write in an atomic transaction
+#[derive(Debug, Clone, PartialEq)]
+pub enum Op {
+    /// Insert a new vector; fails if the ID is taken
+    Insert {
+        id: VectorId,
+        vector: Vec<f32>,
+        metadata: Option<Value>,
+    },
+    /// Insert a vector or replace an existing one
+    Upsert {
+        id: VectorId,
+        vector: Vec<f32>,
+        metadata: Option<Value>,
+    },
+    /// Delete a vector (by primary ID or alias); a missing ID is not an error
+    Delete { id: VectorId },
+    /// Replace the metadata of an existing vector, keeping its vector
+    SetMetadata {
+        id: VectorId,
+        metadata: Option<Value>,
+    },
+}
+
+impl Op {
+    pub fn id(&self) -> &VectorId {
+        match self {
+            Op::Insert { id, .. }
+            | Op::Upsert { id, .. }
+            | Op::Delete { id }
+            | Op::SetMetadata { id, .. } => id,
+        }
+    }
+}
+
+/// Validate `ops` against the current contents, returning them ready to apply
+///
+/// Vectors are passed through `prepare` (so the returned ops hold vectors as
+/// stored) and aliases through `resolve`. Existence is tracked across the
+/// batch, so an `Insert` after a `Delete` of the same ID is accepted.
+pub(crate) fn plan(
+    ops: Vec<Op>,
+    max_metadata_bytes: usize,
+    exists: impl Fn(&VectorId) -> bool,
+    resolve: impl Fn(&VectorId) -> VectorId,
+    prepare: impl Fn(&[f32], Option<&Value>) -> Result<Vec<f32>>,
+) -> Result<Vec<Op>> {
+    let mut live: HashMap<VectorId, bool> = HashMap::new();
+    let is_live = |live: &HashMap<VectorId, bool>, id: &VectorId| {
+        live.get(id).copied().unwrap_or_else(|| exists(id))
+    };
+
+    ops.into_iter()
+        .map(|op| {
+            let op = match op {
+                Op::Insert {
+                    id,
+                    vector,
+                    metadata,
+                } => {
+                    if is_live(&live, &id) {
+                        return Err(Error::DuplicateId(id.to_string()));
+                    }
+                    let vector = prepare(&vector, metadata.as_ref())?;
+                    Op::Insert {
+                        id,
+                        vector,
+                        metadata,
+                    }
+                }
+                Op::Upsert {
+                    id,
+                    vector,
+                    metadata,
+                } => {
+                    let vector = prepare(&vector, metadata.as_ref())?;
+                    Op::Upsert {
+                        id,
+                        vector,
+                        metadata,
+                    }
+                }
+                Op::Delete { id } => Op::Delete { id: resolve(&id) },
+                Op::SetMetadata { id, metadata } => {
+                    let id = resolve(&id);
+                    if !is_live(&live, &id) {
+                        return Err(Error::VectorNotFound(id.to_string()));
+                    }
+                    types::check_metadata_size(metadata.as_ref(), max_metadata_bytes)?;
+                    Op::SetMetadata { id, metadata }
+                }
+            };
+            live.insert(op.id().clone(), !matches!(op, Op::Delete { .. }));
+            Ok(op)
+        })
+        .collect()
+}
+
+/// Drop the planned `Insert` and `Upsert` ops that `mode` skips (see
+/// `types::dedup_batch`)
+///
+/// Only an op creating an ID that no other op touches can be dropped, and it
+/// is only matched against stored vectors the transaction leaves alone.
+pub(crate) fn dedup(
+    ops: Vec<Op>,
+    mode: InsertMode,
+    exists: impl Fn(&VectorId) -> bool,
+    find: impl Fn(&[f32]) -> Result<Option<VectorId>>,
+) -> Result<Vec<Op>> {
+    if mode == InsertMode::Always {
+        return Ok(ops);
+    }
+    let mut touches: HashMap<VectorId, usize> = HashMap::new();
+    for op in &ops {
+        *touches.entry(op.id().clone()).or_default() += 1;
+    }
+    let droppable: HashSet<VectorId> = ops
+        .iter()
+        .filter(|op| matches!(op, Op::Insert { .. } | Op::Upsert { .. }))
+        .map(Op::id)
+        .filter(|id| touches[*id] == 1 && !exists(id))
+        .cloned()
+        .collect();
+
+    // Lend each op's vector to the batch and hand it back afterwards
+    let items = ops
+        .into_iter()
+        .map(|mut op| {
+            let vector = match &mut op {
+                Op::Insert { vector, .. } | Op::Upsert { vector, .. } => std::mem::take(vector),
+                _ => Vec::new(),
+            };
+            (op.id().clone(), vector, op)
+        })
+        .collect();
+    let (kept, _) = types::dedup_batch(
+        mode,
+        items,
+        |id| droppable.contains(id),
+        |vector| Ok(find(vector)?.filter(|stored| !touches.contains_key(stored))),
+    )?;
+    Ok(kept
+        .into_iter()
+        .map(|(_, vector, mut op)| {
+            if let Op::Insert { vector: lent, .. } | Op::Upsert { vector: lent, .. } = &mut op {
+                *lent = vector;
+            }
+            op
+        })
+        .collect())
+}
+
+/// `SetMetadata` ops merging `patch` into every live vector matching `filter`
+///
+/// Vectors the patch would leave unchanged get no op.
+pub(crate) fn patch_metadata(storage: &VectorStorage, filter: &Filter, patch: &Value) -> Vec<Op> {
+    storage
+        .all_internal_ids()
+        .into_iter()
+        .filter_map(|internal_id| {
+            let id = storage.get_external_id(internal_id)?;
+            // Skip deleted vectors and slots left behind by upserts
+            if storage.get_internal_id(&id) != Some(internal_id)
+                || !storage.matches_filter(internal_id, filter)
+            {
+                return None;
+            }
+            let before = storage.get_metadata(internal_id);
+            let after = types::merge_patch(before.clone(), patch);
+            (after != before).then_some(Op::SetMetadata {
+                id,
+                metadata: after,
+            })
+        })
+        .collect()
+}
+
+/// Applies planned ops to a collection's storage, graphs and aliases
+pub(crate) struct Writer<'a> {
+    pub storage: &'a VectorStorage,
+    pub indexes: Vec<&'a HnswIndex>,
+    pub aliases: &'a mut Aliases,
+    pub eager_edge_cleanup: bool,
+}
+
+impl Writer<'_> {
+    /// Apply every op, or none: on failure the applied ops are undone
+    pub fn apply_all(&mut self, ops: &[Op]) -> Result<()> {
+        let aliases = self.aliases.clone();
+        let mut undo = Vec::with_capacity(ops.len());
+
+        for op in ops {
+            let id = op.id().clone();
+            let before = self.storage.get_internal_id(&id).map(|internal_id| {
+                (
+                    self.storage.get(internal_id).unwrap_or_default(),
+                    self.storage.get_metadata(internal_id),
+                )
+            });
+            undo.push((id, before));
+
+            if let Err(e) = self.apply(op) {
+                for (id, before) in undo.into_iter().rev() {
+                    let _ = match before {
+                        Some((vector, metadata)) => self.apply(&Op::Upsert {
+                            id,
+                            vector,
+                            metadata,
+                        }),
+                        None => self.apply(&Op::Delete { id }),
+                    };
+                }
+                *self.aliases = aliases;
+                return Err(e);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn apply(&mut self, op: &Op) -> Result<()> {
+        let previous = self.storage.get_internal_id(op.id());
+        match op {
+            Op::Insert {
+                id,
+                vector,
+                metadata,
+            } => {
+                let internal_id = self.storage.insert(id.clone(), vector, metadata.clone())?;
+                for index in &self.indexes {
+                    index.insert(internal_id, vector, self.storage)?;
+                }
+            }
+            Op::Upsert {
+                id,
+                vector,
+                metadata,
+            } => self.upsert(id, vector, metadata.clone())?,
+            Op::Delete { id } => {
+                if self.storage.delete(id)? {
+                    self.aliases.remove_target(id);
+                }
+            }
+            Op::SetMetadata { id, metadata } => self
+                .storage
+                .update_metadata(id, metadata.clone().unwrap_or(Value::Null))?,
+        }
+
+        if self.eager_edge_cleanup {
+            if let Some(old) = previous {
+                if self.storage.get_internal_id(op.id()) != Some(old) {
+                    for index in &self.indexes {
+                        index.remove(old, self.storage);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn upsert(&self, id: &VectorId, vector: &[f32], metadata: Option<Value>) -> Result<()> {
+        let internal_id = self.storage.upsert(id.clone(), vector, metadata)?;
+        for index in &self.indexes {
+            index.insert(internal_id, vector, self.storage)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distance::DistanceMetric;
+    use crate::hnsw::HnswConfig;
+    use serde_json::json;
+
+    #[test]
+    fn test_apply_all_rolls_back_on_failure() {
+        let storage = VectorStorage::new(2);
+        let index = HnswIndex::new(HnswConfig::default(), DistanceMetric::Euclidean);
+        let mut aliases = Aliases::default();
+        let id = storage.insert("a".into(), &[1.0, 0.0], None).unwrap();
+        index.insert(id, &[1.0, 0.0], &storage).unwrap();
+        aliases.restore("first".into(), "a".into());
+
+        let mut writer = Writer {
+            storage: &storage,
+            indexes: vec![&index],
+            aliases: &mut aliases,
+            eager_edge_cleanup: false,
+        };
+        // Unplanned ops skip validation, so the last one fails while applying
+        let result = writer.apply_all(&[
+            Op::SetMetadata {
+                id: "a".into(),
+                metadata: Some(json!({"k": 1})),
+            },
+            Op::Delete { id: "a".into() },
+            Op::Upsert {
+                id: "b".into(),
+                vector: vec![0.0, 1.0],
+                metadata: None,
+            },
+            Op::Insert {
+                id: "c".into(),
+                vector: vec![0.0],
+                metadata: None,
+            },
+        ]);
+        assert!(matches!(result, Err(Error::DimensionMismatch { .. })));
+
+        assert_eq!(storage.len(), 1);
+        assert!(storage.get_internal_id(&"b".into()).is_none());
+        let a = storage.get_internal_id(&"a".into()).unwrap();
+        assert_eq!(storage.get(a).unwrap(), vec![1.0, 0.0]);
+        assert_eq!(storage.get_metadata(a), None);
+        assert_eq!(
+            aliases.resolve(&"first".into(), |id| storage.get_internal_id(id).is_some()),
+            VectorId::from("a")
+        );
+    }
+}
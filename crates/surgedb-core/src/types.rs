@@ -5,35 +5,133 @@ use serde::{Deserialize, Serialize};
 /// A vector represented as a slice of f32 values
 pub type Vector = [f32];
 
+/// One search result: external ID, distance and (merged) metadata
+pub type SearchHit = (VectorId, f32, Option<serde_json::Value>);
+
 /// External vector identifier (user-facing)
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
-pub struct VectorId(String);
+///
+/// Either a string or an unsigned integer. A string holding a canonical
+/// decimal `u64` (digits only, no leading zeros) is stored as the integer,
+/// so `VectorId::from(7)` and `VectorId::from("7")` are the same ID and
+/// numeric datasets do not pay for a heap-allocated string per vector.
+///
+/// Always serialized as a string, keeping snapshots, WAL files and JSON
+/// responses unchanged; deserialization also accepts an integer.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum VectorId {
+    Str(String),
+    Num(u64),
+}
 
 impl VectorId {
     pub fn new(id: impl Into<String>) -> Self {
-        Self(id.into())
+        Self::from(id.into())
     }
 
-    pub fn as_str(&self) -> &str {
-        &self.0
+    /// The ID as text; integers are rendered in decimal
+    pub fn as_str(&self) -> std::borrow::Cow<'_, str> {
+        match self {
+            VectorId::Str(s) => std::borrow::Cow::Borrowed(s),
+            VectorId::Num(n) => std::borrow::Cow::Owned(n.to_string()),
+        }
+    }
+
+    /// The integer value of a numeric ID
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            VectorId::Num(n) => Some(*n),
+            VectorId::Str(_) => None,
+        }
+    }
+
+    /// Parse `s` as a canonical decimal `u64`
+    fn canonical_num(s: &str) -> Option<u64> {
+        let canonical = !s.is_empty()
+            && s.len() <= 20
+            && s.bytes().all(|b| b.is_ascii_digit())
+            && (s == "0" || !s.starts_with('0'));
+        canonical.then(|| s.parse().ok()).flatten()
     }
 }
 
 impl From<&str> for VectorId {
     fn from(s: &str) -> Self {
-        Self(s.to_string())
+        match Self::canonical_num(s) {
+            Some(n) => VectorId::Num(n),
+            None => VectorId::Str(s.to_string()),
+        }
     }
 }
 
 impl From<String> for VectorId {
     fn from(s: String) -> Self {
-        Self(s)
+        match Self::canonical_num(&s) {
+            Some(n) => VectorId::Num(n),
+            None => VectorId::Str(s),
+        }
+    }
+}
+
+impl From<u64> for VectorId {
+    fn from(n: u64) -> Self {
+        VectorId::Num(n)
     }
 }
 
 impl std::fmt::Display for VectorId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
+        match self {
+            VectorId::Str(s) => f.write_str(s),
+            VectorId::Num(n) => write!(f, "{}", n),
+        }
+    }
+}
+
+impl Serialize for VectorId {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            VectorId::Str(s) => serializer.serialize_str(s),
+            VectorId::Num(n) => serializer.collect_str(n),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for VectorId {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct IdVisitor;
+
+        impl serde::de::Visitor<'_> for IdVisitor {
+            type Value = VectorId;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str("a string or non-negative integer ID")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, s: &str) -> Result<VectorId, E> {
+                Ok(VectorId::from(s))
+            }
+
+            fn visit_string<E: serde::de::Error>(self, s: String) -> Result<VectorId, E> {
+                Ok(VectorId::from(s))
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, n: u64) -> Result<VectorId, E> {
+                Ok(VectorId::Num(n))
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, n: i64) -> Result<VectorId, E> {
+                u64::try_from(n)
+                    .map(VectorId::Num)
+                    .map_err(|_| E::custom(format!("ID must not be negative, got {}", n)))
+            }
+        }
+
+        // Binary formats cannot be asked for "any" type; they hold strings
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(IdVisitor)
+        } else {
+            deserializer.deserialize_string(IdVisitor)
+        }
     }
 }
 
@@ -91,3 +189,700 @@ impl From<usize> for InternalId {
         Self(id as u32)
     }
 }
+
+/// Merge collection-level default metadata with a vector's own metadata.
+///
+/// The result is `{...defaults, ...metadata}`: keys present on the vector
+/// override the defaults. Non-object metadata is returned unchanged.
+pub(crate) fn merge_default_metadata(
+    defaults: Option<&serde_json::Value>,
+    metadata: Option<serde_json::Value>,
+) -> Option<serde_json::Value> {
+    use serde_json::Value;
+
+    let defaults = match defaults {
+        Some(Value::Object(map)) if !map.is_empty() => map,
+        _ => return metadata,
+    };
+
+    match metadata {
+        None | Some(Value::Null) => Some(Value::Object(defaults.clone())),
+        Some(Value::Object(own)) => {
+            let mut merged = defaults.clone();
+            merged.extend(own);
+            Some(Value::Object(merged))
+        }
+        other => other,
+    }
+}
+
+/// Apply a JSON merge patch (RFC 7396) to `metadata`
+///
+/// Object patches merge key by key, recursively; a `null` value removes the
+/// key. Any other patch replaces the metadata outright, and a result of
+/// `null` means no metadata.
+pub(crate) fn merge_patch(
+    metadata: Option<serde_json::Value>,
+    patch: &serde_json::Value,
+) -> Option<serde_json::Value> {
+    use serde_json::Value;
+
+    fn apply(target: &mut Value, patch: &Value) {
+        let Value::Object(patch) = patch else {
+            *target = patch.clone();
+            return;
+        };
+        if !target.is_object() {
+            *target = Value::Object(Default::default());
+        }
+        let Value::Object(target) = target else {
+            unreachable!()
+        };
+        for (key, value) in patch {
+            if value.is_null() {
+                target.remove(key);
+            } else {
+                apply(target.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+    }
+
+    let mut metadata = metadata.unwrap_or(Value::Null);
+    apply(&mut metadata, patch);
+    (!metadata.is_null()).then_some(metadata)
+}
+
+/// Reject metadata whose serialized JSON exceeds `limit` bytes (0 = unlimited)
+pub(crate) fn check_metadata_size(
+    metadata: Option<&serde_json::Value>,
+    limit: usize,
+) -> crate::error::Result<()> {
+    if limit == 0 {
+        return Ok(());
+    }
+    if let Some(meta) = metadata {
+        let size = serde_json::to_vec(meta)?.len();
+        if size > limit {
+            return Err(crate::error::Error::MetadataTooLarge { size, limit });
+        }
+    }
+    Ok(())
+}
+
+fn l2_norm(vector: &[f32]) -> f32 {
+    vector.iter().map(|x| x * x).sum::<f32>().sqrt()
+}
+
+/// Reject vectors with a NaN or infinite component when `enabled` is set
+///
+/// Such components make every distance to the vector NaN or infinite, which
+/// the graph cannot order.
+pub(crate) fn check_finite(vector: &[f32], enabled: bool) -> crate::error::Result<()> {
+    if !enabled {
+        return Ok(());
+    }
+    match vector.iter().position(|x| !x.is_finite()) {
+        Some(index) => Err(crate::error::Error::InvalidVector {
+            index,
+            value: vector[index],
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Allowed deviation of a vector's L2 norm from 1.0 under `require_unit_norm`
+pub const UNIT_NORM_EPSILON: f32 = 1e-3;
+
+/// Reject vectors that are not unit length when `required` is set
+pub(crate) fn check_unit_norm(vector: &[f32], required: bool) -> crate::error::Result<()> {
+    if !required {
+        return Ok(());
+    }
+    let norm = l2_norm(vector);
+    if (norm - 1.0).abs() < UNIT_NORM_EPSILON {
+        Ok(())
+    } else {
+        Err(crate::error::Error::NotUnitNorm { norm })
+    }
+}
+
+/// Queries more than this factor above the largest (or below the smallest)
+/// stored norm are rejected under `validate_query`
+pub const QUERY_NORM_TOLERANCE: f32 = 100.0;
+
+/// Range of L2 norms of the vectors written to a collection, used to reject
+/// garbage queries when `validate_query` is set (a no-op otherwise)
+///
+/// The range only widens: deletes don't shrink it.
+pub(crate) struct QueryNorms {
+    enabled: bool,
+    range: crate::sync::RwLock<Option<(f32, f32)>>,
+}
+
+impl QueryNorms {
+    pub(crate) fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            range: crate::sync::RwLock::new(None),
+        }
+    }
+
+    /// Widen the range to include a written vector
+    pub(crate) fn observe(&self, vector: &[f32]) {
+        if !self.enabled {
+            return;
+        }
+        let norm = l2_norm(vector);
+        if !norm.is_finite() {
+            return;
+        }
+        let mut range = self.range.write();
+        *range = Some(match *range {
+            Some((min, max)) => (min.min(norm), max.max(norm)),
+            None => (norm, norm),
+        });
+    }
+
+    /// Reject non-finite queries, zero queries under cosine-based metrics,
+    /// and queries whose norm is far outside the stored range
+    pub(crate) fn check(
+        &self,
+        query: &[f32],
+        metric: crate::distance::DistanceMetric,
+    ) -> crate::error::Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        let norm = l2_norm(query);
+        let invalid = |reason| Err(crate::error::Error::InvalidQuery { norm, reason });
+        if !norm.is_finite() {
+            return invalid("norm is not finite");
+        }
+        let cosine = matches!(
+            metric,
+            crate::distance::DistanceMetric::Cosine
+                | crate::distance::DistanceMetric::Hybrid { .. }
+        );
+        if norm == 0.0 && cosine {
+            return invalid("a zero vector has no direction to compare under cosine distance");
+        }
+        if let Some((min, max)) = *self.range.read() {
+            // The origin is a valid query under the other metrics
+            let too_small = norm > 0.0 && norm < min / QUERY_NORM_TOLERANCE;
+            if norm > max * QUERY_NORM_TOLERANCE || too_small {
+                return invalid("norm is far outside the range of stored vectors");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How inserts and queries whose length differs from the configured
+/// dimensions are handled
+///
+/// Zero padding leaves dot products with the padded vector unchanged, so
+/// short vectors are compared on the dimensions they have; that is only
+/// meaningful if the missing tail is near zero in practice. Truncation drops
+/// the tail, which costs recall unless the embedding front-loads its signal
+/// (e.g. Matryoshka-trained models).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DimensionPolicy {
+    /// Reject any mismatch with `DimensionMismatch`
+    #[default]
+    Strict,
+    /// Zero-pad short vectors; long vectors are rejected
+    PadZero,
+    /// Drop the extra trailing dimensions of long vectors; short vectors are rejected
+    Truncate,
+}
+
+impl DimensionPolicy {
+    /// Bring `vector` to `dimensions` under this policy, borrowing it when it
+    /// already fits
+    pub(crate) fn fit(
+        self,
+        dimensions: usize,
+        vector: &[f32],
+    ) -> crate::error::Result<std::borrow::Cow<'_, [f32]>> {
+        use std::borrow::Cow;
+        use std::cmp::Ordering;
+
+        match (vector.len().cmp(&dimensions), self) {
+            (Ordering::Equal, _) => Ok(Cow::Borrowed(vector)),
+            (Ordering::Less, DimensionPolicy::PadZero) => {
+                let mut padded = vector.to_vec();
+                padded.resize(dimensions, 0.0);
+                Ok(Cow::Owned(padded))
+            }
+            (Ordering::Greater, DimensionPolicy::Truncate) => {
+                Ok(Cow::Borrowed(&vector[..dimensions]))
+            }
+            _ => Err(crate::error::Error::DimensionMismatch {
+                expected: dimensions,
+                got: vector.len(),
+            }),
+        }
+    }
+}
+
+/// How `set_metadata` combines new metadata with a vector's stored metadata
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum MetadataUpdate {
+    /// Replace the stored metadata outright; `null` clears it
+    #[default]
+    Replace,
+    /// Overwrite just the top-level keys of the new object, keeping the
+    /// stored object's other keys (otherwise the same as `Replace`)
+    Merge,
+}
+
+impl MetadataUpdate {
+    /// The metadata to store after applying `update` to `stored`
+    pub(crate) fn apply(
+        self,
+        stored: Option<serde_json::Value>,
+        update: serde_json::Value,
+    ) -> serde_json::Value {
+        use serde_json::Value;
+        match (self, stored, update) {
+            (Self::Merge, Some(Value::Object(mut stored)), Value::Object(update)) => {
+                stored.extend(update);
+                Value::Object(stored)
+            }
+            (_, _, update) => update,
+        }
+    }
+}
+
+/// How `VectorDb::insert` treats a vector whose content is already stored
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum InsertMode {
+    /// Store every vector under its own ID
+    #[default]
+    Always,
+    /// Skip a vector within `epsilon` (Euclidean, after projection and
+    /// normalization) of a stored one, keeping the stored ID and metadata
+    ///
+    /// An `epsilon` of 0 matches identical content through a hash lookup.
+    /// Larger values also check the nearest graph candidates, so a near
+    /// duplicate the search misses is still inserted.
+    DedupByContent { epsilon: f32 },
+}
+
+impl InsertMode {
+    pub(crate) fn validate(&self) -> crate::error::Result<()> {
+        if let Self::DedupByContent { epsilon } = *self {
+            if !(epsilon >= 0.0 && epsilon.is_finite()) {
+                return Err(crate::error::Error::InvalidConfig(format!(
+                    "dedup epsilon must be finite and non-negative, got {}",
+                    epsilon
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How a batch import treats an ID that is already stored or repeated within
+/// the batch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DuplicatePolicy {
+    /// Keep the stored vector, or the first occurrence within the batch
+    #[default]
+    Skip,
+    /// Replace the stored vector, or the earlier occurrence within the batch
+    Overwrite,
+    /// Fail with `DuplicateId` before anything is written
+    Error,
+}
+
+/// How the items of a batch import were applied
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct BatchReport {
+    /// Items stored under a new ID
+    pub inserted: usize,
+    /// Duplicates dropped under `DuplicatePolicy::Skip`, and vectors
+    /// skipped by `InsertMode::DedupByContent`
+    pub skipped: usize,
+    /// Duplicates that replaced a stored vector or an earlier item under
+    /// `DuplicatePolicy::Overwrite`
+    pub overwritten: usize,
+}
+
+impl BatchReport {
+    /// Fold the counts of another batch into this one
+    pub fn add(&mut self, other: BatchReport) {
+        self.inserted += other.inserted;
+        self.skipped += other.skipped;
+        self.overwritten += other.overwritten;
+    }
+
+    /// Count `count` items taken as inserted as skipped by `InsertMode`
+    pub(crate) fn deduplicated(mut self, count: usize) -> Self {
+        self.inserted = self.inserted.saturating_sub(count);
+        self.skipped += count;
+        self
+    }
+}
+
+/// A page of vector IDs and metadata from `list_after`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ListPage {
+    /// Vectors in listing order
+    pub entries: Vec<(VectorId, Option<serde_json::Value>)>,
+    /// Opaque position to pass as the next `list_after` cursor, or `None`
+    /// once no listed vector follows
+    pub next_cursor: Option<u64>,
+}
+
+/// Build a `list_after` page by walking storage slots from `cursor`;
+/// `entry` resolves a slot to its listing entry, or `None` for stale slots
+pub(crate) fn list_page(
+    cursor: Option<u64>,
+    limit: usize,
+    slots: usize,
+    entry: impl Fn(InternalId) -> Option<(VectorId, Option<serde_json::Value>)>,
+) -> ListPage {
+    let mut slot = usize::try_from(cursor.unwrap_or(0)).unwrap_or(usize::MAX);
+    let mut entries = Vec::new();
+    while slot < slots && entries.len() < limit {
+        entries.extend(entry(InternalId::from(slot)));
+        slot += 1;
+    }
+    // Skip ahead to the next live slot, so the last page carries no cursor
+    while slot < slots && entry(InternalId::from(slot)).is_none() {
+        slot += 1;
+    }
+    ListPage {
+        entries,
+        next_cursor: (slot < slots).then_some(slot as u64),
+    }
+}
+
+/// Resolve the duplicate IDs of a batch under `policy`, returning the items
+/// to write (each ID at most once, in first-occurrence order) and the counts.
+/// `exists` reports whether an ID is already stored.
+pub(crate) fn resolve_duplicates<T>(
+    items: impl IntoIterator<Item = (VectorId, T)>,
+    policy: DuplicatePolicy,
+    exists: impl Fn(&VectorId) -> bool,
+) -> crate::error::Result<(Vec<(VectorId, T)>, BatchReport)> {
+    let mut resolved: Vec<(VectorId, T)> = Vec::new();
+    let mut positions = std::collections::HashMap::new();
+    let mut report = BatchReport::default();
+    for (id, item) in items {
+        let earlier = positions.get(&id).copied();
+        if earlier.is_none() && !exists(&id) {
+            positions.insert(id.clone(), resolved.len());
+            resolved.push((id, item));
+            report.inserted += 1;
+            continue;
+        }
+        match (policy, earlier) {
+            (DuplicatePolicy::Skip, _) => report.skipped += 1,
+            (DuplicatePolicy::Overwrite, Some(position)) => {
+                resolved[position].1 = item;
+                report.overwritten += 1;
+            }
+            (DuplicatePolicy::Overwrite, None) => {
+                positions.insert(id.clone(), resolved.len());
+                resolved.push((id, item));
+                report.overwritten += 1;
+            }
+            (DuplicatePolicy::Error, _) => {
+                return Err(crate::error::Error::DuplicateId(id.to_string()))
+            }
+        }
+    }
+    Ok((resolved, report))
+}
+
+/// Stored slots bucketed by a hash of their vector, for
+/// `InsertMode::DedupByContent`
+///
+/// Slots are hashed lazily on lookup, so every write path is covered without
+/// hooking into it; deleted and overwritten slots are skipped when matched.
+pub(crate) struct ContentHashes {
+    /// Slots hashed so far, and the slots per content hash
+    buckets: crate::sync::RwLock<(usize, std::collections::HashMap<u64, Vec<InternalId>>)>,
+}
+
+impl Default for ContentHashes {
+    fn default() -> Self {
+        Self {
+            buckets: crate::sync::RwLock::new((0, Default::default())),
+        }
+    }
+}
+
+impl ContentHashes {
+    fn hash(vector: &[f32]) -> u64 {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        for &x in vector {
+            // -0.0 and 0.0 are the same content
+            (x + 0.0).to_bits().hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    /// Find a live slot storing exactly `vector`, after hashing the slots
+    /// below `slots` that are new since the last lookup. `get` returns a
+    /// slot's vector if it is live.
+    pub(crate) fn find(
+        &self,
+        vector: &[f32],
+        slots: usize,
+        get: impl Fn(InternalId) -> Option<Vec<f32>>,
+    ) -> Option<InternalId> {
+        let mut guard = self.buckets.write();
+        let (hashed, buckets) = &mut *guard;
+        for slot in *hashed..slots {
+            let internal_id = InternalId::from(slot);
+            if let Some(stored) = get(internal_id) {
+                buckets
+                    .entry(Self::hash(&stored))
+                    .or_default()
+                    .push(internal_id);
+            }
+        }
+        *hashed = (*hashed).max(slots);
+
+        buckets
+            .get(&Self::hash(vector))?
+            .iter()
+            .copied()
+            .find(|&internal_id| get(internal_id).is_some_and(|stored| stored == vector))
+    }
+
+    /// The primary ID of a live vector in `storage` matching the prepared
+    /// `vector` under `mode`
+    pub(crate) fn find_duplicate(
+        &self,
+        mode: InsertMode,
+        vector: &[f32],
+        storage: &crate::storage::VectorStorage,
+        index: &crate::hnsw::HnswIndex,
+    ) -> crate::error::Result<Option<VectorId>> {
+        let InsertMode::DedupByContent { epsilon } = mode else {
+            return Ok(None);
+        };
+        // Deleted and overwritten slots keep their vectors but no longer map back
+        let live_id = |internal_id| {
+            storage
+                .get_external_id(internal_id)
+                .filter(|id| storage.get_internal_id(id) == Some(internal_id))
+        };
+
+        let exact = self.find(vector, storage.total_slots(), |internal_id| {
+            live_id(internal_id)?;
+            storage.get(internal_id)
+        });
+        if let Some(internal_id) = exact {
+            return Ok(live_id(internal_id));
+        }
+        if epsilon == 0.0 || storage.is_empty() {
+            return Ok(None);
+        }
+
+        const CANDIDATES: usize = 10;
+        let candidates = index.search(vector, CANDIDATES, &storage.view(), None)?;
+        Ok(candidates
+            .into_iter()
+            .filter_map(|(internal_id, _)| {
+                let stored = storage.get(internal_id)?;
+                let distance = crate::distance::DistanceMetric::Euclidean.distance(vector, &stored);
+                (distance <= epsilon).then_some((internal_id, distance))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .and_then(|(internal_id, _)| live_id(internal_id)))
+    }
+}
+
+/// A batch item: ID, prepared vector and whatever travels with it
+type BatchItem<M> = (VectorId, Vec<f32>, M);
+
+/// Drop the items of a batch of prepared vectors that `mode` skips,
+/// returning the kept items in order and how many were dropped
+///
+/// Only items `may_drop` accepts are checked: against the stored vectors
+/// through `find`, and against the earlier items of the batch kept this way.
+pub(crate) fn dedup_batch<M>(
+    mode: InsertMode,
+    items: Vec<BatchItem<M>>,
+    may_drop: impl Fn(&VectorId) -> bool,
+    find: impl Fn(&[f32]) -> crate::error::Result<Option<VectorId>>,
+) -> crate::error::Result<(Vec<BatchItem<M>>, usize)> {
+    let InsertMode::DedupByContent { epsilon } = mode else {
+        return Ok((items, 0));
+    };
+
+    let mut kept = Vec::with_capacity(items.len());
+    // Positions in `kept` of the new vectors, by content hash when matching
+    // exactly; a near match has to compare against all of them
+    let mut added: std::collections::HashMap<u64, Vec<usize>> = Default::default();
+    let mut dropped = 0;
+    for (id, vector, extra) in items {
+        if !may_drop(&id) {
+            kept.push((id, vector, extra));
+            continue;
+        }
+        let key = if epsilon == 0.0 {
+            ContentHashes::hash(&vector)
+        } else {
+            0
+        };
+        let in_batch = added.get(&key).is_some_and(|positions| {
+            positions.iter().any(|&i: &usize| {
+                let earlier: &Vec<f32> = &kept[i].1;
+                crate::distance::DistanceMetric::Euclidean.distance(&vector, earlier) <= epsilon
+            })
+        });
+        if in_batch || find(&vector)?.is_some() {
+            dropped += 1;
+            continue;
+        }
+        added.entry(key).or_default().push(kept.len());
+        kept.push((id, vector, extra));
+    }
+    Ok((kept, dropped))
+}
+
+/// Shuffle a batch with a seeded RNG before it is inserted.
+///
+/// Only the last entry per ID is kept, so the batch upserts the same final
+/// state as it would in its original order.
+pub(crate) fn shuffle_batch<V, M>(
+    items: Vec<(VectorId, V, M)>,
+    seed: u64,
+) -> Vec<(VectorId, V, M)> {
+    use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+    use std::collections::HashMap;
+
+    let last: HashMap<VectorId, usize> = items
+        .iter()
+        .enumerate()
+        .map(|(i, (id, _, _))| (id.clone(), i))
+        .collect();
+    let mut items: Vec<_> = items
+        .into_iter()
+        .enumerate()
+        .filter(|(i, (id, _, _))| last[id] == *i)
+        .map(|(_, item)| item)
+        .collect();
+    items.shuffle(&mut StdRng::seed_from_u64(seed));
+    items
+}
+
+/// The `k` nearest `(id, distance)` candidates, nearest first
+///
+/// Keeps a bounded max-heap of the best `k` seen so far instead of sorting
+/// every candidate, so selecting from `n` costs O(n log k). Equal distances
+/// keep their input order, as a stable sort would.
+pub fn top_k(
+    candidates: impl IntoIterator<Item = (InternalId, f32)>,
+    k: usize,
+) -> Vec<(InternalId, f32)> {
+    use std::cmp::Ordering;
+    use std::collections::BinaryHeap;
+
+    struct Entry {
+        distance: f32,
+        seq: usize,
+        id: InternalId,
+    }
+    impl PartialEq for Entry {
+        fn eq(&self, other: &Self) -> bool {
+            self.cmp(other) == Ordering::Equal
+        }
+    }
+    impl Eq for Entry {}
+    impl PartialOrd for Entry {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+    impl Ord for Entry {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.distance
+                .total_cmp(&other.distance)
+                .then(self.seq.cmp(&other.seq))
+        }
+    }
+
+    if k == 0 {
+        return Vec::new();
+    }
+    let mut heap = BinaryHeap::with_capacity(k);
+    for (seq, (id, distance)) in candidates.into_iter().enumerate() {
+        let entry = Entry { distance, seq, id };
+        if heap.len() < k {
+            heap.push(entry);
+        } else if let Some(mut worst) = heap.peek_mut() {
+            if entry < *worst {
+                *worst = entry;
+            }
+        }
+    }
+    heap.into_sorted_vec()
+        .into_iter()
+        .map(|entry| (entry.id, entry.distance))
+        .collect()
+}
+
+/// Secondary keys (e.g. a human-readable slug) resolving to primary vector IDs
+///
+/// Primary IDs take precedence: a key only resolves through the alias map
+/// when no vector is stored under the key itself.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Aliases(std::collections::HashMap<VectorId, VectorId>);
+
+impl Aliases {
+    /// Resolve a primary ID or alias to the primary ID; `exists` reports
+    /// whether a vector is stored under a primary ID
+    pub(crate) fn resolve(&self, id: &VectorId, exists: impl Fn(&VectorId) -> bool) -> VectorId {
+        if !exists(id) {
+            if let Some(primary) = self.0.get(id) {
+                return primary.clone();
+            }
+        }
+        id.clone()
+    }
+
+    /// Point `alias` at the vector `target` (a primary ID or another alias)
+    /// and return its primary ID
+    pub(crate) fn add(
+        &mut self,
+        alias: VectorId,
+        target: &VectorId,
+        exists: impl Fn(&VectorId) -> bool,
+    ) -> crate::error::Result<VectorId> {
+        let primary = self.resolve(target, &exists);
+        if !exists(&primary) {
+            return Err(crate::error::Error::VectorNotFound(target.to_string()));
+        }
+        let taken = match self.0.get(&alias) {
+            Some(existing) => *existing != primary,
+            None => exists(&alias),
+        };
+        if taken {
+            return Err(crate::error::Error::DuplicateId(alias.to_string()));
+        }
+        self.0.insert(alias, primary.clone());
+        Ok(primary)
+    }
+
+    /// Restore an alias without validation (snapshot recovery)
+    pub(crate) fn restore(&mut self, alias: VectorId, primary: VectorId) {
+        self.0.insert(alias, primary);
+    }
+
+    /// Drop every alias pointing at a deleted vector
+    pub(crate) fn remove_target(&mut self, primary: &VectorId) {
+        self.0.retain(|_, target| target != primary);
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&VectorId, &VectorId)> {
+        self.0.iter()
+    }
+}
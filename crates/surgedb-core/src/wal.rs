@@ -9,6 +9,8 @@
 //! - CRC32 checksums ensure data integrity
 
 use crate::error::{Error, Result};
+use crate::snapshot::crc32;
+use crate::transaction::Op;
 use crate::types::VectorId;
 use bincode::{deserialize, serialize};
 use serde::{Deserialize, Serialize};
@@ -37,6 +39,85 @@ pub enum WalEntry {
     Delete { id: VectorId },
     /// Checkpoint marker (snapshot was taken)
     Checkpoint { snapshot_id: u64 },
+    /// Point an alias at a vector's primary ID
+    Alias { alias: VectorId, id: VectorId },
+    /// Insert or replace a vector
+    Upsert {
+        id: VectorId,
+        vector: Vec<f32>,
+        #[serde(with = "crate::types::metadata_serde")]
+        metadata: Option<Value>,
+    },
+    /// Replace a vector's metadata
+    SetMetadata {
+        id: VectorId,
+        #[serde(with = "crate::types::metadata_serde")]
+        metadata: Option<Value>,
+    },
+    /// Writes applied all-or-nothing; a single record, so a torn write
+    /// fails its checksum and recovery skips the whole batch
+    Transaction { entries: Vec<WalEntry> },
+    /// Drop every vector and alias
+    Clear,
+}
+
+impl WalEntry {
+    /// The transaction op this entry replays as, if it is a data write
+    pub fn into_op(self) -> Option<Op> {
+        match self {
+            WalEntry::Insert {
+                id,
+                vector,
+                metadata,
+            } => Some(Op::Insert {
+                id,
+                vector,
+                metadata,
+            }),
+            WalEntry::Upsert {
+                id,
+                vector,
+                metadata,
+            } => Some(Op::Upsert {
+                id,
+                vector,
+                metadata,
+            }),
+            WalEntry::Delete { id } => Some(Op::Delete { id }),
+            WalEntry::SetMetadata { id, metadata } => Some(Op::SetMetadata { id, metadata }),
+            WalEntry::Checkpoint { .. }
+            | WalEntry::Alias { .. }
+            | WalEntry::Transaction { .. }
+            | WalEntry::Clear => None,
+        }
+    }
+}
+
+impl From<Op> for WalEntry {
+    fn from(op: Op) -> Self {
+        match op {
+            Op::Insert {
+                id,
+                vector,
+                metadata,
+            } => WalEntry::Insert {
+                id,
+                vector,
+                metadata,
+            },
+            Op::Upsert {
+                id,
+                vector,
+                metadata,
+            } => WalEntry::Upsert {
+                id,
+                vector,
+                metadata,
+            },
+            Op::Delete { id } => WalEntry::Delete { id },
+            Op::SetMetadata { id, metadata } => WalEntry::SetMetadata { id, metadata },
+        }
+    }
 }
 
 /// WAL record with checksum
@@ -67,23 +148,23 @@ impl WalRecord {
     }
 }
 
-/// Simple CRC32 implementation (IEEE polynomial)
-fn crc32(data: &[u8]) -> u32 {
-    let mut crc: u32 = 0xFFFFFFFF;
-    for byte in data {
-        crc ^= *byte as u32;
-        for _ in 0..8 {
-            crc = if crc & 1 != 0 {
-                (crc >> 1) ^ 0xEDB88320
-            } else {
-                crc >> 1
-            };
-        }
-    }
-    !crc
-}
+/// Name of the segment being appended to
+const CURRENT_SEGMENT: &str = "current.wal";
+
+/// Sealed segments are named `segment-<last seq>.wal`, zero-padded so that
+/// name order is sequence order
+const SEGMENT_PREFIX: &str = "segment-";
+const SEGMENT_SUFFIX: &str = ".wal";
+
+/// Size of the magic bytes and version at the start of every segment
+const HEADER_SIZE: u64 = 5;
 
 /// Write-Ahead Log manager
+///
+/// Entries go to `current.wal`. With a segment size set, a full current
+/// segment is sealed under a name recording its last sequence number and a
+/// fresh one is started; reads span the sealed segments in order, then the
+/// current one.
 pub struct Wal {
     /// Directory containing WAL files
     dir: PathBuf,
@@ -95,8 +176,12 @@ pub struct Wal {
     last_checkpoint_seq: u64,
     /// Maximum WAL size before auto-checkpoint (bytes)
     max_wal_size: u64,
-    /// Current WAL file size
+    /// Total size of all segments
     current_size: u64,
+    /// Seal the current segment once it would grow past this (0 = never)
+    segment_size: u64,
+    /// Size of the current segment
+    segment_bytes: u64,
 }
 
 impl Wal {
@@ -105,7 +190,7 @@ impl Wal {
         let dir = dir.as_ref().to_path_buf();
         fs::create_dir_all(&dir)?;
 
-        let wal_path = dir.join("current.wal");
+        let wal_path = dir.join(CURRENT_SEGMENT);
         let (file, seq, size) = if wal_path.exists() {
             // Open existing WAL and find last sequence number
             let mut f = OpenOptions::new().read(true).append(true).open(&wal_path)?;
@@ -119,42 +204,112 @@ impl Wal {
             f.seek(SeekFrom::End(0))?;
             (Some(BufWriter::new(f)), seq, size)
         } else {
-            // Create new WAL file
-            let f = OpenOptions::new()
-                .create(true)
-                .write(true)
-                .truncate(true)
-                .open(&wal_path)?;
-
-            let mut writer = BufWriter::new(f);
-            // Write header
-            writer.write_all(WAL_MAGIC)?;
-            writer.write_all(&[WAL_VERSION])?;
-            writer.flush()?;
-
-            (Some(writer), 0, 5) // 5 bytes for header
+            (Some(Self::create_segment(&wal_path)?), 0, HEADER_SIZE)
         };
 
+        // A segment sealed just before a crash leaves the current one empty
+        let seq = seq.max(Self::last_sealed_seq(&dir)?);
+        let mut sealed_size = 0;
+        for (_, path) in Self::sealed_segments(&dir)? {
+            sealed_size += fs::metadata(path)?.len();
+        }
+
         Ok(Self {
             dir,
             file,
             seq,
             last_checkpoint_seq: 0,
             max_wal_size: 64 * 1024 * 1024, // 64MB default
-            current_size: size,
+            current_size: sealed_size + size,
+            segment_size: 0,
+            segment_bytes: size,
         })
     }
 
-    /// Find the last sequence number in a WAL file
-    fn find_last_seq(path: &Path) -> Option<u64> {
-        let file = File::open(path).ok()?;
-        let mut reader = BufReader::new(file);
+    /// Open a WAL for reading only, e.g. one another process is writing
+    ///
+    /// Nothing is created on disk, and `append` must not be called on the
+    /// result: with no file open, entries would be silently dropped.
+    pub fn open_read_only(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        let wal_path = dir.join(CURRENT_SEGMENT);
+        let seq = Self::find_last_seq(&wal_path).unwrap_or(0);
+        let seq = seq.max(Self::last_sealed_seq(&dir)?);
 
-        // Skip header
-        let mut header = [0u8; 5];
-        reader.read_exact(&mut header).ok()?;
+        Ok(Self {
+            dir,
+            file: None,
+            seq,
+            last_checkpoint_seq: 0,
+            max_wal_size: 64 * 1024 * 1024,
+            current_size: 0,
+            segment_size: 0,
+            segment_bytes: 0,
+        })
+    }
+
+    /// Create a segment holding just the header
+    fn create_segment(path: &Path) -> Result<BufWriter<File>> {
+        let f = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(path)?;
+
+        let mut writer = BufWriter::new(f);
+        writer.write_all(WAL_MAGIC)?;
+        writer.write_all(&[WAL_VERSION])?;
+        writer.flush()?;
+        Ok(writer)
+    }
+
+    /// Sealed segments with the last sequence number each holds, oldest first
+    fn sealed_segments(dir: &Path) -> Result<Vec<(u64, PathBuf)>> {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut segments = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            let last_seq = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix(SEGMENT_PREFIX))
+                .and_then(|name| name.strip_suffix(SEGMENT_SUFFIX))
+                .and_then(|seq| seq.parse::<u64>().ok());
+            if let Some(last_seq) = last_seq {
+                segments.push((last_seq, path));
+            }
+        }
+        segments.sort();
+        Ok(segments)
+    }
+
+    fn last_sealed_seq(dir: &Path) -> Result<u64> {
+        Ok(Self::sealed_segments(dir)?
+            .last()
+            .map_or(0, |(last_seq, _)| *last_seq))
+    }
+
+    /// Every segment in replay order
+    fn segment_paths(&self) -> Result<Vec<PathBuf>> {
+        let mut paths: Vec<PathBuf> = Self::sealed_segments(&self.dir)?
+            .into_iter()
+            .map(|(_, path)| path)
+            .collect();
+        let current = self.dir.join(CURRENT_SEGMENT);
+        if current.exists() {
+            paths.push(current);
+        }
+        Ok(paths)
+    }
 
-        let mut last_seq = 0u64;
+    /// Read the records of one segment after its header, stopping at the
+    /// first one that is torn or fails to deserialize
+    fn read_segment(reader: &mut impl Read) -> Vec<WalRecord> {
+        let mut records = Vec::new();
 
         loop {
             // Read record length
@@ -170,38 +325,78 @@ impl Wal {
                 break;
             }
 
-            // Deserialize record
-            if let Ok(record) = deserialize::<WalRecord>(&data) {
-                if record.verify() {
-                    last_seq = record.seq;
-                }
+            match deserialize::<WalRecord>(&data) {
+                Ok(record) => records.push(record),
+                Err(_) => break,
             }
         }
 
-        Some(last_seq)
+        records
+    }
+
+    /// Find the last sequence number in a WAL file
+    fn find_last_seq(path: &Path) -> Option<u64> {
+        let file = File::open(path).ok()?;
+        let mut reader = BufReader::new(file);
+
+        // Skip header
+        let mut header = [0u8; 5];
+        reader.read_exact(&mut header).ok()?;
+
+        let last_seq = Self::read_segment(&mut reader)
+            .iter()
+            .rev()
+            .find(|record| record.verify())
+            .map(|record| record.seq);
+
+        Some(last_seq.unwrap_or(0))
     }
 
     /// Append an entry to the WAL
     pub fn append(&mut self, entry: WalEntry) -> Result<u64> {
-        self.seq += 1;
-        let record = WalRecord::new(self.seq, entry);
+        let record = WalRecord::new(self.seq + 1, entry);
 
         let data = serialize(&record).map_err(|e| Error::Serialization {
             message: e.to_string(),
         })?;
         let len = data.len() as u32;
+        let record_size = 4 + data.len() as u64;
 
+        if self.segment_size > 0
+            && self.segment_bytes > HEADER_SIZE
+            && self.segment_bytes + record_size > self.segment_size
+        {
+            self.rotate()?;
+        }
+
+        self.seq += 1;
         if let Some(ref mut file) = self.file {
             file.write_all(&len.to_le_bytes())?;
             file.write_all(&data)?;
             file.flush()?;
 
-            self.current_size += 4 + data.len() as u64;
+            self.current_size += record_size;
+            self.segment_bytes += record_size;
         }
 
         Ok(self.seq)
     }
 
+    /// Seal the current segment and start a new one
+    fn rotate(&mut self) -> Result<()> {
+        self.sync()?;
+
+        let sealed = self.dir.join(format!(
+            "{}{:020}{}",
+            SEGMENT_PREFIX, self.seq, SEGMENT_SUFFIX
+        ));
+        fs::rename(self.dir.join(CURRENT_SEGMENT), sealed)?;
+        self.file = Some(Self::create_segment(&self.dir.join(CURRENT_SEGMENT))?);
+        self.current_size += HEADER_SIZE;
+        self.segment_bytes = HEADER_SIZE;
+        Ok(())
+    }
+
     /// Sync WAL to disk (fsync)
     pub fn sync(&mut self) -> Result<()> {
         if let Some(ref mut file) = self.file {
@@ -213,62 +408,39 @@ impl Wal {
 
     /// Read all entries from the WAL (for recovery)
     pub fn read_all(&self) -> Result<Vec<WalEntry>> {
-        let wal_path = self.dir.join("current.wal");
-        if !wal_path.exists() {
-            return Ok(Vec::new());
-        }
-
-        let file = File::open(&wal_path)?;
-        let mut reader = BufReader::new(file);
-
-        // Read and verify header
-        let mut magic = [0u8; 4];
-        reader.read_exact(&mut magic)?;
-        if &magic != WAL_MAGIC {
-            return Err(Error::WalCorrupted {
-                message: format!(
-                    "Invalid WAL magic bytes: expected {:?}, got {:?}",
-                    WAL_MAGIC, magic
-                ),
-            });
-        }
-
-        let mut version = [0u8; 1];
-        reader.read_exact(&mut version)?;
-        if version[0] != WAL_VERSION {
-            return Err(Error::UnsupportedVersion {
-                version: version[0],
-                supported: "1",
-            });
-        }
-
         let mut entries = Vec::new();
 
-        loop {
-            // Read record length
-            let mut len_bytes = [0u8; 4];
-            if reader.read_exact(&mut len_bytes).is_err() {
-                break;
+        for path in self.segment_paths()? {
+            let file = File::open(&path)?;
+            let mut reader = BufReader::new(file);
+
+            // Read and verify header
+            let mut magic = [0u8; 4];
+            reader.read_exact(&mut magic)?;
+            if &magic != WAL_MAGIC {
+                return Err(Error::WalCorrupted {
+                    message: format!(
+                        "Invalid WAL magic bytes: expected {:?}, got {:?}",
+                        WAL_MAGIC, magic
+                    ),
+                });
             }
-            let len = u32::from_le_bytes(len_bytes) as usize;
 
-            // Read record data
-            let mut data = vec![0u8; len];
-            if reader.read_exact(&mut data).is_err() {
-                break;
+            let mut version = [0u8; 1];
+            reader.read_exact(&mut version)?;
+            if version[0] != WAL_VERSION {
+                return Err(Error::UnsupportedVersion {
+                    version: version[0],
+                    supported: "1",
+                });
             }
 
-            // Deserialize and verify record
-            match deserialize::<WalRecord>(&data) {
-                Ok(record) => {
-                    if record.verify() {
-                        entries.push(record.entry);
-                    } else {
-                        // Corrupted record, stop reading
-                        break;
-                    }
+            for record in Self::read_segment(&mut reader) {
+                if !record.verify() {
+                    // Corrupted record, stop reading
+                    return Ok(entries);
                 }
-                Err(_) => break,
+                entries.push(record.entry);
             }
         }
 
@@ -277,68 +449,69 @@ impl Wal {
 
     /// Read entries after a specific sequence number (for recovery after checkpoint)
     pub fn read_after(&self, after_seq: u64) -> Result<Vec<WalEntry>> {
-        let wal_path = self.dir.join("current.wal");
-        if !wal_path.exists() {
-            return Ok(Vec::new());
-        }
-
-        let file = File::open(&wal_path)?;
-        let mut reader = BufReader::new(file);
-
-        // Skip header
-        let mut header = [0u8; 5];
-        reader.read_exact(&mut header)?;
+        Ok(self
+            .read_records_after(after_seq)?
+            .into_iter()
+            .map(|(_, entry)| entry)
+            .collect())
+    }
 
+    /// Read entries after a specific sequence number, paired with their sequence numbers
+    pub fn read_records_after(&self, after_seq: u64) -> Result<Vec<(u64, WalEntry)>> {
         let mut entries = Vec::new();
 
-        loop {
-            let mut len_bytes = [0u8; 4];
-            if reader.read_exact(&mut len_bytes).is_err() {
-                break;
-            }
-            let len = u32::from_le_bytes(len_bytes) as usize;
-
-            let mut data = vec![0u8; len];
-            if reader.read_exact(&mut data).is_err() {
-                break;
-            }
-
-            match deserialize::<WalRecord>(&data) {
-                Ok(record) => {
-                    if record.verify() && record.seq > after_seq {
-                        entries.push(record.entry);
-                    }
-                }
-                Err(_) => break,
-            }
+        for path in self.segment_paths()? {
+            // A sealed segment can be pruned between listing and opening
+            let file = match File::open(&path) {
+                Ok(file) => file,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            let mut reader = BufReader::new(file);
+
+            // Skip header
+            let mut header = [0u8; 5];
+            reader.read_exact(&mut header)?;
+
+            entries.extend(
+                Self::read_segment(&mut reader)
+                    .into_iter()
+                    .filter(|record| record.verify() && record.seq > after_seq)
+                    .map(|record| (record.seq, record.entry)),
+            );
         }
 
         Ok(entries)
     }
 
     /// Clear the WAL (after successful checkpoint)
+    ///
+    /// Sealed segments all precede the checkpoint, so they are deleted.
     pub fn clear(&mut self) -> Result<()> {
         // Close current file
         self.file = None;
 
-        let wal_path = self.dir.join("current.wal");
-
         // Create new empty WAL
-        let f = OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(&wal_path)?;
-
-        let mut writer = BufWriter::new(f);
-        writer.write_all(WAL_MAGIC)?;
-        writer.write_all(&[WAL_VERSION])?;
-        writer.flush()?;
-
-        self.file = Some(writer);
+        let wal_path = self.dir.join(CURRENT_SEGMENT);
+        self.file = Some(Self::create_segment(&wal_path)?);
         self.last_checkpoint_seq = self.seq;
-        self.current_size = 5;
+        self.current_size = HEADER_SIZE;
+        self.segment_bytes = HEADER_SIZE;
+
+        self.prune_segments(self.last_checkpoint_seq)
+    }
 
+    /// Delete sealed segments whose entries all have a sequence number of at
+    /// most `covered_seq`, i.e. that a checkpoint at that point makes redundant
+    fn prune_segments(&mut self, covered_seq: u64) -> Result<()> {
+        for (last_seq, path) in Self::sealed_segments(&self.dir)? {
+            if last_seq > covered_seq {
+                break;
+            }
+            let size = fs::metadata(&path)?.len();
+            fs::remove_file(&path)?;
+            self.current_size = self.current_size.saturating_sub(size);
+        }
         Ok(())
     }
 
@@ -361,6 +534,12 @@ impl Wal {
     pub fn set_max_size(&mut self, size: u64) {
         self.max_wal_size = size;
     }
+
+    /// Seal the current segment and start a new one once it would grow past
+    /// `size` bytes (0 keeps a single segment)
+    pub fn set_segment_size(&mut self, size: u64) {
+        self.segment_size = size;
+    }
 }
 
 impl Drop for Wal {
@@ -508,6 +687,47 @@ mod tests {
         assert_eq!(seq, 6); // Sequence continues
     }
 
+    #[test]
+    fn test_wal_segment_rotation() {
+        let dir = tempdir().unwrap();
+        let insert = |i: usize| WalEntry::Insert {
+            id: format!("v{}", i).into(),
+            vector: vec![i as f32; 8],
+            metadata: None,
+        };
+
+        {
+            let mut wal = Wal::open(dir.path()).unwrap();
+            wal.set_segment_size(256);
+            for i in 0..20 {
+                wal.append(insert(i)).unwrap();
+            }
+            let sealed = Wal::sealed_segments(dir.path()).unwrap();
+            assert!(sealed.len() > 2);
+            for (_, path) in &sealed {
+                assert!(fs::metadata(path).unwrap().len() <= 256);
+            }
+            // A crash right after sealing leaves an empty current segment
+            wal.rotate().unwrap();
+        }
+
+        let mut wal = Wal::open(dir.path()).unwrap();
+        assert_eq!(wal.seq(), 20);
+        assert_eq!(Wal::open_read_only(dir.path()).unwrap().seq(), 20);
+        let seqs: Vec<u64> = wal
+            .read_records_after(5)
+            .unwrap()
+            .iter()
+            .map(|(seq, _)| *seq)
+            .collect();
+        assert_eq!(seqs, (6..=20).collect::<Vec<_>>());
+        assert_eq!(wal.read_all().unwrap().len(), 20);
+
+        wal.clear().unwrap();
+        assert!(Wal::sealed_segments(dir.path()).unwrap().is_empty());
+        assert_eq!(wal.append(insert(20)).unwrap(), 21);
+    }
+
     #[test]
     fn test_wal_with_metadata() {
         let dir = tempdir().unwrap();
@@ -0,0 +1,40 @@
+use surgedb_core::{PersistentConfig, PersistentVectorDb};
+use tempfile::tempdir;
+
+fn config() -> PersistentConfig {
+    PersistentConfig {
+        dimensions: 4,
+        sync_writes: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_aliases_survive_wal_replay_and_snapshot() {
+    let dir = tempdir().unwrap();
+
+    {
+        let mut db = PersistentVectorDb::open(dir.path(), config()).unwrap();
+        db.insert("uuid-1", &[1.0, 0.0, 0.0, 0.0], None).unwrap();
+        db.insert("uuid-2", &[0.0, 1.0, 0.0, 0.0], None).unwrap();
+        db.add_alias("first", "uuid-1").unwrap();
+        db.add_alias("second", "uuid-2").unwrap();
+    }
+
+    // Recovered from the WAL
+    {
+        let mut db = PersistentVectorDb::open(dir.path(), config()).unwrap();
+        assert!(db.get("first").unwrap().is_some());
+        assert!(db.delete("second").unwrap());
+        db.checkpoint().unwrap();
+    }
+
+    // Recovered from the snapshot
+    {
+        let db = PersistentVectorDb::open(dir.path(), config()).unwrap();
+        let (vector, _) = db.get("first").unwrap().unwrap();
+        assert_eq!(vector, vec![1.0, 0.0, 0.0, 0.0]);
+        assert!(db.get("second").unwrap().is_none());
+        assert!(db.get("uuid-2").unwrap().is_none());
+    }
+}
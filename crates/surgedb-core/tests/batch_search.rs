@@ -0,0 +1,36 @@
+use surgedb_core::{Config, Database, DistanceMetric, QuantizationType};
+
+#[test]
+fn test_search_batch_per_query_k() {
+    for quantization in [QuantizationType::None, QuantizationType::SQ8] {
+        let db = Database::new();
+        let config = Config {
+            dimensions: 4,
+            distance_metric: DistanceMetric::Euclidean,
+            quantization,
+            ..Default::default()
+        };
+        db.create_collection("docs", config).unwrap();
+        let collection = db.get_collection("docs").unwrap();
+        for i in 0..20 {
+            let v = [i as f32, 1.0, 0.0, 0.0];
+            collection.insert(format!("vec{}", i), &v, None).unwrap();
+        }
+
+        let queries = vec![
+            (vec![3.0, 1.0, 0.0, 0.0], 1),
+            (vec![12.0, 1.0, 0.0, 0.0], 5),
+        ];
+        let results = collection.search_batch(&queries, None).unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].len(), 1);
+        assert_eq!(results[1].len(), 5);
+        assert_eq!(results[0][0].0.as_str(), "vec3");
+        assert_eq!(results[1][0].0.as_str(), "vec12");
+
+        // A bad query fails the whole batch
+        let bad = vec![(vec![1.0, 0.0], 1)];
+        assert!(collection.search_batch(&bad, None).is_err());
+    }
+}
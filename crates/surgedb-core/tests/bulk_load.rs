@@ -0,0 +1,89 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde_json::json;
+use surgedb_core::{
+    BatchReport, DuplicatePolicy, HnswConfig, PersistentConfig, PersistentVectorDb,
+    SnapshotManager, Wal, WalEntry,
+};
+use tempfile::tempdir;
+
+const DIMENSIONS: usize = 32;
+
+fn dataset(count: usize) -> Vec<(String, Vec<f32>)> {
+    let mut rng = StdRng::seed_from_u64(11);
+    (0..count)
+        .map(|i| {
+            let vector = (0..DIMENSIONS).map(|_| rng.gen::<f32>() - 0.5).collect();
+            (format!("vec{}", i), vector)
+        })
+        .collect()
+}
+
+#[test]
+fn test_bulk_load_skips_wal_and_recovers() {
+    let data = dataset(2000);
+    // A small WAL, so per-write logging would checkpoint many times over
+    let config = PersistentConfig {
+        dimensions: DIMENSIONS,
+        hnsw: HnswConfig {
+            ef_construction: 32,
+            ..HnswConfig::memory_optimized()
+        },
+        sync_writes: true,
+        checkpoint_threshold: 1024,
+        ..Default::default()
+    };
+
+    let dir = tempdir().unwrap();
+    {
+        let mut db = PersistentVectorDb::open(dir.path(), config.clone()).unwrap();
+        db.insert("existing", &data[0].1, None).unwrap();
+        let items = data
+            .iter()
+            .map(|(id, vector)| (id.as_str(), vector.clone(), Some(json!({"id": id}))))
+            // Already-present IDs are skipped
+            .chain(std::iter::once(("existing", data[1].1.clone(), None)));
+        assert_eq!(
+            db.bulk_load(items, DuplicatePolicy::Skip).unwrap(),
+            BatchReport {
+                inserted: data.len(),
+                skipped: 1,
+                overwritten: 0,
+            }
+        );
+
+        // The whole load went into a single snapshot, not the WAL
+        let snapshots = SnapshotManager::new(db.snapshot_dir()).unwrap();
+        let (snapshot_id, _) = snapshots.list_snapshots().unwrap().pop().unwrap();
+        assert_eq!(snapshots.list_snapshots().unwrap().len(), 1);
+        assert_eq!(
+            snapshots.load_latest().unwrap().unwrap().len(),
+            data.len() + 1
+        );
+
+        // Normal logging resumes after the load
+        db.insert("after", &data[2].1, None).unwrap();
+        let wal = Wal::open_read_only(dir.path().join("wal")).unwrap();
+        let entries = wal.read_all().unwrap();
+        assert_eq!(entries.len(), 2, "{:?}", entries);
+        assert!(matches!(
+            entries[0],
+            WalEntry::Checkpoint { snapshot_id: id } if id == snapshot_id
+        ));
+        assert!(matches!(&entries[1], WalEntry::Insert { id, .. } if id.as_str() == "after"));
+    }
+
+    let db = PersistentVectorDb::open(dir.path(), config).unwrap();
+    assert_eq!(db.len(), data.len() + 2);
+    let (vector, metadata) = db.get("existing").unwrap().unwrap();
+    assert_eq!(vector, data[0].1);
+    assert_eq!(metadata, None);
+    assert!(db.get("after").unwrap().is_some());
+    for (id, vector) in data.iter().step_by(97) {
+        let (stored, metadata) = db.get(id).unwrap().unwrap();
+        assert_eq!(&stored, vector);
+        assert_eq!(metadata, Some(json!({"id": id})));
+        // "existing" and "after" reuse dataset vectors, so check the distance
+        let results = db.search(vector, 1, None).unwrap();
+        assert!(results[0].1 < 1e-5);
+    }
+}
@@ -0,0 +1,141 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use surgedb_core::{Config, DistanceMetric, Error, RoutingConfig, VectorDb};
+
+const DIM: usize = 32;
+const CLUSTERS: usize = 16;
+const PER_CLUSTER: usize = 200;
+const K: usize = 10;
+
+/// Tight gaussian-ish blobs around random centers
+fn clustered(rng: &mut StdRng, centers: &[Vec<f32>], n: usize) -> Vec<Vec<f32>> {
+    (0..n)
+        .map(|i| {
+            centers[i % centers.len()]
+                .iter()
+                .map(|c| c + rng.gen_range(-0.5..0.5))
+                .collect()
+        })
+        .collect()
+}
+
+fn recall(db: &VectorDb, queries: &[Vec<f32>], nprobe: Option<usize>) -> f32 {
+    let mut found = 0;
+    for query in queries {
+        let exact = db
+            .search_exact_with_metric(query, K, DistanceMetric::Euclidean, None)
+            .unwrap();
+        let approx = match nprobe {
+            Some(nprobe) => db.search_with_nprobe(query, K, nprobe, None).unwrap(),
+            None => db.search(query, K, None).unwrap(),
+        };
+        found += exact
+            .iter()
+            .filter(|(id, _, _)| approx.iter().any(|(hit, _, _)| hit == id))
+            .count();
+    }
+    found as f32 / (queries.len() * K) as f32
+}
+
+#[test]
+fn test_routed_search_keeps_recall_on_clustered_data() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let centers: Vec<Vec<f32>> = (0..CLUSTERS)
+        .map(|_| (0..DIM).map(|_| rng.gen_range(-10.0..10.0)).collect())
+        .collect();
+    let mut db = VectorDb::new(Config {
+        dimensions: DIM,
+        distance_metric: DistanceMetric::Euclidean,
+        ..Default::default()
+    })
+    .unwrap();
+    for (i, vector) in clustered(&mut rng, &centers, CLUSTERS * PER_CLUSTER)
+        .iter()
+        .enumerate()
+    {
+        db.insert(format!("v{}", i), vector, None).unwrap();
+    }
+    let queries = clustered(&mut rng, &centers, 100);
+
+    let err = db.search_with_nprobe(&queries[0], K, 2, None).unwrap_err();
+    assert!(matches!(err, Error::InvalidConfig(_)), "{}", err);
+    let bad = RoutingConfig {
+        nprobe: CLUSTERS + 1,
+        num_centroids: CLUSTERS,
+        ..Default::default()
+    };
+    assert!(db.train_routing(bad).is_err());
+
+    let full = recall(&db, &queries, None);
+    db.train_routing(RoutingConfig {
+        num_centroids: CLUSTERS,
+        nprobe: 4,
+        ..Default::default()
+    })
+    .unwrap();
+    assert_eq!(db.routing().unwrap().nprobe, 4);
+
+    let routed = recall(&db, &queries, None);
+    assert!(routed >= 0.9, "routed recall {} (full {})", routed, full);
+    let widest = recall(&db, &queries, Some(CLUSTERS));
+    assert!(widest >= full - 0.02, "nprobe {} recall {}", CLUSTERS, widest);
+
+    // Vectors inserted after training are routed to the existing centroids
+    let late = clustered(&mut rng, &centers, 1).remove(0);
+    db.insert("late", &late, None).unwrap();
+    let hits = db.search(&late, 1, None).unwrap();
+    assert_eq!(hits[0].0.as_str(), "late");
+
+    db.clear_routing();
+    assert!(db.routing().is_none());
+    assert!(recall(&db, &queries, None) >= full - 0.02);
+}
+
+#[test]
+fn test_search_debug_reports_probed_lists() {
+    // Two far-apart blobs, which two centroids partition exactly
+    let mut rng = StdRng::seed_from_u64(11);
+    let centers = vec![vec![20.0; DIM], vec![-20.0; DIM]];
+    let mut db = VectorDb::new(Config {
+        dimensions: DIM,
+        distance_metric: DistanceMetric::Euclidean,
+        ..Default::default()
+    })
+    .unwrap();
+    for (i, vector) in clustered(&mut rng, &centers, 400).iter().enumerate() {
+        db.insert(format!("v{}", i), vector, None).unwrap();
+    }
+    let queries = clustered(&mut rng, &centers, 10);
+
+    let err = db.search_debug(&queries[0], K, None, None).unwrap_err();
+    assert!(matches!(err, Error::InvalidConfig(_)), "{}", err);
+    assert_eq!(db.routing_list("v0"), None);
+
+    db.train_routing(RoutingConfig {
+        num_centroids: 2,
+        nprobe: 1,
+        ..Default::default()
+    })
+    .unwrap();
+    assert_ne!(db.routing_list("v0"), db.routing_list("v1"));
+
+    for query in &queries {
+        let exact = db
+            .search_exact_with_metric(query, 1, DistanceMetric::Euclidean, None)
+            .unwrap();
+        let nearest_list = db.routing_list(&exact[0].0.as_str()).unwrap();
+
+        // The one probed list holds the true neighbor and every candidate
+        let (hits, debug) = db.search_debug(query, K, None, None).unwrap();
+        assert_eq!(debug.probed.len(), 1);
+        assert_eq!(debug.probed[0].list, nearest_list);
+        assert_eq!(debug.probed[0].candidates, 2 * K);
+        assert_eq!(hits[0].0, exact[0].0);
+
+        // Probing the other blob too adds nothing
+        let (_, widest) = db.search_debug(query, K, Some(2), None).unwrap();
+        assert_eq!(widest.probed.len(), 2);
+        assert_eq!(widest.probed[0], debug.probed[0]);
+        assert_eq!(widest.probed[1].candidates, 0);
+    }
+}
@@ -0,0 +1,119 @@
+use surgedb_core::{
+    Config, Error, MmapConfig, MmapVectorDb, PersistentConfig, PersistentVectorDb, QuantizedConfig,
+    QuantizedVectorDb, VectorDb,
+};
+use tempfile::tempdir;
+
+fn vector(i: usize) -> Vec<f32> {
+    (0..8).map(|d| ((i * 8 + d) as f32).sin()).collect()
+}
+
+#[test]
+fn test_clear_restarts_internal_ids() {
+    let mut db = VectorDb::new(Config {
+        dimensions: 8,
+        ..Default::default()
+    })
+    .unwrap();
+    for i in 0..50 {
+        db.insert(format!("v{}", i), &vector(i), None).unwrap();
+    }
+    db.add_alias("first", "v0").unwrap();
+    db.delete("v1").unwrap();
+
+    db.clear();
+    assert!(db.is_empty());
+    assert_eq!(db.physical_len(), 0);
+    assert!(db.get("first").unwrap().is_none());
+    assert!(matches!(
+        db.search(&vector(0), 5, None),
+        Err(Error::EmptyIndex)
+    ));
+
+    db.insert("v0", &vector(0), None).unwrap();
+    assert_eq!(db.neighborhood("v0").unwrap().0.as_usize(), 0);
+    assert_eq!(db.search(&vector(0), 5, None).unwrap()[0].0.as_str(), "v0");
+
+    let mut quantized = QuantizedVectorDb::new(QuantizedConfig {
+        dimensions: 8,
+        ..Default::default()
+    })
+    .unwrap();
+    for i in 0..50 {
+        quantized
+            .insert(format!("v{}", i), &vector(i), None)
+            .unwrap();
+    }
+    quantized.clear();
+    assert!(quantized.is_empty());
+    assert_eq!(quantized.physical_len(), 0);
+    quantized.insert("v7", &vector(7), None).unwrap();
+    assert_eq!(
+        quantized.search(&vector(7), 1, None).unwrap()[0].0.as_str(),
+        "v7"
+    );
+}
+
+#[test]
+fn test_persistent_clear_survives_recovery() {
+    let dir = tempdir().unwrap();
+    let config = PersistentConfig {
+        dimensions: 8,
+        ..Default::default()
+    };
+
+    {
+        let mut db = PersistentVectorDb::open(dir.path(), config.clone()).unwrap();
+        for i in 0..50 {
+            db.insert(format!("v{}", i), &vector(i), None).unwrap();
+        }
+        // The truncation must also undo what a snapshot already holds
+        db.checkpoint().unwrap();
+        db.insert("late", &vector(50), None).unwrap();
+
+        db.clear().unwrap();
+        assert!(db.is_empty());
+        db.insert("after", &vector(1), None).unwrap();
+        assert_eq!(db.neighborhood("after").unwrap().0.as_usize(), 0);
+    }
+
+    let db = PersistentVectorDb::open(dir.path(), config).unwrap();
+    assert_eq!(db.len(), 1);
+    assert_eq!(db.physical_len(), 1);
+    assert!(db.get("v0").unwrap().is_none());
+    assert!(db.get("late").unwrap().is_none());
+    assert_eq!(
+        db.search(&vector(1), 5, None).unwrap()[0].0.as_str(),
+        "after"
+    );
+}
+
+#[test]
+fn test_mmap_clear_truncates_files() {
+    let dir = tempdir().unwrap();
+    let config = MmapConfig {
+        dimensions: 8,
+        ..Default::default()
+    };
+
+    {
+        let mut db = MmapVectorDb::open(dir.path(), config.clone()).unwrap();
+        for i in 0..50 {
+            db.insert(format!("v{}", i), &vector(i)).unwrap();
+        }
+        let full = db.disk_usage();
+
+        db.clear().unwrap();
+        assert!(db.is_empty());
+        assert!(db.disk_usage() < full);
+        assert!(matches!(db.search(&vector(0), 5), Err(Error::EmptyIndex)));
+
+        db.insert("after", &vector(3)).unwrap();
+        db.sync().unwrap();
+        db.save_index().unwrap();
+    }
+
+    let db = MmapVectorDb::open(dir.path(), config).unwrap();
+    assert_eq!(db.len(), 1);
+    assert_eq!(db.search(&vector(3), 5).unwrap()[0].0.as_str(), "after");
+}
@@ -0,0 +1,237 @@
+use serde_json::json;
+use surgedb_core::{
+    Config, Database, DistanceMetric, HnswConfig, PersistentConfig, PersistentVectorDb,
+    QuantizationType, VectorDb,
+};
+use tempfile::tempdir;
+
+fn vector(i: usize) -> Vec<f32> {
+    (0..16)
+        .map(|d| ((i * 31 + d * 7) % 97) as f32 / 97.0)
+        .collect()
+}
+
+#[test]
+fn test_compact_shrinks_memory_and_disk() {
+    let dir = tempdir().unwrap();
+    let config = PersistentConfig {
+        dimensions: 16,
+        ..Default::default()
+    };
+
+    {
+        let mut db = PersistentVectorDb::open(dir.path(), config.clone()).unwrap();
+        for i in 0..500 {
+            db.insert(format!("v{}", i), &vector(i), Some(json!({"i": i})))
+                .unwrap();
+        }
+        db.checkpoint().unwrap();
+        for i in 0..400 {
+            assert!(db.delete(format!("v{}", i)).unwrap());
+        }
+        db.add_alias("last", "v499").unwrap();
+        db.sync().unwrap();
+
+        let memory_before = db.memory_usage();
+        let disk_before = db.disk_usage();
+
+        assert_eq!(db.compact().unwrap(), 400);
+        assert_eq!(db.len(), 100);
+        assert_eq!(db.physical_len(), 100);
+        assert!(db.memory_usage() < memory_before);
+        assert!(db.disk_usage() < disk_before);
+
+        let results = db.search(&vector(450), 1, None).unwrap();
+        assert_eq!(results[0].0.as_str(), "v450");
+    }
+
+    let db = PersistentVectorDb::open(dir.path(), config).unwrap();
+    assert_eq!(db.len(), 100);
+    assert!(db.get("v0").unwrap().is_none());
+    assert_eq!(
+        db.get("last").unwrap().unwrap(),
+        (vector(499), Some(json!({"i": 499})))
+    );
+    let results = db.search(&vector(450), 1, None).unwrap();
+    assert_eq!(results[0].0.as_str(), "v450");
+}
+
+/// Like `vector`, but distinct for every `i`
+fn point(i: usize) -> Vec<f32> {
+    let mut v = vector(i);
+    v[0] = i as f32;
+    v
+}
+
+fn in_memory_config() -> Config {
+    Config {
+        dimensions: 16,
+        distance_metric: DistanceMetric::Euclidean,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_in_memory_compact_after_delete_and_reinsert() {
+    let mut db = VectorDb::new(in_memory_config()).unwrap();
+    for i in 0..500 {
+        db.insert(format!("v{}", i), &point(i), Some(json!({"i": i})))
+            .unwrap();
+    }
+    for i in 0..400 {
+        assert!(db.delete(format!("v{}", i)).unwrap());
+    }
+    // Reinsert a few deleted IDs and overwrite a live one
+    for i in 0..50 {
+        db.insert(format!("v{}", i), &point(i), Some(json!({"i": i})))
+            .unwrap();
+    }
+    db.upsert("v499", &point(499), Some(json!({"i": -1})))
+        .unwrap();
+    assert_eq!(db.len(), 150);
+    assert_eq!(db.physical_len(), 551);
+    assert_eq!(db.deleted_count(), 401);
+
+    // Tombstoned slots are skipped by search and listing
+    let results = db.search(&point(200), 5, None).unwrap();
+    assert!(results
+        .iter()
+        .all(|(id, _, _)| db.get(&id.as_str()).unwrap().is_some()));
+    assert_eq!(db.list(0, 1000).len(), 150);
+
+    let memory_before = db.memory_usage();
+    assert_eq!(db.compact().unwrap(), 401);
+    assert_eq!(db.deleted_count(), 0);
+    assert_eq!(db.len(), 150);
+    assert_eq!(db.physical_len(), 150);
+    assert!(db.memory_usage() < memory_before);
+    assert_eq!(db.compact().unwrap(), 0);
+
+    for i in [0, 49, 450] {
+        let results = db.search(&point(i), 1, None).unwrap();
+        assert_eq!(results[0].0.as_str(), format!("v{}", i));
+        assert_eq!(results[0].2, Some(json!({"i": i})));
+    }
+    assert_eq!(
+        db.get("v499").unwrap().unwrap(),
+        (point(499), Some(json!({"i": -1})))
+    );
+    assert!(db.get("v100").unwrap().is_none());
+
+    // New inserts take the slots after the compacted ones
+    db.insert("fresh", &[2.0; 16], None).unwrap();
+    assert_eq!(db.physical_len(), 151);
+    let results = db.search(&[2.0; 16], 1, None).unwrap();
+    assert_eq!(results[0].0.as_str(), "fresh");
+}
+
+#[test]
+fn test_auto_compact_past_tombstone_fraction() {
+    let db = Database::new();
+    db.create_collection(
+        "docs",
+        Config {
+            auto_compact_fraction: 0.25,
+            ..in_memory_config()
+        },
+    )
+    .unwrap();
+    let docs = db.get_collection("docs").unwrap();
+    for i in 0..100 {
+        docs.insert(format!("v{}", i), &point(i), None).unwrap();
+    }
+    for i in 0..25 {
+        docs.delete(&format!("v{}", i)).unwrap();
+    }
+    // Exactly at the threshold, not past it
+    docs.wait_for_compaction();
+    assert_eq!(docs.deleted_count(), 25);
+    docs.delete("v25").unwrap();
+    docs.wait_for_compaction();
+    assert_eq!(docs.deleted_count(), 0);
+    assert_eq!(docs.len(), 74);
+
+    let results = docs.search(&point(60), 1, None).unwrap();
+    assert_eq!(results[0].0.as_str(), "v60");
+
+    assert!(VectorDb::new(Config {
+        auto_compact_fraction: 1.0,
+        ..in_memory_config()
+    })
+    .is_err());
+}
+
+#[test]
+fn test_every_collection_type_compacts() {
+    let dir = tempdir().unwrap();
+    let db = Database::open(dir.path()).unwrap();
+    let config = Config {
+        auto_compact_fraction: 0.25,
+        ..in_memory_config()
+    };
+    db.create_collection("persistent", config.clone()).unwrap();
+    let memory = Database::new();
+    memory
+        .create_collection(
+            "quantized",
+            Config {
+                quantization: QuantizationType::SQ8,
+                ..config
+            },
+        )
+        .unwrap();
+
+    for docs in [
+        db.get_collection("persistent").unwrap(),
+        memory.get_collection("quantized").unwrap(),
+    ] {
+        for i in 0..100 {
+            docs.insert(format!("v{}", i), &point(i), Some(json!({"i": i})))
+                .unwrap();
+        }
+        for i in 0..20 {
+            docs.upsert(format!("v{}", i), &point(i), Some(json!({"i": -1})))
+                .unwrap();
+        }
+        // The last delete takes tombstones past a quarter of the 120 slots
+        for i in 20..31 {
+            docs.delete(&format!("v{}", i)).unwrap();
+        }
+        docs.wait_for_compaction();
+        assert_eq!(docs.deleted_count(), 0);
+        assert_eq!(docs.len(), 89);
+        assert!(docs.get("v25").unwrap().is_none());
+        assert_eq!(docs.get("v5").unwrap().unwrap().1, Some(json!({"i": -1})));
+        let results = docs.search(&point(60), 1, None).unwrap();
+        assert_eq!(results[0].0.as_str(), "v60");
+    }
+
+    drop(db);
+    let db = Database::open(dir.path()).unwrap();
+    let docs = db.get_collection("persistent").unwrap();
+    assert_eq!(docs.len(), 89);
+    assert_eq!(docs.deleted_count(), 0);
+}
+
+#[test]
+fn test_compact_honors_parallel_build() {
+    let mut db = VectorDb::new(Config {
+        hnsw: HnswConfig {
+            parallel_build: true,
+            ..Default::default()
+        },
+        ..in_memory_config()
+    })
+    .unwrap();
+    for i in 0..500 {
+        db.insert(format!("v{}", i), &point(i), None).unwrap();
+    }
+    for i in 0..250 {
+        db.delete(format!("v{}", i)).unwrap();
+    }
+    assert_eq!(db.compact().unwrap(), 250);
+    for i in [250, 300, 499] {
+        let results = db.search(&point(i), 1, None).unwrap();
+        assert_eq!(results[0].0.as_str(), format!("v{}", i));
+    }
+}
@@ -0,0 +1,98 @@
+//! Inserts and searches interleaved from several threads on one `VectorDb`
+
+#![cfg(feature = "parallel")]
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+use surgedb_core::{Config, Error, VectorDb};
+
+const DIM: usize = 16;
+const WRITERS: usize = 4;
+const READERS: usize = 4;
+const PER_WRITER: usize = 250;
+
+fn vector(i: usize) -> Vec<f32> {
+    (0..DIM)
+        .map(|d| ((i * DIM + d) as f32 * 0.37).sin())
+        .collect()
+}
+
+#[test]
+fn test_inserts_and_searches_interleave_without_deadlock() {
+    let db = Arc::new(
+        VectorDb::new(Config {
+            dimensions: DIM,
+            ..Default::default()
+        })
+        .unwrap(),
+    );
+    let (done_tx, done_rx) = mpsc::channel();
+
+    let worker_db = Arc::clone(&db);
+    let searches = thread::spawn(move || {
+        let db = worker_db;
+        let writing = Arc::new(AtomicBool::new(true));
+        let searches = Arc::new(AtomicUsize::new(0));
+
+        let readers: Vec<_> = (0..READERS)
+            .map(|r| {
+                let (db, writing, searches) =
+                    (Arc::clone(&db), Arc::clone(&writing), Arc::clone(&searches));
+                thread::spawn(move || {
+                    let mut i = r;
+                    while writing.load(Ordering::Relaxed) {
+                        match db.search(&vector(i % (WRITERS * PER_WRITER)), 10, None) {
+                            Ok(results) => {
+                                assert!(results.len() <= 10);
+                                // Only fully inserted vectors are returned
+                                for (id, _, _) in &results {
+                                    assert!(db.get(&id.as_str()).unwrap().is_some());
+                                }
+                            }
+                            Err(Error::EmptyIndex) => {}
+                            Err(e) => panic!("search failed: {}", e),
+                        }
+                        searches.fetch_add(1, Ordering::Relaxed);
+                        i += READERS;
+                    }
+                })
+            })
+            .collect();
+
+        let writers: Vec<_> = (0..WRITERS)
+            .map(|w| {
+                let db = Arc::clone(&db);
+                thread::spawn(move || {
+                    for n in 0..PER_WRITER {
+                        let i = n * WRITERS + w;
+                        db.insert_concurrent(format!("v{}", i), &vector(i), None)
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        writing.store(false, Ordering::Relaxed);
+        for reader in readers {
+            reader.join().unwrap();
+        }
+        let searches = searches.load(Ordering::Relaxed);
+        done_tx.send(()).unwrap();
+        searches
+    });
+
+    // A deadlock would otherwise hang the test run instead of failing it
+    if let Err(mpsc::RecvTimeoutError::Timeout) = done_rx.recv_timeout(Duration::from_secs(120)) {
+        panic!("inserts and searches deadlocked");
+    }
+    assert!(searches.join().unwrap() > 0);
+
+    assert_eq!(db.len(), WRITERS * PER_WRITER);
+    let health = db.self_recall(200, 10, 7);
+    assert!(health.recall >= 0.95, "self recall {}", health.recall);
+}
@@ -0,0 +1,181 @@
+use serde_json::json;
+use surgedb_core::{
+    BatchReport, Config, Database, DistanceMetric, DuplicatePolicy, Error, InsertMode, Op,
+    PersistentConfig, PersistentVectorDb, QuantizationType, VectorDb, VectorId,
+};
+use tempfile::tempdir;
+
+type Item = (VectorId, Vec<f32>, Option<serde_json::Value>);
+
+const MODE: InsertMode = InsertMode::DedupByContent { epsilon: 0.0 };
+
+/// "a" is stored as [1, 0, 0]
+fn seeded() -> VectorDb {
+    let mut db = VectorDb::new(Config {
+        dimensions: 3,
+        distance_metric: DistanceMetric::Euclidean,
+        insert_mode: MODE,
+        ..Default::default()
+    })
+    .unwrap();
+    db.insert("a", &[1.0, 0.0, 0.0], None).unwrap();
+    db
+}
+
+/// A copy of "a", a new vector, and a repeat of that new vector
+fn batch() -> Vec<Item> {
+    vec![
+        ("copy".into(), vec![1.0, 0.0, 0.0], None),
+        ("b".into(), vec![0.0, 1.0, 0.0], None),
+        ("b2".into(), vec![0.0, 1.0, 0.0], None),
+    ]
+}
+
+fn ids(db: &VectorDb) -> Vec<String> {
+    let mut ids: Vec<String> = db
+        .list(0, usize::MAX)
+        .into_iter()
+        .map(|(id, _)| id.to_string())
+        .collect();
+    ids.sort();
+    ids
+}
+
+#[test]
+fn test_batch_writes_skip_stored_and_repeated_content() {
+    let mut db = seeded();
+    db.insert_batch(batch()).unwrap();
+    assert_eq!(ids(&db), ["a", "b"]);
+
+    let mut db = seeded();
+    db.upsert_batch(batch()).unwrap();
+    assert_eq!(ids(&db), ["a", "b"]);
+
+    let mut db = seeded();
+    let report = db.import_batch(batch(), DuplicatePolicy::Skip).unwrap();
+    assert_eq!(
+        report,
+        BatchReport {
+            inserted: 1,
+            skipped: 2,
+            overwritten: 0,
+        }
+    );
+    assert_eq!(ids(&db), ["a", "b"]);
+
+    let mut db = seeded();
+    db.transaction(
+        batch()
+            .into_iter()
+            .map(|(id, vector, metadata)| Op::Insert {
+                id,
+                vector,
+                metadata,
+            })
+            .collect(),
+    )
+    .unwrap();
+    assert_eq!(ids(&db), ["a", "b"]);
+}
+
+#[test]
+fn test_updates_are_never_skipped() {
+    // Updating "a" to the content of "b" keeps both
+    let mut db = seeded();
+    db.insert("b", &[0.0, 1.0, 0.0], None).unwrap();
+    db.upsert_batch(vec![(
+        "a".into(),
+        vec![0.0, 1.0, 0.0],
+        Some(json!({"v": 2})),
+    )])
+    .unwrap();
+    assert_eq!(ids(&db), ["a", "b"]);
+    assert_eq!(db.get("a").unwrap().unwrap().1, Some(json!({"v": 2})));
+
+    // A new vector matching content the batch moves away is kept
+    let mut db = seeded();
+    db.upsert_batch(vec![
+        ("a".into(), vec![0.0, 0.0, 1.0], None),
+        ("c".into(), vec![1.0, 0.0, 0.0], None),
+    ])
+    .unwrap();
+    assert_eq!(ids(&db), ["a", "c"]);
+
+    let mut db = seeded();
+    db.transaction(vec![
+        Op::Delete { id: "a".into() },
+        Op::Insert {
+            id: "c".into(),
+            vector: vec![1.0, 0.0, 0.0],
+            metadata: None,
+        },
+    ])
+    .unwrap();
+    assert_eq!(ids(&db), ["c"]);
+}
+
+#[test]
+fn test_persistent_writes_skip_stored_content() {
+    let dir = tempdir().unwrap();
+    let config = PersistentConfig {
+        dimensions: 3,
+        distance_metric: DistanceMetric::Euclidean,
+        insert_mode: MODE,
+        ..Default::default()
+    };
+    {
+        let mut db = PersistentVectorDb::open(dir.path(), config.clone()).unwrap();
+        db.insert("a", &[1.0, 0.0, 0.0], None).unwrap();
+        db.insert("copy", &[1.0, 0.0, 0.0], None).unwrap();
+        db.transaction(vec![Op::Upsert {
+            id: "copy2".into(),
+            vector: vec![1.0, 0.0, 0.0],
+            metadata: None,
+        }])
+        .unwrap();
+        let report = db.bulk_load(batch(), DuplicatePolicy::Skip).unwrap();
+        assert_eq!(report.inserted, 1);
+        assert_eq!(report.skipped, 2);
+        assert_eq!(db.len(), 2);
+    }
+
+    let db = PersistentVectorDb::open(dir.path(), config).unwrap();
+    assert_eq!(db.len(), 2);
+    assert!(db.contains("a") && db.contains("b"));
+}
+
+#[test]
+fn test_collections_apply_insert_mode() {
+    let dir = tempdir().unwrap();
+    let db = Database::open(dir.path()).unwrap();
+    let config = Config {
+        dimensions: 3,
+        distance_metric: DistanceMetric::Euclidean,
+        insert_mode: MODE,
+        ..Default::default()
+    };
+    db.create_collection("docs", config.clone()).unwrap();
+    let docs = db.get_collection("docs").unwrap();
+    docs.insert("a".to_string(), &[1.0, 0.0, 0.0], None)
+        .unwrap();
+    docs.insert("b".to_string(), &[0.0, 1.0, 0.0], None)
+        .unwrap();
+    docs.insert("copy".to_string(), &[1.0, 0.0, 0.0], None)
+        .unwrap();
+    assert_eq!(docs.len(), 2);
+
+    // An upsert of a stored ID is an update, whatever its content
+    docs.upsert("a".to_string(), &[0.0, 1.0, 0.0], None)
+        .unwrap();
+    assert_eq!(docs.len(), 2);
+    assert_eq!(docs.get("a").unwrap().unwrap().0, vec![0.0, 1.0, 0.0]);
+
+    let quantized = Database::new().create_collection(
+        "q",
+        Config {
+            quantization: QuantizationType::SQ8,
+            ..config
+        },
+    );
+    assert!(matches!(quantized, Err(Error::InvalidConfig(_))));
+}
@@ -0,0 +1,135 @@
+use serde_json::json;
+use surgedb_core::{
+    BatchReport, Config, DuplicatePolicy, Error, PersistentConfig, PersistentVectorDb, VectorDb,
+    VectorId,
+};
+use tempfile::tempdir;
+
+type Item = (VectorId, Vec<f32>, Option<serde_json::Value>);
+
+/// "a" is stored as [1, 0, 0]
+fn seeded() -> VectorDb {
+    let mut db = VectorDb::new(Config {
+        dimensions: 3,
+        ..Default::default()
+    })
+    .unwrap();
+    db.insert("a", &[1.0, 0.0, 0.0], None).unwrap();
+    db
+}
+
+/// New "b" and "c", "a" again (already stored), then "b" repeated
+fn batch() -> Vec<Item> {
+    vec![
+        ("b".into(), vec![0.0, 1.0, 0.0], Some(json!({"copy": 1}))),
+        ("a".into(), vec![0.0, 0.0, 1.0], Some(json!({"copy": 1}))),
+        ("c".into(), vec![1.0, 1.0, 0.0], None),
+        ("b".into(), vec![0.0, 1.0, 1.0], Some(json!({"copy": 2}))),
+    ]
+}
+
+fn counts(inserted: usize, skipped: usize, overwritten: usize) -> BatchReport {
+    BatchReport {
+        inserted,
+        skipped,
+        overwritten,
+    }
+}
+
+#[test]
+fn test_import_batch_skip_keeps_first() {
+    let mut db = seeded();
+    let report = db.import_batch(batch(), DuplicatePolicy::Skip).unwrap();
+    assert_eq!(report, counts(2, 2, 0));
+
+    assert_eq!(db.len(), 3);
+    assert_eq!(db.get("a").unwrap().unwrap().0, vec![1.0, 0.0, 0.0]);
+    let (vector, metadata) = db.get("b").unwrap().unwrap();
+    assert_eq!(vector, vec![0.0, 1.0, 0.0]);
+    assert_eq!(metadata, Some(json!({"copy": 1})));
+}
+
+#[test]
+fn test_import_batch_overwrite_keeps_last() {
+    let mut db = seeded();
+    let report = db
+        .import_batch(batch(), DuplicatePolicy::Overwrite)
+        .unwrap();
+    assert_eq!(report, counts(2, 0, 2));
+
+    assert_eq!(db.len(), 3);
+    assert_eq!(db.get("a").unwrap().unwrap().0, vec![0.0, 0.0, 1.0]);
+    let (vector, metadata) = db.get("b").unwrap().unwrap();
+    assert_eq!(vector, vec![0.0, 1.0, 1.0]);
+    assert_eq!(metadata, Some(json!({"copy": 2})));
+}
+
+#[test]
+fn test_import_batch_error_writes_nothing() {
+    let mut db = seeded();
+    let err = db
+        .import_batch(batch(), DuplicatePolicy::Error)
+        .unwrap_err();
+    assert!(
+        matches!(err, Error::DuplicateId(ref id) if id == "a"),
+        "{}",
+        err
+    );
+    assert_eq!(db.len(), 1);
+
+    // A repeat within the batch is caught too
+    let repeated: Vec<Item> = batch()
+        .into_iter()
+        .filter(|(id, _, _)| id.as_str() != "a")
+        .collect();
+    let err = db
+        .import_batch(repeated.clone(), DuplicatePolicy::Error)
+        .unwrap_err();
+    assert!(
+        matches!(err, Error::DuplicateId(ref id) if id == "b"),
+        "{}",
+        err
+    );
+    assert_eq!(db.len(), 1);
+
+    let unique = repeated.into_iter().take(2).collect();
+    let report = db.import_batch(unique, DuplicatePolicy::Error).unwrap();
+    assert_eq!(report, counts(2, 0, 0));
+    assert_eq!(db.len(), 3);
+}
+
+#[test]
+fn test_bulk_load_follows_duplicate_policy() {
+    let config = PersistentConfig {
+        dimensions: 3,
+        ..Default::default()
+    };
+    let expected = [
+        (DuplicatePolicy::Skip, counts(2, 2, 0), vec![1.0, 0.0, 0.0]),
+        (
+            DuplicatePolicy::Overwrite,
+            counts(2, 0, 2),
+            vec![0.0, 0.0, 1.0],
+        ),
+    ];
+    for (policy, expected, stored_a) in expected {
+        let dir = tempdir().unwrap();
+        {
+            let mut db = PersistentVectorDb::open(dir.path(), config.clone()).unwrap();
+            db.insert("a", &[1.0, 0.0, 0.0], None).unwrap();
+            assert_eq!(db.bulk_load(batch(), policy).unwrap(), expected);
+        }
+
+        // The loaded batch is checkpointed
+        let db = PersistentVectorDb::open(dir.path(), config.clone()).unwrap();
+        assert_eq!(db.len(), 3, "{:?}", policy);
+        assert_eq!(db.get("a").unwrap().unwrap().0, stored_a, "{:?}", policy);
+    }
+
+    let dir = tempdir().unwrap();
+    let mut db = PersistentVectorDb::open(dir.path(), config).unwrap();
+    db.insert("a", &[1.0, 0.0, 0.0], None).unwrap();
+    let err = db.bulk_load(batch(), DuplicatePolicy::Error).unwrap_err();
+    assert!(matches!(err, Error::DuplicateId(_)), "{}", err);
+    assert_eq!(db.len(), 1);
+}
@@ -0,0 +1,73 @@
+use serde_json::json;
+use surgedb_core::{DistanceMetric, Error, PersistentConfig, PersistentVectorDb};
+use tempfile::tempdir;
+
+fn config() -> PersistentConfig {
+    PersistentConfig {
+        dimensions: 4,
+        distance_metric: DistanceMetric::Euclidean,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_export_round_trips_vectors_metadata_and_aliases() {
+    let dir = tempdir().unwrap();
+
+    let mut db = PersistentVectorDb::open(dir.path().join("src"), config()).unwrap();
+    for i in 0..1200 {
+        let metadata = (i % 3 == 0).then(|| json!({ "n": i }));
+        db.insert(
+            format!("doc{}", i),
+            &[i as f32, 1.0, 0.0, (i % 7) as f32],
+            metadata,
+        )
+        .unwrap();
+    }
+    assert!(db.delete("doc5").unwrap());
+    db.add_alias("first", "doc0").unwrap();
+
+    let mut export = Vec::new();
+    db.export(&mut export).unwrap();
+
+    let dest = dir.path().join("dest");
+    let imported = PersistentVectorDb::import(&dest, export.as_slice()).unwrap();
+    assert_eq!(imported.len(), 1199);
+    assert_eq!(imported.config().distance_metric, DistanceMetric::Euclidean);
+    assert!(imported.get("doc5").unwrap().is_none());
+    assert_eq!(imported.get("doc3").unwrap(), db.get("doc3").unwrap());
+    assert_eq!(imported.get("first").unwrap(), db.get("doc0").unwrap());
+    let hits = imported.search(&[42.0, 1.0, 0.0, 0.0], 1, None).unwrap();
+    assert_eq!(hits[0].0.as_str(), "doc42");
+    drop(imported);
+
+    // The import is checkpointed and reopens without the export
+    let reopened = PersistentVectorDb::open(&dest, config()).unwrap();
+    assert_eq!(reopened.len(), 1199);
+
+    // Existing data is never overwritten
+    assert!(matches!(
+        PersistentVectorDb::import(&dest, export.as_slice()),
+        Err(Error::InvalidConfig(_))
+    ));
+}
+
+#[test]
+fn test_import_rejects_unknown_export_version() {
+    let dir = tempdir().unwrap();
+    let db = PersistentVectorDb::open(dir.path().join("src"), config()).unwrap();
+    let mut export = Vec::new();
+    db.export(&mut export).unwrap();
+
+    export[4] += 1;
+    match PersistentVectorDb::import(dir.path().join("v2"), export.as_slice()) {
+        Err(Error::UnsupportedVersion { version, .. }) => assert_eq!(version, 2),
+        other => panic!("expected UnsupportedVersion, got {:?}", other.err()),
+    }
+
+    export[..4].copy_from_slice(b"NOPE");
+    assert!(matches!(
+        PersistentVectorDb::import(dir.path().join("bad"), export.as_slice()),
+        Err(Error::Deserialization { .. })
+    ));
+}
@@ -0,0 +1,93 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde_json::json;
+use std::collections::HashMap;
+use surgedb_core::{Config, Database, QuantizationType};
+
+const CATEGORIES: [&str; 5] = ["books", "movies", "music", "games", "toys"];
+
+fn tally_matches(quantization: QuantizationType) {
+    let db = Database::new();
+    let config = Config {
+        dimensions: 4,
+        quantization,
+        ..Default::default()
+    };
+    db.create_collection("docs", config).unwrap();
+    let collection = db.get_collection("docs").unwrap();
+
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut tally: HashMap<&str, usize> = HashMap::new();
+    let mut assigned = Vec::new();
+    for i in 0..500 {
+        // Skewed towards "books" so the top value is unambiguous
+        let category = if rng.gen_bool(0.4) {
+            CATEGORIES[0]
+        } else {
+            CATEGORIES[rng.gen_range(0..CATEGORIES.len())]
+        };
+        let v = [rng.gen(), rng.gen(), rng.gen(), 1.0];
+        collection
+            .insert(
+                format!("vec{}", i),
+                &v,
+                Some(json!({"meta": {"category": category}})),
+            )
+            .unwrap();
+        assigned.push(category);
+    }
+
+    // Deleted and re-categorized vectors must not be double counted
+    for i in 0..40 {
+        assert!(collection.delete(&format!("vec{}", i)).unwrap());
+    }
+    for (i, category) in assigned.iter_mut().enumerate().take(60).skip(40) {
+        collection
+            .upsert(
+                format!("vec{}", i),
+                &[0.0, 1.0, 0.0, 0.0],
+                Some(json!({"meta": {"category": "toys"}})),
+            )
+            .unwrap();
+        *category = "toys";
+    }
+    for category in &assigned[40..] {
+        *tally.entry(category).or_default() += 1;
+    }
+
+    let facets = collection.facet("meta.category", 10, None);
+    assert!(!facets.approximate);
+    assert_eq!(facets.scanned, 460);
+
+    let got: HashMap<String, usize> = facets
+        .values
+        .iter()
+        .map(|v| (v.value.as_str().unwrap().to_string(), v.count))
+        .collect();
+    let expected: HashMap<String, usize> = tally.iter().map(|(k, v)| (k.to_string(), *v)).collect();
+    assert_eq!(got, expected);
+    assert!(facets
+        .values
+        .windows(2)
+        .all(|pair| pair[0].count >= pair[1].count));
+
+    let top = collection.facet("meta.category", 2, None);
+    assert_eq!(top.values, facets.values[..2]);
+
+    // A sample still finds the dominant value and estimates its count
+    let sampled = collection.facet("meta.category", 1, Some(100));
+    assert!(sampled.approximate);
+    assert!(sampled.scanned <= 100);
+    assert_eq!(sampled.values[0].value, json!("books"));
+    let exact = expected["books"] as f64;
+    assert!((sampled.values[0].count as f64 - exact).abs() < exact * 0.3);
+}
+
+#[test]
+fn test_facet_counts_match_manual_tally() {
+    tally_matches(QuantizationType::None);
+}
+
+#[test]
+fn test_facet_counts_quantized() {
+    tally_matches(QuantizationType::SQ8);
+}
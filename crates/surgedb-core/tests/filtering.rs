@@ -79,3 +79,37 @@ fn test_complex_filtering() {
     assert_eq!(results.len(), 1);
     assert_eq!(results[0].0.as_str(), "v1");
 }
+
+#[test]
+fn test_impossible_filter_reports_stats() {
+    let mut db = VectorDb::new(Config {
+        dimensions: 4,
+        ..Default::default()
+    })
+    .unwrap();
+    for i in 0..200 {
+        let x = i as f32;
+        db.insert(
+            format!("vec{}", i),
+            &[x.sin(), x.cos(), 1.0, (x * 0.5).sin()],
+            Some(json!({"category": "books"})),
+        )
+        .unwrap();
+    }
+
+    let filter = Filter::Exact("category".to_string(), json!("nonexistent"));
+    let (results, stats) = db
+        .search_with_stats(&[1.0, 0.0, 1.0, 0.0], 10, Some(&filter))
+        .unwrap();
+    assert!(results.is_empty());
+    assert!(stats.filtered_out > 0);
+    assert!(stats.exhausted);
+
+    // A satisfiable search stops at the distance bound instead
+    let (results, stats) = db
+        .search_with_stats(&[1.0, 0.0, 1.0, 0.0], 1, None)
+        .unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(stats.filtered_out, 0);
+    assert!(!stats.exhausted);
+}
@@ -0,0 +1,63 @@
+use serde_json::json;
+use std::collections::HashMap;
+use surgedb_core::{Config, Database, DistanceMetric, MissingGroup};
+
+#[test]
+fn test_grouped_search_returns_best_hit_per_group() {
+    let db = Database::new();
+    let config = Config {
+        dimensions: 2,
+        distance_metric: DistanceMetric::Euclidean,
+        ..Default::default()
+    };
+    db.create_collection("posts", config).unwrap();
+    let collection = db.get_collection("posts").unwrap();
+
+    // Ten posts per author; the closest ones all belong to author 0
+    let mut best: HashMap<String, (String, f32)> = HashMap::new();
+    for author in 0..5 {
+        for post in 0..10 {
+            let id = format!("a{}-p{}", author, post);
+            let x = (author * 10 + post) as f32;
+            collection
+                .insert(id.clone(), &[x, 0.0], Some(json!({"author": author})))
+                .unwrap();
+            let entry = best
+                .entry(author.to_string())
+                .or_insert((id.clone(), f32::MAX));
+            if x < entry.1 {
+                *entry = (id, x);
+            }
+        }
+    }
+    collection
+        .insert(
+            "anonymous".to_string(),
+            &[0.5, 0.0],
+            Some(json!({"other": 1})),
+        )
+        .unwrap();
+
+    let results = collection
+        .search_grouped(&[0.0, 0.0], 3, "author", MissingGroup::Exclude, None)
+        .unwrap();
+    let ids: Vec<_> = results.iter().map(|(id, _, _)| id.as_str()).collect();
+    assert_eq!(ids, ["a0-p0", "a1-p0", "a2-p0"]);
+
+    // Asking for more groups than exist returns one hit per group
+    let results = collection
+        .search_grouped(&[0.0, 0.0], 10, "author", MissingGroup::Exclude, None)
+        .unwrap();
+    assert_eq!(results.len(), 5);
+    for (id, _, metadata) in &results {
+        let author = metadata.as_ref().unwrap()["author"].to_string();
+        assert_eq!(id.as_str(), best[&author].0);
+    }
+
+    // Missing keys form their own group when asked
+    let results = collection
+        .search_grouped(&[0.0, 0.0], 3, "author", MissingGroup::Null, None)
+        .unwrap();
+    let ids: Vec<_> = results.iter().map(|(id, _, _)| id.as_str()).collect();
+    assert_eq!(ids, ["a0-p0", "anonymous", "a1-p0"]);
+}
@@ -0,0 +1,122 @@
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use surgedb_core::{Config, InsertMode, Op, VectorDb, VectorId};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Event {
+    Insert(String, Vec<f32>, Option<Value>),
+    Delete(String),
+}
+
+fn hooked(config: Config) -> (VectorDb, Arc<Mutex<Vec<Event>>>) {
+    let mut db = VectorDb::new(config).unwrap();
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&events);
+    db.on_insert(Box::new(
+        move |id: &VectorId, vector: &[f32], metadata: &Option<Value>| {
+            sink.lock().unwrap().push(Event::Insert(
+                id.to_string(),
+                vector.to_vec(),
+                metadata.clone(),
+            ));
+        },
+    ));
+    let sink = Arc::clone(&events);
+    db.on_delete(Box::new(move |id: &VectorId| {
+        sink.lock().unwrap().push(Event::Delete(id.to_string()));
+    }));
+    (db, events)
+}
+
+fn take(events: &Mutex<Vec<Event>>) -> Vec<Event> {
+    std::mem::take(&mut *events.lock().unwrap())
+}
+
+#[test]
+fn test_hooks_fire_on_insert_and_delete() {
+    let (mut db, events) = hooked(Config {
+        dimensions: 2,
+        ..Default::default()
+    });
+    let insert = |id: &str, vector: &[f32], metadata: Option<Value>| {
+        Event::Insert(id.to_string(), vector.to_vec(), metadata)
+    };
+
+    db.insert("a", &[1.0, 0.0], Some(json!({"n": 1}))).unwrap();
+    db.upsert("a", &[0.0, 1.0], None).unwrap();
+    assert_eq!(
+        take(&events),
+        [
+            insert("a", &[1.0, 0.0], Some(json!({"n": 1}))),
+            insert("a", &[0.0, 1.0], None),
+        ]
+    );
+
+    // A failed write does not fire
+    assert!(db.insert("a", &[1.0, 1.0], None).is_err());
+    assert!(take(&events).is_empty());
+
+    db.insert_batch(vec![
+        ("b".into(), vec![1.0, 1.0], None),
+        ("c".into(), vec![2.0, 1.0], Some(json!({"n": 3}))),
+    ])
+    .unwrap();
+    db.upsert_batch(vec![("d".into(), vec![3.0, 1.0], None)])
+        .unwrap();
+    assert_eq!(
+        take(&events),
+        [
+            insert("b", &[1.0, 1.0], None),
+            insert("c", &[2.0, 1.0], Some(json!({"n": 3}))),
+            insert("d", &[3.0, 1.0], None),
+        ]
+    );
+
+    // Deletes report the primary ID, and only when something was removed
+    db.add_alias("bee", "b").unwrap();
+    assert!(db.delete("bee").unwrap());
+    assert!(!db.delete("missing").unwrap());
+    assert_eq!(take(&events), [Event::Delete("b".to_string())]);
+
+    db.transaction(vec![
+        Op::Delete { id: "c".into() },
+        Op::Delete { id: "c".into() },
+        Op::Insert {
+            id: "e".into(),
+            vector: vec![4.0, 1.0],
+            metadata: None,
+        },
+        Op::SetMetadata {
+            id: "d".into(),
+            metadata: Some(json!({"n": 4})),
+        },
+    ])
+    .unwrap();
+    assert_eq!(
+        take(&events),
+        [
+            Event::Delete("c".to_string()),
+            insert("e", &[4.0, 1.0], None)
+        ]
+    );
+}
+
+#[test]
+fn test_insert_hook_sees_stored_vector_and_skips_dedup() {
+    let (mut db, events) = hooked(Config {
+        dimensions: 2,
+        normalize: true,
+        insert_mode: InsertMode::DedupByContent { epsilon: 0.0 },
+        ..Default::default()
+    });
+
+    db.insert("a", &[3.0, 4.0], None).unwrap();
+    assert_eq!(
+        take(&events),
+        [Event::Insert("a".to_string(), vec![0.6, 0.8], None)]
+    );
+
+    let stored = db.insert_or_existing("b", &[3.0, 4.0], None).unwrap();
+    assert_eq!(stored.as_str(), "a");
+    assert!(take(&events).is_empty());
+}
@@ -0,0 +1,92 @@
+use serde_json::json;
+use surgedb_core::{Config, PersistentConfig, PersistentVectorDb, VectorDb, VectorId};
+use tempfile::tempdir;
+
+#[test]
+fn test_integer_ids_share_the_string_form() {
+    assert_eq!(VectorId::from(7), VectorId::from("7"));
+    assert_eq!(VectorId::from("7").as_u64(), Some(7));
+    assert_eq!(VectorId::from(u64::MAX).as_str(), u64::MAX.to_string());
+    // Only canonical decimals are integers
+    for text in ["07", "-7", "7.0", " 7", "18446744073709551616", ""] {
+        let id = VectorId::from(text);
+        assert_eq!(id.as_u64(), None, "{:?}", text);
+        assert_eq!(id.as_str(), text);
+    }
+    assert_eq!(
+        std::mem::size_of::<VectorId>(),
+        std::mem::size_of::<String>()
+    );
+
+    // Serialized as before: always a string
+    assert_eq!(
+        serde_json::to_value(VectorId::from(42)).unwrap(),
+        json!("42")
+    );
+    let parsed: Vec<VectorId> = serde_json::from_value(json!([42, "42", "doc"])).unwrap();
+    assert_eq!(
+        parsed,
+        [VectorId::from(42), VectorId::from(42), "doc".into()]
+    );
+    assert!(serde_json::from_value::<VectorId>(json!(-1)).is_err());
+    let bytes = bincode::serialize(&VectorId::from(42)).unwrap();
+    assert_eq!(bytes, bincode::serialize(&"42".to_string()).unwrap());
+    assert_eq!(
+        bincode::deserialize::<VectorId>(&bytes).unwrap(),
+        VectorId::from(42)
+    );
+}
+
+#[test]
+fn test_insert_auto_assigns_the_next_free_integer() {
+    let mut db = VectorDb::new(Config {
+        dimensions: 2,
+        ..Default::default()
+    })
+    .unwrap();
+    db.insert(1u64, &[0.0, 1.0], None).unwrap();
+    db.insert("doc", &[1.0, 1.0], None).unwrap();
+
+    let first = db.insert_auto(&[1.0, 0.0], None).unwrap();
+    let second = db.insert_auto(&[2.0, 1.0], Some(json!({"n": 2}))).unwrap();
+    let third = db.insert_auto(&[3.0, 1.0], None).unwrap();
+    assert_eq!(
+        [first.as_u64(), second.as_u64(), third.as_u64()],
+        [Some(0), Some(2), Some(3)]
+    );
+
+    // Integer and string lookups reach the same vector
+    assert_eq!(db.get("2").unwrap().unwrap().1, Some(json!({"n": 2})));
+    assert!(db.delete(3u64).unwrap());
+    assert_eq!(db.len(), 4);
+
+    db.clear();
+    assert_eq!(
+        db.insert_auto(&[1.0, 0.0], None).unwrap(),
+        VectorId::from(0)
+    );
+}
+
+#[test]
+fn test_integer_ids_survive_reopen() {
+    let dir = tempdir().unwrap();
+    let config = PersistentConfig {
+        dimensions: 2,
+        ..Default::default()
+    };
+    {
+        let mut db = PersistentVectorDb::open(dir.path(), config.clone()).unwrap();
+        db.insert(10u64, &[1.0, 0.0], None).unwrap();
+        db.insert("ten", &[0.0, 1.0], None).unwrap();
+        db.checkpoint().unwrap();
+        db.insert(11u64, &[1.0, 1.0], None).unwrap();
+    }
+
+    let db = PersistentVectorDb::open(dir.path(), config).unwrap();
+    assert_eq!(db.len(), 3);
+    for id in ["10", "11", "ten"] {
+        assert!(db.get(id).unwrap().is_some(), "{}", id);
+    }
+    let hits = db.search(&[1.0, 0.0], 1, None).unwrap();
+    assert_eq!(hits[0].0, VectorId::from(10));
+}
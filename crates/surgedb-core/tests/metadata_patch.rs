@@ -0,0 +1,147 @@
+use serde_json::json;
+use surgedb_core::filter::Filter;
+use surgedb_core::{
+    Config, Error, MetadataUpdate, PersistentConfig, PersistentVectorDb, QuantizedConfig,
+    QuantizedVectorDb, VectorDb,
+};
+use tempfile::tempdir;
+
+fn tag(i: usize) -> &'static str {
+    if i.is_multiple_of(2) {
+        "even"
+    } else {
+        "odd"
+    }
+}
+
+#[test]
+fn test_patch_updates_only_matching_vectors() {
+    let mut db = VectorDb::new(Config {
+        dimensions: 2,
+        ..Default::default()
+    })
+    .unwrap();
+    for i in 0..10 {
+        let metadata = json!({"tag": tag(i), "n": i, "nested": {"keep": true, "drop": 1}});
+        db.insert(i.to_string(), &[i as f32, 1.0], Some(metadata))
+            .unwrap();
+    }
+
+    let even = Filter::Exact("tag".into(), json!("even"));
+    let patch = json!({"reviewed": true, "nested": {"drop": null, "added": "x"}});
+    assert_eq!(db.update_metadata_by_filter(&even, &patch).unwrap(), 5);
+
+    for i in 0..10 {
+        let (vector, metadata) = db.get(&i.to_string()).unwrap().unwrap();
+        assert_eq!(vector, vec![i as f32, 1.0]);
+        let expected = if tag(i) == "even" {
+            json!({"tag": "even", "n": i, "reviewed": true, "nested": {"keep": true, "added": "x"}})
+        } else {
+            json!({"tag": "odd", "n": i, "nested": {"keep": true, "drop": 1}})
+        };
+        assert_eq!(metadata, Some(expected));
+    }
+
+    // Patched fields are searchable, and reapplying changes nothing
+    let reviewed = Filter::Exact("reviewed".into(), json!(true));
+    assert_eq!(
+        db.search(&[0.0, 1.0], 10, Some(&reviewed)).unwrap().len(),
+        5
+    );
+    assert_eq!(db.update_metadata_by_filter(&even, &patch).unwrap(), 0);
+}
+
+#[test]
+fn test_persistent_patch_survives_reopen() {
+    let dir = tempdir().unwrap();
+    let config = PersistentConfig {
+        dimensions: 2,
+        sync_writes: true,
+        ..Default::default()
+    };
+
+    {
+        let mut db = PersistentVectorDb::open(dir.path(), config.clone()).unwrap();
+        for i in 0..6 {
+            db.insert(
+                i.to_string(),
+                &[i as f32, 1.0],
+                Some(json!({"tag": tag(i)})),
+            )
+            .unwrap();
+        }
+        let even = Filter::Exact("tag".into(), json!("even"));
+        let updated = db
+            .update_metadata_by_filter(&even, &json!({"tag": null, "score": 1}))
+            .unwrap();
+        assert_eq!(updated, 3);
+    }
+
+    let db = PersistentVectorDb::open(dir.path(), config).unwrap();
+    assert_eq!(db.len(), 6);
+    for i in 0..6 {
+        let expected = if tag(i) == "even" {
+            json!({"score": 1})
+        } else {
+            json!({"tag": "odd"})
+        };
+        assert_eq!(db.get(&i.to_string()).unwrap().unwrap().1, Some(expected));
+    }
+}
+
+#[test]
+fn test_set_metadata_keeps_vector_and_slot() {
+    let mut db = VectorDb::new(Config {
+        dimensions: 2,
+        ..Default::default()
+    })
+    .unwrap();
+    db.insert("a", &[1.0, 0.0], Some(json!({"tag": "old", "n": 1})))
+        .unwrap();
+    db.insert("b", &[0.0, 1.0], None).unwrap();
+    db.add_alias("first", "a").unwrap();
+
+    db.set_metadata("first", json!({"tag": "new"}), MetadataUpdate::Merge)
+        .unwrap();
+    assert_eq!(
+        db.get("a").unwrap().unwrap(),
+        (vec![1.0, 0.0], Some(json!({"tag": "new", "n": 1})))
+    );
+    db.set_metadata("a", json!({"tag": "replaced"}), MetadataUpdate::Replace)
+        .unwrap();
+    assert_eq!(
+        db.get("a").unwrap().unwrap().1,
+        Some(json!({"tag": "replaced"}))
+    );
+    assert_eq!(db.physical_len(), 2);
+
+    // Filters see the new metadata straight away
+    let replaced = Filter::Exact("tag".into(), json!("replaced"));
+    let hits = db.search(&[0.0, 1.0], 2, Some(&replaced)).unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].0.as_str(), "a");
+
+    db.set_metadata("a", json!(null), MetadataUpdate::Replace)
+        .unwrap();
+    assert_eq!(db.get("a").unwrap().unwrap().1, None);
+    assert!(matches!(
+        db.set_metadata("missing", json!({}), MetadataUpdate::Replace),
+        Err(Error::VectorNotFound(_))
+    ));
+
+    let mut quantized = QuantizedVectorDb::new(QuantizedConfig {
+        dimensions: 2,
+        ..Default::default()
+    })
+    .unwrap();
+    quantized.insert("q", &[0.5, 0.5], None).unwrap();
+    let (before, _) = quantized.get_quantized("q").unwrap();
+    quantized
+        .set_metadata("q", json!({"tag": "x"}), MetadataUpdate::Merge)
+        .unwrap();
+    assert_eq!(quantized.get_quantized("q").unwrap().0, before);
+    assert_eq!(
+        quantized.get("q").unwrap().unwrap().1,
+        Some(json!({"tag": "x"}))
+    );
+}
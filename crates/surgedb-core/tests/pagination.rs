@@ -0,0 +1,111 @@
+use std::collections::HashSet;
+use surgedb_core::{Config, Database, QuantizationType};
+
+fn page_through(quantization: QuantizationType) {
+    let db = Database::new();
+    let config = Config {
+        dimensions: 4,
+        quantization,
+        ..Default::default()
+    };
+    db.create_collection("docs", config).unwrap();
+    let collection = db.get_collection("docs").unwrap();
+
+    for i in 0..250 {
+        let v = [i as f32, 1.0, 0.5, 0.25];
+        collection.insert(format!("vec{}", i), &v, None).unwrap();
+    }
+
+    // Updates leave stale slots behind and deletes leave holes
+    for i in 0..10 {
+        collection
+            .upsert(format!("vec{}", i), &[0.0, 1.0, 0.0, 0.0], None)
+            .unwrap();
+    }
+    for i in 100..120 {
+        assert!(collection.delete(&format!("vec{}", i)).unwrap());
+    }
+
+    let mut seen = HashSet::new();
+    let mut cursor = None;
+    let mut pages = 0;
+    loop {
+        let page = collection.list_after(cursor, 32);
+        assert!(!page.entries.is_empty() && page.entries.len() <= 32);
+        for (id, _) in &page.entries {
+            assert!(seen.insert(id.to_string()), "{} listed twice", id);
+        }
+        pages += 1;
+        cursor = page.next_cursor;
+        if cursor.is_none() {
+            break;
+        }
+    }
+
+    let expected: HashSet<String> = (0..250)
+        .filter(|i| !(100..120).contains(i))
+        .map(|i| format!("vec{}", i))
+        .collect();
+    assert_eq!(seen, expected);
+    assert_eq!(pages, expected.len().div_ceil(32));
+}
+
+#[test]
+fn test_cursor_pagination_covers_all_vectors() {
+    page_through(QuantizationType::None);
+}
+
+#[test]
+fn test_cursor_pagination_quantized() {
+    page_through(QuantizationType::SQ8);
+}
+
+#[test]
+fn test_cursor_survives_update_and_delete_of_page_end() {
+    let db = Database::new();
+    let config = Config {
+        dimensions: 4,
+        ..Default::default()
+    };
+    db.create_collection("docs", config).unwrap();
+    let collection = db.get_collection("docs").unwrap();
+    for i in 0..30 {
+        let v = [i as f32, 1.0, 0.5, 0.25];
+        collection.insert(format!("vec{}", i), &v, None).unwrap();
+    }
+
+    let first = collection.list_after(None, 10);
+    assert_eq!(first.entries.last().unwrap().0.as_str(), "vec9");
+    // The last vector of the page moves to the end of the listing
+    collection
+        .upsert("vec9".to_string(), &[0.0, 1.0, 0.0, 0.0], None)
+        .unwrap();
+    let second = collection.list_after(first.next_cursor, 10);
+    let ids: Vec<String> = second
+        .entries
+        .iter()
+        .map(|(id, _)| id.to_string())
+        .collect();
+    assert_eq!(
+        ids,
+        (10..20).map(|i| format!("vec{}", i)).collect::<Vec<_>>()
+    );
+
+    // ... or is deleted
+    assert!(collection.delete("vec19").unwrap());
+    let third = collection.list_after(second.next_cursor, 10);
+    let ids: Vec<String> = third.entries.iter().map(|(id, _)| id.to_string()).collect();
+    let mut expected: Vec<String> = (20..30).map(|i| format!("vec{}", i)).collect();
+    expected.push("vec9".to_string());
+    assert_eq!(ids[..10], expected[..10]);
+    let rest = collection.list_after(third.next_cursor, 10);
+    assert_eq!(rest.entries.len(), 1);
+    assert_eq!(rest.entries[0].0.as_str(), "vec9");
+    assert_eq!(rest.next_cursor, None);
+
+    // A cursor past the end lists nothing
+    assert_eq!(
+        collection.list_after(Some(u64::MAX), 10),
+        Default::default()
+    );
+}
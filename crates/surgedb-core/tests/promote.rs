@@ -0,0 +1,60 @@
+use surgedb_core::{Error, PersistentConfig, PersistentVectorDb};
+use tempfile::tempdir;
+
+fn config() -> PersistentConfig {
+    PersistentConfig {
+        dimensions: 4,
+        sync_writes: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_promoted_follower_accepts_writes() {
+    let dir = tempdir().unwrap();
+
+    let mut primary = PersistentVectorDb::open(dir.path(), config()).unwrap();
+    primary.insert("a", &[1.0, 0.0, 0.0, 0.0], None).unwrap();
+
+    let mut follower = PersistentVectorDb::open_follower(dir.path(), config()).unwrap();
+    assert!(follower.is_follower());
+    assert_eq!(follower.len(), 1);
+
+    // Records written after the follower opened, across a checkpoint
+    primary.insert("b", &[0.0, 1.0, 0.0, 0.0], None).unwrap();
+    primary.checkpoint().unwrap();
+    primary.insert("c", &[0.0, 0.0, 1.0, 0.0], None).unwrap();
+    assert!(follower.catch_up().unwrap() > 0);
+    assert_eq!(follower.len(), 3);
+    assert_eq!(follower.catch_up().unwrap(), 0);
+
+    primary.delete("a").unwrap();
+    assert_eq!(follower.catch_up().unwrap(), 1);
+    assert!(follower.get("a").unwrap().is_none());
+
+    // Followers are read-only, and cannot take over a live primary
+    assert!(matches!(
+        follower.insert("d", &[0.0, 0.0, 0.0, 1.0], None),
+        Err(Error::InvalidConfig(_))
+    ));
+    assert!(matches!(follower.promote(), Err(Error::LockFailed { .. })));
+    assert!(follower.is_follower());
+
+    // The primary fails after one last write
+    primary.insert("e", &[1.0, 1.0, 0.0, 0.0], None).unwrap();
+    drop(primary);
+
+    follower.promote().unwrap();
+    assert!(!follower.is_follower());
+    assert!(follower.get("e").unwrap().is_some());
+
+    follower.insert("d", &[0.0, 0.0, 0.0, 1.0], None).unwrap();
+    let results = follower.search(&[0.0, 0.0, 0.0, 1.0], 1, None).unwrap();
+    assert_eq!(results[0].0.as_str(), "d");
+    drop(follower);
+
+    let reopened = PersistentVectorDb::open(dir.path(), config()).unwrap();
+    assert_eq!(reopened.len(), 4);
+    assert!(reopened.get("d").unwrap().is_some());
+    assert!(reopened.get("a").unwrap().is_none());
+}
@@ -0,0 +1,69 @@
+use surgedb_core::{
+    Config, Error, PersistentConfig, PersistentVectorDb, QuantizedConfig, QuantizedVectorDb,
+    SearchHit, VectorDb,
+};
+use tempfile::tempdir;
+
+fn vector(i: usize) -> Vec<f32> {
+    (0..8).map(|d| ((i * 8 + d) as f32).sin()).collect()
+}
+
+fn ids(hits: &[SearchHit]) -> Vec<String> {
+    hits.iter().map(|(id, _, _)| id.to_string()).collect()
+}
+
+/// Self-exclusion drops the queried vector and still returns `k` hits
+fn check(search: impl Fn(&str, usize, bool) -> surgedb_core::Result<Vec<SearchHit>>) {
+    let with_self = search("v3", 5, false).unwrap();
+    assert_eq!(with_self.len(), 5);
+    assert_eq!(with_self[0].0.as_str(), "v3");
+
+    let without = search("v3", 5, true).unwrap();
+    assert_eq!(without.len(), 5);
+    assert!(!ids(&without).iter().any(|id| id == "v3"));
+    assert_eq!(ids(&without)[..4], ids(&with_self)[1..]);
+
+    assert!(matches!(
+        search("missing", 5, true),
+        Err(Error::VectorNotFound(_))
+    ));
+}
+
+#[test]
+fn test_search_by_id_in_every_collection_kind() {
+    let mut db = VectorDb::new(Config {
+        dimensions: 8,
+        ..Default::default()
+    })
+    .unwrap();
+    let mut quantized = QuantizedVectorDb::new(QuantizedConfig {
+        dimensions: 8,
+        keep_originals: true,
+        ..Default::default()
+    })
+    .unwrap();
+    let dir = tempdir().unwrap();
+    let mut persistent = PersistentVectorDb::open(
+        dir.path(),
+        PersistentConfig {
+            dimensions: 8,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    for i in 0..50 {
+        let id = format!("v{}", i);
+        db.insert(id.as_str(), &vector(i), None).unwrap();
+        quantized.insert(id.as_str(), &vector(i), None).unwrap();
+        persistent.insert(id.as_str(), &vector(i), None).unwrap();
+    }
+
+    check(|id, k, exclude| db.search_by_id(id, k, None, exclude));
+    check(|id, k, exclude| quantized.search_by_id(id, k, None, exclude));
+    check(|id, k, exclude| persistent.search_by_id(id, k, None, exclude));
+
+    // Aliases resolve to the stored vector, which is still excluded
+    persistent.add_alias("third", "v3").unwrap();
+    let hits = persistent.search_by_id("third", 5, None, true).unwrap();
+    assert!(!ids(&hits).iter().any(|id| id == "v3"));
+}
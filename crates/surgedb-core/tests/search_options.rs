@@ -0,0 +1,106 @@
+use serde_json::json;
+use surgedb_core::filter::Filter;
+use surgedb_core::{
+    Config, Database, DistanceMetric, QuantizationType, RoutingConfig, SearchOptions,
+};
+
+#[test]
+fn test_search_options_combine() {
+    for quantization in [QuantizationType::None, QuantizationType::SQ8] {
+        let db = Database::new();
+        let config = Config {
+            dimensions: 2,
+            distance_metric: DistanceMetric::Euclidean,
+            quantization,
+            ..Default::default()
+        };
+        db.create_collection("posts", config).unwrap();
+        let posts = db.get_collection("posts").unwrap();
+        for i in 0..12 {
+            let metadata = json!({ "author": i / 4, "draft": i % 4 == 0 });
+            posts
+                .insert(format!("p{}", i), &[i as f32, 0.0], Some(metadata))
+                .unwrap();
+        }
+
+        // Grouped, filtered, widened and with vectors, all at once
+        let options = SearchOptions {
+            ef: Some(64),
+            filter: Some(Filter::Exact("draft".into(), json!(false))),
+            group_by: Some("author".into()),
+            include_vectors: true,
+            ..Default::default()
+        };
+        let outcome = posts.search_with_options(&[0.0, 0.0], 3, &options).unwrap();
+        let ids: Vec<_> = outcome.hits.iter().map(|(hit, _)| hit.0.as_str()).collect();
+        assert_eq!(ids, ["p1", "p5", "p9"]);
+        for ((id, _, _), vector) in &outcome.hits {
+            let (stored, _) = posts.get(&id.as_str()).unwrap().unwrap();
+            assert_eq!(vector.as_ref(), Some(&stored));
+        }
+        assert!(outcome.stats.filtered_out > 0);
+
+        // Plain options match a plain search
+        let queries = vec![(vec![3.0, 0.0], 2), (vec![10.0, 0.0], 1)];
+        let outcomes = posts
+            .search_batch_with_options(&queries, &SearchOptions::default())
+            .unwrap();
+        for ((query, k), outcome) in queries.iter().zip(outcomes) {
+            let plain = posts.search(query, *k, None).unwrap();
+            let hits: Vec<_> = outcome.hits.into_iter().map(|(hit, _)| hit).collect();
+            assert_eq!(hits, plain);
+        }
+    }
+}
+
+#[test]
+fn test_search_options_report_routing() {
+    let db = Database::new();
+    let config = Config {
+        dimensions: 2,
+        distance_metric: DistanceMetric::Euclidean,
+        ..Default::default()
+    };
+    db.create_collection("docs", config.clone()).unwrap();
+    let docs = db.get_collection("docs").unwrap();
+    for i in 0..40 {
+        let x = if i % 2 == 0 { 10.0 } else { -10.0 };
+        docs.insert(format!("v{}", i), &[x, i as f32 * 0.01], None)
+            .unwrap();
+    }
+    let debug = SearchOptions {
+        debug: true,
+        ..Default::default()
+    };
+
+    // Nothing to report until routing is trained
+    let outcome = docs.search_with_options(&[10.0, 0.0], 5, &debug).unwrap();
+    assert_eq!(outcome.routing, None);
+
+    docs.train_routing(RoutingConfig {
+        num_centroids: 2,
+        nprobe: 1,
+        ..Default::default()
+    })
+    .unwrap();
+    let outcome = docs.search_with_options(&[10.0, 0.0], 5, &debug).unwrap();
+    let routing = outcome.routing.unwrap();
+    assert_eq!(routing.probed.len(), 1);
+    assert_eq!(routing.probed[0].candidates, 10);
+    assert!(outcome.hits.iter().all(|((id, _, _), _)| {
+        let n: usize = id.as_str()[1..].parse().unwrap();
+        n.is_multiple_of(2)
+    }));
+    let outcome = docs
+        .search_with_options(&[10.0, 0.0], 5, &SearchOptions::default())
+        .unwrap();
+    assert_eq!(outcome.routing, None);
+
+    let quantized = Config {
+        quantization: QuantizationType::SQ8,
+        ..config
+    };
+    db.create_collection("q", quantized).unwrap();
+    let q = db.get_collection("q").unwrap();
+    assert!(q.train_routing(RoutingConfig::default()).is_err());
+}
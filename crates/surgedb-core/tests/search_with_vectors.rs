@@ -0,0 +1,48 @@
+use serde_json::json;
+use surgedb_core::{Config, Database, DistanceMetric, QuantizationType};
+
+#[test]
+fn test_search_with_vectors_attaches_stored_vectors() {
+    for quantization in [QuantizationType::None, QuantizationType::SQ8] {
+        let db = Database::new();
+        let config = Config {
+            dimensions: 4,
+            distance_metric: DistanceMetric::Euclidean,
+            quantization,
+            ..Default::default()
+        };
+        db.create_collection("docs", config).unwrap();
+        let collection = db.get_collection("docs").unwrap();
+        for i in 0..20 {
+            let v = [i as f32, 1.0, 0.5, 0.0];
+            let metadata = json!({ "n": i });
+            collection
+                .insert(format!("vec{}", i), &v, Some(metadata))
+                .unwrap();
+        }
+
+        let query = [7.0, 1.0, 0.5, 0.0];
+        let plain = collection.search(&query, 3, None).unwrap();
+        let results = collection.search_with_vectors(&query, 3, None).unwrap();
+
+        // Same hits as a plain search, each with its own vector
+        assert_eq!(results.len(), 3);
+        for ((hit, vector), expected) in results.iter().zip(&plain) {
+            assert_eq!(hit, expected);
+            let (stored, _) = collection.get(&hit.0.as_str()).unwrap().unwrap();
+            assert_eq!(vector, &stored);
+        }
+        let (nearest, vector) = &results[0];
+        assert_eq!(nearest.0.as_str(), "vec7");
+        assert_eq!(nearest.2, Some(json!({ "n": 7 })));
+        match quantization {
+            QuantizationType::None => assert_eq!(vector, &[7.0, 1.0, 0.5, 0.0]),
+            // De-quantized, so only close to what was inserted
+            _ => {
+                for (got, want) in vector.iter().zip([7.0, 1.0, 0.5, 0.0]) {
+                    assert!((got - want).abs() < 0.05, "{:?}", vector);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use serde_json::json;
+use surgedb_core::{MetadataUpdate, Op, PersistentConfig, PersistentVectorDb};
+use tempfile::tempdir;
+
+fn vector(i: usize) -> Vec<f32> {
+    (0..16).map(|d| ((i * 16 + d) as f32).sin()).collect()
+}
+
+/// Offset of the serialized graph: the header (magic, version, id, wal_seq,
+/// dimensions, vector_count), the `Some` tag, the checksum and the length
+const GRAPH_OFFSET: usize = 4 + 1 + 8 * 4 + 1 + 4 + 8;
+
+#[test]
+fn test_corrupt_snapshot_graph_is_rebuilt() {
+    let dir = tempdir().unwrap();
+    let config = PersistentConfig {
+        dimensions: 16,
+        ..Default::default()
+    };
+
+    let expected: Vec<_> = {
+        let mut db = PersistentVectorDb::open(dir.path(), config.clone()).unwrap();
+        for i in 0..300 {
+            db.insert(format!("v{}", i), &vector(i), None).unwrap();
+        }
+        db.checkpoint().unwrap();
+        (0..300)
+            .step_by(30)
+            .map(|i| db.search(&vector(i), 5, None).unwrap())
+            .collect()
+    };
+
+    // An intact graph is loaded as-is
+    {
+        let db = PersistentVectorDb::open(dir.path(), config.clone()).unwrap();
+        for (i, hits) in (0..300).step_by(30).zip(&expected) {
+            assert_eq!(&db.search(&vector(i), 5, None).unwrap(), hits);
+        }
+    }
+
+    let snapshot = std::fs::read_dir(dir.path().join("snapshots"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| path.extension().is_some_and(|ext| ext == "snap"))
+        .unwrap();
+    let mut bytes = std::fs::read(&snapshot).unwrap();
+    let graph_len = u64::from_le_bytes(bytes[GRAPH_OFFSET - 8..GRAPH_OFFSET].try_into().unwrap());
+    assert!(graph_len > 20 && GRAPH_OFFSET + (graph_len as usize) < bytes.len());
+    bytes[GRAPH_OFFSET + 20] ^= 0xFF;
+    std::fs::write(&snapshot, bytes).unwrap();
+
+    // A corrupt one is rebuilt from the snapshot's vectors
+    let db = PersistentVectorDb::open(dir.path(), config).unwrap();
+    assert_eq!(db.len(), 300);
+    for i in (0..300).step_by(30) {
+        let hits = db.search(&vector(i), 5, None).unwrap();
+        assert_eq!(hits[0].0.as_str(), format!("v{}", i));
+    }
+}
+
+/// Wide enough that vectors, rather than the graph, dominate a snapshot
+const WIDE: usize = 128;
+
+fn wide_vector(i: usize) -> Vec<f32> {
+    (0..WIDE).map(|d| ((i * WIDE + d) as f32).sin()).collect()
+}
+
+#[test]
+fn test_incremental_checkpoints_reopen_cleanly() {
+    let dir = tempdir().unwrap();
+    let config = PersistentConfig {
+        dimensions: WIDE,
+        incremental_snapshots: true,
+        max_snapshot_deltas: 20,
+        ..Default::default()
+    };
+    let snapshots = dir.path().join("snapshots");
+    let files = |extension: &str| -> Vec<u64> {
+        std::fs::read_dir(&snapshots)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == extension))
+            .map(|path| path.metadata().unwrap().len())
+            .collect()
+    };
+
+    let expected: Vec<_> = {
+        let mut db = PersistentVectorDb::open(dir.path(), config.clone()).unwrap();
+        for i in 0..300 {
+            db.insert(
+                format!("v{}", i),
+                &wide_vector(i),
+                Some(json!({"round": 0})),
+            )
+            .unwrap();
+        }
+        db.checkpoint().unwrap();
+
+        for round in 1..=10 {
+            for i in 280 + round * 20..300 + round * 20 {
+                db.insert(
+                    format!("v{}", i),
+                    &wide_vector(i),
+                    Some(json!({"round": round})),
+                )
+                .unwrap();
+            }
+            // Touch earlier slots: delete, overwrite and retag one each
+            let earlier = (round - 1) * 30;
+            db.delete(format!("v{}", earlier)).unwrap();
+            db.transaction(vec![Op::Upsert {
+                id: format!("v{}", earlier + 1).into(),
+                vector: wide_vector(10_000 + round),
+                metadata: None,
+            }])
+            .unwrap();
+            db.set_metadata(
+                &format!("v{}", earlier + 2),
+                json!({"retagged": round}),
+                MetadataUpdate::Replace,
+            )
+            .unwrap();
+            db.checkpoint().unwrap();
+        }
+        // A tail of writes left in the WAL
+        db.insert("tail", &wide_vector(20_000), None).unwrap();
+
+        (0..500)
+            .step_by(7)
+            .map(|i| (i, db.get(&format!("v{}", i)).unwrap()))
+            .collect()
+    };
+
+    // One full snapshot, then a delta per later checkpoint. Relinked graph
+    // nodes are rewritten whole, but the untouched vectors are not.
+    let (bases, deltas) = (files("snap"), files("delta"));
+    assert_eq!(bases.len(), 1);
+    assert_eq!(deltas.len(), 10);
+    assert!(
+        deltas.iter().all(|&size| size < bases[0] / 2),
+        "{:?}",
+        deltas
+    );
+
+    let db = PersistentVectorDb::open(dir.path(), config.clone()).unwrap();
+    assert_eq!(db.len(), 500 - 10 + 1);
+    for (i, stored) in &expected {
+        assert_eq!(&db.get(&format!("v{}", i)).unwrap(), stored, "v{}", i);
+    }
+    assert_eq!(
+        db.get("v32").unwrap().unwrap().1,
+        Some(json!({"retagged": 2}))
+    );
+    assert!(db.get("v30").unwrap().is_none());
+    assert!(db.get("tail").unwrap().is_some());
+    for i in (0..500).step_by(13).filter(|&i| i >= 300 || i % 30 > 2) {
+        let hits = db.search(&wide_vector(i), 1, None).unwrap();
+        assert_eq!(hits[0].0.as_str(), format!("v{}", i));
+    }
+    assert!(db.self_recall(100, 10, 3).recall >= 0.95);
+    drop(db);
+
+    // Reaching the chain limit writes a fresh full snapshot
+    let mut db = PersistentVectorDb::open(
+        dir.path(),
+        PersistentConfig {
+            max_snapshot_deltas: 10,
+            ..config
+        },
+    )
+    .unwrap();
+    db.checkpoint().unwrap();
+    assert!(files("delta").is_empty());
+    assert_eq!(files("snap").len(), 2);
+    assert_eq!(db.len(), 500 - 10 + 1);
+}
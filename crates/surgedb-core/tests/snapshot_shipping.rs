@@ -0,0 +1,74 @@
+use std::time::Duration;
+use surgedb_core::{LocalDestination, PersistentConfig, PersistentVectorDb, SnapshotShipper};
+use tempfile::tempdir;
+
+fn config() -> PersistentConfig {
+    PersistentConfig {
+        dimensions: 4,
+        incremental_snapshots: true,
+        ..Default::default()
+    }
+}
+
+fn vector(i: usize) -> Vec<f32> {
+    vec![i as f32, 1.0, (i % 3) as f32, 0.5]
+}
+
+#[test]
+fn test_snapshots_shipped_on_interval_open_at_destination() {
+    let dir = tempdir().unwrap();
+    let primary_dir = dir.path().join("primary");
+    let standby_dir = dir.path().join("standby");
+    let interval = Duration::from_millis(300);
+
+    let mut db = PersistentVectorDb::open(&primary_dir, config()).unwrap();
+    let destination = LocalDestination::new(&standby_dir).unwrap();
+    let mut shipper = SnapshotShipper::new(destination.clone(), interval);
+
+    // Nothing checkpointed yet, so nothing to ship
+    assert_eq!(shipper.maybe_ship(&db).unwrap(), Some(0));
+
+    for i in 0..20 {
+        db.insert(format!("v{}", i), &vector(i), None).unwrap();
+    }
+    db.checkpoint().unwrap();
+    // Shipped on the last call, so not due again until the interval passes
+    assert_eq!(shipper.maybe_ship(&db).unwrap(), None);
+    std::thread::sleep(interval);
+    assert_eq!(shipper.maybe_ship(&db).unwrap(), Some(1));
+
+    // A delta on top of the shipped snapshot is the only file copied next
+    for i in 20..30 {
+        db.insert(format!("v{}", i), &vector(i), None).unwrap();
+    }
+    db.checkpoint().unwrap();
+    assert_eq!(shipper.maybe_ship(&db).unwrap(), None);
+    std::thread::sleep(interval);
+    assert_eq!(shipper.maybe_ship(&db).unwrap(), Some(1));
+    assert_eq!(
+        std::fs::read_dir(destination.snapshot_dir())
+            .unwrap()
+            .count(),
+        2
+    );
+
+    // The standby opens from the shipped chain alone
+    let standby = PersistentVectorDb::open(&standby_dir, config()).unwrap();
+    assert_eq!(standby.len(), 30);
+    for i in [0, 19, 29] {
+        let (stored, _) = standby.get(&format!("v{}", i)).unwrap().unwrap();
+        assert_eq!(stored, vector(i));
+    }
+    drop(standby);
+
+    // A full snapshot starts a new chain and the old one is removed
+    db.compact().unwrap();
+    std::thread::sleep(interval);
+    assert_eq!(shipper.maybe_ship(&db).unwrap(), Some(1));
+    let shipped: Vec<_> = std::fs::read_dir(destination.snapshot_dir())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    assert_eq!(shipped.len(), 1, "{:?}", shipped);
+    assert!(shipped[0].ends_with(".snap"));
+}
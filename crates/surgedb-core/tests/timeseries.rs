@@ -0,0 +1,52 @@
+use surgedb_core::{Config, Database, StatsCollector};
+use tempfile::tempdir;
+
+#[test]
+fn test_collector_records_growing_samples() {
+    let dir = tempdir().unwrap();
+    let db = Database::open(dir.path()).unwrap();
+    let config = Config {
+        dimensions: 4,
+        ..Default::default()
+    };
+    db.create_collection("docs", config).unwrap();
+    let collection = db.get_collection("docs").unwrap();
+
+    let mut collector = StatsCollector::new(16);
+    collector.collect(&db);
+    for round in 0..3 {
+        for i in 0..10 {
+            let v = [round as f32, i as f32, 1.0, 0.5];
+            collection
+                .insert(format!("r{}-{}", round, i), &v, None)
+                .unwrap();
+        }
+        collector.collect(&db);
+    }
+
+    let series = collector.series("docs").unwrap();
+    assert_eq!(series.len(), 4);
+    let lens: Vec<usize> = series.samples().map(|s| s.len).collect();
+    assert_eq!(lens, vec![0, 10, 20, 30]);
+    assert!(
+        series
+            .samples()
+            .zip(series.samples().skip(1))
+            .all(|(a, b)| b.timestamp_ms >= a.timestamp_ms
+                && b.disk_usage_bytes >= a.disk_usage_bytes)
+    );
+
+    // Deletes show up as tombstones while the live count drops
+    assert!(collection.delete("r0-0").unwrap());
+    collector.collect(&db);
+    let latest = collector.series("docs").unwrap().latest().unwrap();
+    assert_eq!(latest.len, 29);
+    assert_eq!(latest.tombstone_count, 1);
+    assert!(latest.memory_usage_bytes > 0);
+    assert!(latest.disk_usage_bytes > 0);
+
+    // Dropped collections lose their series
+    db.delete_collection("docs").unwrap();
+    collector.collect(&db);
+    assert!(collector.series("docs").is_none());
+}
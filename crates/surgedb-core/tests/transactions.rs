@@ -0,0 +1,133 @@
+use serde_json::json;
+use surgedb_core::{Config, Error, Op, PersistentConfig, PersistentVectorDb, VectorDb};
+use tempfile::tempdir;
+
+fn seeded() -> VectorDb {
+    let mut db = VectorDb::new(Config {
+        dimensions: 4,
+        ..Default::default()
+    })
+    .unwrap();
+    db.insert("a", &[1.0, 0.0, 0.0, 0.0], Some(json!({"v": 1})))
+        .unwrap();
+    db.insert("b", &[0.0, 1.0, 0.0, 0.0], None).unwrap();
+    db
+}
+
+#[test]
+fn test_invalid_op_aborts_whole_transaction() {
+    let mut db = seeded();
+
+    let result = db.transaction(vec![
+        Op::Insert {
+            id: "c".into(),
+            vector: vec![0.0, 0.0, 1.0, 0.0],
+            metadata: None,
+        },
+        Op::Delete { id: "a".into() },
+        Op::SetMetadata {
+            id: "b".into(),
+            metadata: Some(json!({"v": 2})),
+        },
+        Op::Upsert {
+            id: "d".into(),
+            vector: vec![1.0, 2.0],
+            metadata: None,
+        },
+    ]);
+    assert!(matches!(result, Err(Error::DimensionMismatch { .. })));
+
+    assert_eq!(db.len(), 2);
+    assert!(db.get("c").unwrap().is_none());
+    assert_eq!(db.get("a").unwrap().unwrap().1, Some(json!({"v": 1})));
+    assert_eq!(db.get("b").unwrap().unwrap().1, None);
+
+    // Validation sees earlier ops in the batch
+    let result = db.transaction(vec![
+        Op::Delete { id: "b".into() },
+        Op::SetMetadata {
+            id: "b".into(),
+            metadata: None,
+        },
+    ]);
+    assert!(matches!(result, Err(Error::VectorNotFound(_))));
+    assert!(db.get("b").unwrap().is_some());
+}
+
+#[test]
+fn test_transaction_applies_all_ops() {
+    let mut db = seeded();
+
+    db.transaction(vec![
+        Op::Delete { id: "a".into() },
+        Op::Insert {
+            id: "a".into(),
+            vector: vec![0.0, 0.0, 0.0, 1.0],
+            metadata: None,
+        },
+        Op::SetMetadata {
+            id: "b".into(),
+            metadata: Some(json!({"v": 3})),
+        },
+        Op::Upsert {
+            id: "c".into(),
+            vector: vec![0.0, 0.0, 1.0, 0.0],
+            metadata: None,
+        },
+    ])
+    .unwrap();
+
+    assert_eq!(db.len(), 3);
+    assert_eq!(db.get("a").unwrap().unwrap().0, vec![0.0, 0.0, 0.0, 1.0]);
+    assert_eq!(
+        db.get("b").unwrap().unwrap(),
+        (vec![0.0, 1.0, 0.0, 0.0], Some(json!({"v": 3})))
+    );
+    let results = db.search(&[0.0, 0.0, 1.0, 0.0], 1, None).unwrap();
+    assert_eq!(results[0].0.as_str(), "c");
+}
+
+#[test]
+fn test_persistent_transaction_recovers_atomically() {
+    let dir = tempdir().unwrap();
+    let config = PersistentConfig {
+        dimensions: 4,
+        sync_writes: true,
+        ..Default::default()
+    };
+
+    {
+        let mut db = PersistentVectorDb::open(dir.path(), config.clone()).unwrap();
+        db.insert("a", &[1.0, 0.0, 0.0, 0.0], None).unwrap();
+        db.transaction(vec![
+            Op::Insert {
+                id: "b".into(),
+                vector: vec![0.0, 1.0, 0.0, 0.0],
+                metadata: None,
+            },
+            Op::SetMetadata {
+                id: "a".into(),
+                metadata: Some(json!({"tag": "x"})),
+            },
+        ])
+        .unwrap();
+
+        let result = db.transaction(vec![
+            Op::Delete { id: "a".into() },
+            Op::Insert {
+                id: "b".into(),
+                vector: vec![0.0, 0.0, 1.0, 0.0],
+                metadata: None,
+            },
+        ]);
+        assert!(matches!(result, Err(Error::DuplicateId(_))));
+    }
+
+    let db = PersistentVectorDb::open(dir.path(), config).unwrap();
+    assert_eq!(db.len(), 2);
+    assert_eq!(
+        db.get("a").unwrap().unwrap(),
+        (vec![1.0, 0.0, 0.0, 0.0], Some(json!({"tag": "x"})))
+    );
+    assert_eq!(db.get("b").unwrap().unwrap().0, vec![0.0, 1.0, 0.0, 0.0]);
+}
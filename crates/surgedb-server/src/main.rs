@@ -1,25 +1,47 @@
 use axum::{
-    extract::{Json, Path, Query, Request, State},
-    http::{header::HeaderName, HeaderValue, Method, StatusCode},
+    body::{Body, Bytes},
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Json, Path, Query, Request, State,
+    },
+    http::{
+        header::{self, HeaderName},
+        HeaderMap, HeaderValue, Method, StatusCode,
+    },
     middleware::{self, Next},
     response::IntoResponse,
-    routing::{delete, get, post},
+    routing::{delete, future::RouteFuture, get, patch, post},
+    serve::IncomingStream,
     Router,
 };
+use futures_util::future::Either;
 use rust_embed::RustEmbed;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use socket2::{Domain, Socket, TcpKeepalive, Type};
+use std::convert::Infallible;
+use std::future::Ready;
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::task::{Context, Poll};
 use std::time::{Duration, Instant};
+use surgedb_core::db::Collection;
 use surgedb_core::filter::Filter;
-use surgedb_core::{Config as DbConfig, Database, DistanceMetric, QuantizationType};
+use surgedb_core::{
+    CollectionStats, Config as DbConfig, Database, DimensionPolicy, DistanceMetric,
+    Error as DbError, Facets, IndexStatsSample, LocalDestination, MetadataUpdate, MissingGroup,
+    QuantizationType, RoutingDebug, ScoreTransform, SearchHit, SearchOptions, SelfRecall,
+    SnapshotShipper, StatsCollector, VectorId,
+};
 use sysinfo::System;
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, oneshot, OwnedSemaphorePermit, Semaphore};
+use tower::Service;
 use tower_http::{
     compression::CompressionLayer, cors::CorsLayer, limit::RequestBodyLimitLayer,
     timeout::TimeoutLayer, trace::TraceLayer,
 };
-use tracing::{info, warn};
+use tracing::{error, info, warn};
 use tracing_subscriber::{fmt, EnvFilter};
 use utoipa::{IntoParams, OpenApi, ToSchema};
 use utoipa_swagger_ui::SwaggerUi;
@@ -67,16 +89,45 @@ async fn index_handler() -> impl IntoResponse {
 // Configuration
 // =============================================================================
 
-#[derive(Clone)]
+#[derive(Clone, Serialize)]
 struct AppConfig {
     port: u16,
     web_port: u16,
+    #[serde(serialize_with = "redact_secret")]
     api_key: Option<String>,
     log_level: String,
     cors_allow_origin: String,
     request_timeout_secs: u64,
     max_request_size_bytes: usize,
+    max_list_limit: usize,
     data_dir: String,
+    /// Concurrent connections accepted before new ones get a 503 (0 = unlimited)
+    max_connections: usize,
+    /// Idle time before TCP keep-alive probes are sent (0 = disabled)
+    tcp_keepalive_secs: u64,
+    /// Snapshot persistent collections on graceful shutdown
+    checkpoint_on_shutdown: bool,
+    checkpoint_timeout_secs: u64,
+    /// Coalesce single inserts arriving within this window into one batch (0 = off)
+    write_batch_window_ms: u64,
+    /// Check index recall against brute force before serving
+    self_test: bool,
+    /// Concurrent searches allowed per collection before further ones queue (0 = unlimited)
+    search_concurrency_per_collection: usize,
+    /// Queries accepted in one batch search request
+    max_batch_queries: usize,
+    /// Seconds between self-recall checks of every collection (0 = off)
+    self_recall_interval_secs: u64,
+    /// Vectors searched for per collection in each self-recall check
+    self_recall_sample: usize,
+    /// Directory the latest snapshots of persistent collections are shipped
+    /// to, one data directory per collection (unset = off)
+    snapshot_ship_dir: Option<String>,
+    /// Seconds between snapshot shipments
+    snapshot_ship_interval_secs: u64,
+    /// Block size, in dimensions, of the blocked distance kernels used for
+    /// high-dimensional vectors (0 = one pass)
+    distance_block: usize,
 }
 
 impl AppConfig {
@@ -103,14 +154,75 @@ impl AppConfig {
                 .unwrap_or_else(|_| "10485760".to_string()) // 10MB
                 .parse()
                 .unwrap_or(10 * 1024 * 1024),
+            max_list_limit: std::env::var("MAX_LIST_LIMIT")
+                .unwrap_or_else(|_| "100".to_string())
+                .parse()
+                .unwrap_or(100),
             data_dir: std::env::var("DATA_DIR").unwrap_or_else(|_| "./data".to_string()),
+            max_connections: std::env::var("MAX_CONNECTIONS")
+                .unwrap_or_else(|_| "1024".to_string())
+                .parse()
+                .unwrap_or(1024),
+            tcp_keepalive_secs: std::env::var("TCP_KEEPALIVE_SECS")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .unwrap_or(60),
+            checkpoint_on_shutdown: std::env::var("CHECKPOINT_ON_SHUTDOWN")
+                .unwrap_or_else(|_| "true".to_string())
+                .parse()
+                .unwrap_or(true),
+            checkpoint_timeout_secs: std::env::var("CHECKPOINT_TIMEOUT_SECS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .unwrap_or(30),
+            write_batch_window_ms: std::env::var("WRITE_BATCH_WINDOW_MS")
+                .unwrap_or_else(|_| "0".to_string())
+                .parse()
+                .unwrap_or(0),
+            self_test: std::env::var("SELF_TEST")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .unwrap_or(false),
+            search_concurrency_per_collection: std::env::var("SEARCH_CONCURRENCY_PER_COLLECTION")
+                .unwrap_or_else(|_| "0".to_string())
+                .parse()
+                .unwrap_or(0),
+            max_batch_queries: std::env::var("MAX_BATCH_QUERIES")
+                .unwrap_or_else(|_| "1024".to_string())
+                .parse()
+                .unwrap_or(1024),
+            self_recall_interval_secs: std::env::var("SELF_RECALL_INTERVAL_SECS")
+                .unwrap_or_else(|_| "0".to_string())
+                .parse()
+                .unwrap_or(0),
+            self_recall_sample: std::env::var("SELF_RECALL_SAMPLE")
+                .unwrap_or_else(|_| "100".to_string())
+                .parse()
+                .unwrap_or(100),
+            snapshot_ship_dir: std::env::var("SNAPSHOT_SHIP_DIR").ok(),
+            snapshot_ship_interval_secs: std::env::var("SNAPSHOT_SHIP_INTERVAL_SECS")
+                .unwrap_or_else(|_| "300".to_string())
+                .parse()
+                .unwrap_or(300),
+            distance_block: std::env::var("DISTANCE_BLOCK")
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(surgedb_core::distance::DEFAULT_DISTANCE_BLOCK),
         }
     }
 }
 
+/// Report whether a secret is set without revealing it
+fn redact_secret<S: serde::Serializer>(
+    secret: &Option<String>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    secret.as_ref().map(|_| "[redacted]").serialize(serializer)
+}
+
 use chrono::{DateTime, Utc};
-use parking_lot::RwLock as PRwLock;
-use std::collections::VecDeque;
+use parking_lot::{Mutex as PMutex, RwLock as PRwLock};
+use std::collections::{BTreeMap, HashMap, VecDeque};
 
 // =============================================================================
 // Configuration
@@ -171,6 +283,12 @@ struct AppState {
     config: AppConfig,
     start_time: Instant,
     metrics: Arc<MetricsRegistry>,
+    index_stats: Arc<PRwLock<StatsCollector>>,
+    /// Set when `WRITE_BATCH_WINDOW_MS` is non-zero
+    write_batcher: Option<Arc<WriteBatcher>>,
+    search_limiter: Arc<SearchLimiter>,
+    /// Latest self-recall check per collection
+    self_recall: Arc<PRwLock<HashMap<String, SelfRecall>>>,
 }
 
 #[derive(Deserialize, ToSchema)]
@@ -184,12 +302,47 @@ struct CreateCollectionRequest {
     distance_metric: DistanceMetric,
     #[serde(default)]
     quantization: Option<QuantizationType>,
+    /// Metadata merged into every result's metadata (per-vector keys win)
+    #[serde(default)]
+    #[schema(example = json!({"tenant": "acme"}))]
+    default_metadata: Option<Value>,
+    /// Maximum serialized metadata size per vector in bytes (0 = unlimited)
+    #[serde(default)]
+    #[schema(example = 0)]
+    max_metadata_bytes: usize,
+    /// Reject inserts whose vectors are not unit length
+    #[serde(default)]
+    #[schema(example = false)]
+    require_unit_norm: bool,
+    /// Reject inserts whose vectors have NaN or infinite components
+    #[serde(default = "default_validate_finite")]
+    #[schema(example = true)]
+    validate_finite: bool,
+    /// Reject zero (under cosine) and wildly out-of-range query vectors
+    #[serde(default)]
+    #[schema(example = false)]
+    validate_query: bool,
+    /// Handling of wrong-length vectors: "Strict", "PadZero" or "Truncate"
+    #[serde(default)]
+    #[schema(example = "Strict")]
+    dimension_mismatch: DimensionPolicy,
+    /// Default score for search results: "Distance", "Similarity",
+    /// "NormalizedSimilarity" (0..1, higher is closer) or
+    /// {"Sigmoid": {"midpoint": 0.5, "steepness": 10.0}}
+    #[serde(default)]
+    #[schema(example = "Distance")]
+    score_transform: ScoreTransform,
+}
+
+fn default_validate_finite() -> bool {
+    true
 }
 
 #[derive(Deserialize, ToSchema)]
 struct InsertRequest {
-    #[schema(example = "vec1")]
-    id: String,
+    /// A string, or a non-negative integer; `7` and `"7"` are the same ID
+    #[schema(value_type = String, example = "vec1")]
+    id: VectorId,
     #[schema(example = "[0.1, 0.2, 0.3]")]
     vector: Vec<f32>,
     metadata: Option<Value>,
@@ -210,14 +363,206 @@ struct SearchRequest {
     /// When false, exclude metadata from response to reduce serialization overhead.
     #[serde(default)]
     include_metadata: Option<bool>,
+    /// When true, respond with a `SearchResponse` that also reports how many
+    /// candidates the filter rejected and whether the graph was fully explored.
+    #[serde(default)]
+    include_stats: bool,
+    /// Return only the closest hit per distinct value of this metadata field
+    /// (dot-separated path), for up to `k` groups
+    #[serde(default)]
+    #[schema(example = "author")]
+    group_by: Option<String>,
+    /// With `group_by`: "Exclude" hits lacking the field, or group them as "Null"
+    #[serde(default)]
+    #[schema(example = "Exclude")]
+    group_missing: MissingGroup,
+    /// HNSW candidate list size for this query (at least `k`); defaults to
+    /// the collection's `ef_search`. Higher is slower but finds more true neighbors.
+    #[serde(default)]
+    #[schema(example = 200)]
+    ef: Option<usize>,
+    /// How each hit's `score` is computed; defaults to the collection's
+    /// `score_transform`. Ranking is always by distance.
+    #[serde(default)]
+    #[schema(example = "Similarity")]
+    score_transform: Option<ScoreTransform>,
+    /// Attach each hit's stored vector as `vector`. Quantized collections
+    /// without originals return the de-quantized approximation.
+    #[serde(default)]
+    include_vectors: bool,
+}
+
+impl SearchRequest {
+    /// The core search options this request asks for
+    fn options(&mut self) -> SearchOptions {
+        SearchOptions {
+            ef: self.ef,
+            filter: self.filter.take(),
+            group_by: self.group_by.take(),
+            group_missing: self.group_missing,
+            include_vectors: self.include_vectors,
+            ..Default::default()
+        }
+    }
+}
+
+/// Search for the neighbors of a stored vector
+#[derive(Deserialize, ToSchema)]
+struct SimilarRequest {
+    /// Number of results; defaults to 10
+    #[serde(default)]
+    #[schema(example = 10)]
+    k: Option<usize>,
+    filter: Option<Filter>,
+    /// Leave the queried vector out of the results; defaults to true
+    #[serde(default)]
+    exclude_self: Option<bool>,
+    /// When false, exclude metadata from response to reduce serialization overhead.
+    #[serde(default)]
+    include_metadata: Option<bool>,
+    /// How each hit's `score` is computed (see `SearchRequest::score_transform`)
+    #[serde(default)]
+    score_transform: Option<ScoreTransform>,
+}
+
+/// One query of a batch search, with its own `k`
+#[derive(Deserialize, ToSchema)]
+struct BatchSearchQuery {
+    #[schema(example = "[0.1, 0.2, 0.3]")]
+    vector: Vec<f32>,
+    #[schema(example = 10)]
+    k: usize,
+}
+
+/// A batch search query: a bare vector using the request's `k`, or a
+/// vector with its own `k`
+#[derive(Deserialize, ToSchema)]
+#[serde(untagged)]
+enum BatchQuery {
+    #[schema(example = "[0.1, 0.2, 0.3]")]
+    Vector(Vec<f32>),
+    Query(BatchSearchQuery),
+}
+
+#[derive(Deserialize, ToSchema)]
+struct BatchSearchRequest {
+    queries: Vec<BatchQuery>,
+    /// Results per query for queries given as bare vectors
+    #[serde(default)]
+    #[schema(example = 10)]
+    k: Option<usize>,
+    /// Applied to every query
+    filter: Option<Filter>,
+    /// When false, exclude metadata from response to reduce serialization overhead.
+    #[serde(default)]
+    include_metadata: Option<bool>,
+    /// How each hit's `score` is computed (see `SearchRequest::score_transform`)
+    #[serde(default)]
+    score_transform: Option<ScoreTransform>,
+    /// Group every query's hits by this field (see `SearchRequest::group_by`)
+    #[serde(default)]
+    group_by: Option<String>,
+    #[serde(default)]
+    group_missing: MissingGroup,
+    /// HNSW candidate list size for every query (see `SearchRequest::ef`)
+    #[serde(default)]
+    ef: Option<usize>,
+    /// Attach each hit's stored vector as `vector`
+    #[serde(default)]
+    include_vectors: bool,
+}
+
+#[derive(Deserialize, IntoParams)]
+struct ExportParams {
+    /// Export format; only `jsonl` (the default) is supported
+    #[param(example = "jsonl")]
+    format: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct BatchSearchResponse {
+    /// One result list per query, in request order
+    results: Vec<Vec<SearchResult>>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct SearchResponse {
+    results: Vec<SearchResult>,
+    /// Candidates examined during traversal and rejected by the filter
+    filtered_out: usize,
+    /// Whether every reachable candidate was examined
+    exhausted: bool,
+    /// The collection's metric, which `distance` is measured in
+    metric: DistanceMetric,
+    /// How `score` was derived from `distance`
+    score_transform: ScoreTransform,
+    /// With `?debug=true`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    debug: Option<SearchDebug>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct SearchDebug {
+    /// Routing lists probed, nearest the query first, with the candidates
+    /// each contributed; null unless the collection has trained routing
+    #[schema(value_type = Option<Object>)]
+    routing: Option<RoutingDebug>,
 }
 
 #[derive(Serialize, ToSchema)]
 struct SearchResult {
     id: String,
+    /// Raw distance under the collection's metric (lower is closer)
     distance: f32,
+    /// `distance` under the request's score transform; equal to `distance`
+    /// unless a transform is requested or configured
+    score: f32,
     #[serde(skip_serializing_if = "Option::is_none")]
     metadata: Option<Value>,
+    /// The stored vector, with `include_vectors`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    vector: Option<Vec<f32>>,
+}
+
+/// Builds `SearchResult`s, scoring each distance under the chosen transform
+#[derive(Clone, Copy)]
+struct Scorer {
+    metric: DistanceMetric,
+    transform: ScoreTransform,
+}
+
+impl Scorer {
+    /// Score with `requested`, or the collection's default transform
+    fn new(collection: &Collection, requested: Option<ScoreTransform>) -> Self {
+        Self {
+            metric: collection.distance_metric(),
+            transform: requested.unwrap_or_else(|| collection.score_transform()),
+        }
+    }
+
+    fn result(&self, id: VectorId, distance: f32, metadata: Option<Value>) -> SearchResult {
+        SearchResult {
+            id: id.as_str().to_string(),
+            distance,
+            score: self.transform.apply(self.metric, distance),
+            metadata,
+            vector: None,
+        }
+    }
+
+    /// Results for the hits of a `SearchOutcome`, with their stored vectors
+    fn results(
+        &self,
+        hits: Vec<(SearchHit, Option<Vec<f32>>)>,
+        include_metadata: bool,
+    ) -> Vec<SearchResult> {
+        hits.into_iter()
+            .map(|((id, distance, metadata), vector)| SearchResult {
+                vector,
+                ..self.result(id, distance, metadata.filter(|_| include_metadata))
+            })
+            .collect()
+    }
 }
 
 #[derive(Serialize, ToSchema)]
@@ -237,6 +582,47 @@ struct HealthResponse {
 struct StatsResponse {
     uptime_seconds: u64,
     database: surgedb_core::DatabaseStats,
+    /// Searches currently holding a slot, by collection
+    search_in_flight: HashMap<String, usize>,
+    /// Latest self-recall check by collection (empty unless
+    /// `SELF_RECALL_INTERVAL_SECS` is set)
+    self_recall: HashMap<String, SelfRecall>,
+    /// Batched single inserts, or null unless `WRITE_BATCH_WINDOW_MS` is set
+    write_batches: Option<WriteBatchStats>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct WriteBatchStats {
+    /// Batches committed, each one `upsert_batch` (and WAL pass)
+    batches: u64,
+    /// Queued inserts those batches held
+    inserts: u64,
+}
+
+#[derive(Serialize, ToSchema)]
+struct AdminConfigResponse {
+    /// Settings the server was started with; the API key is redacted
+    #[schema(value_type = Object)]
+    server: AppConfig,
+    /// Effective configuration of each collection, by name
+    #[schema(value_type = Object)]
+    collections: BTreeMap<String, Value>,
+    build: BuildInfo,
+}
+
+#[derive(Serialize, ToSchema)]
+struct BuildInfo {
+    version: String,
+    /// Distance kernels in use: `neon`, `avx`, `simd128` or `scalar`
+    simd_backend: String,
+    /// Cargo features surgedb-core was compiled with
+    features: Vec<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct TimeseriesResponse {
+    /// Per-collection samples, oldest first
+    collections: std::collections::HashMap<String, Vec<IndexStatsSample>>,
 }
 
 #[derive(Deserialize, IntoParams)]
@@ -245,6 +631,39 @@ struct PaginationParams {
     offset: Option<usize>,
     #[param(example = 10)]
     limit: Option<usize>,
+    /// Resume from the previous page's `next_cursor` instead of using `offset`
+    cursor: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+struct SearchParams {
+    /// Respond with a `SearchResponse` carrying a `debug` block that reports
+    /// the routing lists the search probed
+    #[serde(default)]
+    debug: bool,
+}
+
+#[derive(Deserialize, IntoParams)]
+struct FacetParams {
+    /// Metadata field to aggregate (dot notation for nested fields)
+    #[param(example = "category")]
+    field: String,
+    /// Number of most frequent values to return
+    #[param(example = 10)]
+    top_n: Option<usize>,
+    /// Estimate counts from about this many vectors instead of scanning all
+    #[param(example = 1000)]
+    sample: Option<usize>,
+}
+
+#[derive(Deserialize, IntoParams)]
+struct GraphParams {
+    /// HNSW layer to export (0 holds every node)
+    #[param(example = 0)]
+    layer: Option<usize>,
+    /// Most edges to return; larger layers are sampled evenly
+    #[param(example = 10000)]
+    limit: Option<usize>,
 }
 
 #[derive(Serialize, ToSchema)]
@@ -263,23 +682,40 @@ struct VectorResponse {
     paths(
         health_check,
         get_stats,
+        get_stats_timeseries,
         get_metrics_history,
+        get_admin_config,
         create_collection,
         list_collections,
         delete_collection,
         insert_vector,
         list_vectors,
+        count_vectors,
+        get_collection_stats,
+        update_metadata,
+        set_vector_metadata,
         batch_insert_vector,
+        raw_insert_vector,
         upsert_vector,
         get_vector,
         delete_vector,
+        clear_vectors,
         search_vector,
+        similar_vectors,
+        batch_search_vector,
+        get_facets,
+        get_graph,
+        export_collection,
     ),
     components(
         schemas(
             CreateCollectionRequest, InsertRequest, BatchInsertRequest,
-            SearchRequest, SearchResult, ErrorResponse, HealthResponse,
-            StatsResponse, VectorResponse, MetricsSnapshot, VectorListEntry
+            SearchRequest, SearchResult, SearchResponse, SearchDebug, SimilarRequest, BatchQuery, BatchSearchQuery,
+            BatchSearchRequest,
+            BatchSearchResponse, ErrorResponse, HealthResponse,
+            StatsResponse, WriteBatchStats, TimeseriesResponse, VectorResponse, MetricsSnapshot, VectorListEntry,
+            VectorListResponse, CountResponse, UpdateMetadataRequest, UpdateMetadataResponse,
+            SetMetadataRequest, GraphResponse, GraphEdge, AdminConfigResponse, BuildInfo
         )
     ),
     tags(
@@ -352,10 +788,422 @@ async fn auth_middleware(
     Ok(next.run(req).await)
 }
 
+// =============================================================================
+// Connection Limits
+// =============================================================================
+
+/// Hands each accepted connection a permit from a shared semaphore.
+///
+/// Connections that arrive while every permit is held are still served, but
+/// only with `503 Service Unavailable` and `Connection: close`, so clients
+/// back off instead of piling up sockets under load.
+#[derive(Clone)]
+struct ConnectionLimit {
+    router: Router,
+    permits: Option<Arc<Semaphore>>,
+}
+
+impl ConnectionLimit {
+    fn new(router: Router, permits: Option<Arc<Semaphore>>) -> Self {
+        Self { router, permits }
+    }
+}
+
+impl<'a> Service<IncomingStream<'a>> for ConnectionLimit {
+    type Response = LimitedConnection;
+    type Error = Infallible;
+    type Future = Ready<Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _stream: IncomingStream<'a>) -> Self::Future {
+        let (admitted, permit) = match &self.permits {
+            None => (true, None),
+            Some(permits) => match permits.clone().try_acquire_owned() {
+                Ok(permit) => (true, Some(Arc::new(permit))),
+                Err(_) => (false, None),
+            },
+        };
+        std::future::ready(Ok(LimitedConnection {
+            router: self.router.clone(),
+            admitted,
+            _permit: permit,
+        }))
+    }
+}
+
+/// Per-connection service; the permit is released once hyper drops the
+/// connection and with it every clone of this service.
+#[derive(Clone)]
+struct LimitedConnection {
+    router: Router,
+    admitted: bool,
+    _permit: Option<Arc<OwnedSemaphorePermit>>,
+}
+
+impl Service<Request> for LimitedConnection {
+    type Response = axum::response::Response;
+    type Error = Infallible;
+    type Future = Either<Ready<Result<Self::Response, Self::Error>>, RouteFuture<Infallible>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        if !self.admitted {
+            let response = (
+                StatusCode::SERVICE_UNAVAILABLE,
+                [(axum::http::header::CONNECTION, "close")],
+                Json(ErrorResponse {
+                    error: "Too many open connections".to_string(),
+                }),
+            )
+                .into_response();
+            return Either::Left(std::future::ready(Ok(response)));
+        }
+        Either::Right(self.router.call(req))
+    }
+}
+
+/// Bind a listener whose accepted sockets inherit the TCP keep-alive settings.
+fn bind_listener(addr: SocketAddr, keepalive_secs: u64) -> std::io::Result<TcpListener> {
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, None)?;
+    socket.set_reuse_address(true)?;
+    if keepalive_secs > 0 {
+        let keepalive = TcpKeepalive::new().with_time(Duration::from_secs(keepalive_secs));
+        socket.set_tcp_keepalive(&keepalive)?;
+    }
+    socket.set_nonblocking(true)?;
+    socket.bind(&addr.into())?;
+    socket.listen(1024)?;
+    TcpListener::from_std(socket.into())
+}
+
+// =============================================================================
+// Write Batching
+// =============================================================================
+
+/// Most queued inserts committed in one batch
+const MAX_WRITE_BATCH: usize = 1024;
+
+/// Outcome of a queued insert, sent back to its handler
+type InsertReply = Result<(), (StatusCode, String)>;
+
+struct PendingInsert {
+    id: String,
+    vector: Vec<f32>,
+    metadata: Option<Value>,
+    reply: oneshot::Sender<InsertReply>,
+}
+
+/// Coalesces single inserts into one `upsert_batch` per collection.
+///
+/// Each collection gets a queue drained by its own task: the first insert to
+/// arrive opens a window, and everything queued when it closes is committed
+/// under a single write lock (and WAL pass for persistent collections).
+/// Handlers respond once the batch holding their insert has committed.
+struct WriteBatcher {
+    db: Arc<Database>,
+    window: Duration,
+    queues: PMutex<HashMap<String, mpsc::UnboundedSender<PendingInsert>>>,
+    batches: std::sync::atomic::AtomicU64,
+    inserts: std::sync::atomic::AtomicU64,
+}
+
+impl WriteBatcher {
+    fn new(db: Arc<Database>, window: Duration) -> Self {
+        Self {
+            db,
+            window,
+            queues: PMutex::new(HashMap::new()),
+            batches: std::sync::atomic::AtomicU64::new(0),
+            inserts: std::sync::atomic::AtomicU64::new(0),
+        }
+    }
+
+    fn stats(&self) -> WriteBatchStats {
+        use std::sync::atomic::Ordering;
+        WriteBatchStats {
+            batches: self.batches.load(Ordering::Relaxed),
+            inserts: self.inserts.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Queue an insert and wait for the batch holding it to commit
+    async fn insert(
+        self: &Arc<Self>,
+        collection: String,
+        id: String,
+        vector: Vec<f32>,
+        metadata: Option<Value>,
+    ) -> InsertReply {
+        let (reply, committed) = oneshot::channel();
+        let queue = self
+            .queues
+            .lock()
+            .entry(collection.clone())
+            .or_insert_with(|| {
+                let (queue, pending) = mpsc::unbounded_channel();
+                tokio::spawn(self.clone().drain(collection, pending));
+                queue
+            })
+            .clone();
+
+        let dropped = || {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "write queue dropped the insert".to_string(),
+            )
+        };
+        queue
+            .send(PendingInsert {
+                id,
+                vector,
+                metadata,
+                reply,
+            })
+            .map_err(|_| dropped())?;
+        committed.await.map_err(|_| dropped())?
+    }
+
+    /// Commit one collection's queued inserts a window at a time
+    async fn drain(
+        self: Arc<Self>,
+        collection: String,
+        mut pending: mpsc::UnboundedReceiver<PendingInsert>,
+    ) {
+        while let Some(first) = pending.recv().await {
+            tokio::time::sleep(self.window).await;
+            let mut batch = vec![first];
+            while batch.len() < MAX_WRITE_BATCH {
+                match pending.try_recv() {
+                    Ok(next) => batch.push(next),
+                    Err(_) => break,
+                }
+            }
+
+            let (items, replies): (Vec<_>, Vec<_>) = batch
+                .into_iter()
+                .map(|p| ((p.id, p.vector, p.metadata), p.reply))
+                .unzip();
+            let db = self.db.clone();
+            let name = collection.clone();
+            let outcomes =
+                match tokio::task::spawn_blocking(move || commit_batch(&db, &name, items)).await {
+                    Ok(outcomes) => outcomes,
+                    Err(e) => {
+                        vec![Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string())); replies.len()]
+                    }
+                };
+            self.batches
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            self.inserts
+                .fetch_add(replies.len() as u64, std::sync::atomic::Ordering::Relaxed);
+            for (reply, outcome) in replies.into_iter().zip(outcomes) {
+                // The client may have gone away
+                let _ = reply.send(outcome);
+            }
+        }
+    }
+}
+
+/// Commit a batch of queued inserts, returning each one's outcome in order
+///
+/// Inserts keep their usual semantics: an ID that is already stored, or taken
+/// earlier in the same batch, is rejected without affecting the others. The
+/// check and the write happen under the collection's write lock, so a
+/// concurrent insert of the same ID is never overwritten.
+fn commit_batch(
+    db: &Database,
+    name: &str,
+    items: Vec<(String, Vec<f32>, Option<Value>)>,
+) -> Vec<InsertReply> {
+    let collection = match db.get_collection(name) {
+        Ok(collection) => collection,
+        Err(e) => return vec![Err((StatusCode::NOT_FOUND, e.to_string())); items.len()],
+    };
+
+    collection
+        .insert_each(items)
+        .into_iter()
+        .map(|outcome| outcome.map_err(|e| (StatusCode::BAD_REQUEST, e.to_string())))
+        .collect()
+}
+
+// =============================================================================
+// Search Isolation
+// =============================================================================
+
+/// Per-collection search permits, so a burst of searches on one collection
+/// queues behind its own limit instead of filling the shared blocking pool
+struct SearchLimiter {
+    permits: usize,
+    collections: PMutex<HashMap<String, Arc<Semaphore>>>,
+}
+
+impl SearchLimiter {
+    fn new(limit: usize) -> Self {
+        Self {
+            permits: if limit == 0 {
+                Semaphore::MAX_PERMITS
+            } else {
+                limit
+            },
+            collections: PMutex::new(HashMap::new()),
+        }
+    }
+
+    /// Wait for a search slot on `collection`; the slot frees when the permit drops
+    async fn acquire(&self, collection: &str) -> OwnedSemaphorePermit {
+        let semaphore = self
+            .collections
+            .lock()
+            .entry(collection.to_string())
+            .or_insert_with(|| Arc::new(Semaphore::new(self.permits)))
+            .clone();
+        semaphore
+            .acquire_owned()
+            .await
+            .expect("search semaphores are never closed")
+    }
+
+    /// Searches holding a permit on each collection that has been searched
+    fn in_flight(&self) -> HashMap<String, usize> {
+        self.collections
+            .lock()
+            .iter()
+            .map(|(name, semaphore)| (name.clone(), self.permits - semaphore.available_permits()))
+            .collect()
+    }
+
+    /// Stop tracking a deleted collection; searches already running keep their permits
+    fn remove(&self, collection: &str) {
+        self.collections.lock().remove(collection);
+    }
+}
+
 // =============================================================================
 // Main Entry Point
 // =============================================================================
 
+/// Compare index search with brute force for every metric, so a broken
+/// distance kernel is caught before serving wrong results
+fn run_self_test() -> Result<(), String> {
+    let start = Instant::now();
+    let reports = surgedb_core::self_test::run().map_err(|e| e.to_string())?;
+    let mut failed = Vec::new();
+    for report in &reports {
+        info!(
+            "Self-test {:?}: recall@10 {:.2}",
+            report.metric, report.recall
+        );
+        if !report.passed() {
+            failed.push(format!("{:?} recall {:.2}", report.metric, report.recall));
+        }
+    }
+    if !failed.is_empty() {
+        return Err(format!(
+            "recall below {} for {} (broken distance function or SIMD path?)",
+            surgedb_core::self_test::MIN_SELF_TEST_RECALL,
+            failed.join(", ")
+        ));
+    }
+    info!("Self-test passed in {:?}", start.elapsed());
+    Ok(())
+}
+
+/// Results checked for each sampled vector during a self-recall check
+const SELF_RECALL_K: usize = 10;
+
+/// Periodically check that each collection's stored vectors are still found
+/// by searching for them, replacing the previous results in `state.self_recall`
+async fn run_self_recall(state: AppState) {
+    let interval = Duration::from_secs(state.config.self_recall_interval_secs);
+    let sample = state.config.self_recall_sample;
+    let mut seed = 0u64;
+    loop {
+        tokio::time::sleep(interval).await;
+        // A fresh sample each round, so over time the whole index is covered
+        seed = seed.wrapping_add(1);
+        let db = state.db.clone();
+        let checked = tokio::task::spawn_blocking(move || {
+            db.list_collections()
+                .into_iter()
+                .filter_map(|name| {
+                    let collection = db.get_collection(&name).ok()?;
+                    let recall = collection.self_recall(sample, SELF_RECALL_K, seed)?;
+                    Some((name, recall))
+                })
+                .collect::<HashMap<_, _>>()
+        })
+        .await;
+        match checked {
+            Ok(checked) => {
+                for (name, recall) in &checked {
+                    if recall.recall < 0.9 {
+                        warn!(
+                            "Self-recall for collection '{}' is {:.1}% over {} vectors",
+                            name,
+                            recall.recall * 100.0,
+                            recall.sampled
+                        );
+                    }
+                }
+                *state.self_recall.write() = checked;
+            }
+            Err(e) => error!("Self-recall check failed: {}", e),
+        }
+    }
+}
+
+/// Periodically ship each persistent collection's latest snapshot chain to
+/// `<dir>/<collection>`, which a warm standby can open as its data directory
+async fn run_snapshot_shipping(db: Arc<Database>, dir: std::path::PathBuf, interval: Duration) {
+    let mut shippers: HashMap<String, SnapshotShipper> = HashMap::new();
+    loop {
+        tokio::time::sleep(interval).await;
+        let db = db.clone();
+        let dir = dir.clone();
+        let shipped = tokio::task::spawn_blocking(move || {
+            for name in db.list_collections() {
+                let Ok(collection) = db.get_collection(&name) else {
+                    continue;
+                };
+                // Rounds are already an interval apart, so shippers never hold back
+                let shipper = match shippers.entry(name.clone()) {
+                    std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+                    std::collections::hash_map::Entry::Vacant(entry) => {
+                        match LocalDestination::new(dir.join(&name)) {
+                            Ok(destination) => {
+                                entry.insert(SnapshotShipper::new(destination, Duration::ZERO))
+                            }
+                            Err(e) => {
+                                error!("Snapshot shipping for '{}' failed: {}", name, e);
+                                continue;
+                            }
+                        }
+                    }
+                };
+                if let Err(e) = collection.ship_snapshots(shipper) {
+                    error!("Snapshot shipping for '{}' failed: {}", name, e);
+                }
+            }
+            shippers
+        })
+        .await;
+        match shipped {
+            Ok(kept) => shippers = kept,
+            Err(e) => {
+                error!("Snapshot shipping failed: {}", e);
+                shippers = HashMap::new();
+            }
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() {
     let config = AppConfig::from_env();
@@ -366,14 +1214,33 @@ async fn main() {
     fmt().with_env_filter(env_filter).with_target(false).init();
 
     info!("Starting SurgeDB Server v{}", env!("CARGO_PKG_VERSION"));
+    surgedb_core::distance::set_distance_block(Some(config.distance_block).filter(|&b| b > 0));
+
+    if config.self_test {
+        if let Err(e) = run_self_test() {
+            error!("Self-test failed, refusing to start: {}", e);
+            std::process::exit(1);
+        }
+    }
 
     let db = Database::open(&config.data_dir).expect("Failed to open database");
     let metrics = Arc::new(MetricsRegistry::new());
+    let db = Arc::new(db);
+    let write_batcher = (config.write_batch_window_ms > 0).then(|| {
+        Arc::new(WriteBatcher::new(
+            db.clone(),
+            Duration::from_millis(config.write_batch_window_ms),
+        ))
+    });
     let state = AppState {
-        db: Arc::new(db),
+        db,
         config: config.clone(),
         start_time: Instant::now(),
         metrics: metrics.clone(),
+        index_stats: Arc::new(PRwLock::new(StatsCollector::new(600))),
+        write_batcher,
+        search_limiter: Arc::new(SearchLimiter::new(config.search_concurrency_per_collection)),
+        self_recall: Arc::new(PRwLock::new(HashMap::new())),
     };
 
     // Background task for metrics collection
@@ -398,6 +1265,7 @@ async fn main() {
                 storage_usage_bytes: db_stats.total_memory_bytes as u64,
             };
             state_clone.metrics.history.write().push_back(snapshot);
+            state_clone.index_stats.write().collect(&state_clone.db);
         }
 
         loop {
@@ -451,20 +1319,42 @@ async fn main() {
                 history.pop_front();
             }
             history.push_back(snapshot);
+            drop(history);
+
+            state_clone.index_stats.write().collect(&state_clone.db);
         }
     });
 
+    if config.self_recall_interval_secs > 0 {
+        tokio::spawn(run_self_recall(state.clone()));
+    }
+
+    if let Some(dir) = &config.snapshot_ship_dir {
+        info!(
+            "Shipping snapshots to {} every {}s",
+            dir, config.snapshot_ship_interval_secs
+        );
+        tokio::spawn(run_snapshot_shipping(
+            state.db.clone(),
+            dir.into(),
+            Duration::from_secs(config.snapshot_ship_interval_secs.max(1)),
+        ));
+    }
+
     let cors = CorsLayer::new()
         .allow_origin(config.cors_allow_origin.parse::<HeaderValue>().unwrap())
         .allow_methods([Method::GET, Method::POST, Method::DELETE])
         .allow_headers([
             axum::http::header::CONTENT_TYPE,
             HeaderName::from_static("x-api-key"),
-        ]);
+        ])
+        .expose_headers([HeaderName::from_static("x-total-count")]);
 
     let api_routes = Router::new()
         .route("/stats", get(get_stats))
+        .route("/stats/timeseries", get(get_stats_timeseries))
         .route("/metrics/history", get(get_metrics_history))
+        .route("/admin/config", get(get_admin_config))
         .route(
             "/collections",
             post(create_collection).get(list_collections),
@@ -472,18 +1362,35 @@ async fn main() {
         .route("/collections/:name", delete(delete_collection))
         .route(
             "/collections/:name/vectors",
-            post(insert_vector).get(list_vectors),
+            post(insert_vector).get(list_vectors).delete(clear_vectors),
         )
+        .route("/collections/:name/count", get(count_vectors))
+        .route("/collections/:name/stats", get(get_collection_stats))
+        .route("/collections/:name/metadata", post(update_metadata))
         .route(
             "/collections/:name/vectors/batch",
             post(batch_insert_vector),
         )
+        .route("/collections/:name/vectors/raw", post(raw_insert_vector))
         .route("/collections/:name/upsert", post(upsert_vector))
         .route(
             "/collections/:name/vectors/:id",
             get(get_vector).delete(delete_vector),
         )
+        .route(
+            "/collections/:name/vectors/:id/metadata",
+            patch(set_vector_metadata),
+        )
+        .route(
+            "/collections/:name/vectors/:id/similar",
+            post(similar_vectors),
+        )
         .route("/collections/:name/search", post(search_vector))
+        .route("/collections/:name/search/batch", post(batch_search_vector))
+        .route("/collections/:name/facets", get(get_facets))
+        .route("/collections/:name/graph", get(get_graph))
+        .route("/collections/:name/export", get(export_collection))
+        .route("/ws/search", get(ws_search))
         .layer(middleware::from_fn_with_state(
             state.clone(),
             auth_middleware,
@@ -505,6 +1412,7 @@ async fn main() {
         .layer(RequestBodyLimitLayer::new(config.max_request_size_bytes))
         .layer(cors);
 
+    let db = state.db.clone();
     let api_app = api_router.clone().with_state(state.clone());
 
     let web_app = Router::new()
@@ -520,8 +1428,14 @@ async fn main() {
     info!("API Server listening on {}", api_addr);
     info!("Web Interface listening on {}", web_addr);
 
-    let api_listener = tokio::net::TcpListener::bind(api_addr).await.unwrap();
-    let web_listener = tokio::net::TcpListener::bind(web_addr).await.unwrap();
+    let api_listener = bind_listener(api_addr, config.tcp_keepalive_secs).unwrap();
+    let web_listener = bind_listener(web_addr, config.tcp_keepalive_secs).unwrap();
+
+    // Both listeners draw from one pool so the cap bounds the whole process
+    let permits =
+        (config.max_connections > 0).then(|| Arc::new(Semaphore::new(config.max_connections)));
+    let api_app = ConnectionLimit::new(api_app, permits.clone());
+    let web_app = ConnectionLimit::new(web_app, permits);
 
     let api_server = axum::serve(api_listener, api_app).with_graceful_shutdown(shutdown_signal());
     let web_server = axum::serve(web_listener, web_app).with_graceful_shutdown(shutdown_signal());
@@ -538,10 +1452,38 @@ async fn main() {
             }
         }
     }
+
+    if config.checkpoint_on_shutdown {
+        checkpoint_collections(db, Duration::from_secs(config.checkpoint_timeout_secs)).await;
+    }
 }
 
-async fn shutdown_signal() {
-    let ctrl_c = async {
+/// Snapshot persistent collections so the next start skips a long WAL replay
+async fn checkpoint_collections(db: Arc<Database>, timeout: Duration) {
+    info!("Checkpointing collections before exit...");
+    let start = Instant::now();
+    let task = tokio::task::spawn_blocking(move || db.checkpoint_all());
+    match tokio::time::timeout(timeout, task).await {
+        Ok(Ok(results)) => {
+            for (name, result) in results {
+                match result {
+                    Ok(()) => info!("Checkpointed collection {}", name),
+                    Err(e) => warn!("Failed to checkpoint collection {}: {}", name, e),
+                }
+            }
+            info!("Shutdown checkpoint finished in {:?}", start.elapsed());
+        }
+        Ok(Err(e)) => warn!("Shutdown checkpoint task failed: {}", e),
+        Err(_) => {
+            // The runtime would otherwise wait for the blocking task on exit
+            warn!("Shutdown checkpoint timed out after {:?}", timeout);
+            std::process::exit(1);
+        }
+    }
+}
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
         tokio::signal::ctrl_c()
             .await
             .expect("failed to install Ctrl+C handler");
@@ -623,6 +1565,55 @@ async fn get_stats(State(state): State<AppState>) -> Json<StatsResponse> {
     Json(StatsResponse {
         uptime_seconds: uptime,
         database: stats,
+        search_in_flight: state.search_limiter.in_flight(),
+        self_recall: state.self_recall.read().clone(),
+        write_batches: state.write_batcher.as_ref().map(|batcher| batcher.stats()),
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/config",
+    responses(
+        (status = 200, description = "Effective server and collection configuration, with build info", body = AdminConfigResponse)
+    ),
+    security(("api_key" = []))
+)]
+async fn get_admin_config(State(state): State<AppState>) -> Json<AdminConfigResponse> {
+    let collections = state
+        .db
+        .list_collections()
+        .into_iter()
+        .filter_map(|name| {
+            let config = state.db.get_collection(&name).ok()?.config();
+            Some((name, config))
+        })
+        .collect();
+    Json(AdminConfigResponse {
+        server: state.config.clone(),
+        collections,
+        build: BuildInfo {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            simd_backend: surgedb_core::distance::simd_backend().to_string(),
+            features: surgedb_core::enabled_features()
+                .into_iter()
+                .map(String::from)
+                .collect(),
+        },
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/stats/timeseries",
+    responses(
+        (status = 200, description = "Index statistics sampled every 6s per collection", body = TimeseriesResponse)
+    ),
+    security(("api_key" = []))
+)]
+async fn get_stats_timeseries(State(state): State<AppState>) -> Json<TimeseriesResponse> {
+    Json(TimeseriesResponse {
+        collections: state.index_stats.read().export(),
     })
 }
 
@@ -644,6 +1635,13 @@ async fn create_collection(
         dimensions: payload.dimensions,
         distance_metric: payload.distance_metric,
         quantization: payload.quantization.unwrap_or(QuantizationType::None),
+        default_metadata: payload.default_metadata,
+        max_metadata_bytes: payload.max_metadata_bytes,
+        require_unit_norm: payload.require_unit_norm,
+        validate_finite: payload.validate_finite,
+        validate_query: payload.validate_query,
+        dimension_mismatch: payload.dimension_mismatch,
+        score_transform: payload.score_transform,
         ..DbConfig::default()
     };
 
@@ -694,6 +1692,7 @@ async fn delete_collection(
 ) -> Result<&'static str, (StatusCode, Json<ErrorResponse>)> {
     match state.db.delete_collection(&name) {
         Ok(_) => {
+            state.search_limiter.remove(&name);
             info!("Deleted collection: {}", name);
             Ok("Deleted")
         }
@@ -736,32 +1735,167 @@ async fn insert_vector(
     })?;
 
     let work_start = Instant::now();
-    let result = tokio::task::spawn_blocking(move || {
-        collection.insert(payload.id, &payload.vector, payload.metadata)
-    })
-    .await
-    .map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: e.to_string(),
-            }),
-        )
-    })?;
+    let id = payload.id.to_string();
+    let result = match &state.write_batcher {
+        Some(batcher) => {
+            batcher
+                .insert(name, id, payload.vector, payload.metadata)
+                .await
+        }
+        None => tokio::task::spawn_blocking(move || {
+            collection
+                .insert(id, &payload.vector, payload.metadata)
+                .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))
+        })
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: e.to_string(),
+                }),
+            )
+        })?,
+    };
 
     let work_ms = work_start.elapsed().as_secs_f64() * 1000.0;
     let total_ms = handler_start.elapsed().as_secs_f64() * 1000.0;
     log_perf("insert_vector", total_ms, work_ms, None, None);
 
     match result {
-        Ok(_) => Ok("Inserted"),
-        Err(e) => Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: e.to_string(),
-            }),
-        )),
+        Ok(()) => Ok("Inserted"),
+        Err((status, error)) => Err((status, Json(ErrorResponse { error }))),
+    }
+}
+
+/// Parse a raw insert body for a collection of `dimensions`
+///
+/// Layout, all integers and floats little-endian:
+/// `u32 count, u32 dim`, then `count * dim` packed `f32`s, then `count` IDs,
+/// each a `u32` byte length followed by that many UTF-8 bytes.
+///
+/// The header is checked against `dimensions` and the body length before
+/// anything is allocated from it.
+fn parse_raw_vectors(body: &[u8], dimensions: usize) -> Result<Vec<(String, Vec<f32>)>, String> {
+    let mut rest = body;
+    let mut take = |len: usize, what: &str| -> Result<&[u8], String> {
+        if rest.len() < len {
+            return Err(format!("body truncated in {}", what));
+        }
+        let (head, tail) = rest.split_at(len);
+        rest = tail;
+        Ok(head)
+    };
+    let read_u32 = |bytes: &[u8]| u32::from_le_bytes(bytes.try_into().unwrap()) as usize;
+
+    let count = read_u32(take(4, "header")?);
+    let dim = read_u32(take(4, "header")?);
+    if dim == 0 {
+        return Err("dim must be positive".to_string());
+    }
+    if dim != dimensions {
+        return Err(format!(
+            "dimension mismatch: expected {}, got {}",
+            dimensions, dim
+        ));
     }
+    // Every item needs its floats and an ID length prefix at least
+    let item_bytes = 4 + 4 * dim;
+    if count > (body.len() - 8) / item_bytes {
+        return Err(format!(
+            "count {} does not fit in a {} byte body",
+            count,
+            body.len()
+        ));
+    }
+    let floats: Vec<f32> = take(count * dim * 4, "vectors")?
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+        .collect();
+    let mut vectors = floats.chunks(dim).map(<[f32]>::to_vec);
+
+    let mut items = Vec::with_capacity(count);
+    for i in 0..count {
+        let len = read_u32(take(4, "IDs")?);
+        let id = std::str::from_utf8(take(len, "IDs")?)
+            .map_err(|_| format!("ID {} is not valid UTF-8", i))?;
+        items.push((id.to_string(), vectors.next().unwrap_or_default()));
+    }
+    if !rest.is_empty() {
+        return Err(format!("{} unexpected trailing bytes", rest.len()));
+    }
+    Ok(items)
+}
+
+#[utoipa::path(
+    post,
+    path = "/collections/{name}/vectors/raw",
+    params(
+        ("name" = String, Path, description = "Collection name")
+    ),
+    request_body(
+        content = Vec<u8>,
+        content_type = "application/octet-stream",
+        description = "u32 count, u32 dim, count * dim f32 vectors, then count u32-length-prefixed UTF-8 IDs; all little-endian"
+    ),
+    responses(
+        (status = 200, description = "Number of vectors upserted", body = usize),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 415, description = "Content-Type is not application/octet-stream", body = ErrorResponse)
+    ),
+    security(("api_key" = []))
+)]
+async fn raw_insert_vector(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<usize>, (StatusCode, Json<ErrorResponse>)> {
+    let handler_start = Instant::now();
+    let error = |status, error: String| (status, Json(ErrorResponse { error }));
+
+    let content_type = headers
+        .get(axum::http::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok());
+    if content_type != Some("application/octet-stream") {
+        return Err(error(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "expected Content-Type: application/octet-stream".to_string(),
+        ));
+    }
+
+    let collection = state
+        .db
+        .get_collection(&name)
+        .map_err(|e| error(StatusCode::NOT_FOUND, e.to_string()))?;
+
+    let work_start = Instant::now();
+    let result = tokio::task::spawn_blocking(move || {
+        let items = parse_raw_vectors(&body, collection.dimensions())?;
+        let count = items.len();
+        let items = items
+            .into_iter()
+            .map(|(id, vector)| (id, vector, None))
+            .collect();
+        collection.upsert_batch(items).map_err(|e| e.to_string())?;
+        Ok(count)
+    })
+    .await
+    .map_err(|e| error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let work_ms = work_start.elapsed().as_secs_f64() * 1000.0;
+    let total_ms = handler_start.elapsed().as_secs_f64() * 1000.0;
+    log_perf(
+        "raw_insert_vector",
+        total_ms,
+        work_ms,
+        None,
+        result.as_ref().ok().copied(),
+    );
+
+    result
+        .map(Json)
+        .map_err(|e| error(StatusCode::BAD_REQUEST, e))
 }
 
 #[utoipa::path(
@@ -794,7 +1928,7 @@ async fn upsert_vector(
 
     let work_start = Instant::now();
     let result = tokio::task::spawn_blocking(move || {
-        collection.upsert(payload.id, &payload.vector, payload.metadata)
+        collection.upsert(payload.id.to_string(), &payload.vector, payload.metadata)
     })
     .await
     .map_err(|e| {
@@ -855,7 +1989,7 @@ async fn batch_insert_vector(
         let items: Vec<(String, Vec<f32>, Option<Value>)> = payload
             .vectors
             .into_iter()
-            .map(|item| (item.id, item.vector, item.metadata))
+            .map(|item| (item.id.to_string(), item.vector, item.metadata))
             .collect();
 
         collection.upsert_batch(items)?;
@@ -1000,6 +2134,53 @@ async fn delete_vector(
     }
 }
 
+#[utoipa::path(
+    delete,
+    path = "/collections/{name}/vectors",
+    params(
+        ("name" = String, Path, description = "Collection name")
+    ),
+    responses(
+        (status = 200, description = "All vectors removed; the collection and its config remain"),
+        (status = 404, description = "Collection not found", body = ErrorResponse)
+    ),
+    security(("api_key" = []))
+)]
+async fn clear_vectors(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<&'static str, (StatusCode, Json<ErrorResponse>)> {
+    let collection = state.db.get_collection(&name).map_err(|e| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )
+    })?;
+
+    let result = tokio::task::spawn_blocking(move || collection.clear())
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: e.to_string(),
+                }),
+            )
+        })?;
+
+    match result {
+        Ok(()) => Ok("Cleared"),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )),
+    }
+}
+
 #[derive(Serialize, ToSchema)]
 struct VectorListEntry {
     id: String,
@@ -1007,6 +2188,14 @@ struct VectorListEntry {
     metadata: Option<Value>,
 }
 
+#[derive(Serialize, ToSchema)]
+struct VectorListResponse {
+    vectors: Vec<VectorListEntry>,
+    /// Opaque position to pass as `cursor` for the next page; absent on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_cursor: Option<String>,
+}
+
 #[utoipa::path(
     get,
     path = "/collections/{name}/vectors",
@@ -1015,7 +2204,8 @@ struct VectorListEntry {
         PaginationParams
     ),
     responses(
-        (status = 200, description = "List of vector records", body = [VectorListEntry])
+        (status = 200, description = "Page of vector records; the X-Total-Count header holds the collection size", body = VectorListResponse),
+        (status = 400, description = "Malformed cursor", body = ErrorResponse)
     ),
     security(("api_key" = []))
 )]
@@ -1023,7 +2213,7 @@ async fn list_vectors(
     State(state): State<AppState>,
     Path(name): Path<String>,
     Query(params): Query<PaginationParams>,
-) -> Result<Json<Vec<VectorListEntry>>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
     let collection = state.db.get_collection(&name).map_err(|e| {
         (
             StatusCode::NOT_FOUND,
@@ -1034,54 +2224,79 @@ async fn list_vectors(
     })?;
 
     let offset = params.offset.unwrap_or(0);
-    let limit = params.limit.unwrap_or(10).min(100);
-
-    let result = tokio::task::spawn_blocking(move || collection.list(offset, limit))
-        .await
-        .map_err(|e| {
+    let limit = params.limit.unwrap_or(10).min(state.config.max_list_limit);
+    let cursor = params
+        .cursor
+        .map(|cursor| cursor.parse::<u64>())
+        .transpose()
+        .map_err(|_| {
             (
-                StatusCode::INTERNAL_SERVER_ERROR,
+                StatusCode::BAD_REQUEST,
                 Json(ErrorResponse {
-                    error: e.to_string(),
+                    error: "Malformed cursor".to_string(),
                 }),
             )
         })?;
 
-    Ok(Json(
-        result
-            .into_iter()
-            .map(|(id, metadata)| VectorListEntry {
-                id: id.to_string(),
-                metadata,
-            })
-            .collect(),
+    let (page, total) = tokio::task::spawn_blocking(move || {
+        // Offset pages list from the start too, so they also carry a cursor
+        let page = match cursor {
+            Some(cursor) => collection.list_after(Some(cursor), limit),
+            None => {
+                let mut page = collection.list_after(None, offset.saturating_add(limit));
+                page.entries.drain(..offset.min(page.entries.len()));
+                page
+            }
+        };
+        (page, collection.len())
+    })
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )
+    })?;
+
+    Ok((
+        [(HeaderName::from_static("x-total-count"), total.to_string())],
+        Json(VectorListResponse {
+            vectors: page
+                .entries
+                .into_iter()
+                .map(|(id, metadata)| VectorListEntry {
+                    id: id.to_string(),
+                    metadata,
+                })
+                .collect(),
+            next_cursor: page.next_cursor.map(|cursor| cursor.to_string()),
+        }),
     ))
 }
 
+#[derive(Serialize, ToSchema)]
+struct CountResponse {
+    count: usize,
+}
+
 #[utoipa::path(
-    post,
-    path = "/collections/{name}/search",
+    get,
+    path = "/collections/{name}/count",
     params(
         ("name" = String, Path, description = "Collection name")
     ),
-    request_body = SearchRequest,
     responses(
-        (status = 200, description = "List of nearest neighbors", body = [SearchResult]),
-        (status = 400, description = "Invalid request", body = ErrorResponse)
+        (status = 200, description = "Number of vectors in the collection", body = CountResponse),
+        (status = 404, description = "Collection not found", body = ErrorResponse)
     ),
     security(("api_key" = []))
 )]
-async fn search_vector(
+async fn count_vectors(
     State(state): State<AppState>,
     Path(name): Path<String>,
-    Json(payload): Json<SearchRequest>,
-) -> Result<Json<Vec<SearchResult>>, (StatusCode, Json<ErrorResponse>)> {
-    let handler_start = Instant::now();
-    let include_metadata = payload.include_metadata.unwrap_or(true);
-    let vector = payload.vector;
-    let k = payload.k;
-    let filter = payload.filter;
-
+) -> Result<Json<CountResponse>, (StatusCode, Json<ErrorResponse>)> {
     let collection = state.db.get_collection(&name).map_err(|e| {
         (
             StatusCode::NOT_FOUND,
@@ -1091,76 +2306,325 @@ async fn search_vector(
         )
     })?;
 
-    if include_metadata {
-        let work_start = Instant::now();
-        let result = tokio::task::spawn_blocking(move || {
-            collection.search(&vector, k, filter.as_ref())
-        })
-        .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: e.to_string(),
-                }),
-            )
-        })?;
+    Ok(Json(CountResponse {
+        count: collection.len(),
+    }))
+}
 
-        match result {
-            Ok(results) => {
-                let map_start = Instant::now();
-                let response: Vec<SearchResult> = results
-                    .into_iter()
-                    .map(|(id, distance, metadata)| SearchResult {
-                        id: id.as_str().to_string(),
-                        distance,
-                        metadata,
-                    })
-                    .collect();
-                let work_ms = work_start.elapsed().as_secs_f64() * 1000.0;
-                let map_ms = map_start.elapsed().as_secs_f64() * 1000.0;
-                let total_ms = handler_start.elapsed().as_secs_f64() * 1000.0;
-                log_perf("search_vector", total_ms, work_ms, Some(map_ms), Some(response.len()));
-                Ok(Json(response))
-            }
-            Err(e) => Err((
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    error: e.to_string(),
-                }),
-            )),
-        }
-    } else {
-        let work_start = Instant::now();
-        let result = tokio::task::spawn_blocking(move || {
-            collection.search_ids(&vector, k, filter.as_ref())
-        })
-        .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: e.to_string(),
-                }),
-            )
-        })?;
+#[utoipa::path(
+    get,
+    path = "/collections/{name}/stats",
+    params(
+        ("name" = String, Path, description = "Collection name")
+    ),
+    responses(
+        (status = 200, description = "Vector count, configuration and memory of the collection", body = CollectionStats),
+        (status = 404, description = "Collection not found", body = ErrorResponse)
+    ),
+    security(("api_key" = []))
+)]
+async fn get_collection_stats(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<CollectionStats>, (StatusCode, Json<ErrorResponse>)> {
+    state.db.collection_stats(&name).map(Json).map_err(|e| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )
+    })
+}
 
-        match result {
-            Ok(results) => {
-                let map_start = Instant::now();
-                let response: Vec<SearchResult> = results
-                    .into_iter()
-                    .map(|(id, distance)| SearchResult {
-                        id: id.as_str().to_string(),
-                        distance,
-                        metadata: None,
-                    })
-                    .collect();
-                let work_ms = work_start.elapsed().as_secs_f64() * 1000.0;
-                let map_ms = map_start.elapsed().as_secs_f64() * 1000.0;
+#[derive(Deserialize, ToSchema)]
+struct UpdateMetadataRequest {
+    /// Vectors whose metadata matches this filter are updated
+    filter: Filter,
+    /// JSON merge patch: keys merge into the metadata, `null` removes a key
+    #[schema(example = json!({"reviewed": true}))]
+    patch: Value,
+}
+
+#[derive(Serialize, ToSchema)]
+struct UpdateMetadataResponse {
+    updated: usize,
+}
+
+#[utoipa::path(
+    post,
+    path = "/collections/{name}/metadata",
+    params(
+        ("name" = String, Path, description = "Collection name")
+    ),
+    request_body = UpdateMetadataRequest,
+    responses(
+        (status = 200, description = "Number of vectors whose metadata changed", body = UpdateMetadataResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Collection not found", body = ErrorResponse)
+    ),
+    security(("api_key" = []))
+)]
+async fn update_metadata(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(payload): Json<UpdateMetadataRequest>,
+) -> Result<Json<UpdateMetadataResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let collection = state.db.get_collection(&name).map_err(|e| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )
+    })?;
+
+    let result = tokio::task::spawn_blocking(move || {
+        collection.update_metadata_by_filter(&payload.filter, &payload.patch)
+    })
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )
+    })?;
+
+    match result {
+        Ok(updated) => Ok(Json(UpdateMetadataResponse { updated })),
+        Err(e) => Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )),
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+struct SetMetadataRequest {
+    /// New metadata for the vector; `null` clears it
+    #[schema(example = json!({"title": "Updated"}))]
+    metadata: Value,
+    /// Overwrite only the given top-level keys, keeping the others
+    #[serde(default)]
+    merge: bool,
+}
+
+#[utoipa::path(
+    patch,
+    path = "/collections/{name}/vectors/{id}/metadata",
+    params(
+        ("name" = String, Path, description = "Collection name"),
+        ("id" = String, Path, description = "Vector ID")
+    ),
+    request_body = SetMetadataRequest,
+    responses(
+        (status = 200, description = "Metadata updated; the vector is unchanged"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Collection or vector not found", body = ErrorResponse)
+    ),
+    security(("api_key" = []))
+)]
+async fn set_vector_metadata(
+    State(state): State<AppState>,
+    Path((name, id)): Path<(String, String)>,
+    Json(payload): Json<SetMetadataRequest>,
+) -> Result<&'static str, (StatusCode, Json<ErrorResponse>)> {
+    let collection = state.db.get_collection(&name).map_err(|e| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )
+    })?;
+
+    let mode = if payload.merge {
+        MetadataUpdate::Merge
+    } else {
+        MetadataUpdate::Replace
+    };
+    let result =
+        tokio::task::spawn_blocking(move || collection.set_metadata(&id, payload.metadata, mode))
+            .await
+            .map_err(|e| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse {
+                        error: e.to_string(),
+                    }),
+                )
+            })?;
+
+    match result {
+        Ok(()) => Ok("Updated"),
+        Err(e) => {
+            let status = match e {
+                DbError::VectorNotFound(_) => StatusCode::NOT_FOUND,
+                _ => StatusCode::BAD_REQUEST,
+            };
+            Err((
+                status,
+                Json(ErrorResponse {
+                    error: e.to_string(),
+                }),
+            ))
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/collections/{name}/vectors/{id}/similar",
+    params(
+        ("name" = String, Path, description = "Collection name"),
+        ("id" = String, Path, description = "ID of the stored vector to search with")
+    ),
+    request_body = SimilarRequest,
+    responses(
+        (status = 200, description = "Nearest neighbors of the stored vector", body = [SearchResult]),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Collection or vector not found", body = ErrorResponse)
+    ),
+    security(("api_key" = []))
+)]
+async fn similar_vectors(
+    State(state): State<AppState>,
+    Path((name, id)): Path<(String, String)>,
+    Json(payload): Json<SimilarRequest>,
+) -> Result<Json<Vec<SearchResult>>, (StatusCode, Json<ErrorResponse>)> {
+    let include_metadata = payload.include_metadata.unwrap_or(true);
+    let k = payload.k.unwrap_or(10);
+    let exclude_self = payload.exclude_self.unwrap_or(true);
+    let filter = payload.filter;
+
+    let collection = state.db.get_collection(&name).map_err(|e| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )
+    })?;
+
+    let scorer = Scorer::new(&collection, payload.score_transform);
+    let permit = state.search_limiter.acquire(&name).await;
+    let result = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        collection.search_by_id(&id, k, filter.as_ref(), exclude_self)
+    })
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )
+    })?;
+
+    let results = result.map_err(|e| {
+        let status = match e {
+            DbError::VectorNotFound(_) => StatusCode::NOT_FOUND,
+            _ => StatusCode::BAD_REQUEST,
+        };
+        (
+            status,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )
+    })?;
+    Ok(Json(
+        results
+            .into_iter()
+            .map(|(id, distance, metadata)| {
+                scorer.result(id, distance, metadata.filter(|_| include_metadata))
+            })
+            .collect(),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/collections/{name}/search",
+    params(
+        ("name" = String, Path, description = "Collection name"),
+        SearchParams
+    ),
+    request_body = SearchRequest,
+    responses(
+        (status = 200, description = "List of nearest neighbors, or a SearchResponse with include_stats or debug", body = [SearchResult]),
+        (status = 400, description = "Invalid request", body = ErrorResponse)
+    ),
+    security(("api_key" = []))
+)]
+async fn search_vector(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Query(params): Query<SearchParams>,
+    Json(mut payload): Json<SearchRequest>,
+) -> Result<axum::response::Response, (StatusCode, Json<ErrorResponse>)> {
+    let handler_start = Instant::now();
+    let include_metadata = payload.include_metadata.unwrap_or(true);
+    let vector = std::mem::take(&mut payload.vector);
+    let k = payload.k;
+
+    let collection = state.db.get_collection(&name).map_err(|e| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )
+    })?;
+
+    let scorer = Scorer::new(&collection, payload.score_transform);
+    let include_stats = payload.include_stats;
+    let options = SearchOptions {
+        debug: params.debug,
+        ..payload.options()
+    };
+    let permit = state.search_limiter.acquire(&name).await;
+
+    // IDs and distances alone skip the metadata and stats bookkeeping
+    if !include_metadata
+        && !include_stats
+        && !options.debug
+        && options.ef.is_none()
+        && options.group_by.is_none()
+        && !options.include_vectors
+    {
+        let work_start = Instant::now();
+        let result = tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            collection.search_ids(&vector, k, options.filter.as_ref())
+        })
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: e.to_string(),
+                }),
+            )
+        })?;
+
+        return match result {
+            Ok(results) => {
+                let map_start = Instant::now();
+                let response: Vec<SearchResult> = results
+                    .into_iter()
+                    .map(|(id, distance)| scorer.result(id, distance, None))
+                    .collect();
+                let work_ms = work_start.elapsed().as_secs_f64() * 1000.0;
+                let map_ms = map_start.elapsed().as_secs_f64() * 1000.0;
                 let total_ms = handler_start.elapsed().as_secs_f64() * 1000.0;
                 log_perf("search_vector", total_ms, work_ms, Some(map_ms), Some(response.len()));
-                Ok(Json(response))
+                Ok(Json(response).into_response())
             }
             Err(e) => Err((
                 StatusCode::BAD_REQUEST,
@@ -1168,6 +2632,476 @@ async fn search_vector(
                     error: e.to_string(),
                 }),
             )),
+        };
+    }
+
+    let work_start = Instant::now();
+    let result = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        collection.search_with_options(&vector, k, &options)
+    })
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )
+    })?;
+
+    let outcome = result.map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )
+    })?;
+    let map_start = Instant::now();
+    let results = scorer.results(outcome.hits, include_metadata);
+    let work_ms = work_start.elapsed().as_secs_f64() * 1000.0;
+    let map_ms = map_start.elapsed().as_secs_f64() * 1000.0;
+    let total_ms = handler_start.elapsed().as_secs_f64() * 1000.0;
+    log_perf(
+        "search_vector",
+        total_ms,
+        work_ms,
+        Some(map_ms),
+        Some(results.len()),
+    );
+    if include_stats || params.debug {
+        return Ok(Json(SearchResponse {
+            results,
+            filtered_out: outcome.stats.filtered_out,
+            exhausted: outcome.stats.exhausted,
+            metric: scorer.metric,
+            score_transform: scorer.transform,
+            debug: params.debug.then_some(SearchDebug {
+                routing: outcome.routing,
+            }),
+        })
+        .into_response());
+    }
+    Ok(Json(results).into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = "/collections/{name}/search/batch",
+    params(
+        ("name" = String, Path, description = "Collection name")
+    ),
+    request_body = BatchSearchRequest,
+    responses(
+        (status = 200, description = "Nearest neighbors for each query, sized by its k", body = BatchSearchResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Collection not found", body = ErrorResponse)
+    ),
+    security(("api_key" = []))
+)]
+async fn batch_search_vector(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(payload): Json<BatchSearchRequest>,
+) -> Result<Json<BatchSearchResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let include_metadata = payload.include_metadata.unwrap_or(true);
+    let options = SearchOptions {
+        ef: payload.ef,
+        filter: payload.filter,
+        group_by: payload.group_by,
+        group_missing: payload.group_missing,
+        include_vectors: payload.include_vectors,
+        ..Default::default()
+    };
+    let bad_request = |error: String| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error }));
+    if payload.queries.len() > state.config.max_batch_queries {
+        return Err(bad_request(format!(
+            "batch of {} queries exceeds the limit of {}",
+            payload.queries.len(),
+            state.config.max_batch_queries
+        )));
+    }
+    let queries = payload
+        .queries
+        .into_iter()
+        .map(|query| match query {
+            BatchQuery::Query(query) => Ok((query.vector, query.k)),
+            BatchQuery::Vector(vector) => payload
+                .k
+                .map(|k| (vector, k))
+                .ok_or_else(|| bad_request("k is required for queries given as vectors".into())),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let collection = state.db.get_collection(&name).map_err(|e| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )
+    })?;
+
+    let scorer = Scorer::new(&collection, payload.score_transform);
+    let permit = state.search_limiter.acquire(&name).await;
+    let result = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        collection.search_batch_with_options(&queries, &options)
+    })
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )
+    })?;
+
+    let batches = result.map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )
+    })?;
+
+    Ok(Json(BatchSearchResponse {
+        results: batches
+            .into_iter()
+            .map(|outcome| scorer.results(outcome.hits, include_metadata))
+            .collect(),
+    }))
+}
+
+// =============================================================================
+// WebSocket Search
+// =============================================================================
+
+/// A search frame sent over `/ws/search`: a `SearchRequest` plus the target
+/// collection and an optional `request_id` echoed back in the reply
+#[derive(Deserialize)]
+struct WsSearchFrame {
+    #[serde(default)]
+    request_id: Option<Value>,
+    collection: String,
+    #[serde(flatten)]
+    search: SearchRequest,
+}
+
+/// Reply to one search frame: `results` (plus stats when requested) or `error`
+#[derive(Serialize)]
+struct WsSearchReply {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    results: Option<Vec<SearchResult>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    filtered_out: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exhausted: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl WsSearchReply {
+    fn error(request_id: Option<Value>, error: String) -> Self {
+        Self {
+            request_id,
+            results: None,
+            filtered_out: None,
+            exhausted: None,
+            error: Some(error),
+        }
+    }
+}
+
+/// Upgrade to a WebSocket that serves searches over one persistent connection.
+///
+/// Sits behind the API key check like every other route, so the upgrade
+/// request itself must carry `x-api-key`.
+async fn ws_search(
+    State(state): State<AppState>,
+    ws: WebSocketUpgrade,
+) -> axum::response::Response {
+    ws.max_message_size(state.config.max_request_size_bytes)
+        .on_upgrade(move |socket| serve_ws_search(socket, state))
+}
+
+/// Answer search frames strictly in order, one at a time.
+///
+/// The next frame is not read until the previous reply has been written, so
+/// a client that stops reading stalls only its own connection instead of
+/// queueing replies in server memory.
+async fn serve_ws_search(mut socket: WebSocket, state: AppState) {
+    while let Some(Ok(message)) = socket.recv().await {
+        let frame = match message {
+            Message::Text(text) => serde_json::from_str::<WsSearchFrame>(&text),
+            Message::Binary(bytes) => serde_json::from_slice::<WsSearchFrame>(&bytes),
+            Message::Close(_) => break,
+            // Pings are answered by the socket itself
+            Message::Ping(_) | Message::Pong(_) => continue,
+        };
+        let reply = match frame {
+            Ok(frame) => ws_search_reply(&state, frame).await,
+            Err(e) => WsSearchReply::error(None, format!("Invalid search frame: {}", e)),
+        };
+        let Ok(reply) = serde_json::to_string(&reply) else {
+            break;
+        };
+        if socket.send(Message::Text(reply)).await.is_err() {
+            break;
+        }
+    }
+}
+
+async fn ws_search_reply(state: &AppState, frame: WsSearchFrame) -> WsSearchReply {
+    let WsSearchFrame {
+        request_id,
+        collection,
+        mut search,
+    } = frame;
+
+    let name = collection;
+    let collection = match state.db.get_collection(&name) {
+        Ok(collection) => collection,
+        Err(e) => return WsSearchReply::error(request_id, e.to_string()),
+    };
+
+    let scorer = Scorer::new(&collection, search.score_transform);
+    let options = search.options();
+    let (vector, k) = (search.vector, search.k);
+    let permit = state.search_limiter.acquire(&name).await;
+    let result = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        collection.search_with_options(&vector, k, &options)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(outcome)) => {
+            let stats = Some(outcome.stats).filter(|_| search.include_stats);
+            let include_metadata = search.include_metadata.unwrap_or(true);
+            WsSearchReply {
+                request_id,
+                results: Some(scorer.results(outcome.hits, include_metadata)),
+                filtered_out: stats.as_ref().map(|s| s.filtered_out),
+                exhausted: stats.as_ref().map(|s| s.exhausted),
+                error: None,
+            }
         }
+        Ok(Err(e)) => WsSearchReply::error(request_id, e.to_string()),
+        Err(e) => WsSearchReply::error(request_id, e.to_string()),
     }
 }
+
+#[utoipa::path(
+    get,
+    path = "/collections/{name}/facets",
+    params(
+        ("name" = String, Path, description = "Collection name"),
+        FacetParams
+    ),
+    responses(
+        (status = 200, description = "Most frequent values of the field with their counts"),
+        (status = 404, description = "Collection not found", body = ErrorResponse)
+    ),
+    security(("api_key" = []))
+)]
+async fn get_facets(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Query(params): Query<FacetParams>,
+) -> Result<Json<Facets>, (StatusCode, Json<ErrorResponse>)> {
+    let collection = state.db.get_collection(&name).map_err(|e| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )
+    })?;
+
+    let top_n = params.top_n.unwrap_or(10);
+    let facets =
+        tokio::task::spawn_blocking(move || collection.facet(&params.field, top_n, params.sample))
+            .await
+            .map_err(|e| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse {
+                        error: e.to_string(),
+                    }),
+                )
+            })?;
+
+    Ok(Json(facets))
+}
+
+/// Edges returned by `/graph` when the request sets no limit
+const DEFAULT_GRAPH_EDGES: usize = 10_000;
+/// Upper bound on `/graph`'s `limit`
+const MAX_GRAPH_EDGES: usize = 100_000;
+
+#[derive(Serialize, ToSchema)]
+struct GraphEdge {
+    from: String,
+    to: String,
+}
+
+#[derive(Serialize, ToSchema)]
+struct GraphResponse {
+    layer: usize,
+    /// Live edges on the layer
+    total_edges: usize,
+    /// Whether `edges` is an evenly spaced sample rather than every edge
+    sampled: bool,
+    edges: Vec<GraphEdge>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/collections/{name}/graph",
+    params(
+        ("name" = String, Path, description = "Collection name"),
+        GraphParams
+    ),
+    responses(
+        (status = 200, description = "Directed HNSW edges of one layer, by vector ID", body = GraphResponse),
+        (status = 400, description = "The collection has no graph index", body = ErrorResponse),
+        (status = 404, description = "Collection not found", body = ErrorResponse)
+    ),
+    security(("api_key" = []))
+)]
+async fn get_graph(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Query(params): Query<GraphParams>,
+) -> Result<Json<GraphResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let collection = state.db.get_collection(&name).map_err(|e| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )
+    })?;
+
+    let layer = params.layer.unwrap_or(0);
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_GRAPH_EDGES)
+        .min(MAX_GRAPH_EDGES);
+    let graph = tokio::task::spawn_blocking(move || collection.graph_edges(layer, limit))
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: e.to_string(),
+                }),
+            )
+        })?
+        .ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    error: format!("Collection '{}' has no graph index", name),
+                }),
+            )
+        })?;
+
+    Ok(Json(GraphResponse {
+        layer,
+        total_edges: graph.total,
+        sampled: graph.edges.len() < graph.total,
+        edges: graph
+            .edges
+            .into_iter()
+            .map(|(from, to)| GraphEdge {
+                from: from.to_string(),
+                to: to.to_string(),
+            })
+            .collect(),
+    }))
+}
+
+/// Records fetched per page while streaming an export
+const EXPORT_PAGE_SIZE: usize = 1000;
+
+#[utoipa::path(
+    get,
+    path = "/collections/{name}/export",
+    params(
+        ("name" = String, Path, description = "Collection name"),
+        ExportParams
+    ),
+    responses(
+        (status = 200, description = "One JSON record per line, as accepted by batch insert", content_type = "application/x-ndjson"),
+        (status = 400, description = "Unsupported format", body = ErrorResponse),
+        (status = 404, description = "Collection not found", body = ErrorResponse)
+    ),
+    security(("api_key" = []))
+)]
+async fn export_collection(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Query(params): Query<ExportParams>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let unsupported = |error: String| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error }));
+    match params.format.as_deref().unwrap_or("jsonl") {
+        "jsonl" => {}
+        other => {
+            return Err(unsupported(format!(
+                "Unknown export format '{}', expected jsonl",
+                other
+            )))
+        }
+    }
+
+    let collection = state.db.get_collection(&name).map_err(|e| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )
+    })?;
+
+    // Pages are serialized on a blocking thread and streamed as they are
+    // ready, so the whole collection is never buffered
+    let (tx, rx) = mpsc::channel::<std::io::Result<Bytes>>(4);
+    tokio::task::spawn_blocking(move || {
+        for page in collection.export(EXPORT_PAGE_SIZE) {
+            let chunk = page.map_err(std::io::Error::other).and_then(|page| {
+                let mut lines = Vec::new();
+                for record in &page {
+                    serde_json::to_writer(&mut lines, record)?;
+                    lines.push(b'\n');
+                }
+                Ok(Bytes::from(lines))
+            });
+            let failed = chunk.is_err();
+            // A closed channel means the client went away
+            if tx.blocking_send(chunk).is_err() || failed {
+                break;
+            }
+        }
+    });
+    let body = Body::from_stream(futures_util::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    }));
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/x-ndjson".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}.jsonl\"", name),
+            ),
+        ],
+        body,
+    ))
+}
@@ -0,0 +1,35 @@
+mod common;
+
+use common::{request_with_key, start_server};
+use serde_json::{json, Value};
+
+#[test]
+fn test_admin_config_reports_settings_and_redacts_api_key() {
+    let key = "s3cret-key";
+    let server = start_server(&[("API_KEY", key), ("REQUEST_TIMEOUT_SECS", "17")]);
+    let port = server.port;
+
+    let (status, _) = request_with_key(port, "GET", "/admin/config", "", None);
+    assert_eq!(status, 401);
+
+    let create = json!({"name": "docs", "dimensions": 3, "distance_metric": "Euclidean"});
+    let (status, body) =
+        request_with_key(port, "POST", "/collections", &create.to_string(), Some(key));
+    assert_eq!(status, 200, "{}", body);
+
+    let (status, body) = request_with_key(port, "GET", "/admin/config", "", Some(key));
+    assert_eq!(status, 200, "{}", body);
+    assert!(!body.contains(key), "{}", body);
+    let config: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(config["server"]["request_timeout_secs"], 17);
+    assert_eq!(config["server"]["api_key"], "[redacted]");
+    assert_eq!(config["collections"]["docs"]["dimensions"], 3);
+    assert_eq!(
+        config["collections"]["docs"]["distance_metric"],
+        "Euclidean"
+    );
+    assert_eq!(config["build"]["version"], env!("CARGO_PKG_VERSION"));
+    assert!(config["build"]["simd_backend"].is_string(), "{}", body);
+    let features = config["build"]["features"].as_array().unwrap();
+    assert!(features.contains(&json!("persistence")), "{}", body);
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use common::{request, start_server};
+use serde_json::{json, Value};
+
+#[test]
+fn test_batch_search_sizes_results_per_query() {
+    let server = start_server(&[]);
+    let call = |path: &str, body: Value| {
+        let (status, body) = request(server.port, "POST", path, &body.to_string());
+        assert!(status < 300, "POST {} -> {}: {}", path, status, body);
+        body
+    };
+
+    call("/collections", json!({"name": "docs", "dimensions": 2}));
+    let vectors: Vec<Value> = (0..6)
+        .map(|i| json!({"id": format!("vec{}", i), "vector": [1.0, i as f32]}))
+        .collect();
+    call(
+        "/collections/docs/vectors/batch",
+        json!({ "vectors": vectors }),
+    );
+
+    let body = call(
+        "/collections/docs/search/batch",
+        json!({"queries": [
+            {"vector": [1.0, 0.0], "k": 1},
+            {"vector": [1.0, 5.0], "k": 5},
+        ]}),
+    );
+    let response: Value = serde_json::from_str(&body).unwrap();
+    let results = response["results"].as_array().unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].as_array().unwrap().len(), 1);
+    assert_eq!(results[1].as_array().unwrap().len(), 5);
+    assert_eq!(results[0][0]["id"], "vec0");
+}
+
+#[test]
+fn test_batch_search_bare_vectors_share_k() {
+    let server = start_server(&[("MAX_BATCH_QUERIES", "8")]);
+    let call = |path: &str, body: Value| request(server.port, "POST", path, &body.to_string());
+
+    call("/collections", json!({"name": "docs", "dimensions": 2}));
+    let vectors: Vec<Value> = (0..20)
+        .map(|i| json!({"id": format!("vec{}", i), "vector": [1.0, i as f32]}))
+        .collect();
+    let (status, _) = call(
+        "/collections/docs/vectors/batch",
+        json!({ "vectors": vectors }),
+    );
+    assert_eq!(status, 200);
+
+    // Results line up with the queries that produced them
+    let queries: Vec<Value> = (0..8).map(|i| json!([1.0, (i * 2) as f32])).collect();
+    let (status, body) = call(
+        "/collections/docs/search/batch",
+        json!({"queries": queries, "k": 3}),
+    );
+    assert_eq!(status, 200, "{}", body);
+    let response: Value = serde_json::from_str(&body).unwrap();
+    let results = response["results"].as_array().unwrap();
+    assert_eq!(results.len(), 8);
+    for (i, hits) in results.iter().enumerate() {
+        assert_eq!(hits.as_array().unwrap().len(), 3);
+        assert_eq!(hits[0]["id"], format!("vec{}", i * 2));
+    }
+
+    let (status, _) = call(
+        "/collections/docs/search/batch",
+        json!({"queries": [[1.0, 0.0]]}),
+    );
+    assert_eq!(status, 400);
+
+    let too_many: Vec<Value> = (0..9).map(|_| json!([1.0, 0.0])).collect();
+    let (status, body) = call(
+        "/collections/docs/search/batch",
+        json!({"queries": too_many, "k": 1}),
+    );
+    assert_eq!(status, 400);
+    assert!(body.contains("limit of 8"), "{}", body);
+}
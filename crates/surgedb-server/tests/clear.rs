@@ -0,0 +1,41 @@
+mod common;
+
+use common::{request, start_server};
+use serde_json::{json, Value};
+
+#[test]
+fn test_clear_keeps_collection_and_config() {
+    let server = start_server(&[]);
+    let port = server.port;
+
+    let create = json!({"name": "docs", "dimensions": 2}).to_string();
+    let (status, _) = request(port, "POST", "/collections", &create);
+    assert_eq!(status, 200);
+
+    let vectors: Vec<Value> = (0..10)
+        .map(|i| json!({"id": format!("v{}", i), "vector": [i as f32, 1.0]}))
+        .collect();
+    let batch = json!({"vectors": vectors}).to_string();
+    let (status, body) = request(port, "POST", "/collections/docs/vectors/batch", &batch);
+    assert_eq!(status, 200, "{}", body);
+
+    let (status, body) = request(port, "DELETE", "/collections/docs/vectors", "");
+    assert_eq!(status, 200, "{}", body);
+
+    let (status, body) = request(port, "GET", "/collections/docs/count", "");
+    assert_eq!(status, 200, "{}", body);
+    assert_eq!(serde_json::from_str::<Value>(&body).unwrap()["count"], 0);
+    let (status, _) = request(port, "GET", "/collections/docs/vectors/v1", "");
+    assert_eq!(status, 404);
+
+    // The collection is reused with its original dimensions
+    let insert = json!({"id": "v1", "vector": [1.0, 0.0]}).to_string();
+    let (status, body) = request(port, "POST", "/collections/docs/vectors", &insert);
+    assert_eq!(status, 200, "{}", body);
+    let wrong = json!({"id": "v2", "vector": [1.0, 0.0, 0.0]}).to_string();
+    let (status, _) = request(port, "POST", "/collections/docs/vectors", &wrong);
+    assert_ne!(status, 200);
+
+    let (status, _) = request(port, "DELETE", "/collections/missing/vectors", "");
+    assert_eq!(status, 404);
+}
@@ -0,0 +1,49 @@
+mod common;
+
+use common::{request, start_server};
+use serde_json::{json, Value};
+
+#[test]
+fn test_collection_stats_endpoint_and_rollup() {
+    let server = start_server(&[]);
+    let port = server.port;
+
+    for (name, dimensions) in [("docs", 3), ("notes", 2)] {
+        let create = json!({"name": name, "dimensions": dimensions}).to_string();
+        let (status, _) = request(port, "POST", "/collections", &create);
+        assert_eq!(status, 200);
+    }
+    let vectors: Vec<Value> = (0..12)
+        .map(|i| json!({"id": format!("v{}", i), "vector": [i as f32, 1.0, 0.5]}))
+        .collect();
+    let batch = json!({"vectors": vectors}).to_string();
+    let (status, body) = request(port, "POST", "/collections/docs/vectors/batch", &batch);
+    assert_eq!(status, 200, "{}", body);
+
+    let (status, body) = request(port, "GET", "/collections/docs/stats", "");
+    assert_eq!(status, 200, "{}", body);
+    let docs: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(docs["name"], "docs");
+    assert_eq!(docs["vector_count"], 12);
+    assert_eq!(docs["dimensions"], 3);
+    assert_eq!(docs["distance_metric"], "Cosine");
+    assert_eq!(docs["quantization"], "None");
+    assert!(docs["memory_bytes"].as_u64().unwrap() > 0, "{}", body);
+
+    // The same entries are rolled up in /stats, sorted by name
+    let (status, body) = request(port, "GET", "/stats", "");
+    assert_eq!(status, 200, "{}", body);
+    let stats: Value = serde_json::from_str(&body).unwrap();
+    let collections = stats["database"]["collections"].as_array().unwrap();
+    let names: Vec<&str> = collections
+        .iter()
+        .map(|c| c["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["docs", "notes"]);
+    assert_eq!(collections[0], docs);
+    assert_eq!(collections[1]["vector_count"], 0);
+    assert_eq!(stats["database"]["total_vectors"], 12);
+
+    let (status, _) = request(port, "GET", "/collections/missing/stats", "");
+    assert_eq!(status, 404);
+}
@@ -0,0 +1,192 @@
+//! Helpers for running the server binary in integration tests
+
+#![allow(dead_code)]
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub struct Server {
+    pub child: Child,
+    pub port: u16,
+    pub data_dir: tempfile::TempDir,
+}
+
+impl Server {
+    pub fn data_path(&self) -> &Path {
+        self.data_dir.path()
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+}
+
+/// Start the server on free ports with a fresh data directory
+pub fn start_server(envs: &[(&str, &str)]) -> Server {
+    let data_dir = tempfile::tempdir().unwrap();
+    let port = free_port();
+    let child = Command::new(env!("CARGO_BIN_EXE_surgedb-server"))
+        .env("PORT", port.to_string())
+        .env("WEB_PORT", free_port().to_string())
+        .env("DATA_DIR", data_dir.path())
+        .env("LOG_LEVEL", "warn")
+        .envs(envs.iter().copied())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    let server = Server {
+        child,
+        port,
+        data_dir,
+    };
+
+    // Wait for the listener without holding a connection open
+    let deadline = Instant::now() + Duration::from_secs(30);
+    while TcpStream::connect(("127.0.0.1", port)).is_err() {
+        assert!(Instant::now() < deadline, "server did not start");
+        thread::sleep(Duration::from_millis(50));
+    }
+    server
+}
+
+pub fn connect(port: u16) -> TcpStream {
+    TcpStream::connect(("127.0.0.1", port)).unwrap()
+}
+
+/// Send a keep-alive request and return the status code, leaving the
+/// connection open
+pub fn get_health(stream: &mut TcpStream) -> u16 {
+    stream
+        .write_all(b"GET /health HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(10)))
+        .unwrap();
+    let mut buf = [0u8; 4096];
+    let n = stream.read(&mut buf).unwrap();
+    status_code(&String::from_utf8_lossy(&buf[..n]))
+}
+
+/// Send a one-shot JSON request and return the status code and body
+pub fn request(port: u16, method: &str, path: &str, body: &str) -> (u16, String) {
+    request_with_key(port, method, path, body, None)
+}
+
+/// Like `request`, sending `api_key` as `x-api-key`
+pub fn request_with_key(
+    port: u16,
+    method: &str,
+    path: &str,
+    body: &str,
+    api_key: Option<&str>,
+) -> (u16, String) {
+    let key_header = api_key
+        .map(|key| format!("x-api-key: {}\r\n", key))
+        .unwrap_or_default();
+    send(
+        port,
+        method,
+        path,
+        &format!("Content-Type: application/json\r\n{}", key_header),
+        body.as_bytes(),
+    )
+}
+
+/// Send a one-shot request with a binary body of the given content type
+pub fn request_bytes(
+    port: u16,
+    method: &str,
+    path: &str,
+    content_type: &str,
+    body: &[u8],
+) -> (u16, String) {
+    send(
+        port,
+        method,
+        path,
+        &format!("Content-Type: {}\r\n", content_type),
+        body,
+    )
+}
+
+/// Like `request`, also returning the response head (status line and headers)
+pub fn request_with_head(port: u16, method: &str, path: &str, body: &str) -> (u16, String, String) {
+    exchange(
+        port,
+        method,
+        path,
+        "Content-Type: application/json\r\n",
+        body.as_bytes(),
+    )
+}
+
+fn send(port: u16, method: &str, path: &str, headers: &str, body: &[u8]) -> (u16, String) {
+    let (status, _, body) = exchange(port, method, path, headers, body);
+    (status, body)
+}
+
+fn exchange(
+    port: u16,
+    method: &str,
+    path: &str,
+    headers: &str,
+    body: &[u8],
+) -> (u16, String, String) {
+    let mut stream = connect(port);
+    write!(
+        stream,
+        "{} {} HTTP/1.1\r\nHost: localhost\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n",
+        method,
+        path,
+        headers,
+        body.len(),
+    )
+    .unwrap();
+    stream.write_all(body).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap_or((&response, ""));
+    let body = if head
+        .to_ascii_lowercase()
+        .contains("transfer-encoding: chunked")
+    {
+        dechunk(body)
+    } else {
+        body.to_string()
+    };
+    (status_code(&response), head.to_string(), body)
+}
+
+/// Join the chunks of a chunked transfer-encoded body
+fn dechunk(mut body: &str) -> String {
+    let mut out = String::new();
+    while let Some((size, rest)) = body.split_once("\r\n") {
+        let size = usize::from_str_radix(size.trim(), 16).unwrap();
+        if size == 0 {
+            break;
+        }
+        out.push_str(&rest[..size]);
+        body = &rest[size + 2..];
+    }
+    out
+}
+
+fn status_code(response: &str) -> u16 {
+    response.split_whitespace().nth(1).unwrap().parse().unwrap()
+}
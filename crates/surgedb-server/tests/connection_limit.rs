@@ -0,0 +1,37 @@
+mod common;
+
+use common::{connect, get_health, start_server};
+use std::net::TcpStream;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Open a connection that the server admits, retrying while closed
+/// connections are still releasing their slots
+fn connect_admitted(port: u16) -> TcpStream {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        let mut stream = connect(port);
+        match get_health(&mut stream) {
+            200 => return stream,
+            503 if Instant::now() < deadline => thread::sleep(Duration::from_millis(50)),
+            status => panic!("unexpected status {}", status),
+        }
+    }
+}
+
+#[test]
+fn test_connections_over_cap_get_503() {
+    let server = start_server(&[("MAX_CONNECTIONS", "2")]);
+    let mut first = connect_admitted(server.port);
+    let _second = connect_admitted(server.port);
+
+    let mut excess = connect(server.port);
+    assert_eq!(get_health(&mut excess), 503);
+
+    // Held connections keep working
+    assert_eq!(get_health(&mut first), 200);
+
+    // Closing one frees its slot for a new client
+    drop(first);
+    connect_admitted(server.port);
+}
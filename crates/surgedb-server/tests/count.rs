@@ -0,0 +1,46 @@
+mod common;
+
+use common::{request, request_with_head, start_server};
+use serde_json::{json, Value};
+
+#[test]
+fn test_count_endpoint_and_total_header() {
+    let server = start_server(&[]);
+    let port = server.port;
+
+    let create = json!({"name": "docs", "dimensions": 2}).to_string();
+    let (status, _) = request(port, "POST", "/collections", &create);
+    assert_eq!(status, 200);
+
+    let count = || {
+        let (status, body) = request(port, "GET", "/collections/docs/count", "");
+        assert_eq!(status, 200, "{}", body);
+        serde_json::from_str::<Value>(&body).unwrap()["count"].clone()
+    };
+    assert_eq!(count(), json!(0));
+
+    let vectors: Vec<Value> = (0..25)
+        .map(|i| json!({"id": format!("v{}", i), "vector": [i as f32, 1.0]}))
+        .collect();
+    let batch = json!({"vectors": vectors}).to_string();
+    let (status, body) = request(port, "POST", "/collections/docs/vectors/batch", &batch);
+    assert_eq!(status, 200, "{}", body);
+    let (status, _) = request(port, "DELETE", "/collections/docs/vectors/v3", "");
+    assert_eq!(status, 200);
+    assert_eq!(count(), json!(24));
+
+    // Each page reports the collection size alongside its slice
+    let (status, head, body) =
+        request_with_head(port, "GET", "/collections/docs/vectors?limit=10", "");
+    assert_eq!(status, 200, "{}", body);
+    assert!(
+        head.to_ascii_lowercase().contains("x-total-count: 24"),
+        "{}",
+        head
+    );
+    let page: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(page["vectors"].as_array().unwrap().len(), 10);
+
+    let (status, _) = request(port, "GET", "/collections/missing/count", "");
+    assert_eq!(status, 404);
+}
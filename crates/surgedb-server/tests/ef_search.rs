@@ -0,0 +1,38 @@
+mod common;
+
+use common::{request, start_server};
+use serde_json::{json, Value};
+
+#[test]
+fn test_search_accepts_per_query_ef() {
+    let server = start_server(&[]);
+    let call = |path: &str, body: Value| request(server.port, "POST", path, &body.to_string());
+
+    call("/collections", json!({"name": "docs", "dimensions": 2}));
+    let vectors: Vec<Value> = (0..20)
+        .map(|i| json!({"id": format!("v{}", i), "vector": [1.0, i as f32]}))
+        .collect();
+    call(
+        "/collections/docs/vectors/batch",
+        json!({ "vectors": vectors }),
+    );
+
+    let search = |body: Value| {
+        let (status, body) = call("/collections/docs/search", body);
+        (status, serde_json::from_str::<Value>(&body).unwrap())
+    };
+    let (status, default) = search(json!({"vector": [1.0, 3.0], "k": 5}));
+    assert_eq!(status, 200);
+    let (status, wide) = search(json!({"vector": [1.0, 3.0], "k": 5, "ef": 500}));
+    assert_eq!(status, 200);
+    assert_eq!(wide, default);
+    let (_, narrow) =
+        search(json!({"vector": [1.0, 3.0], "k": 5, "ef": 1, "include_metadata": false}));
+    assert_eq!(narrow.as_array().unwrap().len(), 5);
+
+    let (status, body) =
+        search(json!({"vector": [1.0, 3.0], "k": 5, "ef": 50, "include_stats": true}));
+    assert_eq!(status, 200);
+    assert_eq!(body["results"], default);
+    assert_eq!(body["filtered_out"], 0);
+}
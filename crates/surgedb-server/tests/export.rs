@@ -0,0 +1,83 @@
+mod common;
+
+use common::{request, request_with_head, start_server};
+use serde_json::{json, Value};
+
+const VECTORS: usize = 1500;
+
+#[test]
+fn test_export_reimports_with_parity() {
+    let server = start_server(&[]);
+    let port = server.port;
+
+    for name in ["source", "restored"] {
+        let create = json!({"name": name, "dimensions": 3}).to_string();
+        let (status, _) = request(port, "POST", "/collections", &create);
+        assert_eq!(status, 200);
+    }
+
+    // More than one export page, with and without metadata
+    let vectors: Vec<Value> = (0..VECTORS)
+        .map(|i| {
+            let mut record = json!({"id": format!("v{}", i), "vector": [i as f32, 1.0, -0.5]});
+            if i % 2 == 0 {
+                record["metadata"] = json!({"n": i, "tag": "even"});
+            }
+            record
+        })
+        .collect();
+    let batch = json!({"vectors": vectors}).to_string();
+    let (status, body) = request(port, "POST", "/collections/source/vectors/batch", &batch);
+    assert_eq!(status, 200, "{}", body);
+
+    let (status, head, export) =
+        request_with_head(port, "GET", "/collections/source/export?format=jsonl", "");
+    assert_eq!(status, 200, "{}", export);
+    let head = head.to_ascii_lowercase();
+    assert!(
+        head.contains("content-type: application/x-ndjson"),
+        "{}",
+        head
+    );
+    assert!(
+        head.contains("content-disposition: attachment; filename=\"source.jsonl\""),
+        "{}",
+        head
+    );
+
+    let records: Vec<Value> = export
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(records.len(), VECTORS);
+
+    let batch = json!({"vectors": records}).to_string();
+    let (status, body) = request(port, "POST", "/collections/restored/vectors/batch", &batch);
+    assert_eq!(status, 200, "{}", body);
+
+    for i in [0, 1, 999, 1000, VECTORS - 1] {
+        let get = |collection: &str| {
+            let path = format!("/collections/{}/vectors/v{}", collection, i);
+            let (status, body) = request(port, "GET", &path, "");
+            assert_eq!(status, 200, "{}", body);
+            serde_json::from_str::<Value>(&body).unwrap()
+        };
+        assert_eq!(get("restored"), get("source"));
+    }
+    let (_, stats) = request(port, "GET", "/stats", "");
+    let stats: Value = serde_json::from_str(&stats).unwrap();
+    let restored = stats["database"]["collections"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|c| c["name"] == "restored")
+        .unwrap();
+    assert_eq!(restored["vector_count"], VECTORS as u64);
+
+    let (status, _) = request(port, "GET", "/collections/source/export", "");
+    assert_eq!(status, 200);
+    let (status, _) = request(port, "GET", "/collections/source/export?format=csv", "");
+    assert_eq!(status, 400);
+    let (status, _) = request(port, "GET", "/collections/missing/export", "");
+    assert_eq!(status, 404);
+}
@@ -0,0 +1,30 @@
+mod common;
+
+use common::{request, start_server};
+use serde_json::json;
+
+#[test]
+fn test_non_finite_vectors_rejected_with_400() {
+    let server = start_server(&[]);
+    let port = server.port;
+
+    for (name, validate_finite) in [("checked", None), ("unchecked", Some(false))] {
+        let mut create = json!({"name": name, "dimensions": 2});
+        if let Some(validate_finite) = validate_finite {
+            create["validate_finite"] = json!(validate_finite);
+        }
+        let (status, _) = request(port, "POST", "/collections", &create.to_string());
+        assert_eq!(status, 200);
+    }
+
+    // JSON has no NaN, but out-of-range numbers parse to an infinite f32
+    let insert = r#"{"id": "v1", "vector": [1.0, 1e39]}"#;
+    let (status, body) = request(port, "POST", "/collections/checked/vectors", insert);
+    assert_eq!(status, 400, "{}", body);
+    assert!(body.contains("component 1 is inf"), "{}", body);
+    let (status, _) = request(port, "GET", "/collections/checked/vectors/v1", "");
+    assert_eq!(status, 404);
+
+    let (status, body) = request(port, "POST", "/collections/unchecked/vectors", insert);
+    assert_eq!(status, 200, "{}", body);
+}
@@ -0,0 +1,45 @@
+mod common;
+
+use common::{request, start_server};
+use serde_json::{json, Value};
+
+#[test]
+fn test_graph_returns_layer_edges_by_id() {
+    let server = start_server(&[]);
+    let port = server.port;
+
+    let create = json!({"name": "docs", "dimensions": 2}).to_string();
+    let (status, _) = request(port, "POST", "/collections", &create);
+    assert_eq!(status, 200);
+
+    let vectors: Vec<Value> = (0..30)
+        .map(|i| json!({"id": format!("v{}", i), "vector": [i as f32, (i % 4) as f32]}))
+        .collect();
+    let batch = json!({"vectors": vectors}).to_string();
+    let (status, body) = request(port, "POST", "/collections/docs/vectors/batch", &batch);
+    assert_eq!(status, 200, "{}", body);
+
+    let (status, body) = request(port, "GET", "/collections/docs/graph", "");
+    assert_eq!(status, 200, "{}", body);
+    let graph: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(graph["layer"], 0);
+    assert_eq!(graph["sampled"], false);
+    let edges = graph["edges"].as_array().unwrap();
+    assert!(!edges.is_empty());
+    assert_eq!(graph["total_edges"], edges.len());
+    for edge in edges {
+        let (from, to) = (edge["from"].as_str().unwrap(), edge["to"].as_str().unwrap());
+        assert!(from.starts_with('v') && to.starts_with('v'));
+        assert_ne!(from, to);
+    }
+
+    let (status, body) = request(port, "GET", "/collections/docs/graph?layer=0&limit=5", "");
+    assert_eq!(status, 200, "{}", body);
+    let sampled: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(sampled["sampled"], true);
+    assert_eq!(sampled["edges"].as_array().unwrap().len(), 5);
+    assert_eq!(sampled["total_edges"], graph["total_edges"]);
+
+    let (status, _) = request(port, "GET", "/collections/missing/graph", "");
+    assert_eq!(status, 404);
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use common::{request, start_server};
+use serde_json::{json, Value};
+
+#[test]
+fn test_search_group_by_returns_one_hit_per_group() {
+    let server = start_server(&[]);
+    let call = |path: &str, body: Value| {
+        let (status, body) = request(server.port, "POST", path, &body.to_string());
+        (status, body)
+    };
+
+    call(
+        "/collections",
+        json!({"name": "posts", "dimensions": 2, "distance_metric": "Euclidean"}),
+    );
+    let vectors: Vec<Value> = (0..12)
+        .map(|i| {
+            json!({
+                "id": format!("p{}", i),
+                "vector": [i as f32, 0.0],
+                "metadata": {"author": i / 4},
+            })
+        })
+        .collect();
+    let (status, body) = call(
+        "/collections/posts/vectors/batch",
+        json!({ "vectors": vectors }),
+    );
+    assert!(status < 300, "{}", body);
+
+    let (status, body) = call(
+        "/collections/posts/search",
+        json!({"vector": [0.0, 0.0], "k": 3, "group_by": "author"}),
+    );
+    assert_eq!(status, 200, "{}", body);
+    let results: Value = serde_json::from_str(&body).unwrap();
+    let ids: Vec<&str> = results
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids, ["p0", "p4", "p8"]);
+
+    // Grouping combines with the other search options
+    let (status, body) = call(
+        "/collections/posts/search",
+        json!({
+            "vector": [0.0, 0.0],
+            "k": 3,
+            "group_by": "author",
+            "ef": 50,
+            "include_vectors": true,
+            "include_stats": true,
+        }),
+    );
+    assert_eq!(status, 200, "{}", body);
+    let response: Value = serde_json::from_str(&body).unwrap();
+    let results = response["results"].as_array().unwrap();
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[1]["id"], "p4");
+    assert_eq!(results[1]["vector"], json!([4.0, 0.0]));
+    assert_eq!(response["exhausted"], true);
+}
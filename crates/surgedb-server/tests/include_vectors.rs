@@ -0,0 +1,93 @@
+mod common;
+
+use common::{request, start_server};
+use serde_json::{json, Value};
+
+#[test]
+fn test_search_include_vectors() {
+    let server = start_server(&[]);
+    let port = server.port;
+
+    let create = json!({"name": "docs", "dimensions": 3, "distance_metric": "Euclidean"});
+    let (status, _) = request(port, "POST", "/collections", &create.to_string());
+    assert_eq!(status, 200);
+    for i in 0..5 {
+        let insert = json!({"id": format!("v{}", i), "vector": [i as f32, 1.0, 0.5]});
+        let (status, body) = request(
+            port,
+            "POST",
+            "/collections/docs/vectors",
+            &insert.to_string(),
+        );
+        assert_eq!(status, 200, "{}", body);
+    }
+
+    // Off by default
+    let search = json!({"vector": [2.0, 1.0, 0.5], "k": 2});
+    let (status, body) = request(
+        port,
+        "POST",
+        "/collections/docs/search",
+        &search.to_string(),
+    );
+    assert_eq!(status, 200, "{}", body);
+    let results: Vec<Value> = serde_json::from_str(&body).unwrap();
+    assert!(
+        results.iter().all(|r| r.get("vector").is_none()),
+        "{}",
+        body
+    );
+
+    let search = json!({"vector": [2.0, 1.0, 0.5], "k": 2, "include_vectors": true});
+    let (status, body) = request(
+        port,
+        "POST",
+        "/collections/docs/search",
+        &search.to_string(),
+    );
+    assert_eq!(status, 200, "{}", body);
+    let results: Vec<Value> = serde_json::from_str(&body).unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0]["id"], "v2");
+    assert_eq!(results[0]["vector"], json!([2.0, 1.0, 0.5]));
+    // The same vector a GET returns
+    let path = format!(
+        "/collections/docs/vectors/{}",
+        results[1]["id"].as_str().unwrap()
+    );
+    let (status, stored) = request(port, "GET", &path, "");
+    assert_eq!(status, 200);
+    let stored: Value = serde_json::from_str(&stored).unwrap();
+    assert_eq!(results[1]["vector"], stored["vector"]);
+
+    let search =
+        json!({"vector": [2.0, 1.0, 0.5], "k": 2, "include_vectors": true, "include_stats": true});
+    let (status, body) = request(
+        port,
+        "POST",
+        "/collections/docs/search",
+        &search.to_string(),
+    );
+    assert_eq!(status, 200, "{}", body);
+    let response: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(response["results"][0]["vector"], json!([2.0, 1.0, 0.5]));
+    assert_eq!(response["filtered_out"], 0);
+
+    // Batches take the same options
+    let batch = json!({
+        "queries": [[2.0, 1.0, 0.5], [4.0, 1.0, 0.5]],
+        "k": 1,
+        "ef": 50,
+        "include_vectors": true,
+    });
+    let (status, body) = request(
+        port,
+        "POST",
+        "/collections/docs/search/batch",
+        &batch.to_string(),
+    );
+    assert_eq!(status, 200, "{}", body);
+    let response: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(response["results"][1][0]["id"], "v4");
+    assert_eq!(response["results"][1][0]["vector"], json!([4.0, 1.0, 0.5]));
+}
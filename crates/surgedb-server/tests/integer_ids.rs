@@ -0,0 +1,48 @@
+mod common;
+
+use common::{request, start_server};
+use serde_json::{json, Value};
+
+#[test]
+fn test_numeric_and_string_ids_address_the_same_vector() {
+    let server = start_server(&[]);
+    let port = server.port;
+
+    let create = json!({"name": "docs", "dimensions": 2}).to_string();
+    let (status, _) = request(port, "POST", "/collections", &create);
+    assert_eq!(status, 200);
+
+    let insert = json!({"id": 42, "vector": [1.0, 0.0]}).to_string();
+    let (status, body) = request(port, "POST", "/collections/docs/vectors", &insert);
+    assert_eq!(status, 200, "{}", body);
+    let batch = json!({"vectors": [
+        {"id": "doc", "vector": [0.0, 1.0]},
+        {"id": 7, "vector": [1.0, 1.0]},
+    ]})
+    .to_string();
+    let (status, body) = request(port, "POST", "/collections/docs/vectors/batch", &batch);
+    assert_eq!(status, 200, "{}", body);
+
+    let (status, body) = request(port, "GET", "/collections/docs/vectors/42", "");
+    assert_eq!(status, 200, "{}", body);
+    let vector: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(vector["id"], "42");
+
+    // "42" and 42 are one ID
+    let duplicate = json!({"id": "42", "vector": [0.5, 0.5]}).to_string();
+    let (status, body) = request(port, "POST", "/collections/docs/vectors", &duplicate);
+    assert_eq!(status, 400);
+    assert!(body.contains("Duplicate vector ID: 42"), "{}", body);
+
+    for path in [
+        "/collections/docs/vectors/doc",
+        "/collections/docs/vectors/7",
+    ] {
+        let (status, body) = request(port, "GET", path, "");
+        assert_eq!(status, 200, "{}: {}", path, body);
+    }
+
+    let negative = json!({"id": -1, "vector": [1.0, 0.0]}).to_string();
+    let (status, _) = request(port, "POST", "/collections/docs/vectors", &negative);
+    assert_eq!(status, 422);
+}
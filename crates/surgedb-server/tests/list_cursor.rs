@@ -0,0 +1,56 @@
+mod common;
+
+use common::{request, start_server};
+use serde_json::{json, Value};
+
+#[test]
+fn test_list_cursor_survives_writes_between_pages() {
+    let server = start_server(&[]);
+    let port = server.port;
+
+    let create = json!({"name": "docs", "dimensions": 2}).to_string();
+    let (status, _) = request(port, "POST", "/collections", &create);
+    assert_eq!(status, 200);
+    let vectors: Vec<Value> = (0..25)
+        .map(|i| json!({"id": format!("v{}", i), "vector": [i as f32, 1.0]}))
+        .collect();
+    let batch = json!({"vectors": vectors}).to_string();
+    let (status, body) = request(port, "POST", "/collections/docs/vectors/batch", &batch);
+    assert_eq!(status, 200, "{}", body);
+
+    let page = |query: &str| {
+        let path = format!("/collections/docs/vectors?limit=10{}", query);
+        let (status, body) = request(port, "GET", &path, "");
+        assert_eq!(status, 200, "{}", body);
+        let page: Value = serde_json::from_str(&body).unwrap();
+        let ids: Vec<String> = page["vectors"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v["id"].as_str().unwrap().to_string())
+            .collect();
+        (ids, page["next_cursor"].as_str().map(str::to_string))
+    };
+
+    let (first, cursor) = page("");
+    assert_eq!(first.last().unwrap(), "v9");
+    // Overwriting and deleting the vectors a cursor follows skips nothing
+    let upsert = json!({"id": "v9", "vector": [0.0, 0.0]}).to_string();
+    let (status, _) = request(port, "POST", "/collections/docs/upsert", &upsert);
+    assert_eq!(status, 200);
+    let (second, cursor) = page(&format!("&cursor={}", cursor.unwrap()));
+    assert_eq!(
+        second,
+        (10..20).map(|i| format!("v{}", i)).collect::<Vec<_>>()
+    );
+    let (status, _) = request(port, "DELETE", "/collections/docs/vectors/v19", "");
+    assert_eq!(status, 200);
+    let (third, cursor) = page(&format!("&cursor={}", cursor.unwrap()));
+    let mut expected: Vec<String> = (20..25).map(|i| format!("v{}", i)).collect();
+    expected.push("v9".to_string());
+    assert_eq!(third, expected);
+    assert_eq!(cursor, None);
+
+    let (status, _) = request(port, "GET", "/collections/docs/vectors?cursor=v9", "");
+    assert_eq!(status, 400);
+}
@@ -0,0 +1,49 @@
+mod common;
+
+use common::{request, start_server};
+use serde_json::{json, Value};
+
+#[test]
+fn test_update_metadata_by_filter() {
+    let server = start_server(&[]);
+    let port = server.port;
+
+    let create = json!({"name": "docs", "dimensions": 2}).to_string();
+    let (status, _) = request(port, "POST", "/collections", &create);
+    assert_eq!(status, 200);
+
+    let vectors: Vec<Value> = (0..6)
+        .map(|i| {
+            let tag = if i % 2 == 0 { "even" } else { "odd" };
+            json!({"id": format!("v{}", i), "vector": [i as f32, 1.0], "metadata": {"tag": tag}})
+        })
+        .collect();
+    let batch = json!({"vectors": vectors}).to_string();
+    let (status, body) = request(port, "POST", "/collections/docs/vectors/batch", &batch);
+    assert_eq!(status, 200, "{}", body);
+
+    let update = json!({
+        "filter": {"Exact": ["tag", "even"]},
+        "patch": {"reviewed": true},
+    })
+    .to_string();
+    let (status, body) = request(port, "POST", "/collections/docs/metadata", &update);
+    assert_eq!(status, 200, "{}", body);
+    assert_eq!(serde_json::from_str::<Value>(&body).unwrap()["updated"], 3);
+
+    for i in 0..6 {
+        let (status, body) = request(
+            port,
+            "GET",
+            &format!("/collections/docs/vectors/v{}", i),
+            "",
+        );
+        assert_eq!(status, 200, "{}", body);
+        let metadata = serde_json::from_str::<Value>(&body).unwrap()["metadata"].clone();
+        let expected = if i % 2 == 0 { json!(true) } else { Value::Null };
+        assert_eq!(metadata["reviewed"], expected);
+    }
+
+    let (status, _) = request(port, "POST", "/collections/missing/metadata", &update);
+    assert_eq!(status, 404);
+}
@@ -0,0 +1,38 @@
+mod common;
+
+use common::{request, start_server};
+use serde_json::{json, Value};
+
+#[test]
+fn test_search_with_range_filter() {
+    let server = start_server(&[]);
+    let call = |path: &str, body: Value| request(server.port, "POST", path, &body.to_string());
+
+    call("/collections", json!({"name": "shop", "dimensions": 2}));
+    let vectors: Vec<Value> = (0..10)
+        .map(|i| {
+            json!({
+                "id": format!("item{}", i),
+                "vector": [1.0, i as f32],
+                "metadata": {"listing": {"price": i as f64 * 10.0 + 0.99}},
+            })
+        })
+        .collect();
+    let (status, _) = call(
+        "/collections/shop/vectors/batch",
+        json!({"vectors": vectors}),
+    );
+    assert_eq!(status, 200);
+
+    let search = json!({
+        "vector": [1.0, 0.0],
+        "k": 10,
+        "filter": {"Range": {"key": "listing.price", "gte": 20.0, "lt": 50}},
+    });
+    let (status, body) = call("/collections/shop/search", search);
+    assert_eq!(status, 200, "{}", body);
+    let results: Vec<Value> = serde_json::from_str(&body).unwrap();
+    let mut ids: Vec<&str> = results.iter().map(|r| r["id"].as_str().unwrap()).collect();
+    ids.sort();
+    assert_eq!(ids, ["item2", "item3", "item4"]);
+}
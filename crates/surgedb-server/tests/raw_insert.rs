@@ -0,0 +1,134 @@
+mod common;
+
+use common::{request, request_bytes, start_server};
+use serde_json::{json, Value};
+
+/// Encode vectors in the raw insert layout
+fn encode(dim: u32, items: &[(&str, Vec<f32>)]) -> Vec<u8> {
+    let mut body = Vec::new();
+    body.extend_from_slice(&(items.len() as u32).to_le_bytes());
+    body.extend_from_slice(&dim.to_le_bytes());
+    for (_, vector) in items {
+        for x in vector {
+            body.extend_from_slice(&x.to_le_bytes());
+        }
+    }
+    for (id, _) in items {
+        body.extend_from_slice(&(id.len() as u32).to_le_bytes());
+        body.extend_from_slice(id.as_bytes());
+    }
+    body
+}
+
+#[test]
+fn test_raw_insert_matches_json_insert() {
+    let server = start_server(&[]);
+    let port = server.port;
+    let post = |path: &str, body: Value| request(port, "POST", path, &body.to_string());
+
+    let items: Vec<(&str, Vec<f32>)> = vec![
+        ("a", vec![1.0, 0.0, 0.0]),
+        ("b", vec![0.0, 1.0, 0.0]),
+        ("ünïcode", vec![0.0, 0.0, 1.0]),
+    ];
+    for name in ["raw", "json"] {
+        let (status, _) = post("/collections", json!({"name": name, "dimensions": 3}));
+        assert_eq!(status, 200);
+    }
+
+    let body = encode(3, &items);
+    let (status, response) = request_bytes(
+        port,
+        "POST",
+        "/collections/raw/vectors/raw",
+        "application/octet-stream",
+        &body,
+    );
+    assert_eq!(status, 200, "{}", response);
+    assert_eq!(response, "3");
+
+    let vectors: Vec<Value> = items
+        .iter()
+        .map(|(id, vector)| json!({"id": id, "vector": vector}))
+        .collect();
+    let (status, _) = post(
+        "/collections/json/vectors/batch",
+        json!({"vectors": vectors}),
+    );
+    assert_eq!(status, 200);
+
+    for (id, vector) in &items {
+        let search = json!({"vector": vector, "k": 3});
+        let (_, raw) = post("/collections/raw/search", search.clone());
+        let (_, json) = post("/collections/json/search", search);
+        // Equidistant hits may come back in either order
+        let parse = |body: &str| {
+            let mut hits: Vec<Value> = serde_json::from_str(body).unwrap();
+            hits.sort_by(|a, b| {
+                let key = |hit: &Value| (hit["distance"].as_f64().unwrap(), hit["id"].to_string());
+                key(a).partial_cmp(&key(b)).unwrap()
+            });
+            hits
+        };
+        let (raw, json) = (parse(&raw), parse(&json));
+        assert_eq!(raw, json);
+        assert_eq!(raw[0]["id"], *id);
+    }
+
+    // Wrong dimensions, truncated bodies and other content types are rejected
+    let (status, _) = request_bytes(
+        port,
+        "POST",
+        "/collections/raw/vectors/raw",
+        "application/octet-stream",
+        &encode(2, &[("c", vec![1.0, 0.0])]),
+    );
+    assert_eq!(status, 400);
+    let (status, _) = request_bytes(
+        port,
+        "POST",
+        "/collections/raw/vectors/raw",
+        "application/octet-stream",
+        &body[..body.len() - 1],
+    );
+    assert_eq!(status, 400);
+    let (status, _) = request_bytes(
+        port,
+        "POST",
+        "/collections/raw/vectors/raw",
+        "application/json",
+        &body,
+    );
+    assert_eq!(status, 415);
+
+    // A header claiming more vectors than the body holds is rejected before
+    // anything is allocated for them
+    let mut huge = Vec::new();
+    huge.extend_from_slice(&u32::MAX.to_le_bytes());
+    huge.extend_from_slice(&0u32.to_le_bytes());
+    let (status, response) = request_bytes(
+        port,
+        "POST",
+        "/collections/raw/vectors/raw",
+        "application/octet-stream",
+        &huge,
+    );
+    assert_eq!(status, 400, "{}", response);
+    let mut huge = Vec::new();
+    huge.extend_from_slice(&u32::MAX.to_le_bytes());
+    huge.extend_from_slice(&3u32.to_le_bytes());
+    huge.extend_from_slice(&[0; 64]);
+    let (status, response) = request_bytes(
+        port,
+        "POST",
+        "/collections/raw/vectors/raw",
+        "application/octet-stream",
+        &huge,
+    );
+    assert_eq!(status, 400, "{}", response);
+    assert!(response.contains("does not fit"), "{}", response);
+
+    // The server is still up
+    let (status, _) = request(port, "GET", "/health", "");
+    assert_eq!(status, 200);
+}
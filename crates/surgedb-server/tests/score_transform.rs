@@ -0,0 +1,82 @@
+mod common;
+
+use common::{request, start_server};
+use serde_json::{json, Value};
+
+#[test]
+fn test_score_transform_default_and_override() {
+    let server = start_server(&[]);
+    let call = |path: &str, body: Value| request(server.port, "POST", path, &body.to_string());
+
+    let create = json!({
+        "name": "docs",
+        "dimensions": 2,
+        "distance_metric": "Euclidean",
+        "score_transform": "Similarity",
+    });
+    let (status, body) = call("/collections", create);
+    assert_eq!(status, 200, "{}", body);
+    let (status, _) = call(
+        "/collections/docs/vectors",
+        json!({"id": "a", "vector": [3.0, 4.0]}),
+    );
+    assert_eq!(status, 200);
+
+    // Euclidean distance 5 from the origin
+    let score = |transform: Option<Value>| {
+        let mut search = json!({"vector": [0.0, 0.0], "k": 1});
+        if let Some(transform) = transform {
+            search["score_transform"] = transform;
+        }
+        let (status, body) = call("/collections/docs/search", search);
+        assert_eq!(status, 200, "{}", body);
+        let hit = serde_json::from_str::<Value>(&body).unwrap()[0].clone();
+        assert_eq!(hit["distance"].as_f64().unwrap(), 5.0);
+        hit["score"].as_f64().unwrap()
+    };
+    assert!((score(None) - 1.0 / 6.0).abs() < 1e-6);
+    assert_eq!(score(Some(json!("Distance"))), 5.0);
+    let sigmoid = json!({"Sigmoid": {"midpoint": 5.0, "steepness": 1.0}});
+    assert!((score(Some(sigmoid)) - 0.5).abs() < 1e-6);
+
+    let (status, _) = call(
+        "/collections/docs/search",
+        json!({"vector": [0.0, 0.0], "k": 1, "score_transform": "Bogus"}),
+    );
+    assert!(status >= 400, "{}", status);
+}
+
+#[test]
+fn test_normalized_similarity_reports_metric() {
+    let server = start_server(&[]);
+    let call = |path: &str, body: Value| request(server.port, "POST", path, &body.to_string());
+
+    let (status, body) = call("/collections", json!({"name": "docs", "dimensions": 2}));
+    assert_eq!(status, 200, "{}", body);
+    for (id, vector) in [("same", [1.0, 0.0]), ("opposite", [-1.0, 0.0])] {
+        let (status, _) = call(
+            "/collections/docs/vectors",
+            json!({"id": id, "vector": vector}),
+        );
+        assert_eq!(status, 200);
+    }
+
+    let search = json!({
+        "vector": [1.0, 0.0],
+        "k": 2,
+        "score_transform": "NormalizedSimilarity",
+        "include_stats": true,
+    });
+    let (status, body) = call("/collections/docs/search", search);
+    assert_eq!(status, 200, "{}", body);
+    let response: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(response["metric"], "Cosine");
+    assert_eq!(response["score_transform"], "NormalizedSimilarity");
+
+    let hits = response["results"].as_array().unwrap();
+    assert_eq!(hits[0]["id"], "same");
+    assert!(hits[0]["distance"].as_f64().unwrap().abs() < 1e-6);
+    assert!((hits[0]["score"].as_f64().unwrap() - 1.0).abs() < 1e-6);
+    assert!((hits[1]["distance"].as_f64().unwrap() - 2.0).abs() < 1e-6);
+    assert!(hits[1]["score"].as_f64().unwrap().abs() < 1e-6);
+}
@@ -0,0 +1,36 @@
+mod common;
+
+use common::{request, start_server};
+use serde_json::{json, Value};
+
+#[test]
+fn test_search_debug_reports_routing() {
+    let server = start_server(&[]);
+    let call = |path: &str, body: Value| {
+        let (status, body) = request(server.port, "POST", path, &body.to_string());
+        (status, body)
+    };
+
+    call("/collections", json!({"name": "docs", "dimensions": 2}));
+    let vectors: Vec<Value> = (0..10)
+        .map(|i| json!({"id": format!("v{}", i), "vector": [1.0, i as f32]}))
+        .collect();
+    let (status, body) = call(
+        "/collections/docs/vectors/batch",
+        json!({ "vectors": vectors }),
+    );
+    assert!(status < 300, "{}", body);
+
+    let search = json!({"vector": [1.0, 3.0], "k": 3});
+    let (status, body) = call("/collections/docs/search", search.clone());
+    assert_eq!(status, 200);
+    let plain: Value = serde_json::from_str(&body).unwrap();
+
+    // The same hits, with an explicit debug block; server collections are
+    // never routed, so there are no probed lists to report
+    let (status, body) = call("/collections/docs/search?debug=true", search);
+    assert_eq!(status, 200, "{}", body);
+    let response: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(response["results"], plain);
+    assert_eq!(response["debug"], json!({"routing": null}));
+}
@@ -0,0 +1,137 @@
+mod common;
+
+use common::{request, request_bytes, start_server};
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const LIMIT: u64 = 2;
+const HEAVY_CLIENTS: usize = 16;
+const HEAVY_VECTORS: usize = 2000;
+const DIMENSIONS: usize = 64;
+const LIGHT_SEARCHES: usize = 20;
+
+/// Encode vectors in the raw insert layout
+fn encode(vectors: &[Vec<f32>]) -> Vec<u8> {
+    let mut body = Vec::new();
+    body.extend_from_slice(&(vectors.len() as u32).to_le_bytes());
+    body.extend_from_slice(&(DIMENSIONS as u32).to_le_bytes());
+    for x in vectors.iter().flatten() {
+        body.extend_from_slice(&x.to_le_bytes());
+    }
+    for i in 0..vectors.len() {
+        let id = format!("v{}", i);
+        body.extend_from_slice(&(id.len() as u32).to_le_bytes());
+        body.extend_from_slice(id.as_bytes());
+    }
+    body
+}
+
+fn heavy_in_flight(port: u16) -> u64 {
+    let (_, body) = request(port, "GET", "/stats", "");
+    let stats: Value = serde_json::from_str(&body).unwrap();
+    stats["search_in_flight"]["heavy"].as_u64().unwrap_or(0)
+}
+
+#[test]
+fn test_saturated_collection_does_not_starve_others() {
+    // The heavy insert is slow in debug builds
+    let server = start_server(&[
+        ("SEARCH_CONCURRENCY_PER_COLLECTION", "2"),
+        ("REQUEST_TIMEOUT_SECS", "300"),
+    ]);
+    let port = server.port;
+    let post = |path: &str, body: Value| request(port, "POST", path, &body.to_string());
+
+    for name in ["heavy", "light"] {
+        let (status, _) = post(
+            "/collections",
+            json!({"name": name, "dimensions": DIMENSIONS}),
+        );
+        assert_eq!(status, 200);
+    }
+    let vectors: Vec<Vec<f32>> = (0..HEAVY_VECTORS)
+        .map(|i| {
+            (0..DIMENSIONS)
+                .map(|d| ((i * 31 + d * 7) % 97) as f32)
+                .collect()
+        })
+        .collect();
+    let (status, body) = request_bytes(
+        port,
+        "POST",
+        "/collections/heavy/vectors/raw",
+        "application/octet-stream",
+        &encode(&vectors),
+    );
+    assert_eq!(status, 200, "{}", body);
+    let (status, body) = request_bytes(
+        port,
+        "POST",
+        "/collections/light/vectors/raw",
+        "application/octet-stream",
+        &encode(&vectors[..10]),
+    );
+    assert_eq!(status, 200, "{}", body);
+
+    // Clients loop searches that walk the whole heavy graph, so its slots
+    // stay taken and further heavy searches queue for them
+    let stop = AtomicBool::new(false);
+    thread::scope(|scope| {
+        for vector in &vectors[..HEAVY_CLIENTS] {
+            let stop = &stop;
+            scope.spawn(move || {
+                let search = json!({"vector": vector, "k": 100, "ef": HEAVY_VECTORS});
+                while !stop.load(Ordering::Relaxed) {
+                    let (status, body) = request(
+                        port,
+                        "POST",
+                        "/collections/heavy/search",
+                        &search.to_string(),
+                    );
+                    assert_eq!(status, 200, "{}", body);
+                }
+            });
+        }
+
+        // Wait until the heavy collection is using every slot it is allowed
+        let deadline = Instant::now() + Duration::from_secs(30);
+        while heavy_in_flight(port) < LIMIT {
+            assert!(Instant::now() < deadline, "heavy searches never saturated");
+            thread::sleep(Duration::from_millis(5));
+        }
+
+        // The light collection is served from its own slots meanwhile, and
+        // the heavy one never exceeds its limit. Clients are stopped before
+        // asserting so a failure cannot leave them looping.
+        let search = json!({"vector": vectors[0], "k": 3}).to_string();
+        let observed: Vec<_> = (0..LIGHT_SEARCHES)
+            .map(|_| {
+                let (status, body) = request(port, "POST", "/collections/light/search", &search);
+                (status, body, heavy_in_flight(port))
+            })
+            .collect();
+        stop.store(true, Ordering::Relaxed);
+
+        for (status, body, in_flight) in &observed {
+            assert_eq!(*status, 200, "{}", body);
+            assert!(*in_flight <= LIMIT, "{} searches in flight", in_flight);
+        }
+        let saturated = observed
+            .iter()
+            .filter(|(_, _, in_flight)| *in_flight == LIMIT)
+            .count();
+        assert!(
+            saturated > LIGHT_SEARCHES / 2,
+            "heavy collection saturated after {} of {} light searches",
+            saturated,
+            LIGHT_SEARCHES
+        );
+    });
+
+    let (_, body) = request(port, "GET", "/stats", "");
+    let stats: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(stats["search_in_flight"]["heavy"], 0);
+    assert_eq!(stats["search_in_flight"]["light"], 0);
+}
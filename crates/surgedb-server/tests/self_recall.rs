@@ -0,0 +1,46 @@
+mod common;
+
+use common::{request, start_server};
+use serde_json::{json, Value};
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_stats_report_self_recall() {
+    let server = start_server(&[
+        ("SELF_RECALL_INTERVAL_SECS", "1"),
+        ("SELF_RECALL_SAMPLE", "50"),
+    ]);
+    let port = server.port;
+
+    let create = json!({"name": "docs", "dimensions": 4}).to_string();
+    let (status, _) = request(port, "POST", "/collections", &create);
+    assert_eq!(status, 200);
+
+    let vectors: Vec<Value> = (0..200)
+        .map(|i| {
+            let vector: Vec<f32> = (0..4).map(|d| ((i * 7 + d * 3) as f32).sin()).collect();
+            json!({"id": format!("v{}", i), "vector": vector})
+        })
+        .collect();
+    let batch = json!({"vectors": vectors}).to_string();
+    let (status, body) = request(port, "POST", "/collections/docs/vectors/batch", &batch);
+    assert_eq!(status, 200, "{}", body);
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        let (status, body) = request(port, "GET", "/stats", "");
+        assert_eq!(status, 200);
+        let stats: Value = serde_json::from_str(&body).unwrap();
+        let docs = &stats["self_recall"]["docs"];
+        if docs["sampled"] == 50 {
+            assert!(docs["recall"].as_f64().unwrap() >= 0.95, "{}", docs);
+            break;
+        }
+        assert!(
+            Instant::now() < deadline,
+            "no self-recall reported: {}",
+            body
+        );
+        std::thread::sleep(Duration::from_millis(200));
+    }
+}
@@ -0,0 +1,10 @@
+mod common;
+
+use common::{request, start_server};
+
+#[test]
+fn test_server_starts_with_self_test() {
+    let server = start_server(&[("SELF_TEST", "true")]);
+    let (status, _) = request(server.port, "GET", "/health", "");
+    assert_eq!(status, 200);
+}
@@ -0,0 +1,76 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::{request, start_server};
+use std::path::Path;
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
+use surgedb_core::Database;
+
+fn dir_size(path: &Path) -> u64 {
+    std::fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok()?.metadata().ok())
+                .map(|m| m.len())
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+#[test]
+fn test_sigterm_checkpoints_before_exit() {
+    let mut server = start_server(&[("CHECKPOINT_ON_SHUTDOWN", "true")]);
+
+    let (status, body) = request(
+        server.port,
+        "POST",
+        "/collections",
+        r#"{"name": "docs", "dimensions": 4}"#,
+    );
+    assert_eq!(status, 200, "{}", body);
+    let vectors: Vec<String> = (0..200)
+        .map(|i| {
+            format!(
+                r#"{{"id": "vec{}", "vector": [{}, 1.0, 0.5, 0.25]}}"#,
+                i, i as f32
+            )
+        })
+        .collect();
+    let (status, body) = request(
+        server.port,
+        "POST",
+        "/collections/docs/vectors/batch",
+        &format!(r#"{{"vectors": [{}]}}"#, vectors.join(",")),
+    );
+    assert_eq!(status, 200, "{}", body);
+
+    let collection_dir = server.data_path().join("docs");
+    let wal_before = dir_size(&collection_dir.join("wal"));
+    assert_eq!(dir_size(&collection_dir.join("snapshots")), 0);
+
+    let status = Command::new("kill")
+        .args(["-TERM", &server.child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let deadline = Instant::now() + Duration::from_secs(30);
+    let exit = loop {
+        if let Some(exit) = server.child.try_wait().unwrap() {
+            break exit;
+        }
+        assert!(Instant::now() < deadline, "server did not exit");
+        thread::sleep(Duration::from_millis(50));
+    };
+    assert!(exit.success());
+
+    // The snapshot holds the data and the WAL left to replay is just the marker
+    assert!(dir_size(&collection_dir.join("snapshots")) > 0);
+    assert!(dir_size(&collection_dir.join("wal")) < wal_before);
+
+    let db = Database::open(server.data_path()).unwrap();
+    assert_eq!(db.collection_stats("docs").unwrap().vector_count, 200);
+}
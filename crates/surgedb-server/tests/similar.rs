@@ -0,0 +1,68 @@
+mod common;
+
+use common::{request, start_server};
+use serde_json::{json, Value};
+
+#[test]
+fn test_similar_searches_with_a_stored_vector() {
+    let server = start_server(&[]);
+    let port = server.port;
+
+    let create = json!({"name": "docs", "dimensions": 2}).to_string();
+    let (status, _) = request(port, "POST", "/collections", &create);
+    assert_eq!(status, 200);
+    let batch = json!({"vectors": [
+        {"id": "a", "vector": [1.0, 0.0], "metadata": {"tag": "x"}},
+        {"id": "b", "vector": [0.9, 0.1], "metadata": {"tag": "y"}},
+        {"id": "c", "vector": [0.7, 0.3], "metadata": {"tag": "x"}},
+        {"id": "d", "vector": [0.0, 1.0], "metadata": {"tag": "x"}},
+    ]})
+    .to_string();
+    let (status, body) = request(port, "POST", "/collections/docs/vectors/batch", &batch);
+    assert_eq!(status, 200, "{}", body);
+
+    let ids = |body: &str| -> Vec<String> {
+        let hits: Vec<Value> = serde_json::from_str(body).unwrap();
+        hits.iter()
+            .map(|hit| hit["id"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    // exclude_self defaults to true
+    let (status, body) = request(
+        port,
+        "POST",
+        "/collections/docs/vectors/a/similar",
+        &json!({"k": 2}).to_string(),
+    );
+    assert_eq!(status, 200, "{}", body);
+    assert_eq!(ids(&body), ["b", "c"]);
+
+    let with_self = json!({"k": 2, "exclude_self": false}).to_string();
+    let (status, body) = request(
+        port,
+        "POST",
+        "/collections/docs/vectors/a/similar",
+        &with_self,
+    );
+    assert_eq!(status, 200, "{}", body);
+    assert_eq!(ids(&body), ["a", "b"]);
+
+    let filtered = json!({"filter": {"Exact": ["tag", "x"]}}).to_string();
+    let (status, body) = request(
+        port,
+        "POST",
+        "/collections/docs/vectors/a/similar",
+        &filtered,
+    );
+    assert_eq!(status, 200, "{}", body);
+    assert_eq!(ids(&body), ["c", "d"]);
+
+    for path in [
+        "/collections/docs/vectors/missing/similar",
+        "/collections/nope/vectors/a/similar",
+    ] {
+        let (status, _) = request(port, "POST", path, "{}");
+        assert_eq!(status, 404, "{}", path);
+    }
+}
@@ -0,0 +1,112 @@
+mod common;
+
+use common::{request, start_server};
+use serde_json::json;
+use std::thread;
+
+const CLIENTS: usize = 16;
+const INSERTS_PER_CLIENT: usize = 25;
+
+/// Fire concurrent single inserts
+fn insert_concurrently(port: u16) {
+    thread::scope(|scope| {
+        for client in 0..CLIENTS {
+            scope.spawn(move || {
+                for i in 0..INSERTS_PER_CLIENT {
+                    let body = json!({
+                        "id": format!("c{}-{}", client, i),
+                        "vector": [client as f32, i as f32],
+                    });
+                    let (status, body) =
+                        request(port, "POST", "/collections/docs/vectors", &body.to_string());
+                    assert_eq!(status, 200, "{}", body);
+                }
+            });
+        }
+    });
+}
+
+#[test]
+fn test_batched_single_inserts_all_commit() {
+    let unbatched = start_server(&[]);
+    let batched = start_server(&[("WRITE_BATCH_WINDOW_MS", "5")]);
+
+    for server in [&unbatched, &batched] {
+        let create = json!({"name": "docs", "dimensions": 2}).to_string();
+        let (status, _) = request(server.port, "POST", "/collections", &create);
+        assert_eq!(status, 200);
+        insert_concurrently(server.port);
+    }
+    let write_batches = |port| {
+        let (_, body) = request(port, "GET", "/stats", "");
+        serde_json::from_str::<serde_json::Value>(&body).unwrap()["write_batches"].clone()
+    };
+    assert!(write_batches(unbatched.port).is_null());
+
+    // Concurrent inserts share batches, committing in far fewer passes
+    let stats = write_batches(batched.port);
+    let inserts = (CLIENTS * INSERTS_PER_CLIENT) as u64;
+    assert_eq!(stats["inserts"], inserts);
+    let batches = stats["batches"].as_u64().unwrap();
+    assert!(batches * 2 <= inserts, "{} batches", batches);
+
+    let port = batched.port;
+    for client in 0..CLIENTS {
+        for i in 0..INSERTS_PER_CLIENT {
+            let path = format!("/collections/docs/vectors/c{}-{}", client, i);
+            let (status, body) = request(port, "GET", &path, "");
+            assert_eq!(status, 200, "{}: {}", path, body);
+        }
+    }
+
+    // Batched inserts still fail one at a time
+    let insert = |body: serde_json::Value| {
+        request(port, "POST", "/collections/docs/vectors", &body.to_string())
+    };
+    let (status, body) = insert(json!({"id": "c0-0", "vector": [0.0, 0.0]}));
+    assert_eq!(status, 400, "{}", body);
+    let (status, _) = insert(json!({"id": "short", "vector": [1.0]}));
+    assert_eq!(status, 400);
+    let (status, _) = insert(json!({"id": "late", "vector": [1.0, 1.0]}));
+    assert_eq!(status, 200);
+
+    let (status, _) = request(
+        port,
+        "POST",
+        "/collections/missing/vectors",
+        "{\"id\": \"a\", \"vector\": [1.0, 1.0]}",
+    );
+    assert_eq!(status, 404);
+}
+
+#[test]
+fn test_batched_inserts_of_one_id_keep_a_single_winner() {
+    let server = start_server(&[("WRITE_BATCH_WINDOW_MS", "5")]);
+    let port = server.port;
+    let create = json!({"name": "docs", "dimensions": 2}).to_string();
+    let (status, _) = request(port, "POST", "/collections", &create);
+    assert_eq!(status, 200);
+
+    let winners: Vec<usize> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..CLIENTS)
+            .map(|client| {
+                scope.spawn(move || {
+                    let body = json!({"id": "shared", "vector": [client as f32, 1.0]});
+                    let (status, _) =
+                        request(port, "POST", "/collections/docs/vectors", &body.to_string());
+                    (status == 200).then_some(client)
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .filter_map(|handle| handle.join().unwrap())
+            .collect()
+    });
+    assert_eq!(winners.len(), 1, "{:?}", winners);
+
+    let (status, body) = request(port, "GET", "/collections/docs/vectors/shared", "");
+    assert_eq!(status, 200);
+    let stored: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(stored["vector"], json!([winners[0] as f32, 1.0]));
+}
@@ -0,0 +1,90 @@
+mod common;
+
+use common::{request_with_key, start_server};
+use serde_json::{json, Value};
+use tungstenite::client::IntoClientRequest;
+use tungstenite::{Error, Message};
+
+const API_KEY: &str = "secret";
+
+#[test]
+fn test_ws_search_replies_in_order() {
+    let server = start_server(&[("API_KEY", API_KEY)]);
+    let call = |method: &str, path: &str, body: Value| {
+        let (status, body) =
+            request_with_key(server.port, method, path, &body.to_string(), Some(API_KEY));
+        assert!(status < 300, "{} {} -> {}: {}", method, path, status, body);
+    };
+
+    call(
+        "POST",
+        "/collections",
+        json!({"name": "docs", "dimensions": 3, "distance_metric": "Euclidean"}),
+    );
+    for (id, vector) in [("x", [1.0, 0.0, 0.0]), ("y", [0.0, 1.0, 0.0])] {
+        call(
+            "POST",
+            "/collections/docs/vectors",
+            json!({"id": id, "vector": vector, "metadata": {"axis": id}}),
+        );
+    }
+
+    let url = format!("ws://127.0.0.1:{}/ws/search", server.port);
+
+    // The upgrade request is authenticated like any other route
+    match tungstenite::connect(&url) {
+        Err(Error::Http(response)) => assert_eq!(response.status(), 401),
+        other => panic!("expected 401, got {:?}", other.map(|(_, r)| r.status())),
+    }
+
+    let mut upgrade = url.as_str().into_client_request().unwrap();
+    upgrade
+        .headers_mut()
+        .insert("x-api-key", API_KEY.parse().unwrap());
+    let (mut socket, _) = tungstenite::connect(upgrade).unwrap();
+
+    // Both frames are in flight before either reply is read
+    for (request_id, vector) in [(1, [0.0, 0.9, 0.1]), (2, [0.9, 0.0, 0.1])] {
+        let frame = json!({
+            "request_id": request_id,
+            "collection": "docs",
+            "vector": vector,
+            "k": 1,
+            "ef": 10,
+            "include_stats": request_id == 2,
+            "include_vectors": request_id == 2,
+        });
+        socket.send(Message::text(frame.to_string())).unwrap();
+    }
+
+    let mut replies = Vec::new();
+    while replies.len() < 2 {
+        if let Message::Text(text) = socket.read().unwrap() {
+            replies.push(serde_json::from_str::<Value>(&text).unwrap());
+        }
+    }
+
+    assert_eq!(replies[0]["request_id"], 1);
+    assert_eq!(replies[0]["results"][0]["id"], "y");
+    assert_eq!(replies[0]["results"][0]["metadata"], json!({"axis": "y"}));
+    assert!(replies[0].get("exhausted").is_none());
+    assert!(replies[0]["results"][0].get("vector").is_none());
+    assert_eq!(replies[1]["request_id"], 2);
+    assert_eq!(replies[1]["results"][0]["id"], "x");
+    assert_eq!(replies[1]["filtered_out"], 0);
+    assert_eq!(replies[1]["results"][0]["vector"], json!([1.0, 0.0, 0.0]));
+
+    // Errors are reported per frame without dropping the connection
+    let frame =
+        json!({"request_id": 3, "collection": "missing", "vector": [0.0, 0.0, 1.0], "k": 1});
+    socket.send(Message::text(frame.to_string())).unwrap();
+    let reply: Value = loop {
+        if let Message::Text(text) = socket.read().unwrap() {
+            break serde_json::from_str(&text).unwrap();
+        }
+    };
+    assert_eq!(reply["request_id"], 3);
+    assert!(reply["error"].as_str().unwrap().contains("missing"));
+
+    socket.close(None).unwrap();
+}
@@ -43,6 +43,7 @@ export default function Collections() {
   
   // Pagination
   const [page, setPage] = useState(0);
+  const [total, setTotal] = useState(0);
   const limit = 50;
 
   useEffect(() => {
@@ -81,7 +82,8 @@ export default function Collections() {
     try {
       const res = await fetch(`/api/collections/${name}/vectors?offset=${p * limit}&limit=${limit}`);
       const data = await res.json();
-      setVectors(Array.isArray(data) ? data : []);
+      setVectors(Array.isArray(data.vectors) ? data.vectors : []);
+      setTotal(Number(res.headers.get('X-Total-Count') ?? 0));
     } catch (err) {
       console.error(err);
     }
@@ -139,6 +141,9 @@ export default function Collections() {
     }
   }
 
+  const collectionStats = (name: string | null) =>
+    stats?.database?.collections?.find((c: any) => c.name === name);
+
   return (
     <div className="flex h-[calc(100vh-80px)] overflow-hidden">
       {/* List Sidebar */}
@@ -170,9 +175,9 @@ export default function Collections() {
                 <span className="text-xs font-black uppercase truncate tracking-tighter">{name}</span>
                 <ChevronRight className={cn("w-3 h-3", selectedCollection === name ? "text-white" : "text-black")} />
               </div>
-              {stats?.database?.collections?.[name] && (
+              {collectionStats(name) && (
                 <span className={cn("text-[10px] font-bold", selectedCollection === name ? "text-white/80" : "text-black/40")}>
-                  {stats.database.collections[name].vector_count} VECTORS
+                  {collectionStats(name).vector_count} VECTORS
                 </span>
               )}
             </button>
@@ -190,10 +195,10 @@ export default function Collections() {
                    <h2 className="text-4xl font-black tracking-tighter uppercase leading-none">{selectedCollection}</h2>
                    <div className="flex items-center gap-2">
                       <span className="px-3 py-1 border-2 border-black text-[10px] font-black bg-white uppercase">
-                        {stats?.database?.collections?.[selectedCollection]?.dimensions}D
+                        {collectionStats(selectedCollection)?.dimensions}D
                       </span>
                       <span className="px-3 py-1 border-2 border-black text-[10px] font-black bg-black text-white uppercase">
-                        {stats?.database?.collections?.[selectedCollection]?.quantization}
+                        {collectionStats(selectedCollection)?.quantization}
                       </span>
                    </div>
                 </div>
@@ -260,7 +265,7 @@ export default function Collections() {
 
               <div className="p-6 bg-white flex items-center justify-between">
                 <div className="text-[10px] font-black text-black/40 tracking-widest">
-                  TOTAL_PAGES: {Math.ceil((stats?.database?.collections?.[selectedCollection]?.vector_count || 0) / limit) || 1}
+                  SHOWING {vectors.length ? page * limit + 1 : 0}-{page * limit + vectors.length} OF {total} // TOTAL_PAGES: {Math.ceil(total / limit) || 1}
                 </div>
                 <div className="flex gap-4">
                   <button 
@@ -271,7 +276,7 @@ export default function Collections() {
                     PREV
                   </button>
                   <button 
-                    disabled={vectors.length < limit}
+                    disabled={(page + 1) * limit >= total}
                     onClick={() => setPage(p => p + 1)}
                     className="btn-brutal btn-brutal-white py-1 px-3 text-[10px]"
                   >
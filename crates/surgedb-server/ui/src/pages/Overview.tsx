@@ -70,7 +70,7 @@ export default function Overview() {
     time: new Date(h.timestamp).toLocaleTimeString([], { hour: '2-digit', minute: '2-digit', second: '2-digit' })
   }));
 
-  const collections = stats?.database?.collections ? Object.entries(stats.database.collections) : [];
+  const collections = stats?.database?.collections ?? [];
 
   return (
     <div className="p-10 space-y-10">
@@ -124,7 +124,7 @@ export default function Overview() {
           </div>
           
           <div className="space-y-4 flex-1 overflow-auto max-h-[300px] pr-2 custom-scrollbar">
-            {collections.map(([name, data]: [string, any]) => (
+            {collections.map(({ name, vector_count }: any) => (
               <Link 
                 key={name} 
                 to={`/collections`} 
@@ -133,7 +133,7 @@ export default function Overview() {
               >
                 <div className="flex flex-col gap-1 min-w-0">
                   <span className="text-xs font-black truncate uppercase tracking-tighter">{name}</span>
-                  <span className="text-[10px] font-bold text-black/50">{data.vector_count} VECTORS</span>
+                  <span className="text-[10px] font-bold text-black/50">{vector_count} VECTORS</span>
                 </div>
                 <ArrowRight className="w-4 h-4 text-black group-hover:text-surge-orange transition-colors" />
               </Link>
@@ -13,11 +13,14 @@
 //! db.insert("doc1", new Float32Array([...]), { title: "Hello" });
 //!
 //! const results = db.search(new Float32Array([...]), 10);
-//! console.log(results);  // [{ id: "doc1", score: 0.95, metadata: {...} }, ...]
+//! console.log(results);  // [{ id: "doc1", distance: 0.1, score: 0.1, metadata: {...} }, ...]
+//!
+//! // Cosine similarity rescaled to 0..1, higher is closer
+//! db.search(query, 10, "NormalizedSimilarity");  // [{ id: "doc1", distance: 0.1, score: 0.95, ... }]
 //! ```
 
 use serde::{Deserialize, Serialize};
-use surgedb_core::VectorId;
+use surgedb_core::{MetadataUpdate, ScoreTransform, VectorId};
 use wasm_bindgen::prelude::*;
 
 // Initialize panic hook for better error messages
@@ -114,6 +117,10 @@ impl From<surgedb_core::Error> for SurgeError {
             surgedb_core::Error::VectorNotFound(_) => "VectorNotFound",
             surgedb_core::Error::DuplicateId(_) => "DuplicateId",
             surgedb_core::Error::EmptyIndex => "EmptyIndex",
+            surgedb_core::Error::MetadataTooLarge { .. } => "MetadataTooLarge",
+            surgedb_core::Error::NotUnitNorm { .. } => "NotUnitNorm",
+            surgedb_core::Error::InvalidQuery { .. } => "InvalidQuery",
+            surgedb_core::Error::InvalidVector { .. } => "InvalidVector",
             surgedb_core::Error::InvalidConfig(_) => "InvalidConfig",
             surgedb_core::Error::InvalidHnswParam { .. } => "InvalidHnswParam",
             surgedb_core::Error::Storage(_) => "StorageError",
@@ -169,10 +176,50 @@ impl From<serde_json::Error> for SurgeError {
 #[derive(Serialize, Deserialize)]
 pub struct SearchResult {
     pub id: String,
+    /// Cosine distance (lower is closer), whatever the score transform
+    pub distance: f32,
     pub score: f32,
     pub metadata: Option<serde_json::Value>,
 }
 
+/// Parse an optional `ScoreTransform` argument ("Distance", "Similarity",
+/// "NormalizedSimilarity" or `{ Sigmoid: { midpoint, steepness } }`),
+/// falling back to `default`
+fn score_transform(transform: JsValue, default: ScoreTransform) -> Result<ScoreTransform, JsValue> {
+    if transform.is_undefined() || transform.is_null() {
+        Ok(default)
+    } else {
+        Ok(serde_wasm_bindgen::from_value(transform)?)
+    }
+}
+
+/// Parse `setMetadata` arguments: JS `null`/`undefined` clear the metadata
+fn metadata_update(
+    metadata: JsValue,
+    merge: Option<bool>,
+) -> Result<(serde_json::Value, MetadataUpdate), JsValue> {
+    let metadata = if metadata.is_undefined() || metadata.is_null() {
+        serde_json::Value::Null
+    } else {
+        serde_wasm_bindgen::from_value(metadata)?
+    };
+    let mode = if merge.unwrap_or(false) {
+        MetadataUpdate::Merge
+    } else {
+        MetadataUpdate::Replace
+    };
+    Ok((metadata, mode))
+}
+
+/// Configuration shared by `new SurgeDB()` and `SurgeDB.deserialize`
+fn db_config(dimensions: u32) -> surgedb_core::Config {
+    surgedb_core::Config {
+        dimensions: dimensions as usize,
+        distance_metric: surgedb_core::DistanceMetric::Cosine,
+        ..Default::default()
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct VectorEntry {
     pub id: String,
@@ -206,17 +253,31 @@ impl SurgeDB {
     /// @param dimensions - The dimensionality of vectors (e.g., 384 for MiniLM, 768 for BERT)
     #[wasm_bindgen(constructor)]
     pub fn new(dimensions: u32) -> Result<SurgeDB, JsValue> {
-        let config = surgedb_core::Config {
-            dimensions: dimensions as usize,
-            distance_metric: surgedb_core::DistanceMetric::Cosine,
-            ..Default::default()
-        };
+        let inner = surgedb_core::VectorDb::new(db_config(dimensions)).map_err(SurgeError::from)?;
+
+        Ok(SurgeDB { inner })
+    }
 
-        let inner = surgedb_core::VectorDb::new(config).map_err(SurgeError::from)?;
+    /// Restore a database from bytes produced by `serialize()`
+    ///
+    /// @param bytes - Uint8Array from `serialize()`, e.g. read back from IndexedDB
+    /// @param dimensions - The dimensionality the database was created with
+    #[wasm_bindgen]
+    pub fn deserialize(bytes: &[u8], dimensions: u32) -> Result<SurgeDB, JsValue> {
+        let inner = surgedb_core::VectorDb::from_bytes(db_config(dimensions), bytes)
+            .map_err(SurgeError::from)?;
 
         Ok(SurgeDB { inner })
     }
 
+    /// Serialize the vectors, metadata and HNSW graph to bytes
+    ///
+    /// @returns Uint8Array to store (IndexedDB, OPFS, ...) and pass to `SurgeDB.deserialize`
+    #[wasm_bindgen]
+    pub fn serialize(&self) -> Result<Vec<u8>, JsValue> {
+        Ok(self.inner.to_bytes().map_err(SurgeError::from)?)
+    }
+
     /// Insert a vector with optional metadata
     ///
     /// @param id - Unique identifier for the vector
@@ -296,6 +357,25 @@ impl SurgeDB {
         Ok(())
     }
 
+    /// Replace a vector's metadata without touching the vector itself
+    ///
+    /// @param id - The ID of the vector
+    /// @param metadata - New JSON metadata (null clears it)
+    /// @param merge - Shallow-merge the keys into the stored object instead
+    #[wasm_bindgen(js_name = setMetadata)]
+    pub fn set_metadata(
+        &mut self,
+        id: String,
+        metadata: JsValue,
+        merge: Option<bool>,
+    ) -> Result<(), JsValue> {
+        let (metadata, mode) = metadata_update(metadata, merge)?;
+        self.inner
+            .set_metadata(&id, metadata, mode)
+            .map_err(SurgeError::from)?;
+        Ok(())
+    }
+
     /// Delete a vector by ID
     ///
     /// @param id - The ID of the vector to delete
@@ -307,6 +387,12 @@ impl SurgeDB {
             .map_err(|e| SurgeError::from(e).into())
     }
 
+    /// Remove every vector, keeping the database's configuration
+    #[wasm_bindgen]
+    pub fn clear(&mut self) {
+        self.inner.clear();
+    }
+
     /// Get a vector by ID
     ///
     /// @param id - The ID of the vector
@@ -332,23 +418,27 @@ impl SurgeDB {
     ///
     /// @param query - Float32Array query vector
     /// @param k - Number of results to return
-    /// @returns Array of { id, score, metadata } objects
+    /// @param transform - Optional score transform: "Distance" (default),
+    ///   "Similarity", "NormalizedSimilarity" (0..1) or
+    ///   { Sigmoid: { midpoint, steepness } }
+    /// @returns Array of { id, distance, score, metadata } objects, where
+    ///   `distance` is always the raw cosine distance
     #[wasm_bindgen]
-    pub fn search(&self, query: Vec<f32>, k: u32) -> Result<JsValue, JsValue> {
+    pub fn search(&self, query: &[f32], k: u32, transform: JsValue) -> Result<JsValue, JsValue> {
+        let config = self.inner.config();
+        let transform = score_transform(transform, config.score_transform)?;
         let results = self
             .inner
-            .search(&query, k as usize, None)
+            .search(query, k as usize, None)
             .map_err(SurgeError::from)?;
 
         let search_results: Vec<SearchResult> = results
             .into_iter()
-            .map(|(id, score, metadata)| {
-                // Debug log removed
-                SearchResult {
-                    id: id.to_string(),
-                    score,
-                    metadata,
-                }
+            .map(|(id, distance, metadata)| SearchResult {
+                id: id.to_string(),
+                distance,
+                score: transform.apply(config.distance_metric, distance),
+                metadata,
             })
             .collect();
 
@@ -475,6 +565,25 @@ impl SurgeDBQuantized {
         Ok(())
     }
 
+    /// Replace a vector's metadata without touching its quantized bytes
+    ///
+    /// @param id - The ID of the vector
+    /// @param metadata - New JSON metadata (null clears it)
+    /// @param merge - Shallow-merge the keys into the stored object instead
+    #[wasm_bindgen(js_name = setMetadata)]
+    pub fn set_metadata(
+        &mut self,
+        id: String,
+        metadata: JsValue,
+        merge: Option<bool>,
+    ) -> Result<(), JsValue> {
+        let (metadata, mode) = metadata_update(metadata, merge)?;
+        self.inner
+            .set_metadata(&id, metadata, mode)
+            .map_err(SurgeError::from)?;
+        Ok(())
+    }
+
     /// Delete a vector by ID
 
     #[wasm_bindgen]
@@ -484,7 +593,16 @@ impl SurgeDBQuantized {
             .map_err(|e| SurgeError::from(e).into())
     }
 
+    /// Remove every vector, keeping the database's configuration
+    #[wasm_bindgen]
+    pub fn clear(&mut self) {
+        self.inner.clear();
+    }
+
     /// Get a vector by ID
+    ///
+    /// Originals are not kept, so the vector is dequantized from SQ8 and only
+    /// approximates the one inserted.
     #[wasm_bindgen]
     pub fn get(&self, id: String) -> Result<JsValue, JsValue> {
         let result = self.inner.get(&id).map_err(SurgeError::from)?;
@@ -502,19 +620,22 @@ impl SurgeDBQuantized {
         }
     }
 
-    /// Search for the k nearest neighbors
+    /// Search for the k nearest neighbors (see `SurgeDB.search` for `transform`)
     #[wasm_bindgen]
-    pub fn search(&self, query: Vec<f32>, k: u32) -> Result<JsValue, JsValue> {
+    pub fn search(&self, query: &[f32], k: u32, transform: JsValue) -> Result<JsValue, JsValue> {
+        let config = self.inner.config();
+        let transform = score_transform(transform, config.score_transform)?;
         let results = self
             .inner
-            .search(&query, k as usize, None)
+            .search(query, k as usize, None)
             .map_err(SurgeError::from)?;
 
         let search_results: Vec<SearchResult> = results
             .into_iter()
-            .map(|(id, score, metadata)| SearchResult {
+            .map(|(id, distance, metadata)| SearchResult {
                 id: id.to_string(),
-                score,
+                distance,
+                score: transform.apply(config.distance_metric, distance),
                 metadata,
             })
             .collect();
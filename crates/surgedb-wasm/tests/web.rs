@@ -0,0 +1,51 @@
+//! Browser tests, run with `wasm-pack test --headless --firefox`
+
+#![cfg(target_arch = "wasm32")]
+
+use surgedb_wasm::SurgeDB;
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+fn to_json(value: &JsValue) -> String {
+    js_sys::JSON::stringify(value).unwrap().into()
+}
+
+#[wasm_bindgen_test]
+fn serialize_round_trips() {
+    let mut db = SurgeDB::new(8).unwrap();
+    for i in 0..100u32 {
+        let vector: Vec<f32> = (0..8)
+            .map(|j| ((i * 7 + j * 13) % 17) as f32 - 8.0)
+            .collect();
+        let metadata = serde_wasm_bindgen::to_value(&serde_json::json!({ "i": i })).unwrap();
+        db.insert(format!("doc{}", i), vector, metadata).unwrap();
+    }
+    let query = [0.5, -1.0, 2.0, 0.0, 1.5, -0.5, 1.0, 3.0];
+
+    let restored = SurgeDB::deserialize(&db.serialize().unwrap(), 8).unwrap();
+
+    assert_eq!(restored.len(), 100);
+    assert_eq!(
+        to_json(&restored.get("doc42".into()).unwrap()),
+        to_json(&db.get("doc42".into()).unwrap())
+    );
+    assert_eq!(
+        to_json(&restored.search(&query, 10, JsValue::UNDEFINED).unwrap()),
+        to_json(&db.search(&query, 10, JsValue::UNDEFINED).unwrap())
+    );
+    assert!(SurgeDB::deserialize(&db.serialize().unwrap(), 4).is_err());
+}
+
+#[wasm_bindgen_test]
+fn clear_empties_database() {
+    let mut db = SurgeDB::new(2).unwrap();
+    db.insert("a".into(), vec![1.0, 0.0], JsValue::UNDEFINED)
+        .unwrap();
+    db.clear();
+    assert!(db.is_empty());
+    db.insert("a".into(), vec![0.0, 1.0], JsValue::UNDEFINED)
+        .unwrap();
+    assert_eq!(db.len(), 1);
+}